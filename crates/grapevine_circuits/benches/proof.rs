@@ -1,16 +1,24 @@
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use grapevine_circuits::nova::{
     continue_nova_proof, get_public_params, get_r1cs, nova_proof, verify_nova_proof,
 };
-use grapevine_circuits::utils::compress_proof;
-use grapevine_circuits::{DEFAULT_PUBLIC_PARAMS_PATH, DEFAULT_R1CS_PATH, DEFAULT_WC_PATH};
-use grapevine_common::utils::random_fr;
+use grapevine_circuits::steps_for_degree;
+use grapevine_circuits::utils::{compress_proof, decompress_proof};
 use grapevine_common::{Fr, NovaProof};
 use std::env::current_dir;
 
+/// Highest degree of separation benchmarked
+const MAX_BENCH_DEGREE: usize = 5;
+
+/**
+ * Benchmarks proving, verifying, and compressing proofs of degree 1 to MAX_BENCH_DEGREE
+ * @notice inputs are fixed (no random auth secrets) so results are comparable between runs
+ *
+ * run with `cargo bench -p grapevine_circuits --features bench`
+ */
 fn benchmark(c: &mut Criterion) {
     // tracker for sizes of proofs
-    let mut proof_sizes: [[usize; 2]; 7] = [[0, 0]; 7];
+    let mut proof_sizes: [[usize; 2]; MAX_BENCH_DEGREE] = [[0, 0]; MAX_BENCH_DEGREE];
     // get proving artifacts
     let params_path = String::from("circom/artifacts/public_params.json");
     let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
@@ -20,21 +28,16 @@ fn benchmark(c: &mut Criterion) {
     let r1cs = get_r1cs(Some(r1cs_path));
     let public_params = get_public_params(Some(params_path));
     // build inputs
-    let usernames: [String; 7] = [
-        "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf",
-    ]
-    .iter()
-    .map(|name| String::from(*name))
-    .collect::<Vec<_>>()
-    .try_into()
-    .unwrap(); // assume degrees of connection will never be mroe than 7
+    let usernames: [String; MAX_BENCH_DEGREE] =
+        ["alpha", "bravo", "charlie", "delta", "echo"].map(String::from);
     let phrase = String::from("I heard it through the grapevine");
-    let auth_secrets: [Fr; 7] = std::array::from_fn(|_| random_fr());
+    let auth_secrets: [Fr; MAX_BENCH_DEGREE] = std::array::from_fn(|i| Fr::from(i as u64 + 1));
 
-    // benchmark degree 1 proof
+    // benchmark the identity (degree 1) proof
     let current_usernames = vec![usernames[0].clone()];
-    let current_auth_secrets = vec![auth_secrets[0].clone()];
-    c.bench_function("degree 1 proof", |b| {
+    let current_auth_secrets = vec![auth_secrets[0]];
+    let mut prove = c.benchmark_group("prove");
+    prove.bench_function(BenchmarkId::from_parameter(1), |b| {
         b.iter(|| {
             nova_proof(
                 wc_path.clone(),
@@ -48,7 +51,7 @@ fn benchmark(c: &mut Criterion) {
         })
     });
 
-    // prepare first degree proof & store sizing data
+    // prepare first degree proof
     let mut proof = nova_proof(
         wc_path.clone(),
         &r1cs,
@@ -58,21 +61,17 @@ fn benchmark(c: &mut Criterion) {
         &current_auth_secrets,
     )
     .unwrap();
-    let uncompressed_size = serde_json::to_string(&proof)
-        .unwrap()
-        .as_bytes()
-        .to_vec()
-        .len();
-    let compressed = compress_proof(&proof).len();
-    proof_sizes[0] = [uncompressed_size, compressed];
-    // benchmark degree 2 proof
-    for i in 1..7 {
+    let mut proofs: Vec<NovaProof> = vec![proof.clone()];
+    // benchmark folding each further degree onto the previous proof
+    for i in 1..MAX_BENCH_DEGREE {
         // get inputs
-        let z0_last = verify_nova_proof(&proof, &public_params, i * 2).unwrap().0;
+        let z0_last = verify_nova_proof(&proof, &public_params, steps_for_degree(i))
+            .unwrap()
+            .0;
         let current_usernames = usernames[i - 1..i + 1].to_vec();
         let current_auth_secrets = auth_secrets[i - 1..i + 1].to_vec();
         // benchmark the next iteration
-        c.bench_function(format!("degree {} proof", i + 1).as_str(), |b| {
+        prove.bench_function(BenchmarkId::from_parameter(i + 1), |b| {
             b.iter(|| {
                 continue_nova_proof(
                     &current_usernames,
@@ -86,7 +85,7 @@ fn benchmark(c: &mut Criterion) {
                 .unwrap()
             })
         });
-        // prepare i degree proof and store sizing data
+        // prepare i degree proof
         continue_nova_proof(
             &current_usernames,
             &current_auth_secrets,
@@ -97,26 +96,54 @@ fn benchmark(c: &mut Criterion) {
             &public_params,
         )
         .unwrap();
-        let uncompressed_size = serde_json::to_string(&proof)
-            .unwrap()
-            .as_bytes()
-            .to_vec()
-            .len();
-        let compressed = compress_proof(&proof).len();
-        proof_sizes[i] = [uncompressed_size, compressed];
+        proofs.push(proof.clone());
+    }
+    prove.finish();
+
+    // benchmark verifying each degree
+    let mut verify = c.benchmark_group("verify");
+    for (i, proof) in proofs.iter().enumerate() {
+        let iterations = steps_for_degree(i + 1);
+        verify.bench_with_input(BenchmarkId::from_parameter(i + 1), proof, |b, proof| {
+            b.iter(|| verify_nova_proof(proof, &public_params, iterations).unwrap())
+        });
     }
+    verify.finish();
+
+    // benchmark compressing and decompressing each degree & store sizing data
+    let mut compress = c.benchmark_group("compress");
+    for (i, proof) in proofs.iter().enumerate() {
+        compress.bench_with_input(BenchmarkId::from_parameter(i + 1), proof, |b, proof| {
+            b.iter(|| compress_proof(proof))
+        });
+    }
+    compress.finish();
+    let mut decompress = c.benchmark_group("decompress");
+    for (i, proof) in proofs.iter().enumerate() {
+        let compressed = compress_proof(proof);
+        decompress.bench_with_input(
+            BenchmarkId::from_parameter(i + 1),
+            &compressed,
+            |b, compressed| b.iter(|| decompress_proof(compressed)),
+        );
+        let uncompressed_size = serde_json::to_string(proof).unwrap().len();
+        proof_sizes[i] = [uncompressed_size, compressed.len()];
+    }
+    decompress.finish();
+
     println!("Proof size benchmarks: ");
-    for i in 0..proof_sizes.len() {
+    for (i, [uncompressed, compressed]) in proof_sizes.iter().enumerate() {
         println!(
             "Degree {}: uncompressed: {} bytes, compressed: {} bytes",
             i + 1,
-            proof_sizes[i][0],
-            proof_sizes[i][1]
+            uncompressed,
+            compressed
         );
     }
 }
 
 /// RESULTS ///
+/// (measured before verification and compression were benchmarked, with random auth secrets)
 /// TIME COMPLEXITY:
 /// degree 1 proof          time:   [656.65 ms 672.54 ms 690.54 ms]
 /// degree 2 proof          time:   [821.74 ms 839.84 ms 860.73 ms]
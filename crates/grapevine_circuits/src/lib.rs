@@ -9,6 +9,21 @@ pub const DEFAULT_WC_PATH: &str =
 pub const DEFAULT_R1CS_PATH: &str = "crates/grapevine_circuits/circom/artifacts/folded.r1cs";
 pub const DEFAULT_PUBLIC_PARAMS_PATH: &str =
     "crates/grapevine_circuits/circom/artifacts/public_params.json";
+pub const CIRCUIT_VERSION: &str = "1"; // bump whenever grapevine.circom changes
+pub const MAX_DEGREE: u8 = u8::MAX; // degrees are tracked as a single byte
+
+/**
+ * Get the number of folded steps in a proof of a given degree of separation, used as the number of
+ * iterations when verifying. A proof starts with a chaff step, then each degree adds a compute step
+ * (the phrase hash at degree 1, a username/ auth secret hash after) followed by a chaff step:
+ *   steps = 1 (initial chaff) + 2 * degree (compute + chaff per degree)
+ *
+ * @param degree - the degree of separation the proof shows (1 for knowledge of the phrase)
+ * @return - the number of steps the proof was folded over
+ */
+pub fn steps_for_degree(degree: usize) -> usize {
+    1 + degree * 2
+}
 
 /**
  * Default start input is 0 first three elements and 1 for chaff step
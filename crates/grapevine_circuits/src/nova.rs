@@ -1,15 +1,28 @@
 use super::{
-    start_input,
-    utils::{build_step_inputs, read_public_params},
-    z0_secondary, DEFAULT_PUBLIC_PARAMS_PATH, DEFAULT_R1CS_PATH,
+    start_input, steps_for_degree,
+    utils::{
+        build_step_inputs, read_proof_checkpoint, read_public_params, try_decompress_proof,
+        write_proof_checkpoint,
+    },
+    z0_secondary, DEFAULT_PUBLIC_PARAMS_PATH, DEFAULT_R1CS_PATH, MAX_DEGREE,
+};
+use grapevine_common::{
+    credential::{Credential, CREDENTIAL_VERSION},
+    crypto::auth_hash,
+    errors::GrapevineError,
+    models::DegreeProof,
+    Fq, Fr, NovaProof, Params, G1, G2,
 };
-use grapevine_common::{Fq, Fr, NovaProof, Params, G1, G2};
 use nova_scotia::{
     circom::{circuit::R1CS, reader::load_r1cs},
     continue_recursive_circuit, create_recursive_circuit, FileLocation,
 };
 use nova_snark::errors::NovaError;
-use std::{env::current_dir, path::PathBuf};
+use std::{
+    env::current_dir,
+    io::{Error, ErrorKind},
+    path::PathBuf,
+};
 
 /**
  * Get public params for the grapevine circuit
@@ -41,6 +54,24 @@ pub fn get_r1cs(path: Option<String>) -> R1CS<Fr> {
     load_r1cs::<G1, G2>(&FileLocation::PathBuf(r1cs_file))
 }
 
+/**
+ * Check that the public params were generated for the given r1cs before proving with them
+ * @notice - the augmented primary circuit in the params always contains the step circuit, so
+ *           params with fewer constraints or variables than the r1cs cannot be for the r1cs
+ *
+ * @param public_params - the public params to prove with
+ * @param r1cs - the r1cs of the grapevine circuit
+ * @return - ArtifactMismatch error if the params cannot correspond to the r1cs
+ */
+pub fn check_artifacts(public_params: &Params, r1cs: &R1CS<Fr>) -> Result<(), GrapevineError> {
+    let (primary_constraints, _) = public_params.num_constraints();
+    let (primary_variables, _) = public_params.num_variables();
+    if primary_constraints < r1cs.constraints.len() || primary_variables < r1cs.num_variables {
+        return Err(GrapevineError::ArtifactMismatch);
+    }
+    Ok(())
+}
+
 /**
  * Create a nova proof for N degrees of separation, where N is the length of the usernames vector - 1
  * @notice - proving knowledge of preimage is degree 0 hence 1 username means N = 0
@@ -60,6 +91,9 @@ pub fn nova_proof(
     usernames: &Vec<String>,
     auth_secrets: &Vec<Fr>,
 ) -> Result<NovaProof, std::io::Error> {
+    // fail early if the params were not generated for this r1cs
+    check_artifacts(public_params, r1cs).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+
     // marshall private inputs into circom inputs
     let mut private_inputs = Vec::new();
     for i in 0..usernames.len() {
@@ -91,7 +125,8 @@ pub fn nova_proof(
             phrase_input,
             username_input,
             auth_secret_input,
-        );
+        )
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
     }
 
     println!("Start input: {:?}", start_input());
@@ -111,7 +146,7 @@ pub fn nova_proof(
  *
  * @param proof - the proof to verify
  * @param public_params - the public params to use to verify the proof
- * @param iterations - the number of iterations to run the verification ((degrees_of_separation + 1) * 2)
+ * @param iterations - the number of iterations to run the verification (see steps_for_degree)
  * @return - true if the proof is valid, false otherwise
  */
 pub fn verify_nova_proof(
@@ -122,6 +157,157 @@ pub fn verify_nova_proof(
     proof.verify(public_params, iterations, &start_input(), &z0_secondary())
 }
 
+/**
+ * Verify a nova-grapevine proof against a set of allowed phrase hashes without revealing which
+ * phrase hash the proof was made for
+ *
+ * @param proof - the proof to verify
+ * @param public_params - the public params to use to verify the proof
+ * @param degree - the degree of separation claimed by the prover
+ * @param allowed - the set of phrase hashes the proof may have been made for
+ * @return - true if the proof is valid for the claimed degree and its phrase hash is in the set
+ */
+pub fn verify_nova_proof_in_set(
+    proof: &NovaProof,
+    public_params: &Params,
+    degree: u8,
+    allowed: &[[u8; 32]],
+) -> Result<bool, NovaError> {
+    let iterations = steps_for_degree(degree as usize);
+    let (outputs, _) = verify_nova_proof(proof, public_params, iterations)?;
+    if outputs[0] != Fr::from(degree as u64) {
+        return Ok(false);
+    }
+    let phrase_hash = outputs[1].to_bytes();
+    Ok(allowed.iter().any(|hash| *hash == phrase_hash))
+}
+
+/**
+ * Check that the metadata stored alongside a degree proof matches the outputs of the proof itself
+ * @notice - guards against proof documents altered by direct writes to the database
+ *
+ * @param proof - the degree proof document with its compressed proof
+ * @param phrase_hash - the hash of the phrase the document references
+ * @param public_params - the public params to use to verify the proof
+ * @return - ProofMetadataMismatch naming the first field that does not match the proof outputs
+ */
+pub fn verify_degree_proof_metadata(
+    proof: &DegreeProof,
+    phrase_hash: &[u8; 32],
+    public_params: &Params,
+) -> Result<(), GrapevineError> {
+    let mismatch = |field: &str| GrapevineError::ProofMetadataMismatch(String::from(field));
+    let compressed = proof.proof.as_ref().ok_or_else(|| mismatch("proof"))?;
+    let degree = proof.degree.ok_or_else(|| mismatch("degree"))?;
+    let decompressed = try_decompress_proof(compressed)
+        .map_err(|_| GrapevineError::SerdeError(String::from("NovaProof")))?;
+    let iterations = steps_for_degree(degree as usize);
+    let (outputs, _) = verify_nova_proof(&decompressed, public_params, iterations)
+        .map_err(|_| GrapevineError::DegreeProofVerificationFailed)?;
+    if outputs[0] != Fr::from(degree as u64) {
+        return Err(mismatch("degree"));
+    }
+    if outputs[1].to_bytes() != *phrase_hash {
+        return Err(mismatch("phrase"));
+    }
+    if proof.auth_hash != Some(outputs[2].to_bytes()) {
+        return Err(mismatch("auth_hash"));
+    }
+    Ok(())
+}
+
+/**
+ * Verify a chain of degree proofs end to end: every proof verifies at its degree, every proof is
+ * for the same phrase, and every proof was made by the prover listed for its degree
+ * @notice - each proof only shows its prover knew the auth secret of someone one degree closer to
+ *           the phrase, so checking the provers is what ties the proofs into one chain and catches
+ *           a valid proof from another chain swapped in
+ *
+ * @param proofs - the proofs in the chain, starting from the degree 1 proof
+ * @param provers - the username and auth secret of the user who made each proof
+ * @param public_params - the public params to use to verify the proofs
+ * @return - ProofChainBroken naming the degree of the first proof that does not link
+ */
+pub fn verify_chain(
+    proofs: &[NovaProof],
+    provers: &[DegreeInputs],
+    public_params: &Params,
+) -> Result<(), GrapevineError> {
+    let broken = |degree: usize, msg: &str| {
+        GrapevineError::ProofChainBroken(degree as u8, String::from(msg))
+    };
+    if proofs.is_empty() || proofs.len() != provers.len() {
+        return Err(broken(0, "every proof needs exactly one prover"));
+    }
+    if proofs.len() > MAX_DEGREE as usize {
+        return Err(broken(0, "chain is longer than the maximum degree"));
+    }
+    let mut phrase_hash: Option<[u8; 32]> = None;
+    for (i, (proof, prover)) in proofs.iter().zip(provers).enumerate() {
+        let degree = i + 1;
+        let (outputs, _) = verify_nova_proof(proof, public_params, steps_for_degree(degree))
+            .map_err(|_| broken(degree, "proof does not verify"))?;
+        if outputs[0] != Fr::from(degree as u64) {
+            return Err(broken(degree, "proof is for a different degree"));
+        }
+        let hash = outputs[1].to_bytes();
+        if *phrase_hash.get_or_insert(hash) != hash {
+            return Err(broken(degree, "proof is for a different phrase"));
+        }
+        let expected = auth_hash(&hash, &prover.username, &prover.auth_secret)
+            .map_err(|e| broken(degree, &e.to_string()))?;
+        if outputs[2].to_bytes() != expected {
+            return Err(broken(degree, "proof was not made by the listed prover"));
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Verify the proof in a bearer credential offline: the credential is signed by the pubkey it
+ * names, was made for the given public params, and its proof verifies at the claimed degree for
+ * the claimed phrase hash
+ * @notice - the holder and origin are NOT verified. The proof does not bind them, and anyone with a
+ *           copy of the proof can sign a credential naming themselves as the holder
+ *
+ * @param credential - the credential to verify
+ * @param public_params - the public params to use to verify the proof
+ * @param params_hash - the artifact hash of the public params
+ * @return - InvalidCredential naming the first check the credential fails
+ */
+pub fn verify_credential_proof(
+    credential: &Credential,
+    public_params: &Params,
+    params_hash: &str,
+) -> Result<(), GrapevineError> {
+    let invalid = |msg: &str| GrapevineError::InvalidCredential(String::from(msg));
+    if credential.version != CREDENTIAL_VERSION {
+        return Err(GrapevineError::InvalidCredential(format!(
+            "Unsupported credential version {}",
+            credential.version
+        )));
+    }
+    if credential.params_hash != params_hash {
+        return Err(invalid("Credential was made for different public params"));
+    }
+    credential.verify_signature()?;
+    if credential.degree == 0 {
+        return Err(invalid("Degree must be at least 1"));
+    }
+    let proof =
+        try_decompress_proof(&credential.proof_bytes()?).map_err(|_| invalid("Malformed proof"))?;
+    let iterations = steps_for_degree(credential.degree as usize);
+    let (outputs, _) = verify_nova_proof(&proof, public_params, iterations)
+        .map_err(|_| invalid("Proof does not verify"))?;
+    if outputs[0] != Fr::from(credential.degree as u64) {
+        return Err(invalid("Proof is for a different degree"));
+    }
+    if outputs[1].to_bytes() != credential.phrase_hash_bytes()? {
+        return Err(invalid("Proof is for a different phrase"));
+    }
+    Ok(())
+}
+
 /**
  * Prove another degree of separation using an existing proof from a grapevine circuit
  *
@@ -143,6 +329,9 @@ pub fn continue_nova_proof(
     r1cs: &R1CS<Fr>,
     public_params: &Params,
 ) -> Result<(), std::io::Error> {
+    // fail early if the params were not generated for this r1cs
+    check_artifacts(public_params, r1cs).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+
     // compute the private inputs for this degree's compute/ chaff step
     let mut private_inputs = Vec::new();
     build_step_inputs(
@@ -150,7 +339,8 @@ pub fn continue_nova_proof(
         None,
         [Some(usernames[0].clone()), Some(usernames[1].clone())],
         [Some(auth_secrets[0]), Some(auth_secrets[1])],
-    );
+    )
+    .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
 
     // compute the next round of the proof
     continue_recursive_circuit(
@@ -164,6 +354,148 @@ pub fn continue_nova_proof(
     )
 }
 
+/**
+ * Continue a proof by one degree like `continue_nova_proof`, checkpointing the proof to disk
+ * before folding and after each step so an interrupted run resumes where it stopped instead of
+ * re-fetching and refolding
+ * @notice - the checkpoint path should be unique to the proof being built on. It is removed once
+ *           the degree is proved
+ *
+ * @param artifacts - the loaded proving artifacts
+ * @param usernames - the usernames of the previous and current prover
+ * @param auth_secrets - the auth secrets of the previous and current prover
+ * @param proof - the proof of the previous degree (replaced by the checkpoint when resuming)
+ * @param degree - the degree of separation `proof` proves
+ * @param checkpoint - the filepath to checkpoint the proof to
+ * @return - an error if the artifacts do not match, a checkpoint cannot be written, or proving fails
+ */
+pub fn continue_nova_proof_resumable(
+    artifacts: &ProvingArtifacts,
+    usernames: &[String],
+    auth_secrets: &[Fr],
+    proof: &mut NovaProof,
+    degree: usize,
+    checkpoint: PathBuf,
+) -> Result<(), std::io::Error> {
+    let public_params = artifacts.public_params;
+    let r1cs = artifacts.r1cs;
+    // fail early if the params were not generated for this r1cs
+    check_artifacts(public_params, r1cs).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+
+    // resume from the checkpoint if it holds this degree partway folded
+    let base = steps_for_degree(degree);
+    let mut completed = 0;
+    if checkpoint.exists() {
+        let (saved, iterations) = read_proof_checkpoint(checkpoint.clone())?;
+        if (base..=base + 2).contains(&iterations) {
+            *proof = saved;
+            completed = iterations - base;
+        }
+    }
+    if completed == 0 {
+        write_proof_checkpoint(proof, base, checkpoint.clone())?;
+    }
+
+    // compute the private inputs for this degree's compute/ chaff step
+    let mut private_inputs = Vec::new();
+    build_step_inputs(
+        &mut private_inputs,
+        None,
+        [Some(usernames[0].clone()), Some(usernames[1].clone())],
+        [Some(auth_secrets[0]), Some(auth_secrets[1])],
+    )
+    .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+
+    // fold the remaining steps one at a time, checkpointing after each
+    for (step, inputs) in private_inputs.into_iter().enumerate().skip(completed) {
+        // each step's witness is computed from the output of the step before it
+        let (previous_output, _) = verify_nova_proof(proof, public_params, base + step)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{:?}", e)))?;
+        continue_recursive_circuit(
+            proof,
+            previous_output,
+            FileLocation::PathBuf(artifacts.wc_path.clone()),
+            r1cs.clone(),
+            vec![inputs],
+            start_input().to_vec(),
+            public_params,
+        )?;
+        write_proof_checkpoint(proof, base + step + 1, checkpoint.clone())?;
+    }
+    std::fs::remove_file(checkpoint)
+}
+
+/**
+ * Handles to the loaded artifacts needed to prove, shared across the steps of a proof
+ */
+pub struct ProvingArtifacts<'a> {
+    pub wc_path: PathBuf,
+    pub r1cs: &'a R1CS<Fr>,
+    pub public_params: &'a Params,
+}
+
+/**
+ * Inputs for proving knowledge of a phrase (degree 1)
+ */
+pub struct IdentityInputs {
+    pub phrase: String,
+    pub username: String, // the username of the phrase creator
+    pub auth_secret: Fr,  // the auth secret of the phrase creator
+}
+
+/**
+ * Inputs for proving one more degree of separation from the previous prover
+ */
+pub struct DegreeInputs {
+    pub username: String, // the username of the user proving the degree
+    pub auth_secret: Fr,  // the auth secret of the user proving the degree
+}
+
+/**
+ * Prove a degree 2 connection in one call: knowledge of the phrase by its creator, continued by
+ * a user the creator shared their auth secret with
+ *
+ * @param artifacts - the loaded proving artifacts
+ * @param identity - the phrase and the creator's username and auth secret
+ * @param degree - the username and auth secret of the user one degree from the creator
+ * @return - the degree 2 proof, or an error if the artifacts do not match or proving fails
+ */
+pub fn prove_degree_2(
+    artifacts: &ProvingArtifacts,
+    identity: &IdentityInputs,
+    degree: &DegreeInputs,
+) -> Result<NovaProof, GrapevineError> {
+    check_artifacts(artifacts.public_params, artifacts.r1cs)?;
+    let proving_failed = |e: Error| GrapevineError::ProvingFailed(e.to_string());
+
+    // prove knowledge of the phrase
+    let mut proof = nova_proof(
+        artifacts.wc_path.clone(),
+        artifacts.r1cs,
+        artifacts.public_params,
+        &identity.phrase,
+        &vec![identity.username.clone()],
+        &vec![identity.auth_secret],
+    )
+    .map_err(proving_failed)?;
+    let (previous_output, _) =
+        verify_nova_proof(&proof, artifacts.public_params, steps_for_degree(1))
+            .map_err(|_| GrapevineError::DegreeProofVerificationFailed)?;
+
+    // continue the proof by one degree
+    continue_nova_proof(
+        &vec![identity.username.clone(), degree.username.clone()],
+        &vec![identity.auth_secret, degree.auth_secret],
+        &mut proof,
+        previous_output,
+        artifacts.wc_path.clone(),
+        artifacts.r1cs,
+        artifacts.public_params,
+    )
+    .map_err(proving_failed)?;
+    Ok(proof)
+}
+
 // /**
 //  * Compute the proving and verifying keys for a compressed circuit
 //  *
@@ -180,7 +512,11 @@ pub fn continue_nova_proof(
 mod test {
     use super::*;
     use crate::utils::{compress_proof, decompress_proof, read_proof, write_proof};
-    use grapevine_common::utils::random_fr;
+    use grapevine_common::{
+        account::GrapevineAccount,
+        crypto::{artifact_hash, hash_phrase},
+        utils::random_fr,
+    };
 
     #[test]
     fn test_degree_0() {
@@ -209,7 +545,7 @@ mod test {
         )
         .unwrap();
 
-        let iterations = 1 + usernames.len() * 2;
+        let iterations = steps_for_degree(usernames.len());
         let verified = verify_nova_proof(&proof, &public_params, iterations).unwrap();
         println!("Verified: {:?}", verified);
     }
@@ -242,7 +578,7 @@ mod test {
         )
         .unwrap();
 
-        let iterations = 1 + usernames.len() * 2;
+        let iterations = steps_for_degree(usernames.len());
         let verified = verify_nova_proof(&proof, &public_params, iterations).unwrap();
         println!("Verified: {:?}", verified);
     }
@@ -277,7 +613,7 @@ mod test {
         )
         .unwrap();
 
-        let iterations = 1 + usernames.len() * 2;
+        let iterations = steps_for_degree(usernames.len());
         let verified = verify_nova_proof(&proof, &public_params, iterations).unwrap();
 
         // todo: compute expected output
@@ -319,7 +655,7 @@ mod test {
         )
         .unwrap();
 
-        let res = verify_nova_proof(&proof, &public_params, 1 + degree * 2).unwrap();
+        let res = verify_nova_proof(&proof, &public_params, steps_for_degree(degree)).unwrap();
         let z0_last = res.0; // step_out for the circuit execution
         assert!(z0_last[0].eq(&Fr::from(degree as u64)));
 
@@ -335,7 +671,7 @@ mod test {
             &public_params,
         )
         .unwrap();
-        let res = verify_nova_proof(&proof, &public_params, 1 + degree * 2).unwrap();
+        let res = verify_nova_proof(&proof, &public_params, steps_for_degree(degree)).unwrap();
         let z0_last = res.0;
         assert!(z0_last[0].eq(&Fr::from(degree as u64)));
 
@@ -351,7 +687,7 @@ mod test {
             &public_params,
         )
         .unwrap();
-        let res = verify_nova_proof(&proof, &public_params, 1 + degree * 2).unwrap();
+        let res = verify_nova_proof(&proof, &public_params, steps_for_degree(degree)).unwrap();
         let z0_last = res.0;
         assert!(z0_last[0].eq(&Fr::from(degree as u64)));
 
@@ -367,7 +703,7 @@ mod test {
             &public_params,
         )
         .unwrap();
-        let res = verify_nova_proof(&proof, &public_params, 1 + degree * 2).unwrap();
+        let res = verify_nova_proof(&proof, &public_params, steps_for_degree(degree)).unwrap();
         let z0_last = res.0;
         assert!(z0_last[0].eq(&Fr::from(degree as u64)));
     }
@@ -406,7 +742,7 @@ mod test {
         )
         .unwrap();
 
-        let res = verify_nova_proof(&proof, &public_params, 1 + degree * 2).unwrap();
+        let res = verify_nova_proof(&proof, &public_params, steps_for_degree(degree)).unwrap();
         let z0_last = res.0; // step_out for the circuit execution
         assert!(z0_last[0].eq(&Fr::from(degree as u64)));
 
@@ -420,7 +756,7 @@ mod test {
         // read proof from fs
         let mut proof = read_proof(proof_path.clone());
         // get z0_last
-        let z0_last = verify_nova_proof(&proof, &public_params, 1 + degree * 2)
+        let z0_last = verify_nova_proof(&proof, &public_params, steps_for_degree(degree))
             .unwrap()
             .0;
         // prove second degree
@@ -435,7 +771,7 @@ mod test {
             &public_params,
         )
         .unwrap();
-        let res = verify_nova_proof(&proof, &public_params, 1 + degree * 2).unwrap();
+        let res = verify_nova_proof(&proof, &public_params, steps_for_degree(degree)).unwrap();
         let z0_last = res.0;
         assert!(z0_last[0].eq(&Fr::from(degree as u64)));
     }
@@ -478,7 +814,546 @@ mod test {
         let decompressed_proof = decompress_proof(&compressed_proof[..]);
 
         // verify the compressed then uncompressed proof
-        let iterations = 1 + usernames.len() * 2;
+        let iterations = steps_for_degree(usernames.len());
         verify_nova_proof(&decompressed_proof, &public_params, iterations).unwrap();
     }
+
+    #[test]
+    fn test_verify_in_set() {
+        // Test that a proof is accepted for a set containing its phrase hash without disclosing which
+        let phrase = String::from("Mind the gap");
+        let usernames = vec![String::from("mach34")];
+        let auth_secrets = vec![random_fr()];
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let wc_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts/grapevine_js/grapevine.wasm");
+        let r1cs = get_r1cs(Some(r1cs_path));
+        let public_params = get_public_params(Some(params_path));
+
+        let proof = nova_proof(
+            wc_path,
+            &r1cs,
+            &public_params,
+            &phrase,
+            &usernames,
+            &auth_secrets,
+        )
+        .unwrap();
+
+        // get the phrase hash the proof was made for
+        let outputs = verify_nova_proof(&proof, &public_params, steps_for_degree(1))
+            .unwrap()
+            .0;
+        let phrase_hash = outputs[1].to_bytes();
+
+        // set of three origins where only one matches
+        let allowed = [random_fr().to_bytes(), phrase_hash, random_fr().to_bytes()];
+        let verified = verify_nova_proof_in_set(&proof, &public_params, 1, &allowed).unwrap();
+        assert!(verified);
+
+        // set without the phrase hash of the proof
+        let disallowed = [random_fr().to_bytes(), random_fr().to_bytes()];
+        let verified = verify_nova_proof_in_set(&proof, &public_params, 1, &disallowed).unwrap();
+        assert!(!verified);
+    }
+
+    #[test]
+    fn test_deterministic_proof() {
+        // Chaff steps use constant zero inputs, so identical inputs must produce identical proofs
+        let phrase = String::from("Same as it ever was");
+        let usernames = vec![String::from("mach34")];
+        let auth_secrets = vec![Fr::from(1234u64)];
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let wc_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts/grapevine_js/grapevine.wasm");
+        let r1cs = get_r1cs(Some(r1cs_path));
+        let public_params = get_public_params(Some(params_path));
+
+        let proof_a = nova_proof(
+            wc_path.clone(),
+            &r1cs,
+            &public_params,
+            &phrase,
+            &usernames,
+            &auth_secrets,
+        )
+        .unwrap();
+        let proof_b = nova_proof(
+            wc_path,
+            &r1cs,
+            &public_params,
+            &phrase,
+            &usernames,
+            &auth_secrets,
+        )
+        .unwrap();
+
+        assert_eq!(compress_proof(&proof_a), compress_proof(&proof_b));
+    }
+
+    #[test]
+    fn test_artifact_mismatch() {
+        // Pair the public params with an r1cs larger than the circuit they were generated for
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let public_params = get_public_params(Some(params_path));
+        let r1cs = get_r1cs(Some(r1cs_path));
+        assert!(check_artifacts(&public_params, &r1cs).is_ok());
+
+        let mut mismatched = r1cs.clone();
+        mismatched.constraints = vec![r1cs.constraints.clone(); 3].concat();
+        let res = check_artifacts(&public_params, &mismatched);
+        assert!(matches!(res, Err(GrapevineError::ArtifactMismatch)));
+    }
+
+    #[test]
+    fn test_doctored_degree_detected() {
+        // Metadata stored with a proof must match the proof outputs
+        let phrase = String::from("Trust but verify");
+        let usernames = vec![String::from("mach34")];
+        let auth_secrets = vec![random_fr()];
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let wc_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts/grapevine_js/grapevine.wasm");
+        let r1cs = get_r1cs(Some(r1cs_path));
+        let public_params = get_public_params(Some(params_path));
+
+        let proof = nova_proof(
+            wc_path,
+            &r1cs,
+            &public_params,
+            &phrase,
+            &usernames,
+            &auth_secrets,
+        )
+        .unwrap();
+        let outputs = verify_nova_proof(&proof, &public_params, steps_for_degree(1))
+            .unwrap()
+            .0;
+        let phrase_hash = outputs[1].to_bytes();
+
+        let mut document = DegreeProof {
+            id: None,
+            phrase: None,
+            inactive: Some(false),
+            auth_hash: Some(outputs[2].to_bytes()),
+            degree: Some(1),
+            user: None,
+            ciphertext: None,
+            proof: Some(compress_proof(&proof)),
+            preceding: None,
+            proceeding: Some(vec![]),
+            expires_at: None,
+            content_hash: None,
+        };
+        assert!(verify_degree_proof_metadata(&document, &phrase_hash, &public_params).is_ok());
+
+        // claim a lower degree of separation than the proof shows
+        document.degree = Some(2);
+        let res = verify_degree_proof_metadata(&document, &phrase_hash, &public_params);
+        assert!(res.is_err(), "Doctored degree should be detected");
+
+        // claim a different auth hash than the proof shows
+        document.degree = Some(1);
+        document.auth_hash = Some(random_fr().to_bytes());
+        let res = verify_degree_proof_metadata(&document, &phrase_hash, &public_params);
+        assert!(matches!(
+            res,
+            Err(GrapevineError::ProofMetadataMismatch(field)) if field == "auth_hash"
+        ));
+    }
+
+    #[test]
+    fn test_steps_for_degree() {
+        // Proofs of each degree must verify with exactly the number of steps they were folded over
+        let expected = [1, 3, 5, 7, 9, 11];
+        for (degree, steps) in expected.iter().enumerate() {
+            assert_eq!(steps_for_degree(degree), *steps);
+        }
+
+        let usernames = vec!["mach34", "jp4g", "ianb", "ct", "chad"]
+            .iter()
+            .map(|s| String::from(*s))
+            .collect::<Vec<String>>();
+        let auth_secrets = (0..usernames.len())
+            .map(|_| random_fr())
+            .collect::<Vec<Fr>>();
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let wc_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts/grapevine_js/grapevine.wasm");
+        let r1cs = get_r1cs(Some(r1cs_path));
+        let public_params = get_public_params(Some(params_path));
+
+        // degree 1
+        let mut proof = nova_proof(
+            wc_path.clone(),
+            &r1cs,
+            &public_params,
+            &String::from("Step by step"),
+            &usernames[0..1].to_vec(),
+            &auth_secrets[0..1].to_vec(),
+        )
+        .unwrap();
+        let mut z0_last = verify_nova_proof(&proof, &public_params, steps_for_degree(1))
+            .unwrap()
+            .0;
+        assert!(verify_nova_proof(&proof, &public_params, steps_for_degree(1) - 1).is_err());
+
+        // degrees 2 through 5
+        for degree in 2..=5 {
+            continue_nova_proof(
+                &usernames[degree - 2..degree].to_vec(),
+                &auth_secrets[degree - 2..degree].to_vec(),
+                &mut proof,
+                z0_last,
+                wc_path.clone(),
+                &r1cs,
+                &public_params,
+            )
+            .unwrap();
+            z0_last = verify_nova_proof(&proof, &public_params, steps_for_degree(degree))
+                .unwrap()
+                .0;
+            assert!(z0_last[0].eq(&Fr::from(degree as u64)));
+            let steps = steps_for_degree(degree - 1);
+            assert!(verify_nova_proof(&proof, &public_params, steps).is_err());
+        }
+    }
+
+    #[test]
+    fn test_hash_phrase_matches_proof_output() {
+        // the locally computed phrase hash should match the phrase hash output by the circuit
+        let phrase = String::from("Hashing this phrase should not need a proof");
+        let usernames = vec![String::from("mach34")];
+        let auth_secrets = vec![random_fr()];
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let wc_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts/grapevine_js/grapevine.wasm");
+        let r1cs = get_r1cs(Some(r1cs_path));
+        let public_params = get_public_params(Some(params_path));
+
+        let proof = nova_proof(
+            wc_path,
+            &r1cs,
+            &public_params,
+            &phrase,
+            &usernames,
+            &auth_secrets,
+        )
+        .unwrap();
+        let (outputs, _) = verify_nova_proof(&proof, &public_params, steps_for_degree(1)).unwrap();
+        assert_eq!(outputs[1].to_bytes(), hash_phrase(&phrase).unwrap());
+    }
+
+    #[test]
+    fn test_prove_degree_2() {
+        let phrase = String::from("Two hops away");
+        let usernames = vec![String::from("mach34"), String::from("jp4g")];
+        let auth_secrets = vec![Fr::from(1234u64), Fr::from(5678u64)];
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let wc_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts/grapevine_js/grapevine.wasm");
+        let r1cs = get_r1cs(Some(r1cs_path));
+        let public_params = get_public_params(Some(params_path));
+
+        // prove degree 2 in one call
+        let artifacts = ProvingArtifacts {
+            wc_path: wc_path.clone(),
+            r1cs: &r1cs,
+            public_params: &public_params,
+        };
+        let identity = IdentityInputs {
+            phrase: phrase.clone(),
+            username: usernames[0].clone(),
+            auth_secret: auth_secrets[0],
+        };
+        let degree = DegreeInputs {
+            username: usernames[1].clone(),
+            auth_secret: auth_secrets[1],
+        };
+        let proof = prove_degree_2(&artifacts, &identity, &degree).unwrap();
+        let (outputs, _) = verify_nova_proof(&proof, &public_params, steps_for_degree(2)).unwrap();
+        assert_eq!(outputs[0], Fr::from(2));
+
+        // chain the same proof manually
+        let mut chained = nova_proof(
+            wc_path.clone(),
+            &r1cs,
+            &public_params,
+            &phrase,
+            &usernames[0..1].to_vec(),
+            &auth_secrets[0..1].to_vec(),
+        )
+        .unwrap();
+        let (previous_output, _) =
+            verify_nova_proof(&chained, &public_params, steps_for_degree(1)).unwrap();
+        continue_nova_proof(
+            &usernames,
+            &auth_secrets,
+            &mut chained,
+            previous_output,
+            wc_path,
+            &r1cs,
+            &public_params,
+        )
+        .unwrap();
+        assert_eq!(compress_proof(&proof), compress_proof(&chained));
+    }
+
+    #[test]
+    fn test_resume_interrupted_degree() {
+        let phrase = String::from("Interrupted folding");
+        let usernames = vec![String::from("mach34"), String::from("jp4g")];
+        let auth_secrets = vec![Fr::from(4321u64), Fr::from(8765u64)];
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let wc_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts/grapevine_js/grapevine.wasm");
+        let r1cs = get_r1cs(Some(r1cs_path));
+        let public_params = get_public_params(Some(params_path));
+
+        let base = nova_proof(
+            wc_path.clone(),
+            &r1cs,
+            &public_params,
+            &phrase,
+            &usernames[0..1].to_vec(),
+            &auth_secrets[0..1].to_vec(),
+        )
+        .unwrap();
+
+        // a checkpoint round trips the proof and its step count
+        let checkpoint = current_dir().unwrap().join("grapevine_resume.checkpoint");
+        write_proof_checkpoint(&base, steps_for_degree(1), checkpoint.clone()).unwrap();
+        let (read, iterations) = read_proof_checkpoint(checkpoint.clone()).unwrap();
+        assert_eq!(iterations, steps_for_degree(1));
+        assert_eq!(compress_proof(&read), compress_proof(&base));
+
+        // simulate a run interrupted after the compute step of degree 2
+        let mut interrupted = base.clone();
+        let (previous_output, _) =
+            verify_nova_proof(&interrupted, &public_params, steps_for_degree(1)).unwrap();
+        let mut private_inputs = Vec::new();
+        build_step_inputs(
+            &mut private_inputs,
+            None,
+            [Some(usernames[0].clone()), Some(usernames[1].clone())],
+            [Some(auth_secrets[0]), Some(auth_secrets[1])],
+        )
+        .unwrap();
+        continue_recursive_circuit(
+            &mut interrupted,
+            previous_output,
+            FileLocation::PathBuf(wc_path.clone()),
+            r1cs.clone(),
+            vec![private_inputs[0].clone()],
+            start_input().to_vec(),
+            &public_params,
+        )
+        .unwrap();
+        write_proof_checkpoint(&interrupted, steps_for_degree(1) + 1, checkpoint.clone()).unwrap();
+
+        // resuming finishes only the chaff step and matches an uninterrupted run
+        let artifacts = ProvingArtifacts {
+            wc_path: wc_path.clone(),
+            r1cs: &r1cs,
+            public_params: &public_params,
+        };
+        let mut resumed = base.clone();
+        continue_nova_proof_resumable(
+            &artifacts,
+            &usernames,
+            &auth_secrets,
+            &mut resumed,
+            1,
+            checkpoint.clone(),
+        )
+        .unwrap();
+        assert!(!checkpoint.exists());
+        let mut chained = base.clone();
+        let (previous_output, _) =
+            verify_nova_proof(&chained, &public_params, steps_for_degree(1)).unwrap();
+        continue_nova_proof(
+            &usernames,
+            &auth_secrets,
+            &mut chained,
+            previous_output,
+            wc_path,
+            &r1cs,
+            &public_params,
+        )
+        .unwrap();
+        assert_eq!(compress_proof(&resumed), compress_proof(&chained));
+        let (outputs, _) =
+            verify_nova_proof(&resumed, &public_params, steps_for_degree(2)).unwrap();
+        assert_eq!(outputs[0], Fr::from(2));
+    }
+
+    #[test]
+    fn test_verify_chain() {
+        let phrase = String::from("Every link in the chain");
+        let usernames = vec![
+            String::from("mach34"),
+            String::from("jp4g"),
+            String::from("ianb"),
+        ];
+        let auth_secrets = vec![random_fr(), random_fr(), random_fr()];
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let wc_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts/grapevine_js/grapevine.wasm");
+        let r1cs = get_r1cs(Some(r1cs_path));
+        let public_params = get_public_params(Some(params_path));
+
+        // prove each degree by continuing the previous proof
+        let mut proof = nova_proof(
+            wc_path.clone(),
+            &r1cs,
+            &public_params,
+            &phrase,
+            &usernames[0..1].to_vec(),
+            &auth_secrets[0..1].to_vec(),
+        )
+        .unwrap();
+        let mut proofs = vec![proof.clone()];
+        for degree in 1..usernames.len() {
+            let (previous_output, _) =
+                verify_nova_proof(&proof, &public_params, steps_for_degree(degree)).unwrap();
+            continue_nova_proof(
+                &usernames[degree - 1..=degree].to_vec(),
+                &auth_secrets[degree - 1..=degree].to_vec(),
+                &mut proof,
+                previous_output,
+                wc_path.clone(),
+                &r1cs,
+                &public_params,
+            )
+            .unwrap();
+            proofs.push(proof.clone());
+        }
+        let provers = usernames
+            .iter()
+            .zip(&auth_secrets)
+            .map(|(username, auth_secret)| DegreeInputs {
+                username: username.clone(),
+                auth_secret: *auth_secret,
+            })
+            .collect::<Vec<_>>();
+        verify_chain(&proofs, &provers, &public_params).unwrap();
+
+        // a valid degree 2 proof from another user's chain does not link
+        let mut foreign = proofs[0].clone();
+        let (previous_output, _) =
+            verify_nova_proof(&foreign, &public_params, steps_for_degree(1)).unwrap();
+        continue_nova_proof(
+            &vec![usernames[0].clone(), String::from("ct")],
+            &vec![auth_secrets[0], random_fr()],
+            &mut foreign,
+            previous_output,
+            wc_path,
+            &r1cs,
+            &public_params,
+        )
+        .unwrap();
+        verify_nova_proof(&foreign, &public_params, steps_for_degree(2)).unwrap();
+        proofs[1] = foreign;
+        match verify_chain(&proofs, &provers, &public_params) {
+            Err(GrapevineError::ProofChainBroken(degree, _)) => assert_eq!(degree, 2),
+            res => panic!("Expected the chain to break at degree 2, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_verify_credential_proof() {
+        // Test that the proof in a credential verifies standalone and tampering is caught
+        let phrase = String::from("Take it with you");
+        let account = GrapevineAccount::new(String::from("mach34"));
+        let usernames = vec![account.username().clone()];
+        let auth_secrets = vec![random_fr()];
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let wc_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts/grapevine_js/grapevine.wasm");
+        let r1cs = get_r1cs(Some(r1cs_path));
+        let public_params = get_public_params(Some(params_path.clone()));
+        let params_hash = artifact_hash(&std::fs::read(&params_path).unwrap());
+
+        let proof = nova_proof(
+            wc_path,
+            &r1cs,
+            &public_params,
+            &phrase,
+            &usernames,
+            &auth_secrets,
+        )
+        .unwrap();
+        let phrase_hash = verify_nova_proof(&proof, &public_params, steps_for_degree(1))
+            .unwrap()
+            .0[1]
+            .to_bytes();
+
+        // round trip the credential through json and verify it with only the params
+        let credential = Credential::new(
+            &account,
+            account.username().clone(),
+            1,
+            &phrase_hash,
+            params_hash.clone(),
+            &compress_proof(&proof),
+        );
+        let json = serde_json::to_string_pretty(&credential).unwrap();
+        let parsed = serde_json::from_str::<Credential>(&json).unwrap();
+        verify_credential_proof(&parsed, &public_params, &params_hash).unwrap();
+
+        // credentials for other params are rejected
+        let other_params = artifact_hash(b"other params");
+        assert!(verify_credential_proof(&parsed, &public_params, &other_params).is_err());
+
+        // a validly signed credential claiming the wrong degree or phrase is rejected
+        let wrong_degree = Credential::new(
+            &account,
+            account.username().clone(),
+            2,
+            &phrase_hash,
+            params_hash.clone(),
+            &compress_proof(&proof),
+        );
+        assert!(verify_credential_proof(&wrong_degree, &public_params, &params_hash).is_err());
+        let wrong_phrase = Credential::new(
+            &account,
+            account.username().clone(),
+            1,
+            &random_fr().to_bytes(),
+            params_hash.clone(),
+            &compress_proof(&proof),
+        );
+        assert!(verify_credential_proof(&wrong_phrase, &public_params, &params_hash).is_err());
+
+        // the holder is not bound, so another account can rewrap the same proof as its own
+        let other = GrapevineAccount::new(String::from("not_mach34"));
+        let rewrapped = Credential::new(
+            &other,
+            account.username().clone(),
+            1,
+            &phrase_hash,
+            params_hash.clone(),
+            &compress_proof(&proof),
+        );
+        verify_credential_proof(&rewrapped, &public_params, &params_hash).unwrap();
+    }
 }
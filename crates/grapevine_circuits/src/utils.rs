@@ -2,10 +2,12 @@ use crate::{EMPTY_SECRET, SECRET_FIELD_LENGTH, ZERO};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use grapevine_common::errors::GrapevineError;
 use grapevine_common::utils::{convert_phrase_to_fr, convert_username_to_fr};
 use grapevine_common::{Fr, NovaProof, Params};
 use serde_json::{json, Value};
-use std::io::{Read, Write};
+use std::io::{Error, ErrorKind, Read, Write};
+use std::path::PathBuf;
 use std::{collections::HashMap, env::current_dir};
 
 /**
@@ -16,20 +18,22 @@ use std::{collections::HashMap, env::current_dir};
  * @param secret - optionally provide the secret to prove knowledge of if degree is 0
  * @param username - optionally provide one or both usernames to hash against
  *   - note: usernames[1] will never be 0 in practice
- * @return - the inputs for one computation step and chaff step
+ * @return - InvalidCircuitInput if the inputs do not form an identity step (secret, no previous
+ *           username or auth secret) or a degree step (no secret, previous username and auth
+ *           secret), or a phrase or username cannot be converted. Nothing is pushed on error
  */
 pub fn build_step_inputs(
     input: &mut Vec<HashMap<String, Value>>,
     secret: Option<String>,
     usernames: [Option<String>; 2],
     auth_secrets: [Option<Fr>; 2],
-) {
-    // @TODO: FIX convert_phrase_to_fr and convert_username_to_fr inputs
+) -> Result<(), GrapevineError> {
+    check_step_inputs(&secret, &usernames, &auth_secrets)?;
 
     // convert the compute step input to strings, or get the default value
     let secret_input: [String; SECRET_FIELD_LENGTH] = match secret {
         Some(phrase) => convert_phrase_to_fr(&phrase)
-            .unwrap()
+            .map_err(|e| GrapevineError::InvalidCircuitInput(e.to_string()))?
             .iter()
             .map(|chunk| format!("0x{}", hex::encode(chunk)))
             .collect::<Vec<String>>()
@@ -46,13 +50,12 @@ pub fn build_step_inputs(
     let usernames_input: [String; 2] = usernames
         .iter()
         .map(|username| match username {
-            Some(username) => format!(
-                "0x{}",
-                hex::encode(convert_username_to_fr(username).unwrap())
-            ),
-            None => String::from(ZERO),
+            Some(username) => convert_username_to_fr(username)
+                .map(|bytes| format!("0x{}", hex::encode(bytes)))
+                .map_err(|e| GrapevineError::InvalidCircuitInput(e.to_string())),
+            None => Ok(String::from(ZERO)),
         })
-        .collect::<Vec<String>>()
+        .collect::<Result<Vec<String>, GrapevineError>>()?
         .try_into()
         .unwrap();
     let auth_secrets_input: [String; 2] = auth_secrets
@@ -82,6 +85,40 @@ pub fn build_step_inputs(
     }
     input.push(compute_step);
     input.push(chaff_step);
+    Ok(())
+}
+
+/**
+ * Checks the optional inputs to a step are consistent with the type of step being built
+ *
+ * @param secret - the phrase if this is an identity (degree 0) step
+ * @param usernames - the previous and current usernames
+ * @param auth_secrets - the previous and current auth secrets
+ * @return - InvalidCircuitInput describing the first missing or unexpected input
+ */
+fn check_step_inputs(
+    secret: &Option<String>,
+    usernames: &[Option<String>; 2],
+    auth_secrets: &[Option<Fr>; 2],
+) -> Result<(), GrapevineError> {
+    if usernames[1].is_none() || auth_secrets[1].is_none() {
+        return Err(GrapevineError::InvalidCircuitInput(String::from(
+            "every step needs the current username and auth secret",
+        )));
+    }
+    // an identity step proves the phrase and has no previous prover
+    let identity = secret.is_some();
+    if identity && (usernames[0].is_some() || auth_secrets[0].is_some()) {
+        return Err(GrapevineError::InvalidCircuitInput(String::from(
+            "an identity step cannot include a previous username or auth secret",
+        )));
+    }
+    if !identity && (usernames[0].is_none() || auth_secrets[0].is_none()) {
+        return Err(GrapevineError::InvalidCircuitInput(String::from(
+            "a degree step needs the previous username and auth secret",
+        )));
+    }
+    Ok(())
 }
 
 /**
@@ -131,6 +168,47 @@ pub fn read_proof(path: std::path::PathBuf) -> NovaProof {
     decompress_proof(&compressed_proof[..])
 }
 
+/**
+ * Save a proof that is partway through being folded so proving can resume after an interruption
+ * @dev the checkpoint is the little endian step count followed by the compressed proof
+ *
+ * @param proof - the Nova Proof to save
+ * @param iterations - the number of folding steps already applied to the proof
+ * @param path - the filepath to save the checkpoint to - includes filename
+ * @return - an error if the checkpoint could not be written
+ */
+pub fn write_proof_checkpoint(
+    proof: &NovaProof,
+    iterations: usize,
+    path: PathBuf,
+) -> Result<(), std::io::Error> {
+    let mut checkpoint = (iterations as u64).to_le_bytes().to_vec();
+    checkpoint.extend(compress_proof(proof));
+    // write beside the checkpoint first so a crash mid-write never corrupts the previous one
+    let staged = path.with_extension("partial");
+    std::fs::write(&staged, checkpoint)?;
+    std::fs::rename(staged, path)
+}
+
+/**
+ * Read a proof saved partway through being folded
+ *
+ * @param path - the filepath to read the checkpoint from
+ * @return - the saved proof and the number of folding steps applied to it, or an error if the
+ *           checkpoint cannot be read or is malformed
+ */
+pub fn read_proof_checkpoint(path: PathBuf) -> Result<(NovaProof, usize), std::io::Error> {
+    let checkpoint = std::fs::read(path)?;
+    if checkpoint.len() < 8 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Truncated proof checkpoint",
+        ));
+    }
+    let iterations = u64::from_le_bytes(checkpoint[..8].try_into().unwrap()) as usize;
+    Ok((try_decompress_proof(&checkpoint[8..])?, iterations))
+}
+
 /**
  * Compress a Nova Proof with flate2 for transit to the server and storage
  *
@@ -154,18 +232,187 @@ pub fn compress_proof(proof: &NovaProof) -> Vec<u8> {
  * @return - the decompressed proof
  */
 pub fn decompress_proof(proof: &[u8]) -> NovaProof {
+    try_decompress_proof(proof).unwrap()
+}
+
+/**
+ * Decompress a Nova Proof, returning an error instead of panicking on malformed input
+ *
+ * @param proof - the compressed Nova Proof to decompress
+ * @return - the decompressed proof, or an error if the bytes are not a valid compressed proof
+ */
+pub fn try_decompress_proof(proof: &[u8]) -> Result<NovaProof, std::io::Error> {
     // decompress the proof into the serialized json string
     let mut decoder = GzDecoder::new(proof);
     let mut serialized = String::new();
-    decoder.read_to_string(&mut serialized).unwrap();
+    decoder.read_to_string(&mut serialized)?;
     // deserialize the proof
-    serde_json::from_str(&serialized).unwrap()
+    Ok(serde_json::from_str(&serialized)?)
+}
+
+/**
+ * Formats a proof can be downloaded in. Proofs are stored gzip compressed, so gzip costs the server
+ * nothing, zstd decompresses faster, and raw bincode skips decompression entirely at the cost of size
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofEncoding {
+    Gzip,
+    Zstd,
+    Raw,
+}
+
+impl ProofEncoding {
+    /**
+     * @return - the name of the format as sent in proof encoding headers
+     */
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProofEncoding::Gzip => "gzip",
+            ProofEncoding::Zstd => "zstd",
+            ProofEncoding::Raw => "raw",
+        }
+    }
+
+    /**
+     * @param name - the name of a format as sent in proof encoding headers
+     * @return - the format, or None if it is not supported
+     */
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "gzip" => Some(ProofEncoding::Gzip),
+            "zstd" => Some(ProofEncoding::Zstd),
+            "raw" | "identity" => Some(ProofEncoding::Raw),
+            _ => None,
+        }
+    }
+
+    /**
+     * Pick the format to send a proof in from a client's comma separated list of formats in order
+     * of preference (e.g. "zstd, gzip"). Quality weights (";q=") are ignored
+     *
+     * @param accept - the list of formats the client accepts, if it sent one
+     * @return - the first supported format in the list, or gzip if there is none
+     */
+    pub fn negotiate(accept: Option<&str>) -> Self {
+        accept
+            .and_then(|accept| {
+                accept
+                    .split(',')
+                    .filter_map(|format| Self::from_name(format.split(';').next().unwrap()))
+                    .next()
+            })
+            .unwrap_or(ProofEncoding::Gzip)
+    }
+}
+
+/**
+ * Encode a Nova Proof in the given format
+ *
+ * @param proof - the Nova Proof to encode
+ * @param encoding - the format to encode the proof in
+ * @return - the encoded proof
+ */
+pub fn encode_proof(proof: &NovaProof, encoding: ProofEncoding) -> Vec<u8> {
+    match encoding {
+        ProofEncoding::Gzip => compress_proof(proof),
+        ProofEncoding::Zstd => {
+            let serialized = serde_json::to_string(&proof).unwrap();
+            zstd::encode_all(serialized.as_bytes(), 0).unwrap()
+        }
+        ProofEncoding::Raw => bincode::serialize(proof).unwrap(),
+    }
+}
+
+/**
+ * Decode a Nova Proof sent in the given format
+ *
+ * @param proof - the encoded Nova Proof
+ * @param encoding - the format the proof was encoded in
+ * @return - the decoded proof, or an error if the bytes are not a valid proof in the format
+ */
+pub fn decode_proof(proof: &[u8], encoding: ProofEncoding) -> Result<NovaProof, Error> {
+    match encoding {
+        ProofEncoding::Gzip => try_decompress_proof(proof),
+        ProofEncoding::Zstd => {
+            let serialized = zstd::decode_all(proof)?;
+            Ok(serde_json::from_slice(&serialized)?)
+        }
+        ProofEncoding::Raw => {
+            bincode::deserialize(proof).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        }
+    }
+}
+
+/**
+ * Convert a stored (gzip compressed) Nova Proof to the given format
+ *
+ * @param proof - the gzip compressed Nova Proof
+ * @param encoding - the format to convert the proof to
+ * @return - the proof in the new format, or an error if the stored proof is malformed
+ */
+pub fn reencode_proof(proof: &[u8], encoding: ProofEncoding) -> Result<Vec<u8>, Error> {
+    match encoding {
+        ProofEncoding::Gzip => Ok(proof.to_vec()),
+        _ => Ok(encode_proof(&try_decompress_proof(proof)?, encoding)),
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn test_degree_step_missing_previous_username() {
+        let mut inputs = Vec::new();
+        let result = build_step_inputs(
+            &mut inputs,
+            None,
+            [None, Some(String::from("bob"))],
+            [Some(Fr::from(1)), Some(Fr::from(2))],
+        );
+        assert!(matches!(
+            result,
+            Err(GrapevineError::InvalidCircuitInput(_))
+        ));
+        assert!(inputs.is_empty());
+
+        // the same step with the previous username is accepted
+        build_step_inputs(
+            &mut inputs,
+            None,
+            [Some(String::from("alice")), Some(String::from("bob"))],
+            [Some(Fr::from(1)), Some(Fr::from(2))],
+        )
+        .unwrap();
+        assert_eq!(inputs.len(), 2);
+    }
+
+    #[test]
+    fn test_identity_step_with_previous_username() {
+        let mut inputs = Vec::new();
+        let result = build_step_inputs(
+            &mut inputs,
+            Some(String::from("phrase")),
+            [Some(String::from("alice")), Some(String::from("bob"))],
+            [None, Some(Fr::from(2))],
+        );
+        assert!(matches!(
+            result,
+            Err(GrapevineError::InvalidCircuitInput(_))
+        ));
+        assert!(inputs.is_empty());
+
+        // an identity step also pushes the initial chaff step
+        build_step_inputs(
+            &mut inputs,
+            Some(String::from("phrase")),
+            [None, Some(String::from("bob"))],
+            [None, Some(Fr::from(2))],
+        )
+        .unwrap();
+        assert_eq!(inputs.len(), 3);
+    }
+
     #[test]
     fn test_phrase_to_fr() {
         let phrase = String::from("And that's the waaaayyy the news goes");
@@ -179,4 +426,15 @@ mod test {
         let bytes = convert_username_to_fr(&username);
         println!("User bytes {:?}", bytes);
     }
+
+    #[test]
+    fn test_negotiate_proof_encoding() {
+        assert_eq!(ProofEncoding::negotiate(None), ProofEncoding::Gzip);
+        assert_eq!(
+            ProofEncoding::negotiate(Some("br, zstd;q=0.9, gzip")),
+            ProofEncoding::Zstd
+        );
+        assert_eq!(ProofEncoding::negotiate(Some("RAW")), ProofEncoding::Raw);
+        assert_eq!(ProofEncoding::negotiate(Some("br")), ProofEncoding::Gzip);
+    }
 }
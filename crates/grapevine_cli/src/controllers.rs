@@ -1,24 +1,53 @@
 use crate::http::{
-    add_relationship_req, create_user_req, degree_proof_req, get_account_details_req,
-    get_available_proofs_req, get_degrees_req, get_known_req, get_nonce_req, get_phrase_req,
-    get_proof_with_params_req, get_pubkey_req, get_relationships_req, phrase_req,
-    reject_relationship_req, show_connections_req,
+    add_relationship_req, create_user_req, deactivate_proof_req, degree_proof_batch_req,
+    degree_proof_req, download_proof_req, export_proofs_req, get_account_details_req,
+    get_available_proofs_req, get_circuit_info_req, get_connected_origins_req, get_degrees_req,
+    get_known_req, get_new_relationships_req, get_nonce_req, get_outbound_relationships_req,
+    get_phrase_full_req, get_phrase_list_req, get_phrase_proof_req, get_proof_with_params_req,
+    get_pubkey_req, get_relationships_req, health_req, import_proofs_req, migrate_proofs_req,
+    orphaned_relationships_req, phrase_req, reject_relationship_req, search_phrases_req,
+    update_phrase_description_req,
+};
+use crate::output::{
+    hex_string, AccountDiagnosis, AccountInfo, CredentialExported, CredentialVerified,
+    DegreeProvedVia, DegreesProved, DoctorCheck, ExportedKey, Identity, KnownPhrases, Message,
+    MigrationReport, MyDegrees, NewRelationships, Origins, OrphanedRelationships, PathProved,
+    PhraseDegree, PhraseDetails, PhraseHash, PhraseList, PhraseMatch, PhraseProved,
+    ProofDownloaded, ProofOutcome, ProofRevoked, ProofsExported, ProofsImported, Relationships,
+    SearchResults, SentRelationships,
 };
 use crate::utils::artifacts_guard;
-use crate::utils::fs::{use_public_params, use_r1cs, use_wasm, ACCOUNT_PATH};
-use grapevine_circuits::nova::{continue_nova_proof, nova_proof, verify_nova_proof};
+use crate::utils::fs::{
+    check_artifacts_exist, check_artifacts_match, get_pending_path, read_pending_proof,
+    read_public_params_with_hash, read_relationships_last_seen, save_pending_proof,
+    session_account, set_account_nonce, use_artifacts, write_relationships_last_seen,
+    GrapevineArtifacts,
+};
+use grapevine_circuits::nova::{
+    continue_nova_proof, nova_proof, verify_credential_proof, verify_nova_proof,
+};
+use grapevine_circuits::steps_for_degree;
 use grapevine_circuits::utils::{compress_proof, decompress_proof};
 use grapevine_common::account::GrapevineAccount;
 use grapevine_common::auth_secret::AuthSecretEncrypted;
+use grapevine_common::backup::read_backup;
+use grapevine_common::credential::Credential;
 use grapevine_common::errors::GrapevineError;
 use grapevine_common::http::requests::{DegreeProofRequest, PhraseRequest};
+use grapevine_common::http::responses::{DegreeData, DegreeProofBatchResult};
+use grapevine_common::models::ProvingData;
+use grapevine_common::utils::{
+    normalize_username, validate_phrase, validate_tags, validate_username,
+};
+use grapevine_common::{MAX_DEGREE_PROOF_BATCH, REQUEST_VERSION};
 
-use std::path::Path;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
 
 /**
  * Get the details of the current account
  */
-pub async fn account_details() -> Result<String, GrapevineError> {
+pub async fn account_details() -> Result<AccountInfo, GrapevineError> {
     // get account
     let mut account = match get_account() {
         Ok(account) => account,
@@ -26,46 +55,54 @@ pub async fn account_details() -> Result<String, GrapevineError> {
     };
     // sync nonce
     synchronize_nonce().await?;
-    let pubkey = hex::encode(account.pubkey().compress());
+    let pubkey = hex_string(account.pubkey().compress());
 
     // Fetch account stats
-    let res = get_account_details_req(&mut account).await;
+    let details = get_account_details_req(&mut account).await?;
+    Ok(AccountInfo {
+        username: account.username().clone(),
+        pubkey,
+        first_degree_count: details.first_degree_count,
+        second_degree_count: details.second_degree_count,
+        phrase_count: details.phrase_count,
+    })
+}
 
-    match res {
-        Ok(_) => {
-            let details = res.unwrap();
-            Ok(format!(
-                "Username: {}\nPublic key: 0x{}\n# 1st degree connections: {}\n# 2nd degree connections: {}\n# phrases created: {}",
-                account.username(),
-                pubkey,
-                details.1,
-                details.2,
-                details.0
-            ))
-        }
-        Err(e) => Err(e),
-    }
+/**
+ * Print the username and public key of the local account without contacting the server
+ */
+pub fn whoami() -> Result<Identity, GrapevineError> {
+    whoami_at(&account_file_path())
+}
+
+/**
+ * Print the username and public key of the account stored at a given path
+ *
+ * @param path - the path to the account file
+ */
+fn whoami_at(path: &Path) -> Result<Identity, GrapevineError> {
+    // get account
+    let account = read_account(path)?;
+    Ok(Identity {
+        username: account.username().clone(),
+        pubkey: hex_string(account.pubkey().compress()),
+    })
 }
 
 /**
  * Export the private key of the current account
  */
-pub fn export_key() -> Result<String, GrapevineError> {
+pub fn export_key() -> Result<ExportedKey, GrapevineError> {
     // get account
     let account = match get_account() {
         Ok(account) => account,
         Err(e) => return Err(e),
     };
-    // Get private key
-    let pk = hex::encode(account.private_key_raw());
-    // Get auth secret
-    let auth_secret = hex::encode(account.auth_secret().to_bytes());
-    Ok(format!(
-        "Sensitive account details for {}:\nPrivate Key: 0x{}\nAuth Secret: 0x{}",
-        account.username(),
-        pk,
-        auth_secret
-    ))
+    Ok(ExportedKey {
+        username: account.username().clone(),
+        private_key: hex_string(account.private_key_raw()),
+        auth_secret: hex_string(account.auth_secret().to_bytes()),
+    })
 }
 
 /**
@@ -73,15 +110,11 @@ pub fn export_key() -> Result<String, GrapevineError> {
  *
  * @param username - the username to register
  */
-pub async fn register(username: &String) -> Result<String, GrapevineError> {
-    // check username is < 30 chars
-    if username.len() > 30 {
-        return Err(GrapevineError::UsernameTooLong(username.clone()));
-    }
-    // check username is ascii
-    if !username.is_ascii() {
-        return Err(GrapevineError::UsernameNotAscii(username.clone()));
-    }
+pub async fn register(username: &String) -> Result<Message, GrapevineError> {
+    // sign and prove with the same NFC form the server stores
+    let username = &normalize_username(username);
+    // check username is non-empty and < 30 bytes (the server decides whether unicode is allowed)
+    validate_username(username, true)?;
     // make account (or retrieve from fs)
     let account = make_or_get_account(username.clone())?;
     // build request body
@@ -89,7 +122,10 @@ pub async fn register(username: &String) -> Result<String, GrapevineError> {
     // send create user request
     let res = create_user_req(body).await;
     match res {
-        Ok(_) => Ok(format!("Success: registered account for \"{}\"", username)),
+        Ok(_) => Ok(Message::new(format!(
+            "Success: registered account for \"{}\"",
+            username
+        ))),
         Err(e) => Err(e),
     }
 }
@@ -99,7 +135,7 @@ pub async fn register(username: &String) -> Result<String, GrapevineError> {
  *
  * @param username - the username of the user to add a connection to
  */
-pub async fn add_relationship(username: &String) -> Result<String, GrapevineError> {
+pub async fn add_relationship(username: &String) -> Result<Message, GrapevineError> {
     // get own account
     let mut account = get_account()?;
     // sync nonce
@@ -114,7 +150,7 @@ pub async fn add_relationship(username: &String) -> Result<String, GrapevineErro
     // send add relationship request
     let res = add_relationship_req(&mut account, body).await;
     match res {
-        Ok(message) => Ok(message),
+        Ok(message) => Ok(Message::new(message)),
         Err(e) => Err(e),
     }
 }
@@ -124,7 +160,7 @@ pub async fn add_relationship(username: &String) -> Result<String, GrapevineErro
  *
  * @param username - the username of the user to reject the relationship with
  */
-pub async fn reject_relationship(username: &String) -> Result<String, GrapevineError> {
+pub async fn reject_relationship(username: &String) -> Result<Message, GrapevineError> {
     // get account
     let mut account = get_account()?;
     // sync nonce
@@ -132,53 +168,77 @@ pub async fn reject_relationship(username: &String) -> Result<String, GrapevineE
     // send request
     let res = reject_relationship_req(username, &mut account).await;
     match res {
-        Ok(_) => Ok(format!(
+        Ok(_) => Ok(Message::new(format!(
             "Success: rejected pending relationship with \"{}\"",
             username
-        )),
+        ))),
         Err(e) => Err(e),
     }
 }
 
+/**
+ * Show relationship requests received since the last time this command was run
+ */
+pub async fn get_new_relationships() -> Result<NewRelationships, GrapevineError> {
+    // get account
+    let mut account = get_account()?;
+    // sync nonce
+    synchronize_nonce().await?;
+    // get the time requests were last checked and the current time
+    let since = read_relationships_last_seen();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as u32;
+    // send request
+    let usernames = get_new_relationships_req(since, &mut account).await?;
+    write_relationships_last_seen(now)?;
+    Ok(NewRelationships { usernames })
+}
+
+/**
+ * Gets the relationship requests this account sent that have not been accepted yet
+ */
+pub async fn get_sent_relationships() -> Result<SentRelationships, GrapevineError> {
+    // get account
+    let mut account = get_account()?;
+    // sync nonce
+    synchronize_nonce().await?;
+    // send request
+    let usernames = get_outbound_relationships_req(&mut account).await?;
+    Ok(SentRelationships { usernames })
+}
+
 /**
  * Gets all (pending, active) relationships for the account
  *
  * @param active - whether to get active relationships or pending relationships
  */
-pub async fn get_relationships(active: bool) -> Result<String, GrapevineError> {
+pub async fn get_relationships(active: bool) -> Result<Relationships, GrapevineError> {
     // get account
     let mut account = get_account()?;
     // sync nonce
     synchronize_nonce().await?;
     // send request
-    let res = get_relationships_req(active, &mut account).await;
-    match res {
-        Ok(data) => {
-            let relation_type = if active { "Active" } else { "Pending" };
-            if data.len() == 0 {
-                println!("No {} relationships found for this account", relation_type);
-                return Ok(String::from(""));
-            }
-            println!("===============================");
-            println!(
-                "Showing {} {} relationships for {}:",
-                data.len(),
-                relation_type,
-                account.username()
-            );
-            for relationship in data {
-                println!("|=> \"{}\"", relationship);
-            }
-            Ok(String::from(""))
-        }
-        Err(e) => Err(e),
-    }
+    let relationships = get_relationships_req(active, &mut account).await?;
+    Ok(Relationships {
+        username: account.username().clone(),
+        active,
+        relationships,
+    })
 }
 
 /**
  * Retrieve the current nonce for the account and synchronize it with the locally stored account
  */
 pub async fn synchronize_nonce() -> Result<String, GrapevineError> {
+    // an account kept in memory by the repl was synchronized when it was kept
+    if let Some(account) = session_account() {
+        return Ok(format!(
+            "Nonce is already synchronized at \"{}\"",
+            account.nonce()
+        ));
+    }
     // get the account
     let mut account = get_account()?;
     // build nonce request body
@@ -200,107 +260,254 @@ pub async fn synchronize_nonce() -> Result<String, GrapevineError> {
                 account.nonce(),
                 expected_nonce
             );
-            account
-                .set_nonce(expected_nonce, Some((&**ACCOUNT_PATH).to_path_buf()))
-                .unwrap();
+            set_account_nonce(&mut account, expected_nonce)?;
             Ok(msg)
         }
     }
 }
 
+/**
+ * Compare the local account with the server's record of it and the local proving artifacts with
+ * the circuit the server verifies against. Read-only unless `fix` is set
+ *
+ * @param fix - whether to synchronize the local nonce to the server's if they differ
+ */
+pub async fn account_doctor(fix: bool) -> Result<AccountDiagnosis, GrapevineError> {
+    let mut account = get_account()?;
+    let server_pubkey = match get_pubkey_req(account.username().clone()).await {
+        Ok(pubkey) => Some(pubkey.compress()),
+        Err(GrapevineError::UserNotFound(_)) => None,
+        Err(e) => return Err(e),
+    };
+    // the nonce request is signed, so it fails if the server holds a different key for the user
+    let server_nonce = get_nonce_req(account.get_nonce_request()).await.ok();
+    let circuit_info = get_circuit_info_req().await?;
+    let artifacts_match = check_artifacts_exist() && check_artifacts_match(&circuit_info);
+    let mut diagnosis = diagnose_account(
+        &account,
+        server_pubkey,
+        server_nonce,
+        &circuit_info.circuit_version,
+        artifacts_match,
+    );
+    if let (true, Some(nonce)) = (fix, server_nonce) {
+        if diagnosis.failed("nonce") {
+            set_account_nonce(&mut account, nonce)?;
+            diagnosis
+                .fixed
+                .push(format!("local nonce synchronized to {}", nonce));
+        }
+    }
+    Ok(diagnosis)
+}
+
+/**
+ * Build the `account doctor` report from the state read locally and from the server
+ *
+ * @param account - the local account
+ * @param server_pubkey - the pubkey the server holds for the username (none if not registered)
+ * @param server_nonce - the nonce the server expects (none if it could not be read)
+ * @param circuit_version - the version of the circuit the server verifies against
+ * @param artifacts_match - whether the local artifacts exist and match the server's
+ * @returns - a check for registration, pubkey, nonce, and artifacts
+ */
+pub fn diagnose_account(
+    account: &GrapevineAccount,
+    server_pubkey: Option<[u8; 32]>,
+    server_nonce: Option<u64>,
+    circuit_version: &String,
+    artifacts_match: bool,
+) -> AccountDiagnosis {
+    let unavailable = || String::from("unavailable");
+    let local_pubkey = account.pubkey().compress();
+    let checks = vec![
+        DoctorCheck {
+            name: String::from("registered"),
+            local: account.username().clone(),
+            server: match server_pubkey {
+                Some(_) => account.username().clone(),
+                None => String::from("not found"),
+            },
+            ok: server_pubkey.is_some(),
+        },
+        DoctorCheck {
+            name: String::from("pubkey"),
+            local: hex_string(local_pubkey),
+            server: server_pubkey.map(hex_string).unwrap_or_else(unavailable),
+            ok: server_pubkey == Some(local_pubkey),
+        },
+        DoctorCheck {
+            name: String::from("nonce"),
+            local: account.nonce().to_string(),
+            server: server_nonce
+                .map(|nonce| nonce.to_string())
+                .unwrap_or_else(unavailable),
+            ok: server_nonce == Some(account.nonce()),
+        },
+        DoctorCheck {
+            name: String::from("artifacts"),
+            local: String::from(match artifacts_match {
+                true => "match",
+                false => "missing or stale",
+            }),
+            server: format!("circuit version {}", circuit_version),
+            ok: artifacts_match,
+        },
+    ];
+    AccountDiagnosis {
+        username: account.username().clone(),
+        checks,
+        fixed: vec![],
+    }
+}
+
+/**
+ * Compute the hash of a phrase the same way the circuit does, without proving or contacting
+ * the server
+ *
+ * @param phrase - the phrase to hash
+ */
+pub fn hash_phrase(phrase: &String) -> Result<PhraseHash, GrapevineError> {
+    // check that phrase fits in the circuit
+    validate_phrase(&phrase)?;
+    let hash = grapevine_common::crypto::hash_phrase(phrase)?;
+    Ok(PhraseHash {
+        phrase_hash: hex_string(hash),
+    })
+}
+
 /**
  * Create a new phrase and proves knowledge of it
  * @notice if phrase does not exists, creates new phrase. Otherwise, proves knowledge of existing phrase
  *
  * @param phrase - the phrase to create
  * @param description - the description of the phrase (discarded if phrase exists)
+ * @param tags - the tags to list the phrase under (discarded if phrase exists)
  */
-pub async fn prove_phrase(phrase: &String, description: &String) -> Result<String, GrapevineError> {
+pub async fn prove_phrase(
+    phrase: &String,
+    description: &String,
+    tags: &Vec<String>,
+) -> Result<PhraseProved, GrapevineError> {
     // ensure artifacts are present
     artifacts_guard().await.unwrap();
-    let params = use_public_params().unwrap();
-    let r1cs = use_r1cs().unwrap();
-    let wc_path = use_wasm().unwrap();
+    let artifacts = use_artifacts().unwrap();
     // get account
     let mut account = get_account()?;
     // sync nonce
     synchronize_nonce().await?;
 
-    // check that phrase is > 180 chars
-    if phrase.len() > 180 {
-        return Err(GrapevineError::PhraseTooLong);
-    }
+    // check that phrase fits in the circuit and the tags can be stored
+    validate_phrase(&phrase)?;
+    validate_tags(tags)?;
 
     // prove phrase
     let username = vec![account.username().clone()];
     let auth_secret = vec![account.auth_secret().clone()];
-    let proof = nova_proof(wc_path, &r1cs, &params, &phrase, &username, &auth_secret).unwrap();
+    let proof = nova_proof(
+        artifacts.wasm.clone(),
+        &artifacts.r1cs,
+        &artifacts.params,
+        &phrase,
+        &username,
+        &auth_secret,
+    )
+    .unwrap();
 
     // compress proof
     let compressed = compress_proof(&proof);
     // encrypt phrase
-    let ciphertext = account.encrypt_phrase(&phrase);
+    let ciphertext = account.encrypt_phrase(&phrase)?;
 
     // build request body
     let body = PhraseRequest {
+        version: REQUEST_VERSION,
         proof: compressed,
         ciphertext,
         description: description.clone(),
+        tags: tags.clone(),
     };
+    // keep the proof on disk until the server accepts it
+    let pending = save_pending_proof(&get_pending_path()?, &body)?;
     // send request
-    let res = phrase_req(&mut account, body).await;
-    match res {
-        Ok(data) => match data.new_phrase {
-            true => Ok(format!(
-                "Success: Created and proved knowledge of new phrase #{}: \"{}\"",
-                data.phrase_index, phrase
-            )),
-            false => Ok(format!(
-                "Success: Proved knowledge of existing phrase #{}: \"{}\"",
-                data.phrase_index, phrase
-            )),
-        },
-        Err(e) => Err(e),
-    }
+    let data = match phrase_req(&mut account, body).await {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Proof saved to {}", pending.display());
+            eprintln!(
+                "Retry submitting it with `grapevine phrase submit-proof {}`",
+                pending.display()
+            );
+            return Err(e);
+        }
+    };
+    let _ = std::fs::remove_file(&pending);
+    Ok(PhraseProved {
+        phrase_index: data.phrase_index,
+        new_phrase: data.new_phrase,
+        phrase: phrase.clone(),
+    })
 }
 
-pub async fn prove_all_available() -> Result<String, GrapevineError> {
+/**
+ * Submits a phrase proof saved by a failed `phrase prove` without proving it again
+ *
+ * @param path - the path of the saved proof
+ * @returns - the phrase index and whether the phrase is new
+ */
+pub async fn submit_phrase_proof(path: &String) -> Result<PhraseProved, GrapevineError> {
+    // get account
+    let mut account = get_account()?;
+    // sync nonce
+    synchronize_nonce().await?;
+
+    // read the saved request
+    let path = Path::new(path);
+    let body = read_pending_proof(path)?;
+    // the phrase is only stored encrypted to this account
+    let phrase = account.decrypt_phrase(&body.ciphertext)?;
+
+    // send request
+    let data = phrase_req(&mut account, body).await?;
+    let _ = std::fs::remove_file(path);
+    Ok(PhraseProved {
+        phrase_index: data.phrase_index,
+        new_phrase: data.new_phrase,
+        phrase,
+    })
+}
+
+pub async fn prove_all_available() -> Result<DegreesProved, GrapevineError> {
     // GETTING
     // get account
     let mut account = get_account()?;
     // sync nonce
     synchronize_nonce().await?;
     // get available proofs
-    let res = get_available_proofs_req(&mut account).await;
+    let res = get_available_proofs_req(&mut account, None).await;
     // handle result
     let proofs = match res {
         Ok(proofs) => proofs,
         Err(e) => {
-            println!("Failed to get available proofs");
+            eprintln!("Failed to get available proofs");
             return Err(e);
         }
     };
-    match proofs.len() {
-        0 => {
-            println!();
-            return Ok(format!(
-                "No new degree proofs found for user \"{}\"",
-                account.username()
-            ));
-        }
-        _ => (),
+    let mut report = DegreesProved {
+        username: account.username().clone(),
+        available: proofs.len(),
+        submitted: 0,
+        proofs: vec![],
+    };
+    if proofs.len() == 0 {
+        return Ok(report);
     }
     // PROVING
     // ensure proving artifacts are downloaded
     artifacts_guard().await.unwrap();
-    let public_params = use_public_params().unwrap();
-    let r1cs = use_r1cs().unwrap();
-    let wc_path = use_wasm().unwrap();
-    if proofs.len() == 0 {
-        return Ok(String::from("No new degree proofs found"));
-    } else {
-        println!("Proving {} new degrees...", proofs.len());
-    }
+    let artifacts = use_artifacts().unwrap();
+    eprintln!("Proving {} new degrees...", proofs.len());
+    let mut pending: Vec<DegreeProofRequest> = vec![];
     for i in 0..proofs.len() {
         let oid = proofs[i].clone();
         // get proof and encrypted auth secret
@@ -309,211 +516,624 @@ pub async fn prove_all_available() -> Result<String, GrapevineError> {
             Ok(proving_data) => proving_data,
             Err(e) => return Err(e),
         };
-        println!(
+        eprintln!(
             "=-=-=-=-=-=-=[Phrase #{}]=-=-=-=-=-=-=",
             proving_data.phrase_index
         );
-        println!("Description: \"{}\"", proving_data.description);
-        println!("Phrase hash: 0x{}", hex::encode(proving_data.phrase_hash));
-        println!("Degree being proved: {}", proving_data.degree + 1);
-        println!("Proving...");
-        // prepare inputs
-        let auth_secret_encrypted = AuthSecretEncrypted {
-            ephemeral_key: proving_data.ephemeral_key,
-            ciphertext: proving_data.ciphertext,
-            username: proving_data.username,
-            recipient: account.pubkey().compress(),
-        };
-        let auth_secret = account.decrypt_auth_secret(auth_secret_encrypted);
-        let mut proof = decompress_proof(&proving_data.proof);
-        let verified =
-            verify_nova_proof(&proof, &public_params, (proving_data.degree * 2) as usize);
-        let previous_output = match verified {
-            Ok(data) => data.0,
-            Err(_) => {
-                println!("Verification Failed");
-                return Err(GrapevineError::DegreeProofVerificationFailed);
-            }
-        };
-        // build nova proof
-        let username_input = vec![auth_secret.username, account.username().clone()];
-        let auth_secret_input = vec![auth_secret.auth_secret, account.auth_secret().clone()];
-        match continue_nova_proof(
-            &username_input,
-            &auth_secret_input,
-            &mut proof,
-            previous_output,
-            wc_path.clone(),
-            &r1cs,
-            &public_params,
-        ) {
-            Ok(_) => (),
-            Err(_) => {
-                println!("Proof continuation failed");
-                return Err(GrapevineError::DegreeProofVerificationFailed);
-            }
-        }
-        let compressed = compress_proof(&proof);
-        // build request body
-        let body = DegreeProofRequest {
-            proof: compressed,
-            // username: account.username().clone(),
-            previous: oid,
+        eprintln!("Description: \"{}\"", proving_data.description);
+        eprintln!("Phrase hash: 0x{}", hex::encode(proving_data.phrase_hash));
+        eprintln!("Degree being proved: {}", proving_data.degree + 1);
+        eprintln!("Proving...");
+        // record the relationship being built on for the report
+        report.proofs.push(ProofOutcome {
+            previous: oid.clone(),
+            relation: proving_data.username.clone(),
             degree: proving_data.degree + 1,
-        };
-        // handle response from server
-        let res: Result<(), GrapevineError> = degree_proof_req(&mut account, body).await;
-        match res {
-            Ok(_) => (),
-            Err(e) => return Err(e),
-        }
-        println!(
-            "Proved degree {} for phrase #{}",
-            proving_data.degree + 1,
-            proving_data.phrase_index
-        );
+            status: 0,
+        });
+        let (degree, phrase_index) = (proving_data.degree + 1, proving_data.phrase_index);
+        pending.push(build_degree_proof(&account, &artifacts, oid, proving_data)?);
+        eprintln!("Proved degree {} for phrase #{}", degree, phrase_index);
+    }
+    // submit the proofs, batching them if there is more than one
+    if pending.len() == 1 {
+        degree_proof_req(&mut account, pending.remove(0)).await?;
+        report.submitted = 1;
+        report.proofs[0].status = 201;
+        return Ok(report);
     }
-    Ok(format!(
-        "Success: proved {} new degree proofs",
-        proofs.len()
-    ))
+    for batch in pending.chunks(MAX_DEGREE_PROOF_BATCH) {
+        let results = degree_proof_batch_req(&mut account, batch.to_vec()).await?;
+        record_batch_results(&mut report, &results);
+    }
+    Ok(report)
 }
 
-pub async fn get_my_proofs() -> Result<String, GrapevineError> {
+/**
+ * Prove a degree of separation from a phrase through one specific relationship, instead of
+ * through every available proof like `prove_all_available`
+ *
+ * @param relation - the username of the relationship whose proof to build on
+ * @param phrase_index - the index of the phrase to prove a degree of separation from
+ */
+pub async fn prove_via(
+    relation: &String,
+    phrase_index: u32,
+) -> Result<DegreeProvedVia, GrapevineError> {
     // get account
     let mut account = get_account()?;
     // sync nonce
     synchronize_nonce().await?;
-    // send request
-    let res = get_degrees_req(&mut account).await;
-    let data = match res {
-        Ok(data) => data,
-        Err(e) => return Err(e),
+    // get the proving data of each available proof to find the one made by the relationship
+    let proofs = get_available_proofs_req(&mut account, None).await?;
+    let mut candidates = vec![];
+    for oid in proofs {
+        let proving_data = get_proof_with_params_req(&mut account, oid.clone()).await?;
+        candidates.push((oid, proving_data));
+    }
+    let (oid, proving_data) = match select_proof_via(candidates, relation, phrase_index) {
+        Some(candidate) => candidate,
+        None => {
+            eprintln!(
+                "No available proof of phrase #{} from \"{}\"",
+                phrase_index, relation
+            );
+            return Err(GrapevineError::DegreeProofNotFound);
+        }
     };
-    println!(
-        "Proofs of {}'s degrees of separation from phrases/ users:",
-        account.username()
+    // build and submit the proof
+    artifacts_guard().await.unwrap();
+    let artifacts = use_artifacts().unwrap();
+    eprintln!(
+        "Proving degree {} of phrase #{} through \"{}\"...",
+        proving_data.degree + 1,
+        phrase_index,
+        relation
     );
-    for degree in data {
-        println!(
-            "=-=-=-=-=-=-=[Phrase #{}]=-=-=-=-=-=-=",
-            degree.phrase_index
-        );
-        println!("Phrase description: \"{}\"", degree.description);
-        println!("Phrase hash: 0x{}", hex::encode(degree.phrase_hash));
-        println!(
-            "Degrees of separation from origin: {}",
-            degree.degree.unwrap()
-        );
-        if degree.relation.is_none() {
-            println!("Phrase created by this user");
-            let phrase = account.decrypt_phrase(&degree.secret_phrase.unwrap());
-            println!("Secret phrase: \"{}\"", phrase);
-        } else {
-            println!("Your relation: {}", degree.relation.unwrap());
-            if degree.preceding_relation.is_some() {
-                println!(
-                    "2nd degree relation: {}",
-                    degree.preceding_relation.unwrap()
-                );
-            }
+    let degree = proving_data.degree + 1;
+    let body = build_degree_proof(&account, &artifacts, oid.clone(), proving_data)?;
+    degree_proof_req(&mut account, body).await?;
+    Ok(DegreeProvedVia {
+        phrase_index,
+        relation: relation.clone(),
+        degree,
+        previous: oid,
+    })
+}
+
+/**
+ * Prove a degree of separation from a user by building on the shortest available chain of proofs
+ * from a phrase the user created
+ *
+ * @param target - the username of the user to prove a degree of separation from
+ */
+pub async fn prove_path(target: &String) -> Result<PathProved, GrapevineError> {
+    // get account
+    let mut account = get_account()?;
+    // sync nonce
+    synchronize_nonce().await?;
+    // available proofs from the target's phrases come back shortest first
+    let proofs = get_available_proofs_req(&mut account, Some(target)).await?;
+    let oid = match proofs.into_iter().next() {
+        Some(oid) => oid,
+        None => {
+            eprintln!("No available chain of proofs leads to \"{}\"", target);
+            return Err(GrapevineError::DegreeProofNotFound);
+        }
+    };
+    let proving_data = get_proof_with_params_req(&mut account, oid.clone()).await?;
+    // build and submit the proof
+    artifacts_guard().await.unwrap();
+    let artifacts = use_artifacts().unwrap();
+    eprintln!(
+        "Proving degree {} from \"{}\" on phrase #{} through \"{}\"...",
+        proving_data.degree + 1,
+        target,
+        proving_data.phrase_index,
+        proving_data.username
+    );
+    let (degree, phrase_index) = (proving_data.degree + 1, proving_data.phrase_index);
+    let relation = proving_data.username.clone();
+    let body = build_degree_proof(&account, &artifacts, oid.clone(), proving_data)?;
+    degree_proof_req(&mut account, body).await?;
+    Ok(PathProved {
+        target: target.clone(),
+        phrase_index,
+        relation,
+        degree,
+        previous: oid,
+    })
+}
+
+/**
+ * Pick the available proof of a phrase that was made by a given relationship
+ *
+ * @param candidates - the oid and proving data of each available proof
+ * @param relation - the username of the relationship that made the proof
+ * @param phrase_index - the index of the phrase the proof is on
+ * @return - the oid and proving data of the matching proof, if any
+ */
+fn select_proof_via(
+    candidates: Vec<(String, ProvingData)>,
+    relation: &String,
+    phrase_index: u32,
+) -> Option<(String, ProvingData)> {
+    candidates.into_iter().find(|(_, proving_data)| {
+        &proving_data.username == relation && proving_data.phrase_index == phrase_index
+    })
+}
+
+/**
+ * Build a degree proof on top of a relationship's proof
+ *
+ * @param account - the account proving the degree
+ * @param artifacts - the loaded proving artifacts
+ * @param oid - the oid of the relationship's proof to build on
+ * @param proving_data - the relationship's proof and encrypted auth secret
+ * @return - the request body for submitting the new degree proof
+ */
+fn build_degree_proof(
+    account: &GrapevineAccount,
+    artifacts: &GrapevineArtifacts,
+    oid: String,
+    proving_data: ProvingData,
+) -> Result<DegreeProofRequest, GrapevineError> {
+    // prepare inputs
+    let auth_secret_encrypted = AuthSecretEncrypted {
+        ephemeral_key: proving_data.ephemeral_key,
+        ciphertext: proving_data.ciphertext,
+        username: proving_data.username,
+        recipient: account.pubkey().compress(),
+    };
+    let auth_secret = account.decrypt_auth_secret(auth_secret_encrypted);
+    let mut proof = decompress_proof(&proving_data.proof);
+    let iterations = steps_for_degree(proving_data.degree as usize);
+    let verified = verify_nova_proof(&proof, &artifacts.params, iterations);
+    let previous_output = match verified {
+        Ok(data) => data.0,
+        Err(_) => {
+            eprintln!("Verification Failed");
+            return Err(GrapevineError::DegreeProofVerificationFailed);
+        }
+    };
+    // build nova proof
+    let username_input = vec![auth_secret.username.clone(), account.username().clone()];
+    let auth_secret_input = vec![auth_secret.auth_secret, account.auth_secret().clone()];
+    match continue_nova_proof(
+        &username_input,
+        &auth_secret_input,
+        &mut proof,
+        previous_output,
+        artifacts.wasm.clone(),
+        &artifacts.r1cs,
+        &artifacts.params,
+    ) {
+        Ok(_) => (),
+        Err(_) => {
+            eprintln!("Proof continuation failed");
+            return Err(GrapevineError::DegreeProofVerificationFailed);
+        }
+    }
+    let compressed = compress_proof(&proof);
+    // build request body
+    Ok(DegreeProofRequest {
+        version: REQUEST_VERSION,
+        proof: compressed,
+        previous: oid,
+        degree: proving_data.degree + 1,
+    })
+}
+
+/**
+ * Record the status the server gave each proof in a batch on the sync report
+ *
+ * @param report - the sync report holding an outcome for each proof that was built
+ * @param results - the status of each proof in the batch, keyed by the proof it was built on
+ */
+fn record_batch_results(report: &mut DegreesProved, results: &[DegreeProofBatchResult]) {
+    for result in results {
+        if let Some(proof) = report
+            .proofs
+            .iter_mut()
+            .find(|proof| proof.previous == result.previous)
+        {
+            proof.status = result.status;
+        }
+        match result.status {
+            200 | 201 => report.submitted += 1,
+            status => eprintln!(
+                "Server rejected proof built on {} with status {}",
+                result.previous, status
+            ),
         }
     }
-    Ok(String::from(""))
 }
 
-pub async fn get_known_phrases() -> Result<String, GrapevineError> {
+/**
+ * Update the description of a phrase created by this account
+ *
+ * @param phrase_index - the index of the phrase to update
+ * @param description - the new description of the phrase
+ */
+pub async fn set_phrase_description(
+    phrase_index: u32,
+    description: &String,
+) -> Result<Message, GrapevineError> {
     // get account
     let mut account = get_account()?;
     // sync nonce
     synchronize_nonce().await?;
     // send request
-    let res = get_known_req(&mut account).await;
-    let data = match res {
-        Ok(data) => data,
-        Err(e) => return Err(e),
-    };
-    for degree in data {
-        println!(
-            "=-=-=-=-=-=-=[Phrase #{}]=-=-=-=-=-=-=",
-            degree.phrase_index
+    let res = update_phrase_description_req(phrase_index, description.clone(), &mut account).await;
+    match res {
+        Ok(_) => Ok(Message::new(format!(
+            "Success: updated description of phrase #{} to \"{}\"",
+            phrase_index, description
+        ))),
+        Err(e) => Err(e),
+    }
+}
+
+/**
+ * Re-serialize every stored proof on the server in the current proof format, batch by batch
+ * @notice requires GRAPEVINE_ADMIN_TOKEN to match the token configured on the server. The
+ *         migration can be resumed from the last reported OID if interrupted
+ *
+ * @param after - the stringified OID to resume after (start of the collection if none)
+ * @param batch - the number of proofs to migrate per request
+ */
+pub async fn migrate_proofs(
+    after: &Option<String>,
+    batch: u32,
+) -> Result<MigrationReport, GrapevineError> {
+    let admin_token = admin_token()?;
+    let mut after = after.clone();
+    let (mut processed, mut migrated, mut failed) = (0, 0, vec![]);
+    loop {
+        let res = migrate_proofs_req(&admin_token, after.as_ref(), batch).await?;
+        if res.processed == 0 {
+            break;
+        }
+        processed += res.processed;
+        migrated += res.migrated;
+        failed.extend(res.failed);
+        after = res.last;
+        eprintln!(
+            "Processed {} proofs ({} migrated, {} failed), resume with --after {}",
+            processed,
+            migrated,
+            failed.len(),
+            after.as_ref().unwrap()
         );
-        println!("Description: \"{}\"", degree.description);
-        println!("Phrase hash: 0x{}", hex::encode(degree.phrase_hash));
-        let phrase = account.decrypt_phrase(&degree.secret_phrase.unwrap());
-        println!("Secret phrase: \"{}\"", phrase);
     }
-    Ok(String::from(""))
+    Ok(MigrationReport {
+        processed,
+        migrated,
+        failed,
+    })
 }
 
-pub async fn get_phrase(phrase_index: u32) -> Result<String, GrapevineError> {
+/**
+ * Back up every stored proof on the server to a file, then check the file against its footer
+ * @notice requires GRAPEVINE_ADMIN_TOKEN to match the token configured on the server
+ *
+ * @param path - the file to write the backup to
+ */
+pub async fn export_proofs(path: &String) -> Result<ProofsExported, GrapevineError> {
+    let admin_token = admin_token()?;
+    export_proofs_req(&admin_token, path).await?;
+    let count = verify_backup_file(path)?;
+    Ok(ProofsExported {
+        path: path.clone(),
+        count,
+    })
+}
+
+/**
+ * Restore proofs on the server from a backup written by `admin export-proofs`
+ * @notice requires GRAPEVINE_ADMIN_TOKEN to match the token configured on the server. The backup
+ *         is checked locally first since the server restores proofs as they are uploaded
+ *
+ * @param path - the file the backup is read from
+ */
+pub async fn import_proofs(path: &String) -> Result<ProofsImported, GrapevineError> {
+    let admin_token = admin_token()?;
+    verify_backup_file(path)?;
+    let count = import_proofs_req(&admin_token, path).await?;
+    Ok(ProofsImported {
+        path: path.clone(),
+        count,
+    })
+}
+
+/**
+ * List relationships on the server whose references on users are out of sync, optionally
+ * repairing them
+ * @notice requires GRAPEVINE_ADMIN_TOKEN to match the token configured on the server
+ *
+ * @param repair - whether to repair the orphaned relationships instead of only listing them
+ */
+pub async fn orphaned_relationships(repair: bool) -> Result<OrphanedRelationships, GrapevineError> {
+    let admin_token = admin_token()?;
+    let relationships = orphaned_relationships_req(&admin_token, repair).await?;
+    Ok(OrphanedRelationships {
+        relationships,
+        repaired: repair,
+    })
+}
+
+/**
+ * Read the admin token for admin commands from GRAPEVINE_ADMIN_TOKEN
+ */
+fn admin_token() -> Result<String, GrapevineError> {
+    match std::env::var("GRAPEVINE_ADMIN_TOKEN") {
+        Ok(token) => Ok(token),
+        Err(_) => Err(GrapevineError::HeaderError(String::from(
+            "GRAPEVINE_ADMIN_TOKEN is not set",
+        ))),
+    }
+}
+
+/**
+ * Check that a proof backup file is complete and matches its footer
+ *
+ * @param path - the backup file
+ * @return - the number of proofs in the backup
+ */
+fn verify_backup_file(path: &String) -> Result<u64, GrapevineError> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) => return Err(GrapevineError::FsError(e.to_string())),
+    };
+    read_backup(BufReader::new(file), |_| Ok(()))
+}
+
+/**
+ * Revoke a degree proof made by this account
+ * @notice any degree proofs built on top of the revoked proof are flagged as inactive
+ *
+ * @param oid - the stringified ObjectID of the proof to revoke
+ */
+pub async fn revoke_proof(oid: &String) -> Result<ProofRevoked, GrapevineError> {
     // get account
     let mut account = get_account()?;
     // sync nonce
     synchronize_nonce().await?;
-    // get degree data
-    let res = get_phrase_req(phrase_index, &mut account).await;
-    let phrase_data = match res {
-        Ok(data) => data,
-        Err(e) => return Err(e),
-    };
-    // get connection data
-    let res = show_connections_req(phrase_index, &mut account).await;
-    let connection_data = match res {
-        Ok(data) => data,
-        Err(e) => return Err(e),
-    };
-
-    // OUTPUT
-    // header (always shown)
-    println!("=-=-=-=-=-=-=[Phrase #{}]=-=-=-=-=-=-=", phrase_index);
-    println!("Phrase description: \"{}\"", &phrase_data.description);
-    println!("Phrase hash: 0x{}", hex::encode(&phrase_data.phrase_hash));
-    // if no degree, show that this user does not know the phrase
-    if phrase_data.degree.is_none() {
-        println!("You do not have any connections to this phrase!");
-        return Ok(String::from(""));
+    // send request
+    let res = deactivate_proof_req(&mut account, oid).await;
+    match res {
+        Ok(flagged) => Ok(ProofRevoked {
+            proof: oid.clone(),
+            flagged,
+        }),
+        Err(e) => Err(e),
     }
-    if phrase_data.secret_phrase.is_some() {
-        // If phrase is known, show secret
-        let decrypted_phrase = account.decrypt_phrase(&phrase_data.secret_phrase.unwrap());
-        println!("Secret phrase: \"{}\"", decrypted_phrase);
-    } else {
-        // If phrase is not known, show degrees of separation from origin + upstream relations
-        println!(
-            "Degrees of separation from phrase: {}",
-            phrase_data.degree.unwrap()
-        );
-        if phrase_data.relation.is_some() {
-            println!(
-                "Your 1st degree relation to this phrase: {}",
-                phrase_data.relation.unwrap()
-            );
-        }
-        if phrase_data.preceding_relation.is_some() {
-            println!(
-                "Your 2nd degree relation to this phrase: {}",
-                phrase_data.preceding_relation.unwrap()
-            );
-        }
+}
+
+/**
+ * Download the compressed bytes of a proof made by this account
+ *
+ * @param oid - the stringified ObjectID of the proof to download
+ * @param path - the file to write the proof to
+ * @returns - where the proof was saved
+ */
+pub async fn download_proof(
+    oid: &String,
+    path: &String,
+) -> Result<ProofDownloaded, GrapevineError> {
+    // get account
+    let mut account = get_account()?;
+    // sync nonce
+    synchronize_nonce().await?;
+    // send request
+    let proof = download_proof_req(&mut account, oid).await?;
+    if let Err(e) = std::fs::write(path, &proof) {
+        return Err(GrapevineError::FsError(e.to_string()));
     }
-    // Show connection data
-    println!("#####################");
-    println!("Total of {} connections to this phrase", connection_data.0);
-    for i in 0..connection_data.1.len() {
-        let connections = connection_data.1.get(i).unwrap();
-        let degree_plural = match i == 0 {
-            true => "degree",
-            false => "degrees",
-        };
-        println!(
-            "Relationships with {} {} connection to this phrase: {}",
-            i + 1,
-            degree_plural,
-            connections
-        );
+    Ok(ProofDownloaded {
+        proof: oid.clone(),
+        path: path.clone(),
+        bytes: proof.len(),
+    })
+}
+
+/**
+ * Package this account's proof of a phrase into a bearer credential signed by this account, whose
+ * proof can be verified offline with `verify-credential`
+ *
+ * @param index - the index of the phrase
+ * @param path - the file to write the credential to
+ */
+pub async fn export_credential(
+    index: u32,
+    path: &String,
+) -> Result<CredentialExported, GrapevineError> {
+    // get account
+    let mut account = get_account()?;
+    // sync nonce
+    synchronize_nonce().await?;
+    // find and download the proof
+    let reference = get_phrase_proof_req(index, &mut account).await?;
+    let proof = download_proof_req(&mut account, &reference.proof).await?;
+    let data = get_phrase_full_req(index, &mut account).await?;
+    // fingerprint the params the server verified the proof against
+    let circuit_info = get_circuit_info_req().await?;
+    let credential = Credential::new(
+        &account,
+        reference.origin.clone(),
+        reference.degree,
+        &data.phrase.phrase_hash,
+        circuit_info.params_hash,
+        &proof,
+    );
+    let serialized = serde_json::to_string_pretty(&credential).unwrap();
+    if let Err(e) = std::fs::write(path, serialized) {
+        return Err(GrapevineError::FsError(e.to_string()));
     }
-    Ok(String::from(""))
+    Ok(CredentialExported {
+        phrase_index: index,
+        degree: reference.degree,
+        origin: reference.origin,
+        path: path.clone(),
+    })
+}
+
+/**
+ * Verify the proof in a credential against the public params in the artifact directory without
+ * contacting the server
+ * @notice - the holder and origin named by the credential are not verified
+ *
+ * @param path - the file the credential is read from
+ */
+pub fn verify_credential_file(path: &String) -> Result<CredentialVerified, GrapevineError> {
+    let serialized = match std::fs::read_to_string(path) {
+        Ok(serialized) => serialized,
+        Err(e) => return Err(GrapevineError::FsError(e.to_string())),
+    };
+    let credential = match serde_json::from_str::<Credential>(&serialized) {
+        Ok(credential) => credential,
+        Err(e) => return Err(GrapevineError::InvalidCredential(e.to_string())),
+    };
+    let (public_params, params_hash) = read_public_params_with_hash()?;
+    verify_credential_proof(&credential, &public_params, &params_hash)?;
+    Ok(CredentialVerified {
+        claimed_holder: credential.holder,
+        claimed_origin: credential.origin,
+        degree: credential.degree,
+        phrase_hash: credential.phrase_hash,
+    })
+}
+
+/**
+ * List the degree proofs made by this account
+ *
+ * @param degree - if provided, only list proofs of exactly this degree
+ * @param max_degree - if provided, only list proofs of at most this degree
+ */
+pub async fn get_my_proofs(
+    degree: Option<u8>,
+    max_degree: Option<u8>,
+) -> Result<MyDegrees, GrapevineError> {
+    // get account
+    let mut account = get_account()?;
+    // sync nonce
+    synchronize_nonce().await?;
+    // send request
+    let data = get_degrees_req(&mut account, degree, max_degree).await?;
+    let degrees = data
+        .into_iter()
+        .map(|degree| phrase_degree(&account, degree))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(MyDegrees {
+        username: account.username().clone(),
+        degrees,
+    })
+}
+
+/**
+ * List every phrase this account has a proof on with its degree and connection count
+ */
+pub async fn get_phrase_list() -> Result<PhraseList, GrapevineError> {
+    // get account
+    let mut account = get_account()?;
+    // sync nonce
+    synchronize_nonce().await?;
+    // send request
+    let phrases = get_phrase_list_req(&mut account).await?;
+    Ok(PhraseList { phrases })
+}
+
+/**
+ * List every phrase creator this account has a proof back to, with the lowest degree to each
+ */
+pub async fn get_connected_origins() -> Result<Origins, GrapevineError> {
+    // get account
+    let mut account = get_account()?;
+    // sync nonce
+    synchronize_nonce().await?;
+    // send request
+    let origins = get_connected_origins_req(&mut account).await?;
+    Ok(Origins { origins })
+}
+
+/**
+ * Search the descriptions of phrases this account or its relationships have proofs on
+ *
+ * @param query - the case-insensitive text to search for
+ */
+pub async fn search_phrases(query: &String) -> Result<SearchResults, GrapevineError> {
+    // get account
+    let mut account = get_account()?;
+    // sync nonce
+    synchronize_nonce().await?;
+    // send request
+    let phrases = search_phrases_req(query, &mut account)
+        .await?
+        .into_iter()
+        .map(|phrase| PhraseMatch {
+            phrase_index: phrase.phrase_index,
+            description: phrase.description,
+            phrase_hash: hex_string(phrase.phrase_hash),
+        })
+        .collect();
+    Ok(SearchResults {
+        query: query.clone(),
+        phrases,
+    })
+}
+
+pub async fn get_known_phrases() -> Result<KnownPhrases, GrapevineError> {
+    // get account
+    let mut account = get_account()?;
+    // sync nonce
+    synchronize_nonce().await?;
+    // send request
+    let data = get_known_req(&mut account).await?;
+    let phrases = data
+        .into_iter()
+        .map(|degree| phrase_degree(&account, degree))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(KnownPhrases { phrases })
+}
+
+pub async fn get_phrase(phrase_index: u32) -> Result<PhraseDetails, GrapevineError> {
+    // get account
+    let mut account = get_account()?;
+    // sync nonce
+    synchronize_nonce().await?;
+    // get degree data and connection data
+    let data = get_phrase_full_req(phrase_index, &mut account).await?;
+    Ok(PhraseDetails {
+        phrase: phrase_degree(&account, data.phrase)?,
+        total_connections: data.total_connections,
+        connections_by_degree: data.connections_by_degree,
+    })
+}
+
+/**
+ * Convert a degree returned by the server into output, decrypting the phrase if it is known
+ *
+ * @param account - the account the phrase ciphertext is encrypted to
+ * @param degree - the degree data returned by the server
+ * @returns - the phrase as seen by this account, or an error if the phrase fails to decrypt
+ */
+fn phrase_degree(
+    account: &GrapevineAccount,
+    degree: DegreeData,
+) -> Result<PhraseDegree, GrapevineError> {
+    let secret_phrase = match degree.secret_phrase {
+        Some(ciphertext) => Some(account.decrypt_phrase(&ciphertext)?),
+        None => None,
+    };
+    Ok(PhraseDegree {
+        phrase_index: degree.phrase_index,
+        description: degree.description,
+        phrase_hash: hex_string(degree.phrase_hash),
+        degree: degree.degree,
+        relation: degree.relation,
+        preceding_relation: degree.preceding_relation,
+        secret_phrase,
+    })
 }
 
 pub fn make_or_get_account(username: String) -> Result<GrapevineAccount, GrapevineError> {
@@ -545,7 +1165,7 @@ pub fn make_or_get_account(username: String) -> Result<GrapevineAccount, Grapevi
             let account = GrapevineAccount::new(username);
             let json = serde_json::to_string(&account).unwrap();
             std::fs::write(&grapevine_account_path, json).unwrap();
-            println!(
+            eprintln!(
                 "Created Grapevine account at {}",
                 grapevine_account_path.display()
             );
@@ -556,18 +1176,15 @@ pub fn make_or_get_account(username: String) -> Result<GrapevineAccount, Grapevi
     Ok(account)
 }
 
-pub async fn health() -> Result<String, GrapevineError> {
-    println!("SERVER URL IS: {}", &**crate::http::SERVER_URL);
+pub async fn health() -> Result<Message, GrapevineError> {
+    eprintln!("SERVER URL IS: {}", &**crate::http::SERVER_URL);
     // ensure artifacts exist
     artifacts_guard().await.unwrap();
     // get health status
-    reqwest::get(&**crate::http::SERVER_URL)
-        .await
-        .unwrap()
-        .text()
-        .await
-        .unwrap();
-    return Ok("Health check passed".to_string());
+    match health_req().await {
+        Ok(_) => Ok(Message::new("Health check passed")),
+        Err(e) => Err(e),
+    }
 }
 
 /**
@@ -576,13 +1193,31 @@ pub async fn health() -> Result<String, GrapevineError> {
  * @returns - the Grapevine account
  */
 pub fn get_account() -> Result<GrapevineAccount, GrapevineError> {
-    // get grapevine path
-    let grapevine_account_path = Path::new(&std::env::var("HOME").unwrap())
+    // use the account kept in memory by the repl if there is one
+    if let Some(account) = session_account() {
+        return Ok(account);
+    }
+    read_account(&account_file_path())
+}
+
+/**
+ * @returns - the path to the account file in ~/.grapevine
+ */
+fn account_file_path() -> PathBuf {
+    Path::new(&std::env::var("HOME").unwrap())
         .join(".grapevine")
-        .join("grapevine.key");
-    // if ~/.grapevine doesn't exist, create it
-    match grapevine_account_path.exists() {
-        true => match GrapevineAccount::from_fs(grapevine_account_path) {
+        .join("grapevine.key")
+}
+
+/**
+ * Attempts to read a Grapevine account from a given path and fails if it cannot
+ *
+ * @param path - the path to the account file
+ * @returns - the Grapevine account
+ */
+fn read_account(path: &Path) -> Result<GrapevineAccount, GrapevineError> {
+    match path.exists() {
+        true => match GrapevineAccount::from_fs(path.to_path_buf()) {
             Ok(account) => Ok(account),
             Err(_) => Err(GrapevineError::FsError(String::from(
                 "Error reading existing Grapevine account from filesystem",
@@ -595,3 +1230,109 @@ pub fn get_account() -> Result<GrapevineAccount, GrapevineError> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_whoami_offline() {
+        // read an account saved in a temp dir; whoami never makes a request
+        let dir = std::env::temp_dir().join("grapevine_whoami_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let account = GrapevineAccount::new(String::from("whoami_user"));
+        account.save(dir.join("grapevine.key")).unwrap();
+
+        let res = whoami_at(&dir.join("grapevine.key")).unwrap().to_string();
+        assert!(res.contains("whoami_user"));
+        assert!(res.contains(&hex::encode(account.pubkey().compress())));
+        assert!(!res.contains(&hex::encode(account.private_key_raw())));
+    }
+
+    #[test]
+    fn test_doctor_reports_drifted_nonce() {
+        let mut account = GrapevineAccount::new(String::from("doctor_user"));
+        account.set_nonce(3, None).unwrap();
+        let pubkey = Some(account.pubkey().compress());
+        let version = String::from("1");
+
+        // the server expects a later nonce than the local account holds
+        let diagnosis = diagnose_account(&account, pubkey, Some(5), &version, true);
+        assert!(diagnosis.failed("nonce"));
+        assert!(!diagnosis.failed("registered"));
+        assert!(!diagnosis.failed("pubkey"));
+        assert!(!diagnosis.failed("artifacts"));
+        let report = diagnosis.to_string();
+        assert!(report.contains("[MISMATCH] nonce: local 3, server 5"));
+        assert!(report.contains("--fix"));
+
+        // nothing is reported once the nonces agree
+        let diagnosis = diagnose_account(&account, pubkey, Some(3), &version, true);
+        assert!(diagnosis.checks.iter().all(|check| check.ok));
+        assert!(!diagnosis.to_string().contains("MISMATCH"));
+    }
+
+    #[test]
+    fn test_sync_report_has_entry_per_proof() {
+        let outcome = |previous: &str, relation: &str| ProofOutcome {
+            previous: String::from(previous),
+            relation: String::from(relation),
+            degree: 2,
+            status: 0,
+        };
+        let mut report = DegreesProved {
+            username: String::from("sync_user"),
+            available: 3,
+            submitted: 0,
+            proofs: vec![
+                outcome("a", "alice"),
+                outcome("b", "bob"),
+                outcome("c", "carol"),
+            ],
+        };
+        let result = |previous: &str, status: u16| DegreeProofBatchResult {
+            previous: String::from(previous),
+            status,
+        };
+        let results = [result("a", 201), result("b", 409), result("c", 200)];
+        record_batch_results(&mut report, &results);
+
+        assert_eq!(report.submitted, 2);
+        let statuses: Vec<u16> = report.proofs.iter().map(|proof| proof.status).collect();
+        assert_eq!(statuses, vec![201, 409, 200]);
+        // the text output lists each accepted proof with its relation and degree
+        let text = report.to_string();
+        assert_eq!(text.lines().count(), 3);
+        assert!(text.contains("Degree 2 through \"alice\" (built on a)"));
+        assert!(!text.contains("bob"));
+        assert!(text.contains("carol"));
+    }
+
+    #[test]
+    fn test_select_proof_via_relation() {
+        let candidate = |oid: &str, username: &str, phrase_index: u32| {
+            let proving_data = ProvingData {
+                phrase_index,
+                phrase_hash: [0u8; 32],
+                description: String::from("Shared phrase"),
+                degree: 1,
+                proof: vec![],
+                username: String::from(username),
+                ephemeral_key: [0u8; 32],
+                ciphertext: [0u8; 48],
+            };
+            (String::from(oid), proving_data)
+        };
+        let candidates = vec![candidate("a", "alice", 3), candidate("b", "bob", 3)];
+
+        // the proof made by the named relationship is chosen over the other relation
+        let (oid, proving_data) =
+            select_proof_via(candidates.clone(), &String::from("bob"), 3).unwrap();
+        assert_eq!(oid, "b");
+        assert_eq!(proving_data.username, "bob");
+
+        // nothing is chosen if the relationship has no proof of the phrase
+        assert!(select_proof_via(candidates.clone(), &String::from("bob"), 4).is_none());
+        assert!(select_proof_via(candidates, &String::from("carol"), 3).is_none());
+    }
+}
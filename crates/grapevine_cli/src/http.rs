@@ -1,22 +1,262 @@
-use crate::utils::fs::ACCOUNT_PATH;
-use babyjubjub_rs::{decompress_point, Point};
+use crate::utils::fs::increment_account_nonce;
+use babyjubjub_rs::Point;
+use grapevine_common::crypto::decompress_pubkey;
 use grapevine_common::http::requests::{
-    CreateUserRequest, DegreeProofRequest, GetNonceRequest, PhraseRequest,
-    NewRelationshipRequest,
+    CreateUserRequest, DegreeProofBatchRequest, DegreeProofRequest, GetNonceRequest,
+    NewRelationshipRequest, PhraseDescriptionRequest, PhraseRequest,
+};
+use grapevine_common::http::responses::{
+    AccountDetailsResponse, CircuitInfoResponse, ConnectedOrigin, DegreeData,
+    DegreeProofBatchResult, PhraseCreationResponse, PhraseFullResponse, PhraseProofResponse,
+    PhraseSearchResult, PhraseSummary, ProofMigrationResponse,
 };
-use grapevine_common::http::responses::{DegreeData, PhraseCreationResponse};
 use grapevine_common::models::ProvingData;
-use grapevine_common::{account::GrapevineAccount, errors::GrapevineError};
+use grapevine_common::{account::GrapevineAccount, errors::GrapevineError, REQUEST_VERSION};
 use lazy_static::lazy_static;
-use reqwest::{Client, StatusCode};
+use reqwest::{Body, Client, Method, RequestBuilder, Response, StatusCode, Url};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// Default number of seconds to wait when connecting to the server
+pub const DEFAULT_CONNECT_TIMEOUT: u64 = 10;
+/// Default number of seconds to wait for a full response from the server (includes proof uploads)
+pub const DEFAULT_REQUEST_TIMEOUT: u64 = 120;
+/// Default number of attempts made for requests that are safe to retry
+pub const DEFAULT_RETRY_ATTEMPTS: u32 = 3;
+/// Default number of milliseconds to wait before the first retry (doubles with each retry)
+pub const DEFAULT_RETRY_BASE_DELAY: u64 = 500;
 
 lazy_static! {
     pub static ref SERVER_URL: String = String::from(env!("SERVER_URL"));
+    static ref CLIENT: Client = build_client(
+        timeout_from_env("GRAPEVINE_CONNECT_TIMEOUT", DEFAULT_CONNECT_TIMEOUT),
+        timeout_from_env("GRAPEVINE_REQUEST_TIMEOUT", DEFAULT_REQUEST_TIMEOUT),
+    );
+    pub static ref RETRY_POLICY: RetryPolicy = RetryPolicy {
+        attempts: match std::env::var("GRAPEVINE_RETRY_ATTEMPTS") {
+            Ok(value) => value.parse::<u32>().unwrap_or(DEFAULT_RETRY_ATTEMPTS),
+            Err(_) => DEFAULT_RETRY_ATTEMPTS,
+        },
+        base_delay: Duration::from_millis(match std::env::var("GRAPEVINE_RETRY_BASE_DELAY") {
+            Ok(value) => value.parse::<u64>().unwrap_or(DEFAULT_RETRY_BASE_DELAY),
+            Err(_) => DEFAULT_RETRY_BASE_DELAY,
+        }),
+    };
+}
+
+/**
+ * Exponential backoff policy for requests that fail for transient reasons
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /**
+     * @param retry - the number of retries already made
+     * @returns - the time to wait before the next retry
+     */
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.base_delay * 2u32.saturating_pow(retry)
+    }
 }
 // pub const SERVER_URL: &str = "http://localhost:8000";
 
+/**
+ * Reads a timeout in seconds from the environment, falling back to a default if unset or invalid
+ *
+ * @param key - the name of the environment variable
+ * @param default - the number of seconds to use if the variable is not set
+ * @returns - the timeout duration
+ */
+fn timeout_from_env(key: &str, default: u64) -> Duration {
+    let seconds = match std::env::var(key) {
+        Ok(value) => value.parse::<u64>().unwrap_or(default),
+        Err(_) => default,
+    };
+    Duration::from_secs(seconds)
+}
+
+/**
+ * Builds the http client shared by all requests to the Grapevine server
+ *
+ * @param connect_timeout - the maximum time to wait when establishing a connection
+ * @param request_timeout - the maximum time to wait for a request to complete
+ * @returns - the configured reqwest client
+ */
+fn build_client(connect_timeout: Duration, request_timeout: Duration) -> Client {
+    Client::builder()
+        .connect_timeout(connect_timeout)
+        .timeout(request_timeout)
+        .build()
+        .unwrap()
+}
+
+/**
+ * Sends a request, converting timeouts into a GrapevineError
+ *
+ * @param request - the request to send
+ * @param url - the url the request is sent to (used in error reporting)
+ * @returns - the response from the server
+ */
+async fn send_request(request: RequestBuilder, url: &String) -> Result<Response, GrapevineError> {
+    match request.send().await {
+        Ok(res) => Ok(res),
+        Err(e) => match e.is_timeout() {
+            true => Err(GrapevineError::RequestTimeout(url.clone())),
+            false => {
+                eprintln!("Error sending request to {}: {}", url, e);
+                Err(GrapevineError::InternalError)
+            }
+        },
+    }
+}
+
+/**
+ * Reads the error sent with an unsuccessful response
+ * @notice: some responses (e.g. most 404s) carry a plain text message instead of a json
+ *          GrapevineError, so the body is not assumed to parse
+ *
+ * @param res - the unsuccessful response from the server
+ * @returns - the error sent by the server, or UnexpectedResponse with the status and body
+ */
+async fn response_error(res: Response) -> GrapevineError {
+    let status = res.status().as_u16();
+    let body = match res.text().await {
+        Ok(body) => body,
+        Err(e) => e.to_string(),
+    };
+    match serde_json::from_str::<GrapevineError>(&body) {
+        Ok(error) => error,
+        Err(_) => GrapevineError::UnexpectedResponse(status, body),
+    }
+}
+
+/**
+ * Sends a request, retrying with exponential backoff on timeouts, connection failures, and 5xx
+ * responses. Client errors (4xx) are returned immediately.
+ * @notice: only use for requests without a nonce signature, as the server consumes the nonce even
+ *          when it fails to respond
+ *
+ * @param request - the request to send (sent once without retries if the body is a stream)
+ * @param url - the url the request is sent to (used in error reporting)
+ * @param policy - the number of attempts and base delay between them
+ * @returns - the first non-transient response, or RetriesExhausted with the final error
+ */
+pub async fn send_request_with_retry(
+    request: RequestBuilder,
+    url: &String,
+    policy: &RetryPolicy,
+) -> Result<Response, GrapevineError> {
+    let attempts = policy.attempts.max(1);
+    let mut attempt = 0;
+    loop {
+        let to_send = match request.try_clone() {
+            Some(to_send) => to_send,
+            None => return send_request(request, url).await,
+        };
+        attempt += 1;
+        let last_error = match send_request(to_send, url).await {
+            Ok(res) => match res.status().is_server_error() {
+                true => res.status().to_string(),
+                false => return Ok(res),
+            },
+            Err(e) => e.to_string(),
+        };
+        if attempt >= attempts {
+            return Err(GrapevineError::RetriesExhausted(
+                url.clone(),
+                attempt,
+                last_error,
+            ));
+        }
+        tokio::time::sleep(policy.delay(attempt - 1)).await;
+    }
+}
+
+/**
+ * Signs a gated request with the account's current nonce, binding the signature to the method,
+ * path, and query so it cannot be replayed against another route or with other parameters
+ * @notice: the path is signed as the server routes it, so any path in SERVER_URL (e.g. a reverse
+ *          proxy prefix) is left out
+ *
+ * @param account - the account making the request
+ * @param method - the http method of the request
+ * @param url - the full url the request is sent to
+ * @returns - the hex-encoded signature for the X-Authorization header
+ */
+fn request_signature(account: &GrapevineAccount, method: Method, url: &String) -> String {
+    let url = Url::parse(url).unwrap();
+    let path = url.path().to_string();
+    let server_path = Url::parse(&**SERVER_URL).unwrap().path().to_string();
+    let path = path
+        .strip_prefix(server_path.trim_end_matches('/'))
+        .unwrap_or(&path);
+    hex::encode(
+        account
+            .sign_request(method.as_str(), path, url.query(), None)
+            .compress(),
+    )
+}
+
 /// GET REQUESTS ///
 
+/**
+ * Makes an HTTP Request to check that the server is reachable
+ *
+ * @returns - Ok if the server responds, or the error type otherwise
+ */
+pub async fn health_req() -> Result<(), GrapevineError> {
+    let url = format!("{}/test/health", &**SERVER_URL);
+    send_request_with_retry(CLIENT.get(&url), &url, &RETRY_POLICY).await?;
+    Ok(())
+}
+
+/**
+ * Makes an HTTP Request to back up every stored proof, streaming the backup into a file
+ *
+ * @param admin_token - the admin token configured on the server
+ * @param path - the file to write the backup to
+ * @returns - Ok if the full backup was written, or the error type otherwise
+ */
+pub async fn export_proofs_req(admin_token: &String, path: &String) -> Result<(), GrapevineError> {
+    let url = format!("{}/proof/admin/export", &**SERVER_URL);
+    let request = CLIENT.get(&url).header("X-Admin-Token", admin_token);
+    let mut res = send_request(request, &url).await?;
+    if res.status() != StatusCode::OK {
+        return Err(response_error(res).await);
+    }
+    let fs_error = |e: std::io::Error| GrapevineError::FsError(e.to_string());
+    let mut file = tokio::fs::File::create(path).await.map_err(fs_error)?;
+    loop {
+        match res.chunk().await {
+            Ok(Some(chunk)) => file.write_all(&chunk).await.map_err(fs_error)?,
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("Error reading backup from {}: {}", url, e);
+                return Err(GrapevineError::InternalError);
+            }
+        }
+    }
+    file.flush().await.map_err(fs_error)
+}
+
+/**
+ * Makes an HTTP Request to get the version and fingerprints of the server's circuit artifacts
+ *
+ * @returns - the circuit info the server verifies proofs with
+ */
+pub async fn get_circuit_info_req() -> Result<CircuitInfoResponse, GrapevineError> {
+    let url = format!("{}/proof/circuit-info", &**SERVER_URL);
+    let res = send_request_with_retry(CLIENT.get(&url), &url, &RETRY_POLICY).await?;
+    match res.status() {
+        StatusCode::OK => Ok(res.json::<CircuitInfoResponse>().await.unwrap()),
+        _ => Err(response_error(res).await),
+    }
+}
+
 /**
  * Makes an HTTP Request to get the public key of a user
  *
@@ -25,54 +265,51 @@ lazy_static! {
  */
 pub async fn get_pubkey_req(username: String) -> Result<Point, GrapevineError> {
     let url = format!("{}/user/{}/pubkey", &**SERVER_URL, username);
-    let res = reqwest::get(&url).await.unwrap();
+    let res = send_request_with_retry(CLIENT.get(&url), &url, &RETRY_POLICY).await?;
     match res.status() {
         StatusCode::OK => {
             let pubkey = res.text().await.unwrap();
-            Ok(decompress_point(hex::decode(pubkey).unwrap().try_into().unwrap()).unwrap())
+            decompress_pubkey(&hex::decode(pubkey).unwrap().try_into().unwrap())
         }
         StatusCode::NOT_FOUND => Err(GrapevineError::UserNotFound(username)),
-        _ => Err(res.json::<GrapevineError>().await.unwrap()),
+        _ => Err(response_error(res).await),
     }
 }
 
 pub async fn get_nonce_req(body: GetNonceRequest) -> Result<u64, GrapevineError> {
     let url = format!("{}/user/nonce", &**SERVER_URL);
-    let client = Client::new();
-    let res = client.post(&url).json(&body).send().await.unwrap();
+    let res = send_request_with_retry(CLIENT.post(&url).json(&body), &url, &RETRY_POLICY).await?;
     match res.status() {
         StatusCode::OK => {
             let nonce = res.text().await.unwrap();
             Ok(nonce.parse().unwrap())
         }
-        _ => Err(res.json::<GrapevineError>().await.unwrap()),
+        _ => Err(response_error(res).await),
     }
 }
 
 pub async fn get_available_proofs_req(
     account: &mut GrapevineAccount,
+    origin: Option<&String>,
 ) -> Result<Vec<String>, GrapevineError> {
-    let url = format!("{}/proof/available", &**SERVER_URL);
-    // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
-    let client = Client::new();
-    let res = client
+    let url = match origin {
+        Some(origin) => format!("{}/proof/available?origin={}", &**SERVER_URL, origin),
+        None => format!("{}/proof/available", &**SERVER_URL),
+    };
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::GET, &url);
+    let request = CLIENT
         .get(&url)
         .header("X-Username", account.username())
         .header("X-Authorization", signature)
-        .send()
-        .await
-        .unwrap();
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
     match res.status() {
         StatusCode::OK => {
-            // increment nonce
-            account
-                .increment_nonce(Some((&**ACCOUNT_PATH).to_path_buf()))
-                .unwrap();
             let proofs = res.json::<Vec<String>>().await.unwrap();
             Ok(proofs)
         }
-        _ => Err(res.json::<GrapevineError>().await.unwrap()),
+        _ => Err(response_error(res).await),
     }
 }
 
@@ -81,30 +318,100 @@ pub async fn get_proof_with_params_req(
     oid: String,
 ) -> Result<ProvingData, GrapevineError> {
     let url = format!("{}/proof/params/{}", &**SERVER_URL, oid);
-    // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
-    let client = Client::new();
-    let res = client
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::GET, &url);
+    let request = CLIENT
         .get(&url)
         .header("X-Username", account.username())
         .header("X-Authorization", signature)
-        .send()
-        .await
-        .unwrap();
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
     match res.status() {
         StatusCode::OK => {
-            // increment nonce
-            account
-                .increment_nonce(Some((&**ACCOUNT_PATH).to_path_buf()))
-                .unwrap();
             let proof = res.json::<ProvingData>().await.unwrap();
             Ok(proof)
         }
-        _ => Err(res.json::<GrapevineError>().await.unwrap()),
+        _ => Err(response_error(res).await),
     }
 }
 
 /// POST REQUESTS ///
+/**
+ * Makes an HTTP Request to re-serialize a batch of stored proofs in the current proof format
+ *
+ * @param admin_token - the admin token configured on the server
+ * @param after - the stringified OID to resume after (start of the collection if none)
+ * @param limit - the maximum number of proofs to migrate in this batch
+ * @returns - the progress of the migration batch or an error
+ */
+pub async fn migrate_proofs_req(
+    admin_token: &String,
+    after: Option<&String>,
+    limit: u32,
+) -> Result<ProofMigrationResponse, GrapevineError> {
+    let url = match after {
+        Some(after) => format!(
+            "{}/proof/admin/migrate?after={}&limit={}",
+            &**SERVER_URL, after, limit
+        ),
+        None => format!("{}/proof/admin/migrate?limit={}", &**SERVER_URL, limit),
+    };
+    let request = CLIENT.post(&url).header("X-Admin-Token", admin_token);
+    let res = send_request(request, &url).await?;
+    match res.status() {
+        StatusCode::OK => Ok(res.json::<ProofMigrationResponse>().await.unwrap()),
+        _ => Err(response_error(res).await),
+    }
+}
+
+/**
+ * Makes an HTTP Request to find relationships whose references on users are out of sync with
+ * the relationships collection, repairing them if requested
+ *
+ * @param admin_token - the admin token configured on the server
+ * @param repair - whether to repair the orphaned relationships instead of only listing them
+ * @returns - the stringified OIDs of the orphaned (or repaired) relationships or an error
+ */
+pub async fn orphaned_relationships_req(
+    admin_token: &String,
+    repair: bool,
+) -> Result<Vec<String>, GrapevineError> {
+    let url = format!("{}/user/admin/orphaned-relationships", &**SERVER_URL);
+    let request = match repair {
+        true => CLIENT.post(format!("{}/repair", url)),
+        false => CLIENT.get(&url),
+    };
+    let res = send_request(request.header("X-Admin-Token", admin_token), &url).await?;
+    match res.status() {
+        StatusCode::OK => Ok(res.json::<Vec<String>>().await.unwrap()),
+        _ => Err(response_error(res).await),
+    }
+}
+
+/**
+ * Makes an HTTP Request to restore proofs from a backup, streaming the backup from a file
+ *
+ * @param admin_token - the admin token configured on the server
+ * @param path - the file the backup is read from
+ * @returns - the number of proofs restored or an error
+ */
+pub async fn import_proofs_req(admin_token: &String, path: &String) -> Result<u64, GrapevineError> {
+    let url = format!("{}/proof/admin/import", &**SERVER_URL);
+    let file = match tokio::fs::File::open(path).await {
+        Ok(file) => file,
+        Err(e) => return Err(GrapevineError::FsError(e.to_string())),
+    };
+    let request = CLIENT
+        .post(&url)
+        .header("X-Admin-Token", admin_token)
+        .body(Body::from(file));
+    let res = send_request(request, &url).await?;
+    match res.status() {
+        StatusCode::OK => Ok(res.json::<u64>().await.unwrap()),
+        _ => Err(response_error(res).await),
+    }
+}
+
 /**
  * Makes an HTTP Request to create a new user
  *
@@ -113,11 +420,11 @@ pub async fn get_proof_with_params_req(
  */
 pub async fn create_user_req(body: CreateUserRequest) -> Result<(), GrapevineError> {
     let url = format!("{}/user/create", &**SERVER_URL);
-    let client = Client::new();
-    let res = client.post(&url).json(&body).send().await.unwrap();
+    let res = send_request(CLIENT.post(&url).json(&body), &url).await?;
     match res.status() {
-        StatusCode::CREATED => return Ok(()),
-        _ => Err(res.json::<GrapevineError>().await.unwrap()),
+        // OK if a retried creation finds the user was already created
+        StatusCode::CREATED | StatusCode::OK => return Ok(()),
+        _ => Err(response_error(res).await),
     }
 }
 
@@ -132,28 +439,24 @@ pub async fn add_relationship_req(
     body: NewRelationshipRequest,
 ) -> Result<String, GrapevineError> {
     let url = format!("{}/user/relationship/add", &**SERVER_URL);
-    // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
-    let client = Client::new();
-    let res = client
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::POST, &url);
+    let request = CLIENT
         .post(&url)
         .json(&body)
         .header("X-Username", account.username())
         .header("X-Authorization", signature)
-        .send()
-        .await
-        .unwrap();
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
     match res.status() {
         StatusCode::CREATED => {
             // get message
             let message = res.text().await.unwrap();
             // increment nonce
-            account
-                .increment_nonce(Some((&**ACCOUNT_PATH).to_path_buf()))
-                .unwrap();
+            increment_account_nonce(account)?;
             return Ok(message);
         }
-        _ => Err(res.json::<GrapevineError>().await.unwrap()),
+        _ => Err(response_error(res).await),
     }
 }
 
@@ -170,84 +473,175 @@ pub async fn phrase_req(
     let url = format!("{}/proof/phrase", &**SERVER_URL);
     // serialize the proof
     let serialized: Vec<u8> = bincode::serialize(&body).unwrap();
-    // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
-    let client = Client::new();
-    let res = client
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::POST, &url);
+    let request = CLIENT
         .post(&url)
         .body(serialized)
         .header("X-Username", account.username())
         .header("X-Authorization", signature)
-        .send()
-        .await
-        .unwrap();
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
     match res.status() {
-        StatusCode::CREATED => {
+        // 200 if the same proof was already submitted
+        StatusCode::CREATED | StatusCode::OK => {
             let data: PhraseCreationResponse = serde_json::from_str(&res.text().await.unwrap()).unwrap();
             // increment nonce
-            account
-                .increment_nonce(Some((&**ACCOUNT_PATH).to_path_buf()))
-                .unwrap();
+            increment_account_nonce(account)?;
             return Ok(data);
         }
         _ => {
-            // Err(res.json::<GrapevineError>().await.unwrap())
+            // Err(response_error(res).await)
             Err(GrapevineError::InternalError)
         }
     }
 }
 
+/**
+ * Makes an HTTP Request to deactivate a degree proof made by the account
+ *
+ * @param account - the account of the user that created the proof
+ * @param oid - the stringified ObjectID of the proof to deactivate
+ * @returns - the number of downstream proofs flagged as inactive
+ */
+pub async fn deactivate_proof_req(
+    account: &mut GrapevineAccount,
+    oid: &String,
+) -> Result<u64, GrapevineError> {
+    let url = format!("{}/proof/deactivate/{}", &**SERVER_URL, oid);
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::POST, &url);
+    let request = CLIENT
+        .post(&url)
+        .header("X-Username", account.username())
+        .header("X-Authorization", signature)
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
+    match res.status() {
+        StatusCode::OK => {
+            // increment nonce
+            increment_account_nonce(account)?;
+            let flagged = res.json::<u64>().await.unwrap();
+            Ok(flagged)
+        }
+        StatusCode::NOT_FOUND => Err(GrapevineError::DegreeProofNotFound),
+        _ => Err(response_error(res).await),
+    }
+}
+
+/**
+ * Makes an HTTP Request to download a degree proof made by the account
+ *
+ * @param account - the account of the user that created the proof
+ * @param oid - the stringified ObjectID of the proof to download
+ * @returns - the gzip-compressed proof
+ */
+pub async fn download_proof_req(
+    account: &mut GrapevineAccount,
+    oid: &String,
+) -> Result<Vec<u8>, GrapevineError> {
+    let url = format!("{}/proof/{}", &**SERVER_URL, oid);
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::GET, &url);
+    let request = CLIENT
+        .get(&url)
+        .header("X-Username", account.username())
+        .header("X-Authorization", signature)
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
+    match res.status() {
+        StatusCode::OK => Ok(res.bytes().await.unwrap().to_vec()),
+        StatusCode::NOT_FOUND => Err(GrapevineError::DegreeProofNotFound),
+        _ => Err(response_error(res).await),
+    }
+}
+
+/**
+ * Makes an HTTP Request to update the description of a phrase created by the account
+ *
+ * @param phrase_index - the index of the phrase to update
+ * @param description - the new description of the phrase
+ * @param account - the account of the user that created the phrase
+ */
+pub async fn update_phrase_description_req(
+    phrase_index: u32,
+    description: String,
+    account: &mut GrapevineAccount,
+) -> Result<(), GrapevineError> {
+    let url = format!(
+        "{}/proof/phrase/{}/description",
+        &**SERVER_URL, phrase_index
+    );
+    let body = PhraseDescriptionRequest { description };
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::POST, &url);
+    let request = CLIENT
+        .post(&url)
+        .json(&body)
+        .header("X-Username", account.username())
+        .header("X-Authorization", signature)
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
+    match res.status() {
+        StatusCode::OK => {
+            // increment nonce
+            increment_account_nonce(account)?;
+            Ok(())
+        }
+        StatusCode::NOT_FOUND => Err(GrapevineError::PhraseNotFound),
+        _ => Err(response_error(res).await),
+    }
+}
+
 pub async fn get_account_details_req(
     account: &mut GrapevineAccount,
-) -> Result<(u64, u64, u64), GrapevineError> {
+) -> Result<AccountDetailsResponse, GrapevineError> {
     let url = format!("{}/user/details", &**SERVER_URL);
-    // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
-    let client = Client::new();
-    let res = client
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::GET, &url);
+    let request = CLIENT
         .get(&url)
         .header("X-Username", account.username())
         .header("X-Authorization", signature)
-        .send()
-        .await
-        .unwrap();
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
     match res.status() {
         StatusCode::OK => {
-            // increment nonce
-            account
-                .increment_nonce(Some((&**ACCOUNT_PATH).to_path_buf()))
-                .unwrap();
-            let details = res.json::<(u64, u64, u64)>().await.unwrap();
+            let details = res.json::<AccountDetailsResponse>().await.unwrap();
             Ok(details)
         }
-        _ => Err(res.json::<GrapevineError>().await.unwrap()),
+        _ => Err(response_error(res).await),
     }
 }
 
 pub async fn get_degrees_req(
     account: &mut GrapevineAccount,
+    degree: Option<u8>,
+    max_degree: Option<u8>,
 ) -> Result<Vec<DegreeData>, GrapevineError> {
     let url = format!("{}/user/degrees", &**SERVER_URL);
-    // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
-    let client = Client::new();
-    let res = client
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::GET, &url);
+    let mut query = vec![];
+    if let Some(degree) = degree {
+        query.push(("degree", degree));
+    }
+    if let Some(max_degree) = max_degree {
+        query.push(("max_degree", max_degree));
+    }
+    let request = CLIENT
         .get(&url)
+        .query(&query)
         .header("X-Username", account.username())
         .header("X-Authorization", signature)
-        .send()
-        .await
-        .unwrap();
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
     match res.status() {
         StatusCode::OK => {
-            // increment nonce
-            account
-                .increment_nonce(Some((&**ACCOUNT_PATH).to_path_buf()))
-                .unwrap();
             let degrees = res.json::<Vec<DegreeData>>().await.unwrap();
             Ok(degrees)
         }
-        _ => Err(res.json::<GrapevineError>().await.unwrap()),
+        _ => Err(response_error(res).await),
     }
 }
 
@@ -264,26 +658,119 @@ pub async fn degree_proof_req(
     let url = format!("{}/proof/degree", &**SERVER_URL);
     // serialize the proof
     let serialized: Vec<u8> = bincode::serialize(&body).unwrap();
-    // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
-    let client = Client::new();
-    let res = client
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::POST, &url);
+    let request = CLIENT
         .post(&url)
         .body(serialized)
         .header("X-Username", account.username())
         .header("X-Authorization", signature)
-        .send()
-        .await
-        .unwrap();
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
     match res.status() {
-        StatusCode::CREATED => {
+        // 200 if the same proof was already submitted
+        StatusCode::CREATED | StatusCode::OK => {
             // increment nonce
-            account
-                .increment_nonce(Some((&**ACCOUNT_PATH).to_path_buf()))
-                .unwrap();
+            increment_account_nonce(account)?;
             return Ok(());
         }
-        _ => Err(res.json::<GrapevineError>().await.unwrap()),
+        _ => Err(response_error(res).await),
+    }
+}
+
+pub async fn degree_proof_batch_req(
+    account: &mut GrapevineAccount,
+    proofs: Vec<DegreeProofRequest>,
+) -> Result<Vec<DegreeProofBatchResult>, GrapevineError> {
+    let url = format!("{}/proof/degree/batch", &**SERVER_URL);
+    // serialize the proofs
+    let body = DegreeProofBatchRequest {
+        version: REQUEST_VERSION,
+        proofs,
+    };
+    let serialized: Vec<u8> = bincode::serialize(&body).unwrap();
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::POST, &url);
+    let request = CLIENT
+        .post(&url)
+        .body(serialized)
+        .header("X-Username", account.username())
+        .header("X-Authorization", signature)
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
+    match res.status() {
+        StatusCode::OK => {
+            // increment nonce
+            increment_account_nonce(account)?;
+            let results = res.json::<Vec<DegreeProofBatchResult>>().await.unwrap();
+            Ok(results)
+        }
+        _ => Err(response_error(res).await),
+    }
+}
+
+pub async fn get_phrase_list_req(
+    account: &mut GrapevineAccount,
+) -> Result<Vec<PhraseSummary>, GrapevineError> {
+    let url = format!("{}/proof/list", &**SERVER_URL);
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::GET, &url);
+    let request = CLIENT
+        .get(&url)
+        .header("X-Username", account.username())
+        .header("X-Authorization", signature)
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
+    match res.status() {
+        StatusCode::OK => {
+            let summaries = res.json::<Vec<PhraseSummary>>().await.unwrap();
+            Ok(summaries)
+        }
+        _ => Err(response_error(res).await),
+    }
+}
+
+pub async fn get_connected_origins_req(
+    account: &mut GrapevineAccount,
+) -> Result<Vec<ConnectedOrigin>, GrapevineError> {
+    let url = format!("{}/proof/origins", &**SERVER_URL);
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::GET, &url);
+    let request = CLIENT
+        .get(&url)
+        .header("X-Username", account.username())
+        .header("X-Authorization", signature)
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
+    match res.status() {
+        StatusCode::OK => {
+            let origins = res.json::<Vec<ConnectedOrigin>>().await.unwrap();
+            Ok(origins)
+        }
+        _ => Err(response_error(res).await),
+    }
+}
+
+pub async fn search_phrases_req(
+    query: &String,
+    account: &mut GrapevineAccount,
+) -> Result<Vec<PhraseSearchResult>, GrapevineError> {
+    let url = format!("{}/proof/search", &**SERVER_URL);
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::GET, &url);
+    let request = CLIENT
+        .get(&url)
+        .query(&[("q", query)])
+        .header("X-Username", account.username())
+        .header("X-Authorization", signature)
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
+    match res.status() {
+        StatusCode::OK => {
+            let phrases = res.json::<Vec<PhraseSearchResult>>().await.unwrap();
+            Ok(phrases)
+        }
+        _ => Err(response_error(res).await),
     }
 }
 
@@ -291,131 +778,296 @@ pub async fn get_known_req(
     account: &mut GrapevineAccount,
 ) -> Result<Vec<DegreeData>, GrapevineError> {
     let url = format!("{}/proof/known", &**SERVER_URL);
-    // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
-    let client = Client::new();
-    let res = client
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::GET, &url);
+    let request = CLIENT
         .get(&url)
         .header("X-Username", account.username())
         .header("X-Authorization", signature)
-        .send()
-        .await
-        .unwrap();
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
     match res.status() {
         StatusCode::OK => {
-            // increment nonce
-            account
-                .increment_nonce(Some((&**ACCOUNT_PATH).to_path_buf()))
-                .unwrap();
             let proofs = res.json::<Vec<DegreeData>>().await.unwrap();
             Ok(proofs)
         }
-        _ => Err(res.json::<GrapevineError>().await.unwrap()),
+        _ => Err(response_error(res).await),
     }
 }
 
-pub async fn get_phrase_req(
+pub async fn get_phrase_full_req(
     phrase_index: u32,
     account: &mut GrapevineAccount,
-) -> Result<DegreeData, GrapevineError> {
-    let url = format!("{}/proof/phrase/{}", &**SERVER_URL, phrase_index);
-    // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
-    let client = Client::new();
-    let res = client
+) -> Result<PhraseFullResponse, GrapevineError> {
+    let url = format!("{}/proof/phrase/{}/full", &**SERVER_URL, phrase_index);
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::GET, &url);
+    let request = CLIENT
         .get(&url)
         .header("X-Username", account.username())
         .header("X-Authorization", signature)
-        .send()
-        .await
-        .unwrap();
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
     match res.status() {
         StatusCode::OK => {
-            // increment nonce
-            account
-                .increment_nonce(Some((&**ACCOUNT_PATH).to_path_buf()))
-                .unwrap();
-            let data = res.json::<DegreeData>().await.unwrap();
+            let data = res.json::<PhraseFullResponse>().await.unwrap();
             Ok(data)
         }
-        _ => Err(res.json::<GrapevineError>().await.unwrap()),
+        StatusCode::NOT_FOUND => Err(GrapevineError::PhraseNotFound),
+        _ => Err(response_error(res).await),
     }
 }
 
-pub async fn show_connections_req(
+/**
+ * Makes an HTTP Request to find the account's active proof of a phrase and the phrase's origin
+ *
+ * @param phrase_index - the index of the phrase
+ * @param account - the account of the user that made the proof
+ * @returns - the oid and degree of the proof and the username of the origin
+ */
+pub async fn get_phrase_proof_req(
     phrase_index: u32,
     account: &mut GrapevineAccount,
-) -> Result<(u64, Vec<u64>), GrapevineError> {
-    let url = format!("{}/proof/connections/{}", &**SERVER_URL, phrase_index);
-    // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
-    let client = Client::new();
-    let res = client
+) -> Result<PhraseProofResponse, GrapevineError> {
+    let url = format!("{}/proof/phrase/{}/proof", &**SERVER_URL, phrase_index);
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::GET, &url);
+    let request = CLIENT
         .get(&url)
         .header("X-Username", account.username())
         .header("X-Authorization", signature)
-        .send()
-        .await
-        .unwrap();
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
     match res.status() {
-        StatusCode::OK => {
-            // increment nonce
-            account
-                .increment_nonce(Some((&**ACCOUNT_PATH).to_path_buf()))
-                .unwrap();
-            let connection_data = res.json::<(u64, Vec<u64>)>().await.unwrap();
-            Ok(connection_data)
-        }
-        _ => Err(res.json::<GrapevineError>().await.unwrap()),
+        StatusCode::OK => Ok(res.json::<PhraseProofResponse>().await.unwrap()),
+        // the phrase does not exist or the account has no active proof of it
+        StatusCode::NOT_FOUND => Err(GrapevineError::DegreeProofNotFound),
+        _ => Err(response_error(res).await),
     }
 }
 
 pub async fn get_relationships_req(active: bool, account: &mut GrapevineAccount) -> Result<Vec<String>, GrapevineError> {
     let route = if active { "active" } else { "pending" };
     let url = format!("{}/user/relationship/{}", &**SERVER_URL, route);
-    // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
-    let client = Client::new();
-    let res = client
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::GET, &url);
+    let request = CLIENT
         .get(&url)
         .header("X-Username", account.username())
         .header("X-Authorization", signature)
-        .send()
-        .await
-        .unwrap();
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
+    match res.status() {
+        StatusCode::OK => {
+            let relationships = res.json::<Vec<String>>().await.unwrap();
+            Ok(relationships)
+        }
+        _ => Err(response_error(res).await),
+    }
+}
+
+pub async fn get_outbound_relationships_req(
+    account: &mut GrapevineAccount,
+) -> Result<Vec<String>, GrapevineError> {
+    let url = format!("{}/user/relationship/outbound", &**SERVER_URL);
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::GET, &url);
+    let request = CLIENT
+        .get(&url)
+        .header("X-Username", account.username())
+        .header("X-Authorization", signature)
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
+    match res.status() {
+        StatusCode::OK => {
+            let relationships = res.json::<Vec<String>>().await.unwrap();
+            Ok(relationships)
+        }
+        _ => Err(response_error(res).await),
+    }
+}
+
+pub async fn get_new_relationships_req(
+    since: u32,
+    account: &mut GrapevineAccount,
+) -> Result<Vec<String>, GrapevineError> {
+    let url = format!("{}/user/relationship/new?since={}", &**SERVER_URL, since);
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::GET, &url);
+    let request = CLIENT
+        .get(&url)
+        .header("X-Username", account.username())
+        .header("X-Authorization", signature)
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
     match res.status() {
         StatusCode::OK => {
-            // increment nonce
-            account
-                .increment_nonce(Some((&**ACCOUNT_PATH).to_path_buf()))
-                .unwrap();
             let relationships = res.json::<Vec<String>>().await.unwrap();
             Ok(relationships)
         }
-        _ => Err(res.json::<GrapevineError>().await.unwrap()),
+        _ => Err(response_error(res).await),
     }
 }
 
 pub async fn reject_relationship_req(username: &String, account: &mut GrapevineAccount) -> Result<(), GrapevineError> {
     let url = format!("{}/user/relationship/reject/{}", &**SERVER_URL, username);
-    // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
-    let client = Client::new();
-    let res = client
+    // produce signature over current nonce and this request
+    let signature = request_signature(account, Method::POST, &url);
+    let request = CLIENT
         .post(&url)
         .header("X-Username", account.username())
         .header("X-Authorization", signature)
-        .send()
-        .await
-        .unwrap();
+        .header("X-Nonce", account.nonce());
+    let res = send_request(request, &url).await?;
     match res.status() {
         StatusCode::OK => {
             // increment nonce
-            account
-                .increment_nonce(Some((&**ACCOUNT_PATH).to_path_buf()))
-                .unwrap();
+            increment_account_nonce(account)?;
             Ok(())
         }
-        _ => Err(res.json::<GrapevineError>().await.unwrap()),
+        _ => Err(response_error(res).await),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Instant;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        // 10.255.255.1 is non-routable so the connection attempt hangs until timeout
+        let url = String::from("http://10.255.255.1/test/health");
+        let client = build_client(Duration::from_secs(1), Duration::from_secs(2));
+        let start = Instant::now();
+        let res = send_request(client.get(&url), &url).await;
+        let elapsed = start.elapsed();
+        match res {
+            Err(GrapevineError::RequestTimeout(timed_out)) => assert_eq!(timed_out, url),
+            _ => panic!("Request to non-routable address should time out"),
+        }
+        assert!(
+            elapsed < Duration::from_secs(4),
+            "Request should time out within the configured bound"
+        );
+    }
+
+    /**
+     * Serves one response per connection on a local port, in order
+     *
+     * @param statuses - the status line to respond with for each successive connection
+     * @returns - the url of the mock server
+     */
+    async fn mock_server(statuses: Vec<&'static str>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/test/health", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = [0u8; 1024];
+                let _ = socket.read(&mut buffer).await;
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_retry_until_success() {
+        let url = mock_server(vec![
+            "503 Service Unavailable",
+            "503 Service Unavailable",
+            "200 OK",
+        ])
+        .await;
+        let client = build_client(Duration::from_secs(1), Duration::from_secs(2));
+        let policy = RetryPolicy {
+            attempts: 3,
+            base_delay: Duration::from_millis(10),
+        };
+        let res = send_request_with_retry(client.get(&url), &url, &policy).await;
+        assert_eq!(res.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_exhausted() {
+        let url = mock_server(vec!["500 Internal Server Error"; 2]).await;
+        let client = build_client(Duration::from_secs(1), Duration::from_secs(2));
+        let policy = RetryPolicy {
+            attempts: 2,
+            base_delay: Duration::from_millis(10),
+        };
+        match send_request_with_retry(client.get(&url), &url, &policy).await {
+            Err(GrapevineError::RetriesExhausted(failed, attempts, _)) => {
+                assert_eq!(failed, url);
+                assert_eq!(attempts, 2);
+            }
+            _ => panic!("Request should fail once the retry budget is spent"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_on_client_error() {
+        // only one response is served, so a retry would fail to connect
+        let url = mock_server(vec!["404 Not Found"]).await;
+        let client = build_client(Duration::from_secs(1), Duration::from_secs(2));
+        let policy = RetryPolicy {
+            attempts: 3,
+            base_delay: Duration::from_millis(10),
+        };
+        let res = send_request_with_retry(client.get(&url), &url, &policy).await;
+        assert_eq!(res.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    /**
+     * Serves a single response with a body on a local port
+     *
+     * @param status - the status line to respond with
+     * @param body - the body to respond with
+     * @returns - the url of the mock server
+     */
+    async fn mock_response(status: &'static str, body: &'static str) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/test/health", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = [0u8; 1024];
+            let _ = socket.read(&mut buffer).await;
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_response_error() {
+        let client = build_client(Duration::from_secs(1), Duration::from_secs(2));
+        // json errors are decoded
+        let url = mock_response("403 Forbidden", "\"NotProofOwner\"").await;
+        let res = send_request(client.get(&url), &url).await.unwrap();
+        assert!(matches!(
+            response_error(res).await,
+            GrapevineError::NotProofOwner
+        ));
+        // plain text errors are kept instead of panicking
+        let url = mock_response("404 Not Found", "Degree proof not found").await;
+        let res = send_request(client.get(&url), &url).await.unwrap();
+        match response_error(res).await {
+            GrapevineError::UnexpectedResponse(status, body) => {
+                assert_eq!(status, 404);
+                assert_eq!(body, "Degree proof not found");
+            }
+            e => panic!("Expected an unexpected response error, got {:?}", e),
+        }
+    }
+}
@@ -1,6 +1,13 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
+use grapevine_common::errors::GrapevineError;
+use output::{render_error, CommandOutput, Message};
+use serde::Serialize;
+use std::fmt::Display;
+use std::io::Write;
+use utils::fs::{forget_session_account, keep_session_account, session_account};
 mod controllers;
 mod http;
+mod output;
 mod utils;
 
 ///    ______                           _           
@@ -16,6 +23,9 @@ mod utils;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Print the result as JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
 }
 
 #[derive(Subcommand)]
@@ -34,6 +44,19 @@ enum Commands {
     /// Commands for interacting with phrases and degree proofs
     #[command(subcommand, verbatim_doc_comment)]
     Phrase(PhraseCommands),
+    /// Maintenance commands for server operators (requires GRAPEVINE_ADMIN_TOKEN)
+    #[command(subcommand, verbatim_doc_comment)]
+    Admin(AdminCommands),
+    /// Verify the proof in a credential made by `phrase credential` using only the local public
+    /// params. The holder and origin it names are not verified
+    /// usage: `grapevine verify-credential <path>`
+    #[command(verbatim_doc_comment)]
+    #[clap(value_parser)]
+    VerifyCredential { path: String },
+    /// Start an interactive session that keeps proving artifacts loaded between commands
+    /// usage: `grapevine repl`
+    #[command(verbatim_doc_comment)]
+    Repl,
 }
 
 #[derive(Subcommand)]
@@ -47,6 +70,14 @@ enum RelationshipCommands {
     /// usage: `grapevine relationship pending`
     #[command(verbatim_doc_comment)]
     Pending,
+    /// Show relationship requests received since you last checked
+    /// usage: `grapevine relationship new`
+    #[command(verbatim_doc_comment)]
+    New,
+    /// Show relationship requests you sent that have not been accepted yet
+    /// usage: `grapevine relationship sent`
+    #[command(verbatim_doc_comment)]
+    Sent,
     /// Reject a pending relationship request
     /// usage: `grapevine relationship reject <username>`
     #[command(verbatim_doc_comment)]
@@ -56,6 +87,12 @@ enum RelationshipCommands {
     /// usage: `grapevine relationship list`
     #[command(verbatim_doc_comment)]
     List,
+    /// Prove your degree of separation from a user along the shortest chain of proofs
+    /// from a phrase they created
+    /// usage: `grapevine relationship prove-path <username>`
+    #[command(verbatim_doc_comment)]
+    #[clap(value_parser)]
+    ProvePath { username: String },
 }
 
 #[derive(Subcommand)]
@@ -71,23 +108,56 @@ enum AccountCommands {
     /// usage: `grapevine account info`
     #[command(verbatim_doc_comment)]
     Info,
+    /// Show the username and public key of the local account without contacting the server
+    /// usage: `grapevine account whoami`
+    #[command(verbatim_doc_comment)]
+    Whoami,
     /// Export the Baby JubJub private key for your account
     /// usage: `grapevine account export`
     #[command(verbatim_doc_comment)]
     Export,
+    /// Compare your local account and proving artifacts against the server
+    /// usage: `grapevine account doctor [--fix]`
+    #[command(verbatim_doc_comment)]
+    Doctor {
+        /// Synchronize the local nonce to the server's if they differ
+        #[clap(long)]
+        fix: bool,
+    },
 }
 
 #[derive(Subcommand)]
 enum PhraseCommands {
-    /// Prove knowledge of a phrase. Description is discarded if the phrase already exists
-    /// usage: `grapevine phrase prove "<phrase>" "<description>"`
+    /// Prove knowledge of a phrase. Description and tags are discarded if the phrase already exists
+    /// usage: `grapevine phrase prove "<phrase>" "<description>" [--tag <tag>]...`
+    #[command(verbatim_doc_comment)]
+    #[clap(value_parser)]
+    Prove {
+        phrase: String,
+        description: String,
+        /// Tag to list the phrase under (repeatable)
+        #[clap(long = "tag")]
+        tags: Vec<String>,
+    },
+    /// Submit a phrase proof saved by a failed `phrase prove` without proving it again
+    /// usage: `grapevine phrase submit-proof <path>`
+    #[command(verbatim_doc_comment)]
+    #[clap(value_parser)]
+    SubmitProof { path: String },
+    /// Compute the hash of a phrase locally without proving or contacting the server
+    /// usage: `grapevine phrase hash "<phrase>"`
     #[command(verbatim_doc_comment)]
     #[clap(value_parser)]
-    Prove { phrase: String, description: String },
+    Hash { phrase: String },
     /// Check for new degree proofs from relationships and build degrees on top of them
     /// usage: `grapevine phrase sync`
     #[command(verbatim_doc_comment)]
     Sync,
+    /// Build a degree on a phrase through one relationship instead of every available proof
+    /// usage: `grapevine phrase prove-via <relation_username> <phrase_index>`
+    #[command(verbatim_doc_comment)]
+    #[clap(value_parser)]
+    ProveVia { relation: String, index: u32 },
     /// Get all information known by this account about a given phrase by its index
     /// usage: `grapevine phrase get <index>`
     #[command(verbatim_doc_comment)]
@@ -98,9 +168,83 @@ enum PhraseCommands {
     #[command(verbatim_doc_comment)]
     Known,
     /// Return all degree proofs created by this account (degree > 1)
-    /// usage: `grapevine phrase degrees`
+    /// usage: `grapevine phrase degrees [--degree <n>] [--max-degree <n>]`
+    #[command(verbatim_doc_comment)]
+    Degrees {
+        /// Only return proofs of exactly this degree
+        #[clap(long)]
+        degree: Option<u8>,
+        /// Only return proofs of at most this degree
+        #[clap(long)]
+        max_degree: Option<u8>,
+    },
+    /// List every phrase you have a proof on with your degree and connection count
+    /// usage: `grapevine phrase list`
+    #[command(verbatim_doc_comment)]
+    List,
+    /// List every phrase creator you can prove separation from and your lowest degree to each
+    /// usage: `grapevine phrase origins`
+    #[command(verbatim_doc_comment)]
+    Origins,
+    /// Search the descriptions of phrases you or your relationships have proofs on
+    /// usage: `grapevine phrase search "<text>"`
+    #[command(verbatim_doc_comment)]
+    #[clap(value_parser)]
+    Search { query: String },
+    /// Revoke a degree proof made by this account. Proofs built on top of it are flagged inactive
+    /// usage: `grapevine phrase revoke <oid>`
+    #[command(verbatim_doc_comment)]
+    #[clap(value_parser)]
+    Revoke { oid: String },
+    /// Save the compressed bytes of a degree proof made by this account to a file
+    /// usage: `grapevine phrase download <oid> <path>`
+    #[command(verbatim_doc_comment)]
+    #[clap(value_parser)]
+    Download { oid: String, path: String },
+    /// Update the description of a phrase you created
+    /// usage: `grapevine phrase set-description <index> "<description>"`
+    #[command(verbatim_doc_comment)]
+    #[clap(value_parser)]
+    SetDescription { index: u32, description: String },
+    /// Save this account's proof of a phrase as a bearer credential whose proof can be verified
+    /// offline (its holder and origin cannot be)
+    /// usage: `grapevine phrase credential <index> <path>`
+    #[command(verbatim_doc_comment)]
+    #[clap(value_parser)]
+    Credential { index: u32, path: String },
+}
+
+#[derive(Subcommand)]
+enum AdminCommands {
+    /// Rewrite every stored proof in the current proof format. Safe to rerun or resume
+    /// usage: `grapevine admin migrate-proofs [--after <oid>] [--batch <size>]`
     #[command(verbatim_doc_comment)]
-    Degrees,
+    MigrateProofs {
+        /// Resume after the proof with this OID
+        #[clap(long)]
+        after: Option<String>,
+        /// Number of proofs to migrate per request
+        #[clap(long, default_value_t = 100)]
+        batch: u32,
+    },
+    /// Stream every stored proof into a backup file
+    /// usage: `grapevine admin export-proofs <path>`
+    #[command(verbatim_doc_comment)]
+    #[clap(value_parser)]
+    ExportProofs { path: String },
+    /// Restore proofs from a backup file made by export-proofs
+    /// usage: `grapevine admin import-proofs <path>`
+    #[command(verbatim_doc_comment)]
+    #[clap(value_parser)]
+    ImportProofs { path: String },
+    /// List relationships whose references on users are out of sync, repairing them if asked
+    /// usage: `grapevine admin orphaned-relationships [--repair]`
+    #[command(verbatim_doc_comment)]
+    OrphanedRelationships {
+        /// Remove dangling references and restore missing ones
+        #[clap(long)]
+        repair: bool,
+    },
 }
 
 /**
@@ -111,38 +255,245 @@ pub async fn main() {
     let cli = Cli::parse();
 
     let result = match &cli.command {
-        Commands::Health => controllers::health().await,
+        Commands::Repl => repl().await.map(|message| CommandOutput::new(&message)),
+        command => run(command).await,
+    };
+    print_result(result, cli.json);
+}
+
+/**
+ * Dispatch a parsed command to its controller
+ *
+ * @param command - the command to run
+ * @returns - the structured result of the command if it succeeds
+ */
+async fn run(command: &Commands) -> Result<CommandOutput, GrapevineError> {
+    match command {
+        Commands::Health => output(controllers::health().await),
         Commands::Account(cmd) => match cmd {
-            AccountCommands::Register { username } => controllers::register(username).await,
-            AccountCommands::Info => controllers::account_details().await,
-            AccountCommands::Export => controllers::export_key(),
+            AccountCommands::Register { username } => output(controllers::register(username).await),
+            AccountCommands::Info => output(controllers::account_details().await),
+            AccountCommands::Whoami => output(controllers::whoami()),
+            AccountCommands::Export => output(controllers::export_key()),
+            AccountCommands::Doctor { fix } => output(controllers::account_doctor(*fix).await),
         },
         Commands::Relationship(cmd) => match cmd {
-            RelationshipCommands::Add { username } => controllers::add_relationship(username).await,
-            RelationshipCommands::Pending => controllers::get_relationships(false).await,
+            RelationshipCommands::Add { username } => {
+                output(controllers::add_relationship(username).await)
+            }
+            RelationshipCommands::Pending => output(controllers::get_relationships(false).await),
+            RelationshipCommands::New => output(controllers::get_new_relationships().await),
+            RelationshipCommands::Sent => output(controllers::get_sent_relationships().await),
             RelationshipCommands::Reject { username } => {
-                controllers::reject_relationship(username).await
+                output(controllers::reject_relationship(username).await)
+            }
+            RelationshipCommands::List => output(controllers::get_relationships(true).await),
+            RelationshipCommands::ProvePath { username } => {
+                output(controllers::prove_path(username).await)
             }
-            RelationshipCommands::List => controllers::get_relationships(true).await,
         },
         Commands::Phrase(cmd) => match cmd {
             PhraseCommands::Prove {
                 phrase,
                 description,
-            } => controllers::prove_phrase(phrase, description).await,
-            PhraseCommands::Sync => controllers::prove_all_available().await,
-            PhraseCommands::Get { index } => controllers::get_phrase(*index).await,
-            PhraseCommands::Known => controllers::get_known_phrases().await,
-            PhraseCommands::Degrees => controllers::get_my_proofs().await,
+                tags,
+            } => output(controllers::prove_phrase(phrase, description, tags).await),
+            PhraseCommands::SubmitProof { path } => {
+                output(controllers::submit_phrase_proof(path).await)
+            }
+            PhraseCommands::Hash { phrase } => output(controllers::hash_phrase(phrase)),
+            PhraseCommands::Sync => output(controllers::prove_all_available().await),
+            PhraseCommands::ProveVia { relation, index } => {
+                output(controllers::prove_via(relation, *index).await)
+            }
+            PhraseCommands::Get { index } => output(controllers::get_phrase(*index).await),
+            PhraseCommands::Known => output(controllers::get_known_phrases().await),
+            PhraseCommands::Degrees { degree, max_degree } => {
+                output(controllers::get_my_proofs(*degree, *max_degree).await)
+            }
+            PhraseCommands::List => output(controllers::get_phrase_list().await),
+            PhraseCommands::Origins => output(controllers::get_connected_origins().await),
+            PhraseCommands::Search { query } => output(controllers::search_phrases(query).await),
+            PhraseCommands::Revoke { oid } => output(controllers::revoke_proof(oid).await),
+            PhraseCommands::Download { oid, path } => {
+                output(controllers::download_proof(oid, path).await)
+            }
+            PhraseCommands::SetDescription { index, description } => {
+                output(controllers::set_phrase_description(*index, description).await)
+            }
+            PhraseCommands::Credential { index, path } => {
+                output(controllers::export_credential(*index, path).await)
+            }
         },
-    };
+        Commands::Admin(cmd) => match cmd {
+            AdminCommands::MigrateProofs { after, batch } => {
+                output(controllers::migrate_proofs(after, *batch).await)
+            }
+            AdminCommands::ExportProofs { path } => output(controllers::export_proofs(path).await),
+            AdminCommands::ImportProofs { path } => output(controllers::import_proofs(path).await),
+            AdminCommands::OrphanedRelationships { repair } => {
+                output(controllers::orphaned_relationships(*repair).await)
+            }
+        },
+        Commands::VerifyCredential { path } => output(controllers::verify_credential_file(path)),
+        Commands::Repl => output(Ok(Message::new("Already in interactive mode"))),
+    }
+}
+
+/**
+ * Capture the result of a controller for rendering
+ *
+ * @param result - the structured result or error returned by the controller
+ * @returns - the result ready to render as text or JSON
+ */
+fn output<T: Serialize + Display>(
+    result: Result<T, GrapevineError>,
+) -> Result<CommandOutput, GrapevineError> {
+    result.map(|result| CommandOutput::new(&result))
+}
 
+/**
+ * Print the outcome of a command
+ *
+ * @param result - the output or error returned by the command
+ * @param json - whether to print JSON instead of text
+ */
+fn print_result(result: Result<CommandOutput, GrapevineError>, json: bool) {
     match result {
-        Ok(message) => {
-            println!("{}", message);
+        Ok(output) => {
+            println!("{}", output.render(json));
         }
         Err(e) => {
-            println!("Error: {}", e);
+            println!("{}", render_error(&e, json));
         }
     };
 }
+
+/** A line read in interactive mode */
+enum ReplLine {
+    Empty,
+    Help,
+    Exit,
+    Command(Cli),
+}
+
+/**
+ * Parse a line of interactive mode input using the same grammar as the CLI
+ *
+ * @param line - the line without the leading `grapevine`, quoted like a shell argument list
+ * @returns - the parsed line, or the message to print if it is not a valid command
+ */
+fn parse_repl_line(line: &str) -> Result<ReplLine, String> {
+    let args = match shlex::split(line) {
+        Some(args) => args,
+        None => return Err(String::from("Error: unterminated quote")),
+    };
+    match args.first().map(|arg| arg.as_str()) {
+        None => Ok(ReplLine::Empty),
+        Some("help") => Ok(ReplLine::Help),
+        Some("exit") | Some("quit") => Ok(ReplLine::Exit),
+        Some(_) => {
+            let args = std::iter::once(String::from("grapevine")).chain(args);
+            match Cli::try_parse_from(args) {
+                Ok(cli) => Ok(ReplLine::Command(cli)),
+                Err(e) => Err(e.to_string()),
+            }
+        }
+    }
+}
+
+/**
+ * Sync the nonce of the local account, if there is one, and keep the account in memory for the
+ * commands that follow
+ */
+async fn keep_account() -> Result<(), GrapevineError> {
+    if controllers::get_account().is_err() {
+        return Ok(());
+    }
+    controllers::synchronize_nonce().await?;
+    keep_session_account(controllers::get_account()?);
+    Ok(())
+}
+
+/**
+ * Run commands read from stdin until `exit`, keeping the account, its synced nonce, and the
+ * proving artifacts in memory between them
+ */
+async fn repl() -> Result<Message, GrapevineError> {
+    println!("Grapevine interactive mode. Type `help` for commands or `exit` to quit");
+    keep_account().await?;
+    let stdin = std::io::stdin();
+    loop {
+        print!("grapevine> ");
+        std::io::stdout().flush().unwrap();
+        let mut line = String::new();
+        // stop at the end of input
+        if stdin.read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+        match parse_repl_line(&line) {
+            Ok(ReplLine::Empty) => continue,
+            Ok(ReplLine::Help) => println!("{}", Cli::command().render_help()),
+            Ok(ReplLine::Exit) => break,
+            Ok(ReplLine::Command(cli)) => {
+                // an account registered during the session is kept from the next command on.
+                // If it cannot be synced yet the command reads and syncs it as usual
+                if session_account().is_none() {
+                    _ = keep_account().await;
+                }
+                let result = run(&cli.command).await;
+                // the nonce may have drifted if the command failed, so read and sync it again
+                if result.is_err() {
+                    forget_session_account();
+                }
+                print_result(result, cli.json);
+            }
+            Err(message) => println!("{}", message),
+        }
+    }
+    Ok(Message::new("Exiting interactive mode"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_repl_line_parsing() {
+        // quoted arguments are kept together like in a shell
+        let line = "phrase prove \"two words\" \"a description\"\n";
+        let command = match parse_repl_line(line) {
+            Ok(ReplLine::Command(cli)) => cli.command,
+            _ => panic!("Expected a command"),
+        };
+        match command {
+            Commands::Phrase(PhraseCommands::Prove {
+                phrase,
+                description,
+                tags,
+            }) => {
+                assert_eq!(phrase, "two words");
+                assert_eq!(description, "a description");
+                assert!(tags.is_empty());
+            }
+            _ => panic!("Expected a phrase prove command"),
+        }
+        assert!(matches!(parse_repl_line("  \n"), Ok(ReplLine::Empty)));
+        assert!(matches!(parse_repl_line("help"), Ok(ReplLine::Help)));
+        assert!(matches!(parse_repl_line("exit\n"), Ok(ReplLine::Exit)));
+        assert!(parse_repl_line("phrase unknown").is_err());
+        assert!(parse_repl_line("phrase prove \"unterminated").is_err());
+    }
+
+    #[test]
+    fn test_json_flag_is_global() {
+        let cli = Cli::try_parse_from(["grapevine", "account", "info", "--json"]).unwrap();
+        assert!(cli.json);
+        assert!(matches!(
+            cli.command,
+            Commands::Account(AccountCommands::Info)
+        ));
+        let cli = Cli::try_parse_from(["grapevine", "account", "info"]).unwrap();
+        assert!(!cli.json);
+    }
+}
@@ -0,0 +1,820 @@
+use grapevine_common::errors::GrapevineError;
+use grapevine_common::http::responses::{ConnectedOrigin, PhraseSummary};
+use serde::Serialize;
+use std::fmt::{Display, Formatter, Result};
+
+/**
+ * The result of a command, rendered as human readable text or as JSON with `--json`
+ */
+pub struct CommandOutput {
+    text: String,
+    json: serde_json::Value,
+}
+
+impl CommandOutput {
+    /**
+     * Capture both renderings of a command result
+     *
+     * @param result - the structured result returned by a controller
+     */
+    pub fn new<T: Serialize + Display>(result: &T) -> Self {
+        CommandOutput {
+            text: result.to_string(),
+            json: serde_json::to_value(result).unwrap(),
+        }
+    }
+
+    /**
+     * @param json - whether to render the result as JSON instead of text
+     * @returns - the rendered result
+     */
+    pub fn render(&self, json: bool) -> String {
+        match json {
+            true => self.json.to_string(),
+            false => self.text.clone(),
+        }
+    }
+}
+
+/**
+ * Render a failed command
+ *
+ * @param error - the error returned by the command
+ * @param json - whether to render the error as a JSON object with an `error` key
+ * @returns - the rendered error
+ */
+pub fn render_error(error: &GrapevineError, json: bool) -> String {
+    match json {
+        true => serde_json::json!({ "error": error.to_string() }).to_string(),
+        false => format!("Error: {}", error),
+    }
+}
+
+/**
+ * Formats bytes as a 0x prefixed hex string
+ */
+pub fn hex_string(bytes: impl AsRef<[u8]>) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/** A command with nothing to report beyond a status message */
+#[derive(Serialize, Debug, Clone)]
+pub struct Message {
+    pub message: String,
+}
+
+impl Message {
+    pub fn new(message: impl Into<String>) -> Self {
+        Message {
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/** `account info` */
+#[derive(Serialize, Debug, Clone)]
+pub struct AccountInfo {
+    pub username: String,
+    pub pubkey: String,
+    pub first_degree_count: u64,
+    pub second_degree_count: u64,
+    pub phrase_count: u64,
+}
+
+impl Display for AccountInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "Username: {}\nPublic key: {}\n# 1st degree connections: {}\n# 2nd degree connections: {}\n# phrases created: {}",
+            self.username,
+            self.pubkey,
+            self.first_degree_count,
+            self.second_degree_count,
+            self.phrase_count
+        )
+    }
+}
+
+/** `account whoami` */
+#[derive(Serialize, Debug, Clone)]
+pub struct Identity {
+    pub username: String,
+    pub pubkey: String,
+}
+
+impl Display for Identity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "Username: {}\nPublic key: {}",
+            self.username, self.pubkey
+        )
+    }
+}
+
+/** A value compared between the local account and the server by `account doctor` */
+#[derive(Serialize, Debug, Clone)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub local: String,
+    pub server: String,
+    pub ok: bool,
+}
+
+/** `account doctor` */
+#[derive(Serialize, Debug, Clone)]
+pub struct AccountDiagnosis {
+    pub username: String,
+    pub checks: Vec<DoctorCheck>,
+    pub fixed: Vec<String>, // repairs made with `--fix`
+}
+
+impl AccountDiagnosis {
+    /**
+     * @param name - the name of the check
+     * @returns - whether the check was run and found drift
+     */
+    pub fn failed(&self, name: &str) -> bool {
+        self.checks
+            .iter()
+            .any(|check| check.name == name && !check.ok)
+    }
+}
+
+impl Display for AccountDiagnosis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "Account doctor for \"{}\":", self.username)?;
+        for check in self.checks.iter() {
+            let status = if check.ok { "ok" } else { "MISMATCH" };
+            write!(
+                f,
+                "\n  [{}] {}: local {}, server {}",
+                status, check.name, check.local, check.server
+            )?;
+        }
+        for fixed in self.fixed.iter() {
+            write!(f, "\nFixed: {}", fixed)?;
+        }
+        if self.failed("nonce") && self.fixed.is_empty() {
+            write!(
+                f,
+                "\nRun `grapevine account doctor --fix` to synchronize the local nonce"
+            )?;
+        }
+        if self.failed("artifacts") {
+            write!(
+                f,
+                "\nRun `grapevine health` to download the server's proving artifacts"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/** `account export` */
+#[derive(Serialize, Debug, Clone)]
+pub struct ExportedKey {
+    pub username: String,
+    pub private_key: String,
+    pub auth_secret: String,
+}
+
+impl Display for ExportedKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "Sensitive account details for {}:\nPrivate Key: {}\nAuth Secret: {}",
+            self.username, self.private_key, self.auth_secret
+        )
+    }
+}
+
+/** `relationship list` and `relationship pending` */
+#[derive(Serialize, Debug, Clone)]
+pub struct Relationships {
+    pub username: String,
+    pub active: bool,
+    pub relationships: Vec<String>,
+}
+
+impl Display for Relationships {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let relation_type = if self.active { "Active" } else { "Pending" };
+        if self.relationships.is_empty() {
+            return write!(
+                f,
+                "No {} relationships found for this account",
+                relation_type
+            );
+        }
+        writeln!(f, "===============================")?;
+        write!(
+            f,
+            "Showing {} {} relationships for {}:",
+            self.relationships.len(),
+            relation_type,
+            self.username
+        )?;
+        for relationship in &self.relationships {
+            write!(f, "\n|=> \"{}\"", relationship)?;
+        }
+        Ok(())
+    }
+}
+
+/** `relationship new` */
+#[derive(Serialize, Debug, Clone)]
+pub struct NewRelationships {
+    pub usernames: Vec<String>,
+}
+
+impl Display for NewRelationships {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.usernames.is_empty() {
+            return write!(f, "No new connection requests since you last checked");
+        }
+        write!(
+            f,
+            "{} new connection requests since you last checked:",
+            self.usernames.len()
+        )?;
+        for username in &self.usernames {
+            write!(f, "\n|=> \"{}\"", username)?;
+        }
+        Ok(())
+    }
+}
+
+/** `relationship sent` */
+#[derive(Serialize, Debug, Clone)]
+pub struct SentRelationships {
+    pub usernames: Vec<String>,
+}
+
+impl Display for SentRelationships {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.usernames.is_empty() {
+            return write!(f, "No sent relationship requests are awaiting a response");
+        }
+        write!(
+            f,
+            "{} sent relationship requests are awaiting a response:",
+            self.usernames.len()
+        )?;
+        for username in &self.usernames {
+            write!(f, "\n|=> \"{}\"", username)?;
+        }
+        Ok(())
+    }
+}
+
+/** `phrase hash` */
+#[derive(Serialize, Debug, Clone)]
+pub struct PhraseHash {
+    pub phrase_hash: String,
+}
+
+impl Display for PhraseHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "Phrase hash: {}", self.phrase_hash)
+    }
+}
+
+/** `phrase prove` */
+#[derive(Serialize, Debug, Clone)]
+pub struct PhraseProved {
+    pub phrase_index: u32,
+    pub new_phrase: bool,
+    pub phrase: String,
+}
+
+impl Display for PhraseProved {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.new_phrase {
+            true => write!(
+                f,
+                "Success: Created and proved knowledge of new phrase #{}: \"{}\"",
+                self.phrase_index, self.phrase
+            ),
+            false => write!(
+                f,
+                "Success: Proved knowledge of existing phrase #{}: \"{}\"",
+                self.phrase_index, self.phrase
+            ),
+        }
+    }
+}
+
+/** A degree proof built by `phrase sync` */
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ProofOutcome {
+    pub previous: String, // the OID of the relationship's proof that was built on
+    pub relation: String, // the relationship that made the proof that was built on
+    pub degree: u8,       // the degree of the new proof
+    pub status: u16,      // the http status the server gave the new proof (0 if not submitted)
+}
+
+/** `phrase sync` */
+#[derive(Serialize, Debug, Clone)]
+pub struct DegreesProved {
+    pub username: String,
+    pub available: usize, // proofs from relationships that could be built on
+    pub submitted: usize, // new degree proofs accepted by the server
+    pub proofs: Vec<ProofOutcome>,
+}
+
+impl Display for DegreesProved {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match (self.available, self.submitted) {
+            (0, _) => write!(
+                f,
+                "No new degree proofs found for user \"{}\"",
+                self.username
+            ),
+            (1, 1) => write!(f, "Success: proved 1 new degree proof")?,
+            (_, submitted) => write!(f, "Success: proved {} new degree proofs", submitted)?,
+        }
+        for proof in self.proofs.iter().filter(|proof| proof.accepted()) {
+            write!(
+                f,
+                "\n  Degree {} through \"{}\" (built on {})",
+                proof.degree, proof.relation, proof.previous
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl ProofOutcome {
+    /** Return whether the server accepted the new proof */
+    pub fn accepted(&self) -> bool {
+        matches!(self.status, 200 | 201)
+    }
+}
+
+/** `phrase prove-via` */
+#[derive(Serialize, Debug, Clone)]
+pub struct DegreeProvedVia {
+    pub phrase_index: u32,
+    pub relation: String, // the relationship whose proof was built on
+    pub degree: u8,       // the degree of the new proof
+    pub previous: String, // the OID of the relationship's proof that was built on
+}
+
+impl Display for DegreeProvedVia {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "Success: proved degree {} of phrase #{} through \"{}\"",
+            self.degree, self.phrase_index, self.relation
+        )
+    }
+}
+
+/** `relationship prove-path` */
+#[derive(Serialize, Debug, Clone)]
+pub struct PathProved {
+    pub target: String,    // the user a degree of separation was proved from
+    pub phrase_index: u32, // the target's phrase the proof is on
+    pub relation: String,  // the relationship whose proof was built on
+    pub degree: u8,        // the degree of the new proof
+    pub previous: String,  // the OID of the relationship's proof that was built on
+}
+
+impl Display for PathProved {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "Success: proved degree {} from \"{}\" on phrase #{} through \"{}\"",
+            self.degree, self.target, self.phrase_index, self.relation
+        )
+    }
+}
+
+/** `phrase revoke` */
+#[derive(Serialize, Debug, Clone)]
+pub struct ProofRevoked {
+    pub proof: String,
+    pub flagged: u64, // dependent proofs flagged inactive
+}
+
+impl Display for ProofRevoked {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "Success: revoked proof {} ({} dependent proofs flagged inactive)",
+            self.proof, self.flagged
+        )
+    }
+}
+
+/** `phrase download` */
+#[derive(Serialize, Debug, Clone)]
+pub struct ProofDownloaded {
+    pub proof: String,
+    pub path: String,
+    pub bytes: usize, // size of the compressed proof
+}
+
+impl Display for ProofDownloaded {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "Success: saved proof {} to {} ({} bytes)",
+            self.proof, self.path, self.bytes
+        )
+    }
+}
+
+/** `phrase credential` */
+#[derive(Serialize, Debug, Clone)]
+pub struct CredentialExported {
+    pub phrase_index: u32,
+    pub degree: u8,
+    pub origin: String,
+    pub path: String,
+}
+
+impl Display for CredentialExported {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "Success: saved credential for phrase {} (degree {} from {}) to {}",
+            self.phrase_index, self.degree, self.origin, self.path
+        )
+    }
+}
+
+/** `verify-credential` */
+#[derive(Serialize, Debug, Clone)]
+pub struct CredentialVerified {
+    pub claimed_holder: String, // not bound by the proof
+    pub claimed_origin: String, // not bound by the proof
+    pub degree: u8,
+    pub phrase_hash: String,
+}
+
+impl Display for CredentialVerified {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "Valid proof: degree {} on phrase {}\nUnverified: the credential claims holder {} and \
+             origin {}, which the proof does not bind",
+            self.degree, self.phrase_hash, self.claimed_holder, self.claimed_origin
+        )
+    }
+}
+
+/** `admin migrate-proofs` */
+#[derive(Serialize, Debug, Clone)]
+pub struct MigrationReport {
+    pub processed: u32,
+    pub migrated: u32,
+    pub failed: Vec<String>,
+}
+
+impl Display for MigrationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for oid in &self.failed {
+            writeln!(f, "Failed to migrate proof {}", oid)?;
+        }
+        write!(
+            f,
+            "Success: migrated {} of {} proofs ({} failed)",
+            self.migrated,
+            self.processed,
+            self.failed.len()
+        )
+    }
+}
+
+/** `admin export-proofs` */
+#[derive(Serialize, Debug, Clone)]
+pub struct ProofsExported {
+    pub path: String,
+    pub count: u64,
+}
+
+impl Display for ProofsExported {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "Success: backed up {} proofs to {}",
+            self.count, self.path
+        )
+    }
+}
+
+/** `admin import-proofs` */
+#[derive(Serialize, Debug, Clone)]
+pub struct ProofsImported {
+    pub path: String,
+    pub count: u64,
+}
+
+impl Display for ProofsImported {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "Success: restored {} proofs from {}",
+            self.count, self.path
+        )
+    }
+}
+
+/** `admin orphaned-relationships` */
+#[derive(Serialize, Debug, Clone)]
+pub struct OrphanedRelationships {
+    pub relationships: Vec<String>,
+    pub repaired: bool,
+}
+
+impl Display for OrphanedRelationships {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for oid in &self.relationships {
+            writeln!(f, "Orphaned relationship {}", oid)?;
+        }
+        let action = match self.repaired {
+            true => "repaired",
+            false => "found",
+        };
+        write!(
+            f,
+            "Success: {} {} orphaned relationships",
+            action,
+            self.relationships.len()
+        )
+    }
+}
+
+/**
+ * A phrase as seen by this account, with the phrase decrypted if this account knows it
+ */
+#[derive(Serialize, Debug, Clone)]
+pub struct PhraseDegree {
+    pub phrase_index: u32,
+    pub description: String,
+    pub phrase_hash: String,
+    pub degree: Option<u8>,
+    pub relation: Option<String>,
+    pub preceding_relation: Option<String>,
+    pub secret_phrase: Option<String>,
+}
+
+/** `phrase degrees` */
+#[derive(Serialize, Debug, Clone)]
+pub struct MyDegrees {
+    pub username: String,
+    pub degrees: Vec<PhraseDegree>,
+}
+
+impl Display for MyDegrees {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "Proofs of {}'s degrees of separation from phrases/ users:",
+            self.username
+        )?;
+        for degree in &self.degrees {
+            write!(
+                f,
+                "\n=-=-=-=-=-=-=[Phrase #{}]=-=-=-=-=-=-=",
+                degree.phrase_index
+            )?;
+            write!(f, "\nPhrase description: \"{}\"", degree.description)?;
+            write!(f, "\nPhrase hash: {}", degree.phrase_hash)?;
+            write!(
+                f,
+                "\nDegrees of separation from origin: {}",
+                degree.degree.unwrap()
+            )?;
+            match &degree.relation {
+                None => {
+                    write!(f, "\nPhrase created by this user")?;
+                    let phrase = degree.secret_phrase.clone().unwrap_or_default();
+                    write!(f, "\nSecret phrase: \"{}\"", phrase)?;
+                }
+                Some(relation) => {
+                    write!(f, "\nYour relation: {}", relation)?;
+                    if let Some(preceding) = &degree.preceding_relation {
+                        write!(f, "\n2nd degree relation: {}", preceding)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/** `phrase known` */
+#[derive(Serialize, Debug, Clone)]
+pub struct KnownPhrases {
+    pub phrases: Vec<PhraseDegree>,
+}
+
+impl Display for KnownPhrases {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut lines = vec![];
+        for phrase in &self.phrases {
+            lines.push(format!(
+                "=-=-=-=-=-=-=[Phrase #{}]=-=-=-=-=-=-=",
+                phrase.phrase_index
+            ));
+            lines.push(format!("Description: \"{}\"", phrase.description));
+            lines.push(format!("Phrase hash: {}", phrase.phrase_hash));
+            let secret = phrase.secret_phrase.clone().unwrap_or_default();
+            lines.push(format!("Secret phrase: \"{}\"", secret));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/** `phrase get` */
+#[derive(Serialize, Debug, Clone)]
+pub struct PhraseDetails {
+    pub phrase: PhraseDegree,
+    pub total_connections: u64,
+    pub connections_by_degree: Vec<u64>, // relationships connected at 1, 2, ... degrees
+}
+
+impl Display for PhraseDetails {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let phrase = &self.phrase;
+        // header (always shown)
+        write!(
+            f,
+            "=-=-=-=-=-=-=[Phrase #{}]=-=-=-=-=-=-=",
+            phrase.phrase_index
+        )?;
+        write!(f, "\nPhrase description: \"{}\"", phrase.description)?;
+        write!(f, "\nPhrase hash: {}", phrase.phrase_hash)?;
+        // if no degree, show that this user does not know the phrase
+        let degree = match phrase.degree {
+            Some(degree) => degree,
+            None => return write!(f, "\nYou do not have any connections to this phrase!"),
+        };
+        match &phrase.secret_phrase {
+            // If phrase is known, show secret
+            Some(secret) => write!(f, "\nSecret phrase: \"{}\"", secret)?,
+            // If phrase is not known, show degrees of separation from origin + upstream relations
+            None => {
+                write!(f, "\nDegrees of separation from phrase: {}", degree)?;
+                if let Some(relation) = &phrase.relation {
+                    write!(f, "\nYour 1st degree relation to this phrase: {}", relation)?;
+                }
+                if let Some(preceding) = &phrase.preceding_relation {
+                    write!(
+                        f,
+                        "\nYour 2nd degree relation to this phrase: {}",
+                        preceding
+                    )?;
+                }
+            }
+        }
+        // Show connection data
+        write!(f, "\n#####################")?;
+        write!(
+            f,
+            "\nTotal of {} connections to this phrase",
+            self.total_connections
+        )?;
+        for (i, connections) in self.connections_by_degree.iter().enumerate() {
+            let degree_plural = match i == 0 {
+                true => "degree",
+                false => "degrees",
+            };
+            write!(
+                f,
+                "\nRelationships with {} {} connection to this phrase: {}",
+                i + 1,
+                degree_plural,
+                connections
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/** `phrase list` */
+#[derive(Serialize, Debug, Clone)]
+pub struct PhraseList {
+    pub phrases: Vec<PhraseSummary>,
+}
+
+impl Display for PhraseList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.phrases.is_empty() {
+            return write!(f, "No phrases found for this account");
+        }
+        let mut lines = vec![];
+        for summary in &self.phrases {
+            lines.push(format!(
+                "=-=-=-=-=-=-=[Phrase #{}]=-=-=-=-=-=-=",
+                summary.phrase_index
+            ));
+            lines.push(format!("Description: \"{}\"", summary.description));
+            lines.push(format!("Degree: {}", summary.degree));
+            lines.push(format!("Connections: {}", summary.connections));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/** `phrase origins` */
+#[derive(Serialize, Debug, Clone)]
+pub struct Origins {
+    pub origins: Vec<ConnectedOrigin>,
+}
+
+impl Display for Origins {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.origins.is_empty() {
+            return write!(f, "No origins found for this account");
+        }
+        let lines = self
+            .origins
+            .iter()
+            .map(|origin| format!("{}: degree {}", origin.origin_username, origin.min_degree))
+            .collect::<Vec<String>>();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/** A phrase matching a `phrase search` query */
+#[derive(Serialize, Debug, Clone)]
+pub struct PhraseMatch {
+    pub phrase_index: u32,
+    pub description: String,
+    pub phrase_hash: String,
+}
+
+/** `phrase search` */
+#[derive(Serialize, Debug, Clone)]
+pub struct SearchResults {
+    pub query: String,
+    pub phrases: Vec<PhraseMatch>,
+}
+
+impl Display for SearchResults {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.phrases.is_empty() {
+            return write!(f, "No phrases found matching \"{}\"", self.query);
+        }
+        let mut lines = vec![];
+        for phrase in &self.phrases {
+            lines.push(format!(
+                "=-=-=-=-=-=-=[Phrase #{}]=-=-=-=-=-=-=",
+                phrase.phrase_index
+            ));
+            lines.push(format!("Description: \"{}\"", phrase.description));
+            lines.push(format!("Phrase hash: {}", phrase.phrase_hash));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_account_info_json() {
+        let info = AccountInfo {
+            username: String::from("json_user"),
+            pubkey: hex_string([1u8; 32]),
+            first_degree_count: 3,
+            second_degree_count: 2,
+            phrase_count: 1,
+        };
+        let output = CommandOutput::new(&info);
+
+        // --json renders a single JSON object with a key for each field
+        let json: serde_json::Value = serde_json::from_str(&output.render(true)).unwrap();
+        let keys = [
+            "username",
+            "pubkey",
+            "first_degree_count",
+            "second_degree_count",
+            "phrase_count",
+        ];
+        for key in keys {
+            assert!(json.get(key).is_some(), "Missing key {}", key);
+        }
+        assert_eq!(json["username"], "json_user");
+        assert_eq!(json["first_degree_count"], 3);
+
+        // text rendering is unchanged
+        assert!(output.render(false).starts_with("Username: json_user\n"));
+
+        // errors are rendered as a JSON object too
+        let error = render_error(&GrapevineError::InternalError, true);
+        let json: serde_json::Value = serde_json::from_str(&error).unwrap();
+        assert!(json.get("error").is_some());
+    }
+}
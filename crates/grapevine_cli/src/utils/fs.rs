@@ -1,4 +1,8 @@
-use crate::http::SERVER_URL;
+use crate::http::{send_request_with_retry, RETRY_POLICY, SERVER_URL};
+use grapevine_common::account::GrapevineAccount;
+use grapevine_common::crypto::artifact_hash;
+use grapevine_common::http::requests::PhraseRequest;
+use grapevine_common::http::responses::CircuitInfoResponse;
 use grapevine_common::{errors::GrapevineError, Fr, Params, G1, G2};
 use lazy_static::lazy_static;
 use nova_scotia::circom::circuit::R1CS;
@@ -7,9 +11,26 @@ use nova_scotia::FileLocation;
 use std::env::{var, VarError};
 use std::fs::write;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 lazy_static! {
     pub static ref ACCOUNT_PATH: PathBuf = get_account_path().unwrap();
+    static ref ARTIFACTS: Mutex<Option<Arc<GrapevineArtifacts>>> = Mutex::new(None);
+    static ref SESSION_ACCOUNT: Mutex<Option<GrapevineAccount>> = Mutex::new(None);
+}
+
+/** Number of times the proving artifacts have been read from disk by this process */
+static ARTIFACT_LOADS: AtomicUsize = AtomicUsize::new(0);
+
+/**
+ * The proving artifacts needed to build a proof, loaded into memory
+ */
+pub struct GrapevineArtifacts {
+    pub params: Params,
+    pub r1cs: R1CS<Fr>,
+    pub wasm: PathBuf,
 }
 
 /**
@@ -30,9 +51,61 @@ pub fn get_account_path() -> Result<PathBuf, GrapevineError> {
     Ok(grapevine_dir_path.join("grapevine.key"))
 }
 
+/**
+ * Keeps the account in memory for the rest of the process, so that long running sessions (the
+ * repl) do not re-read it or re-sync its nonce for every command
+ *
+ * @param account - the account, with its nonce synchronized with the server
+ */
+pub fn keep_session_account(account: GrapevineAccount) {
+    *SESSION_ACCOUNT.lock().unwrap() = Some(account);
+}
+
+/**
+ * Drops the account kept in memory, so the next command reads it from disk and re-syncs its nonce
+ */
+pub fn forget_session_account() {
+    *SESSION_ACCOUNT.lock().unwrap() = None;
+}
+
+/**
+ * @returns - the account kept in memory by `keep_session_account`, if any
+ */
+pub fn session_account() -> Option<GrapevineAccount> {
+    SESSION_ACCOUNT.lock().unwrap().clone()
+}
+
+/**
+ * Sets the nonce of the account and saves it to disk, updating the copy kept in memory if any
+ *
+ * @param account - the account to update
+ * @param nonce - the nonce to set
+ */
+pub fn set_account_nonce(account: &mut GrapevineAccount, nonce: u64) -> Result<(), GrapevineError> {
+    if let Err(e) = account.set_nonce(nonce, Some(ACCOUNT_PATH.to_path_buf())) {
+        return Err(GrapevineError::FsError(e.to_string()));
+    }
+    let mut session = SESSION_ACCOUNT.lock().unwrap();
+    if session.is_some() {
+        *session = Some(account.clone());
+    }
+    Ok(())
+}
+
+/**
+ * Increments the nonce of the account after a state-changing request and saves it to disk,
+ * updating the copy kept in memory if any
+ *
+ * @param account - the account that made the request
+ */
+pub fn increment_account_nonce(account: &mut GrapevineAccount) -> Result<(), GrapevineError> {
+    let nonce = account.nonce() + 1;
+    set_account_nonce(account, nonce)
+}
+
 pub fn use_public_params() -> Result<Params, Box<dyn std::error::Error>> {
     // get the path to grapevine (will create if it does not exist)
-    let filepath = get_storage_path().unwrap().join("public_params.json");
+    let filepath = get_artifacts_path().unwrap().join("public_params.json");
     // read in params file
     let public_params_file = std::fs::read_to_string(filepath).expect("Unable to read file");
 
@@ -45,14 +118,49 @@ pub fn use_public_params() -> Result<Params, Box<dyn std::error::Error>> {
 
 pub fn use_r1cs() -> Result<R1CS<Fr>, Box<dyn std::error::Error>> {
     // get the path to grapevine (will create if it does not exist)
-    let filepath = get_storage_path().unwrap().join("grapevine.r1cs");
+    let filepath = get_artifacts_path().unwrap().join("grapevine.r1cs");
     // read in params file
     Ok(load_r1cs::<G1, G2>(&FileLocation::PathBuf(filepath)))
 }
 
 pub fn use_wasm() -> Result<PathBuf, Box<dyn std::error::Error>> {
     // get the path to grapevine (will create if it does not exist)
-    Ok(get_storage_path().unwrap().join("grapevine.wasm"))
+    Ok(get_artifacts_path().unwrap().join("grapevine.wasm"))
+}
+
+/**
+ * Gets the proving artifacts, only reading them from disk the first time they are used so that
+ * long running sessions (the repl) do not reload them for every proof
+ *
+ * @returns - the proving artifacts shared by every caller in this process
+ */
+pub fn use_artifacts() -> Result<Arc<GrapevineArtifacts>, Box<dyn std::error::Error>> {
+    let mut artifacts = ARTIFACTS.lock().unwrap();
+    if let Some(artifacts) = &*artifacts {
+        return Ok(artifacts.clone());
+    }
+    let loaded = Arc::new(GrapevineArtifacts {
+        params: use_public_params()?,
+        r1cs: use_r1cs()?,
+        wasm: use_wasm()?,
+    });
+    ARTIFACT_LOADS.fetch_add(1, Ordering::Relaxed);
+    *artifacts = Some(loaded.clone());
+    Ok(loaded)
+}
+
+/**
+ * @returns - true if the proving artifacts have already been loaded into memory
+ */
+pub fn artifacts_loaded() -> bool {
+    ARTIFACTS.lock().unwrap().is_some()
+}
+
+/**
+ * @returns - the number of times the proving artifacts have been read from disk
+ */
+pub fn artifact_loads() -> usize {
+    ARTIFACT_LOADS.load(Ordering::Relaxed)
 }
 
 /**
@@ -69,20 +177,135 @@ pub fn get_storage_path() -> Result<PathBuf, VarError> {
     };
     // if does not exist, create the dir
     if !grapevine_path.exists() {
-        println!("Creating .grapevine directory...");
+        eprintln!("Creating .grapevine directory...");
         std::fs::create_dir(grapevine_path.clone()).unwrap();
     }
     Ok(grapevine_path)
 }
 
 /**
- * Checks whether r1cs, wasm, witcalc exist in ~/.grapevine
+ * Gets the directory proving artifacts are stored in, checking in order:
+ *  - the GRAPEVINE_ARTIFACTS_DIR environment variable
+ *  - $XDG_CACHE_HOME/grapevine
+ *  - ~/.grapevine
+ * If the directory does not exist, create it
+ *
+ * @returns {PathBuf} path to the artifact directory if successful
+ */
+pub fn get_artifacts_path() -> Result<PathBuf, GrapevineError> {
+    let artifacts_path = match (var("GRAPEVINE_ARTIFACTS_DIR"), var("XDG_CACHE_HOME")) {
+        (Ok(dir), _) if !dir.is_empty() => PathBuf::from(dir),
+        (_, Ok(cache)) if !cache.is_empty() => Path::new(&cache).join("grapevine"),
+        _ => match get_storage_path() {
+            Ok(path) => path,
+            Err(e) => return Err(GrapevineError::FsError(e.to_string())),
+        },
+    };
+    // if does not exist, create the dir
+    if !artifacts_path.exists() {
+        eprintln!(
+            "Creating artifact directory {}...",
+            artifacts_path.display()
+        );
+        if let Err(e) = std::fs::create_dir_all(&artifacts_path) {
+            return Err(GrapevineError::FsError(e.to_string()));
+        }
+    }
+    Ok(artifacts_path)
+}
+
+/**
+ * Reads the unix timestamp of when new relationship requests were last checked
+ *
+ * @returns {u32} the last seen timestamp, or 0 if never checked
+ */
+pub fn read_relationships_last_seen() -> u32 {
+    let path = get_storage_path().unwrap().join("relationships_last_seen");
+    match std::fs::read_to_string(path) {
+        Ok(timestamp) => timestamp.trim().parse::<u32>().unwrap_or(0),
+        Err(_) => 0,
+    }
+}
+
+/**
+ * Saves the unix timestamp of when new relationship requests were last checked
+ *
+ * @param timestamp - the unix timestamp (seconds) to save
+ */
+pub fn write_relationships_last_seen(timestamp: u32) -> Result<(), GrapevineError> {
+    let path = get_storage_path().unwrap().join("relationships_last_seen");
+    match write(path, timestamp.to_string()) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(GrapevineError::FsError(e.to_string())),
+    }
+}
+
+/**
+ * Gets the directory proven phrases are kept in until the server accepts them
+ * If the directory does not exist, create it
+ *
+ * @returns {PathBuf} path to ~/.grapevine/pending if successful
+ */
+pub fn get_pending_path() -> Result<PathBuf, GrapevineError> {
+    let pending_path = match get_storage_path() {
+        Ok(path) => path.join("pending"),
+        Err(e) => return Err(GrapevineError::FsError(e.to_string())),
+    };
+    if let Err(e) = std::fs::create_dir_all(&pending_path) {
+        return Err(GrapevineError::FsError(e.to_string()));
+    }
+    Ok(pending_path)
+}
+
+/**
+ * Saves a proven phrase request so it can be submitted again if submitting it fails
+ * @notice the phrase is only stored encrypted to the account, as it is sent to the server
+ *
+ * @param dir - the directory to save the request in
+ * @param request - the phrase request to save
+ * @returns {PathBuf} the path of the saved request (phrase-<unix millis>.proof)
+ */
+pub fn save_pending_proof(dir: &Path, request: &PhraseRequest) -> Result<PathBuf, GrapevineError> {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let path = dir.join(format!("phrase-{}.proof", millis));
+    let serialized = match bincode::serialize(request) {
+        Ok(serialized) => serialized,
+        Err(e) => return Err(GrapevineError::SerdeError(e.to_string())),
+    };
+    match write(&path, serialized) {
+        Ok(_) => Ok(path),
+        Err(e) => Err(GrapevineError::FsError(e.to_string())),
+    }
+}
+
+/**
+ * Reads a phrase request saved by `save_pending_proof`
+ *
+ * @param path - the path of the saved request
+ * @returns {PhraseRequest} the phrase request, ready to submit
+ */
+pub fn read_pending_proof(path: &Path) -> Result<PhraseRequest, GrapevineError> {
+    let serialized = match std::fs::read(path) {
+        Ok(serialized) => serialized,
+        Err(e) => return Err(GrapevineError::FsError(e.to_string())),
+    };
+    match bincode::deserialize::<PhraseRequest>(&serialized) {
+        Ok(request) => Ok(request),
+        Err(_) => Err(GrapevineError::SerdeError(String::from("PhraseRequest"))),
+    }
+}
+
+/**
+ * Checks whether r1cs, wasm, witcalc exist in the artifact directory
  *
  * @returns {bool} true if all artifacts exist, false otherwise
  */
 pub fn check_artifacts_exist() -> bool {
-    // get the path to grapevine (will create if it does not exist)
-    let storage_dir = get_storage_path().unwrap();
+    // get the path to the artifacts (will create if it does not exist)
+    let storage_dir = get_artifacts_path().unwrap();
     // specify artifact files to check
     let r1cs_path = storage_dir.join("grapevine.r1cs");
     let wasm_path = storage_dir.join("grapevine.wasm");
@@ -92,19 +315,63 @@ pub fn check_artifacts_exist() -> bool {
 }
 
 /**
- * Retrieves proving artifacts (r1cs, wasm witcalc, nova public params) and saves them to .grapevine
+ * Checks whether the r1cs and public params in the artifact directory match the server's copies
+ *
+ * @param circuit_info - the artifact fingerprints reported by the server
+ * @returns {bool} true if both artifacts hash to the server's fingerprints, false otherwise
+ */
+pub fn check_artifacts_match(circuit_info: &CircuitInfoResponse) -> bool {
+    let storage_dir = get_artifacts_path().unwrap();
+    let matches = |artifact: &str, expected: &String| {
+        let bytes = std::fs::read(storage_dir.join(artifact));
+        bytes.is_ok_and(|bytes| artifact_hash(&bytes) == *expected)
+    };
+    matches("grapevine.r1cs", &circuit_info.r1cs_hash)
+        && matches("public_params.json", &circuit_info.params_hash)
+}
+
+/**
+ * Reads the public params in the artifact directory along with their fingerprint, without
+ * contacting the server
+ *
+ * @returns - the public params and their artifact hash, or an error if they cannot be read
+ */
+pub fn read_public_params_with_hash() -> Result<(Params, String), GrapevineError> {
+    let filepath = get_artifacts_path()?.join("public_params.json");
+    let bytes = match std::fs::read(&filepath) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return Err(GrapevineError::FsError(format!(
+                "{}: {}",
+                filepath.display(),
+                e
+            )))
+        }
+    };
+    let public_params = match serde_json::from_slice::<Params>(&bytes) {
+        Ok(public_params) => public_params,
+        Err(e) => return Err(GrapevineError::SerdeError(e.to_string())),
+    };
+    Ok((public_params, artifact_hash(&bytes)))
+}
+
+/**
+ * Retrieves proving artifacts (r1cs, wasm witcalc, nova public params) and saves them to the
+ * artifact directory
  *
  * @returns - result of whether or not artifacts were downloaded successfully
  */
 pub async fn get_artifacts() -> Result<(), Box<dyn std::error::Error>> {
     let artifacts = ["grapevine.r1cs", "grapevine.wasm", "public_params.json"];
     for artifact in artifacts {
-        println!("Downloading {}...", artifact);
-        let path = get_storage_path().unwrap().join(artifact);
+        eprintln!("Downloading {}...", artifact);
+        let path = get_artifacts_path().unwrap().join(artifact);
         let url = format!("{}/static/{}", &**SERVER_URL, artifact);
         download_file(url, path.clone()).await.unwrap();
-        println!("Downloaded {} to {}", artifact, path.display());
+        eprintln!("Downloaded {} to {}", artifact, path.display());
     }
+    // force the new artifacts to be read on next use
+    *ARTIFACTS.lock().unwrap() = None;
     Ok(())
 }
 
@@ -116,7 +383,142 @@ pub async fn get_artifacts() -> Result<(), Box<dyn std::error::Error>> {
  * @returns - result of whether or not file downloaded successfully
  */
 async fn download_file(uri: String, path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-    let data = reqwest::get(uri).await?.bytes().await?;
+    // artifacts can be large, so use a client without the api request timeout
+    let request = reqwest::Client::new().get(&uri);
+    let data = send_request_with_retry(request, &uri, &RETRY_POLICY)
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
     write(path, data)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::MutexGuard;
+
+    /** Serializes the tests that point GRAPEVINE_ARTIFACTS_DIR at their own directory */
+    static ARTIFACTS_DIR_LOCK: Mutex<()> = Mutex::new(());
+
+    fn lock_artifacts_dir() -> MutexGuard<'static, ()> {
+        // a failed test poisons the lock, which should not fail the tests after it
+        ARTIFACTS_DIR_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    #[test]
+    fn test_artifacts_dir_from_env() {
+        let _lock = lock_artifacts_dir();
+        // point the artifact dir at a temp dir that does not exist yet
+        let dir = std::env::temp_dir().join("grapevine_artifacts_dir_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::env::set_var("GRAPEVINE_ARTIFACTS_DIR", &dir);
+
+        assert_eq!(get_artifacts_path().unwrap(), dir);
+        assert!(dir.exists(), "Artifact directory should be created");
+        assert_eq!(use_wasm().unwrap(), dir.join("grapevine.wasm"));
+
+        // the r1cs should be loaded from the artifact dir
+        let r1cs_path = Path::new("../grapevine_circuits/circom/artifacts/grapevine.r1cs");
+        std::fs::copy(r1cs_path, dir.join("grapevine.r1cs")).unwrap();
+        let r1cs = use_r1cs().unwrap();
+        let expected = load_r1cs::<G1, G2>(&FileLocation::PathBuf(r1cs_path.to_path_buf()));
+        assert_eq!(r1cs.constraints.len(), expected.constraints.len());
+
+        std::env::remove_var("GRAPEVINE_ARTIFACTS_DIR");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_stale_artifacts_detected() {
+        let _lock = lock_artifacts_dir();
+        let dir = std::env::temp_dir().join("grapevine_stale_artifacts_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::env::set_var("GRAPEVINE_ARTIFACTS_DIR", &dir);
+        get_artifacts_path().unwrap();
+        write(dir.join("grapevine.r1cs"), b"r1cs").unwrap();
+        write(dir.join("public_params.json"), b"params").unwrap();
+
+        let mut circuit_info = CircuitInfoResponse {
+            circuit_version: String::from("1"),
+            r1cs_hash: artifact_hash(b"r1cs"),
+            params_hash: artifact_hash(b"params"),
+            max_degree: u8::MAX,
+        };
+        assert!(check_artifacts_match(&circuit_info));
+
+        // a server running a newer circuit should invalidate the local artifacts
+        circuit_info.params_hash = artifact_hash(b"new params");
+        assert!(!check_artifacts_match(&circuit_info));
+
+        std::env::remove_var("GRAPEVINE_ARTIFACTS_DIR");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_artifacts_loaded_once() {
+        let _lock = lock_artifacts_dir();
+        let dir = std::env::temp_dir().join("grapevine_artifacts_loaded_once_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::env::set_var("GRAPEVINE_ARTIFACTS_DIR", &dir);
+        get_artifacts_path().unwrap();
+        let artifacts = Path::new("../grapevine_circuits/circom/artifacts");
+        std::fs::copy(artifacts.join("grapevine.r1cs"), dir.join("grapevine.r1cs")).unwrap();
+        std::fs::copy(
+            artifacts.join("public_params.json"),
+            dir.join("public_params.json"),
+        )
+        .unwrap();
+
+        // drop artifacts another test may have loaded from its own directory
+        *ARTIFACTS.lock().unwrap() = None;
+
+        // sequential uses in one session should share the artifacts read on first use
+        let first = use_artifacts().unwrap();
+        let loads = artifact_loads();
+        let second = use_artifacts().unwrap();
+        assert!(artifacts_loaded());
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(artifact_loads(), loads);
+
+        std::env::remove_var("GRAPEVINE_ARTIFACTS_DIR");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_pending_proof_round_trip() {
+        let dir = std::env::temp_dir().join("grapevine_pending_proof_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let request = PhraseRequest {
+            version: 2,
+            proof: vec![1, 2, 3],
+            ciphertext: [7; 192],
+            description: String::from("Saved before submitting"),
+            tags: vec![String::from("retry")],
+        };
+        let path = save_pending_proof(&dir, &request).unwrap();
+        assert!(path.starts_with(&dir));
+
+        // the saved file can be read back and submitted on its own
+        let read = read_pending_proof(&path).unwrap();
+        assert_eq!(read.version, request.version);
+        assert_eq!(read.proof, request.proof);
+        assert_eq!(read.ciphertext, request.ciphertext);
+        assert_eq!(read.description, request.description);
+        assert_eq!(read.tags, request.tags);
+
+        // anything else is rejected
+        write(&path, b"not a proof").unwrap();
+        assert!(matches!(
+            read_pending_proof(&path),
+            Err(GrapevineError::SerdeError(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
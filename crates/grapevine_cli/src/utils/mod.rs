@@ -1,9 +1,25 @@
+use crate::http::get_circuit_info_req;
+
 pub mod fs;
 
 pub async fn artifacts_guard() -> Result<(), Box<dyn std::error::Error>> {
+    // artifacts already in memory were checked when they were loaded
+    if fs::artifacts_loaded() {
+        return Ok(());
+    }
     // check if artifacts exist
     if !fs::check_artifacts_exist() {
-        println!("Downloading proving artifacts...");
+        eprintln!("Downloading proving artifacts...");
+        fs::get_artifacts().await?;
+        return Ok(());
+    }
+    // check that local artifacts match the circuit the server verifies against
+    let circuit_info = get_circuit_info_req().await?;
+    if !fs::check_artifacts_match(&circuit_info) {
+        eprintln!(
+            "Proving artifacts are stale, downloading circuit version {}...",
+            circuit_info.circuit_version
+        );
         fs::get_artifacts().await?;
     }
     Ok(())
@@ -0,0 +1,11 @@
+#![no_main]
+
+use grapevine_common_fuzz::decode;
+use libfuzzer_sys::fuzz_target;
+
+// the first byte selects the request type, the rest is the request body
+fuzz_target!(|data: &[u8]| {
+    if let Some((kind, body)) = data.split_first() {
+        decode(*kind, body);
+    }
+});
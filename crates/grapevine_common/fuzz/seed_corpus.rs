@@ -0,0 +1,78 @@
+use grapevine_common::account::GrapevineAccount;
+use grapevine_common::http::requests::{
+    DegreeProofBatchRequest, DegreeProofRequest, DelegatedDegreeProofRequest, PhraseRequest,
+    PhraseRequestV1, VerifyInSetRequest,
+};
+use grapevine_common::REQUEST_VERSION;
+use grapevine_common_fuzz::REQUEST_KINDS;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/**
+ * Write a valid encoding of each request type to corpus/decode_request, prefixed with the byte
+ * that selects its type in the fuzz target
+ */
+fn main() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("corpus/decode_request");
+    std::fs::create_dir_all(&dir).unwrap();
+    let write = |kind: &str, request: Vec<u8>| {
+        let index = REQUEST_KINDS.iter().position(|k| *k == kind).unwrap();
+        let mut seed = vec![index as u8];
+        seed.extend(request);
+        std::fs::write(dir.join(kind), seed).unwrap();
+    };
+
+    let alice = GrapevineAccount::new_deterministic(String::from("alice"), [1; 32]);
+    let bob = GrapevineAccount::new_deterministic(String::from("bob"), [2; 32]);
+    // deserialization does not look inside the proof, so any bytes stand in for one
+    let proof = vec![0xab; 256];
+    let degree_proof = DegreeProofRequest {
+        version: REQUEST_VERSION,
+        proof: proof.clone(),
+        previous: String::from("65f1c0ffee65f1c0ffee65f1"),
+        degree: 2,
+    };
+
+    write("create_user", encode(&alice.create_user_request()));
+    write("get_nonce", encode(&alice.get_nonce_request()));
+    let relationship = alice.new_relationship_request(bob.username(), &bob.pubkey());
+    write("new_relationship", encode(&relationship));
+    let phrase = PhraseRequest {
+        version: REQUEST_VERSION,
+        proof: proof.clone(),
+        ciphertext: alice.encrypt_phrase(&String::from("seed phrase")).unwrap(),
+        description: String::from("seed description"),
+        tags: vec![String::from("seed")],
+    };
+    write("phrase", encode(&phrase));
+    let phrase_v1 = PhraseRequestV1 {
+        version: 1,
+        proof: proof.clone(),
+        ciphertext: phrase.ciphertext,
+        description: phrase.description.clone(),
+    };
+    write("phrase_v1", encode(&phrase_v1));
+    write("degree_proof", encode(&degree_proof));
+    let batch = DegreeProofBatchRequest {
+        version: REQUEST_VERSION,
+        proofs: vec![degree_proof.clone(), degree_proof.clone()],
+    };
+    write("degree_proof_batch", encode(&batch));
+    let delegated = DelegatedDegreeProofRequest {
+        version: REQUEST_VERSION,
+        previous: degree_proof.previous.clone(),
+        auth_secrets: [[3; 32], [4; 32]],
+    };
+    write("delegated_degree_proof", encode(&delegated));
+    let verify = VerifyInSetRequest {
+        proof,
+        degree: 2,
+        allowed: vec![[5; 32], [6; 32]],
+    };
+    write("verify_in_set", encode(&verify));
+    println!("wrote {} seeds to {}", REQUEST_KINDS.len(), dir.display());
+}
+
+fn encode<T: Serialize>(request: &T) -> Vec<u8> {
+    bincode::serialize(request).unwrap()
+}
@@ -0,0 +1,51 @@
+use grapevine_common::http::requests::{
+    decode_request, CreateUserRequest, DegreeProofBatchRequest, DegreeProofRequest,
+    DelegatedDegreeProofRequest, GetNonceRequest, NewRelationshipRequest, PhraseRequest,
+    PhraseRequestV1, VerifyInSetRequest,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/** The request types a fuzz input can be decoded as, selected by its first byte */
+pub const REQUEST_KINDS: [&str; 9] = [
+    "create_user",
+    "get_nonce",
+    "new_relationship",
+    "phrase",
+    "phrase_v1",
+    "degree_proof",
+    "degree_proof_batch",
+    "delegated_degree_proof",
+    "verify_in_set",
+];
+
+/**
+ * Decode a request body as one of the request types, checking that anything accepted is the
+ * canonical encoding of the request it decodes to
+ *
+ * @param kind - the index into REQUEST_KINDS (wrapped) of the request type to decode
+ * @param body - the request body
+ */
+pub fn decode(kind: u8, body: &[u8]) {
+    match kind as usize % REQUEST_KINDS.len() {
+        0 => round_trip::<CreateUserRequest>(body),
+        1 => round_trip::<GetNonceRequest>(body),
+        2 => round_trip::<NewRelationshipRequest>(body),
+        3 => round_trip::<PhraseRequest>(body),
+        4 => round_trip::<PhraseRequestV1>(body),
+        5 => round_trip::<DegreeProofRequest>(body),
+        6 => round_trip::<DegreeProofBatchRequest>(body),
+        7 => round_trip::<DelegatedDegreeProofRequest>(body),
+        _ => round_trip::<VerifyInSetRequest>(body),
+    }
+}
+
+fn round_trip<T: Serialize + DeserializeOwned>(body: &[u8]) {
+    if let Ok(request) = decode_request::<T>(body) {
+        // trailing bytes are allowed, so the body only has to start with the encoding
+        let bytes = bincode::serialize(&request).unwrap();
+        assert!(
+            body.starts_with(&bytes),
+            "decoded request does not re-encode to the body"
+        );
+    }
+}
@@ -1,16 +1,18 @@
 use crate::auth_secret::{AuthSecret, AuthSecretEncrypted, AuthSecretEncryptedUser};
-use crate::crypto::{gen_aes_key, new_private_key, nonce_hash};
+use crate::credential::Credential;
+use crate::crypto::{self, gen_aes_key, new_private_key, recovery_hash, request_hash};
+use crate::errors::GrapevineError;
 use crate::http::requests::{CreateUserRequest, GetNonceRequest, NewRelationshipRequest};
-use crate::utils::{convert_username_to_fr, random_fr};
+use crate::utils::{convert_username_to_fr, random_fr, zeroize_fr};
 use crate::Fr;
-use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use argon2::{Algorithm, Argon2, Params, Version};
 use babyjubjub_rs::{Point, PrivateKey, Signature};
 use num_bigint::{BigInt, Sign};
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
 use std::path::PathBuf;
+use zeroize::Zeroize;
 
-type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
-type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GrapevineAccount {
     username: String,
@@ -27,8 +29,44 @@ impl GrapevineAccount {
      * @returns - the new account with an autogenerated private key and auth secret
      */
     pub fn new(username: String) -> GrapevineAccount {
-        let private_key = new_private_key();
-        let auth_secret = random_fr();
+        GrapevineAccount::from_secrets(username, new_private_key(), random_fr())
+    }
+
+    /**
+     * Generates an account whose keys are derived from a seed, so the same seed always reproduces
+     * the same identity. Intended for tests and tooling - never use a guessable seed for real use
+     * @notice - keys are derived with domain separated sha3-256 hashes of the seed:
+     *           private key = H("grapevine/private_key" | seed)
+     *           auth secret = H("grapevine/auth_secret" | seed), last byte zeroed to fit in Fr
+     *
+     * @param username - the username to associate with this account
+     * @param seed - the 32 byte seed to derive the private key and auth secret from
+     * @returns - the new account with a private key and auth secret derived from the seed
+     */
+    pub fn new_deterministic(username: String, seed: [u8; 32]) -> GrapevineAccount {
+        let derive = |domain: &[u8]| -> [u8; 32] {
+            let mut hasher = Sha3_256::new();
+            hasher.update(domain);
+            hasher.update(seed);
+            hasher.finalize().into()
+        };
+        let private_key = derive(b"grapevine/private_key");
+        let mut auth_secret = derive(b"grapevine/auth_secret");
+        // 0 the last byte to ensure it always falls within the prime field Fr
+        auth_secret[31] = 0;
+        let auth_secret = Fr::from_bytes(&auth_secret).unwrap();
+        GrapevineAccount::from_secrets(username, private_key, auth_secret)
+    }
+
+    /**
+     * Builds an account with a fresh nonce from its secrets
+     *
+     * @param username - the username to associate with this account
+     * @param private_key - the Baby Jubjub private key of the account
+     * @param auth_secret - the auth secret used to link to proofs made by this account
+     * @returns - the new account
+     */
+    fn from_secrets(username: String, private_key: [u8; 32], auth_secret: Fr) -> GrapevineAccount {
         GrapevineAccount {
             username,
             auth_secret,
@@ -37,6 +75,19 @@ impl GrapevineAccount {
         }
     }
 
+    /**
+     * Copies the account with a new private key, keeping its username, auth secret, and nonce
+     * @notice - used after recovering an account through its guardians
+     *
+     * @param private_key - the Baby Jubjub private key the account was moved to
+     * @returns - the account using the new private key
+     */
+    pub fn with_private_key(&self, private_key: [u8; 32]) -> GrapevineAccount {
+        let mut account = self.clone();
+        account.private_key = private_key;
+        account
+    }
+
     /// PERSISTENCE METHODS ///
 
     /**
@@ -106,40 +157,58 @@ impl GrapevineAccount {
     /// PHRASE ENCRYPTION METHODS ///
 
     /**
-     * Encrypt a phrase for this account
+     * Get the key this account encrypts its phrases with, derived from the account keypair
+     *
+     * @returns - the 32 byte phrase encryption key
      */
-    pub fn encrypt_phrase(&self, phrase: &String) -> [u8; 192] {
-        // convert phrase to binary
-        let mut bytes = phrase.as_bytes().to_vec();
-        bytes.resize(180, 0);
-        let mut buf = [0u8; 192];
-        buf[..bytes.len()].copy_from_slice(&bytes);
-        // generate encryption key
+    pub fn phrase_key(&self) -> [u8; 32] {
         let (aes_key, aes_iv) = gen_aes_key(self.private_key(), self.pubkey());
-        // encrypt padded phrase
-        Aes128CbcEnc::new(aes_key[..].into(), aes_iv[..].into())
-            .encrypt_padded_mut::<Pkcs7>(&mut buf, bytes.len())
-            .unwrap()
-            .try_into()
-            .unwrap()
+        let mut key = [0u8; 32];
+        key[..16].copy_from_slice(&aes_key);
+        key[16..].copy_from_slice(&aes_iv);
+        key
+    }
+
+    /**
+     * Encrypt a phrase for this account
+     *
+     * @param phrase - the phrase to encrypt
+     * @returns - the encrypted phrase, or PhraseTooLong if it is over MAX_SECRET_CHARS bytes
+     */
+    pub fn encrypt_phrase(&self, phrase: &String) -> Result<[u8; 192], GrapevineError> {
+        crypto::encrypt_phrase(&self.phrase_key(), phrase)
     }
 
     /**
      * Decrypt a phrase for this account
+     * @notice - falls back to the legacy aes-128-cbc format, so phrases stored before
+     *           authenticated encryption can still be read and upgraded with `reencrypt_phrase`
+     *
+     * @param ciphertext - the phrase encrypted by `encrypt_phrase`
+     * @returns - the phrase, or PhraseDecryptionFailed if it was not encrypted for this account
      */
-    pub fn decrypt_phrase(&self, ciphertext: &[u8; 192]) -> String {
-        // derive asymmetric key key
-        let (aes_key, aes_iv) = gen_aes_key(self.private_key(), self.pubkey());
-        // decrypt ciphertext
-        let mut buf = ciphertext.clone();
-        let ptr: [u8; 180] = Aes128CbcDec::new(aes_key[..].into(), aes_iv[..].into())
-            .decrypt_padded_mut::<Pkcs7>(&mut buf)
-            .unwrap()
-            .try_into()
-            .unwrap();
-        // return the string
-        let end = ptr.iter().position(|&r| r == 0).unwrap_or(ptr.len());
-        String::from_utf8(ptr[..end].to_vec()).unwrap()
+    pub fn decrypt_phrase(&self, ciphertext: &[u8; 192]) -> Result<String, GrapevineError> {
+        let key = self.phrase_key();
+        crypto::decrypt_phrase(&key, ciphertext)
+            .or_else(|_| crypto::decrypt_legacy_phrase(&key, ciphertext))
+    }
+
+    /**
+     * Re-encrypt a phrase encrypted for this account under another phrase key
+     * @notice - used after rotating the account keypair, since the phrase key is derived from it
+     *
+     * @param ciphertext - the phrase encrypted by `encrypt_phrase`
+     * @param new_key - the phrase key to encrypt under (see `phrase_key` of the rotated account)
+     * @returns - the phrase encrypted under the new key, or PhraseDecryptionFailed if it was not
+     *            encrypted for this account
+     */
+    pub fn reencrypt_phrase(
+        &self,
+        ciphertext: &[u8; 192],
+        new_key: &[u8; 32],
+    ) -> Result<[u8; 192], GrapevineError> {
+        let phrase = self.decrypt_phrase(ciphertext)?;
+        crypto::encrypt_phrase(new_key, &phrase)
     }
 
     /// SIGNING METHODS ///
@@ -158,13 +227,50 @@ impl GrapevineAccount {
     }
 
     /**
-     * Produce a signature over the sha256 hash H|username, nonce| of this account
+     * Produce a signature over the sha256 hash H|username, nonce, method, path, query, tenant| of
+     * this account
      *
-     * @returns - the signature authorizing arbitrary gated http actions
+     * @param method - the http method of the gated request
+     * @param path - the path of the gated request from the server root
+     * @param query - the raw query string of the gated request, if any
+     * @param tenant - the X-Tenant header the gated request is sent with, if any
+     * @returns - the signature authorizing one gated http action
      */
-    pub fn sign_nonce(&self) -> Signature {
-        let message =
-            BigInt::from_bytes_le(Sign::Plus, &nonce_hash(&self.username, self.nonce)[..]);
+    pub fn sign_request(
+        &self,
+        method: &str,
+        path: &str,
+        query: Option<&str>,
+        tenant: Option<&str>,
+    ) -> Signature {
+        let hash = request_hash(&self.username, self.nonce, method, path, query, tenant);
+        let message = BigInt::from_bytes_le(Sign::Plus, &hash[..]);
+        self.private_key().sign(message).unwrap()
+    }
+
+    /**
+     * Produce a guardian's approval for moving another account to a new pubkey
+     *
+     * @param username - the username of the account being recovered
+     * @param pubkey - the pubkey the account is being moved to
+     * @param nonce - the current nonce of the account being recovered
+     * @returns - the signature over H|"grapevine/recover", username, pubkey, nonce|
+     */
+    pub fn sign_recovery(&self, username: &String, pubkey: &Point, nonce: u64) -> Signature {
+        let hash = recovery_hash(username, &pubkey.compress(), nonce);
+        let message = BigInt::from_bytes_le(Sign::Plus, &hash[..]);
+        self.private_key().sign(message).unwrap()
+    }
+
+    /**
+     * Sign a credential so a verifier can check it was not altered after this account signed it
+     * @notice - this does not show the proof in the credential was made by this account
+     *
+     * @param credential - the credential to sign (its signature field is not covered)
+     * @returns - the signature over the hash of the credential
+     */
+    pub fn sign_credential(&self, credential: &Credential) -> Signature {
+        let message = BigInt::from_bytes_le(Sign::Plus, &credential.hash()[..]);
         self.private_key().sign(message).unwrap()
     }
 
@@ -253,10 +359,106 @@ impl GrapevineAccount {
     }
 }
 
+/**
+ * Wipe the private key and auth secret so they do not linger in memory
+ * @notice - only the in-memory copy is wiped; an account saved to the filesystem is unaffected
+ */
+impl Zeroize for GrapevineAccount {
+    fn zeroize(&mut self) {
+        self.private_key.zeroize();
+        zeroize_fr(&mut self.auth_secret);
+    }
+}
+
+impl Drop for GrapevineAccount {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+pub const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024; // 1 GiB, most memory a stored KDF can ask for
+pub const MAX_KDF_ITERATIONS: u32 = 64; // most passes a stored KDF can ask for
+pub const MAX_KDF_PARALLELISM: u32 = 16; // most lanes a stored KDF can ask for
+
+/**
+ * Argon2id cost parameters used to derive an account encryption key from a password
+ * @dev stored alongside an encrypted account so the costs can be raised for new files while
+ *      older files still decrypt with the parameters they were written with
+ */
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    // OWASP recommended minimum for argon2id
+    fn default() -> Self {
+        KdfParams {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /**
+     * Checks the parameters are within bounds before any memory is allocated for the KDF
+     * @dev params are read from an untrusted file, so a huge memory cost must be rejected here
+     *
+     * @return - InvalidKdfParams if any cost is zero, over its maximum, or the memory is too
+     *           small for the number of lanes
+     */
+    pub fn validate(&self) -> Result<(), GrapevineError> {
+        if self.iterations == 0 || self.iterations > MAX_KDF_ITERATIONS {
+            return Err(GrapevineError::InvalidKdfParams(format!(
+                "iterations must be 1 to {}",
+                MAX_KDF_ITERATIONS
+            )));
+        }
+        if self.parallelism == 0 || self.parallelism > MAX_KDF_PARALLELISM {
+            return Err(GrapevineError::InvalidKdfParams(format!(
+                "parallelism must be 1 to {}",
+                MAX_KDF_PARALLELISM
+            )));
+        }
+        // argon2 needs at least 8 KiB per lane
+        if self.memory_kib < 8 * self.parallelism || self.memory_kib > MAX_KDF_MEMORY_KIB {
+            return Err(GrapevineError::InvalidKdfParams(format!(
+                "memory must be {} to {} KiB",
+                8 * self.parallelism,
+                MAX_KDF_MEMORY_KIB
+            )));
+        }
+        Ok(())
+    }
+
+    /**
+     * Derives a 32 byte encryption key from a password with argon2id
+     *
+     * @param password - the password the account is encrypted with
+     * @param salt - the random salt stored with the encrypted account (at least 8 bytes)
+     * @return - the derived key, or InvalidKdfParams if the params or salt are rejected
+     */
+    pub fn derive_key(&self, password: &[u8], salt: &[u8]) -> Result<[u8; 32], GrapevineError> {
+        self.validate()?;
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| GrapevineError::InvalidKdfParams(e.to_string()))?;
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password, salt, &mut key)
+            .map_err(|e| GrapevineError::InvalidKdfParams(e.to_string()))?;
+        Ok(key)
+    }
+}
+
 #[cfg(test)]
 mod test {
 
     use super::*;
+    use std::mem::ManuallyDrop;
 
     #[test]
     fn test_serialize() {
@@ -273,8 +475,140 @@ mod test {
         let username = String::from("JP4G");
         let account = GrapevineAccount::new(username);
         let phrase = String::from("This is a test phrase");
-        let ciphertext = account.encrypt_phrase(&phrase);
-        let decrypted = account.decrypt_phrase(&ciphertext);
+        let ciphertext = account.encrypt_phrase(&phrase).unwrap();
+        let decrypted = account.decrypt_phrase(&ciphertext).unwrap();
         assert_eq!(decrypted, phrase);
     }
+
+    #[test]
+    fn test_reencrypt_phrase_after_rotation() {
+        let account = GrapevineAccount::new(String::from("JP4G"));
+        let phrase = String::from("This phrase outlives the old key");
+        let ciphertext = account.encrypt_phrase(&phrase).unwrap();
+
+        // rotate to a new keypair, whose phrase key cannot read the old ciphertext
+        let rotated = account.with_private_key(new_private_key());
+        assert!(rotated.decrypt_phrase(&ciphertext).is_err());
+
+        let reencrypted = account
+            .reencrypt_phrase(&ciphertext, &rotated.phrase_key())
+            .unwrap();
+        assert_eq!(rotated.decrypt_phrase(&reencrypted).unwrap(), phrase);
+    }
+
+    #[test]
+    fn test_decrypt_legacy_phrase() {
+        use aes::cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit};
+        type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
+
+        let account = GrapevineAccount::new(String::from("JP4G"));
+        let phrase = String::from("Stored before authenticated encryption");
+        // encrypt the way phrases were encrypted before `crypto::encrypt_phrase`
+        let mut bytes = phrase.as_bytes().to_vec();
+        bytes.resize(180, 0); // the legacy format padded phrases to 180 bytes
+        let mut buf = [0u8; 192];
+        buf[..bytes.len()].copy_from_slice(&bytes);
+        let (aes_key, aes_iv) = gen_aes_key(account.private_key(), account.pubkey());
+        let legacy: [u8; 192] = Aes128CbcEnc::new(aes_key[..].into(), aes_iv[..].into())
+            .encrypt_padded_mut::<Pkcs7>(&mut buf, bytes.len())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert!(crypto::decrypt_phrase(&account.phrase_key(), &legacy).is_err());
+        assert_eq!(account.decrypt_phrase(&legacy).unwrap(), phrase);
+
+        // re-encrypting upgrades the phrase to the authenticated format
+        let rotated = account.with_private_key(new_private_key());
+        let upgraded = account
+            .reencrypt_phrase(&legacy, &rotated.phrase_key())
+            .unwrap();
+        assert_eq!(
+            crypto::decrypt_phrase(&rotated.phrase_key(), &upgraded).unwrap(),
+            phrase
+        );
+
+        // another account cannot read it
+        let other = GrapevineAccount::new(String::from("JP4G"));
+        assert!(other.decrypt_phrase(&legacy).is_err());
+    }
+
+    #[test]
+    fn test_new_deterministic() {
+        let seed = [7u8; 32];
+        let account = GrapevineAccount::new_deterministic(String::from("JP4G"), seed);
+        let again = GrapevineAccount::new_deterministic(String::from("JP4G"), seed);
+        assert_eq!(account.private_key_raw(), again.private_key_raw());
+        assert_eq!(account.pubkey().compress(), again.pubkey().compress());
+        assert_eq!(account.auth_secret(), again.auth_secret());
+        assert_eq!(account.nonce(), 0);
+
+        // a different seed yields a different identity
+        let other = GrapevineAccount::new_deterministic(String::from("JP4G"), [8u8; 32]);
+        assert_ne!(account.pubkey().compress(), other.pubkey().compress());
+        assert_ne!(account.auth_secret(), other.auth_secret());
+    }
+
+    #[test]
+    fn test_kdf_params_default_round_trip() {
+        let params = KdfParams::default();
+        let json = serde_json::to_string(&params).unwrap();
+        let deserialized = serde_json::from_str::<KdfParams>(&json).unwrap();
+        assert_eq!(deserialized, params);
+        assert!(deserialized.validate().is_ok());
+
+        // the same password and salt always derive the same key
+        let salt = [3u8; 16];
+        let key = params.derive_key(b"password", &salt).unwrap();
+        assert_eq!(key, deserialized.derive_key(b"password", &salt).unwrap());
+        assert_ne!(key, params.derive_key(b"passw0rd", &salt).unwrap());
+    }
+
+    #[test]
+    fn test_kdf_params_rejects_absurd() {
+        let default = KdfParams::default();
+        for params in [
+            KdfParams {
+                memory_kib: u32::MAX,
+                ..default
+            },
+            KdfParams {
+                memory_kib: 0,
+                ..default
+            },
+            KdfParams {
+                iterations: 0,
+                ..default
+            },
+            KdfParams {
+                iterations: u32::MAX,
+                ..default
+            },
+            KdfParams {
+                parallelism: MAX_KDF_PARALLELISM + 1,
+                ..default
+            },
+        ] {
+            assert!(matches!(
+                params.derive_key(b"password", &[3u8; 16]),
+                Err(GrapevineError::InvalidKdfParams(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_zeroize_on_drop() {
+        let mut account = ManuallyDrop::new(GrapevineAccount::new(String::from("JP4G")));
+        let saved = serde_json::to_string(&*account).unwrap();
+        assert_ne!(account.private_key, [0u8; 32]);
+        assert_ne!(account.auth_secret, Fr::from(0));
+
+        // drop the account in place so its memory can still be read afterwards
+        unsafe { ManuallyDrop::drop(&mut account) };
+        assert_eq!(account.private_key, [0u8; 32]);
+        assert_eq!(account.auth_secret, Fr::from(0));
+
+        // the serialized copy is unaffected
+        let restored = serde_json::from_str::<GrapevineAccount>(&saved).unwrap();
+        assert_ne!(restored.private_key, [0u8; 32]);
+    }
 }
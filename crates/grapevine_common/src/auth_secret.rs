@@ -1,4 +1,5 @@
 use crate::crypto::gen_aes_key;
+use crate::utils::zeroize_fr;
 use crate::Fr;
 use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
 use babyjubjub_rs::{Point, PrivateKey};
@@ -27,6 +28,12 @@ pub struct AuthSecret {
     pub auth_secret: Fr,
 }
 
+impl Drop for AuthSecret {
+    fn drop(&mut self) {
+        zeroize_fr(&mut self.auth_secret);
+    }
+}
+
 pub trait AuthSecretEncryptedUser {
     /**
      * Create a new encrypted auth secret
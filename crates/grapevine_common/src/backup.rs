@@ -0,0 +1,193 @@
+use crate::errors::GrapevineError;
+use sha3::{Digest, Sha3_256};
+use std::io::Read;
+
+/// A proof backup is BACKUP_MAGIC followed by one record per proof document and a footer:
+///   - record: u32 little endian length of the bson document, then the document
+///   - footer: u32 zero, u64 little endian record count, sha3-256 of every record
+pub const BACKUP_MAGIC: [u8; 8] = *b"GVPROOF1";
+pub const MAX_BACKUP_RECORD: u32 = 16 * 1024 * 1024; // largest bson document mongodb stores
+
+/**
+ * Tracks the count and checksum of the records written to or read from a proof backup
+ */
+pub struct BackupDigest {
+    count: u64,
+    hasher: Sha3_256,
+}
+
+impl BackupDigest {
+    pub fn new() -> Self {
+        BackupDigest {
+            count: 0,
+            hasher: Sha3_256::new(),
+        }
+    }
+
+    /**
+     * @return - the number of records seen so far
+     */
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /**
+     * Add a record to the count and checksum
+     *
+     * @param document - the serialized bson document of the record
+     */
+    pub fn update(&mut self, document: &[u8]) {
+        self.hasher.update((document.len() as u32).to_le_bytes());
+        self.hasher.update(document);
+        self.count += 1;
+    }
+
+    /**
+     * Encode a proof document as a backup record, adding it to the count and checksum
+     *
+     * @param document - the serialized bson document of the proof
+     * @return - the length prefixed record
+     */
+    pub fn record(&mut self, document: &[u8]) -> Vec<u8> {
+        self.update(document);
+        let mut record = Vec::with_capacity(document.len() + 4);
+        record.extend_from_slice(&(document.len() as u32).to_le_bytes());
+        record.extend_from_slice(document);
+        record
+    }
+
+    /**
+     * Encode the footer that ends a backup
+     *
+     * @return - the end marker, record count, and checksum
+     */
+    pub fn footer(self) -> Vec<u8> {
+        let mut footer = Vec::with_capacity(44);
+        footer.extend_from_slice(&0u32.to_le_bytes());
+        footer.extend_from_slice(&self.count.to_le_bytes());
+        footer.extend_from_slice(&self.hasher.finalize());
+        footer
+    }
+
+    /**
+     * Check the records read from a backup against the count and checksum in its footer
+     *
+     * @param count - the record count read from the footer
+     * @param checksum - the checksum read from the footer
+     * @return - the number of records, or InvalidBackup if either does not match
+     */
+    pub fn verify(self, count: u64, checksum: &[u8; 32]) -> Result<u64, GrapevineError> {
+        if self.count != count {
+            return Err(GrapevineError::InvalidBackup(format!(
+                "footer lists {} proofs but {} were read",
+                count, self.count
+            )));
+        }
+        if self.hasher.finalize().as_slice() != checksum {
+            return Err(GrapevineError::InvalidBackup(String::from(
+                "checksum does not match",
+            )));
+        }
+        Ok(count)
+    }
+}
+
+impl Default for BackupDigest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+ * Read a proof backup record by record, passing each document to a callback, then check the footer
+ * @notice documents are handed over as they are read, so an invalid footer is only detected after
+ *         every record has been processed
+ *
+ * @param reader - the backup to read
+ * @param on_record - called with the serialized bson document of each record
+ * @return - the number of records, or InvalidBackup if the backup is malformed or truncated
+ */
+pub fn read_backup<R: Read, F: FnMut(Vec<u8>) -> Result<(), GrapevineError>>(
+    mut reader: R,
+    mut on_record: F,
+) -> Result<u64, GrapevineError> {
+    let truncated = |_| GrapevineError::InvalidBackup(String::from("backup is truncated"));
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic).map_err(truncated)?;
+    if magic != BACKUP_MAGIC {
+        return Err(GrapevineError::InvalidBackup(String::from(
+            "not a proof backup",
+        )));
+    }
+    let mut digest = BackupDigest::new();
+    loop {
+        let mut length = [0u8; 4];
+        reader.read_exact(&mut length).map_err(truncated)?;
+        let length = u32::from_le_bytes(length);
+        if length == 0 {
+            let mut count = [0u8; 8];
+            let mut checksum = [0u8; 32];
+            reader.read_exact(&mut count).map_err(truncated)?;
+            reader.read_exact(&mut checksum).map_err(truncated)?;
+            return digest.verify(u64::from_le_bytes(count), &checksum);
+        }
+        if length > MAX_BACKUP_RECORD {
+            return Err(GrapevineError::InvalidBackup(format!(
+                "record of {} bytes is too large",
+                length
+            )));
+        }
+        let mut document = vec![0u8; length as usize];
+        reader.read_exact(&mut document).map_err(truncated)?;
+        digest.update(&document);
+        on_record(document)?;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn backup(documents: &[&[u8]]) -> Vec<u8> {
+        let mut digest = BackupDigest::new();
+        let mut bytes = BACKUP_MAGIC.to_vec();
+        for document in documents {
+            bytes.extend(digest.record(document));
+        }
+        bytes.extend(digest.footer());
+        bytes
+    }
+
+    #[test]
+    fn test_backup_round_trip() {
+        let documents: [&[u8]; 3] = [b"first", b"second", b"third"];
+        let bytes = backup(&documents);
+        let mut read = vec![];
+        let count = read_backup(&bytes[..], |document| {
+            read.push(document);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(read, documents.map(|document| document.to_vec()));
+    }
+
+    #[test]
+    fn test_backup_detects_corruption() {
+        let bytes = backup(&[b"first", b"second"]);
+
+        // a flipped byte in a record fails the checksum
+        let mut corrupted = bytes.clone();
+        corrupted[14] ^= 1;
+        assert!(matches!(
+            read_backup(&corrupted[..], |_| Ok(())),
+            Err(GrapevineError::InvalidBackup(_))
+        ));
+
+        // a backup cut off before the footer is truncated
+        assert!(matches!(
+            read_backup(&bytes[..bytes.len() - 40], |_| Ok(())),
+            Err(GrapevineError::InvalidBackup(_))
+        ));
+    }
+}
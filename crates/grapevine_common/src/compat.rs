@@ -42,9 +42,19 @@ pub fn ff_ce_to_le_bytes(el: &Fr_ff_ce) -> [u8; 32] {
  * @return - ff_ce Fr element
  */
 pub fn ff_ce_from_le_bytes(bytes: [u8; 32]) -> Fr_ff_ce {
+    try_ff_ce_from_le_bytes(bytes).unwrap()
+}
+
+/**
+ * Wraps little endian byte representation of Fr in ff_ce Fr element if it is in the field
+ *
+ * @param bytes - the little endian bytes to convert to Fr element
+ * @return - ff_ce Fr element, or None if the bytes are not a canonical field element
+ */
+pub fn try_ff_ce_from_le_bytes(bytes: [u8; 32]) -> Option<Fr_ff_ce> {
     let mut repr = FrRepr::default();
-    repr.read_le(&bytes[..]).unwrap();
-    Fr_ff_ce::from_repr(repr).unwrap()
+    repr.read_le(&bytes[..]).ok()?;
+    Fr_ff_ce::from_repr(repr).ok()
 }
 
 pub fn convert_ff_to_ff_ce(el: &Fr_ff) -> Fr_ff_ce {
@@ -0,0 +1,212 @@
+use crate::account::GrapevineAccount;
+use crate::crypto::decompress_pubkey;
+use crate::errors::GrapevineError;
+use babyjubjub_rs::{decompress_signature, verify};
+use num_bigint::{BigInt, Sign};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+pub const CREDENTIAL_VERSION: u8 = 1; // version of credentials written by this build
+
+/**
+ * A degree proof packaged as a bearer credential to be handed to a verifier outside of the server
+ * @notice only the degree and phrase hash are bound by the proof. The holder and origin are
+ *         unverified claims: the signature only shows the credential was signed by the pubkey it
+ *         names, and anyone with a copy of the proof (e.g. a relationship that downloaded it) can
+ *         wrap it in a credential naming and signed by themselves
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Credential {
+    pub version: u8,
+    pub holder: String,      // claimed holder of the proof, not bound by the proof
+    pub pubkey: String,      // hex compressed pubkey that signs the credential
+    pub origin: String,      // claimed origin of the phrase, not bound by the proof
+    pub degree: u8,          // degree of separation proven
+    pub phrase_hash: String, // hex phrase hash output by the proof
+    pub params_hash: String, // fingerprint of the public params the proof verifies against
+    pub proof: String,       // hex gzip-compressed proof
+    pub signature: String,   // hex compressed signature by `pubkey` over the credential hash
+}
+
+impl Credential {
+    /**
+     * Package a degree proof into a credential signed by an account
+     *
+     * @param account - the account claiming to hold the proof
+     * @param origin - the username of the origin of the phrase
+     * @param degree - the degree of separation proven
+     * @param phrase_hash - the phrase hash output by the proof
+     * @param params_hash - the artifact hash of the public params the proof verifies against
+     * @param proof - the gzip-compressed proof
+     * @returns - the signed credential
+     */
+    pub fn new(
+        account: &GrapevineAccount,
+        origin: String,
+        degree: u8,
+        phrase_hash: &[u8; 32],
+        params_hash: String,
+        proof: &[u8],
+    ) -> Credential {
+        let mut credential = Credential {
+            version: CREDENTIAL_VERSION,
+            holder: account.username().clone(),
+            pubkey: hex::encode(account.pubkey().compress()),
+            origin,
+            degree,
+            phrase_hash: hex::encode(phrase_hash),
+            params_hash,
+            proof: hex::encode(proof),
+            signature: String::new(),
+        };
+        credential.signature = hex::encode(account.sign_credential(&credential).compress());
+        credential
+    }
+
+    /**
+     * Computes the sha256 hash H |"grapevine/credential", every field but the signature| with
+     * last byte zeroed, which the holder signs
+     *
+     * @return - the hash of the credential
+     */
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = Sha3_256::new();
+        hasher.update(b"grapevine/credential");
+        hasher.update([self.version, self.degree]);
+        // length prefix each string so fields cannot be shifted into each other
+        let fields = [
+            &self.holder,
+            &self.pubkey,
+            &self.origin,
+            &self.phrase_hash,
+            &self.params_hash,
+            &self.proof,
+        ];
+        for field in fields {
+            hasher.update((field.len() as u64).to_le_bytes());
+            hasher.update(field.as_bytes());
+        }
+        let mut hash: [u8; 32] = hasher.finalize().into();
+        // 0 the last byte to ensure it always falls within the prime field Fr
+        hash[31] = 0;
+
+        hash
+    }
+
+    /**
+     * Check that the credential was signed by the pubkey it names
+     *
+     * @return - Ok if the signature is valid, or InvalidCredential/ InvalidPubkey otherwise
+     */
+    pub fn verify_signature(&self) -> Result<(), GrapevineError> {
+        let pubkey = decompress_pubkey(&decode_hex(&self.pubkey, "pubkey")?)?;
+        let signature = decompress_signature(&decode_hex(&self.signature, "signature")?)
+            .map_err(|_| GrapevineError::InvalidCredential(String::from("Malformed signature")))?;
+        let message = BigInt::from_bytes_le(Sign::Plus, &self.hash()[..]);
+        match verify(pubkey, signature, message) {
+            true => Ok(()),
+            false => Err(GrapevineError::InvalidCredential(String::from(
+                "Signature does not match the credential",
+            ))),
+        }
+    }
+
+    /**
+     * @return - the phrase hash claimed by the credential
+     */
+    pub fn phrase_hash_bytes(&self) -> Result<[u8; 32], GrapevineError> {
+        decode_hex(&self.phrase_hash, "phrase hash")
+    }
+
+    /**
+     * @return - the gzip-compressed proof carried by the credential
+     */
+    pub fn proof_bytes(&self) -> Result<Vec<u8>, GrapevineError> {
+        hex::decode(&self.proof)
+            .map_err(|_| GrapevineError::InvalidCredential(String::from("Malformed proof")))
+    }
+}
+
+/**
+ * Decode a fixed length hex field of a credential
+ *
+ * @param field - the hex string
+ * @param name - the name of the field, for the error message
+ * @return - the decoded bytes, or InvalidCredential if the field is not hex of the right length
+ */
+fn decode_hex<const N: usize>(field: &String, name: &str) -> Result<[u8; N], GrapevineError> {
+    hex::decode(field)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| GrapevineError::InvalidCredential(format!("Malformed {}", name)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample_credential(account: &GrapevineAccount) -> Credential {
+        let proof = vec![7u8; 64];
+        Credential::new(
+            account,
+            String::from("origin"),
+            2,
+            &[1u8; 32],
+            String::from("params"),
+            &proof,
+        )
+    }
+
+    #[test]
+    fn test_credential_signature() {
+        let account = GrapevineAccount::new(String::from("holder"));
+        let credential = sample_credential(&account);
+        credential.verify_signature().unwrap();
+        // the credential survives being written to and read from json
+        let json = serde_json::to_string(&credential).unwrap();
+        let parsed = serde_json::from_str::<Credential>(&json).unwrap();
+        assert_eq!(parsed, credential);
+        parsed.verify_signature().unwrap();
+        assert_eq!(parsed.phrase_hash_bytes().unwrap(), [1u8; 32]);
+        assert_eq!(parsed.proof_bytes().unwrap(), vec![7u8; 64]);
+    }
+
+    #[test]
+    fn test_credential_tampering_detected() {
+        let account = GrapevineAccount::new(String::from("holder"));
+        let credential = sample_credential(&account);
+        let tampered = [
+            Credential {
+                degree: 1,
+                ..credential.clone()
+            },
+            Credential {
+                origin: String::from("someone else"),
+                ..credential.clone()
+            },
+            Credential {
+                holder: String::from("impostor"),
+                ..credential.clone()
+            },
+        ];
+        for tampered in tampered {
+            assert!(matches!(
+                tampered.verify_signature(),
+                Err(GrapevineError::InvalidCredential(_))
+            ));
+        }
+        // re-signing with another account does not match the named pubkey
+        let other = GrapevineAccount::new(String::from("impostor"));
+        let forged = Credential {
+            signature: sample_credential(&other).signature,
+            ..credential.clone()
+        };
+        assert!(forged.verify_signature().is_err());
+        // malformed fields are rejected instead of panicking
+        let malformed = Credential {
+            pubkey: String::from("zz"),
+            ..credential
+        };
+        assert!(malformed.verify_signature().is_err());
+    }
+}
@@ -1,9 +1,24 @@
-use crate::{compat::{ff_ce_from_le_bytes, ff_ce_to_le_bytes}, utils::{convert_phrase_to_fr, convert_username_to_fr}};
-use babyjubjub_rs::{Point, PrivateKey};
-use num_bigint::{RandBigInt, ToBigInt};
+use crate::{
+    compat::{
+        convert_ff_to_ff_ce, ff_ce_from_le_bytes, ff_ce_to_le_bytes, try_ff_ce_from_le_bytes,
+    },
+    errors::GrapevineError,
+    utils::{convert_phrase_to_fr, convert_username_to_fr, random_fr},
+    Fr, MAX_SECRET_CHARS, MERKLE_DEPTH,
+};
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use aes::Aes128;
+use aes_gcm_siv::aead::{generic_array::GenericArray, AeadInPlace, KeyInit};
+use aes_gcm_siv::Aes256GcmSiv;
+use babyjubjub_rs::{Fr as Fr_ff_ce, Point, PrivateKey};
+use ff::PrimeField;
+use num_bigint::{BigInt, RandBigInt, ToBigInt};
+use rand::RngCore;
 use sha256::digest;
 use sha3::{Digest, Sha3_256};
 
+type Aes128CbcDec = cbc::Decryptor<Aes128>;
+
 /**
  * Computes an AES-CBC-128 Key from a Baby Jub Jub shared secret
  *
@@ -29,6 +44,33 @@ pub fn gen_aes_key(sk: PrivateKey, pk: Point) -> ([u8; 16], [u8; 16]) {
     (aes_key, aes_iv)
 }
 
+// order of the prime subgroup generated by the Baby Jub Jub base point (curve order / cofactor 8)
+const SUBGROUP_ORDER: &[u8] =
+    b"2736030358979909402780800718157159386076813972158567259200215660948447373041";
+
+/**
+ * Decompresses a Baby Jub Jub public key received from an untrusted source
+ * @notice rejects points that are not on the curve as well as points outside of the prime order
+ *         subgroup, which would otherwise let a small-order key pass signature checks
+ *
+ * @param bytes - the compressed public key
+ * @return - the decompressed public key, or an error if it is not a valid key
+ */
+pub fn decompress_pubkey(bytes: &[u8; 32]) -> Result<Point, GrapevineError> {
+    let point = babyjubjub_rs::decompress_point(*bytes)
+        .map_err(|e| GrapevineError::InvalidPubkey(format!("Not on curve: {}", e)))?;
+    // a point is in the subgroup iff multiplying it by the subgroup order gives the identity (0, 1)
+    let order = BigInt::parse_bytes(SUBGROUP_ORDER, 10).unwrap();
+    let mut identity = [0u8; 32];
+    identity[0] = 1;
+    if point.mul_scalar(&order).compress() != identity {
+        return Err(GrapevineError::InvalidPubkey(String::from(
+            "Not in the prime order subgroup",
+        )));
+    }
+    Ok(point)
+}
+
 /**
  * Generates a new private key as a 32 byte array
  *
@@ -42,13 +84,62 @@ pub fn new_private_key() -> [u8; 32] {
 }
 
 /**
- * Computes the sha256 hash H |username, nonce| with last byte zeroed
+ * Fingerprints a proving artifact (r1cs, wasm, public params) so mismatched versions can be caught
+ *
+ * @param artifact - the raw bytes of the artifact file
+ * @return - the hex-encoded sha256 hash of the artifact
+ */
+pub fn artifact_hash(artifact: &[u8]) -> String {
+    digest(artifact)
+}
+
+/**
+ * Computes the content hash of a compressed proof so identical resubmissions can be recognized
+ *
+ * @param proof - the gzip-compressed proof bytes as submitted
+ * @return - the sha256 hash of the compressed proof
+ */
+pub fn proof_content_hash(proof: &[u8]) -> [u8; 32] {
+    hex::decode(digest(proof)).unwrap().try_into().unwrap()
+}
+
+/**
+ * Canonicalizes a query string for signing, so reordering its parameters does not change the
+ * signed message
+ *
+ * @param query - the raw (still percent-encoded) query string, without the leading "?"
+ * @return - the non-empty "&" separated parameters in sorted order, joined by "&"
+ */
+pub fn canonical_query(query: Option<&str>) -> String {
+    let mut params = query
+        .unwrap_or_default()
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .collect::<Vec<&str>>();
+    params.sort_unstable();
+    params.join("&")
+}
+
+/**
+ * Computes the sha256 hash H |username, nonce, method, path, query, tenant| with last byte
+ * zeroed, binding a nonce signature to a single request
  *
  * @param username - the username to hash
  * @param nonce - the nonce to hash
- * @return - the sha256 hash of the username and nonce
+ * @param method - the http method of the request (case insensitive)
+ * @param path - the path of the request from the server root
+ * @param query - the raw query string of the request, if any (see `canonical_query`)
+ * @param tenant - the X-Tenant header of the request, if any
+ * @return - the sha256 hash of the username, nonce, and request
  */
-pub fn nonce_hash(username: &String, nonce: u64) -> [u8; 32] {
+pub fn request_hash(
+    username: &String,
+    nonce: u64,
+    method: &str,
+    path: &str,
+    query: Option<&str>,
+    tenant: Option<&str>,
+) -> [u8; 32] {
     let mut hasher = Sha3_256::new();
     // add username to hash buffer
     let username_bytes = convert_username_to_fr(username).unwrap();
@@ -56,6 +147,15 @@ pub fn nonce_hash(username: &String, nonce: u64) -> [u8; 32] {
     // add nonce to hash buffer
     let nonce_bytes = nonce.to_le_bytes();
     hasher.update(nonce_bytes);
+    // add the canonical request to hash buffer
+    let request = format!(
+        "{} {}?{} {}",
+        method.to_uppercase(),
+        path,
+        canonical_query(query),
+        tenant.unwrap_or_default()
+    );
+    hasher.update(request.as_bytes());
     // compute sha256 hash
     let mut hash: [u8; 32] = hasher.finalize().into();
     // 0 the last byte to ensure it always falls within the prime field Fr
@@ -65,16 +165,543 @@ pub fn nonce_hash(username: &String, nonce: u64) -> [u8; 32] {
 }
 
 /**
- * Computes the poseidon hash of a phrase
- * @TODO: FIX THIS HASH IT DOES NOT LINE UP WITH CIRCOM
- * 
+ * Computes the sha256 hash H |"grapevine/recover", username, pubkey, nonce| with last byte zeroed,
+ * which guardians sign to approve moving an account to a new pubkey
+ *
+ * @param username - the username of the account being recovered
+ * @param pubkey - the compressed pubkey the account is being moved to
+ * @param nonce - the current nonce of the account, so approvals cannot be replayed
+ * @return - the sha256 hash of the recovery
+ */
+pub fn recovery_hash(username: &String, pubkey: &[u8; 32], nonce: u64) -> [u8; 32] {
+    let mut hasher = Sha3_256::new();
+    hasher.update(b"grapevine/recover");
+    hasher.update(convert_username_to_fr(username).unwrap());
+    hasher.update(pubkey);
+    hasher.update(nonce.to_le_bytes());
+    let mut hash: [u8; 32] = hasher.finalize().into();
+    // 0 the last byte to ensure it always falls within the prime field Fr
+    hash[31] = 0;
+
+    hash
+}
+
+/**
+ * Computes the poseidon hash of a phrase exactly as the grapevine circuit does, so the phrase
+ * hash output by a proof can be reproduced locally
+ *
  * @param phrase - the phrase to hash
- * @return - the poseidon hash of the phrase
+ * @return - the little endian bytes of the poseidon hash of the phrase, or an error if the
+ *           phrase is not valid
  */
-pub fn phrase_hash(phrase: &String) -> [u8; 32] {
-    let bytes: Vec<poseidon_rs::Fr> = convert_phrase_to_fr(&phrase).unwrap().iter().map(|fr| ff_ce_from_le_bytes(*fr)).collect();
+pub fn hash_phrase(phrase: &String) -> Result<[u8; 32], GrapevineError> {
+    let inputs = convert_phrase_to_fr(phrase)?
+        .iter()
+        .map(|chunk| circuit_input(chunk))
+        .collect::<Vec<_>>();
+    let hash = poseidon_rs::Poseidon::new().hash(inputs).unwrap();
+    Ok(ff_ce_to_le_bytes(&hash))
+}
+
+/**
+ * Computes the auth hash output by a proof for its prover exactly as the grapevine circuit does,
+ * so the prover of a proof can be checked by anyone holding their username and auth secret
+ *
+ * @param phrase_hash - the little endian bytes of the phrase hash output by the proof
+ * @param username - the username of the prover
+ * @param auth_secret - the auth secret of the prover
+ * @return - the little endian bytes of the auth hash, or an error if the phrase hash is not a
+ *           field element or the username is too long
+ */
+pub fn auth_hash(
+    phrase_hash: &[u8; 32],
+    username: &String,
+    auth_secret: &Fr,
+) -> Result<[u8; 32], GrapevineError> {
+    let phrase_hash =
+        try_ff_ce_from_le_bytes(*phrase_hash).ok_or(GrapevineError::InvalidPhraseHash)?;
+    let username = circuit_input(&convert_username_to_fr(username)?);
+    let auth_secret = circuit_input(&auth_secret.to_repr());
+    let hash = poseidon_rs::Poseidon::new()
+        .hash(vec![phrase_hash, username, auth_secret])
+        .unwrap();
+    Ok(ff_ce_to_le_bytes(&hash))
+}
+
+/**
+ * Reads bytes the way the circuit reads the hex string of an input: as a big endian number
+ * reduced into the field
+ *
+ * @param bytes - the bytes hex encoded into the circuit input
+ * @return - the input as an ff_ce field element
+ */
+fn circuit_input(bytes: &[u8]) -> Fr_ff_ce {
+    let el = bytes.iter().fold(Fr::from(0), |acc, byte| {
+        acc * Fr::from(256) + Fr::from(*byte as u64)
+    });
+    convert_ff_to_ff_ce(&el)
+}
+
+/**
+ * Generates a random blinding factor for a phrase commitment
+ *
+ * @return - the little endian bytes of a random field element
+ */
+pub fn new_blinding() -> [u8; 32] {
+    random_fr().to_repr()
+}
 
-    let hasher = poseidon_rs::Poseidon::new();
-    let hash = hasher.hash(bytes).unwrap();
+/**
+ * Commits to a phrase without revealing it, so knowledge of the phrase can later be shown to
+ * predate the reveal
+ *
+ * @param phrase - the phrase to commit to
+ * @param blinding - the blinding factor from `new_blinding`, kept secret until the reveal
+ * @return - the commitment, or an error if the phrase or blinding factor is invalid
+ */
+pub fn commit_phrase(phrase: &String, blinding: &[u8; 32]) -> Result<[u8; 32], GrapevineError> {
+    commit_phrase_hash(&hash_phrase(phrase)?, blinding)
+}
+
+/**
+ * Computes a phrase commitment as the poseidon hash of the phrase hash (as output by the circuit)
+ * and a blinding factor, so a reveal can be checked without the phrase itself
+ *
+ * @param phrase_hash - the little endian bytes of the poseidon hash of the phrase
+ * @param blinding - the little endian bytes of the blinding factor
+ * @return - the commitment, or InvalidCommitment if either input is not a field element
+ */
+pub fn commit_phrase_hash(
+    phrase_hash: &[u8; 32],
+    blinding: &[u8; 32],
+) -> Result<[u8; 32], GrapevineError> {
+    let phrase_hash = try_ff_ce_from_le_bytes(*phrase_hash).ok_or(
+        GrapevineError::InvalidCommitment(String::from("phrase hash is not a field element")),
+    )?;
+    let blinding = try_ff_ce_from_le_bytes(*blinding).ok_or(GrapevineError::InvalidCommitment(
+        String::from("blinding factor is not a field element"),
+    ))?;
+    let hash = poseidon_rs::Poseidon::new()
+        .hash(vec![phrase_hash, blinding])
+        .unwrap();
+    Ok(ff_ce_to_le_bytes(&hash))
+}
+
+/**
+ * Computes the leaf of a user in the merkle tree of users as the poseidon hash of their username
+ *
+ * @param username - the username of the user
+ * @return - the little endian bytes of the leaf, or an error if the username is too long
+ */
+pub fn merkle_leaf(username: &String) -> Result<[u8; 32], GrapevineError> {
+    let username = ff_ce_from_le_bytes(convert_username_to_fr(username)?);
+    let hash = poseidon_rs::Poseidon::new().hash(vec![username]).unwrap();
+    Ok(ff_ce_to_le_bytes(&hash))
+}
+
+/**
+ * Computes the parent of two nodes in the merkle tree of users with poseidon, as the circuit will
+ * @notice panics if either node is not a field element, which never happens for nodes built from
+ *         merkle_leaf
+ *
+ * @param left - the little endian bytes of the left child
+ * @param right - the little endian bytes of the right child
+ * @return - the little endian bytes of the parent
+ */
+pub fn merkle_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let inputs = vec![ff_ce_from_le_bytes(*left), ff_ce_from_le_bytes(*right)];
+    let hash = poseidon_rs::Poseidon::new().hash(inputs).unwrap();
     ff_ce_to_le_bytes(&hash)
-}
\ No newline at end of file
+}
+
+/**
+ * Computes the root of an empty subtree at each level of the merkle tree of users
+ *
+ * @return - MERKLE_DEPTH + 1 hashes, where the hash at index i is the root of an empty subtree of
+ *           height i (index 0 is the empty leaf)
+ */
+pub fn merkle_zero_hashes() -> Vec<[u8; 32]> {
+    let mut zeros = vec![[0u8; 32]];
+    for level in 0..MERKLE_DEPTH {
+        zeros.push(merkle_hash(&zeros[level], &zeros[level]));
+    }
+    zeros
+}
+
+/**
+ * Computes the root of the merkle tree of users from its leaves, padding with empty leaves
+ *
+ * @param leaves - the leaves in insertion order (at most 2^MERKLE_DEPTH)
+ * @return - the merkle root
+ */
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let zeros = merkle_zero_hashes();
+    let mut layer = leaves.to_vec();
+    for level in 0..MERKLE_DEPTH {
+        layer = layer
+            .chunks(2)
+            .map(|pair| merkle_hash(&pair[0], pair.get(1).unwrap_or(&zeros[level])))
+            .collect();
+    }
+    layer.first().copied().unwrap_or(zeros[MERKLE_DEPTH])
+}
+
+/**
+ * Checks that a leaf is included in the merkle tree of users with a given root
+ *
+ * @param leaf - the leaf to check
+ * @param index - the position of the leaf in the tree
+ * @param siblings - the sibling of each node on the path from the leaf to the root
+ * @param root - the merkle root to check against
+ * @return - true if the path hashes to the root, false otherwise (including malformed paths)
+ */
+pub fn verify_merkle_proof(
+    leaf: &[u8; 32],
+    index: u32,
+    siblings: &[[u8; 32]],
+    root: &[u8; 32],
+) -> bool {
+    // reject paths of the wrong length or containing bytes that are not field elements
+    if siblings.len() != MERKLE_DEPTH
+        || std::iter::once(leaf)
+            .chain(siblings.iter())
+            .any(|node| try_ff_ce_from_le_bytes(*node).is_none())
+    {
+        return false;
+    }
+    let mut node = *leaf;
+    let mut index = index;
+    for sibling in siblings {
+        node = match index & 1 {
+            0 => merkle_hash(&node, sibling),
+            _ => merkle_hash(sibling, &node),
+        };
+        index >>= 1;
+    }
+    index == 0 && node == *root
+}
+
+/** Length of the random nonce at the start of a phrase ciphertext */
+pub const PHRASE_NONCE_LENGTH: usize = 12;
+
+/** Length of the aes-gcm-siv tag at the end of a phrase ciphertext */
+pub const PHRASE_TAG_LENGTH: usize = 16;
+
+/** Length of the zero padded phrase in the legacy aes-128-cbc format */
+const LEGACY_PHRASE_LENGTH: usize = 180;
+
+// the nonce, padded phrase and tag must fill the ciphertext exactly
+const _: () = assert!(PHRASE_NONCE_LENGTH + MAX_SECRET_CHARS + PHRASE_TAG_LENGTH == 192);
+
+/**
+ * Derives a subkey for one use of a phrase encryption key
+ *
+ * @param key - the phrase encryption key
+ * @param label - domain separator for the use of the subkey
+ * @return - the sha3 hash of the label and key
+ */
+fn phrase_subkey(key: &[u8; 32], label: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha3_256::new();
+    hasher.update(label);
+    hasher.update(key);
+    hasher.finalize().into()
+}
+
+/**
+ * Builds the aes-256-gcm-siv cipher for a phrase encryption key
+ *
+ * @param key - the phrase encryption key
+ * @return - the cipher keyed with a subkey of the phrase key
+ */
+fn phrase_cipher(key: &[u8; 32]) -> Aes256GcmSiv {
+    let aead_key = phrase_subkey(key, b"grapevine-phrase-aead");
+    Aes256GcmSiv::new(GenericArray::from_slice(&aead_key))
+}
+
+/**
+ * Encrypts a phrase with authenticated encryption so it can be stored by a third party
+ *
+ * Ciphertext layout (192 bytes):
+ *  - [0..12] random nonce
+ *  - [12..176] the phrase zero padded to MAX_SECRET_CHARS bytes, encrypted with aes-256-gcm-siv
+ *  - [176..192] the aes-gcm-siv tag
+ *
+ * The nonce is random, so encrypting the same phrase twice gives different ciphertexts.
+ *
+ * @param key - the 32 byte phrase encryption key
+ * @param plaintext - the phrase to encrypt
+ * @return - the encrypted phrase, or PhraseTooLong if it is over MAX_SECRET_CHARS bytes
+ */
+pub fn encrypt_phrase(key: &[u8; 32], plaintext: &String) -> Result<[u8; 192], GrapevineError> {
+    let bytes = plaintext.as_bytes();
+    if bytes.len() > MAX_SECRET_CHARS {
+        return Err(GrapevineError::PhraseTooLong);
+    }
+    // zero pad the phrase
+    let mut padded = [0u8; MAX_SECRET_CHARS];
+    padded[..bytes.len()].copy_from_slice(bytes);
+    // encrypt under a fresh nonce
+    let mut nonce = [0u8; PHRASE_NONCE_LENGTH];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let tag = phrase_cipher(key)
+        .encrypt_in_place_detached(GenericArray::from_slice(&nonce), b"", &mut padded)
+        .map_err(|_| GrapevineError::InternalError)?;
+    // serialize as | nonce | ciphertext | tag |
+    let mut ciphertext = [0u8; 192];
+    ciphertext[..PHRASE_NONCE_LENGTH].copy_from_slice(&nonce);
+    ciphertext[PHRASE_NONCE_LENGTH..192 - PHRASE_TAG_LENGTH].copy_from_slice(&padded);
+    ciphertext[192 - PHRASE_TAG_LENGTH..].copy_from_slice(&tag);
+    Ok(ciphertext)
+}
+
+/**
+ * Decrypts a phrase encrypted with `encrypt_phrase`, checking it has not been tampered with
+ *
+ * @param key - the 32 byte phrase encryption key
+ * @param ciphertext - the encrypted phrase
+ * @return - the phrase, or PhraseDecryptionFailed if the key is wrong or the ciphertext was
+ *           modified
+ */
+pub fn decrypt_phrase(key: &[u8; 32], ciphertext: &[u8; 192]) -> Result<String, GrapevineError> {
+    let nonce = &ciphertext[..PHRASE_NONCE_LENGTH];
+    let tag = &ciphertext[192 - PHRASE_TAG_LENGTH..];
+    let mut padded: [u8; MAX_SECRET_CHARS] = ciphertext
+        [PHRASE_NONCE_LENGTH..192 - PHRASE_TAG_LENGTH]
+        .try_into()
+        .unwrap();
+    phrase_cipher(key)
+        .decrypt_in_place_detached(
+            GenericArray::from_slice(nonce),
+            b"",
+            &mut padded,
+            GenericArray::from_slice(tag),
+        )
+        .map_err(|_| GrapevineError::PhraseDecryptionFailed)?;
+    // strip the zero padding
+    let end = padded.iter().position(|&r| r == 0).unwrap_or(padded.len());
+    String::from_utf8(padded[..end].to_vec()).map_err(|_| GrapevineError::PhraseDecryptionFailed)
+}
+
+/**
+ * Decrypts a phrase encrypted with the aes-128-cbc scheme used before `encrypt_phrase`
+ * @notice - kept so phrases stored before the switch can still be read and re-encrypted
+ *
+ * @param key - the 32 byte phrase encryption key (the aes key followed by the aes iv)
+ * @param ciphertext - the zero padded phrase encrypted with aes-128-cbc and pkcs7 padding
+ * @return - the phrase, or PhraseDecryptionFailed if the key is wrong or the padding is invalid
+ */
+pub fn decrypt_legacy_phrase(
+    key: &[u8; 32],
+    ciphertext: &[u8; 192],
+) -> Result<String, GrapevineError> {
+    let mut buf = *ciphertext;
+    let padded = Aes128CbcDec::new(key[..16].into(), key[16..].into())
+        .decrypt_padded_mut::<Pkcs7>(&mut buf)
+        .map_err(|_| GrapevineError::PhraseDecryptionFailed)?;
+    // the phrase was always zero padded to LEGACY_PHRASE_LENGTH bytes before encryption
+    if padded.len() != LEGACY_PHRASE_LENGTH {
+        return Err(GrapevineError::PhraseDecryptionFailed);
+    }
+    // strip the zero padding
+    let end = padded.iter().position(|&r| r == 0).unwrap_or(padded.len());
+    String::from_utf8(padded[..end].to_vec()).map_err(|_| GrapevineError::PhraseDecryptionFailed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_phrase_encryption_round_trip() {
+        let key = [3u8; 32];
+        let phrase = String::from("A phrase stored by an integrator");
+        let ciphertext = encrypt_phrase(&key, &phrase).unwrap();
+        assert_eq!(decrypt_phrase(&key, &ciphertext).unwrap(), phrase);
+        // the phrase is not readable in the ciphertext
+        assert!(!ciphertext
+            .windows(phrase.len())
+            .any(|window| window == phrase.as_bytes()));
+        // a phrase using every byte decrypts too
+        let phrase = "a".repeat(MAX_SECRET_CHARS);
+        let ciphertext = encrypt_phrase(&key, &phrase).unwrap();
+        assert_eq!(decrypt_phrase(&key, &ciphertext).unwrap(), phrase);
+        // the nonce is random, so the same phrase encrypts differently each time
+        let again = encrypt_phrase(&key, &phrase).unwrap();
+        assert_ne!(again, ciphertext);
+        assert_eq!(decrypt_phrase(&key, &again).unwrap(), phrase);
+    }
+
+    #[test]
+    fn test_phrase_encryption_rejects_long_phrases() {
+        let key = [3u8; 32];
+        let phrase = "a".repeat(MAX_SECRET_CHARS + 1);
+        assert!(matches!(
+            encrypt_phrase(&key, &phrase),
+            Err(GrapevineError::PhraseTooLong)
+        ));
+        // a multi byte character crossing the limit is rejected rather than split
+        let phrase = format!("{}é", "a".repeat(MAX_SECRET_CHARS - 1));
+        assert!(matches!(
+            encrypt_phrase(&key, &phrase),
+            Err(GrapevineError::PhraseTooLong)
+        ));
+    }
+
+    #[test]
+    fn test_phrase_decryption_detects_tampering() {
+        let key = [3u8; 32];
+        let ciphertext = encrypt_phrase(&key, &String::from("Do not tamper")).unwrap();
+        // flipping a byte in the nonce, the body or the tag is detected
+        for index in [0, PHRASE_NONCE_LENGTH, 192 - PHRASE_TAG_LENGTH, 191] {
+            let mut tampered = ciphertext;
+            tampered[index] ^= 1;
+            assert!(matches!(
+                decrypt_phrase(&key, &tampered),
+                Err(GrapevineError::PhraseDecryptionFailed)
+            ));
+        }
+        // the wrong key is detected
+        assert!(matches!(
+            decrypt_phrase(&[4u8; 32], &ciphertext),
+            Err(GrapevineError::PhraseDecryptionFailed)
+        ));
+    }
+
+    #[test]
+    fn test_commit_phrase() {
+        let phrase = String::from("A phrase known before it was revealed");
+        let blinding = new_blinding();
+        let commitment = commit_phrase(&phrase, &blinding).unwrap();
+
+        // the commitment can be checked from the phrase hash alone
+        let phrase_hash = hash_phrase(&phrase).unwrap();
+        assert_eq!(
+            commit_phrase_hash(&phrase_hash, &blinding).unwrap(),
+            commitment
+        );
+
+        // a different blinding factor or phrase gives a different commitment
+        assert_ne!(commit_phrase(&phrase, &new_blinding()).unwrap(), commitment);
+        let other = String::from("A different phrase");
+        assert_ne!(commit_phrase(&other, &blinding).unwrap(), commitment);
+
+        // the blinding factor must be a field element
+        assert!(matches!(
+            commit_phrase(&phrase, &[255u8; 32]),
+            Err(GrapevineError::InvalidCommitment(_))
+        ));
+    }
+
+    #[test]
+    fn test_merkle_root() {
+        let zeros = merkle_zero_hashes();
+        assert_eq!(zeros.len(), MERKLE_DEPTH + 1);
+        // an empty tree has the empty subtree root
+        assert_eq!(merkle_root(&[]), zeros[MERKLE_DEPTH]);
+        // a single leaf is hashed up against empty subtrees
+        let alice = merkle_leaf(&String::from("alice")).unwrap();
+        let expected =
+            (0..MERKLE_DEPTH).fold(alice, |node, level| merkle_hash(&node, &zeros[level]));
+        assert_eq!(merkle_root(&[alice]), expected);
+        // leaf order matters
+        let bob = merkle_leaf(&String::from("bob")).unwrap();
+        assert_ne!(merkle_root(&[alice, bob]), merkle_root(&[bob, alice]));
+    }
+
+    #[test]
+    fn test_merkle_inclusion_path() {
+        let leaves = ["alice", "bob", "carol"]
+            .iter()
+            .map(|username| merkle_leaf(&String::from(*username)).unwrap())
+            .collect::<Vec<_>>();
+        let root = merkle_root(&leaves);
+        // build the path for carol (index 2) by hand
+        let zeros = merkle_zero_hashes();
+        let mut siblings = vec![zeros[0], merkle_hash(&leaves[0], &leaves[1])];
+        siblings.extend_from_slice(&zeros[2..MERKLE_DEPTH]);
+        assert!(verify_merkle_proof(&leaves[2], 2, &siblings, &root));
+        // the path does not prove another leaf or position
+        assert!(!verify_merkle_proof(&leaves[0], 2, &siblings, &root));
+        assert!(!verify_merkle_proof(&leaves[2], 3, &siblings, &root));
+        // malformed paths are rejected
+        assert!(!verify_merkle_proof(&leaves[2], 2, &siblings[1..], &root));
+        let mut bad = siblings.clone();
+        bad[0] = [0xff; 32];
+        assert!(!verify_merkle_proof(&leaves[2], 2, &bad, &root));
+    }
+
+    #[test]
+    fn test_request_hash_binds_request() {
+        let username = String::from("alice");
+        let hash = |method, path, query| request_hash(&username, 3, method, path, query, None);
+        let degrees = hash("GET", "/user/degrees", None);
+        assert_eq!(degrees, hash("get", "/user/degrees", None));
+        assert_eq!(degrees, hash("GET", "/user/degrees", Some("")));
+        assert_ne!(degrees, hash("POST", "/user/degrees", None));
+        assert_ne!(degrees, hash("GET", "/proof/degree", None));
+        assert_ne!(
+            degrees,
+            request_hash(&username, 4, "GET", "/user/degrees", None, None)
+        );
+        assert_eq!(degrees[31], 0);
+        // the query is signed, but not the order of its parameters
+        let filtered = hash("GET", "/user/degrees", Some("degree=2&limit=5"));
+        assert_ne!(degrees, filtered);
+        assert_ne!(
+            filtered,
+            hash("GET", "/user/degrees", Some("degree=3&limit=5"))
+        );
+        assert_eq!(
+            filtered,
+            hash("GET", "/user/degrees", Some("limit=5&degree=2"))
+        );
+        // as is the tenant the request is made under
+        assert_ne!(
+            degrees,
+            request_hash(&username, 3, "GET", "/user/degrees", None, Some("tenant_a"))
+        );
+    }
+
+    #[test]
+    fn test_recovery_hash_binds_recovery() {
+        let username = String::from("alice");
+        let hash = recovery_hash(&username, &[1u8; 32], 3);
+        assert_ne!(hash, recovery_hash(&String::from("bob"), &[1u8; 32], 3));
+        assert_ne!(hash, recovery_hash(&username, &[2u8; 32], 3));
+        assert_ne!(hash, recovery_hash(&username, &[1u8; 32], 4));
+        assert_eq!(hash[31], 0);
+    }
+
+    #[test]
+    fn test_decompress_pubkey() {
+        // a generated account key is accepted
+        let pubkey = crate::account::GrapevineAccount::new(String::from("alice")).pubkey();
+        let decompressed = decompress_pubkey(&pubkey.compress()).unwrap();
+        assert!(decompressed.equals(pubkey.clone()));
+
+        // a y coordinate with no matching x on the curve is rejected
+        let off_curve = (2u8..)
+            .map(|y| {
+                let mut bytes = [0u8; 32];
+                bytes[0] = y;
+                bytes
+            })
+            .find(|bytes| babyjubjub_rs::decompress_point(*bytes).is_err())
+            .unwrap();
+        assert!(matches!(
+            decompress_pubkey(&off_curve),
+            Err(GrapevineError::InvalidPubkey(_))
+        ));
+
+        // adding the order 2 point (0, -1) gives (-x, -y): on the curve but outside the subgroup
+        let (mut x, mut y) = (pubkey.x, pubkey.y);
+        ff_ce::Field::negate(&mut x);
+        ff_ce::Field::negate(&mut y);
+        let shifted = Point { x, y }.compress();
+        assert!(babyjubjub_rs::decompress_point(shifted).is_ok());
+        assert!(matches!(
+            decompress_pubkey(&shifted),
+            Err(GrapevineError::InvalidPubkey(_))
+        ));
+    }
+}
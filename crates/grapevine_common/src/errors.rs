@@ -7,9 +7,14 @@ pub enum GrapevineError {
     UserNotFound(String),
     UsernameTooLong(String),
     UsernameNotAscii(String),
+    UsernameInvalidCharacters(String),
+    UsernameEmpty,
     PubkeyExists(String),
+    InvalidPubkey(String),
     UserExists(String),
     PhraseTooLong,
+    PhraseInvalidCharacters,
+    PhraseEmpty,
     NoPendingRelationship(String, String),
     PendingRelationshipExists(String, String),
     ActiveRelationshipExists(String, String),
@@ -24,7 +29,39 @@ pub enum GrapevineError {
     SerdeError(String),
     DegreeProofExists,
     DegreeProofVerificationFailed,
-    FsError(String)
+    DegreeProofNotFound,
+    NotProofOwner,
+    NotPhraseOrigin,
+    RequestTimeout(String),
+    ArtifactMismatch,
+    UnsupportedVersion(u8, u8, u8),
+    UnknownTenant(String),
+    DelegatedProvingDisabled,
+    RetriesExhausted(String, u32, String),
+    ProofMetadataMismatch(String),
+    FsError(String),
+    PhraseDecryptionFailed,
+    ShuttingDown,
+    ProvingFailed(String),
+    InvalidTags(String),
+    InvalidVisibility(String),
+    InvalidGuardians(String),
+    RecoveryNotEnabled(String),
+    RecoveryThresholdNotMet(u8, u8),
+    InvalidKdfParams(String),
+    InvalidCircuitInput(String),
+    InvalidCommitment(String),
+    CommitmentNotFound,
+    CommitmentExists,
+    InvalidBackup(String),
+    InvalidCursor(String),
+    ProofChainBroken(u8, String),
+    PrecedingNotRelationship(String),
+    NonceReplayed(u64, u64),
+    NonceAhead(u64, u64),
+    QuotaExceeded(String, u64),
+    InvalidCredential(String),
+    UnexpectedResponse(u16, String),
 }
 
 impl std::fmt::Display for GrapevineError {
@@ -41,13 +78,25 @@ impl std::fmt::Display for GrapevineError {
             GrapevineError::UsernameNotAscii(msg) => {
                 write!(f, "Username {} is not ascii", msg)
             }
+            GrapevineError::UsernameInvalidCharacters(msg) => {
+                write!(
+                    f,
+                    "Username {} has invisible or unnormalized characters",
+                    msg
+                )
+            }
             GrapevineError::PubkeyExists(msg) => {
                 write!(f, "Pubkey {} already used by another account", msg)
             }
             GrapevineError::UserExists(msg) => {
                 write!(f, "User {} already exists with the supplied pubkey", msg)
             },
+            GrapevineError::UsernameEmpty => write!(f, "Username cannot be empty or whitespace"),
             GrapevineError::PhraseTooLong => write!(f, "Phrase is too long"),
+            GrapevineError::PhraseEmpty => write!(f, "Phrase cannot be empty or whitespace"),
+            GrapevineError::PhraseInvalidCharacters => {
+                write!(f, "Phrase cannot contain control characters")
+            }
             GrapevineError::PendingRelationshipExists(sender, recipient) => {
                 write!(
                     f,
@@ -77,6 +126,19 @@ impl std::fmt::Display for GrapevineError {
                 "Nonce mismatch: expected {}, got {}. Retry this call",
                 expected, actual
             ),
+            &GrapevineError::NonceReplayed(presented, current) => write!(
+                f,
+                "Nonce {} was already used (current nonce is {})",
+                presented, current
+            ),
+            &GrapevineError::NonceAhead(presented, current) => write!(
+                f,
+                "Nonce {} is ahead of the server (current nonce is {}). Sync and retry this call",
+                presented, current
+            ),
+            GrapevineError::QuotaExceeded(resource, limit) => {
+                write!(f, "Quota exceeded: at most {} {} allowed", limit, resource)
+            }
             GrapevineError::PhraseExists => {
                 write!(f, "This phrase has already added used by another account")
             }
@@ -95,9 +157,234 @@ impl std::fmt::Display for GrapevineError {
             GrapevineError::DegreeProofVerificationFailed => {
                 write!(f, "Failed to verify degree proof")
             },
+            GrapevineError::DegreeProofNotFound => write!(f, "Degree proof not found"),
+            GrapevineError::NotProofOwner => {
+                write!(f, "Degree proof was not created by this account")
+            }
+            GrapevineError::NotPhraseOrigin => {
+                write!(f, "Only the creator of a phrase can modify it")
+            }
+            GrapevineError::RequestTimeout(url) => write!(f, "Request to {} timed out", url),
+            GrapevineError::ArtifactMismatch => {
+                write!(f, "Public params do not match the grapevine circuit r1cs")
+            }
+            GrapevineError::UnsupportedVersion(version, min, max) => write!(
+                f,
+                "Request version {} is not supported (expected {} to {})",
+                version, min, max
+            ),
+            GrapevineError::UnknownTenant(tenant) => write!(f, "Unknown tenant {}", tenant),
+            GrapevineError::DelegatedProvingDisabled => {
+                write!(f, "User has not opted in to delegated proving")
+            }
+            GrapevineError::ProofMetadataMismatch(field) => {
+                write!(f, "Stored {} does not match the proof outputs", field)
+            }
+            GrapevineError::RetriesExhausted(url, attempts, msg) => {
+                write!(
+                    f,
+                    "Request to {} failed after {} attempts: {}",
+                    url, attempts, msg
+                )
+            }
             GrapevineError::FsError(msg) => write!(f, "Filesystem error: {}", msg),
+            GrapevineError::ShuttingDown => {
+                write!(f, "Server is shutting down and not accepting new proofs")
+            }
+            GrapevineError::ProvingFailed(msg) => write!(f, "Failed to generate proof: {}", msg),
+            GrapevineError::InvalidTags(msg) => write!(f, "Invalid phrase tags: {}", msg),
+            GrapevineError::InvalidVisibility(msg) => {
+                write!(f, "Invalid phrase visibility: {}", msg)
+            }
+            GrapevineError::InvalidGuardians(msg) => write!(f, "Invalid guardians: {}", msg),
+            GrapevineError::InvalidPubkey(msg) => write!(f, "Invalid pubkey: {}", msg),
+            GrapevineError::RecoveryNotEnabled(username) => {
+                write!(f, "User {} has not set up guardians for recovery", username)
+            }
+            GrapevineError::RecoveryThresholdNotMet(approvals, threshold) => {
+                write!(
+                    f,
+                    "Recovery needs {} guardian approvals but only {} were valid",
+                    threshold, approvals
+                )
+            }
+            GrapevineError::InvalidKdfParams(msg) => {
+                write!(f, "Invalid key derivation parameters: {}", msg)
+            }
+            GrapevineError::InvalidCommitment(msg) => {
+                write!(f, "Invalid phrase commitment: {}", msg)
+            }
+            GrapevineError::CommitmentNotFound => write!(f, "Phrase commitment not found"),
+            GrapevineError::CommitmentExists => {
+                write!(f, "This phrase commitment has already been recorded")
+            }
+            GrapevineError::InvalidCredential(msg) => write!(f, "Invalid credential: {}", msg),
+            GrapevineError::UnexpectedResponse(status, body) => {
+                write!(f, "Server responded with status {}: {}", status, body)
+            }
+            GrapevineError::InvalidCircuitInput(msg) => {
+                write!(f, "Invalid circuit input: {}", msg)
+            }
+            GrapevineError::InvalidBackup(msg) => write!(f, "Invalid proof backup: {}", msg),
+            GrapevineError::InvalidCursor(msg) => write!(f, "Invalid page cursor: {}", msg),
+            GrapevineError::ProofChainBroken(degree, msg) => {
+                write!(f, "Proof chain broken at degree {}: {}", degree, msg)
+            }
+            GrapevineError::PrecedingNotRelationship(oid) => {
+                write!(f, "Proof {} was not made by an active relationship", oid)
+            }
+            GrapevineError::PhraseDecryptionFailed => {
+                write!(
+                    f,
+                    "Phrase ciphertext is invalid or not encrypted with this key"
+                )
+            }
+        }
+    }
+}
+
+impl GrapevineError {
+    /**
+     * Get the http status code the server responds with when a request fails with this error
+     *
+     * @return - the http status code for the error
+     */
+    pub fn http_status(&self) -> u16 {
+        match self {
+            GrapevineError::UsernameTooLong(_)
+            | GrapevineError::UsernameNotAscii(_)
+            | GrapevineError::UsernameInvalidCharacters(_)
+            | GrapevineError::UsernameEmpty
+            | GrapevineError::PhraseTooLong
+            | GrapevineError::PhraseInvalidCharacters
+            | GrapevineError::PhraseEmpty
+            | GrapevineError::RelationshipSenderIsTarget
+            | GrapevineError::InvalidPhraseHash
+            | GrapevineError::HeaderError(_)
+            | GrapevineError::SerdeError(_)
+            | GrapevineError::DegreeProofVerificationFailed
+            | GrapevineError::UnsupportedVersion(_, _, _)
+            | GrapevineError::UnknownTenant(_)
+            | GrapevineError::InvalidTags(_)
+            | GrapevineError::InvalidVisibility(_)
+            | GrapevineError::InvalidGuardians(_)
+            | GrapevineError::InvalidPubkey(_)
+            | GrapevineError::InvalidCommitment(_)
+            | GrapevineError::InvalidBackup(_)
+            | GrapevineError::InvalidCursor(_)
+            | GrapevineError::ProofChainBroken(_, _) => 400,
+            GrapevineError::Signature(_)
+            | GrapevineError::NonceMismatch(_, _)
+            | GrapevineError::NonceReplayed(_, _)
+            | GrapevineError::NonceAhead(_, _)
+            | GrapevineError::RecoveryThresholdNotMet(_, _) => 401,
+            GrapevineError::NotProofOwner
+            | GrapevineError::NotPhraseOrigin
+            | GrapevineError::DelegatedProvingDisabled
+            | GrapevineError::RecoveryNotEnabled(_)
+            | GrapevineError::PrecedingNotRelationship(_)
+            | GrapevineError::QuotaExceeded(_, _) => 403,
+            GrapevineError::UserNotFound(_)
+            | GrapevineError::NoPendingRelationship(_, _)
+            | GrapevineError::PhraseNotFound
+            | GrapevineError::DegreeProofNotFound
+            | GrapevineError::CommitmentNotFound => 404,
+            GrapevineError::UsernameExists(_)
+            | GrapevineError::PubkeyExists(_)
+            | GrapevineError::UserExists(_)
+            | GrapevineError::PendingRelationshipExists(_, _)
+            | GrapevineError::ActiveRelationshipExists(_, _)
+            | GrapevineError::PhraseExists
+            | GrapevineError::DegreeProofExists
+            | GrapevineError::CommitmentExists => 409,
+            GrapevineError::MongoError(_)
+            | GrapevineError::InternalError
+            | GrapevineError::ProofMetadataMismatch(_) => 500,
+            GrapevineError::ShuttingDown => 503,
+            // client side errors that are never sent by the server
+            GrapevineError::RequestTimeout(_)
+            | GrapevineError::ArtifactMismatch
+            | GrapevineError::RetriesExhausted(_, _, _)
+            | GrapevineError::FsError(_)
+            | GrapevineError::PhraseDecryptionFailed
+            | GrapevineError::ProvingFailed(_)
+            | GrapevineError::InvalidKdfParams(_)
+            | GrapevineError::InvalidCircuitInput(_)
+            | GrapevineError::InvalidCredential(_)
+            | GrapevineError::UnexpectedResponse(_, _) => 500,
         }
     }
 }
 
 impl std::error::Error for GrapevineError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_http_status() {
+        let empty = String::new;
+        let cases = [
+            (GrapevineError::UsernameTooLong(empty()), 400),
+            (GrapevineError::UsernameNotAscii(empty()), 400),
+            (GrapevineError::UsernameInvalidCharacters(empty()), 400),
+            (GrapevineError::UsernameEmpty, 400),
+            (GrapevineError::PhraseTooLong, 400),
+            (GrapevineError::PhraseInvalidCharacters, 400),
+            (GrapevineError::PhraseEmpty, 400),
+            (GrapevineError::RelationshipSenderIsTarget, 400),
+            (GrapevineError::InvalidPhraseHash, 400),
+            (GrapevineError::HeaderError(empty()), 400),
+            (GrapevineError::SerdeError(empty()), 400),
+            (GrapevineError::DegreeProofVerificationFailed, 400),
+            (GrapevineError::UnsupportedVersion(0, 1, 1), 400),
+            (GrapevineError::UnknownTenant(empty()), 400),
+            (GrapevineError::InvalidTags(empty()), 400),
+            (GrapevineError::InvalidVisibility(empty()), 400),
+            (GrapevineError::InvalidGuardians(empty()), 400),
+            (GrapevineError::InvalidPubkey(empty()), 400),
+            (GrapevineError::InvalidCommitment(empty()), 400),
+            (GrapevineError::InvalidBackup(empty()), 400),
+            (GrapevineError::InvalidCursor(empty()), 400),
+            (GrapevineError::ProofChainBroken(2, empty()), 400),
+            (GrapevineError::Signature(empty()), 401),
+            (GrapevineError::NonceMismatch(1, 0), 401),
+            (GrapevineError::NonceReplayed(0, 1), 401),
+            (GrapevineError::NonceAhead(2, 1), 401),
+            (GrapevineError::RecoveryThresholdNotMet(1, 2), 401),
+            (GrapevineError::NotProofOwner, 403),
+            (GrapevineError::NotPhraseOrigin, 403),
+            (GrapevineError::DelegatedProvingDisabled, 403),
+            (GrapevineError::RecoveryNotEnabled(empty()), 403),
+            (GrapevineError::PrecedingNotRelationship(empty()), 403),
+            (GrapevineError::QuotaExceeded(empty(), 1), 403),
+            (GrapevineError::UserNotFound(empty()), 404),
+            (GrapevineError::NoPendingRelationship(empty(), empty()), 404),
+            (GrapevineError::PhraseNotFound, 404),
+            (GrapevineError::DegreeProofNotFound, 404),
+            (GrapevineError::CommitmentNotFound, 404),
+            (GrapevineError::UsernameExists(empty()), 409),
+            (GrapevineError::PubkeyExists(empty()), 409),
+            (GrapevineError::UserExists(empty()), 409),
+            (
+                GrapevineError::PendingRelationshipExists(empty(), empty()),
+                409,
+            ),
+            (
+                GrapevineError::ActiveRelationshipExists(empty(), empty()),
+                409,
+            ),
+            (GrapevineError::PhraseExists, 409),
+            (GrapevineError::DegreeProofExists, 409),
+            (GrapevineError::CommitmentExists, 409),
+            (GrapevineError::MongoError(empty()), 500),
+            (GrapevineError::InternalError, 500),
+            (GrapevineError::ProofMetadataMismatch(empty()), 500),
+            (GrapevineError::ShuttingDown, 503),
+        ];
+        for (error, status) in cases {
+            assert_eq!(error.http_status(), status, "Wrong status for {:?}", error);
+        }
+    }
+}
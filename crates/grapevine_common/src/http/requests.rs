@@ -1,4 +1,7 @@
-use serde::{Deserialize, Serialize};
+use crate::models::PhraseVisibility;
+use bincode::Options;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CreateUserRequest {
     pub username: String,
@@ -9,11 +12,36 @@ pub struct CreateUserRequest {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PhraseRequest {
+    pub version: u8, // must stay the first field so it can be read before deserializing
     #[serde(with = "serde_bytes")]
     pub proof: Vec<u8>, // compressed proof
     #[serde(with = "serde_bytes")]
     pub ciphertext: [u8; 192], // encrypted phrase
     pub description: String, // description (discarded if phrase already exists)
+    pub tags: Vec<String>, // tags (discarded if phrase already exists)
+}
+
+// PhraseRequest sent by clients before tags were added (request version 1)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhraseRequestV1 {
+    pub version: u8,
+    #[serde(with = "serde_bytes")]
+    pub proof: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    pub ciphertext: [u8; 192],
+    pub description: String,
+}
+
+impl From<PhraseRequestV1> for PhraseRequest {
+    fn from(request: PhraseRequestV1) -> Self {
+        PhraseRequest {
+            version: request.version,
+            proof: request.proof,
+            ciphertext: request.ciphertext,
+            description: request.description,
+            tags: vec![],
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -23,6 +51,26 @@ pub struct GetNonceRequest {
     pub signature: [u8; 64],
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GuardiansRequest {
+    pub guardians: Vec<String>, // active relationships that can approve recovery (empty disables)
+    pub threshold: u8,          // guardian approvals needed to recover (0 if no guardians)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GuardianApproval {
+    pub guardian: String,
+    #[serde(with = "serde_bytes")]
+    pub signature: [u8; 64], // guardian signature over recovery_hash(username, pubkey, nonce)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecoveryRequest {
+    pub username: String,
+    pub pubkey: [u8; 32], // the compressed pubkey to move the account to
+    pub approvals: Vec<GuardianApproval>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TestProofCompressionRequest {
     pub proof: Vec<u8>,
@@ -40,7 +88,121 @@ pub struct NewRelationshipRequest {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DegreeProofRequest {
+    pub version: u8, // must stay the first field so it can be read before deserializing
     pub proof: Vec<u8>,
     pub previous: String,
     pub degree: u8,
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DegreeProofBatchRequest {
+    pub version: u8, // must stay the first field so it can be read before deserializing
+    pub proofs: Vec<DegreeProofRequest>, // at most MAX_DEGREE_PROOF_BATCH proofs
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DelegatedDegreeProofRequest {
+    pub version: u8, // must stay the first field so it can be read before deserializing
+    pub previous: String,
+    pub auth_secrets: [[u8; 32]; 2], // [previous prover's auth secret, requester's auth secret]
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhraseDescriptionRequest {
+    pub description: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhraseTagsRequest {
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhraseVisibilityRequest {
+    pub visibility: PhraseVisibility,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhraseCiphertextRequest {
+    #[serde(with = "serde_bytes")]
+    pub ciphertext: [u8; 192], // the phrase encrypted under the user's current phrase key
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhraseCommitRequest {
+    pub commitment: [u8; 32], // see grapevine_common::crypto::commit_phrase
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhraseRevealRequest {
+    pub commitment: [u8; 32],
+    pub phrase_hash: [u8; 32], // the phrase itself is never sent
+    pub blinding: [u8; 32],
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VerifyInSetRequest {
+    pub proof: Vec<u8>,
+    pub degree: u8,
+    pub allowed: Vec<[u8; 32]>, // phrase hashes the proof may be made for
+}
+
+/**
+ * Deserialize a binary request body the same way as bincode::deserialize, but with a byte limit
+ * equal to the length of the body. A length prefix claiming more bytes than were sent fails
+ * before anything is allocated for it, so a crafted body cannot force a large allocation
+ *
+ * @param bytes - the request body
+ * @return - the deserialized request
+ */
+pub fn decode_request<T: DeserializeOwned>(bytes: &[u8]) -> bincode::Result<T> {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(bytes.len() as u64)
+        .deserialize(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_request_matches_bincode() {
+        let request = DegreeProofBatchRequest {
+            version: 2,
+            proofs: vec![DegreeProofRequest {
+                version: 2,
+                proof: vec![1, 2, 3],
+                previous: String::from("65f1c0ffee"),
+                degree: 2,
+            }],
+        };
+        let bytes = bincode::serialize(&request).unwrap();
+        let decoded = decode_request::<DegreeProofBatchRequest>(&bytes).unwrap();
+        assert_eq!(bincode::serialize(&decoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_decode_request_rejects_oversized_length_prefix() {
+        // a phrase request whose proof claims to be u64::MAX bytes long
+        let mut bytes = vec![2u8];
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        bytes.extend_from_slice(&[0u8; 16]);
+        assert!(decode_request::<PhraseRequest>(&bytes).is_err());
+
+        // a batch claiming more proofs than the body could hold
+        let mut bytes = vec![2u8];
+        bytes.extend_from_slice(&(u32::MAX as u64).to_le_bytes());
+        assert!(decode_request::<DegreeProofBatchRequest>(&bytes).is_err());
+
+        // a truncated body is rejected rather than read past
+        let request = VerifyInSetRequest {
+            proof: vec![7; 64],
+            degree: 1,
+            allowed: vec![[1; 32]],
+        };
+        let bytes = bincode::serialize(&request).unwrap();
+        assert!(decode_request::<VerifyInSetRequest>(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::errors::GrapevineError;
+use bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -13,8 +15,222 @@ pub struct DegreeData {
     pub secret_phrase: Option<[u8; 192]>
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhraseFullResponse {
+    pub phrase: DegreeData,
+    pub total_connections: u64,
+    pub connections_by_degree: Vec<u64>, // relationships connected at 1, 2, ... degrees
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhraseProofResponse {
+    pub proof: String,  // the stringified oid of the user's active proof of the phrase
+    pub degree: u8,     // the degree of separation proven
+    pub origin: String, // the username of the first user to prove knowledge of the phrase
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccountDetailsResponse {
+    pub phrase_count: u64,
+    pub first_degree_count: u64,
+    pub second_degree_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhraseSummary {
+    pub phrase_index: u32,
+    pub description: String,
+    pub degree: u8,
+    pub connections: u64, // active proofs on the phrase made by the user's relationships
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConnectedOrigin {
+    pub origin_username: String, // the creator of phrases the user has proofs on
+    pub min_degree: u8,          // the lowest degree of separation the user has from the origin
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhraseSearchResult {
+    pub phrase_index: u32,
+    pub description: String,
+    #[serde(with = "serde_bytes")]
+    pub phrase_hash: [u8; 32],
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PhraseCreationResponse {
     pub phrase_index: u32,
     pub new_phrase: bool,
-}
\ No newline at end of file
+    #[serde(default)]
+    pub proof: String, // stringified ObjectID of the stored (or previously stored) proof
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VerifyInSetResponse {
+    pub verified: bool,
+    pub degree: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DegreeProofBatchResult {
+    pub previous: String, // the OID of the proof the submitted proof was built from
+    pub status: u16,      // the http status the proof would have received if sent on its own
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProofMigrationResponse {
+    pub processed: u32,       // proofs read in this batch
+    pub migrated: u32,        // proofs rewritten because their serialization changed
+    pub failed: Vec<String>,  // OIDs of proofs that could not be decompressed or verified
+    pub last: Option<String>, // OID to resume after (none once every proof has been processed)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CircuitInfoResponse {
+    pub circuit_version: String, // version of the circuit the server verifies proofs against
+    pub r1cs_hash: String,       // sha256 hash of the r1cs artifact served by the server
+    pub params_hash: String,     // sha256 hash of the public params artifact served by the server
+    pub max_degree: u8,          // highest degree of separation the server accepts proofs for
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AvailableProofEvent {
+    pub proof: String,    // the stringified oid of the new proof that can be built from
+    pub username: String, // the relationship that made the proof
+    pub degree: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProofChild {
+    pub oid: String,      // the stringified oid of the proof built on the queried proof
+    pub username: String, // the user that made the proof
+    pub degree: u8,
+    pub inactive: bool, // whether the proof was flagged inactive (e.g. by a deactivation upstream)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LeaderboardEntry {
+    pub username: String,
+    pub score: u64, // the user's value for the requested leaderboard metric
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PhraseRevealResponse {
+    pub phrase_hash: [u8; 32],
+    pub committed_at: i64, // unix timestamp (milliseconds) the commitment was recorded
+    pub revealed_at: i64,  // unix timestamp (milliseconds) the commitment was first revealed
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MerkleRootResponse {
+    pub root: [u8; 32], // root of the merkle tree of users
+    pub size: u32,      // number of users in the tree
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MerkleProofResponse {
+    pub username: String,
+    pub index: u32,              // position of the user's leaf in the tree
+    pub leaf: [u8; 32],          // poseidon hash of the username
+    pub siblings: Vec<[u8; 32]>, // sibling of each node on the path from the leaf to the root
+    pub root: [u8; 32],          // root the path hashes to
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next: Option<String>, // cursor to request the following page with (none on the last page)
+}
+
+impl<T> Page<T> {
+    /**
+     * Build a page from a batch of items fetched with a limit one higher than the page size
+     * @dev the extra item only signals that another page follows, so a listing needs one query
+     *
+     * @param items - up to limit + 1 items, sorted by the ObjectID the cursor resumes after
+     * @param limit - the number of items in a full page
+     * @param oid - returns the ObjectID of an item
+     * @return - the first `limit` items and a cursor after the last of them if more remain
+     */
+    pub fn from_batch<F: Fn(&T) -> ObjectId>(mut items: Vec<T>, limit: usize, oid: F) -> Self {
+        if items.len() <= limit {
+            return Page { items, next: None };
+        }
+        items.truncate(limit);
+        let next = items.last().map(|item| encode_cursor(&oid(item)));
+        Page { items, next }
+    }
+}
+
+/**
+ * Encode the ObjectID of the last item on a page as an opaque cursor for the next page
+ *
+ * @param oid - the ObjectID of the last item returned
+ * @return - the cursor to send back to get the items after it
+ */
+pub fn encode_cursor(oid: &ObjectId) -> String {
+    hex::encode(oid.bytes())
+}
+
+/**
+ * Decode a cursor sent by a client back into the ObjectID to resume after
+ *
+ * @param cursor - the cursor returned as `next` by a previous page
+ * @return - the ObjectID to resume after, or InvalidCursor if the cursor is malformed
+ */
+pub fn decode_cursor(cursor: &str) -> Result<ObjectId, GrapevineError> {
+    let bytes: [u8; 12] = match hex::decode(cursor).map(|bytes| bytes.try_into()) {
+        Ok(Ok(bytes)) => bytes,
+        _ => return Err(GrapevineError::InvalidCursor(String::from(cursor))),
+    };
+    Ok(ObjectId::from_bytes(bytes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_account_details_deserialize() {
+        let json = r#"{"phrase_count":1,"first_degree_count":4,"second_degree_count":2}"#;
+        let details: AccountDetailsResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(details.phrase_count, 1);
+        assert_eq!(details.first_degree_count, 4);
+        assert_eq!(details.second_degree_count, 2);
+    }
+    #[test]
+    fn test_cursor_round_trip() {
+        let oid = ObjectId::new();
+        let cursor = encode_cursor(&oid);
+        assert_eq!(decode_cursor(&cursor).unwrap(), oid);
+        for cursor in ["", "not a cursor", &cursor[2..]] {
+            assert!(matches!(
+                decode_cursor(cursor),
+                Err(GrapevineError::InvalidCursor(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_page_from_batch() {
+        let oids: Vec<ObjectId> = (0..3).map(|_| ObjectId::new()).collect();
+
+        // a batch with more than a page of items resumes after the last item kept
+        let page = Page::from_batch(oids.clone(), 2, |oid| *oid);
+        assert_eq!(page.items, oids[..2]);
+        assert_eq!(decode_cursor(&page.next.unwrap()).unwrap(), oids[1]);
+
+        // a batch that fits on the page is the last page
+        let page = Page::from_batch(oids.clone(), 3, |oid| *oid);
+        assert_eq!(page.items, oids);
+        assert!(page.next.is_none());
+
+        // an empty final page has no cursor
+        let page = Page::from_batch(vec![], 2, |oid: &ObjectId| *oid);
+        assert!(page.items.is_empty());
+        assert!(page.next.is_none());
+    }
+}
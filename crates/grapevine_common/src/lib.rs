@@ -3,7 +3,9 @@ use nova_snark::{provider, traits::circuit::TrivialTestCircuit, PublicParams, Re
 
 pub mod account;
 pub mod auth_secret;
+pub mod backup;
 pub mod compat;
+pub mod credential;
 pub mod crypto;
 pub mod http;
 pub mod utils;
@@ -18,5 +20,12 @@ pub type Params = PublicParams<G1, G2, C1<G1>, C2<G2>>;
 pub type NovaProof = RecursiveSNARK<G1, G2, CircomCircuit<Fr>, TrivialTestCircuit<Fq>>;
 
 pub const SECRET_FIELD_LENGTH: usize = 6;
-pub const MAX_SECRET_CHARS: usize = 180;
+pub const MAX_SECRET_CHARS: usize = 164; // longest phrase in bytes that fits an encrypted phrase
 pub const MAX_USERNAME_CHARS: usize = 30;
+pub const REQUEST_VERSION: u8 = 2; // version of binary proof requests sent by this build
+pub const MIN_REQUEST_VERSION: u8 = 1; // oldest binary proof request version still accepted
+pub const MAX_DEGREE_PROOF_BATCH: usize = 16; // most degree proofs accepted in one batch request
+pub const MAX_PHRASE_TAGS: usize = 8; // most tags a phrase can be given
+pub const MAX_TAG_CHARS: usize = 32; // longest tag in bytes
+pub const MAX_GUARDIANS: usize = 8; // most guardians that can approve recovering an account
+pub const MERKLE_DEPTH: usize = 20; // levels in the merkle tree of users (up to 2^20 leaves)
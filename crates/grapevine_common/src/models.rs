@@ -16,6 +16,10 @@ pub struct DegreeProof {
     pub proof: Option<Vec<u8>>, // compressed proof
     pub preceding: Option<ObjectId>, // the proof that this proof is built on (null if first)
     pub proceeding: Option<Vec<ObjectId>>, // proofs that are built on top of this proof
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<bson::DateTime>, // when the proof stops being usable (never if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<[u8; 32]>, // sha256 of the compressed proof, unique per proof
 }
 
 // all data needed from server to prove a degree of separation
@@ -25,7 +29,7 @@ pub struct ProvingData {
     #[serde(default, with = "serde_bytes")]
     pub phrase_hash: [u8; 32],
     pub description: String,
-    pub degree: u8, // multiply by 2 to get iterations
+    pub degree: u8, // see grapevine_circuits::steps_for_degree for iterations
     pub proof: Vec<u8>,
     pub username: String,
     #[serde(with = "serde_bytes")]
@@ -58,6 +62,21 @@ pub struct User {
     pub pubkey: Option<[u8; 32]>,
     pub relationships: Option<Vec<ObjectId>>, // references to connections (includes reference to connected user + their auth secret)
     pub degree_proofs: Option<Vec<ObjectId>>, // references to degree proofs by this user
+    pub delegated_proving: Option<bool>, // whether the user allows the server to prove on their behalf
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guardians: Option<Vec<ObjectId>>, // relationships that can approve recovering the account
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_threshold: Option<u8>, // guardian approvals needed to recover the account
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<UserStats>, // cached account details (recomputed by an admin)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UserStats {
+    pub phrase_count: u64,
+    pub first_degree_count: u64,
+    pub second_degree_count: u64,
+    pub computed_at: bson::DateTime, // when the counts were last aggregated
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -67,4 +86,41 @@ pub struct Phrase {
     pub hash: Option<[u8; 32]>, // hash of phrase
     pub index: Option<u32>, // separate uid shown to user
     pub description: Option<String>, // text to be shown with the phrase
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>, // topics the phrase is listed under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<PhraseVisibility>, // who can see the phrase (public if not set)
+}
+
+/// Who can see that a phrase exists and read its description
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PhraseVisibility {
+    #[default]
+    Public,
+    Connections(u8), // only users within this many degrees of separation of the phrase
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PhraseCommitment {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user: Option<ObjectId>, // the user that committed to the phrase
+    pub commitment: Option<[u8; 32]>, // poseidon hash of the phrase hash and a blinding factor
+    pub committed_at: Option<bson::DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phrase_hash: Option<[u8; 32]>, // set once the commitment is revealed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revealed_at: Option<bson::DateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MerkleNode {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub level: Option<u32>, // height above the leaves (0 for leaves)
+    pub index: Option<u32>, // position within the level
+    pub hash: Option<[u8; 32]>, // poseidon hash of the children (or of the username for leaves)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>, // the user a leaf belongs to
 }
\ No newline at end of file
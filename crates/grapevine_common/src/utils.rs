@@ -1,5 +1,9 @@
-use crate::{Fr, MAX_SECRET_CHARS, MAX_USERNAME_CHARS, SECRET_FIELD_LENGTH};
-use std::error::Error;
+use crate::errors::GrapevineError;
+use crate::{
+    Fr, MAX_PHRASE_TAGS, MAX_SECRET_CHARS, MAX_TAG_CHARS, MAX_USERNAME_CHARS, SECRET_FIELD_LENGTH,
+};
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
 
 /**
  * Generates a new stringified random bn254 field element
@@ -10,6 +14,131 @@ pub fn random_fr() -> Fr {
     ff::Field::random(rand::rngs::OsRng)
 }
 
+/**
+ * Overwrites a field element with zero in a way the compiler cannot optimize away
+ * @dev Fr does not implement Zeroize, so this mirrors what the zeroize crate does for its types
+ *
+ * @param fr - the secret field element to wipe
+ */
+pub fn zeroize_fr(fr: &mut Fr) {
+    // a volatile write is never elided even if the value is not read again before being dropped
+    unsafe { std::ptr::write_volatile(fr, Fr::from(0)) };
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+/**
+ * Checks that a phrase can be packed into the circuit's secret input
+ * @dev the circuit packs utf8 bytes, so the limit applies to the byte length of the phrase
+ *
+ * @param phrase - the phrase to validate
+ * @return - PhraseEmpty if empty or only whitespace, PhraseTooLong if over MAX_SECRET_CHARS bytes,
+ *           PhraseInvalidCharacters if the phrase contains control characters (including the
+ *           null byte used as padding)
+ */
+pub fn validate_phrase(phrase: &str) -> Result<(), GrapevineError> {
+    if phrase.trim().is_empty() {
+        return Err(GrapevineError::PhraseEmpty);
+    }
+    if phrase.len() > MAX_SECRET_CHARS {
+        return Err(GrapevineError::PhraseTooLong);
+    }
+    if phrase.chars().any(|c| c.is_control()) {
+        return Err(GrapevineError::PhraseInvalidCharacters);
+    }
+    Ok(())
+}
+
+/**
+ * Normalizes a username to Unicode NFC so visually identical names have identical bytes
+ * @dev the circuit hashes the utf8 bytes of the username, so the normalized form must be used
+ *      everywhere the username is signed, hashed, or stored
+ *
+ * @param username - the username as entered
+ * @return - the NFC normalized username
+ */
+pub fn normalize_username(username: &str) -> String {
+    username.nfc().collect()
+}
+
+/**
+ * Checks that a username can be registered
+ *
+ * @param username - the (normalized) username to validate
+ * @param unicode - whether non-ascii usernames are allowed (strict ascii if false)
+ * @return - UsernameEmpty if empty or only whitespace, UsernameTooLong if over MAX_USERNAME_CHARS
+ *           bytes (or grapheme clusters), UsernameNotAscii if the username contains non-ascii
+ *           characters in strict mode, UsernameInvalidCharacters if the username is not NFC or
+ *           contains control, invisible, or direction-changing characters
+ */
+pub fn validate_username(username: &String, unicode: bool) -> Result<(), GrapevineError> {
+    if username.trim().is_empty() {
+        return Err(GrapevineError::UsernameEmpty);
+    }
+    // the circuit packs the utf8 bytes of the username into a single field element
+    if username.len() > MAX_USERNAME_CHARS {
+        return Err(GrapevineError::UsernameTooLong(username.clone()));
+    }
+    if !unicode {
+        if !username.is_ascii() {
+            return Err(GrapevineError::UsernameNotAscii(username.clone()));
+        }
+        return Ok(());
+    }
+    if username.graphemes(true).count() > MAX_USERNAME_CHARS {
+        return Err(GrapevineError::UsernameTooLong(username.clone()));
+    }
+    // characters that render as nothing (or reorder text) let two names look identical
+    let confusable = |c: char| {
+        c.is_control()
+            || matches!(c, '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}')
+            || matches!(c, '\u{2060}'..='\u{2069}' | '\u{FEFF}')
+    };
+    if !is_nfc(username) || username.chars().any(confusable) {
+        return Err(GrapevineError::UsernameInvalidCharacters(username.clone()));
+    }
+    Ok(())
+}
+
+/**
+ * Checks that a set of phrase tags can be stored
+ *
+ * @param tags - the tags to validate
+ * @return - InvalidTags if there are more than MAX_PHRASE_TAGS tags, or any tag is empty, over
+ *           MAX_TAG_CHARS bytes, contains whitespace or control characters, or is repeated
+ */
+pub fn validate_tags(tags: &[String]) -> Result<(), GrapevineError> {
+    if tags.len() > MAX_PHRASE_TAGS {
+        return Err(GrapevineError::InvalidTags(format!(
+            "at most {} tags allowed",
+            MAX_PHRASE_TAGS
+        )));
+    }
+    for (i, tag) in tags.iter().enumerate() {
+        if tag.is_empty() || tag.len() > MAX_TAG_CHARS {
+            return Err(GrapevineError::InvalidTags(format!(
+                "tags must be 1 to {} bytes",
+                MAX_TAG_CHARS
+            )));
+        }
+        if tag.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(GrapevineError::InvalidTags(format!(
+                "tag \"{}\" contains whitespace",
+                tag
+            )));
+        }
+        if tags[..i].contains(tag) {
+            return Err(GrapevineError::InvalidTags(format!(
+                "tag \"{}\" is repeated",
+                tag
+            )));
+        }
+    }
+    Ok(())
+}
+
+// every byte a phrase may have must fit in the 31 bytes packed into each secret field element
+const _: () = assert!(MAX_SECRET_CHARS <= SECRET_FIELD_LENGTH * 31);
+
 /**
  * Converts a given word to array of 6 field elements
  * @dev split into 31-byte strings to fit in finite field and pad with 0's where necessary
@@ -19,11 +148,9 @@ pub fn random_fr() -> Fr {
  */
 pub fn convert_phrase_to_fr(
     phrase: &String,
-) -> Result<[[u8; 32]; SECRET_FIELD_LENGTH], Box<dyn Error>> {
+) -> Result<[[u8; 32]; SECRET_FIELD_LENGTH], GrapevineError> {
     // check
-    if phrase.len() > MAX_SECRET_CHARS {
-        return Err("Phrase must be <= 180 characters".into());
-    }
+    validate_phrase(phrase)?;
 
     let mut chunks: [[u8; 32]; SECRET_FIELD_LENGTH] = Default::default();
     for i in 0..SECRET_FIELD_LENGTH {
@@ -47,15 +174,209 @@ pub fn convert_phrase_to_fr(
  * Converts a given username to a field element
  *
  * @param username - the username to convert to utf8 and into field element
- * @return - the username serialied into the field element
+ * @return - the username serialied into the field element, or UsernameTooLong if over
+ *           MAX_USERNAME_CHARS bytes
  */
-pub fn convert_username_to_fr(username: &String) -> Result<[u8; 32], Box<dyn Error>> {
+pub fn convert_username_to_fr(username: &String) -> Result<[u8; 32], GrapevineError> {
     if username.len() > MAX_USERNAME_CHARS {
-        return Err("Username must be <= 30 characters".into());
+        return Err(GrapevineError::UsernameTooLong(username.clone()));
     }
     let mut bytes: [u8; 32] = [0; 32];
     bytes[1..(username.len() + 1)].copy_from_slice(&username.as_bytes()[..]);
     bytes.reverse();
     //    Ok(format!("0x{}", hex::encode(bytes)))
     Ok(bytes)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate_phrase_length() {
+        let phrase = "a".repeat(MAX_SECRET_CHARS);
+        assert!(validate_phrase(&phrase).is_ok());
+        let phrase = "a".repeat(MAX_SECRET_CHARS + 1);
+        assert!(matches!(
+            validate_phrase(&phrase),
+            Err(GrapevineError::PhraseTooLong)
+        ));
+        // multi-byte characters count by byte
+        let phrase = "é".repeat(MAX_SECRET_CHARS / 2 + 1);
+        assert!(matches!(
+            validate_phrase(&phrase),
+            Err(GrapevineError::PhraseTooLong)
+        ));
+    }
+
+    #[test]
+    fn test_validate_phrase_control_characters() {
+        for phrase in ["new\nline", "tab\tbed", "null\0byte", "bell\u{7}"] {
+            assert!(matches!(
+                validate_phrase(phrase),
+                Err(GrapevineError::PhraseInvalidCharacters)
+            ));
+        }
+        assert!(validate_phrase("Plain old phrase, with punctuation!").is_ok());
+    }
+
+    #[test]
+    fn test_validate_phrase_empty() {
+        for phrase in ["", " ", "\t"] {
+            assert!(matches!(
+                validate_phrase(phrase),
+                Err(GrapevineError::PhraseEmpty)
+            ));
+        }
+        assert!(validate_phrase(" padded ").is_ok());
+    }
+
+    #[test]
+    fn test_validate_username() {
+        for username in ["", " ", "\t"] {
+            assert!(matches!(
+                validate_username(&String::from(username)),
+                Err(GrapevineError::UsernameEmpty)
+            ));
+        }
+        assert!(matches!(
+            validate_username(&"a".repeat(MAX_USERNAME_CHARS + 1), false),
+            Err(GrapevineError::UsernameTooLong(_))
+        ));
+        assert!(matches!(
+            validate_username(&String::from("usérname"), false),
+            Err(GrapevineError::UsernameNotAscii(_))
+        ));
+        assert!(validate_username(&String::from("mach34"), false).is_ok());
+    }
+
+    #[test]
+    fn test_validate_unicode_username() {
+        // emoji are only accepted when unicode usernames are enabled
+        let emoji = String::from("😍");
+        assert!(matches!(
+            validate_username(&emoji, false),
+            Err(GrapevineError::UsernameNotAscii(_))
+        ));
+        assert!(validate_username(&emoji, true).is_ok());
+        assert!(validate_username(&String::from("usérname"), true).is_ok());
+
+        // the byte length still has to fit in the circuit
+        assert!(matches!(
+            validate_username(&"é".repeat(MAX_USERNAME_CHARS / 2 + 1), true),
+            Err(GrapevineError::UsernameTooLong(_))
+        ));
+
+        // invisible characters and unnormalized names are rejected
+        for username in ["mach\u{200B}34", "mach\u{202E}34", "use\u{301}rname"] {
+            assert!(matches!(
+                validate_username(&String::from(username), true),
+                Err(GrapevineError::UsernameInvalidCharacters(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_normalize_username() {
+        // e followed by a combining acute accent composes to a single é
+        let decomposed = "use\u{301}rname";
+        let normalized = normalize_username(decomposed);
+        assert_eq!(normalized, "us\u{e9}rname");
+        assert_eq!(normalized.chars().count(), decomposed.chars().count() - 1);
+        assert!(validate_username(&normalized, true).is_ok());
+        // the normalized form is what gets packed into the circuit
+        assert_eq!(
+            convert_username_to_fr(&normalized).unwrap(),
+            convert_username_to_fr(&String::from("usérname")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_validate_tags() {
+        let tags = |tags: &[&str]| tags.iter().map(|t| String::from(*t)).collect::<Vec<_>>();
+        assert!(validate_tags(&[]).is_ok());
+        assert!(validate_tags(&tags(&["music", "rust-lang"])).is_ok());
+        let too_many = (0..=MAX_PHRASE_TAGS)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>();
+        let too_long = "a".repeat(MAX_TAG_CHARS + 1);
+        for invalid in [
+            too_many,
+            tags(&[""]),
+            tags(&[&too_long]),
+            tags(&["two words"]),
+            tags(&["music", "music"]),
+        ] {
+            assert!(matches!(
+                validate_tags(&invalid),
+                Err(GrapevineError::InvalidTags(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_convert_phrase_to_fr_packing() {
+        // bytes are packed 31 per element after a zero byte, then reversed to little endian
+        let phrase = String::from("ab");
+        let chunks = convert_phrase_to_fr(&phrase).unwrap();
+        assert_eq!(chunks[0][31], 0);
+        assert_eq!(chunks[0][30], b'a');
+        assert_eq!(chunks[0][29], b'b');
+        assert!(chunks[1..].iter().all(|chunk| *chunk == [0u8; 32]));
+
+        // the 32nd byte of the phrase starts the second element
+        let phrase = "a".repeat(31) + "b";
+        let chunks = convert_phrase_to_fr(&phrase).unwrap();
+        assert_eq!(chunks[0][0], b'a');
+        assert_eq!(chunks[1][30], b'b');
+
+        let phrase = "a".repeat(MAX_SECRET_CHARS + 1);
+        assert!(matches!(
+            convert_phrase_to_fr(&phrase),
+            Err(GrapevineError::PhraseTooLong)
+        ));
+    }
+
+    #[test]
+    fn test_multibyte_phrase_exceeds_packing() {
+        // MAX_SECRET_CHARS characters fit when ascii, but not when each takes 2 to 4 bytes
+        for c in ["é", "語", "😀"] {
+            let phrase = c.repeat(MAX_SECRET_CHARS);
+            assert_eq!(phrase.chars().count(), MAX_SECRET_CHARS);
+            assert!(phrase.len() > SECRET_FIELD_LENGTH * 31);
+            assert!(matches!(
+                validate_phrase(&phrase),
+                Err(GrapevineError::PhraseTooLong)
+            ));
+            assert!(matches!(
+                convert_phrase_to_fr(&phrase),
+                Err(GrapevineError::PhraseTooLong)
+            ));
+        }
+
+        // a multibyte phrase within the byte budget is packed in full
+        let phrase = "語".repeat(MAX_SECRET_CHARS / 3);
+        let chunks = convert_phrase_to_fr(&phrase).unwrap();
+        let packed: Vec<u8> = chunks
+            .iter()
+            .flat_map(|chunk| chunk.iter().rev().skip(1).copied())
+            .take(phrase.len())
+            .collect();
+        assert_eq!(packed, phrase.as_bytes());
+    }
+
+    #[test]
+    fn test_convert_username_to_fr() {
+        let username = String::from("mach34");
+        let bytes = convert_username_to_fr(&username).unwrap();
+        assert_eq!(bytes[31], 0);
+        assert_eq!(bytes[30], b'm');
+        assert_eq!(bytes[25], b'4');
+
+        let username = "a".repeat(MAX_USERNAME_CHARS + 1);
+        assert!(matches!(
+            convert_username_to_fr(&username),
+            Err(GrapevineError::UsernameTooLong(_))
+        ));
+    }
+}
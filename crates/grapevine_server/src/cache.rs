@@ -0,0 +1,205 @@
+use crate::mongo::LeaderboardMetric;
+use grapevine_common::http::responses::LeaderboardEntry;
+use lazy_static::lazy_static;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_PROOF_CACHE_SIZE: usize = 256;
+pub const DEFAULT_LEADERBOARD_TTL: u64 = 60;
+
+lazy_static! {
+    pub static ref PROOF_CACHE_SIZE: usize = match std::env::var("GRAPEVINE_PROOF_CACHE_SIZE") {
+        Ok(size) => size.parse::<usize>().unwrap_or(DEFAULT_PROOF_CACHE_SIZE),
+        Err(_) => DEFAULT_PROOF_CACHE_SIZE,
+    };
+}
+
+/**
+ * The parts of a degree proof's proving data that do not depend on who is requesting it
+ */
+#[derive(Debug, Clone)]
+pub struct CachedProof {
+    pub creator: ObjectId,
+    pub username: String,
+    pub description: String,
+    pub phrase_index: u32,
+    pub phrase_hash: [u8; 32],
+    pub degree: u8,
+    pub proof: Vec<u8>,
+    pub expires_at: Option<DateTime>,
+}
+
+impl CachedProof {
+    /**
+     * @return - true if the proof has passed its expiry time and can no longer be built from
+     */
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= DateTime::now())
+    }
+}
+
+/** A proof ObjectID scoped to the name of the database it was read from */
+pub type ProofCacheKey = (String, ObjectId);
+
+/**
+ * Least recently used cache of degree proofs keyed by database name and proof ObjectID
+ */
+pub struct ProofCache {
+    capacity: usize,
+    entries: Mutex<(HashMap<ProofCacheKey, CachedProof>, VecDeque<ProofCacheKey>)>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ProofCache {
+    /**
+     * Create a new proof cache
+     *
+     * @param capacity - the maximum number of proofs to hold before evicting the least recently used
+     */
+    pub fn new(capacity: usize) -> Self {
+        ProofCache {
+            capacity,
+            entries: Mutex::new((HashMap::new(), VecDeque::new())),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /**
+     * Get a proof from the cache, marking it as most recently used
+     *
+     * @param key - the database name and ObjectID of the proof
+     * @return - the cached proof if present
+     */
+    pub fn get(&self, key: &ProofCacheKey) -> Option<CachedProof> {
+        let mut entries = self.entries.lock().unwrap();
+        let (map, order) = &mut *entries;
+        match map.get(key) {
+            Some(proof) => {
+                let proof = proof.clone();
+                order.retain(|entry| entry != key);
+                order.push_back(key.clone());
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(proof)
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /**
+     * Add a proof to the cache, evicting the least recently used proof if full
+     *
+     * @param key - the database name and ObjectID of the proof
+     * @param proof - the proof data to cache
+     */
+    pub fn insert(&self, key: ProofCacheKey, proof: CachedProof) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let (map, order) = &mut *entries;
+        if map.insert(key.clone(), proof).is_some() {
+            order.retain(|entry| *entry != key);
+        } else if map.len() > self.capacity {
+            if let Some(evicted) = order.pop_front() {
+                map.remove(&evicted);
+            }
+        }
+        order.push_back(key);
+    }
+
+    /**
+     * Drop all cached proofs (used when proofs are deactivated or phrase data changes)
+     */
+    pub fn invalidate(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.0.clear();
+        entries.1.clear();
+    }
+
+    /**
+     * @return - the number of lookups served from the cache
+     */
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /**
+     * @return - the number of lookups that missed the cache
+     */
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+/** A leaderboard metric scoped to the name of the database it was computed from */
+pub type LeaderboardCacheKey = (String, LeaderboardMetric);
+
+/**
+ * Briefly holds computed leaderboards, since each one aggregates over a whole collection
+ */
+pub struct LeaderboardCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<LeaderboardCacheKey, (Instant, Vec<LeaderboardEntry>)>>,
+}
+
+impl LeaderboardCache {
+    /**
+     * Create a new leaderboard cache
+     *
+     * @param ttl - how long a computed leaderboard is served before it is recomputed
+     */
+    pub fn new(ttl: Duration) -> Self {
+        LeaderboardCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /**
+     * Read the leaderboard ttl (in seconds) from GRAPEVINE_LEADERBOARD_TTL, falling back to the
+     * default if unset or malformed
+     */
+    pub fn from_env() -> Self {
+        let ttl = match std::env::var("GRAPEVINE_LEADERBOARD_TTL") {
+            Ok(seconds) => seconds.parse::<u64>().unwrap_or(DEFAULT_LEADERBOARD_TTL),
+            Err(_) => DEFAULT_LEADERBOARD_TTL,
+        };
+        LeaderboardCache::new(Duration::from_secs(ttl))
+    }
+
+    /**
+     * Get a leaderboard from the cache if it was computed within the ttl
+     *
+     * @param key - the database name and metric of the leaderboard
+     * @return - the cached leaderboard if present and fresh
+     */
+    pub fn get(&self, key: &LeaderboardCacheKey) -> Option<Vec<LeaderboardEntry>> {
+        let entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((computed, leaderboard)) if computed.elapsed() < self.ttl => {
+                Some(leaderboard.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /**
+     * Add a freshly computed leaderboard to the cache
+     *
+     * @param key - the database name and metric of the leaderboard
+     * @param leaderboard - the computed leaderboard
+     */
+    pub fn insert(&self, key: LeaderboardCacheKey, leaderboard: Vec<LeaderboardEntry>) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(key, (Instant::now(), leaderboard));
+    }
+}
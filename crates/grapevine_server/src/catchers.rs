@@ -1,22 +1,30 @@
+use grapevine_circuits::utils::ProofEncoding;
 use grapevine_common::errors::GrapevineError;
 use rocket::{
+    fs::NamedFile,
     http::{ContentType, Status},
     request::Request,
     response::{self, Responder, Response},
     serde::json::Json,
+    tokio::{fs::File, io::Take},
 };
 use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
 
 // TODO: Rename to GrapvineServerError?
 
 #[derive(Responder)]
 pub enum GrapevineResponse {
+    #[response(status = 200)]
+    Success(String),
     #[response(status = 201)]
     Created(String),
     #[response(status = 400)]
     BadRequest(ErrorMessage),
-    // #[response(status = 401)]
-    // Unauthorized(ErrorMessage),
+    #[response(status = 401)]
+    Unauthorized(ErrorMessage),
+    #[response(status = 403)]
+    Forbidden(ErrorMessage),
     #[response(status = 404)]
     NotFound(String),
     #[response(status = 409)]
@@ -25,10 +33,121 @@ pub enum GrapevineResponse {
     TooLarge(String),
     #[response(status = 500)]
     InternalError(ErrorMessage),
+    #[response(status = 503)]
+    Unavailable(ErrorMessage),
     // #[response(status = 501)]
     // NotImplemented(String),
 }
 
+impl GrapevineResponse {
+    /**
+     * @return - the http status this response is sent with
+     */
+    pub fn status(&self) -> Status {
+        match self {
+            GrapevineResponse::Success(_) => Status::Ok,
+            GrapevineResponse::Created(_) => Status::Created,
+            GrapevineResponse::BadRequest(_) => Status::BadRequest,
+            GrapevineResponse::Unauthorized(_) => Status::Unauthorized,
+            GrapevineResponse::Forbidden(_) => Status::Forbidden,
+            GrapevineResponse::NotFound(_) => Status::NotFound,
+            GrapevineResponse::Conflict(_) => Status::Conflict,
+            GrapevineResponse::TooLarge(_) => Status::PayloadTooLarge,
+            GrapevineResponse::InternalError(_) => Status::InternalServerError,
+            GrapevineResponse::Unavailable(_) => Status::ServiceUnavailable,
+        }
+    }
+}
+
+impl From<GrapevineError> for GrapevineResponse {
+    /**
+     * Wrap an error in the response matching its http status so routes can propagate it with `?`
+     */
+    fn from(error: GrapevineError) -> Self {
+        match error.http_status() {
+            400 => GrapevineResponse::BadRequest(ErrorMessage(Some(error), None)),
+            401 => GrapevineResponse::Unauthorized(ErrorMessage(Some(error), None)),
+            403 => GrapevineResponse::Forbidden(ErrorMessage(Some(error), None)),
+            404 => GrapevineResponse::NotFound(error.to_string()),
+            409 => GrapevineResponse::Conflict(ErrorMessage(Some(error), None)),
+            413 => GrapevineResponse::TooLarge(error.to_string()),
+            503 => GrapevineResponse::Unavailable(ErrorMessage(Some(error), None)),
+            _ => GrapevineResponse::InternalError(ErrorMessage(Some(error), None)),
+        }
+    }
+}
+
+/** A response carrying a proof in a negotiated format, named in the X-Proof-Encoding header */
+pub struct EncodedProof<R>(pub R, pub ProofEncoding);
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for EncodedProof<R> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'o> {
+        let mut res = self.0.respond_to(req)?;
+        res.set_raw_header("X-Proof-Encoding", self.1.name());
+        Ok(res)
+    }
+}
+
+/** A static artifact (or one byte range of it) with its validators, or an empty 304 / 416 */
+pub enum StaticArtifact {
+    File(NamedFile, String, SystemTime),
+    Partial {
+        body: Take<File>,
+        content_type: Option<ContentType>,
+        etag: String,
+        modified: SystemTime,
+        range: (u64, u64),
+        len: u64,
+    },
+    NotModified(String),
+    Unsatisfiable(u64),
+}
+
+impl<'r> Responder<'r, 'static> for StaticArtifact {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        match self {
+            StaticArtifact::File(file, etag, modified) => {
+                let mut res = file.respond_to(req)?;
+                res.set_raw_header("ETag", etag);
+                res.set_raw_header("Last-Modified", httpdate::fmt_http_date(modified));
+                res.set_raw_header("Accept-Ranges", "bytes");
+                // artifacts can change on deploy, so clients revalidate before reusing a copy
+                res.set_raw_header("Cache-Control", "no-cache");
+                Ok(res)
+            }
+            StaticArtifact::Partial {
+                body,
+                content_type,
+                etag,
+                modified,
+                range: (first, last),
+                len,
+            } => {
+                let mut res = Response::build();
+                if let Some(content_type) = content_type {
+                    res.header(content_type);
+                }
+                res.status(Status::PartialContent)
+                    .raw_header("ETag", etag)
+                    .raw_header("Last-Modified", httpdate::fmt_http_date(modified))
+                    .raw_header("Accept-Ranges", "bytes")
+                    .raw_header("Cache-Control", "no-cache")
+                    .raw_header("Content-Range", format!("bytes {}-{}/{}", first, last, len))
+                    .streamed_body(body)
+                    .ok()
+            }
+            StaticArtifact::NotModified(etag) => Response::build()
+                .status(Status::NotModified)
+                .raw_header("ETag", etag)
+                .ok(),
+            StaticArtifact::Unsatisfiable(len) => Response::build()
+                .status(Status::RangeNotSatisfiable)
+                .raw_header("Content-Range", format!("bytes */{}", len))
+                .ok(),
+        }
+    }
+}
+
 // #[catch(400)]
 // pub fn bad_request(req: &Request) -> GrapevineResponse {
 //     match req.local_cache(|| ErrorMessage(None)) {
@@ -0,0 +1,59 @@
+use grapevine_common::http::responses::AvailableProofEvent;
+use mongodb::bson::oid::ObjectId;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, Receiver, Sender};
+
+/** Number of undelivered events a subscriber can fall behind by before it starts missing them */
+pub const EVENT_BUFFER_SIZE: usize = 32;
+
+/**
+ * Publish/ subscribe channels notifying connected users of new proofs they can build from,
+ * keyed by the ObjectID of the subscribed user
+ */
+#[derive(Clone, Default)]
+pub struct ProofEvents {
+    channels: Arc<Mutex<HashMap<ObjectId, Sender<AvailableProofEvent>>>>,
+}
+
+impl ProofEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Subscribe to events for a user
+     *
+     * @param user - the ObjectID of the user to receive events for
+     * @return - a receiver that yields every event published to the user from now on
+     */
+    pub fn subscribe(&self, user: &ObjectId) -> Receiver<AvailableProofEvent> {
+        let mut channels = self.channels.lock().unwrap();
+        match channels.get(user) {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let (sender, receiver) = broadcast::channel(EVENT_BUFFER_SIZE);
+                channels.insert(*user, sender);
+                receiver
+            }
+        }
+    }
+
+    /**
+     * Publish an event to a set of users, skipping any that are not subscribed
+     *
+     * @param users - the ObjectIDs of the users to notify
+     * @param event - the event to send each user
+     */
+    pub fn publish(&self, users: &[ObjectId], event: &AvailableProofEvent) {
+        let mut channels = self.channels.lock().unwrap();
+        for user in users {
+            if let Some(sender) = channels.get(user) {
+                // a failed send means every subscriber has disconnected
+                if sender.send(event.clone()).is_err() {
+                    channels.remove(user);
+                }
+            }
+        }
+    }
+}
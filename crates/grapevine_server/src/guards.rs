@@ -2,16 +2,119 @@ use crate::catchers::ErrorMessage;
 use grapevine_common::errors::GrapevineError;
 use crate::mongo::GrapevineDB;
 use babyjubjub_rs::{decompress_point, decompress_signature, verify};
-use grapevine_common::crypto::nonce_hash;
+use grapevine_circuits::utils::ProofEncoding;
+use grapevine_common::crypto::request_hash;
+use lazy_static::lazy_static;
 use num_bigint::{BigInt, Sign};
 use rocket::{
-    http::Status,
+    http::{Method, Status},
     outcome::Outcome::{Error as Failure, Success},
     request::{FromRequest, Outcome, Request},
     State,
 };
+use std::ops::Deref;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-/** A username passed through header that passes the signed nonce check */
+pub const DEFAULT_USERNAME_HEADER: &str = "X-Username";
+pub const DEFAULT_AUTHORIZATION_HEADER: &str = "X-Authorization";
+
+lazy_static! {
+    static ref ADMIN_TOKEN: Option<String> = match std::env::var("GRAPEVINE_ADMIN_TOKEN") {
+        Ok(token) if !token.is_empty() => Some(token),
+        _ => None,
+    };
+}
+
+/**
+ * Names of the headers the AuthenticatedUser guard reads the username and request signature from,
+ * for deployments behind proxies that strip or rename custom headers
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthHeaders {
+    pub username: String,      // header carrying the username
+    pub authorization: String, // header carrying the signature over the nonce and request
+}
+
+impl Default for AuthHeaders {
+    fn default() -> Self {
+        AuthHeaders {
+            username: String::from(DEFAULT_USERNAME_HEADER),
+            authorization: String::from(DEFAULT_AUTHORIZATION_HEADER),
+        }
+    }
+}
+
+impl AuthHeaders {
+    /**
+     * Read the header names from GRAPEVINE_USERNAME_HEADER and GRAPEVINE_AUTHORIZATION_HEADER,
+     * falling back to X-Username and X-Authorization if unset or empty
+     */
+    pub fn from_env() -> Self {
+        let header = |var: &str, default: &str| match std::env::var(var) {
+            Ok(name) if !name.trim().is_empty() => String::from(name.trim()),
+            _ => String::from(default),
+        };
+        AuthHeaders {
+            username: header("GRAPEVINE_USERNAME_HEADER", DEFAULT_USERNAME_HEADER),
+            authorization: header(
+                "GRAPEVINE_AUTHORIZATION_HEADER",
+                DEFAULT_AUTHORIZATION_HEADER,
+            ),
+        }
+    }
+}
+
+/** The database selected by the optional X-Tenant header (default database if absent) */
+#[derive(Clone)]
+pub struct TenantDB(pub GrapevineDB);
+
+impl Deref for TenantDB {
+    type Target = GrapevineDB;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for TenantDB {
+    type Error = ErrorMessage;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        // Connect to mongodb
+        let mongo = match request.guard::<&State<GrapevineDB>>().await {
+            Success(db) => db,
+            _ => {
+                return Failure((
+                    Status::InternalServerError,
+                    ErrorMessage(
+                        Some(GrapevineError::MongoError(String::from(
+                            "Error connecting to database",
+                        ))),
+                        None,
+                    ),
+                ));
+            }
+        };
+        // Select the tenant database if the X-Tenant header is present
+        match request.headers().get_one("X-Tenant") {
+            Some(tenant) => match mongo.tenant(tenant) {
+                Ok(db) => Success(TenantDB(db)),
+                Err(e) => Failure((Status::BadRequest, ErrorMessage(Some(e), None))),
+            },
+            None => Success(TenantDB(mongo.inner().clone())),
+        }
+    }
+}
+
+/**
+ * A username passed through header whose signature over its nonce and this request verifies
+ * @notice only state-changing requests consume the nonce. A read-only (GET/HEAD) request is
+ *         verified against the current nonce without incrementing it, so its signature can be
+ *         replayed until the user's next state-changing request. The path, query, and X-Tenant
+ *         header are all part of the signed message, so a replay can only repeat the exact same
+ *         read
+ */
 #[derive(Debug, Clone)]
 pub struct AuthenticatedUser(pub String);
 
@@ -20,9 +123,10 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
     type Error = ErrorMessage;
 
     async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        // Connect to mongodb
-        let mongo = match request.guard::<&State<GrapevineDB>>().await {
+        // Connect to the tenant database
+        let mongo = match request.guard::<TenantDB>().await {
             Success(db) => db,
+            Failure(e) => return Failure(e),
             _ => {
                 return Failure((
                     Status::InternalServerError,
@@ -35,23 +139,30 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
                 ));
             }
         };
-        // Check for X-Username header
-        let username = match request.headers().get_one("X-Username") {
+        // Use the configured header names (X-Username and X-Authorization by default)
+        let headers = request
+            .rocket()
+            .state::<AuthHeaders>()
+            .cloned()
+            .unwrap_or_default();
+        // Check for username header
+        let username = match request.headers().get_one(&headers.username) {
             Some(username) => String::from(username),
             None => {
                 return Failure((
                     Status::BadRequest,
                     ErrorMessage(
-                        Some(GrapevineError::HeaderError(String::from(
-                            "couldn't find X-Username",
+                        Some(GrapevineError::HeaderError(format!(
+                            "couldn't find {}",
+                            headers.username
                         ))),
                         None,
                     ),
                 ));
             }
         };
-        // Check for X-Authorization header (signature over nonce)
-        let signature = match request.headers().get_one("X-Authorization") {
+        // Check for authorization header (signature over nonce, method, path, query, and tenant)
+        let signature = match request.headers().get_one(&headers.authorization) {
             Some(data) => {
                 // attempt to parse the signature
                 let bytes: [u8; 64] = hex::decode(data).unwrap().try_into().unwrap();
@@ -61,8 +172,9 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
                         return Failure((
                             Status::BadRequest,
                             ErrorMessage(
-                                Some(GrapevineError::HeaderError(String::from(
-                                    "couldn't parse X-Authorization",
+                                Some(GrapevineError::HeaderError(format!(
+                                    "couldn't parse {}",
+                                    headers.authorization
                                 ))),
                                 None,
                             ),
@@ -74,8 +186,9 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
                 return Failure((
                     Status::BadRequest,
                     ErrorMessage(
-                        Some(GrapevineError::HeaderError(String::from(
-                            "couldn't find X-Authorization",
+                        Some(GrapevineError::HeaderError(format!(
+                            "couldn't find {}",
+                            headers.authorization
                         ))),
                         None,
                     ),
@@ -92,11 +205,44 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
                 ));
             }
         };
+        // Reject a presented nonce that is behind (replayed) or ahead of (skipped) the stored nonce
+        if let Some(presented) = request.headers().get_one("X-Nonce") {
+            let presented = match presented.parse::<u64>() {
+                Ok(presented) => presented,
+                Err(_) => {
+                    return Failure((
+                        Status::BadRequest,
+                        ErrorMessage(
+                            Some(GrapevineError::HeaderError(String::from(
+                                "couldn't parse X-Nonce",
+                            ))),
+                            None,
+                        ),
+                    ));
+                }
+            };
+            if presented < nonce {
+                let error = GrapevineError::NonceReplayed(presented, nonce);
+                return Failure((Status::Unauthorized, ErrorMessage(Some(error), Some(nonce))));
+            }
+            if presented > nonce {
+                let error = GrapevineError::NonceAhead(presented, nonce);
+                return Failure((Status::Unauthorized, ErrorMessage(Some(error), Some(nonce))));
+            }
+        }
         // convert pubkey to bjj point (assumes won't fail due to other checks)
         let pubkey = decompress_point(pubkey).unwrap();
-        // Take the sha256 hash of the nonce and username, and convert to bjj message format
-        let message = BigInt::from_bytes_le(Sign::Plus, &nonce_hash(&username, nonce));
-        // Check that signature matches expected nonce/ username hash
+        // Hash the nonce and username with the request being made, and convert to bjj message format
+        let hash = request_hash(
+            &username,
+            nonce,
+            request.method().as_str(),
+            request.uri().path().as_str(),
+            request.uri().query().map(|query| query.as_str()),
+            request.headers().get_one("X-Tenant"),
+        );
+        let message = BigInt::from_bytes_le(Sign::Plus, &hash);
+        // Check that signature matches expected nonce/ username/ request hash
         match verify(pubkey, signature, message) {
             true => (),
             false => {
@@ -111,9 +257,17 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
                 ));
             }
         };
+        // Read-only requests leave the nonce for the next state-changing request
+        if is_read_only(request.method()) {
+            return Success(AuthenticatedUser(username));
+        }
         // Increment nonce in database
-        match mongo.increment_nonce(&username).await {
+        match mongo.increment_nonce(&username, nonce).await {
             Ok(_) => Success(AuthenticatedUser(username)),
+            // another request signed with the same nonce was accepted first
+            Err(e @ GrapevineError::NonceReplayed(_, _)) => {
+                Failure((Status::Unauthorized, ErrorMessage(Some(e), Some(nonce + 1))))
+            }
             Err(_) => Failure((
                 Status::InternalServerError,
                 ErrorMessage(
@@ -126,3 +280,173 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
         }
     }
 }
+
+/**
+ * Check whether a request method cannot change server state, so it does not consume a nonce
+ *
+ * @param method - the http method of the request
+ * @return - true for GET and HEAD requests
+ */
+fn is_read_only(method: Method) -> bool {
+    matches!(method, Method::Get | Method::Head)
+}
+
+/** A request carrying the admin token from GRAPEVINE_ADMIN_TOKEN in the X-Admin-Token header */
+#[derive(Debug, Clone)]
+pub struct Admin;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = ErrorMessage;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        // Admin routes are disabled unless a token is configured
+        let expected = match &*ADMIN_TOKEN {
+            Some(token) => token,
+            None => {
+                return Failure((
+                    Status::Forbidden,
+                    ErrorMessage(
+                        Some(GrapevineError::HeaderError(String::from(
+                            "admin routes are disabled",
+                        ))),
+                        None,
+                    ),
+                ));
+            }
+        };
+        match request.headers().get_one("X-Admin-Token") {
+            Some(token) if token == expected => Success(Admin),
+            _ => Failure((
+                Status::Unauthorized,
+                ErrorMessage(
+                    Some(GrapevineError::HeaderError(String::from(
+                        "invalid X-Admin-Token",
+                    ))),
+                    None,
+                ),
+            )),
+        }
+    }
+}
+
+/** The proof format requested in the optional X-Accept-Proof-Encoding header (gzip if absent) */
+#[derive(Debug, Clone, Copy)]
+pub struct AcceptProofEncoding(pub ProofEncoding);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AcceptProofEncoding {
+    type Error = ErrorMessage;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let accept = request.headers().get_one("X-Accept-Proof-Encoding");
+        Success(AcceptProofEncoding(ProofEncoding::negotiate(accept)))
+    }
+}
+
+/**
+ * The validators of a client's cached copy (If-None-Match, If-Modified-Since) and the part of a
+ * file it is asking for (Range, If-Range)
+ */
+#[derive(Debug, Clone)]
+pub struct ConditionalRequest {
+    pub if_none_match: Option<String>,
+    pub if_modified_since: Option<SystemTime>,
+    pub range: Option<String>,
+    pub if_range: Option<String>,
+}
+
+impl ConditionalRequest {
+    /**
+     * Check whether the client's cached copy of a file is still current
+     * @notice If-Modified-Since is ignored when If-None-Match is sent (RFC 9110 13.1.3)
+     *
+     * @param etag - the quoted entity tag of the file
+     * @param modified - when the file was last modified
+     * @return - true if the client can reuse its copy (304), false if the file must be sent
+     */
+    pub fn is_current(&self, etag: &str, modified: SystemTime) -> bool {
+        if let Some(tags) = &self.if_none_match {
+            return tags
+                .split(',')
+                .map(|tag| tag.trim())
+                .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag);
+        }
+        match (self.if_modified_since, modified.duration_since(UNIX_EPOCH)) {
+            // http dates only have second precision
+            (Some(since), Ok(modified)) => {
+                since >= UNIX_EPOCH + Duration::from_secs(modified.as_secs())
+            }
+            _ => false,
+        }
+    }
+
+    /**
+     * Resolve the Range header against a file. Only a single byte range is served; anything else
+     * (including a range conditioned by an If-Range that no longer matches) gets the whole file
+     *
+     * @param etag - the quoted entity tag of the file
+     * @param modified - when the file was last modified
+     * @param len - the size of the file in bytes
+     * @return - the first and last (inclusive) byte to send, None to send the whole file, or
+     *           RangeNotSatisfiable if the range starts past the end of the file
+     */
+    pub fn byte_range(
+        &self,
+        etag: &str,
+        modified: SystemTime,
+        len: u64,
+    ) -> Result<Option<(u64, u64)>, Status> {
+        let range = match self.range.as_deref().and_then(|r| r.strip_prefix("bytes=")) {
+            Some(range) if !range.contains(',') => range.trim(),
+            _ => return Ok(None),
+        };
+        if let Some(validator) = &self.if_range {
+            let matches = match httpdate::parse_http_date(validator) {
+                Ok(date) => httpdate::fmt_http_date(date) == httpdate::fmt_http_date(modified),
+                Err(_) => validator == etag,
+            };
+            if !matches {
+                return Ok(None);
+            }
+        }
+        let (first, last) = match range.split_once('-') {
+            Some(bounds) => bounds,
+            None => return Ok(None),
+        };
+        let (first, last) = match (first.parse::<u64>(), last.parse::<u64>()) {
+            // bytes=first-last
+            (Ok(first), Ok(last)) if first <= last => (first, last.min(len.saturating_sub(1))),
+            // bytes=first-
+            (Ok(first), Err(_)) if last.is_empty() => (first, len.saturating_sub(1)),
+            // bytes=-suffix
+            (Err(_), Ok(suffix)) if first.is_empty() && suffix > 0 => {
+                (len.saturating_sub(suffix), len.saturating_sub(1))
+            }
+            (Err(_), Ok(_)) if first.is_empty() => return Err(Status::RangeNotSatisfiable),
+            _ => return Ok(None),
+        };
+        match first < len {
+            true => Ok(Some((first, last))),
+            false => Err(Status::RangeNotSatisfiable),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ConditionalRequest {
+    type Error = ErrorMessage;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let headers = request.headers();
+        Success(ConditionalRequest {
+            if_none_match: headers.get_one("If-None-Match").map(String::from),
+            // a malformed date is treated as absent
+            if_modified_since: headers
+                .get_one("If-Modified-Since")
+                .and_then(|date| httpdate::parse_http_date(date).ok()),
+            range: headers.get_one("Range").map(String::from),
+            if_range: headers.get_one("If-Range").map(String::from),
+        })
+    }
+}
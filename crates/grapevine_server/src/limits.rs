@@ -0,0 +1,91 @@
+use crate::catchers::GrapevineResponse;
+use rocket::data::{Data, ToByteUnit};
+
+pub const DEFAULT_IDENTITY_PROOF_LIMIT: u64 = 2 * 1024 * 1024;
+pub const DEFAULT_DEGREE_PROOF_LIMIT: u64 = 2 * 1024 * 1024;
+pub const DEFAULT_BACKUP_LIMIT: u64 = 1024 * 1024 * 1024;
+pub const DEFAULT_PHRASE_QUOTA: u64 = 1000;
+pub const DEFAULT_PENDING_RELATIONSHIP_QUOTA: u64 = 100;
+
+/**
+ * Maximum request body sizes (in bytes) accepted by the routes proofs are uploaded to
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct ProofLimits {
+    pub identity: u64, // phrase (degree 1) proofs
+    pub degree: u64,   // degree proofs, which may need more room at high degrees
+    pub backup: u64,   // proof backups restored through the admin import route
+}
+
+impl ProofLimits {
+    /**
+     * Read the proof body limits from GRAPEVINE_IDENTITY_PROOF_LIMIT,
+     * GRAPEVINE_DEGREE_PROOF_LIMIT, and GRAPEVINE_BACKUP_LIMIT, falling back to the defaults if
+     * unset or malformed
+     */
+    pub fn from_env() -> Self {
+        let limit = |var: &str, default: u64| match std::env::var(var) {
+            Ok(bytes) => bytes.parse::<u64>().unwrap_or(default),
+            Err(_) => default,
+        };
+        let identity = limit(
+            "GRAPEVINE_IDENTITY_PROOF_LIMIT",
+            DEFAULT_IDENTITY_PROOF_LIMIT,
+        );
+        let degree = limit("GRAPEVINE_DEGREE_PROOF_LIMIT", DEFAULT_DEGREE_PROOF_LIMIT);
+        let backup = limit("GRAPEVINE_BACKUP_LIMIT", DEFAULT_BACKUP_LIMIT);
+        ProofLimits {
+            identity,
+            degree,
+            backup,
+        }
+    }
+}
+
+/**
+ * Caps on what a single user can create, to limit spam
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct UserQuotas {
+    pub phrases: u64,               // phrases the user may originate
+    pub pending_relationships: u64, // outbound relationships awaiting acceptance
+}
+
+impl UserQuotas {
+    /**
+     * Read the quotas from GRAPEVINE_PHRASE_QUOTA and GRAPEVINE_PENDING_RELATIONSHIP_QUOTA,
+     * falling back to the defaults if unset or malformed
+     */
+    pub fn from_env() -> Self {
+        let quota = |var: &str, default: u64| match std::env::var(var) {
+            Ok(count) => count.parse::<u64>().unwrap_or(default),
+            Err(_) => default,
+        };
+        let phrases = quota("GRAPEVINE_PHRASE_QUOTA", DEFAULT_PHRASE_QUOTA);
+        let pending_relationships = quota(
+            "GRAPEVINE_PENDING_RELATIONSHIP_QUOTA",
+            DEFAULT_PENDING_RELATIONSHIP_QUOTA,
+        );
+        UserQuotas {
+            phrases,
+            pending_relationships,
+        }
+    }
+}
+
+/**
+ * Stream in a request body, rejecting it if it does not fit within the limit
+ *
+ * @param data - the request body
+ * @param limit - the maximum number of bytes to accept
+ * @return - the full request body, or a TooLarge response naming the limit
+ */
+pub async fn read_body(data: Data<'_>, limit: u64) -> Result<Vec<u8>, GrapevineResponse> {
+    match data.open(limit.bytes()).into_bytes().await {
+        Ok(body) if body.is_complete() => Ok(body.into_inner()),
+        _ => Err(GrapevineResponse::TooLarge(format!(
+            "Request body exceeds {} bytes",
+            limit
+        ))),
+    }
+}
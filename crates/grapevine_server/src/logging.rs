@@ -0,0 +1,158 @@
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::{Data, Request, Response};
+use std::time::Instant;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::EnvFilter;
+
+pub const DEFAULT_LOG_FILTER: &str = "info"; // used when RUST_LOG is unset or malformed
+pub const MAX_REQUEST_ID_CHARS: usize = 64; // longest X-Request-Id accepted from a client
+
+/**
+ * How server logs are written
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Pretty, // human readable lines
+    Json,   // one JSON object per event, for log collectors
+}
+
+impl LogFormat {
+    /**
+     * @param format - the name of the format (pretty or json)
+     * @return - the format, or None if the name is not recognized
+     */
+    pub fn parse(format: &str) -> Option<Self> {
+        match format.trim().to_ascii_lowercase().as_str() {
+            "pretty" => Some(LogFormat::Pretty),
+            "json" => Some(LogFormat::Json),
+            _ => None,
+        }
+    }
+
+    /**
+     * Read the log format from GRAPEVINE_LOG_FORMAT, falling back to pretty if unset or
+     * unrecognized
+     */
+    pub fn from_env() -> Self {
+        std::env::var("GRAPEVINE_LOG_FORMAT")
+            .ok()
+            .and_then(|format| Self::parse(&format))
+            .unwrap_or(LogFormat::Pretty)
+    }
+}
+
+/**
+ * Build the subscriber server logs are written through
+ * @notice json events include the fields of the span they were logged in, such as request_id
+ *
+ * @param format - whether to write human readable lines or JSON objects
+ * @param filter - which events to keep
+ * @param writer - where to write the logs
+ * @return - the subscriber
+ */
+pub fn build_subscriber<W>(
+    format: LogFormat,
+    filter: EnvFilter,
+    writer: W,
+) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer);
+    match format {
+        LogFormat::Pretty => Box::new(builder.finish()),
+        LogFormat::Json => Box::new(
+            builder
+                .json()
+                .flatten_event(true)
+                .with_current_span(true)
+                .finish(),
+        ),
+    }
+}
+
+/**
+ * Install the global subscriber writing to stdout, filtered by RUST_LOG (DEFAULT_LOG_FILTER if
+ * unset) in the format set by GRAPEVINE_LOG_FORMAT
+ */
+pub fn init() {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+    let subscriber = build_subscriber(LogFormat::from_env(), filter, std::io::stdout);
+    tracing::subscriber::set_global_default(subscriber).expect("logger already initialized");
+}
+
+/**
+ * The id a request's log events are tagged with and when the request arrived
+ */
+struct RequestContext {
+    id: String,
+    start: Instant,
+}
+
+impl RequestContext {
+    /**
+     * Use the id the client (or a proxy in front of the server) sent in X-Request-Id, or a new
+     * uuid if there is none or it is not a short token
+     *
+     * @param incoming - the X-Request-Id header, if sent
+     */
+    fn new(incoming: Option<&str>) -> Self {
+        let id = match incoming {
+            Some(id)
+                if !id.is_empty()
+                    && id.len() <= MAX_REQUEST_ID_CHARS
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) =>
+            {
+                String::from(id)
+            }
+            _ => uuid::Uuid::new_v4().to_string(),
+        };
+        RequestContext {
+            id,
+            start: Instant::now(),
+        }
+    }
+}
+
+/**
+ * Tags every request with an id, logs its arrival and response within a `request` span carrying
+ * that id, and returns the id in the X-Request-Id response header
+ */
+pub struct RequestLogger;
+
+#[rocket::async_trait]
+impl Fairing for RequestLogger {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request logger",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        let context = RequestContext::new(request.headers().get_one("X-Request-Id"));
+        let context = request.local_cache(|| context);
+        let span = tracing::info_span!("request", request_id = %context.id);
+        let _entered = span.enter();
+        tracing::info!(method = %request.method(), uri = %request.uri(), "request received");
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let context = request.local_cache(|| RequestContext::new(None));
+        response.set_header(Header::new("X-Request-Id", context.id.clone()));
+        let span = tracing::info_span!("request", request_id = %context.id);
+        let _entered = span.enter();
+        tracing::info!(
+            status = response.status().code,
+            elapsed_ms = context.start.elapsed().as_millis() as u64,
+            "response sent"
+        );
+    }
+}
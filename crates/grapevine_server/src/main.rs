@@ -1,38 +1,84 @@
 #[macro_use]
 extern crate rocket;
 // use catchers::{bad_request, not_found, unauthorized};
+use cache::{LeaderboardCache, ProofCache, PROOF_CACHE_SIZE};
+use events::ProofEvents;
+use guards::AuthHeaders;
 use lazy_static::lazy_static;
+use limits::{ProofLimits, UserQuotas};
+use logging::RequestLogger;
 use mongo::GrapevineDB;
 use mongodb::bson::doc;
-use rocket::fs::{relative, FileServer};
+use shutdown::ProofDrain;
 
+mod cache;
 mod catchers;
+mod events;
 mod guards;
+mod limits;
+mod logging;
 mod mongo;
 mod routes;
+mod shutdown;
 mod utils;
+mod verifier;
 
 lazy_static! {
     static ref MONGODB_URI: String = String::from(env!("MONGODB_URI"));
     static ref DATABASE_NAME: String = String::from(env!("DATABASE_NAME"));
+    static ref TENANTS: Vec<String> = match std::env::var("GRAPEVINE_TENANTS") {
+        Ok(tenants) => tenants
+            .split(',')
+            .map(|tenant| String::from(tenant.trim()))
+            .filter(|tenant| !tenant.is_empty())
+            .collect(),
+        Err(_) => vec![],
+    };
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Initialize logger (before connecting so the mongodb settings are logged)
+    logging::init();
     // connect to mongodb
-    let mongo = GrapevineDB::init(&*DATABASE_NAME, &*MONGODB_URI).await;
-    // Initialize logger
-    tracing_subscriber::fmt::init();
+    let mongo = GrapevineDB::init(&*DATABASE_NAME, &*MONGODB_URI)
+        .await
+        .with_tenants(TENANTS.clone());
+    // let mongodb clean up expired proofs and reject duplicate proofs
+    mongo.ensure_proof_indexes().await?;
+    // fingerprint the served artifacts before accepting requests
+    lazy_static::initialize(&utils::CIRCUIT_INFO);
     // TODO: Route formatting/ segmenting logic
     rocket::build()
         // add mongodb client to context
         .manage(mongo)
+        // add proof cache to context
+        .manage(ProofCache::new(*PROOF_CACHE_SIZE))
+        // add leaderboard cache to context
+        .manage(LeaderboardCache::from_env())
+        // add proof body size limits to context
+        .manage(ProofLimits::from_env())
+        // add per-user creation quotas to context
+        .manage(UserQuotas::from_env())
+        // add the header names requests are authenticated with to context
+        .manage(AuthHeaders::from_env())
+        // add new proof notification channels to context
+        .manage(ProofEvents::new())
+        // let in-flight proof submissions finish on shutdown
+        .manage(ProofDrain::from_env())
+        .attach(ProofDrain::fairing())
+        // tag each request's logs with a request id
+        .attach(RequestLogger)
         // mount user routes
         .mount("/user", &**routes::USER_ROUTES)
         // mount proof routes
         .mount("/proof", &**routes::PROOF_ROUTES)
-        // mount artifact file server
-        .mount("/static", FileServer::from(relative!("static")))
+        // mount websocket routes
+        .mount("/ws", &**routes::WS_ROUTES)
+        // mount stats routes
+        .mount("/stats", &**routes::STATS_ROUTES)
+        // mount artifact routes
+        .mount("/static", &**routes::ARTIFACT_ROUTES)
         // mount test methods (TO BE REMOVED)
         .mount("/test", routes![health])
         // register request guards
@@ -49,34 +95,61 @@ async fn health() -> &'static str {
 
 #[cfg(test)]
 mod test_rocket {
-    use self::utils::{use_public_params, use_r1cs, use_wasm};
+    use self::utils::{check_proof, use_public_params, use_r1cs, use_wasm, ProofRejectionReason};
 
     use super::*;
+    use crate::logging::LogFormat;
+    use crate::mongo::{MongoPoolConfig, DEFAULT_MONGO_CONNECT_TIMEOUT_SECS};
     use grapevine_circuits::{
         nova::{continue_nova_proof, nova_proof, verify_nova_proof},
-        utils::{compress_proof, decompress_proof},
+        steps_for_degree,
+        utils::{compress_proof, decode_proof, decompress_proof, ProofEncoding},
     };
+    #[cfg(feature = "delegated-proving")]
+    use grapevine_common::http::requests::DelegatedDegreeProofRequest;
     use grapevine_common::{
         account::GrapevineAccount,
         auth_secret::AuthSecretEncrypted,
+        backup::read_backup,
+        crypto::{
+            artifact_hash, commit_phrase, hash_phrase, merkle_leaf, merkle_root, new_blinding,
+            new_private_key, verify_merkle_proof,
+        },
+        errors::GrapevineError,
         http::{
             requests::{
-                CreateUserRequest, DegreeProofRequest, NewRelationshipRequest, PhraseRequest,
+                CreateUserRequest, DegreeProofBatchRequest, DegreeProofRequest, GuardianApproval,
+                GuardiansRequest, NewRelationshipRequest, PhraseCiphertextRequest,
+                PhraseCommitRequest, PhraseDescriptionRequest, PhraseRequest, PhraseRequestV1,
+                PhraseRevealRequest, PhraseTagsRequest, PhraseVisibilityRequest, RecoveryRequest,
+                VerifyInSetRequest,
+            },
+            responses::{
+                AccountDetailsResponse, AvailableProofEvent, CircuitInfoResponse, ConnectedOrigin,
+                DegreeData, DegreeProofBatchResult, LeaderboardEntry, MerkleProofResponse,
+                MerkleRootResponse, PhraseCreationResponse, PhraseFullResponse,
+                PhraseProofResponse, PhraseRevealResponse, PhraseSearchResult, PhraseSummary,
+                ProofChild, ProofMigrationResponse, VerifyInSetResponse,
             },
-            responses::{DegreeData, PhraseCreationResponse},
         },
-        models::{DegreeProof, ProvingData, User},
+        models::{DegreeProof, PhraseVisibility, ProvingData, Relationship, User},
+        utils::random_fr,
+        REQUEST_VERSION,
     };
     use lazy_static::lazy_static;
+    use mongodb::bson::{oid::ObjectId, DateTime};
     use rocket::{
         form::validate::Contains,
         http::{ContentType, Header, Status},
         local::asynchronous::Client,
     };
     use std::sync::Mutex;
+    use std::time::Duration;
+    use tracing_subscriber::EnvFilter;
 
     lazy_static! {
         static ref USERS: Mutex<Vec<GrapevineAccount>> = Mutex::new(vec![]);
+        static ref PROOF_EVENTS: ProofEvents = ProofEvents::new();
     }
 
     struct GrapevineTestContext {
@@ -85,19 +158,50 @@ mod test_rocket {
 
     impl GrapevineTestContext {
         async fn init() -> Self {
+            Self::init_with_quotas(UserQuotas::from_env()).await
+        }
+
+        async fn init_with_quotas(quotas: UserQuotas) -> Self {
+            // tests may set the header env vars, so other tests use the default names
+            Self::init_with(quotas, AuthHeaders::default()).await
+        }
+
+        async fn init_with(quotas: UserQuotas, auth_headers: AuthHeaders) -> Self {
             let database_name = String::from("grapevine_mocked");
-            let mongo = GrapevineDB::init(&database_name, &*MONGODB_URI).await;
+            let mongo = GrapevineDB::init(&database_name, &*MONGODB_URI)
+                .await
+                .with_tenants(vec![String::from("tenant_a"), String::from("tenant_b")]);
             let rocket = rocket::build()
                 // add mongodb client to context
                 .manage(mongo)
+                // add proof cache to context
+                .manage(ProofCache::new(*PROOF_CACHE_SIZE))
+                // add leaderboard cache to context
+                .manage(LeaderboardCache::from_env())
+                // add proof body size limits to context
+                .manage(ProofLimits::from_env())
+                // add per-user creation quotas to context
+                .manage(quotas)
+                // add the header names requests are authenticated with to context
+                .manage(auth_headers)
+                // share notification channels across contexts so tests can subscribe
+                .manage(PROOF_EVENTS.clone())
+                // track in-flight proof submissions
+                .manage(ProofDrain::from_env())
+                // tag each request's logs with a request id
+                .attach(RequestLogger)
                 // mount user routes
                 .mount("/user", &**routes::USER_ROUTES)
                 // mount proof routes
                 .mount("/proof", &**routes::PROOF_ROUTES)
+                // mount websocket routes
+                .mount("/ws", &**routes::WS_ROUTES)
+                // mount stats routes
+                .mount("/stats", &**routes::STATS_ROUTES)
                 // mount test routes
                 .mount("/", routes![health])
-                // mount artifact file server
-                .mount("/static", FileServer::from(relative!("static")));
+                // mount artifact routes
+                .mount("/static", &**routes::ARTIFACT_ROUTES);
             // .register("/", catchers![bad_request, not_found, unauthorized]);
 
             GrapevineTestContext {
@@ -110,6 +214,15 @@ mod test_rocket {
     async fn add_relationship_request(
         from: &mut GrapevineAccount,
         to: &mut GrapevineAccount,
+    ) -> (u16, Option<String>) {
+        let context = GrapevineTestContext::init().await;
+        add_relationship_request_to(&context, from, to).await
+    }
+
+    async fn add_relationship_request_to(
+        context: &GrapevineTestContext,
+        from: &mut GrapevineAccount,
+        to: &mut GrapevineAccount,
     ) -> (u16, Option<String>) {
         let pubkey = to.pubkey();
         let encrypted_auth_secret = from.encrypt_auth_secret(pubkey);
@@ -120,10 +233,8 @@ mod test_rocket {
             ciphertext: encrypted_auth_secret.ciphertext,
         };
 
-        let context = GrapevineTestContext::init().await;
-
         let username = from.username().clone();
-        let signature = generate_nonce_signature(from);
+        let signature = generate_request_signature(from, "POST", "/user/relationship/add");
 
         let res = context
             .client
@@ -143,16 +254,41 @@ mod test_rocket {
         (code, msg)
     }
 
-    fn generate_nonce_signature(user: &GrapevineAccount) -> String {
-        let nonce_signature = user.sign_nonce();
-        hex::encode(nonce_signature.compress())
+    fn generate_request_signature(user: &GrapevineAccount, method: &str, uri: &str) -> String {
+        let (path, query) = match uri.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (uri, None),
+        };
+        let request_signature = user.sign_request(method, path, query, None);
+        hex::encode(request_signature.compress())
+    }
+
+    async fn nonce_request(
+        context: &GrapevineTestContext,
+        user: &GrapevineAccount,
+        signature: &String,
+        nonce: u64,
+    ) -> (Status, Option<String>, Option<String>) {
+        let res = context
+            .client
+            .post("/user/delegated-proving/false")
+            .header(Header::new("X-Authorization", signature.clone()))
+            .header(Header::new("X-Username", user.username().clone()))
+            .header(Header::new("X-Nonce", nonce.to_string()))
+            .dispatch()
+            .await;
+        let status = res.status();
+        let server_nonce = res.headers().get_one("X-Nonce").map(String::from);
+        (status, server_nonce, res.into_string().await)
     }
 
-    async fn get_account_details_request(user: &mut GrapevineAccount) -> Option<(u64, u64, u64)> {
+    async fn get_account_details_request(
+        user: &mut GrapevineAccount,
+    ) -> Option<AccountDetailsResponse> {
         let context = GrapevineTestContext::init().await;
 
         let username = user.username().clone();
-        let signature = generate_nonce_signature(user);
+        let signature = generate_request_signature(user, "GET", "/user/details");
 
         let res = context
             .client
@@ -161,10 +297,9 @@ mod test_rocket {
             .header(Header::new("X-Username", username))
             .dispatch()
             .await
-            .into_json::<(u64, u64, u64)>()
+            .into_json::<AccountDetailsResponse>()
             .await;
 
-        let _ = user.increment_nonce(None);
         res
     }
 
@@ -172,7 +307,7 @@ mod test_rocket {
         let context = GrapevineTestContext::init().await;
 
         let username = user.username().clone();
-        let signature = generate_nonce_signature(user);
+        let signature = generate_request_signature(user, "GET", "/user/degrees");
 
         context
             .client
@@ -185,15 +320,39 @@ mod test_rocket {
             .await
     }
 
+    async fn get_filtered_degrees_request(
+        user: &mut GrapevineAccount,
+        query: &str,
+    ) -> Option<Vec<DegreeData>> {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let uri = format!("/user/degrees?{}", query);
+        let signature = generate_request_signature(user, "GET", &uri);
+
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await
+            .into_json::<Vec<DegreeData>>()
+            .await;
+
+        res
+    }
+
     async fn get_available_degrees_request(user: &mut GrapevineAccount) -> Option<Vec<String>> {
         let context = GrapevineTestContext::init().await;
 
         let username = user.username().clone();
-        let signature = generate_nonce_signature(user);
+        let uri = format!("/proof/available");
+        let signature = generate_request_signature(user, "GET", &uri);
 
         let degrees = context
             .client
-            .get(format!("/proof/available"))
+            .get(uri)
             .header(Header::new("X-Authorization", signature))
             .header(Header::new("X-Username", username))
             .dispatch()
@@ -201,11 +360,90 @@ mod test_rocket {
             .into_json::<Vec<String>>()
             .await;
 
-        // Increment nonce after request
-        let _ = user.increment_nonce(None);
         degrees
     }
 
+    async fn get_phrase_list_request(user: &mut GrapevineAccount) -> Option<Vec<PhraseSummary>> {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let signature = generate_request_signature(user, "GET", "/proof/list");
+
+        let res = context
+            .client
+            .get("/proof/list")
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await
+            .into_json::<Vec<PhraseSummary>>()
+            .await;
+        res
+    }
+
+    async fn get_available_degrees_from_request(
+        user: &mut GrapevineAccount,
+        origin: &String,
+    ) -> (u16, Option<Vec<String>>) {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let uri = format!("/proof/available?origin={}", origin);
+        let signature = generate_request_signature(user, "GET", &uri);
+
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await;
+        let code = res.status().code;
+        (code, res.into_json::<Vec<String>>().await)
+    }
+
+    async fn get_connected_origins_request(
+        user: &mut GrapevineAccount,
+    ) -> Option<Vec<ConnectedOrigin>> {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let signature = generate_request_signature(user, "GET", "/proof/origins");
+
+        let res = context
+            .client
+            .get("/proof/origins")
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await
+            .into_json::<Vec<ConnectedOrigin>>()
+            .await;
+        res
+    }
+
+    async fn search_phrases_request(
+        user: &mut GrapevineAccount,
+        query: &str,
+    ) -> Option<Vec<PhraseSearchResult>> {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let uri = format!("/proof/search?q={}", query);
+        let signature = generate_request_signature(user, "GET", &uri);
+
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await
+            .into_json::<Vec<PhraseSearchResult>>()
+            .await;
+        res
+    }
+
     async fn get_phrase_connection_request(
         user: &mut GrapevineAccount,
         phrase_index: u32,
@@ -213,36 +451,79 @@ mod test_rocket {
         let context = GrapevineTestContext::init().await;
 
         let username = user.username().clone();
-        let signature = generate_nonce_signature(user);
+        let uri = format!("/proof/connections/{}", phrase_index);
+        let signature = generate_request_signature(user, "GET", &uri);
 
         let res = context
             .client
-            .get(format!("/proof/connections/{}", phrase_index))
+            .get(uri)
             .header(Header::new("X-Authorization", signature))
             .header(Header::new("X-Username", username))
             .dispatch()
             .await
             .into_json::<(u64, Vec<u64>)>()
             .await;
-        let _ = user.increment_nonce(None);
         res
     }
 
-    async fn create_degree_proof_request(
+    async fn get_phrase_full_request(
+        user: &mut GrapevineAccount,
+        phrase_index: u32,
+    ) -> Option<PhraseFullResponse> {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let uri = format!("/proof/phrase/{}/full", phrase_index);
+        let signature = generate_request_signature(user, "GET", &uri);
+
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await
+            .into_json::<PhraseFullResponse>()
+            .await;
+        res
+    }
+
+    async fn get_phrase_proof_request(
+        user: &mut GrapevineAccount,
+        phrase_index: u32,
+    ) -> (Status, String) {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let uri = format!("/proof/phrase/{}/proof", phrase_index);
+        let signature = generate_request_signature(user, "GET", &uri);
+
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await;
+        (res.status(), res.into_string().await.unwrap())
+    }
+
+    async fn build_degree_proof_request(
         prev_id: &str,
         user: &mut GrapevineAccount,
-    ) -> (u16, Option<String>) {
+    ) -> DegreeProofRequest {
         let public_params = use_public_params().unwrap();
         let r1cs = use_r1cs().unwrap();
         let wc_path = use_wasm().unwrap();
         let context = GrapevineTestContext::init().await;
 
         let username = user.username().clone();
-        let signature_params = generate_nonce_signature(user);
+        let uri = format!("/proof/params/{}", prev_id);
+        let signature_params = generate_request_signature(user, "GET", &uri);
 
         let preceding = context
             .client
-            .get(format!("/proof/params/{}", prev_id))
+            .get(uri)
             .header(Header::new("X-Authorization", signature_params))
             .header(Header::new("X-Username", username.clone()))
             .dispatch()
@@ -251,9 +532,6 @@ mod test_rocket {
             .await
             .unwrap();
 
-        // Increment nonce after request
-        let _ = user.increment_nonce(None);
-
         let auth_secret_encrypted = AuthSecretEncrypted {
             ephemeral_key: preceding.ephemeral_key,
             ciphertext: preceding.ciphertext,
@@ -265,13 +543,16 @@ mod test_rocket {
         // decompress proof
         let mut proof = decompress_proof(&preceding.proof);
         // verify proof
-        let previous_output =
-            verify_nova_proof(&proof, &public_params, (preceding.degree * 2) as usize)
-                .unwrap()
-                .0;
+        let previous_output = verify_nova_proof(
+            &proof,
+            &public_params,
+            steps_for_degree(preceding.degree as usize),
+        )
+        .unwrap()
+        .0;
 
         // build nova proof
-        let username_input = vec![auth_secret.username, username.clone()];
+        let username_input = vec![auth_secret.username.clone(), username.clone()];
         let auth_secret_input = vec![auth_secret.auth_secret, user.auth_secret().clone()];
 
         continue_nova_proof(
@@ -286,14 +567,31 @@ mod test_rocket {
 
         let compressed = compress_proof(&proof);
 
-        let body = DegreeProofRequest {
+        DegreeProofRequest {
+            version: REQUEST_VERSION,
             proof: compressed,
             previous: String::from(prev_id),
             degree: preceding.degree + 1,
-        };
-        let serialized: Vec<u8> = bincode::serialize(&body).unwrap();
+        }
+    }
+
+    async fn create_degree_proof_request(
+        prev_id: &str,
+        user: &mut GrapevineAccount,
+    ) -> (u16, Option<String>) {
+        let body = build_degree_proof_request(prev_id, user).await;
+        submit_degree_proof_request(&body, user).await
+    }
+
+    async fn submit_degree_proof_request(
+        body: &DegreeProofRequest,
+        user: &mut GrapevineAccount,
+    ) -> (u16, Option<String>) {
+        let serialized: Vec<u8> = bincode::serialize(body).unwrap();
 
-        let signature_continue = generate_nonce_signature(user);
+        let context = GrapevineTestContext::init().await;
+        let username = user.username().clone();
+        let signature_continue = generate_request_signature(user, "POST", "/proof/degree");
 
         let res = context
             .client
@@ -313,6 +611,35 @@ mod test_rocket {
         (code, msg)
     }
 
+    async fn degree_proof_batch_request(
+        proofs: Vec<DegreeProofRequest>,
+        user: &mut GrapevineAccount,
+    ) -> Vec<DegreeProofBatchResult> {
+        let context = GrapevineTestContext::init().await;
+
+        let body = DegreeProofBatchRequest {
+            version: REQUEST_VERSION,
+            proofs,
+        };
+        let serialized: Vec<u8> = bincode::serialize(&body).unwrap();
+        let username = user.username().clone();
+        let signature = generate_request_signature(user, "POST", "/proof/degree/batch");
+
+        let res = context
+            .client
+            .post("/proof/degree/batch")
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .body(serialized)
+            .dispatch()
+            .await
+            .into_json::<Vec<DegreeProofBatchResult>>()
+            .await
+            .unwrap();
+        let _ = user.increment_nonce(None);
+        res
+    }
+
     /**
      * Create a new phrase
      *
@@ -328,9 +655,25 @@ mod test_rocket {
         description: String,
         user: &mut GrapevineAccount,
     ) -> (u16, String) {
-        // init context
-        let context: GrapevineTestContext = GrapevineTestContext::init().await;
+        tagged_phrase_request(phrase, description, vec![], user).await
+    }
+
+    async fn tagged_phrase_request(
+        phrase: &String,
+        description: String,
+        tags: Vec<String>,
+        user: &mut GrapevineAccount,
+    ) -> (u16, String) {
+        let body = build_phrase_request(phrase, description, tags, user);
+        submit_phrase_request(&body, user).await
+    }
 
+    fn build_phrase_request(
+        phrase: &String,
+        description: String,
+        tags: Vec<String>,
+        user: &GrapevineAccount,
+    ) -> PhraseRequest {
         // create the phrase proof
         let username_vec = vec![user.username().clone()];
         let auth_secret_vec = vec![user.auth_secret().clone()];
@@ -352,17 +695,35 @@ mod test_rocket {
         // compress proof
         let compressed = compress_proof(&proof);
         // encrypt phrase
-        let ciphertext = user.encrypt_phrase(&phrase);
+        let ciphertext = user.encrypt_phrase(&phrase).unwrap();
 
-        // Mock http request
-        let body = PhraseRequest {
+        PhraseRequest {
+            version: REQUEST_VERSION,
             proof: compressed,
             ciphertext,
             description,
-        };
-        let serialized: Vec<u8> = bincode::serialize(&body).unwrap();
+            tags,
+        }
+    }
+
+    async fn submit_phrase_request(
+        body: &PhraseRequest,
+        user: &mut GrapevineAccount,
+    ) -> (u16, String) {
+        // init context
+        let context: GrapevineTestContext = GrapevineTestContext::init().await;
+        submit_phrase_request_to(&context, body, user).await
+    }
+
+    async fn submit_phrase_request_to(
+        context: &GrapevineTestContext,
+        body: &PhraseRequest,
+        user: &mut GrapevineAccount,
+    ) -> (u16, String) {
+        // Mock http request
+        let serialized: Vec<u8> = bincode::serialize(body).unwrap();
         let username = user.username().clone();
-        let signature = generate_nonce_signature(user);
+        let signature = generate_request_signature(user, "POST", "/proof/phrase");
         let res = context
             .client
             .post("/proof/phrase")
@@ -382,1427 +743,5009 @@ mod test_rocket {
         (code, msg)
     }
 
-    async fn create_user_request(
+    async fn download_proof_request(
         context: &GrapevineTestContext,
-        request: &CreateUserRequest,
-    ) -> String {
-        context
+        user: &mut GrapevineAccount,
+        oid: &String,
+    ) -> (Status, Option<ContentType>, Vec<u8>) {
+        let username = user.username().clone();
+        let uri = format!("/proof/{}", oid);
+        let signature = generate_request_signature(user, "GET", &uri);
+        let res = context
             .client
-            .post("/user/create")
-            .header(ContentType::JSON)
-            .body(serde_json::json!(request).to_string())
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
             .dispatch()
-            .await
-            .into_string()
-            .await
-            .unwrap()
-    }
+            .await;
 
-    async fn get_user_request(context: &GrapevineTestContext, username: String) -> Option<User> {
-        context
-            .client
-            .get(format!("/user/{}", username))
-            .dispatch()
-            .await
-            .into_json::<User>()
-            .await
+        let status = res.status();
+        let content_type = res.content_type();
+        (status, content_type, res.into_bytes().await.unwrap())
     }
 
-    async fn get_relationships_request(
+    async fn download_encoded_proof_request(
         context: &GrapevineTestContext,
         user: &mut GrapevineAccount,
-        active: bool,
-    ) -> Option<Vec<String>> {
+        oid: &String,
+        accept: &str,
+    ) -> (Option<String>, Vec<u8>) {
         let username = user.username().clone();
-        let signature = generate_nonce_signature(user);
-        let route = if active { "active" } else { "pending" };
+        let uri = format!("/proof/{}", oid);
+        let signature = generate_request_signature(user, "GET", &uri);
         let res = context
             .client
-            .get(format!("/user/relationship/{}", route))
+            .get(uri)
             .header(Header::new("X-Authorization", signature))
             .header(Header::new("X-Username", username))
+            .header(Header::new("X-Accept-Proof-Encoding", accept.to_string()))
             .dispatch()
-            .await
-            .into_json::<Vec<String>>()
             .await;
 
-        // Increment nonce after request
-        let _ = user.increment_nonce(None);
-        res
+        let encoding = res.headers().get_one("X-Proof-Encoding").map(String::from);
+        (encoding, res.into_bytes().await.unwrap())
     }
 
-    async fn reject_relationship_request(
+    async fn get_known_request(
         context: &GrapevineTestContext,
-        from: &mut GrapevineAccount,
-        to: &String,
-    ) -> (u16, Option<String>) {
-        let username = from.username().clone();
-        let signature = generate_nonce_signature(from);
-
-        let res = context
+        user: &mut GrapevineAccount,
+    ) -> Vec<DegreeData> {
+        let username = user.username().clone();
+        let uri = String::from("/proof/known");
+        let signature = generate_request_signature(user, "GET", &uri);
+        let known = context
             .client
-            .post(format!("/user/relationship/reject/{}", to))
+            .get(uri)
             .header(Header::new("X-Authorization", signature))
             .header(Header::new("X-Username", username))
             .dispatch()
-            .await;
+            .await
+            .into_json::<Vec<DegreeData>>()
+            .await
+            .unwrap();
 
-        let code = res.status().code;
+        known
+    }
+
+    async fn phrase_ciphertext_request(
+        context: &GrapevineTestContext,
+        user: &mut GrapevineAccount,
+        phrase_index: u32,
+        ciphertext: [u8; 192],
+    ) -> Status {
+        let username = user.username().clone();
+        let uri = format!("/proof/phrase/{}/ciphertext", phrase_index);
+        let signature = generate_request_signature(user, "POST", &uri);
+        let status = context
+            .client
+            .post(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .json(&PhraseCiphertextRequest { ciphertext })
+            .dispatch()
+            .await
+            .status();
+
+        // Increment nonce after request
+        let _ = user.increment_nonce(None);
+        status
+    }
+
+    async fn get_proof_children_request(
+        context: &GrapevineTestContext,
+        user: &mut GrapevineAccount,
+        oid: &String,
+    ) -> (Status, Option<Vec<ProofChild>>) {
+        let username = user.username().clone();
+        let uri = format!("/proof/{}/children", oid);
+        let signature = generate_request_signature(user, "GET", &uri);
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await;
+
+        (res.status(), res.into_json::<Vec<ProofChild>>().await)
+    }
+
+    async fn create_user_request(
+        context: &GrapevineTestContext,
+        request: &CreateUserRequest,
+    ) -> String {
+        context
+            .client
+            .post("/user/create")
+            .header(ContentType::JSON)
+            .body(serde_json::json!(request).to_string())
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap()
+    }
+
+    async fn phrase_commitment_request<T: serde::Serialize>(
+        context: &GrapevineTestContext,
+        user: &mut GrapevineAccount,
+        action: &str,
+        body: &T,
+    ) -> (Status, Option<String>) {
+        let username = user.username().clone();
+        let uri = format!("/proof/phrase/{}", action);
+        let signature = generate_request_signature(user, "POST", &uri);
+        let res = context
+            .client
+            .post(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .json(body)
+            .dispatch()
+            .await;
+
+        // Increment nonce after request
+        let _ = user.increment_nonce(None);
+        (res.status(), res.into_string().await)
+    }
+
+    async fn get_user_request(context: &GrapevineTestContext, username: String) -> Option<User> {
+        context
+            .client
+            .get(format!("/user/{}", username))
+            .dispatch()
+            .await
+            .into_json::<User>()
+            .await
+    }
+
+    async fn get_relationships_request(
+        context: &GrapevineTestContext,
+        user: &mut GrapevineAccount,
+        active: bool,
+    ) -> Option<Vec<String>> {
+        let username = user.username().clone();
+        let route = if active { "active" } else { "pending" };
+        let uri = format!("/user/relationship/{}", route);
+        let signature = generate_request_signature(user, "GET", &uri);
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await
+            .into_json::<Vec<String>>()
+            .await;
+
+        res
+    }
+
+    async fn get_relationship_auth_secret_request(
+        context: &GrapevineTestContext,
+        user: &mut GrapevineAccount,
+        sender: &String,
+    ) -> (Status, Option<AuthSecretEncrypted>) {
+        let username = user.username().clone();
+        let uri = format!("/user/relationship/auth/{}", sender);
+        let signature = generate_request_signature(user, "GET", &uri);
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await;
+
+        (res.status(), res.into_json::<AuthSecretEncrypted>().await)
+    }
+
+    async fn get_new_relationships_request(
+        context: &GrapevineTestContext,
+        user: &mut GrapevineAccount,
+        since: u32,
+    ) -> Option<Vec<String>> {
+        let username = user.username().clone();
+        let uri = format!("/user/relationship/new?since={}", since);
+        let signature = generate_request_signature(user, "GET", &uri);
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await
+            .into_json::<Vec<String>>()
+            .await;
+
+        res
+    }
+
+    async fn get_outbound_relationships_request(
+        context: &GrapevineTestContext,
+        user: &mut GrapevineAccount,
+    ) -> Option<Vec<String>> {
+        let username = user.username().clone();
+        let uri = "/user/relationship/outbound";
+        let signature = generate_request_signature(user, "GET", uri);
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await
+            .into_json::<Vec<String>>()
+            .await;
+
+        res
+    }
+
+    async fn get_mutual_relationships_request(
+        context: &GrapevineTestContext,
+        user: &mut GrapevineAccount,
+        other: &String,
+    ) -> (Status, Option<Vec<String>>) {
+        let username = user.username().clone();
+        let uri = format!("/user/mutual/{}", other);
+        let signature = generate_request_signature(user, "GET", &uri);
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await;
+
+        (res.status(), res.into_json::<Vec<String>>().await)
+    }
+
+    async fn reject_relationship_request(
+        context: &GrapevineTestContext,
+        from: &mut GrapevineAccount,
+        to: &String,
+    ) -> (u16, Option<String>) {
+        let username = from.username().clone();
+        let uri = format!("/user/relationship/reject/{}", to);
+        let signature = generate_request_signature(from, "POST", &uri);
+
+        let res = context
+            .client
+            .post(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await;
+
+        let code = res.status().code;
+        let msg = res.into_string().await;
+
+        // Increment nonce after request
+        let _ = from.increment_nonce(None);
+
+        (code, msg)
+    }
+
+    async fn deactivate_proof_request(
+        user: &mut GrapevineAccount,
+        oid: &String,
+    ) -> (u16, Option<String>) {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let uri = format!("/proof/deactivate/{}", oid);
+        let signature = generate_request_signature(user, "POST", &uri);
+
+        let res = context
+            .client
+            .post(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await;
+
+        let code = res.status().code;
         let msg = res.into_string().await;
 
-        // Increment nonce after request
-        let _ = from.increment_nonce(None);
+        // Increment nonce after request
+        let _ = user.increment_nonce(None);
+
+        (code, msg)
+    }
+
+    async fn get_phrases_by_tag_request(
+        user: &mut GrapevineAccount,
+        tag: &str,
+    ) -> Option<Vec<PhraseSearchResult>> {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let uri = format!("/proof/phrases/by-tag/{}", tag);
+        let signature = generate_request_signature(user, "GET", &uri);
+
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await
+            .into_json::<Vec<PhraseSearchResult>>()
+            .await;
+        res
+    }
+
+    async fn update_phrase_tags_request(
+        user: &mut GrapevineAccount,
+        phrase_index: u32,
+        tags: Vec<String>,
+    ) -> u16 {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let uri = format!("/proof/phrase/{}/tags", phrase_index);
+        let signature = generate_request_signature(user, "POST", &uri);
+        let body = PhraseTagsRequest { tags };
+
+        let res = context
+            .client
+            .post(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .json(&body)
+            .dispatch()
+            .await;
+
+        // Increment nonce after request
+        let _ = user.increment_nonce(None);
+        res.status().code
+    }
+
+    async fn update_phrase_visibility_request(
+        user: &mut GrapevineAccount,
+        phrase_index: u32,
+        visibility: PhraseVisibility,
+    ) -> u16 {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let uri = format!("/proof/phrase/{}/visibility", phrase_index);
+        let signature = generate_request_signature(user, "POST", &uri);
+        let body = PhraseVisibilityRequest { visibility };
+
+        let res = context
+            .client
+            .post(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .json(&body)
+            .dispatch()
+            .await;
+
+        // Increment nonce after request
+        let _ = user.increment_nonce(None);
+        res.status().code
+    }
+
+    async fn get_phrase_request(
+        user: &mut GrapevineAccount,
+        phrase_index: u32,
+    ) -> (u16, Option<DegreeData>) {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let uri = format!("/proof/phrase/{}", phrase_index);
+        let signature = generate_request_signature(user, "GET", &uri);
+
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await;
+
+        (res.status().code, res.into_json::<DegreeData>().await)
+    }
+
+    async fn update_phrase_description_request(
+        user: &mut GrapevineAccount,
+        phrase_index: u32,
+        description: String,
+    ) -> (u16, Option<String>) {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let uri = format!("/proof/phrase/{}/description", phrase_index);
+        let signature = generate_request_signature(user, "POST", &uri);
+        let body = PhraseDescriptionRequest { description };
+
+        let res = context
+            .client
+            .post(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .json(&body)
+            .dispatch()
+            .await;
+
+        let code = res.status().code;
+        let msg = res.into_string().await;
+
+        // Increment nonce after request
+        let _ = user.increment_nonce(None);
+
+        (code, msg)
+    }
+
+    /**
+     * Configure the admin token read by the Admin guard (only read once, so always the same value)
+     *
+     * @return - the admin token to send in the X-Admin-Token header
+     */
+    fn admin_token() -> String {
+        let token = String::from("grapevine_test_admin_token");
+        std::env::set_var("GRAPEVINE_ADMIN_TOKEN", &token);
+        token
+    }
+
+    async fn migrate_proofs_request(after: Option<String>, limit: i64) -> ProofMigrationResponse {
+        let context = GrapevineTestContext::init().await;
+        let url = match after {
+            Some(after) => format!("/proof/admin/migrate?after={}&limit={}", after, limit),
+            None => format!("/proof/admin/migrate?limit={}", limit),
+        };
+        context
+            .client
+            .post(url)
+            .header(Header::new("X-Admin-Token", admin_token()))
+            .dispatch()
+            .await
+            .into_json::<ProofMigrationResponse>()
+            .await
+            .unwrap()
+    }
+
+    async fn scan_proof_integrity_request() -> Vec<String> {
+        let context = GrapevineTestContext::init().await;
+        context
+            .client
+            .get("/proof/admin/integrity")
+            .header(Header::new("X-Admin-Token", admin_token()))
+            .dispatch()
+            .await
+            .into_json::<Vec<String>>()
+            .await
+            .unwrap()
+    }
+
+    async fn export_proofs_request() -> Vec<u8> {
+        let context = GrapevineTestContext::init().await;
+        context
+            .client
+            .get("/proof/admin/export")
+            .header(Header::new("X-Admin-Token", admin_token()))
+            .dispatch()
+            .await
+            .into_bytes()
+            .await
+            .unwrap()
+    }
+
+    async fn recompute_stats_request(username: &String) -> Option<AccountDetailsResponse> {
+        let context = GrapevineTestContext::init().await;
+        context
+            .client
+            .post(format!("/user/admin/recompute-stats/{}", username))
+            .header(Header::new("X-Admin-Token", admin_token()))
+            .dispatch()
+            .await
+            .into_json::<AccountDetailsResponse>()
+            .await
+    }
+
+    async fn orphaned_relationships_request(repair: bool) -> Vec<String> {
+        let context = GrapevineTestContext::init().await;
+        let request = match repair {
+            true => context
+                .client
+                .post("/user/admin/orphaned-relationships/repair"),
+            false => context.client.get("/user/admin/orphaned-relationships"),
+        };
+        request
+            .header(Header::new("X-Admin-Token", admin_token()))
+            .dispatch()
+            .await
+            .into_json::<Vec<String>>()
+            .await
+            .unwrap()
+    }
+
+    async fn import_proofs_request(backup: Vec<u8>) -> (u16, Option<String>) {
+        let context = GrapevineTestContext::init().await;
+        let res = context
+            .client
+            .post("/proof/admin/import")
+            .header(Header::new("X-Admin-Token", admin_token()))
+            .body(backup)
+            .dispatch()
+            .await;
+        (res.status().code, res.into_string().await)
+    }
+
+    async fn get_circuit_info_request() -> CircuitInfoResponse {
+        let context = GrapevineTestContext::init().await;
+        context
+            .client
+            .get("/proof/circuit-info")
+            .dispatch()
+            .await
+            .into_json::<CircuitInfoResponse>()
+            .await
+            .unwrap()
+    }
+
+    #[cfg(feature = "delegated-proving")]
+    async fn set_delegated_proving_request(
+        user: &mut GrapevineAccount,
+        enabled: bool,
+    ) -> (u16, Option<String>) {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let uri = format!("/user/delegated-proving/{}", enabled);
+        let signature = generate_request_signature(user, "POST", &uri);
+
+        let res = context
+            .client
+            .post(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await;
+
+        let code = res.status().code;
+        let msg = res.into_string().await;
+
+        // Increment nonce after request
+        let _ = user.increment_nonce(None);
+
+        (code, msg)
+    }
+
+    async fn set_guardians_request(
+        user: &mut GrapevineAccount,
+        guardians: Vec<String>,
+        threshold: u8,
+    ) -> (u16, Option<String>) {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let signature = generate_request_signature(user, "POST", "/user/guardians");
+        let body = GuardiansRequest {
+            guardians,
+            threshold,
+        };
+
+        let res = context
+            .client
+            .post("/user/guardians")
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .json(&body)
+            .dispatch()
+            .await;
+
+        let code = res.status().code;
+        let msg = res.into_string().await;
+
+        // Increment nonce after request
+        let _ = user.increment_nonce(None);
+
+        (code, msg)
+    }
+
+    async fn recover_account_request(request: &RecoveryRequest) -> (u16, Option<String>) {
+        let context = GrapevineTestContext::init().await;
+
+        let res = context
+            .client
+            .post("/user/recover")
+            .json(request)
+            .dispatch()
+            .await;
+
+        let code = res.status().code;
+        let msg = res.into_string().await;
+        (code, msg)
+    }
+
+    #[cfg(feature = "delegated-proving")]
+    async fn get_proving_data_request(oid: &str, user: &mut GrapevineAccount) -> ProvingData {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let uri = format!("/proof/params/{}", oid);
+        let signature = generate_request_signature(user, "GET", &uri);
+
+        let proving_data = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .dispatch()
+            .await
+            .into_json::<ProvingData>()
+            .await
+            .unwrap();
+
+        proving_data
+    }
+
+    #[cfg(feature = "delegated-proving")]
+    async fn delegated_degree_proof_request(
+        prev_id: &str,
+        auth_secrets: [[u8; 32]; 2],
+        user: &mut GrapevineAccount,
+    ) -> (u16, Option<String>) {
+        let context = GrapevineTestContext::init().await;
+
+        let username = user.username().clone();
+        let signature = generate_request_signature(user, "POST", "/proof/degree/delegated");
+        let body = DelegatedDegreeProofRequest {
+            version: REQUEST_VERSION,
+            previous: String::from(prev_id),
+            auth_secrets,
+        };
+        let serialized: Vec<u8> = bincode::serialize(&body).unwrap();
+
+        let res = context
+            .client
+            .post("/proof/degree/delegated")
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .body(serialized)
+            .dispatch()
+            .await;
+
+        let code = res.status().code;
+        let msg = res.into_string().await;
+
+        // Increment nonce after request
+        let _ = user.increment_nonce(None);
+
+        (code, msg)
+    }
+
+    #[rocket::async_test]
+    async fn test_proof_reordering_with_3_proof_chain() {
+        let context = GrapevineTestContext::init().await;
+
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        // Create test users
+        let mut users = vec![
+            GrapevineAccount::new(String::from("User_A")),
+            GrapevineAccount::new(String::from("User_B")),
+            GrapevineAccount::new(String::from("User_C")),
+        ];
+
+        for i in 0..users.len() {
+            let request = users[i].create_user_request();
+            create_user_request(&context, &request).await;
+        }
+
+        // Create phrase a phrase as User A
+        let phrase = String::from("The sheep waited patiently in the field");
+        let description = String::from("Sheep have no patience");
+        _ = phrase_request(&phrase, description, &mut users[0]).await;
+
+        // Add relationship between User A and User B, B and C
+        for i in 0..users.len() - 1 {
+            // Remove users from vector to reference
+            let mut preceding = users.remove(i);
+            // Proceeding is now an index below after removal
+            let mut proceeding = users.remove(i);
+            add_relationship_request(&mut preceding, &mut proceeding).await;
+            add_relationship_request(&mut proceeding, &mut preceding).await;
+
+            // Create degree proofs: A <- B <- C
+            let proofs = get_available_degrees_request(&mut proceeding)
+                .await
+                .unwrap();
+
+            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+
+            // Add users back to vector
+            users.insert(i, preceding);
+            users.insert(i + 1, proceeding);
+        }
+        println!("8====================================D");
+
+        let mut user_a = users.remove(0);
+        // User C is now an index below after removal
+        let mut user_c = users.remove(1);
+
+        // Establish relationship between A and C now
+        add_relationship_request(&mut user_a, &mut user_c).await;
+        add_relationship_request(&mut user_c, &mut user_a).await;
+
+        // Check that C now has an available degree request
+        let proofs_c = get_available_degrees_request(&mut user_c).await.unwrap();
+
+        // Create new degree proof between A and C
+        create_degree_proof_request(&proofs_c[0], &mut user_c).await;
+    }
+
+    #[rocket::async_test]
+    #[ignore]
+    async fn test_proof_reordering_with_4_proof_chain() {
+        let context = GrapevineTestContext::init().await;
+
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        // Create test users
+        let mut users = vec![
+            GrapevineAccount::new(String::from("User_A")),
+            GrapevineAccount::new(String::from("User_B")),
+            GrapevineAccount::new(String::from("User_C")),
+            GrapevineAccount::new(String::from("User_D")),
+        ];
+
+        for i in 0..users.len() {
+            let request = users[i].create_user_request();
+            create_user_request(&context, &request).await;
+        }
+
+        // Create phrase a phrase as User A
+        let phrase = String::from("And that's the waaaayyyy the news goes");
+        let description = String::from("Wubalubadubdub!");
+        _ = phrase_request(&phrase, description, &mut users[0]).await;
+
+        // Create relationships and degree proofs: A <- B <- C <- D
+        for i in 0..users.len() - 1 {
+            // Remove users from vector to reference
+            let mut preceding = users.remove(i);
+            // Proceeding is now an index below after removal
+            let mut proceeding = users.remove(i);
+
+            add_relationship_request(&mut preceding, &mut proceeding).await;
+            add_relationship_request(&mut proceeding, &mut preceding).await;
+
+            let proofs = get_available_degrees_request(&mut proceeding)
+                .await
+                .unwrap();
+            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+
+            // Add users back to vector
+            users.insert(i, preceding);
+            users.insert(i + 1, proceeding);
+        }
+
+        let mut user_a = users.remove(0);
+        let mut user_c = users.remove(1);
+        // Establish relationship between A and C now
+        add_relationship_request(&mut user_a, &mut user_c).await;
+        add_relationship_request(&mut user_c, &mut user_a).await;
+
+        // Check that C now has an available degree request
+        let proofs_c = get_available_degrees_request(&mut user_c).await.unwrap();
+        // Create new deree proof between A and C
+        create_degree_proof_request(&proofs_c[0], &mut user_c).await;
+
+        users.insert(0, user_a);
+        users.insert(2, user_c);
+
+        // Check avaiable degree with D and perform necessary update
+        let proofs_d = get_available_degrees_request(&mut users[3]).await.unwrap();
+        // Create new degree proof between C and D
+        create_degree_proof_request(&proofs_d[0], &mut users[3]).await;
+    }
+
+    #[rocket::async_test]
+    #[ignore]
+    async fn test_proof_reordering_with_5_proof_chain() {
+        let context = GrapevineTestContext::init().await;
+
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        // Create test users
+        let mut users = vec![
+            GrapevineAccount::new(String::from("User_A")),
+            GrapevineAccount::new(String::from("User_B")),
+            GrapevineAccount::new(String::from("User_C")),
+            GrapevineAccount::new(String::from("User_D")),
+            GrapevineAccount::new(String::from("User_E")),
+        ];
+
+        for i in 0..users.len() {
+            let request = users[i].create_user_request();
+            create_user_request(&context, &request).await;
+        }
+
+        // Create phrase a phrase as User A
+        let phrase = String::from("You are what you eat");
+        let description = String::from("Mediocre cryptographer");
+        _ = phrase_request(&phrase, description, &mut users[0]).await;
+
+        // Add relationship and degree proofs: A <- B, B <- C
+        for i in 0..2 {
+            // Remove users from vector to reference
+            let mut preceding = users.remove(i);
+            // Proceeding is now an index below after removal
+            let mut proceeding = users.remove(i);
+
+            add_relationship_request(&mut preceding, &mut proceeding).await;
+            add_relationship_request(&mut proceeding, &mut preceding).await;
+
+            let proofs = get_available_degrees_request(&mut proceeding)
+                .await
+                .unwrap();
+            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+
+            // Add users back to vector
+            users.insert(i, preceding);
+            users.insert(i + 1, proceeding);
+        }
+
+        // Add relationship and degree proofs: C <- D, C <- E
+        for i in 0..2 {
+            let mut preceding = users.remove(2);
+            // Proceeding is now an index below after removal
+            let mut proceeding = users.remove(i + 2);
+
+            add_relationship_request(&mut preceding, &mut proceeding).await;
+            add_relationship_request(&mut proceeding, &mut preceding).await;
+
+            let proofs = get_available_degrees_request(&mut proceeding)
+                .await
+                .unwrap();
+            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+
+            users.insert(2, preceding);
+            users.insert(i + 3, proceeding);
+        }
+
+        // Set every proof to degree 2
+        for i in 0..3 {
+            let mut preceding = users.remove(0);
+            // Proceeding is now an index below after removal
+            let mut proceeding = users.remove(i + 1);
+
+            add_relationship_request(&mut preceding, &mut proceeding).await;
+            add_relationship_request(&mut proceeding, &mut preceding).await;
+
+            let proofs = get_available_degrees_request(&mut proceeding)
+                .await
+                .unwrap();
+            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+
+            users.insert(0, preceding);
+            users.insert(i + 2, proceeding);
+        }
+    }
+
+    #[rocket::async_test]
+    #[ignore]
+    async fn test_proof_reordering_with_27_proof_chain() {
+        // should this test pass?
+        // Start with tree structure and eventually have each user connect directly to A
+        let context = GrapevineTestContext::init().await;
+
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let mut users: Vec<GrapevineAccount> = vec![];
+        // Create test users
+        for i in 0..27 {
+            let usersname = format!("User_{}", i);
+            let user = GrapevineAccount::new(usersname);
+            let creation_request = user.create_user_request();
+            create_user_request(&context, &creation_request).await;
+            users.push(user);
+        }
+
+        // Create phrase a phrase as User A
+        let phrase =
+            String::from("They're bureaucrats. I don't respect them. Just keep shooting Morty.");
+        let description = String::from("It's a figure if speech Morty.");
+        _ = phrase_request(&phrase, description, &mut users[0]).await;
+
+        println!("Started");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        // Create relationships and degree 2 proofs
+        for i in 0..2 {
+            // Remove users from vector to reference
+            let mut preceding = users.remove(0);
+            // Proceeding is now an index below after removal
+            let mut proceeding = users.remove(i);
+
+            add_relationship_request(&mut preceding, &mut proceeding).await;
+            add_relationship_request(&mut proceeding, &mut preceding).await;
+
+            let proofs = get_available_degrees_request(&mut proceeding)
+                .await
+                .unwrap();
+
+            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+            // Add users back to vector
+            users.insert(0, preceding);
+            users.insert(i + 1, proceeding);
+        }
+        println!("Degree 2");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        // Create relationships and degree 3 proofs
+        for i in 0..6 {
+            let preceding_index = 1 + i / 3;
+
+            // Remove users from vector to reference
+            let mut preceding = users.remove(preceding_index);
+            // Proceeding is now an index below after removal
+            let mut proceeding = users.remove(i + 2);
+
+            add_relationship_request(&mut preceding, &mut proceeding).await;
+            add_relationship_request(&mut proceeding, &mut preceding).await;
+
+            let proofs = get_available_degrees_request(&mut proceeding)
+                .await
+                .unwrap();
+
+            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+            // Add users back to vector
+            users.insert(preceding_index, preceding);
+            users.insert(i + 2, proceeding);
+        }
+        println!("Degree 3");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        // Create relationships and degree 4 proofs
+        for i in 0..18 {
+            let preceding_index = 3 + i / 3;
+            println!("Index: {}", preceding_index);
+            // Remove users from vector to reference
+            let mut preceding = users.remove(preceding_index);
+            // Proceeding is now an index below after removal
+            let mut proceeding = users.remove(i + 8);
+
+            add_relationship_request(&mut preceding, &mut proceeding).await;
+            add_relationship_request(&mut proceeding, &mut preceding).await;
+
+            let proofs = get_available_degrees_request(&mut proceeding)
+                .await
+                .unwrap();
+
+            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+
+            // Add users back to vector
+            users.insert(preceding_index, preceding);
+            users.insert(i + 9, proceeding);
+        }
+
+        println!("Degree 4");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        // Bring all proofs to degree 2
+        for i in 0..24 {
+            // Remove users from vector to reference
+            let mut preceding = users.remove(0);
+            // Proceeding is now an index below after removal
+            let mut proceeding = users.remove(i + 2);
+            add_relationship_request(&mut preceding, &mut proceeding).await;
+            add_relationship_request(&mut proceeding, &mut preceding).await;
+
+            let proofs = get_available_degrees_request(&mut proceeding)
+                .await
+                .unwrap();
+
+            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+
+            // Add users back to vector
+            users.insert(0, preceding);
+            users.insert(i + 3, proceeding);
+        }
+        println!("Done");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+        println!("================");
+    }
+
+    #[rocket::async_test]
+    async fn test_get_degrees_refactor() {
+        let context = GrapevineTestContext::init().await;
+
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        // Create test users
+        let mut users = vec![
+            GrapevineAccount::new(String::from("User_A")),
+            GrapevineAccount::new(String::from("User_B")),
+            GrapevineAccount::new(String::from("User_C")),
+            GrapevineAccount::new(String::from("User_D")),
+        ];
+
+        for i in 0..users.len() {
+            let request = users[i].create_user_request();
+            create_user_request(&context, &request).await;
+        }
+
+        // Create phrase a phrase as User A
+        let phrase = String::from("You are what you eat");
+        let description = String::from("Mediocre cryptographer");
+        _ = phrase_request(&phrase, description, &mut users[0]).await;
+
+        // Add relationship and degree proofs: A <- B, B <- C, C <- D
+        for i in 0..3 {
+            // Remove users from vector to reference
+            let mut preceding = users.remove(i);
+            // Proceeding is now an index below after removal
+            let mut proceeding = users.remove(i);
+
+            add_relationship_request(&mut preceding, &mut proceeding).await;
+            add_relationship_request(&mut proceeding, &mut preceding).await;
+
+            let proofs = get_available_degrees_request(&mut proceeding)
+                .await
+                .unwrap();
+            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+
+            // Add users back to vector
+            users.insert(i, preceding);
+            users.insert(i + 1, proceeding);
+        }
+
+        // Get degree proofs for user C
+        let degrees = get_all_degrees(&users[2]).await;
+        println!("Degrees: {:?}", degrees);
+        let degrees = get_all_degrees(&users[1]).await;
+        println!("Degrees B: {:?}", degrees);
+        let degrees = get_all_degrees(&users[0]).await;
+        println!("Degrees A: {:?}", degrees);
+    }
+
+    #[rocket::async_test]
+    async fn test_inactive_relationships_hidden_in_degree_return() {
+        let context = GrapevineTestContext::init().await;
+
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        // Create test users
+        let mut users = vec![
+            GrapevineAccount::new(String::from("User_A")),
+            GrapevineAccount::new(String::from("User_B")),
+            GrapevineAccount::new(String::from("User_C")),
+            GrapevineAccount::new(String::from("User_D")),
+            GrapevineAccount::new(String::from("User_E")),
+            GrapevineAccount::new(String::from("User_F")),
+        ];
+
+        for i in 0..users.len() {
+            let request = users[i].create_user_request();
+            create_user_request(&context, &request).await;
+        }
+
+        // Create phrase a phrase as User A
+        let phrase = String::from("The sheep waited patiently in the field");
+        let description = String::from("Sheep have no patience");
+        _ = phrase_request(&phrase, description, &mut users[0]).await;
+
+        // Add relationship and degree proofs
+        for i in 0..users.len() - 1 {
+            // Remove users from vector to reference
+            let mut preceding = users.remove(i);
+            // Proceeding is now an index below after removal
+            let mut proceeding = users.remove(i);
+
+            add_relationship_request(&mut preceding, &mut proceeding).await;
+            add_relationship_request(&mut proceeding, &mut preceding).await;
+
+            let proofs = get_available_degrees_request(&mut proceeding)
+                .await
+                .unwrap();
+            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+
+            // Add users back to vector
+            users.insert(i, preceding);
+            users.insert(i + 1, proceeding);
+        }
+
+        // Link 3 middle users to A
+        for i in 0..3 {
+            // Remove users from vector to reference
+            let mut preceding = users.remove(0);
+            // Proceeding is now an index below after removal
+            let mut proceeding = users.remove(i + 1);
+
+            add_relationship_request(&mut preceding, &mut proceeding).await;
+            add_relationship_request(&mut proceeding, &mut preceding).await;
+
+            let proofs = get_available_degrees_request(&mut proceeding)
+                .await
+                .unwrap();
+            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+
+            // Add users back to vector
+            users.insert(0, preceding);
+            users.insert(i + 2, proceeding);
+        }
+
+        // Get degrees
+        let degrees = get_all_degrees(&mut users[3]).await;
+        assert_eq!(
+            degrees.unwrap().len(),
+            1,
+            "Inactive degrees should have gotten removed from user's list of degree proofs"
+        )
+    }
+
+    #[rocket::async_test]
+    async fn test_create_user_wrong_signature() {
+        // initiate context
+        let context = GrapevineTestContext::init().await;
+        // generate two accounts
+        let account_1 = GrapevineAccount::new(String::from("userA1"));
+        let account_2 = GrapevineAccount::new(String::from("userA2"));
+        // generate a signature from account 2
+        let bad_sig = account_2.sign_username().compress();
+        // generate a "Create User" http request from account 1
+        let mut request = account_1.create_user_request();
+        // set the signature for creating account 1 to be the signature of account 2
+        request.signature = bad_sig;
+
+        // check response failure
+        let msg = create_user_request(&context, &request).await;
+        assert!(
+            msg.contains("Could not verify user creation signature"),
+            "Request should fail due to mismatched msg"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_create_user_invalid_pubkey() {
+        let context = GrapevineTestContext::init().await;
+        // find a point on the curve that is outside of the prime order subgroup
+        let outside_subgroup = (2u8..)
+            .map(|y| {
+                let mut bytes = [0u8; 32];
+                bytes[0] = y;
+                bytes
+            })
+            .find(|bytes| {
+                babyjubjub_rs::decompress_point(*bytes).is_ok()
+                    && grapevine_common::crypto::decompress_pubkey(bytes).is_err()
+            })
+            .unwrap();
+        let mut request = GrapevineAccount::new(String::from("userA1")).create_user_request();
+        request.pubkey = outside_subgroup;
+
+        let msg = create_user_request(&context, &request).await;
+        assert!(
+            msg.contains("InvalidPubkey"),
+            "Request should fail due to the pubkey not being in the subgroup"
+        );
+        assert!(get_user_request(&context, String::from("userA1"))
+            .await
+            .is_none());
+    }
+
+    #[rocket::async_test]
+    async fn test_username_exceeding_character_limit() {
+        let context = GrapevineTestContext::init().await;
+
+        let account = GrapevineAccount::new(String::from("userA1"));
+
+        let mut request = account.create_user_request();
+
+        let username = "fake_username_1234567890_abcdef";
+
+        request.username = username.to_string();
+
+        let msg = create_user_request(&context, &request).await;
+
+        let condition = msg.contains("UsernameTooLong") && msg.contains(username);
+
+        assert!(
+            condition,
+            "Username should be marked as exceeding 30 characters"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_username_with_non_ascii_characters() {
+        let context = GrapevineTestContext::init().await;
+
+        let username = "😍";
+
+        let account = GrapevineAccount::new(String::from(username));
+
+        let request = account.create_user_request();
+
+        let msg = create_user_request(&context, &request).await;
+
+        let condition = msg.contains("UsernameNotAscii") && msg.contains(username);
+
+        assert!(condition, "User should be created");
+    }
+
+    #[rocket::async_test]
+    async fn test_username_empty_or_whitespace() {
+        let context = GrapevineTestContext::init().await;
+
+        for username in ["", " ", "\t"] {
+            let account = GrapevineAccount::new(String::from(username));
+            let msg = create_user_request(&context, &account.create_user_request()).await;
+            assert!(
+                msg.contains("UsernameEmpty"),
+                "Username {:?} should be rejected as empty",
+                username
+            );
+        }
+    }
+
+    #[rocket::async_test]
+    async fn test_successful_user_creation() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let username = String::from("username_successful_creation");
+
+        let context = GrapevineTestContext::init().await;
+
+        let account = GrapevineAccount::new(username.clone());
+
+        let request = account.create_user_request();
+
+        assert_eq!(
+            create_user_request(&context, &request).await,
+            "User succefully created",
+            "User should be created"
+        );
+
+        // Check that user was stored in DB
+        let user = get_user_request(&context, username).await;
+        assert!(user.is_some(), "User should be stored inside of MongoDB");
+    }
+
+    #[rocket::async_test]
+    async fn test_duplicate_user() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let username = String::from("username_duplicate_user");
+
+        let context = GrapevineTestContext::init().await;
+
+        let account = GrapevineAccount::new(username.clone());
+
+        let request = account.create_user_request();
+
+        create_user_request(&context, &request).await;
+
+        // retrying the exact same creation succeeds with the existing user
+        let res = context
+            .client
+            .post("/user/create")
+            .header(ContentType::JSON)
+            .body(serde_json::json!(request).to_string())
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
+        let user = get_user_request(&context, username.clone()).await.unwrap();
+        assert_eq!(user.pubkey.unwrap(), account.pubkey().compress());
+
+        // a different key for the same username still conflicts
+        let other = GrapevineAccount::new(username.clone());
+        let msg = create_user_request(&context, &other.create_user_request()).await;
+
+        let condition = msg.contains("UsernameExists") && msg.contains("username_duplicate_user");
+
+        assert!(condition, "Users should be enforced to be unique.")
+    }
+
+    #[rocket::async_test]
+    async fn test_missing_authorization_headers() {
+        let context = GrapevineTestContext::init().await;
+
+        // test without X-Username or X-Authorization header
+        let res = context.client.get("/user/degrees").dispatch().await;
+        assert_eq!(res.status(), Status::BadRequest);
+
+        // make user
+        let user = GrapevineAccount::new(String::from("user_missing_auth_header"));
+        // test without X-Authorization header
+        let username = user.username().clone();
+        let username_header = Header::new("X-Username", username);
+        let res = context
+            .client
+            .get("/user/degrees")
+            .header(username_header)
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::BadRequest);
+
+        // test without X-Username header
+        let signature = generate_request_signature(&user, "GET", "/user/degrees");
+        let signature_header = Header::new("X-Authorization", signature);
+        let res = context
+            .client
+            .get("/user/degrees")
+            .header(signature_header)
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::BadRequest);
+    }
+
+    #[rocket::async_test]
+    #[ignore]
+    async fn test_invalid_authorization_header() {
+        let context = GrapevineTestContext::init().await;
+
+        let username = String::from("user_invalid_auth_header");
+
+        let auth_header = Header::new("X-Authorization", "00000000000");
+        let username_header = Header::new("X-Username", username);
+
+        let res = context
+            .client
+            .get("/user/degrees")
+            .header(auth_header)
+            .header(username_header)
+            .dispatch()
+            .await;
+        let message = res.into_string().await.unwrap();
+        println!("Message: {}", message);
+    }
+
+    #[rocket::async_test]
+    async fn test_relationship_creation_with_empty_request_body() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let user_a = GrapevineAccount::new(String::from("user_relationship_1_a"));
+        let user_b = GrapevineAccount::new(String::from("user_relationship_1_b"));
+
+        // Create users
+        let user_a_request = user_a.create_user_request();
+        let user_b_request = user_b.create_user_request();
+        create_user_request(&context, &user_a_request).await;
+        create_user_request(&context, &user_b_request).await;
+
+        let signature = user_a.sign_request("POST", "/user/relationship", None, None);
+        let encoded = hex::encode(signature.compress());
+
+        let res = context
+            .client
+            .post("/user/relationship")
+            .header(Header::new("X-Authorization", encoded))
+            .header(Header::new("X-Username", user_a.username().clone()))
+            .json::<Vec<u8>>(&vec![])
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+
+        println!("Message: {}", res);
+
+        assert_eq!(
+            "User cannot have a relationship with themself", res,
+            "User should not be able to have a relationsip with themselves."
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_relationship_creation_with_nonexistent_recipient() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_relationship_2_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_relationship_2_b"));
+
+        // Create user
+        let user_a_request = user_a.create_user_request();
+        create_user_request(&context, &user_a_request).await;
+
+        let (_, msg) = add_relationship_request(&mut user_a, &mut user_b).await;
+
+        assert_eq!(
+            msg.unwrap(),
+            "Recipient does not exist.",
+            "Recipient shouldn't exist"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_relationship_where_to_is_also_from() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_relationship_3_a"));
+        let mut clone_a = user_a.clone();
+
+        // Create user
+        let user_a_request = user_a.create_user_request();
+        create_user_request(&context, &user_a_request).await;
+
+        let (_, msg) = add_relationship_request(&mut user_a, &mut clone_a).await;
+
+        assert!(
+            msg.unwrap().contains("RelationshipSenderIsTarget"),
+            "Relationship cannot be made with your own account"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_successful_relationship_creation() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_relationship_4_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_relationship_4_b"));
+
+        // Create user
+        let user_a_request = user_a.create_user_request();
+        let user_b_request = user_b.create_user_request();
+        create_user_request(&context, &user_a_request).await;
+        create_user_request(&context, &user_b_request).await;
+
+        // Check add pending relationship request
+        let (code, msg) = add_relationship_request(&mut user_a, &mut user_b).await;
+        assert_eq!(
+            code,
+            Status::Created.code,
+            "Relationship add code should be 201"
+        );
+        assert_eq!(
+            msg.unwrap(),
+            "Relationship from user_relationship_4_a to user_relationship_4_b pending!",
+            "Relationship should be pending"
+        );
+
+        // Check activate relationship request
+        let (code, msg) = add_relationship_request(&mut user_b, &mut user_a).await;
+        assert_eq!(
+            code,
+            Status::Created.code,
+            "Relationship add code should be 201"
+        );
+        assert_eq!(
+            msg.unwrap(),
+            "Relationship from user_relationship_4_b to user_relationship_4_a activated!",
+            "Relationship should be activated"
+        );
+
+        // Check no pending relationships
+        let pending_relationships = get_relationships_request(&context, &mut user_b, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            pending_relationships.len(),
+            0,
+            "User A should have no pending relationships"
+        );
+
+        // Check a and b have pending requests
+        let active_relationships = get_relationships_request(&context, &mut user_a, true)
+            .await
+            .unwrap();
+        assert_eq!(
+            active_relationships.len(),
+            1,
+            "User A should have one active relationship"
+        );
+        assert_eq!(
+            active_relationships.get(0).unwrap(),
+            user_b.username(),
+            "User B should be active relationship"
+        );
+        let active_relationships = get_relationships_request(&context, &mut user_b, true)
+            .await
+            .unwrap();
+        assert_eq!(
+            active_relationships.len(),
+            1,
+            "User B should have one active relationship"
+        );
+        assert_eq!(
+            active_relationships.get(0).unwrap(),
+            user_a.username(),
+            "User A should be active relationship"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_only_prove_with_active_relationship() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_b"));
+
+        // Create user
+        let user_a_request = user_a.create_user_request();
+        let user_b_request = user_b.create_user_request();
+        create_user_request(&context, &user_a_request).await;
+        create_user_request(&context, &user_b_request).await;
+
+        // Add relationship from a to b
+        _ = add_relationship_request(&mut user_a, &mut user_b).await;
+
+        // Create phrase a phrase as User A
+        let phrase = String::from("The sheep waited patiently in the field");
+        let description = String::from("Sheep have no patience");
+        _ = phrase_request(&phrase, description, &mut user_a).await;
+
+        // Get available proofs as b and check 0 returned
+        let proofs = get_available_degrees_request(&mut user_b).await.unwrap();
+        assert_eq!(proofs.len(), 0, "No proofs should be available");
+
+        // Add relationship from b to a
+        _ = add_relationship_request(&mut user_b, &mut user_a).await;
+
+        // Get available proofs as b and check can prove
+        let proofs = get_available_degrees_request(&mut user_b).await.unwrap();
+        assert_eq!(proofs.len(), 1, "Proof should be available");
+        let (code, _) = create_degree_proof_request(&proofs[0], &mut user_b).await;
+        assert_eq!(code, Status::Created.code, "Proof should be created");
+    }
+
+    #[rocket::async_test]
+    async fn test_reject_relationship() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+        let context = GrapevineTestContext::init().await;
+
+        // create users
+        let mut user_a = GrapevineAccount::new(String::from("user_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_b"));
+        _ = create_user_request(&context, &user_a.create_user_request()).await;
+        _ = create_user_request(&context, &user_b.create_user_request()).await;
+
+        // send pending relationship request from a to b
+        _ = add_relationship_request(&mut user_a, &mut user_b).await;
+        // retrieve pending relationships as b
+        let pending_relationships = get_relationships_request(&context, &mut user_b, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            pending_relationships.len(),
+            1,
+            "User B should have one pending relationship"
+        );
+        assert_eq!(
+            pending_relationships.get(0).unwrap(),
+            user_a.username(),
+            "User A should be pending relationship"
+        );
+        // reject relationship from b to a
+        let (code, _) = reject_relationship_request(&context, &mut user_b, user_a.username()).await;
+        assert_eq!(code, Status::Ok.code, "Relationship should be rejected");
+        // show request was removed from pending relationship
+        let pending_relationships = get_relationships_request(&context, &mut user_b, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            pending_relationships.len(),
+            0,
+            "User B should have one pending relationship"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_duplicate_pending_relationship() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_relationship_5_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_relationship_5_b"));
+
+        // Create user
+        let user_a_request = user_a.create_user_request();
+        let user_b_request = user_b.create_user_request();
+        create_user_request(&context, &user_a_request).await;
+        create_user_request(&context, &user_b_request).await;
+
+        add_relationship_request(&mut user_a, &mut user_b).await;
+
+        let (code, msg_res) = add_relationship_request(&mut user_a, &mut user_b).await;
+        assert_eq!(
+            code,
+            Status::Conflict.code,
+            "Relationship should be a conflict"
+        );
+        let msg = msg_res.unwrap();
+        let condition = msg.contains("PendingRelationshipExists")
+            && msg.contains("user_relationship_5_a")
+            && msg.contains("user_relationship_5_b");
+        assert!(condition, "Duplicate pending relationships cannot exist.");
+    }
+
+    #[rocket::async_test]
+    async fn test_duplicate_active_relationship() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_relationship_5_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_relationship_5_b"));
+
+        // Create user
+        let user_a_request = user_a.create_user_request();
+        let user_b_request = user_b.create_user_request();
+        create_user_request(&context, &user_a_request).await;
+        create_user_request(&context, &user_b_request).await;
+
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
+
+        let (code, msg_res) = add_relationship_request(&mut user_a, &mut user_b).await;
+        assert_eq!(
+            code,
+            Status::Conflict.code,
+            "Relationship should be a conflict"
+        );
+        let msg = msg_res.unwrap();
+        let condition = msg.contains("ActiveRelationshipExists")
+            && msg.contains("user_relationship_5_a")
+            && msg.contains("user_relationship_5_b");
+        assert!(condition, "Duplicate active relationships cannot exist.");
+    }
+
+    #[rocket::async_test]
+    async fn test_duplicate_phrase() {
+        // initialize context
+        GrapevineDB::drop("grapevine_mocked").await;
+        let context = GrapevineTestContext::init().await;
+
+        // add user
+        let mut user = GrapevineAccount::new(String::from("user"));
+        create_user_request(&context, &user.create_user_request()).await;
+
+        // create & prove phrase
+        let phrase = String::from("This is a phrase");
+        let description = String::from("This is a description");
+        _ = phrase_request(&phrase, description.clone(), &mut user).await;
+
+        // attempt to create & prove a duplicate phrase
+        let (code, msg) = phrase_request(&phrase, description, &mut user).await;
+        assert!(
+            msg.contains("DegreeProofExists"),
+            "Duplicate phrase should be prevented from being added",
+        );
+        assert!(
+            code == Status::Conflict.code,
+            "Duplicate phrase should return a 409 status code",
+        )
+    }
+
+    #[rocket::async_test]
+    async fn test_multiple_degree_1() {
+        // initialize context
+        GrapevineDB::drop("grapevine_mocked").await;
+        let context = GrapevineTestContext::init().await;
+
+        // add users
+        let mut user1 = GrapevineAccount::new(String::from("user1"));
+        let mut user2 = GrapevineAccount::new(String::from("user2"));
+        create_user_request(&context, &user1.create_user_request()).await;
+        create_user_request(&context, &user2.create_user_request()).await;
+
+        // create phrase
+        let phrase = String::from("This is a phrase");
+        let description = String::from("This is a description");
+        let (_, res) = phrase_request(&phrase, description.clone(), &mut user1).await;
+        let data: PhraseCreationResponse = serde_json::from_str(&res).unwrap();
+
+        // check that new phrase was created at index 1
+        assert_eq!(data.phrase_index, 1);
+        assert_eq!(data.new_phrase, true);
+
+        // create phrase
+        let phrase = String::from("This is a phrase");
+        let description = String::from("This is a different description");
+        let (_, res) = phrase_request(&phrase, description.clone(), &mut user2).await;
+        let data: PhraseCreationResponse = serde_json::from_str(&res).unwrap();
+
+        // check that existing phrase was proven at index 1
+        assert_eq!(data.phrase_index, 1);
+        assert_eq!(data.new_phrase, false);
+    }
+
+    #[rocket::async_test]
+    async fn test_create_degree_proof_with_invalid_request_body() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let user = GrapevineAccount::new(String::from("user_degree_proof_1"));
+        let request = user.create_user_request();
+
+        create_user_request(&context, &request).await;
+
+        let signature = user.sign_request("POST", "/proof/degree", None, None);
+        let encoded = hex::encode(signature.compress());
+
+        let msg = context
+            .client
+            .post("/proof/degree")
+            .header(Header::new("X-Authorization", encoded))
+            .header(Header::new("X-Username", user.username().clone()))
+            .body(vec![])
+            .dispatch()
+            .await
+            .into_string()
+            .await
+            .unwrap();
+
+        let condition = msg.contains("SerdeError") && msg.contains("DegreeProofRequest");
+        assert!(
+            condition,
+            "Degree proof continuation should fail with invalid body"
+        )
+    }
+
+    #[rocket::async_test]
+    async fn test_successful_degree_proof_creation() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_degree_proof_2_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_degree_proof_2_b"));
+
+        // Create users
+        let user_a_request = user_a.create_user_request();
+        let user_b_request = user_b.create_user_request();
+        create_user_request(&context, &user_a_request).await;
+        create_user_request(&context, &user_b_request).await;
+
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
+
+        // Create phrase a phrase as User A
+        let phrase = String::from("The first phrase to end them all");
+        let description = String::from("And on the first day, user_a made a phrase");
+        _ = phrase_request(&phrase, description, &mut user_a).await;
+
+        // prove 2nd degree separation as user b
+        let proofs = get_available_degrees_request(&mut user_b).await.unwrap();
+        let (code, _) = create_degree_proof_request(&proofs[0], &mut user_b).await;
+        assert_eq!(
+            code,
+            Status::Created.code,
+            "Degree proof should have been created"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_duplicate_degree_proof() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_degree_proof_3_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_degree_proof_3_b"));
+
+        // Create users
+        let user_a_request = user_a.create_user_request();
+        let user_b_request = user_b.create_user_request();
+        create_user_request(&context, &user_a_request).await;
+        create_user_request(&context, &user_b_request).await;
+
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
+
+        // Create phrase a phrase as User A
+        let phrase = String::from("The first phrase to end them all");
+        let description = String::from("And on the first day, user_a made a phrase");
+        _ = phrase_request(&phrase, description, &mut user_a).await;
+
+        // get proofs as user b
+        let proofs = get_available_degrees_request(&mut user_b).await.unwrap();
+
+        // prove degree 2 separation of phrase as user b
+        create_degree_proof_request(&proofs[0], &mut user_b).await;
+
+        // attempt to prove degree 2 separation of phrase as user b again
+        let (_, msg) = create_degree_proof_request(&proofs[0], &mut user_b).await;
+        assert!(
+            msg.unwrap().contains("DegreeProofExists"),
+            "Cannot create a second degree proof between same accounts for same phrase"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_get_account_details() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context: GrapevineTestContext = GrapevineTestContext::init().await;
+
+        // Create test users
+        let mut users = vec![
+            GrapevineAccount::new(String::from("user_account_details_1")),
+            GrapevineAccount::new(String::from("user_account_details_2")),
+            GrapevineAccount::new(String::from("user_account_details_3")),
+            GrapevineAccount::new(String::from("user_account_details_4")),
+            GrapevineAccount::new(String::from("user_account_details_5")),
+            GrapevineAccount::new(String::from("user_account_details_6")),
+            GrapevineAccount::new(String::from("user_account_details_7")),
+            GrapevineAccount::new(String::from("user_account_details_8")),
+            GrapevineAccount::new(String::from("user_account_details_9")),
+        ];
+
+        for i in 0..users.len() {
+            let request = users[i].create_user_request();
+            create_user_request(&context, &request).await;
+        }
+
+        let mut user_a = users.remove(0);
+        let mut user_b = users.remove(0);
+        let mut user_c = users.remove(0);
+        let mut user_d = users.remove(0);
+        let mut user_e = users.remove(0);
+        let mut user_f = users.remove(0);
+        let mut user_g = users.remove(0);
+        let mut user_h = users.remove(0);
+        let mut user_i = users.remove(0);
+
+        let details = get_account_details_request(&mut user_a).await.unwrap();
+        assert_eq!(details.phrase_count, 0, "Phrase count should be 0");
+        assert_eq!(
+            details.first_degree_count, 0,
+            "First degree count should be 0"
+        );
+        assert_eq!(
+            details.second_degree_count, 0,
+            "Second degree count should be 0"
+        );
+
+        // Create phrase a phrase as User A
+        let phrase = String::from("The first phrase to end them all");
+        let description = String::from("And on the first day, user_a made a phrase");
+        _ = phrase_request(&phrase, description, &mut user_a).await;
+
+        let details = get_account_details_request(&mut user_a).await.unwrap();
+        assert_eq!(details.phrase_count, 1, "Phrase count should be 1");
+        assert_eq!(
+            details.first_degree_count, 0,
+            "First degree count should be 0"
+        );
+        assert_eq!(
+            details.second_degree_count, 0,
+            "Second degree count should be 0"
+        );
+
+        // Add first degree connection and second degree connection
+        add_relationship_request(&mut user_b, &mut user_a).await;
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_c, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_c).await;
+        let details = get_account_details_request(&mut user_a).await.unwrap();
+        assert_eq!(details.phrase_count, 1, "Phrase count should be 1");
+        assert_eq!(
+            details.first_degree_count, 1,
+            "First degree count should be 1"
+        );
+        assert_eq!(
+            details.second_degree_count, 1,
+            "Second degree count should be 1"
+        );
+
+        // Add more second degree connections
+        add_relationship_request(&mut user_d, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_d).await;
+        add_relationship_request(&mut user_e, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_e).await;
+        let details = get_account_details_request(&mut user_a).await.unwrap();
+        assert_eq!(details.phrase_count, 1, "Phrase count should be 1");
+        assert_eq!(
+            details.first_degree_count, 1,
+            "First degree count should be 1"
+        );
+        assert_eq!(
+            details.second_degree_count, 3,
+            "Second degree count should be 3"
+        );
 
-        (code, msg)
+        // Second degree connections become first degree connections
+        add_relationship_request(&mut user_d, &mut user_a).await;
+        add_relationship_request(&mut user_a, &mut user_d).await;
+        add_relationship_request(&mut user_e, &mut user_a).await;
+        add_relationship_request(&mut user_a, &mut user_e).await;
+        let details = get_account_details_request(&mut user_a).await.unwrap();
+        assert_eq!(details.phrase_count, 1, "Phrase count should be 1");
+        assert_eq!(
+            details.first_degree_count, 3,
+            "First degree count should be 3"
+        );
+        assert_eq!(
+            details.second_degree_count, 1,
+            "Second degree count should be 1"
+        );
+
+        // Test where 3 new degree 2 connections added at once
+        add_relationship_request(&mut user_f, &mut user_a).await;
+        add_relationship_request(&mut user_a, &mut user_f).await;
+        add_relationship_request(&mut user_f, &mut user_g).await;
+        add_relationship_request(&mut user_g, &mut user_f).await;
+        add_relationship_request(&mut user_f, &mut user_h).await;
+        add_relationship_request(&mut user_h, &mut user_f).await;
+        add_relationship_request(&mut user_f, &mut user_i).await;
+        add_relationship_request(&mut user_i, &mut user_f).await;
+        let details = get_account_details_request(&mut user_a).await.unwrap();
+        assert_eq!(details.phrase_count, 1, "Phrase count should be 1");
+        assert_eq!(
+            details.first_degree_count, 4,
+            "First degree count should be 4"
+        );
+        assert_eq!(
+            details.second_degree_count, 4,
+            "Second degree count should be 4"
+        );
     }
 
     #[rocket::async_test]
-    async fn test_proof_reordering_with_3_proof_chain() {
-        let context = GrapevineTestContext::init().await;
-
+    async fn test_get_phrase_connections() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
-        // Create test users
-        let mut users = vec![
-            GrapevineAccount::new(String::from("User_A")),
-            GrapevineAccount::new(String::from("User_B")),
-            GrapevineAccount::new(String::from("User_C")),
-        ];
+        let context = GrapevineTestContext::init().await;
 
-        for i in 0..users.len() {
-            let request = users[i].create_user_request();
+        let mut users: Vec<GrapevineAccount> = vec![];
+
+        for i in 0..7 {
+            let user =
+                GrapevineAccount::new(String::from(format!("user_account_details_{}", i + 1)));
+            let request = user.create_user_request();
             create_user_request(&context, &request).await;
+            users.push(user);
         }
 
-        // Create phrase a phrase as User A
-        let phrase = String::from("The sheep waited patiently in the field");
-        let description = String::from("Sheep have no patience");
-        _ = phrase_request(&phrase, description, &mut users[0]).await;
+        // create phrase 1
+        let phrase = String::from("Where there's smoke there's fire");
+        let description = String::from("5 alarm");
+        let (_, res) = phrase_request(&phrase, description.clone(), &mut users[0]).await;
+        let data: PhraseCreationResponse = serde_json::from_str(&res).unwrap();
 
-        // Add relationship between User A and User B, B and C
-        for i in 0..users.len() - 1 {
+        let connections = get_phrase_connection_request(&mut users[0], data.phrase_index)
+            .await
+            .unwrap();
+        assert_eq!(connections.0, 0);
+        assert_eq!(connections.1.len(), 0);
+        // Create degree proofs and relationships
+        for i in 0..users.len() - 3 {
             // Remove users from vector to reference
             let mut preceding = users.remove(i);
             // Proceeding is now an index below after removal
             let mut proceeding = users.remove(i);
+
             add_relationship_request(&mut preceding, &mut proceeding).await;
             add_relationship_request(&mut proceeding, &mut preceding).await;
 
-            // Create degree proofs: A <- B <- C
             let proofs = get_available_degrees_request(&mut proceeding)
                 .await
                 .unwrap();
-
             create_degree_proof_request(&proofs[0], &mut proceeding).await;
 
             // Add users back to vector
             users.insert(i, preceding);
             users.insert(i + 1, proceeding);
         }
-        println!("8====================================D");
 
         let mut user_a = users.remove(0);
-        // User C is now an index below after removal
-        let mut user_c = users.remove(1);
+        let mut user_b = users.remove(0);
+        let mut user_c = users.remove(0);
+        let mut user_d = users.remove(0);
+        let mut user_f = users.remove(1);
+        let mut user_g = users.remove(1);
+
+        // user_b <- user_c -> user_d
+        let connections = get_phrase_connection_request(&mut user_c, data.phrase_index)
+            .await
+            .unwrap();
+        // has 2 total connections
+        assert_eq!(connections.0, 2);
+        // not connected to user_a at 1st degree spot
+        assert_eq!(*connections.1.get(0).unwrap(), 0);
+        // user_b is is at 2nd degree spot, showing 1 2nd degree connection
+        assert_eq!(*connections.1.get(1).unwrap(), 1);
+        // user_c is not connected to themselves and no other 3rd degree connections
+        assert_eq!(*connections.1.get(2).unwrap(), 0);
+        // user d is at 4th degree spot, showing 1 4th degree connection
+        assert_eq!(*connections.1.get(3).unwrap(), 1);
+        // connection vector is length 4, implying no connections past degree 4
+        assert_eq!(connections.1.len(), 4);
+
+        add_relationship_request(&mut user_a, &mut user_f).await;
+        add_relationship_request(&mut user_f, &mut user_a).await;
+
+        let proofs = get_available_degrees_request(&mut user_f).await.unwrap();
+        // User F has proof of degree 2
+        create_degree_proof_request(&proofs[0], &mut user_f).await;
+        // User G has degree proof 3
+        add_relationship_request(&mut user_b, &mut user_g).await;
+        add_relationship_request(&mut user_g, &mut user_b).await;
+
+        let proofs = get_available_degrees_request(&mut user_g).await.unwrap();
+        create_degree_proof_request(&proofs[0], &mut user_g).await;
 
-        // Establish relationship between A and C now
-        add_relationship_request(&mut user_a, &mut user_c).await;
         add_relationship_request(&mut user_c, &mut user_a).await;
+        add_relationship_request(&mut user_a, &mut user_c).await;
+        add_relationship_request(&mut user_c, &mut user_d).await;
+        add_relationship_request(&mut user_d, &mut user_c).await;
+        add_relationship_request(&mut user_c, &mut user_f).await;
+        add_relationship_request(&mut user_f, &mut user_c).await;
+        add_relationship_request(&mut user_c, &mut user_g).await;
+        add_relationship_request(&mut user_g, &mut user_c).await;
 
-        // Check that C now has an available degree request
-        let proofs_c = get_available_degrees_request(&mut user_c).await.unwrap();
+        // User C should have:
+        // * Connection to User A with proof of degree 1
+        // * Connection to User B with proof of degree 2
+        // * Connection to User D with proof of degree 4
+        // * Connection to User F with proof of degree 2
+        // * Connection to User G with proof of degree 3
+        let connections = get_phrase_connection_request(&mut user_c, data.phrase_index)
+            .await
+            .unwrap();
+
+        assert_eq!(connections.0, 5);
+        assert_eq!(*connections.1.get(0).unwrap(), 1);
+        assert_eq!(*connections.1.get(1).unwrap(), 2);
+        assert_eq!(*connections.1.get(2).unwrap(), 1);
+        assert_eq!(*connections.1.get(3).unwrap(), 1);
+        assert_eq!(connections.1.len(), 4);
+
+        // create phrase 2
+        let phrase = String::from("Raindrops are falling on my head");
+        let description = String::from("Get an umbrella ig");
+        let (_, res) = phrase_request(&phrase, description.clone(), &mut users[0]).await;
+        let data: PhraseCreationResponse = serde_json::from_str(&res).unwrap();
+
+        let connections = get_phrase_connection_request(&mut user_c, data.phrase_index)
+            .await
+            .unwrap();
+
+        assert_eq!(connections.0, 0);
+        assert_eq!(connections.1.len(), 0);
+    }
+
+    #[rocket::async_test]
+    async fn test_deactivated_proof_not_available() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_deactivate_1_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_deactivate_1_b"));
+        let mut user_c = GrapevineAccount::new(String::from("user_deactivate_1_c"));
+
+        // Create users
+        for user in [&user_a, &user_b, &user_c] {
+            let request = user.create_user_request();
+            create_user_request(&context, &request).await;
+        }
+
+        // A <- B <- C
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
+        add_relationship_request(&mut user_b, &mut user_c).await;
+        add_relationship_request(&mut user_c, &mut user_b).await;
+
+        // Create phrase a phrase as User A and prove degree 2 as user b
+        let phrase = String::from("Revoke the proof and the chain goes with it");
+        let description = String::from("Proof deactivation");
+        _ = phrase_request(&phrase, description, &mut user_a).await;
+        let proofs = get_available_degrees_request(&mut user_b).await.unwrap();
+        create_degree_proof_request(&proofs[0], &mut user_b).await;
+
+        // user c can build from user b's proof
+        let proofs = get_available_degrees_request(&mut user_c).await.unwrap();
+        assert_eq!(
+            proofs.len(),
+            1,
+            "User C should be able to build from User B"
+        );
+
+        // user c cannot deactivate user b's proof
+        let (code, _) = deactivate_proof_request(&mut user_c, &proofs[0]).await;
+        assert_eq!(
+            code,
+            Status::Forbidden.code,
+            "Only the proof owner can deactivate a proof"
+        );
+
+        // user b deactivates their proof
+        let (code, _) = deactivate_proof_request(&mut user_b, &proofs[0]).await;
+        assert_eq!(code, Status::Ok.code, "Proof should have been deactivated");
+
+        let proofs = get_available_degrees_request(&mut user_c).await.unwrap();
+        assert_eq!(
+            proofs.len(),
+            0,
+            "Deactivated proofs should not be available to build from"
+        );
+    }
+
+    #[test]
+    fn test_proof_rejection_output_mismatch() {
+        let params = use_public_params().unwrap();
+        let r1cs = use_r1cs().unwrap();
+        let wc_path = use_wasm().unwrap();
+
+        // prove knowledge of a phrase (degree 1)
+        let user = GrapevineAccount::new(String::from("user_rejection_1"));
+        let phrase = String::from("Not all those who wander are lost");
+        let proof = nova_proof(
+            wc_path,
+            &r1cs,
+            &params,
+            &phrase,
+            &vec![user.username().clone()],
+            &vec![user.auth_secret().clone()],
+        )
+        .unwrap();
+        let compressed = compress_proof(&proof);
+
+        // proof verifies but does not output the claimed degree
+        let res = check_proof(&compressed, &params, steps_for_degree(1), 2);
+        assert_eq!(res.unwrap_err(), ProofRejectionReason::OutputMismatch);
+
+        // proof does not verify with the wrong number of folds
+        let res = check_proof(&compressed, &params, steps_for_degree(2), 2);
+        assert_eq!(res.unwrap_err(), ProofRejectionReason::Verify);
+
+        // proof cannot be parsed
+        let res = check_proof(&compressed[1..], &params, steps_for_degree(1), 1);
+        assert_eq!(res.unwrap_err(), ProofRejectionReason::Parse);
+    }
+
+    #[rocket::async_test]
+    async fn test_update_phrase_description_non_origin() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_description_1_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_description_1_b"));
+        create_user_request(&context, &user_a.create_user_request()).await;
+        create_user_request(&context, &user_b.create_user_request()).await;
+
+        // user a creates the phrase and user b proves knowledge of the same phrase
+        let phrase = String::from("Typos are forever unless you can fix them");
+        let description = String::from("Spelling is hrad");
+        let (_, res) = phrase_request(&phrase, description.clone(), &mut user_a).await;
+        let data: PhraseCreationResponse = serde_json::from_str(&res).unwrap();
+        _ = phrase_request(&phrase, description, &mut user_b).await;
+
+        // user b cannot update the description
+        let new_description = String::from("Spelling is hard");
+        let (code, _) = update_phrase_description_request(
+            &mut user_b,
+            data.phrase_index,
+            new_description.clone(),
+        )
+        .await;
+        assert_eq!(
+            code,
+            Status::Forbidden.code,
+            "Only the phrase origin can update the description"
+        );
+
+        // user a can update the description
+        let (code, _) =
+            update_phrase_description_request(&mut user_a, data.phrase_index, new_description)
+                .await;
+        assert_eq!(
+            code,
+            Status::Ok.code,
+            "Phrase origin should update description"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_verify_proof_in_set() {
+        let context = GrapevineTestContext::init().await;
+
+        let params = use_public_params().unwrap();
+        let r1cs = use_r1cs().unwrap();
+        let wc_path = use_wasm().unwrap();
+
+        // prove knowledge of a phrase (degree 1)
+        let user = GrapevineAccount::new(String::from("user_verify_in_set_1"));
+        let phrase = String::from("Three may keep a secret if two of them are dead");
+        let proof = nova_proof(
+            wc_path,
+            &r1cs,
+            &params,
+            &phrase,
+            &vec![user.username().clone()],
+            &vec![user.auth_secret().clone()],
+        )
+        .unwrap();
+        let phrase_hash = verify_nova_proof(&proof, &params, steps_for_degree(1))
+            .unwrap()
+            .0[1]
+            .to_bytes();
+
+        // set of three origins where the proof matches the second
+        let allowed = vec![random_fr().to_bytes(), phrase_hash, random_fr().to_bytes()];
+        let body = VerifyInSetRequest {
+            proof: compress_proof(&proof),
+            degree: 1,
+            allowed,
+        };
+        let serialized = bincode::serialize(&body).unwrap();
+        let res = context
+            .client
+            .post("/proof/verify-in-set")
+            .body(serialized)
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
 
-        // Create new degree proof between A and C
-        create_degree_proof_request(&proofs_c[0], &mut user_c).await;
+        // response only contains the verification result and degree
+        let raw = res.into_string().await.unwrap();
+        let json: serde_json::Value = serde_json::from_str(&raw).unwrap();
+        assert_eq!(json.as_object().unwrap().len(), 2);
+        let data: VerifyInSetResponse = serde_json::from_str(&raw).unwrap();
+        assert!(
+            data.verified,
+            "Proof should verify against set containing its phrase"
+        );
+        assert_eq!(data.degree, 1);
     }
 
     #[rocket::async_test]
-    #[ignore]
-    async fn test_proof_reordering_with_4_proof_chain() {
-        let context = GrapevineTestContext::init().await;
-
+    async fn test_get_new_relationships_since() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
-        // Create test users
-        let mut users = vec![
-            GrapevineAccount::new(String::from("User_A")),
-            GrapevineAccount::new(String::from("User_B")),
-            GrapevineAccount::new(String::from("User_C")),
-            GrapevineAccount::new(String::from("User_D")),
-        ];
-
-        for i in 0..users.len() {
-            let request = users[i].create_user_request();
-            create_user_request(&context, &request).await;
-        }
-
-        // Create phrase a phrase as User A
-        let phrase = String::from("And that's the waaaayyyy the news goes");
-        let description = String::from("Wubalubadubdub!");
-        _ = phrase_request(&phrase, description, &mut users[0]).await;
-
-        // Create relationships and degree proofs: A <- B <- C <- D
-        for i in 0..users.len() - 1 {
-            // Remove users from vector to reference
-            let mut preceding = users.remove(i);
-            // Proceeding is now an index below after removal
-            let mut proceeding = users.remove(i);
+        let context = GrapevineTestContext::init().await;
 
-            add_relationship_request(&mut preceding, &mut proceeding).await;
-            add_relationship_request(&mut proceeding, &mut preceding).await;
+        let mut user_a = GrapevineAccount::new(String::from("user_new_relationships_1_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_new_relationships_1_b"));
+        let mut user_c = GrapevineAccount::new(String::from("user_new_relationships_1_c"));
+        create_user_request(&context, &user_a.create_user_request()).await;
+        create_user_request(&context, &user_b.create_user_request()).await;
+        create_user_request(&context, &user_c.create_user_request()).await;
 
-            let proofs = get_available_degrees_request(&mut proceeding)
-                .await
-                .unwrap();
-            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+        // user b sends a request before the cutoff
+        add_relationship_request(&mut user_b, &mut user_a).await;
 
-            // Add users back to vector
-            users.insert(i, preceding);
-            users.insert(i + 1, proceeding);
-        }
+        // wait until the next second so the cutoff excludes user b's request
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        let cutoff = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as u32;
 
-        let mut user_a = users.remove(0);
-        let mut user_c = users.remove(1);
-        // Establish relationship between A and C now
-        add_relationship_request(&mut user_a, &mut user_c).await;
+        // user c sends a request after the cutoff
         add_relationship_request(&mut user_c, &mut user_a).await;
 
-        // Check that C now has an available degree request
-        let proofs_c = get_available_degrees_request(&mut user_c).await.unwrap();
-        // Create new deree proof between A and C
-        create_degree_proof_request(&proofs_c[0], &mut user_c).await;
-
-        users.insert(0, user_a);
-        users.insert(2, user_c);
+        let pending = get_relationships_request(&context, &mut user_a, false)
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 2, "Both requests should be pending");
 
-        // Check avaiable degree with D and perform necessary update
-        let proofs_d = get_available_degrees_request(&mut users[3]).await.unwrap();
-        // Create new degree proof between C and D
-        create_degree_proof_request(&proofs_d[0], &mut users[3]).await;
+        let new = get_new_relationships_request(&context, &mut user_a, cutoff)
+            .await
+            .unwrap();
+        assert_eq!(
+            new,
+            vec![user_c.username().clone()],
+            "Only the request sent after the cutoff should be new"
+        );
     }
 
     #[rocket::async_test]
-    #[ignore]
-    async fn test_proof_reordering_with_5_proof_chain() {
-        let context = GrapevineTestContext::init().await;
-
+    async fn test_unsupported_request_version() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
-        // Create test users
-        let mut users = vec![
-            GrapevineAccount::new(String::from("User_A")),
-            GrapevineAccount::new(String::from("User_B")),
-            GrapevineAccount::new(String::from("User_C")),
-            GrapevineAccount::new(String::from("User_D")),
-            GrapevineAccount::new(String::from("User_E")),
-        ];
-
-        for i in 0..users.len() {
-            let request = users[i].create_user_request();
-            create_user_request(&context, &request).await;
-        }
-
-        // Create phrase a phrase as User A
-        let phrase = String::from("You are what you eat");
-        let description = String::from("Mediocre cryptographer");
-        _ = phrase_request(&phrase, description, &mut users[0]).await;
-
-        // Add relationship and degree proofs: A <- B, B <- C
-        for i in 0..2 {
-            // Remove users from vector to reference
-            let mut preceding = users.remove(i);
-            // Proceeding is now an index below after removal
-            let mut proceeding = users.remove(i);
+        let context = GrapevineTestContext::init().await;
 
-            add_relationship_request(&mut preceding, &mut proceeding).await;
-            add_relationship_request(&mut proceeding, &mut preceding).await;
+        let mut user = GrapevineAccount::new(String::from("user_request_version_1"));
+        create_user_request(&context, &user.create_user_request()).await;
 
-            let proofs = get_available_degrees_request(&mut proceeding)
-                .await
-                .unwrap();
-            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+        // build a phrase request from an unknown future version
+        let body = PhraseRequest {
+            version: REQUEST_VERSION + 1,
+            proof: vec![],
+            ciphertext: user
+                .encrypt_phrase(&String::from("From the future"))
+                .unwrap(),
+            description: String::from("Time travel"),
+            tags: vec![],
+        };
+        let serialized: Vec<u8> = bincode::serialize(&body).unwrap();
+        let username = user.username().clone();
+        let signature = generate_request_signature(&user, "POST", "/proof/phrase");
+        let res = context
+            .client
+            .post("/proof/phrase")
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", username))
+            .body(serialized)
+            .dispatch()
+            .await;
+        let _ = user.increment_nonce(None);
 
-            // Add users back to vector
-            users.insert(i, preceding);
-            users.insert(i + 1, proceeding);
+        assert_eq!(res.status(), Status::BadRequest);
+        let err = res.into_json::<GrapevineError>().await.unwrap();
+        match err {
+            GrapevineError::UnsupportedVersion(version, _, max) => {
+                assert_eq!(version, REQUEST_VERSION + 1);
+                assert_eq!(max, REQUEST_VERSION);
+            }
+            _ => panic!("Expected UnsupportedVersion error, got {:?}", err),
         }
+    }
 
-        // Add relationship and degree proofs: C <- D, C <- E
-        for i in 0..2 {
-            let mut preceding = users.remove(2);
-            // Proceeding is now an index below after removal
-            let mut proceeding = users.remove(i + 2);
+    #[rocket::async_test]
+    async fn test_proof_params_cached() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
 
-            add_relationship_request(&mut preceding, &mut proceeding).await;
-            add_relationship_request(&mut proceeding, &mut preceding).await;
+        let context = GrapevineTestContext::init().await;
 
-            let proofs = get_available_degrees_request(&mut proceeding)
-                .await
-                .unwrap();
-            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+        let mut user_a = GrapevineAccount::new(String::from("user_proof_cache_1_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_proof_cache_1_b"));
+        create_user_request(&context, &user_a.create_user_request()).await;
+        create_user_request(&context, &user_b.create_user_request()).await;
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
 
-            users.insert(2, preceding);
-            users.insert(i + 3, proceeding);
+        let phrase = String::from("Cache rules everything around me");
+        _ = phrase_request(&phrase, String::from("Caching"), &mut user_a).await;
+        let available = get_available_degrees_request(&mut user_b).await.unwrap();
+        let oid = available[0].clone();
+
+        // fetch the same proof twice from the same server
+        for _ in 0..2 {
+            let uri = format!("/proof/params/{}", oid);
+            let signature = generate_request_signature(&user_b, "GET", &uri);
+            let res = context
+                .client
+                .get(uri)
+                .header(Header::new("X-Authorization", signature))
+                .header(Header::new("X-Username", user_b.username().clone()))
+                .dispatch()
+                .await;
+            assert_eq!(res.status(), Status::Ok);
         }
 
-        // Set every proof to degree 2
-        for i in 0..3 {
-            let mut preceding = users.remove(0);
-            // Proceeding is now an index below after removal
-            let mut proceeding = users.remove(i + 1);
-
-            add_relationship_request(&mut preceding, &mut proceeding).await;
-            add_relationship_request(&mut proceeding, &mut preceding).await;
-
-            let proofs = get_available_degrees_request(&mut proceeding)
-                .await
-                .unwrap();
-            create_degree_proof_request(&proofs[0], &mut proceeding).await;
-
-            users.insert(0, preceding);
-            users.insert(i + 2, proceeding);
-        }
+        let cache = context.client.rocket().state::<ProofCache>().unwrap();
+        assert_eq!(
+            cache.misses(),
+            1,
+            "First fetch should read the proof from the db"
+        );
+        assert_eq!(
+            cache.hits(),
+            1,
+            "Second fetch should be served from the cache"
+        );
     }
 
     #[rocket::async_test]
-    #[ignore]
-    async fn test_proof_reordering_with_27_proof_chain() {
-        // should this test pass?
-        // Start with tree structure and eventually have each user connect directly to A
-        let context = GrapevineTestContext::init().await;
-
+    async fn test_tenant_isolation() {
         // Reset db with clean state
-        GrapevineDB::drop("grapevine_mocked").await;
-
-        let mut users: Vec<GrapevineAccount> = vec![];
-        // Create test users
-        for i in 0..27 {
-            let usersname = format!("User_{}", i);
-            let user = GrapevineAccount::new(usersname);
-            let creation_request = user.create_user_request();
-            create_user_request(&context, &creation_request).await;
-            users.push(user);
-        }
+        GrapevineDB::drop("grapevine_mocked_tenant_a").await;
+        GrapevineDB::drop("grapevine_mocked_tenant_b").await;
 
-        // Create phrase a phrase as User A
-        let phrase =
-            String::from("They're bureaucrats. I don't respect them. Just keep shooting Morty.");
-        let description = String::from("It's a figure if speech Morty.");
-        _ = phrase_request(&phrase, description, &mut users[0]).await;
+        let context = GrapevineTestContext::init().await;
 
-        println!("Started");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        // Create relationships and degree 2 proofs
-        for i in 0..2 {
-            // Remove users from vector to reference
-            let mut preceding = users.remove(0);
-            // Proceeding is now an index below after removal
-            let mut proceeding = users.remove(i);
+        // create a user under tenant a
+        let user = GrapevineAccount::new(String::from("user_tenant_1"));
+        let res = context
+            .client
+            .post("/user/create")
+            .header(ContentType::JSON)
+            .header(Header::new("X-Tenant", "tenant_a"))
+            .body(serde_json::json!(user.create_user_request()).to_string())
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Created);
 
-            add_relationship_request(&mut preceding, &mut proceeding).await;
-            add_relationship_request(&mut proceeding, &mut preceding).await;
+        // user is visible under tenant a
+        let res = context
+            .client
+            .get(format!("/user/{}", user.username()))
+            .header(Header::new("X-Tenant", "tenant_a"))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
 
-            let proofs = get_available_degrees_request(&mut proceeding)
-                .await
-                .unwrap();
+        // user is not visible under tenant b
+        let res = context
+            .client
+            .get(format!("/user/{}", user.username()))
+            .header(Header::new("X-Tenant", "tenant_b"))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::NotFound);
 
-            create_degree_proof_request(&proofs[0], &mut proceeding).await;
-            // Add users back to vector
-            users.insert(0, preceding);
-            users.insert(i + 1, proceeding);
-        }
-        println!("Degree 2");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        // Create relationships and degree 3 proofs
-        for i in 0..6 {
-            let preceding_index = 1 + i / 3;
+        // tenants outside of the allowlist are rejected
+        let res = context
+            .client
+            .get(format!("/user/{}", user.username()))
+            .header(Header::new("X-Tenant", "tenant_c"))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::BadRequest);
+    }
 
-            // Remove users from vector to reference
-            let mut preceding = users.remove(preceding_index);
-            // Proceeding is now an index below after removal
-            let mut proceeding = users.remove(i + 2);
+    #[rocket::async_test]
+    async fn test_get_phrase_list() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
 
-            add_relationship_request(&mut preceding, &mut proceeding).await;
-            add_relationship_request(&mut proceeding, &mut preceding).await;
+        let context = GrapevineTestContext::init().await;
 
-            let proofs = get_available_degrees_request(&mut proceeding)
-                .await
-                .unwrap();
+        let mut user_a = GrapevineAccount::new(String::from("user_phrase_list_1_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_phrase_list_1_b"));
+        let mut user_c = GrapevineAccount::new(String::from("user_phrase_list_1_c"));
 
-            create_degree_proof_request(&proofs[0], &mut proceeding).await;
-            // Add users back to vector
-            users.insert(preceding_index, preceding);
-            users.insert(i + 2, proceeding);
+        // Create users
+        for user in [&user_a, &user_b, &user_c] {
+            let request = user.create_user_request();
+            create_user_request(&context, &request).await;
         }
-        println!("Degree 3");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        // Create relationships and degree 4 proofs
-        for i in 0..18 {
-            let preceding_index = 3 + i / 3;
-            println!("Index: {}", preceding_index);
-            // Remove users from vector to reference
-            let mut preceding = users.remove(preceding_index);
-            // Proceeding is now an index below after removal
-            let mut proceeding = users.remove(i + 8);
 
-            add_relationship_request(&mut preceding, &mut proceeding).await;
-            add_relationship_request(&mut proceeding, &mut preceding).await;
+        // A <- B <- C
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
+        add_relationship_request(&mut user_b, &mut user_c).await;
+        add_relationship_request(&mut user_c, &mut user_b).await;
 
-            let proofs = get_available_degrees_request(&mut proceeding)
-                .await
-                .unwrap();
+        // user a creates a phrase that user c proves at degree 3
+        let phrase_a = String::from("A phrase from two hops away");
+        let (_, res) = phrase_request(&phrase_a, String::from("Far"), &mut user_a).await;
+        let index_a = serde_json::from_str::<PhraseCreationResponse>(&res)
+            .unwrap()
+            .phrase_index;
+        let proofs = get_available_degrees_request(&mut user_b).await.unwrap();
+        create_degree_proof_request(&proofs[0], &mut user_b).await;
+        let proofs = get_available_degrees_request(&mut user_c).await.unwrap();
+        create_degree_proof_request(&proofs[0], &mut user_c).await;
 
-            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+        // user c creates their own phrase
+        let phrase_c = String::from("A phrase known firsthand");
+        let (_, res) = phrase_request(&phrase_c, String::from("Near"), &mut user_c).await;
+        let index_c = serde_json::from_str::<PhraseCreationResponse>(&res)
+            .unwrap()
+            .phrase_index;
+
+        let summaries = get_phrase_list_request(&mut user_c).await.unwrap();
+        assert_eq!(summaries.len(), 2, "User C should have proofs on 2 phrases");
+        assert_eq!(summaries[0].phrase_index, index_c);
+        assert_eq!(summaries[0].degree, 1);
+        assert_eq!(summaries[0].connections, 0);
+        assert_eq!(summaries[1].phrase_index, index_a);
+        assert_eq!(summaries[1].degree, 3);
+        assert_eq!(
+            summaries[1].connections, 1,
+            "User B has a proof on phrase A"
+        );
+    }
 
-            // Add users back to vector
-            users.insert(preceding_index, preceding);
-            users.insert(i + 9, proceeding);
+    #[cfg(feature = "delegated-proving")]
+    #[rocket::async_test]
+    async fn test_delegated_degree_proof() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+        let public_params = use_public_params().unwrap();
+        let r1cs = use_r1cs().unwrap();
+        let wc_path = use_wasm().unwrap();
+
+        let mut user_a = GrapevineAccount::new(String::from("user_delegated_1_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_delegated_1_b"));
+        let mut user_c = GrapevineAccount::new(String::from("user_delegated_1_c"));
+        for user in [&user_a, &user_b, &user_c] {
+            let request = user.create_user_request();
+            create_user_request(&context, &request).await;
         }
 
-        println!("Degree 4");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        // Bring all proofs to degree 2
-        for i in 0..24 {
-            // Remove users from vector to reference
-            let mut preceding = users.remove(0);
-            // Proceeding is now an index below after removal
-            let mut proceeding = users.remove(i + 2);
-            add_relationship_request(&mut preceding, &mut proceeding).await;
-            add_relationship_request(&mut proceeding, &mut preceding).await;
+        // A <- B <- C
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
+        add_relationship_request(&mut user_b, &mut user_c).await;
+        add_relationship_request(&mut user_c, &mut user_b).await;
 
-            let proofs = get_available_degrees_request(&mut proceeding)
-                .await
-                .unwrap();
+        let phrase = String::from("Prove it for me");
+        _ = phrase_request(&phrase, String::from("Delegated"), &mut user_a).await;
 
-            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+        // decrypt the auth secret user a shared with user b
+        let proofs = get_available_degrees_request(&mut user_b).await.unwrap();
+        let preceding = get_proving_data_request(&proofs[0], &mut user_b).await;
+        let auth_secret = user_b.decrypt_auth_secret(AuthSecretEncrypted {
+            ephemeral_key: preceding.ephemeral_key,
+            ciphertext: preceding.ciphertext,
+            username: preceding.username,
+            recipient: user_b.pubkey().compress(),
+        });
+        let auth_secrets = [
+            auth_secret.auth_secret.to_bytes(),
+            user_b.auth_secret().to_bytes(),
+        ];
 
-            // Add users back to vector
-            users.insert(0, preceding);
-            users.insert(i + 3, proceeding);
-        }
-        println!("Done");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
-        println!("================");
+        // delegated proving is rejected until user b opts in
+        let (code, _) = delegated_degree_proof_request(&proofs[0], auth_secrets, &mut user_b).await;
+        assert_eq!(code, Status::Forbidden.code);
+        set_delegated_proving_request(&mut user_b, true).await;
+        let (code, _) = delegated_degree_proof_request(&proofs[0], auth_secrets, &mut user_b).await;
+        assert_eq!(code, Status::Created.code);
+
+        // build the same proof client-side
+        let mut proof = decompress_proof(&preceding.proof);
+        let previous_output = verify_nova_proof(
+            &proof,
+            &public_params,
+            steps_for_degree(preceding.degree as usize),
+        )
+        .unwrap()
+        .0;
+        continue_nova_proof(
+            &vec![auth_secret.username.clone(), user_b.username().clone()],
+            &vec![auth_secret.auth_secret, user_b.auth_secret().clone()],
+            &mut proof,
+            previous_output,
+            wc_path,
+            &r1cs,
+            &public_params,
+        )
+        .unwrap();
+
+        // the proof stored for user b should match the client-side proof
+        let proofs = get_available_degrees_request(&mut user_c).await.unwrap();
+        let stored = get_proving_data_request(&proofs[0], &mut user_c).await;
+        assert_eq!(stored.degree, 2);
+        assert_eq!(
+            stored.proof,
+            compress_proof(&proof),
+            "Delegated proof should match the client-side proof"
+        );
     }
 
     #[rocket::async_test]
-    async fn test_get_degrees_refactor() {
-        let context = GrapevineTestContext::init().await;
-
+    async fn test_migrate_proofs() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
-        // Create test users
-        let mut users = vec![
-            GrapevineAccount::new(String::from("User_A")),
-            GrapevineAccount::new(String::from("User_B")),
-            GrapevineAccount::new(String::from("User_C")),
-            GrapevineAccount::new(String::from("User_D")),
-        ];
+        let context = GrapevineTestContext::init().await;
 
-        for i in 0..users.len() {
-            let request = users[i].create_user_request();
+        let mut user_a = GrapevineAccount::new(String::from("user_migrate_1_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_migrate_1_b"));
+        let mut user_c = GrapevineAccount::new(String::from("user_migrate_1_c"));
+        for user in [&user_a, &user_b, &user_c] {
+            let request = user.create_user_request();
             create_user_request(&context, &request).await;
         }
 
-        // Create phrase a phrase as User A
-        let phrase = String::from("You are what you eat");
-        let description = String::from("Mediocre cryptographer");
-        _ = phrase_request(&phrase, description, &mut users[0]).await;
+        // A <- B <- C
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
+        add_relationship_request(&mut user_b, &mut user_c).await;
+        add_relationship_request(&mut user_c, &mut user_b).await;
 
-        // Add relationship and degree proofs: A <- B, B <- C, C <- D
-        for i in 0..3 {
-            // Remove users from vector to reference
-            let mut preceding = users.remove(i);
-            // Proceeding is now an index below after removal
-            let mut proceeding = users.remove(i);
+        // build a collection of 3 proofs
+        let phrase = String::from("Old proofs, new format");
+        _ = phrase_request(&phrase, String::from("Migration"), &mut user_a).await;
+        let proofs = get_available_degrees_request(&mut user_b).await.unwrap();
+        create_degree_proof_request(&proofs[0], &mut user_b).await;
+        let proofs = get_available_degrees_request(&mut user_c).await.unwrap();
+        create_degree_proof_request(&proofs[0], &mut user_c).await;
+
+        // migrate one proof at a time, resuming from the last migrated proof
+        let mut after = None;
+        let mut processed = 0;
+        loop {
+            let res = migrate_proofs_request(after, 1).await;
+            assert!(res.failed.is_empty(), "Every proof should verify");
+            if res.processed == 0 {
+                assert!(res.last.is_none());
+                break;
+            }
+            processed += res.processed;
+            after = res.last;
+        }
+        assert_eq!(processed, 3, "Every proof should be processed once");
 
-            add_relationship_request(&mut preceding, &mut proceeding).await;
-            add_relationship_request(&mut proceeding, &mut preceding).await;
+        // rerunning the migration changes nothing
+        let res = migrate_proofs_request(None, 100).await;
+        assert_eq!(res.processed, 3);
+        assert_eq!(res.migrated, 0);
 
-            let proofs = get_available_degrees_request(&mut proceeding)
-                .await
-                .unwrap();
-            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+        // all proofs still verify against their stored metadata
+        let mismatches = scan_proof_integrity_request().await;
+        assert!(mismatches.is_empty(), "Migrated proofs should still verify");
+    }
 
-            // Add users back to vector
-            users.insert(i, preceding);
-            users.insert(i + 1, proceeding);
+    #[rocket::async_test]
+    async fn test_get_connected_origins() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_origins_1_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_origins_1_b"));
+        let mut user_x = GrapevineAccount::new(String::from("user_origins_1_x"));
+        let mut user_y = GrapevineAccount::new(String::from("user_origins_1_y"));
+        let mut user_u = GrapevineAccount::new(String::from("user_origins_1_u"));
+        for user in [&user_a, &user_b, &user_x, &user_y, &user_u] {
+            let request = user.create_user_request();
+            create_user_request(&context, &request).await;
         }
 
-        // Get degree proofs for user C
-        let degrees = get_all_degrees(&users[2]).await;
-        println!("Degrees: {:?}", degrees);
-        let degrees = get_all_degrees(&users[1]).await;
-        println!("Degrees B: {:?}", degrees);
-        let degrees = get_all_degrees(&users[0]).await;
-        println!("Degrees A: {:?}", degrees);
+        // A <- U <- Y <- X <- B
+        add_relationship_request(&mut user_a, &mut user_u).await;
+        add_relationship_request(&mut user_u, &mut user_a).await;
+        add_relationship_request(&mut user_b, &mut user_x).await;
+        add_relationship_request(&mut user_x, &mut user_b).await;
+        add_relationship_request(&mut user_x, &mut user_y).await;
+        add_relationship_request(&mut user_y, &mut user_x).await;
+        add_relationship_request(&mut user_y, &mut user_u).await;
+        add_relationship_request(&mut user_u, &mut user_y).await;
+
+        // user u proves user b's phrase at degree 4
+        let phrase_b = String::from("A phrase from far away");
+        _ = phrase_request(&phrase_b, String::from("Far"), &mut user_b).await;
+        for user in [&mut user_x, &mut user_y, &mut user_u] {
+            let proofs = get_available_degrees_request(user).await.unwrap();
+            create_degree_proof_request(&proofs[0], user).await;
+        }
+
+        // user u proves user a's phrase at degree 2
+        let phrase_a = String::from("A phrase from nearby");
+        _ = phrase_request(&phrase_a, String::from("Near"), &mut user_a).await;
+        let proofs = get_available_degrees_request(&mut user_u).await.unwrap();
+        create_degree_proof_request(&proofs[0], &mut user_u).await;
+
+        // user u creates their own phrase which should not be listed as an origin
+        let phrase_u = String::from("A phrase of my own");
+        _ = phrase_request(&phrase_u, String::from("Own"), &mut user_u).await;
+
+        let origins = get_connected_origins_request(&mut user_u).await.unwrap();
+        assert_eq!(origins.len(), 2, "User U should be connected to 2 origins");
+        assert_eq!(origins[0].origin_username, *user_a.username());
+        assert_eq!(origins[0].min_degree, 2);
+        assert_eq!(origins[1].origin_username, *user_b.username());
+        assert_eq!(origins[1].min_degree, 4);
+
+        // user b only knows their own phrase
+        let origins = get_connected_origins_request(&mut user_b).await.unwrap();
+        assert!(
+            origins.is_empty(),
+            "User B has no proofs back to other origins"
+        );
     }
 
     #[rocket::async_test]
-    async fn test_inactive_relationships_hidden_in_degree_return() {
-        let context = GrapevineTestContext::init().await;
+    async fn test_get_circuit_info() {
+        let info = get_circuit_info_request().await;
+        assert!(!info.circuit_version.is_empty());
+        assert!(!info.r1cs_hash.is_empty());
+        assert!(!info.params_hash.is_empty());
+
+        // hashes should fingerprint the artifacts clients download from /static
+        let r1cs = std::fs::read("static/grapevine.r1cs").unwrap();
+        let public_params = std::fs::read("static/public_params.json").unwrap();
+        assert_eq!(info.r1cs_hash, artifact_hash(&r1cs));
+        assert_eq!(info.params_hash, artifact_hash(&public_params));
+    }
 
+    #[rocket::async_test]
+    async fn test_search_phrases() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
-        // Create test users
-        let mut users = vec![
-            GrapevineAccount::new(String::from("User_A")),
-            GrapevineAccount::new(String::from("User_B")),
-            GrapevineAccount::new(String::from("User_C")),
-            GrapevineAccount::new(String::from("User_D")),
-            GrapevineAccount::new(String::from("User_E")),
-            GrapevineAccount::new(String::from("User_F")),
-        ];
+        let context = GrapevineTestContext::init().await;
 
-        for i in 0..users.len() {
-            let request = users[i].create_user_request();
+        let mut user_a = GrapevineAccount::new(String::from("user_search_1_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_search_1_b"));
+        for user in [&user_a, &user_b] {
+            let request = user.create_user_request();
             create_user_request(&context, &request).await;
         }
 
-        // Create phrase a phrase as User A
-        let phrase = String::from("The sheep waited patiently in the field");
-        let description = String::from("Sheep have no patience");
-        _ = phrase_request(&phrase, description, &mut users[0]).await;
+        // user a creates three phrases, two with a description containing "garden"
+        let descriptions = ["Garden gnomes", "Rock garden", "Ocean tides"];
+        for (i, description) in descriptions.iter().enumerate() {
+            let phrase = format!("Searchable phrase {}", i);
+            _ = phrase_request(&phrase, String::from(*description), &mut user_a).await;
+        }
 
-        // Add relationship and degree proofs
-        for i in 0..users.len() - 1 {
-            // Remove users from vector to reference
-            let mut preceding = users.remove(i);
-            // Proceeding is now an index below after removal
-            let mut proceeding = users.remove(i);
+        let results = search_phrases_request(&mut user_a, "GARDEN").await.unwrap();
+        assert_eq!(
+            results.len(),
+            2,
+            "Search should be a case-insensitive substring match"
+        );
+        assert_eq!(results[0].description, descriptions[0]);
+        assert_eq!(results[1].description, descriptions[1]);
 
-            add_relationship_request(&mut preceding, &mut proceeding).await;
-            add_relationship_request(&mut proceeding, &mut preceding).await;
+        // user b has no connection to user a's phrases
+        let results = search_phrases_request(&mut user_b, "garden").await.unwrap();
+        assert!(
+            results.is_empty(),
+            "Unconnected phrases should not be visible"
+        );
+    }
 
-            let proofs = get_available_degrees_request(&mut proceeding)
-                .await
-                .unwrap();
-            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+    #[rocket::async_test]
+    async fn test_identity_proof_over_body_limit() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
 
-            // Add users back to vector
-            users.insert(i, preceding);
-            users.insert(i + 1, proceeding);
-        }
+        let context = GrapevineTestContext::init().await;
 
-        // Link 3 middle users to A
-        for i in 0..3 {
-            // Remove users from vector to reference
-            let mut preceding = users.remove(0);
-            // Proceeding is now an index below after removal
-            let mut proceeding = users.remove(i + 1);
+        let user = GrapevineAccount::new(String::from("user_body_limit_1"));
+        let request = user.create_user_request();
+        create_user_request(&context, &request).await;
 
-            add_relationship_request(&mut preceding, &mut proceeding).await;
-            add_relationship_request(&mut proceeding, &mut preceding).await;
+        // send a body one byte over the configured identity proof limit
+        let limit = ProofLimits::from_env().identity;
+        let body = vec![REQUEST_VERSION; limit as usize + 1];
+        let signature = generate_request_signature(&user, "POST", "/proof/phrase");
+        let res = context
+            .client
+            .post("/proof/phrase")
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", user.username().clone()))
+            .body(body)
+            .dispatch()
+            .await;
 
-            let proofs = get_available_degrees_request(&mut proceeding)
-                .await
-                .unwrap();
-            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+        assert_eq!(res.status(), Status::PayloadTooLarge);
+        let msg = res.into_string().await.unwrap();
+        assert_eq!(msg, format!("Request body exceeds {} bytes", limit));
+    }
 
-            // Add users back to vector
-            users.insert(0, preceding);
-            users.insert(i + 2, proceeding);
+    #[rocket::async_test]
+    async fn test_degree_proof_batch() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_batch_1_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_batch_1_b"));
+        for user in [&user_a, &user_b] {
+            let request = user.create_user_request();
+            create_user_request(&context, &request).await;
         }
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
 
-        // Get degrees
-        let degrees = get_all_degrees(&mut users[3]).await;
+        // user a creates two phrases for user b to prove
+        for i in 0..2 {
+            let phrase = format!("Batched phrase {}", i);
+            _ = phrase_request(&phrase, String::from("Batch"), &mut user_a).await;
+        }
+        let available = get_available_degrees_request(&mut user_b).await.unwrap();
+        assert_eq!(available.len(), 2);
+        let valid_0 = build_degree_proof_request(&available[0], &mut user_b).await;
+        let valid_1 = build_degree_proof_request(&available[1], &mut user_b).await;
+        // claim the wrong degree for a copy of a valid proof
+        let mut invalid = valid_0.clone();
+        invalid.degree = 3;
+
+        let results =
+            degree_proof_batch_request(vec![valid_0, invalid, valid_1], &mut user_b).await;
+        let statuses: Vec<u16> = results.iter().map(|result| result.status).collect();
         assert_eq!(
-            degrees.unwrap().len(),
-            1,
-            "Inactive degrees should have gotten removed from user's list of degree proofs"
-        )
+            statuses,
+            vec![
+                Status::Created.code,
+                Status::BadRequest.code,
+                Status::Created.code
+            ],
+            "Only the invalid proof should fail"
+        );
+        assert_eq!(results[0].previous, available[0]);
+        assert_eq!(results[2].previous, available[1]);
+
+        // both valid proofs were stored
+        let available = get_available_degrees_request(&mut user_b).await.unwrap();
+        assert!(available.is_empty(), "Both phrases should be proven");
+    }
+
+    #[test]
+    fn test_error_response_status() {
+        let cases = [
+            (GrapevineError::PhraseTooLong, Status::BadRequest),
+            (GrapevineError::NotProofOwner, Status::Forbidden),
+            (GrapevineError::NotPhraseOrigin, Status::Forbidden),
+            (GrapevineError::PhraseNotFound, Status::NotFound),
+            (GrapevineError::DegreeProofExists, Status::Conflict),
+            (GrapevineError::InternalError, Status::InternalServerError),
+        ];
+        for (error, status) in cases {
+            let response = catchers::GrapevineResponse::from(error);
+            assert_eq!(response.status(), status);
+        }
     }
 
     #[rocket::async_test]
-    async fn test_create_user_wrong_signature() {
-        // initiate context
+    async fn test_new_proof_event_delivered() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
         let context = GrapevineTestContext::init().await;
-        // generate two accounts
-        let account_1 = GrapevineAccount::new(String::from("userA1"));
-        let account_2 = GrapevineAccount::new(String::from("userA2"));
-        // generate a signature from account 2
-        let bad_sig = account_2.sign_username().compress();
-        // generate a "Create User" http request from account 1
-        let mut request = account_1.create_user_request();
-        // set the signature for creating account 1 to be the signature of account 2
-        request.signature = bad_sig;
 
-        // check response failure
-        let msg = create_user_request(&context, &request).await;
-        assert!(
-            msg.contains("Could not verify user creation signature"),
-            "Request should fail due to mismatched msg"
+        let mut user_a = GrapevineAccount::new(String::from("user_proof_event_1"));
+        let mut user_b = GrapevineAccount::new(String::from("user_proof_event_2"));
+        let user_c = GrapevineAccount::new(String::from("user_proof_event_3"));
+        for user in [&user_a, &user_b, &user_c] {
+            create_user_request(&context, &user.create_user_request()).await;
+        }
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
+
+        // subscribe user b and unrelated user c to new proof events
+        let oid_b = get_user_request(&context, user_b.username().clone())
+            .await
+            .unwrap()
+            .id
+            .unwrap();
+        let oid_c = get_user_request(&context, user_c.username().clone())
+            .await
+            .unwrap()
+            .id
+            .unwrap();
+        let mut receiver_b = PROOF_EVENTS.subscribe(&oid_b);
+        let mut receiver_c = PROOF_EVENTS.subscribe(&oid_c);
+
+        // user a creating a phrase should notify user b that it can build from it
+        let phrase = String::from("Notify my relationships about this one");
+        let description = String::from("Pushed to relationships");
+        _ = phrase_request(&phrase, description, &mut user_a).await;
+        let available = get_available_degrees_request(&mut user_b).await.unwrap();
+        let event = receiver_b.try_recv().unwrap();
+        assert_eq!(
+            event,
+            AvailableProofEvent {
+                proof: available[0].clone(),
+                username: user_a.username().clone(),
+                degree: 1,
+            }
         );
+        assert!(receiver_c.try_recv().is_err());
     }
 
     #[rocket::async_test]
-    async fn test_username_exceeding_character_limit() {
+    async fn test_expired_proof_not_available() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
         let context = GrapevineTestContext::init().await;
 
-        let account = GrapevineAccount::new(String::from("userA1"));
+        let mut user_a = GrapevineAccount::new(String::from("user_proof_expiry_1"));
+        let mut user_b = GrapevineAccount::new(String::from("user_proof_expiry_2"));
+        create_user_request(&context, &user_a.create_user_request()).await;
+        create_user_request(&context, &user_b.create_user_request()).await;
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
+        let oid_a = get_user_request(&context, user_a.username().clone())
+            .await
+            .unwrap()
+            .id
+            .unwrap();
 
-        let mut request = account.create_user_request();
+        // store one proof that has already expired and one that expires tomorrow as user a
+        let db = context.client.rocket().state::<GrapevineDB>().unwrap();
+        let now = DateTime::now().timestamp_millis();
+        let mut proof_oids = vec![];
+        for expires_at in [now - 1000, now + 24 * 60 * 60 * 1000] {
+            let description = String::from("Expiring phrase");
+            let (phrase, _) = db
+                .create_phrase(random_fr().to_bytes(), description, vec![])
+                .await
+                .unwrap();
+            let proof = DegreeProof {
+                id: None,
+                inactive: Some(false),
+                phrase: Some(phrase),
+                auth_hash: Some(random_fr().to_bytes()),
+                user: Some(oid_a),
+                degree: Some(1),
+                ciphertext: None,
+                proof: Some(vec![]),
+                preceding: None,
+                proceeding: Some(vec![]),
+                expires_at: Some(DateTime::from_millis(expires_at)),
+                content_hash: None,
+            };
+            proof_oids.push(db.add_proof(&oid_a, &proof).await.unwrap());
+        }
 
-        let username = "fake_username_1234567890_abcdef";
+        // only the unexpired proof can be built from
+        let available = get_available_degrees_request(&mut user_b).await.unwrap();
+        assert_eq!(available, vec![proof_oids[1].to_string()]);
+    }
 
-        request.username = username.to_string();
+    #[rocket::async_test]
+    async fn test_in_flight_proof_completes_during_shutdown() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
 
-        let msg = create_user_request(&context, &request).await;
+        let context = GrapevineTestContext::init().await;
 
-        let condition = msg.contains("UsernameTooLong") && msg.contains(username);
+        let mut user_a = GrapevineAccount::new(String::from("user_shutdown_1"));
+        let mut user_b = GrapevineAccount::new(String::from("user_shutdown_2"));
+        create_user_request(&context, &user_a.create_user_request()).await;
+        create_user_request(&context, &user_b.create_user_request()).await;
 
-        assert!(
-            condition,
-            "Username should be marked as exceeding 30 characters"
-        );
+        // build a phrase proof for user a to be in flight when shutdown starts
+        let phrase = String::from("Submitted right before shutdown");
+        let proof = nova_proof(
+            use_wasm().unwrap(),
+            &use_r1cs().unwrap(),
+            &use_public_params().unwrap(),
+            &phrase,
+            &vec![user_a.username().clone()],
+            &vec![user_a.auth_secret().clone()],
+        )
+        .unwrap();
+        let body = PhraseRequest {
+            version: REQUEST_VERSION,
+            proof: compress_proof(&proof),
+            ciphertext: user_a.encrypt_phrase(&phrase).unwrap(),
+            description: String::from("In flight"),
+            tags: vec![],
+        };
+        let signature = generate_request_signature(&user_a, "POST", "/proof/phrase");
+        let in_flight = context
+            .client
+            .post("/proof/phrase")
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", user_a.username().clone()))
+            .body(bincode::serialize(&body).unwrap())
+            .dispatch();
+
+        // signal shutdown once the submission is being verified
+        let drain = context.client.rocket().state::<ProofDrain>().unwrap();
+        let shutdown = async {
+            while drain.in_flight() == 0 {
+                rocket::tokio::task::yield_now().await;
+            }
+            drain.stop();
+            // new submissions are turned away while draining
+            let signature = generate_request_signature(&user_b, "POST", "/proof/phrase");
+            let res = context
+                .client
+                .post("/proof/phrase")
+                .header(Header::new("X-Authorization", signature))
+                .header(Header::new("X-Username", user_b.username().clone()))
+                .body(vec![])
+                .dispatch()
+                .await;
+            let status = res.status();
+            let msg = res.into_string().await.unwrap();
+            // wait for the in-flight submission to finish
+            let remaining = drain.wait(Duration::from_secs(60)).await;
+            (status, msg, remaining)
+        };
+        let (res, (status, msg, remaining)) = rocket::tokio::join!(in_flight, shutdown);
+        assert_eq!(status, Status::ServiceUnavailable);
+        assert!(msg.contains("ShuttingDown"));
+        assert_eq!(remaining, 0);
+        // the submission in flight when shutdown started was still stored
+        assert_eq!(res.status(), Status::Created);
+        let _ = user_a.increment_nonce(None);
+        let _ = user_b.increment_nonce(None);
     }
 
     #[rocket::async_test]
-    async fn test_username_with_non_ascii_characters() {
-        let context = GrapevineTestContext::init().await;
+    async fn test_phrases_by_tag() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
 
-        let username = "😍";
+        let context = GrapevineTestContext::init().await;
 
-        let account = GrapevineAccount::new(String::from(username));
+        let mut user = GrapevineAccount::new(String::from("user_phrase_tags"));
+        create_user_request(&context, &user.create_user_request()).await;
 
-        let request = account.create_user_request();
+        // create phrases with tags
+        let tags = |tags: &[&str]| tags.iter().map(|t| String::from(*t)).collect::<Vec<_>>();
+        let mut indexes = vec![];
+        for (phrase, phrase_tags) in [
+            ("Tagged phrase one", tags(&["music", "jazz"])),
+            ("Tagged phrase two", tags(&["music"])),
+        ] {
+            let description = String::from("Tagged");
+            let (code, msg) =
+                tagged_phrase_request(&String::from(phrase), description, phrase_tags, &mut user)
+                    .await;
+            assert_eq!(code, Status::Created.code);
+            let res = serde_json::from_str::<PhraseCreationResponse>(&msg).unwrap();
+            indexes.push(res.phrase_index);
+        }
 
-        let msg = create_user_request(&context, &request).await;
+        // invalid tags are rejected before the proof is verified
+        let (code, msg) = tagged_phrase_request(
+            &String::from("Badly tagged phrase"),
+            String::from("Tagged"),
+            tags(&["two words"]),
+            &mut user,
+        )
+        .await;
+        assert_eq!(code, Status::BadRequest.code);
+        assert!(msg.contains("InvalidTags"));
 
-        let condition = msg.contains("UsernameNotAscii") && msg.contains(username);
+        // clients still sending version 1 requests create untagged phrases
+        let phrase = String::from("Untagged phrase");
+        let proof = nova_proof(
+            use_wasm().unwrap(),
+            &use_r1cs().unwrap(),
+            &use_public_params().unwrap(),
+            &phrase,
+            &vec![user.username().clone()],
+            &vec![user.auth_secret().clone()],
+        )
+        .unwrap();
+        let body = PhraseRequestV1 {
+            version: 1,
+            proof: compress_proof(&proof),
+            ciphertext: user.encrypt_phrase(&phrase).unwrap(),
+            description: String::from("Untagged"),
+        };
+        let signature = generate_request_signature(&user, "POST", "/proof/phrase");
+        let res = context
+            .client
+            .post("/proof/phrase")
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", user.username().clone()))
+            .body(bincode::serialize(&body).unwrap())
+            .dispatch()
+            .await;
+        let _ = user.increment_nonce(None);
+        assert_eq!(res.status(), Status::Created);
+        let res = res.into_json::<PhraseCreationResponse>().await.unwrap();
+        indexes.push(res.phrase_index);
 
-        assert!(condition, "User should be created");
+        // list phrases by tag
+        let music = get_phrases_by_tag_request(&mut user, "music")
+            .await
+            .unwrap();
+        let music = music.iter().map(|p| p.phrase_index).collect::<Vec<_>>();
+        assert_eq!(music, indexes[0..2].to_vec());
+        let jazz = get_phrases_by_tag_request(&mut user, "jazz").await.unwrap();
+        assert_eq!(jazz.len(), 1);
+        assert_eq!(jazz[0].phrase_index, indexes[0]);
+        assert_eq!(jazz[0].tags, tags(&["music", "jazz"]));
+
+        // the origin can retag a phrase
+        let code = update_phrase_tags_request(&mut user, indexes[2], tags(&["jazz"])).await;
+        assert_eq!(code, Status::Ok.code);
+        let jazz = get_phrases_by_tag_request(&mut user, "jazz").await.unwrap();
+        let jazz = jazz.iter().map(|p| p.phrase_index).collect::<Vec<_>>();
+        assert_eq!(jazz, vec![indexes[0], indexes[2]]);
+        let code = update_phrase_tags_request(&mut user, indexes[2], tags(&["", "jazz"])).await;
+        assert_eq!(code, Status::BadRequest.code);
     }
 
     #[rocket::async_test]
-    async fn test_successful_user_creation() {
+    async fn test_resubmitted_proof_returns_existing() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
-        let username = String::from("username_successful_creation");
-
         let context = GrapevineTestContext::init().await;
 
-        let account = GrapevineAccount::new(username.clone());
-
-        let request = account.create_user_request();
+        let user_a = GrapevineAccount::new(String::from("user_resubmit_a"));
+        let user_b = GrapevineAccount::new(String::from("user_resubmit_b"));
+        create_user_request(&context, &user_a.create_user_request()).await;
+        create_user_request(&context, &user_b.create_user_request()).await;
 
-        assert_eq!(
-            create_user_request(&context, &request).await,
-            "User succefully created",
-            "User should be created"
-        );
+        // build the identity proof once so both submissions carry identical bytes
+        let phrase = String::from("Resubmitted phrase");
+        let proof = nova_proof(
+            use_wasm().unwrap(),
+            &use_r1cs().unwrap(),
+            &use_public_params().unwrap(),
+            &phrase,
+            &vec![user_a.username().clone()],
+            &vec![user_a.auth_secret().clone()],
+        )
+        .unwrap();
+        let body = PhraseRequest {
+            version: REQUEST_VERSION,
+            proof: compress_proof(&proof),
+            ciphertext: user_a.encrypt_phrase(&phrase).unwrap(),
+            description: String::from("Resubmitted"),
+            tags: vec![],
+        };
+        let serialized = bincode::serialize(&body).unwrap();
+
+        // submit as the prover twice, then as a different user
+        let mut users = [user_a, user_b];
+        let mut responses = vec![];
+        for i in [0, 0, 1] {
+            let user = &mut users[i];
+            let signature = generate_request_signature(user, "POST", "/proof/phrase");
+            let res = context
+                .client
+                .post("/proof/phrase")
+                .header(Header::new("X-Authorization", signature))
+                .header(Header::new("X-Username", user.username().clone()))
+                .body(serialized.clone())
+                .dispatch()
+                .await;
+            let _ = user.increment_nonce(None);
+            responses.push((res.status(), res.into_string().await.unwrap()));
+        }
 
-        // Check that user was stored in DB
-        let user = get_user_request(&context, username).await;
-        assert!(user.is_some(), "User should be stored inside of MongoDB");
+        // the first submission is stored
+        assert_eq!(responses[0].0, Status::Created);
+        let created = serde_json::from_str::<PhraseCreationResponse>(&responses[0].1).unwrap();
+        assert!(created.new_phrase);
+        // resubmitting returns the stored proof instead of a conflict
+        assert_eq!(responses[1].0, Status::Ok);
+        let existing = serde_json::from_str::<PhraseCreationResponse>(&responses[1].1).unwrap();
+        assert_eq!(existing.proof, created.proof);
+        assert_eq!(existing.phrase_index, created.phrase_index);
+        assert!(!existing.new_phrase);
+        // another user cannot claim the same proof bytes
+        assert_eq!(responses[2].0, Status::Conflict);
+        assert!(responses[2].1.contains("DegreeProofExists"));
     }
 
     #[rocket::async_test]
-    async fn test_duplicate_user() {
+    async fn test_download_own_proof() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
-        let username = String::from("username_duplicate_user");
-
         let context = GrapevineTestContext::init().await;
 
-        let account = GrapevineAccount::new(username.clone());
+        let mut user_a = GrapevineAccount::new(String::from("user_download_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_download_b"));
+        create_user_request(&context, &user_a.create_user_request()).await;
+        create_user_request(&context, &user_b.create_user_request()).await;
 
-        let request = account.create_user_request();
+        let phrase = String::from("Downloadable phrase");
+        let (code, msg) = phrase_request(&phrase, String::from("Download"), &mut user_a).await;
+        assert_eq!(code, Status::Created.code);
+        let oid = serde_json::from_str::<PhraseCreationResponse>(&msg)
+            .unwrap()
+            .proof;
 
-        create_user_request(&context, &request).await;
-        let msg = create_user_request(&context, &request).await;
+        // the owner receives the compressed proof bytes
+        let (status, content_type, proof) =
+            download_proof_request(&context, &mut user_a, &oid).await;
+        assert_eq!(status, Status::Ok);
+        assert_eq!(content_type, Some(ContentType::Binary));
+        let params = use_public_params().unwrap();
+        assert!(check_proof(&proof, &params, steps_for_degree(1), 1).is_ok());
 
-        let condition = msg.contains("UserExists") && msg.contains("username_duplicate_user");
+        // other users cannot download it
+        let (status, _, msg) = download_proof_request(&context, &mut user_b, &oid).await;
+        assert_eq!(status, Status::Forbidden);
+        assert!(String::from_utf8(msg).unwrap().contains("NotProofOwner"));
 
-        assert!(condition, "Users should be enforced to be unique.")
+        // unknown proofs are not found
+        let missing = ObjectId::new().to_hex();
+        let (status, _, _) = download_proof_request(&context, &mut user_a, &missing).await;
+        assert_eq!(status, Status::NotFound);
     }
 
     #[rocket::async_test]
-    async fn test_missing_authorization_headers() {
+    async fn test_merkle_tree_of_users() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
         let context = GrapevineTestContext::init().await;
 
-        // test without X-Username or X-Authorization header
-        let res = context.client.get("/user/degrees").dispatch().await;
-        assert_eq!(res.status(), Status::BadRequest);
+        // the tree starts empty
+        let res = context.client.get("/user/merkle-root").dispatch().await;
+        let empty = res.into_json::<MerkleRootResponse>().await.unwrap();
+        assert_eq!(empty.root, merkle_root(&[]));
+        assert_eq!(empty.size, 0);
+
+        // each new user is appended to the tree
+        let usernames = ["user_merkle_a", "user_merkle_b", "user_merkle_c"];
+        for username in usernames {
+            let user = GrapevineAccount::new(String::from(username));
+            create_user_request(&context, &user.create_user_request()).await;
+        }
+        let leaves = usernames
+            .iter()
+            .map(|username| merkle_leaf(&String::from(*username)).unwrap())
+            .collect::<Vec<_>>();
+        let res = context.client.get("/user/merkle-root").dispatch().await;
+        let root = res.into_json::<MerkleRootResponse>().await.unwrap();
+        assert_eq!(root.root, merkle_root(&leaves));
+        assert_eq!(root.size, 3);
+
+        // inclusion paths verify against the root
+        for (index, username) in usernames.iter().enumerate() {
+            let res = context
+                .client
+                .get(format!("/user/merkle-proof/{}", username))
+                .dispatch()
+                .await;
+            let proof = res.into_json::<MerkleProofResponse>().await.unwrap();
+            assert_eq!(proof.index, index as u32);
+            assert_eq!(proof.leaf, leaves[index]);
+            assert_eq!(proof.root, root.root);
+            assert!(verify_merkle_proof(
+                &proof.leaf,
+                proof.index,
+                &proof.siblings,
+                &root.root
+            ));
+        }
 
-        // make user
-        let user = GrapevineAccount::new(String::from("user_missing_auth_header"));
-        // test without X-Authorization header
-        let username = user.username().clone();
-        let username_header = Header::new("X-Username", username);
+        // users that do not exist have no path
         let res = context
             .client
-            .get("/user/degrees")
-            .header(username_header)
+            .get("/user/merkle-proof/user_merkle_missing")
             .dispatch()
             .await;
-        assert_eq!(res.status(), Status::BadRequest);
+        assert_eq!(res.status(), Status::NotFound);
+    }
 
-        // test without X-Username header
-        let signature_header = Header::new("X-Authorization", generate_nonce_signature(&user));
+    #[rocket::async_test]
+    async fn test_signature_bound_to_request() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user = GrapevineAccount::new(String::from("user_request_signature"));
+        create_user_request(&context, &user.create_user_request()).await;
+
+        // a signature for one route does not authorize another route with the same nonce
+        let signature = generate_request_signature(&user, "GET", "/user/degrees");
+        let res = context
+            .client
+            .post("/proof/degree")
+            .header(Header::new("X-Authorization", signature.clone()))
+            .header(Header::new("X-Username", user.username().clone()))
+            .body(vec![])
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Unauthorized);
+        assert!(res.into_string().await.unwrap().contains("Signature"));
+
+        // the nonce was not consumed, so the signature still works for the request it was made for
         let res = context
             .client
             .get("/user/degrees")
-            .header(signature_header)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", user.username().clone()))
             .dispatch()
             .await;
-        assert_eq!(res.status(), Status::BadRequest);
+        assert_eq!(res.status(), Status::Ok);
     }
 
     #[rocket::async_test]
-    #[ignore]
-    async fn test_invalid_authorization_header() {
+    async fn test_verification_does_not_block_requests() {
         let context = GrapevineTestContext::init().await;
 
-        let username = String::from("user_invalid_auth_header");
+        // hold a verification on the pool until every health check has been answered. If it ran
+        // on the async runtime instead, the health checks could never complete
+        let (release, blocked) = std::sync::mpsc::channel::<()>();
+        let verification = verifier::verify_in_pool(move || blocked.recv().is_ok());
+        let health_checks = async {
+            let checks = (0..32).map(|_| context.client.get("/health").dispatch());
+            let responses = futures::future::join_all(checks).await;
+            release.send(()).unwrap();
+            responses
+        };
+        let run = async { rocket::tokio::join!(verification, health_checks) };
+        let (verified, responses) = rocket::tokio::time::timeout(Duration::from_secs(30), run)
+            .await
+            .unwrap();
 
-        let auth_header = Header::new("X-Authorization", "00000000000");
-        let username_header = Header::new("X-Username", username);
+        assert!(verified.unwrap());
+        assert_eq!(responses.len(), 32);
+        assert!(responses.iter().all(|res| res.status() == Status::Ok));
+    }
+
+    #[rocket::async_test]
+    async fn test_recover_account_with_guardians() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user = GrapevineAccount::new(String::from("user_recover"));
+        create_user_request(&context, &user.create_user_request()).await;
+        let mut guardians: Vec<GrapevineAccount> = (0..3)
+            .map(|i| GrapevineAccount::new(format!("user_recover_guardian_{}", i)))
+            .collect();
+        for guardian in guardians.iter_mut() {
+            create_user_request(&context, &guardian.create_user_request()).await;
+            add_relationship_request(guardian, &mut user).await;
+            add_relationship_request(&mut user, guardian).await;
+        }
+        let usernames: Vec<String> = guardians.iter().map(|g| g.username().clone()).collect();
+
+        // the threshold cannot exceed the number of guardians
+        let (code, _) = set_guardians_request(&mut user, usernames.clone(), 4).await;
+        assert_eq!(code, Status::BadRequest.code);
+        let (code, _) = set_guardians_request(&mut user, usernames.clone(), 2).await;
+        assert_eq!(code, Status::Ok.code);
+
+        // one approval does not meet the threshold
+        let private_key = new_private_key();
+        let mut recovered = user.with_private_key(private_key);
+        let username = user.username().clone();
+        let pubkey = recovered.pubkey();
+        let approve = |guardian: &GrapevineAccount| GuardianApproval {
+            guardian: guardian.username().clone(),
+            signature: guardian
+                .sign_recovery(&username, &pubkey, user.nonce())
+                .compress(),
+        };
+        let mut request = RecoveryRequest {
+            username: username.clone(),
+            pubkey: pubkey.compress(),
+            approvals: vec![approve(&guardians[0])],
+        };
+        let (code, msg) = recover_account_request(&request).await;
+        assert_eq!(code, Status::Unauthorized.code);
+        assert!(msg.unwrap().contains("RecoveryThresholdNotMet"));
+
+        // repeating an approval does not count twice
+        request.approvals.push(approve(&guardians[0]));
+        let (code, _) = recover_account_request(&request).await;
+        assert_eq!(code, Status::Unauthorized.code);
 
+        // two distinct approvals rotate the key
+        request.approvals = vec![approve(&guardians[0]), approve(&guardians[2])];
+        let (code, _) = recover_account_request(&request).await;
+        assert_eq!(code, Status::Ok.code);
+
+        // the approvals cannot be replayed since the nonce moved
+        let (code, _) = recover_account_request(&request).await;
+        assert_eq!(code, Status::Unauthorized.code);
+
+        // the old key no longer authenticates but the new key does
         let res = context
             .client
-            .get("/user/degrees")
-            .header(auth_header)
-            .header(username_header)
+            .get("/user/details")
+            .header(Header::new(
+                "X-Authorization",
+                generate_request_signature(&user, "GET", "/user/details"),
+            ))
+            .header(Header::new("X-Username", username.clone()))
             .dispatch()
             .await;
-        let message = res.into_string().await.unwrap();
-        println!("Message: {}", message);
+        assert_eq!(res.status(), Status::Unauthorized);
+        let _ = recovered.increment_nonce(None);
+        assert!(get_account_details_request(&mut recovered).await.is_some());
+
+        // a rotation checked against a nonce that has since moved is not applied
+        let db = context.client.rocket().state::<GrapevineDB>().unwrap();
+        let stale = db
+            .rotate_pubkey(&username, &guardians[1].pubkey().compress(), user.nonce())
+            .await;
+        assert!(matches!(stale, Err(GrapevineError::NonceReplayed(_, _))));
+        assert!(get_account_details_request(&mut recovered).await.is_some());
+    }
+
+    #[rocket::async_test]
+    async fn test_proof_children() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_children_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_children_b"));
+        create_user_request(&context, &user_a.create_user_request()).await;
+        create_user_request(&context, &user_b.create_user_request()).await;
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
+
+        let phrase = String::from("Phrase with children");
+        let (_, msg) = phrase_request(&phrase, String::from("Children"), &mut user_a).await;
+        let oid = serde_json::from_str::<PhraseCreationResponse>(&msg)
+            .unwrap()
+            .proof;
+
+        // nothing has been built on the proof yet
+        let (status, children) = get_proof_children_request(&context, &mut user_a, &oid).await;
+        assert_eq!(status, Status::Ok);
+        assert!(children.unwrap().is_empty());
+
+        // user b builds a degree 2 proof on user a's proof
+        let proofs = get_available_degrees_request(&mut user_b).await.unwrap();
+        assert_eq!(proofs, vec![oid.clone()]);
+        let (code, _) = create_degree_proof_request(&proofs[0], &mut user_b).await;
+        assert_eq!(code, Status::Created.code);
+
+        let (status, children) = get_proof_children_request(&context, &mut user_a, &oid).await;
+        assert_eq!(status, Status::Ok);
+        let children = children.unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].username, *user_b.username());
+        assert_eq!(children[0].degree, 2);
+        assert!(!children[0].inactive);
+
+        // only the maker of the proof can list its children
+        let (status, _) = get_proof_children_request(&context, &mut user_b, &oid).await;
+        assert_eq!(status, Status::Forbidden);
+    }
+
+    #[rocket::async_test]
+    async fn test_leaderboard_by_connections() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut users: Vec<GrapevineAccount> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|name| GrapevineAccount::new(format!("user_leaderboard_{}", name)))
+            .collect();
+        for user in users.iter() {
+            create_user_request(&context, &user.create_user_request()).await;
+        }
+        // a has 3 connections, b and c have 2, and d has 1
+        for (i, j) in [(0, 1), (0, 2), (0, 3), (1, 2)] {
+            let (left, right) = users.split_at_mut(j);
+            add_relationship_request(&mut left[i], &mut right[0]).await;
+            add_relationship_request(&mut right[0], &mut left[i]).await;
+        }
+
+        let client = &context.client;
+        let leaderboard = |uri: &'static str| async move {
+            let res = client.get(uri).dispatch().await;
+            let status = res.status();
+            (status, res.into_json::<Vec<LeaderboardEntry>>().await)
+        };
+        let (status, ranked) = leaderboard("/stats/leaderboard?metric=connections").await;
+        assert_eq!(status, Status::Ok);
+        let ranked = ranked.unwrap();
+        let usernames: Vec<&String> = ranked.iter().map(|entry| &entry.username).collect();
+        let expected: Vec<&String> = users.iter().map(|user| user.username()).collect();
+        assert_eq!(usernames, expected);
+        let scores: Vec<u64> = ranked.iter().map(|entry| entry.score).collect();
+        assert_eq!(scores, vec![3, 2, 2, 1]);
+
+        // the limit truncates the leaderboard
+        let (_, ranked) = leaderboard("/stats/leaderboard?limit=2").await;
+        assert_eq!(ranked.unwrap().len(), 2);
+
+        // the leaderboard is served from the cache until it expires
+        let (left, right) = users.split_at_mut(3);
+        add_relationship_request(&mut left[1], &mut right[0]).await;
+        add_relationship_request(&mut right[0], &mut left[1]).await;
+        let (_, ranked) = leaderboard("/stats/leaderboard").await;
+        assert_eq!(ranked.unwrap()[3].score, 1);
+
+        // unknown metrics are rejected
+        let (status, _) = leaderboard("/stats/leaderboard?metric=karma").await;
+        assert_eq!(status, Status::BadRequest);
+    }
+
+    #[rocket::async_test]
+    async fn test_relationship_auth_secret_requires_active_relationship() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_auth_secret_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_auth_secret_b"));
+        let mut user_c = GrapevineAccount::new(String::from("user_auth_secret_c"));
+        for user in [&user_a, &user_b, &user_c] {
+            create_user_request(&context, &user.create_user_request()).await;
+        }
+
+        // a pending relationship does not expose the auth secret
+        _ = add_relationship_request(&mut user_a, &mut user_b).await;
+        let (status, _) =
+            get_relationship_auth_secret_request(&context, &mut user_b, user_a.username()).await;
+        assert_eq!(status, Status::NotFound);
+
+        // once active the recipient can fetch and decrypt the sender's auth secret
+        _ = add_relationship_request(&mut user_b, &mut user_a).await;
+        let (status, encrypted) =
+            get_relationship_auth_secret_request(&context, &mut user_b, user_a.username()).await;
+        assert_eq!(status, Status::Ok);
+        let encrypted = encrypted.unwrap();
+        assert_eq!(&encrypted.username, user_a.username());
+        assert_eq!(encrypted.recipient, user_b.pubkey().compress());
+        let auth_secret = user_b.decrypt_auth_secret(encrypted);
+        assert_eq!(&auth_secret.auth_secret, user_a.auth_secret());
+
+        // a user without a relationship to the sender cannot fetch it
+        let (status, _) =
+            get_relationship_auth_secret_request(&context, &mut user_c, user_a.username()).await;
+        assert_eq!(status, Status::NotFound);
     }
 
     #[rocket::async_test]
-    async fn test_relationship_creation_with_empty_request_body() {
+    async fn test_relationships_sorted_by_username() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
         let context = GrapevineTestContext::init().await;
 
-        let user_a = GrapevineAccount::new(String::from("user_relationship_1_a"));
-        let user_b = GrapevineAccount::new(String::from("user_relationship_1_b"));
-
-        // Create users
-        let user_a_request = user_a.create_user_request();
-        let user_b_request = user_b.create_user_request();
-        create_user_request(&context, &user_a_request).await;
-        create_user_request(&context, &user_b_request).await;
+        let mut recipient = GrapevineAccount::new(String::from("user_sorted"));
+        create_user_request(&context, &recipient.create_user_request()).await;
 
-        let signature = user_a.sign_nonce();
-        let encoded = hex::encode(signature.compress());
+        // send requests in an order that is not alphabetical
+        let mut senders = vec![];
+        for username in ["user_sorted_c", "user_sorted_a", "user_sorted_b"] {
+            let mut sender = GrapevineAccount::new(String::from(username));
+            create_user_request(&context, &sender.create_user_request()).await;
+            _ = add_relationship_request(&mut sender, &mut recipient).await;
+            senders.push(sender);
+        }
 
-        let res = context
-            .client
-            .post("/user/relationship")
-            .header(Header::new("X-Authorization", encoded))
-            .header(Header::new("X-Username", user_a.username().clone()))
-            .json::<Vec<u8>>(&vec![])
-            .dispatch()
+        let expected = vec!["user_sorted_a", "user_sorted_b", "user_sorted_c"];
+        let pending = get_relationships_request(&context, &mut recipient, false)
             .await
-            .into_string()
+            .unwrap();
+        assert_eq!(pending, expected);
+
+        // accept in another non-alphabetical order
+        for i in [1, 2, 0] {
+            _ = add_relationship_request(&mut recipient, &mut senders[i]).await;
+        }
+        let active = get_relationships_request(&context, &mut recipient, true)
             .await
             .unwrap();
+        assert_eq!(active, expected);
+    }
 
-        println!("Message: {}", res);
+    #[rocket::async_test]
+    async fn test_phrase_commit_and_reveal() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
+
+        let mut user_a = GrapevineAccount::new(String::from("user_commit_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_commit_b"));
+        create_user_request(&context, &user_a.create_user_request()).await;
+        create_user_request(&context, &user_b.create_user_request()).await;
+
+        // commit to a phrase without sending it
+        let phrase = String::from("I knew this phrase before anyone else");
+        let blinding = new_blinding();
+        let commitment = commit_phrase(&phrase, &blinding).unwrap();
+        let commit = PhraseCommitRequest { commitment };
+        let (status, _) = phrase_commitment_request(&context, &mut user_a, "commit", &commit).await;
+        assert_eq!(status, Status::Created);
+        let (status, _) = phrase_commitment_request(&context, &mut user_a, "commit", &commit).await;
+        assert_eq!(status, Status::Conflict);
+
+        // an opening with the wrong blinding factor does not match
+        let phrase_hash = hash_phrase(&phrase).unwrap();
+        let mismatched = PhraseRevealRequest {
+            commitment,
+            phrase_hash,
+            blinding: new_blinding(),
+        };
+        let (status, msg) =
+            phrase_commitment_request(&context, &mut user_a, "reveal", &mismatched).await;
+        assert_eq!(status, Status::BadRequest);
+        assert!(msg.unwrap().contains("InvalidCommitment"));
+
+        // the correct opening reveals the phrase hash and when it was committed
+        let reveal = PhraseRevealRequest {
+            commitment,
+            phrase_hash,
+            blinding,
+        };
+        let (status, msg) =
+            phrase_commitment_request(&context, &mut user_a, "reveal", &reveal).await;
+        assert_eq!(status, Status::Ok);
+        let revealed = serde_json::from_str::<PhraseRevealResponse>(&msg.unwrap()).unwrap();
+        assert_eq!(revealed.phrase_hash, phrase_hash);
+        assert!(revealed.committed_at <= revealed.revealed_at);
+
+        // another user cannot claim the commitment with the same opening
+        let (status, _) = phrase_commitment_request(&context, &mut user_b, "reveal", &reveal).await;
+        assert_eq!(status, Status::NotFound);
+    }
 
+    #[rocket::async_test]
+    async fn test_mongo_pool_config_from_vars() {
+        // read the settings from a map rather than the process environment other tests share
+        let config_from = |vars: &[(&str, &str)]| {
+            MongoPoolConfig::from_vars(|name| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+        let config = config_from(&[
+            ("GRAPEVINE_MONGO_MAX_POOL_SIZE", "25"),
+            ("GRAPEVINE_MONGO_MIN_POOL_SIZE", "2"),
+            ("GRAPEVINE_MONGO_CONNECT_TIMEOUT_SECS", "7"),
+            ("GRAPEVINE_MONGO_SERVER_SELECTION_TIMEOUT_SECS", "12"),
+        ]);
+
+        let mut options = mongodb::options::ClientOptions::parse("mongodb://localhost:27017")
+            .await
+            .unwrap();
+        config.apply(&mut options);
+        assert_eq!(options.max_pool_size, Some(25));
+        assert_eq!(options.min_pool_size, Some(2));
+        assert_eq!(options.connect_timeout, Some(Duration::from_secs(7)));
         assert_eq!(
-            "User cannot have a relationship with themself", res,
-            "User should not be able to have a relationsip with themselves."
+            options.server_selection_timeout,
+            Some(Duration::from_secs(12))
+        );
+
+        // malformed values fall back to the defaults
+        let config = config_from(&[
+            ("GRAPEVINE_MONGO_MAX_POOL_SIZE", "many"),
+            ("GRAPEVINE_MONGO_CONNECT_TIMEOUT_SECS", "soon"),
+        ]);
+        assert_eq!(config.max_pool_size, None);
+        assert_eq!(
+            config.connect_timeout,
+            Duration::from_secs(DEFAULT_MONGO_CONNECT_TIMEOUT_SECS)
         );
     }
 
     #[rocket::async_test]
-    async fn test_relationship_creation_with_nonexistent_recipient() {
+    async fn test_export_and_import_proofs() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
         let context = GrapevineTestContext::init().await;
 
-        let mut user_a = GrapevineAccount::new(String::from("user_relationship_2_a"));
-        let mut user_b = GrapevineAccount::new(String::from("user_relationship_2_b"));
+        let mut user_a = GrapevineAccount::new(String::from("user_backup_1_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_backup_1_b"));
+        for user in [&user_a, &user_b] {
+            let request = user.create_user_request();
+            create_user_request(&context, &request).await;
+        }
+        let phrase = String::from("Backed up before the outage");
+        _ = phrase_request(&phrase, String::from("Backup"), &mut user_a).await;
+        let phrase = String::from("Restored after the outage");
+        _ = phrase_request(&phrase, String::from("Backup"), &mut user_b).await;
 
-        // Create user
-        let user_a_request = user_a.create_user_request();
-        create_user_request(&context, &user_a_request).await;
+        // the export lists both proofs and passes its own footer check
+        let exported = export_proofs_request().await;
+        let count = read_backup(&exported[..], |_| Ok(())).unwrap();
+        assert_eq!(count, 2);
 
-        let (_, msg) = add_relationship_request(&mut user_a, &mut user_b).await;
+        // restoring into an empty database brings back the same proofs
+        GrapevineDB::drop("grapevine_mocked").await;
+        let (code, msg) = import_proofs_request(exported.clone()).await;
+        assert_eq!(code, Status::Ok.code);
+        assert_eq!(msg.unwrap(), "2");
+        assert_eq!(export_proofs_request().await, exported);
+
+        // a corrupted backup is rejected
+        let mut corrupted = exported.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 1;
+        GrapevineDB::drop("grapevine_mocked").await;
+        let (code, msg) = import_proofs_request(corrupted).await;
+        assert_eq!(code, Status::BadRequest.code);
+        assert!(msg.unwrap().contains("InvalidBackup"));
+
+        // none of its proofs were written before the footer check failed
+        let exported = export_proofs_request().await;
+        assert_eq!(read_backup(&exported[..], |_| Ok(())).unwrap(), 0);
+    }
+
+    #[rocket::async_test]
+    async fn test_download_proof_encodings() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
+
+        let context = GrapevineTestContext::init().await;
 
+        let mut user = GrapevineAccount::new(String::from("user_encoding_1"));
+        create_user_request(&context, &user.create_user_request()).await;
+        let phrase = String::from("Sent however you like");
+        let (_, msg) = phrase_request(&phrase, String::from("Encoding"), &mut user).await;
+        let oid = serde_json::from_str::<PhraseCreationResponse>(&msg)
+            .unwrap()
+            .proof;
+
+        // the stored gzip proof is sent when nothing better is supported
+        let (encoding, gzip) =
+            download_encoded_proof_request(&context, &mut user, &oid, "br").await;
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+
+        // the first supported format the client lists is used
+        let (encoding, zstd) =
+            download_encoded_proof_request(&context, &mut user, &oid, "br, zstd, gzip").await;
+        assert_eq!(encoding.as_deref(), Some("zstd"));
+        assert_ne!(zstd, gzip);
+        let (encoding, raw) =
+            download_encoded_proof_request(&context, &mut user, &oid, "raw").await;
+        assert_eq!(encoding.as_deref(), Some("raw"));
+
+        // every format decodes to the same proof
+        let decoded = decode_proof(&gzip, ProofEncoding::Gzip).unwrap();
         assert_eq!(
-            msg.unwrap(),
-            "Recipient does not exist.",
-            "Recipient shouldn't exist"
+            compress_proof(&decode_proof(&zstd, ProofEncoding::Zstd).unwrap()),
+            compress_proof(&decoded)
+        );
+        assert_eq!(
+            compress_proof(&decode_proof(&raw, ProofEncoding::Raw).unwrap()),
+            compress_proof(&decoded)
         );
     }
 
     #[rocket::async_test]
-    async fn test_relationship_where_to_is_also_from() {
+    async fn test_nonce_bounds() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
         let context = GrapevineTestContext::init().await;
 
-        let mut user_a = GrapevineAccount::new(String::from("user_relationship_3_a"));
-        let mut clone_a = user_a.clone();
-
-        // Create user
-        let user_a_request = user_a.create_user_request();
-        create_user_request(&context, &user_a_request).await;
+        let mut user = GrapevineAccount::new(String::from("user_nonce_bounds"));
+        create_user_request(&context, &user.create_user_request()).await;
 
-        let (_, msg) = add_relationship_request(&mut user_a, &mut clone_a).await;
+        // a request with the current nonce is accepted
+        let nonce = user.nonce();
+        let signature = generate_request_signature(&user, "POST", "/user/delegated-proving/false");
+        let (status, _, _) = nonce_request(&context, &user, &signature, nonce).await;
+        assert_eq!(status, Status::Ok);
+        let _ = user.increment_nonce(None);
 
-        assert!(
-            msg.unwrap().contains("RelationshipSenderIsTarget"),
-            "Relationship cannot be made with your own account"
-        );
+        // replaying the same request is rejected as a replay
+        let (status, server_nonce, msg) = nonce_request(&context, &user, &signature, nonce).await;
+        assert_eq!(status, Status::Unauthorized);
+        assert!(msg.unwrap().contains("NonceReplayed"));
+        assert_eq!(server_nonce, Some((nonce + 1).to_string()));
+
+        // skipping ahead of the server is rejected without consuming the nonce
+        let signature = generate_request_signature(&user, "POST", "/user/delegated-proving/false");
+        let (status, server_nonce, msg) =
+            nonce_request(&context, &user, &signature, nonce + 5).await;
+        assert_eq!(status, Status::Unauthorized);
+        assert!(msg.unwrap().contains("NonceAhead"));
+        assert_eq!(server_nonce, Some((nonce + 1).to_string()));
+
+        // the current nonce still works after the rejections
+        let (status, _, _) = nonce_request(&context, &user, &signature, user.nonce()).await;
+        assert_eq!(status, Status::Ok);
     }
 
     #[rocket::async_test]
-    async fn test_successful_relationship_creation() {
+    async fn test_update_phrase_ciphertext() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
         let context = GrapevineTestContext::init().await;
 
-        let mut user_a = GrapevineAccount::new(String::from("user_relationship_4_a"));
-        let mut user_b = GrapevineAccount::new(String::from("user_relationship_4_b"));
+        let mut user_a = GrapevineAccount::new(String::from("user_reencrypt_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_reencrypt_b"));
+        create_user_request(&context, &user_a.create_user_request()).await;
+        create_user_request(&context, &user_b.create_user_request()).await;
+        let phrase = String::from("Readable after rotation");
+        let (_, msg) = phrase_request(&phrase, String::from("Rotation"), &mut user_a).await;
+        let phrase_index = serde_json::from_str::<PhraseCreationResponse>(&msg)
+            .unwrap()
+            .phrase_index;
+
+        // re-encrypt the stored phrase for a rotated keypair
+        let known = get_known_request(&context, &mut user_a).await;
+        let ciphertext = known[0].secret_phrase.unwrap();
+        let rotated = user_a.with_private_key(new_private_key());
+        let reencrypted = user_a
+            .reencrypt_phrase(&ciphertext, &rotated.phrase_key())
+            .unwrap();
+        let status =
+            phrase_ciphertext_request(&context, &mut user_a, phrase_index, reencrypted).await;
+        assert_eq!(status, Status::Ok);
+
+        // the stored ciphertext now decrypts with the rotated key
+        let known = get_known_request(&context, &mut user_a).await;
+        let stored = known[0].secret_phrase.unwrap();
+        assert_eq!(rotated.decrypt_phrase(&stored).unwrap(), phrase);
+
+        // users without a degree 1 proof of the phrase cannot set its ciphertext
+        let status =
+            phrase_ciphertext_request(&context, &mut user_b, phrase_index, reencrypted).await;
+        assert_eq!(status, Status::NotFound);
+    }
 
-        // Create user
-        let user_a_request = user_a.create_user_request();
-        let user_b_request = user_b.create_user_request();
-        create_user_request(&context, &user_a_request).await;
-        create_user_request(&context, &user_b_request).await;
+    #[rocket::async_test]
+    async fn test_get_degrees_filtered_by_degree() {
+        let context = GrapevineTestContext::init().await;
 
-        // Check add pending relationship request
-        let (code, msg) = add_relationship_request(&mut user_a, &mut user_b).await;
-        assert_eq!(
-            code,
-            Status::Created.code,
-            "Relationship add code should be 201"
-        );
-        assert_eq!(
-            msg.unwrap(),
-            "Relationship from user_relationship_4_a to user_relationship_4_b pending!",
-            "Relationship should be pending"
-        );
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
 
-        // Check activate relationship request
-        let (code, msg) = add_relationship_request(&mut user_b, &mut user_a).await;
-        assert_eq!(
-            code,
-            Status::Created.code,
-            "Relationship add code should be 201"
-        );
-        assert_eq!(
-            msg.unwrap(),
-            "Relationship from user_relationship_4_b to user_relationship_4_a activated!",
-            "Relationship should be activated"
-        );
+        let mut user_a = GrapevineAccount::new(String::from("User_degree_filter_A"));
+        let mut user_b = GrapevineAccount::new(String::from("User_degree_filter_B"));
+        let mut user_c = GrapevineAccount::new(String::from("User_degree_filter_C"));
+        for user in [&user_a, &user_b, &user_c] {
+            let request = user.create_user_request();
+            create_user_request(&context, &request).await;
+        }
 
-        // Check no pending relationships
-        let pending_relationships = get_relationships_request(&context, &mut user_b, false)
+        // A <- B <- C
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
+        add_relationship_request(&mut user_b, &mut user_c).await;
+        add_relationship_request(&mut user_c, &mut user_b).await;
+
+        // B proves degree 2 on A's phrase
+        let phrase = String::from("Filter the degrees of separation");
+        let description = String::from("Phrase by A");
+        _ = phrase_request(&phrase, description, &mut user_a).await;
+        let proofs = get_available_degrees_request(&mut user_b).await.unwrap();
+        create_degree_proof_request(&proofs[0], &mut user_b).await;
+
+        // C proves degree 3 on A's phrase and degree 2 on B's phrase
+        let phrase = String::from("Second phrase for the degree filter");
+        let description = String::from("Phrase by B");
+        _ = phrase_request(&phrase, description, &mut user_b).await;
+        let proofs = get_available_degrees_request(&mut user_c).await.unwrap();
+        assert_eq!(proofs.len(), 2);
+        for proof in proofs.iter() {
+            create_degree_proof_request(proof, &mut user_c).await;
+        }
+
+        let degrees = get_filtered_degrees_request(&mut user_c, "").await.unwrap();
+        assert_eq!(degrees.len(), 2);
+
+        let degrees = get_filtered_degrees_request(&mut user_c, "degree=2")
             .await
             .unwrap();
-        assert_eq!(
-            pending_relationships.len(),
-            0,
-            "User A should have no pending relationships"
-        );
+        assert_eq!(degrees.len(), 1);
+        assert!(degrees.iter().all(|degree| degree.degree == Some(2)));
 
-        // Check a and b have pending requests
-        let active_relationships = get_relationships_request(&context, &mut user_a, true)
+        let degrees = get_filtered_degrees_request(&mut user_c, "degree=3")
             .await
             .unwrap();
-        assert_eq!(
-            active_relationships.len(),
-            1,
-            "User A should have one active relationship"
-        );
-        assert_eq!(
-            active_relationships.get(0).unwrap(),
-            user_b.username(),
-            "User B should be active relationship"
-        );
-        let active_relationships = get_relationships_request(&context, &mut user_b, true)
+        assert_eq!(degrees.len(), 1);
+        assert_eq!(degrees[0].degree, Some(3));
+
+        let degrees = get_filtered_degrees_request(&mut user_c, "max_degree=2")
             .await
             .unwrap();
-        assert_eq!(
-            active_relationships.len(),
-            1,
-            "User B should have one active relationship"
-        );
-        assert_eq!(
-            active_relationships.get(0).unwrap(),
-            user_a.username(),
-            "User A should be active relationship"
-        );
+        assert_eq!(degrees.len(), 1);
+        assert_eq!(degrees[0].degree, Some(2));
     }
 
     #[rocket::async_test]
-    async fn test_only_prove_with_active_relationship() {
+    async fn test_recompute_stats() {
+        let context = GrapevineTestContext::init().await;
+
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
+        let mut user_a = GrapevineAccount::new(String::from("user_recompute_stats_1"));
+        let mut user_b = GrapevineAccount::new(String::from("user_recompute_stats_2"));
+        let mut user_c = GrapevineAccount::new(String::from("user_recompute_stats_3"));
+        for user in [&user_a, &user_b, &user_c] {
+            let request = user.create_user_request();
+            create_user_request(&context, &request).await;
+        }
+
+        let phrase = String::from("Counting connections is expensive");
+        let description = String::from("Cache the stats");
+        _ = phrase_request(&phrase, description, &mut user_a).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
+        add_relationship_request(&mut user_a, &mut user_b).await;
+
+        // recomputed stats match the live aggregation
+        let live = get_account_details_request(&mut user_a).await.unwrap();
+        let recomputed = recompute_stats_request(user_a.username()).await.unwrap();
+        assert_eq!(recomputed.phrase_count, live.phrase_count);
+        assert_eq!(recomputed.first_degree_count, live.first_degree_count);
+        assert_eq!(recomputed.second_degree_count, live.second_degree_count);
+
+        // the details route serves the cached stats until they are recomputed
+        add_relationship_request(&mut user_c, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_c).await;
+        let details = get_account_details_request(&mut user_a).await.unwrap();
+        assert_eq!(details.second_degree_count, 0);
+        let recomputed = recompute_stats_request(user_a.username()).await.unwrap();
+        assert_eq!(recomputed.second_degree_count, 1);
+        let details = get_account_details_request(&mut user_a).await.unwrap();
+        assert_eq!(details.phrase_count, 1);
+        assert_eq!(details.first_degree_count, 1);
+        assert_eq!(details.second_degree_count, 1);
+
+        // unknown users cannot be recomputed
+        let res = context
+            .client
+            .post("/user/admin/recompute-stats/user_recompute_stats_missing")
+            .header(Header::new("X-Admin-Token", admin_token()))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn test_degree_proof_preceding_must_be_relationship() {
         let context = GrapevineTestContext::init().await;
 
-        let mut user_a = GrapevineAccount::new(String::from("user_a"));
-        let mut user_b = GrapevineAccount::new(String::from("user_b"));
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
 
-        // Create user
-        let user_a_request = user_a.create_user_request();
-        let user_b_request = user_b.create_user_request();
-        create_user_request(&context, &user_a_request).await;
-        create_user_request(&context, &user_b_request).await;
+        let mut user_a = GrapevineAccount::new(String::from("user_preceding_relation_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_preceding_relation_b"));
+        let mut user_c = GrapevineAccount::new(String::from("user_preceding_relation_c"));
+        let mut user_d = GrapevineAccount::new(String::from("user_preceding_relation_d"));
+        for user in [&user_a, &user_b, &user_c, &user_d] {
+            let request = user.create_user_request();
+            create_user_request(&context, &request).await;
+        }
 
-        // Add relationship from a to b
-        _ = add_relationship_request(&mut user_a, &mut user_b).await;
+        // A and C both know the phrase, but B only has a relationship with A
+        let phrase = String::from("Only build on proofs from your relationships");
+        _ = phrase_request(&phrase, String::from("Preceding check"), &mut user_a).await;
+        _ = phrase_request(&phrase, String::from("Preceding check"), &mut user_c).await;
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
+        // D only exists to find the OID of C's proof
+        add_relationship_request(&mut user_c, &mut user_d).await;
+        add_relationship_request(&mut user_d, &mut user_c).await;
+        let proof_a = get_available_degrees_request(&mut user_b).await.unwrap();
+        let proof_c = get_available_degrees_request(&mut user_d).await.unwrap();
+
+        // a valid proof built from A's proof cannot claim C's proof as its preceding proof
+        let mut request = build_degree_proof_request(&proof_a[0], &mut user_b).await;
+        request.previous = proof_c[0].clone();
+        let serialized = bincode::serialize(&request).unwrap();
+        let signature = generate_request_signature(&user_b, "POST", "/proof/degree");
+        let res = context
+            .client
+            .post("/proof/degree")
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", user_b.username().clone()))
+            .body(serialized)
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Forbidden);
+        let msg = res.into_string().await.unwrap();
+        assert!(msg.contains("PrecedingNotRelationship"));
+        user_b.increment_nonce(None);
 
-        // Create phrase a phrase as User A
-        let phrase = String::from("The sheep waited patiently in the field");
-        let description = String::from("Sheep have no patience");
-        _ = phrase_request(&phrase, description, &mut user_a).await;
+        // the same proof is accepted with its real preceding proof
+        let (code, _) = create_degree_proof_request(&proof_a[0], &mut user_b).await;
+        assert_eq!(code, Status::Created.code);
+    }
 
-        // Get available proofs as b and check 0 returned
-        let proofs = get_available_degrees_request(&mut user_b).await.unwrap();
-        assert_eq!(proofs.len(), 0, "No proofs should be available");
+    #[rocket::async_test]
+    async fn test_artifact_conditional_get() {
+        let context = GrapevineTestContext::init().await;
 
-        // Add relationship from b to a
-        _ = add_relationship_request(&mut user_b, &mut user_a).await;
+        let res = context
+            .client
+            .get("/static/grapevine.wasm")
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
+        let etag = String::from(res.headers().get_one("ETag").unwrap());
+        let last_modified = String::from(res.headers().get_one("Last-Modified").unwrap());
+        let wasm = res.into_bytes().await.unwrap();
+        assert_eq!(wasm, std::fs::read("static/grapevine.wasm").unwrap());
+
+        // a matching ETag is not modified
+        let res = context
+            .client
+            .get("/static/grapevine.wasm")
+            .header(Header::new("If-None-Match", etag.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::NotModified);
+        assert_eq!(res.headers().get_one("ETag"), Some(etag.as_str()));
+        assert!(res.into_bytes().await.unwrap_or_default().is_empty());
+
+        // a stale ETag gets the file again, even if the date would match
+        let res = context
+            .client
+            .get("/static/grapevine.wasm")
+            .header(Header::new("If-None-Match", "\"stale\""))
+            .header(Header::new("If-Modified-Since", last_modified.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
+
+        // the Last-Modified date is also a validator
+        let res = context
+            .client
+            .get("/static/grapevine.wasm")
+            .header(Header::new("If-Modified-Since", last_modified))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::NotModified);
 
-        // Get available proofs as b and check can prove
-        let proofs = get_available_degrees_request(&mut user_b).await.unwrap();
-        assert_eq!(proofs.len(), 1, "Proof should be available");
-        let (code, _) = create_degree_proof_request(&proofs[0], &mut user_b).await;
-        assert_eq!(code, Status::Created.code, "Proof should be created");
+        // missing artifacts are not found
+        let res = context.client.get("/static/missing.r1cs").dispatch().await;
+        assert_eq!(res.status(), Status::NotFound);
     }
 
     #[rocket::async_test]
-    async fn test_reject_relationship() {
-        // Reset db with clean state
-        GrapevineDB::drop("grapevine_mocked").await;
+    async fn test_artifact_range_request() {
         let context = GrapevineTestContext::init().await;
+        let r1cs = std::fs::read("static/grapevine.r1cs").unwrap();
+        let range_request = |range: &str| {
+            context
+                .client
+                .get("/static/grapevine.r1cs")
+                .header(Header::new("Range", String::from(range)))
+        };
 
-        // create users
-        let mut user_a = GrapevineAccount::new(String::from("user_a"));
-        let mut user_b = GrapevineAccount::new(String::from("user_b"));
-        _ = create_user_request(&context, &user_a.create_user_request()).await;
-        _ = create_user_request(&context, &user_b.create_user_request()).await;
-
-        // send pending relationship request from a to b
-        _ = add_relationship_request(&mut user_a, &mut user_b).await;
-        // retrieve pending relationships as b
-        let pending_relationships = get_relationships_request(&context, &mut user_b, false)
-            .await
-            .unwrap();
-        assert_eq!(
-            pending_relationships.len(),
-            1,
-            "User B should have one pending relationship"
-        );
-        assert_eq!(
-            pending_relationships.get(0).unwrap(),
-            user_a.username(),
-            "User A should be pending relationship"
-        );
-        // reject relationship from b to a
-        let (code, _) = reject_relationship_request(&context, &mut user_b, user_a.username()).await;
-        assert_eq!(code, Status::Ok.code, "Relationship should be rejected");
-        // show request was removed from pending relationship
-        let pending_relationships = get_relationships_request(&context, &mut user_b, false)
-            .await
-            .unwrap();
+        // a bounded range returns exactly those bytes
+        let res = range_request("bytes=100-199").dispatch().await;
+        assert_eq!(res.status(), Status::PartialContent);
+        let content_range = format!("bytes 100-199/{}", r1cs.len());
         assert_eq!(
-            pending_relationships.len(),
-            0,
-            "User B should have one pending relationship"
+            res.headers().get_one("Content-Range"),
+            Some(content_range.as_str())
         );
+        assert_eq!(res.into_bytes().await.unwrap(), r1cs[100..200]);
+
+        // an open range resumes a download from an offset
+        let offset = r1cs.len() - 1000;
+        let res = range_request(&format!("bytes={}-", offset))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::PartialContent);
+        assert_eq!(res.into_bytes().await.unwrap(), r1cs[offset..]);
+
+        // a suffix range returns the end of the file
+        let res = range_request("bytes=-10").dispatch().await;
+        assert_eq!(res.status(), Status::PartialContent);
+        assert_eq!(res.into_bytes().await.unwrap(), r1cs[r1cs.len() - 10..]);
+
+        // a range past the end of the file cannot be satisfied
+        let res = range_request(&format!("bytes={}-", r1cs.len()))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::RangeNotSatisfiable);
+
+        // a range conditioned on a stale ETag gets the whole file
+        let res = range_request("bytes=100-199")
+            .header(Header::new("If-Range", "\"stale\""))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
+        assert_eq!(res.headers().get_one("Accept-Ranges"), Some("bytes"));
+        assert_eq!(res.into_bytes().await.unwrap(), r1cs);
     }
 
     #[rocket::async_test]
-    async fn test_duplicate_pending_relationship() {
+    async fn test_submit_saved_phrase_proof() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
         let context = GrapevineTestContext::init().await;
 
-        let mut user_a = GrapevineAccount::new(String::from("user_relationship_5_a"));
-        let mut user_b = GrapevineAccount::new(String::from("user_relationship_5_b"));
-
-        // Create user
-        let user_a_request = user_a.create_user_request();
-        let user_b_request = user_b.create_user_request();
-        create_user_request(&context, &user_a_request).await;
-        create_user_request(&context, &user_b_request).await;
-
-        add_relationship_request(&mut user_a, &mut user_b).await;
+        let mut user = GrapevineAccount::new(String::from("user_saved_proof"));
+        create_user_request(&context, &user.create_user_request()).await;
 
-        let (code, msg_res) = add_relationship_request(&mut user_a, &mut user_b).await;
-        assert_eq!(
-            code,
-            Status::Conflict.code,
-            "Relationship should be a conflict"
-        );
-        let msg = msg_res.unwrap();
-        let condition = msg.contains("PendingRelationshipExists")
-            && msg.contains("user_relationship_5_a")
-            && msg.contains("user_relationship_5_b");
-        assert!(condition, "Duplicate pending relationships cannot exist.");
+        // prove a phrase and save the request instead of submitting it
+        let phrase = String::from("Proven offline and submitted later");
+        let body = build_phrase_request(&phrase, String::from("Saved"), vec![], &user);
+        let path = std::env::temp_dir().join("grapevine_saved_phrase.proof");
+        std::fs::write(&path, bincode::serialize(&body).unwrap()).unwrap();
+
+        // the saved file is submitted without proving again
+        let saved = bincode::deserialize::<PhraseRequest>(&std::fs::read(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(user.decrypt_phrase(&saved.ciphertext).unwrap(), phrase);
+        let (code, msg) = submit_phrase_request(&saved, &mut user).await;
+        assert_eq!(code, Status::Created.code);
+        let created = serde_json::from_str::<PhraseCreationResponse>(&msg).unwrap();
+        assert!(created.new_phrase);
+
+        // submitting the same file again does not create a second proof
+        let (code, msg) = submit_phrase_request(&saved, &mut user).await;
+        assert_eq!(code, Status::Conflict.code);
+        assert!(msg.contains("DegreeProofExists"));
     }
 
     #[rocket::async_test]
-    async fn test_duplicate_active_relationship() {
+    async fn test_get_mutual_relationships() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
         let context = GrapevineTestContext::init().await;
 
-        let mut user_a = GrapevineAccount::new(String::from("user_relationship_5_a"));
-        let mut user_b = GrapevineAccount::new(String::from("user_relationship_5_b"));
+        let mut user_a = GrapevineAccount::new(String::from("user_mutual_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_mutual_b"));
+        let mut user_c = GrapevineAccount::new(String::from("user_mutual_c"));
+        let mut user_d = GrapevineAccount::new(String::from("user_mutual_d"));
+        let mut user_e = GrapevineAccount::new(String::from("user_mutual_e"));
+        for user in [&user_a, &user_b, &user_c, &user_d, &user_e] {
+            create_user_request(&context, &user.create_user_request()).await;
+        }
 
-        // Create user
-        let user_a_request = user_a.create_user_request();
-        let user_b_request = user_b.create_user_request();
-        create_user_request(&context, &user_a_request).await;
-        create_user_request(&context, &user_b_request).await;
+        // c is connected to both a and b
+        add_relationship_request(&mut user_a, &mut user_c).await;
+        add_relationship_request(&mut user_c, &mut user_a).await;
+        add_relationship_request(&mut user_b, &mut user_c).await;
+        add_relationship_request(&mut user_c, &mut user_b).await;
+        // d is only connected to a
+        add_relationship_request(&mut user_a, &mut user_d).await;
+        add_relationship_request(&mut user_d, &mut user_a).await;
+        // e has only sent a pending request to a and is connected to b
+        add_relationship_request(&mut user_e, &mut user_a).await;
+        add_relationship_request(&mut user_b, &mut user_e).await;
+        add_relationship_request(&mut user_e, &mut user_b).await;
 
-        add_relationship_request(&mut user_a, &mut user_b).await;
-        add_relationship_request(&mut user_b, &mut user_a).await;
+        let (status, mutual) =
+            get_mutual_relationships_request(&context, &mut user_a, user_b.username()).await;
+        assert_eq!(status, Status::Ok);
+        assert_eq!(mutual.unwrap(), vec![user_c.username().clone()]);
 
-        let (code, msg_res) = add_relationship_request(&mut user_a, &mut user_b).await;
-        assert_eq!(
-            code,
-            Status::Conflict.code,
-            "Relationship should be a conflict"
-        );
-        let msg = msg_res.unwrap();
-        let condition = msg.contains("ActiveRelationshipExists")
-            && msg.contains("user_relationship_5_a")
-            && msg.contains("user_relationship_5_b");
-        assert!(condition, "Duplicate active relationships cannot exist.");
+        // users without any shared connection have no mutual relationships
+        let (status, mutual) =
+            get_mutual_relationships_request(&context, &mut user_d, user_b.username()).await;
+        assert_eq!(status, Status::Ok);
+        assert!(mutual.unwrap().is_empty());
+
+        let (status, _) =
+            get_mutual_relationships_request(&context, &mut user_a, &String::from("nobody")).await;
+        assert_eq!(status, Status::NotFound);
     }
 
     #[rocket::async_test]
-    async fn test_duplicate_phrase() {
-        // initialize context
+    async fn test_connections_only_phrase_visibility() {
+        // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
+
         let context = GrapevineTestContext::init().await;
 
-        // add user
-        let mut user = GrapevineAccount::new(String::from("user"));
-        create_user_request(&context, &user.create_user_request()).await;
+        let mut user_a = GrapevineAccount::new(String::from("user_visibility_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_visibility_b"));
+        let mut user_c = GrapevineAccount::new(String::from("user_visibility_c"));
+        create_user_request(&context, &user_a.create_user_request()).await;
+        create_user_request(&context, &user_b.create_user_request()).await;
+        create_user_request(&context, &user_c.create_user_request()).await;
 
-        // create & prove phrase
-        let phrase = String::from("This is a phrase");
-        let description = String::from("This is a description");
-        _ = phrase_request(&phrase, description.clone(), &mut user).await;
+        // b is connected to a, c is not connected to anyone
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
 
-        // attempt to create & prove a duplicate phrase
-        let (code, msg) = phrase_request(&phrase, description, &mut user).await;
-        assert!(
-            msg.contains("DegreeProofExists"),
-            "Duplicate phrase should be prevented from being added",
-        );
-        assert!(
-            code == Status::Conflict.code,
-            "Duplicate phrase should return a 409 status code",
-        )
+        let phrase = String::from("Only for the inner circle");
+        let (code, msg) = phrase_request(&phrase, String::from("Hushed"), &mut user_a).await;
+        assert_eq!(code, Status::Created.code);
+        let index = serde_json::from_str::<PhraseCreationResponse>(&msg)
+            .unwrap()
+            .phrase_index;
+
+        // phrases are public by default
+        let (code, data) = get_phrase_request(&mut user_c, index).await;
+        assert_eq!(code, Status::Ok.code);
+        assert_eq!(data.unwrap().description, "Hushed");
+
+        // only the origin can restrict a phrase, and never below 1 degree
+        let connections = PhraseVisibility::Connections(1);
+        let code = update_phrase_visibility_request(&mut user_b, index, connections).await;
+        assert_eq!(code, Status::Forbidden.code);
+        let code =
+            update_phrase_visibility_request(&mut user_a, index, PhraseVisibility::Connections(0))
+                .await;
+        assert_eq!(code, Status::BadRequest.code);
+        let code = update_phrase_visibility_request(&mut user_a, index, connections).await;
+        assert_eq!(code, Status::Ok.code);
+
+        // a non-connected user cannot retrieve the description
+        let (code, data) = get_phrase_request(&mut user_c, index).await;
+        assert_eq!(code, Status::NotFound.code);
+        assert!(data.is_none());
+        // b is 2 degrees from the phrase, so it is hidden from b too
+        let (code, _) = get_phrase_request(&mut user_b, index).await;
+        assert_eq!(code, Status::NotFound.code);
+        let results = search_phrases_request(&mut user_b, "hushed").await.unwrap();
+        assert!(results.is_empty());
+        // the connection counts are hidden as well
+        assert!(get_phrase_connection_request(&mut user_c, index)
+            .await
+            .is_none());
+        assert!(get_phrase_connection_request(&mut user_b, index)
+            .await
+            .is_none());
+        // the origin still sees it
+        let (code, data) = get_phrase_request(&mut user_a, index).await;
+        assert_eq!(code, Status::Ok.code);
+        assert_eq!(data.unwrap().description, "Hushed");
+        assert!(get_phrase_connection_request(&mut user_a, index)
+            .await
+            .is_some());
+
+        // widening the visibility reveals it to b but not to c
+        let connections = PhraseVisibility::Connections(2);
+        let code = update_phrase_visibility_request(&mut user_a, index, connections).await;
+        assert_eq!(code, Status::Ok.code);
+        let (code, data) = get_phrase_request(&mut user_b, index).await;
+        assert_eq!(code, Status::Ok.code);
+        assert_eq!(data.unwrap().description, "Hushed");
+        let results = search_phrases_request(&mut user_b, "hushed").await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].phrase_index, index);
+        let (code, _) = get_phrase_request(&mut user_c, index).await;
+        assert_eq!(code, Status::NotFound.code);
     }
 
     #[rocket::async_test]
-    async fn test_multiple_degree_1() {
-        // initialize context
+    async fn test_inbound_and_outbound_pending_relationships() {
+        // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
-        let context = GrapevineTestContext::init().await;
 
-        // add users
-        let mut user1 = GrapevineAccount::new(String::from("user1"));
-        let mut user2 = GrapevineAccount::new(String::from("user2"));
-        create_user_request(&context, &user1.create_user_request()).await;
-        create_user_request(&context, &user2.create_user_request()).await;
+        let context = GrapevineTestContext::init().await;
 
-        // create phrase
-        let phrase = String::from("This is a phrase");
-        let description = String::from("This is a description");
-        let (_, res) = phrase_request(&phrase, description.clone(), &mut user1).await;
-        let data: PhraseCreationResponse = serde_json::from_str(&res).unwrap();
+        let mut user_a = GrapevineAccount::new(String::from("user_outbound_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_outbound_b"));
+        let mut user_c = GrapevineAccount::new(String::from("user_outbound_c"));
+        let mut user_d = GrapevineAccount::new(String::from("user_outbound_d"));
+        for user in [&user_a, &user_b, &user_c, &user_d] {
+            create_user_request(&context, &user.create_user_request()).await;
+        }
 
-        // check that new phrase was created at index 1
-        assert_eq!(data.phrase_index, 1);
-        assert_eq!(data.new_phrase, true);
+        // a sends requests to b and c, and receives one from d
+        add_relationship_request(&mut user_a, &mut user_b).await;
+        add_relationship_request(&mut user_a, &mut user_c).await;
+        add_relationship_request(&mut user_d, &mut user_a).await;
 
-        // create phrase
-        let phrase = String::from("This is a phrase");
-        let description = String::from("This is a different description");
-        let (_, res) = phrase_request(&phrase, description.clone(), &mut user2).await;
-        let data: PhraseCreationResponse = serde_json::from_str(&res).unwrap();
+        let inbound = get_relationships_request(&context, &mut user_a, false)
+            .await
+            .unwrap();
+        assert_eq!(inbound, vec![user_d.username().clone()]);
+        let outbound = get_outbound_relationships_request(&context, &mut user_a)
+            .await
+            .unwrap();
+        assert_eq!(
+            outbound,
+            vec![user_b.username().clone(), user_c.username().clone()]
+        );
 
-        // check that existing phrase was proven at index 1
-        assert_eq!(data.phrase_index, 1);
-        assert_eq!(data.new_phrase, false);
+        // once b reciprocates, only c is still pending on the other side
+        add_relationship_request(&mut user_b, &mut user_a).await;
+        let outbound = get_outbound_relationships_request(&context, &mut user_a)
+            .await
+            .unwrap();
+        assert_eq!(outbound, vec![user_c.username().clone()]);
+        let outbound = get_outbound_relationships_request(&context, &mut user_b)
+            .await
+            .unwrap();
+        assert!(outbound.is_empty());
     }
 
     #[rocket::async_test]
-    async fn test_create_degree_proof_with_invalid_request_body() {
+    async fn test_self_relationship_rejected_by_route_and_db() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
         let context = GrapevineTestContext::init().await;
 
-        let user = GrapevineAccount::new(String::from("user_degree_proof_1"));
-        let request = user.create_user_request();
-
-        create_user_request(&context, &request).await;
+        let mut user = GrapevineAccount::new(String::from("user_self_relationship"));
+        let mut clone = user.clone();
+        create_user_request(&context, &user.create_user_request()).await;
 
-        let signature = user.sign_nonce();
-        let encoded = hex::encode(signature.compress());
+        // the route rejects the request before touching the db
+        let (code, msg) = add_relationship_request(&mut user, &mut clone).await;
+        assert_eq!(code, Status::BadRequest.code);
+        assert!(msg.unwrap().contains("RelationshipSenderIsTarget"));
 
-        let msg = context
-            .client
-            .post("/proof/degree")
-            .header(Header::new("X-Authorization", encoded))
-            .header(Header::new("X-Username", user.username().clone()))
-            .body(vec![])
-            .dispatch()
+        // the db refuses to store a relationship from a user to themselves
+        let id = get_user_request(&context, user.username().clone())
             .await
-            .into_string()
+            .unwrap()
+            .id;
+        let encrypted_auth_secret = user.encrypt_auth_secret(user.pubkey());
+        let relationship = Relationship {
+            id: None,
+            sender: id,
+            recipient: id,
+            ephemeral_key: Some(encrypted_auth_secret.ephemeral_key),
+            ciphertext: Some(encrypted_auth_secret.ciphertext),
+            active: Some(false),
+        };
+        let db = context.client.rocket().state::<GrapevineDB>().unwrap();
+        let res = db.add_pending_relationship(&relationship).await;
+        assert!(matches!(
+            res,
+            Err(GrapevineError::RelationshipSenderIsTarget)
+        ));
+
+        let pending = get_relationships_request(&context, &mut user, false)
             .await
             .unwrap();
-
-        let condition = msg.contains("SerdeError") && msg.contains("DegreeProofRequest");
-        assert!(
-            condition,
-            "Degree proof continuation should fail with invalid body"
-        )
+        assert!(pending.is_empty());
     }
 
     #[rocket::async_test]
-    async fn test_successful_degree_proof_creation() {
+    async fn test_get_phrase_full_matches_separate_requests() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
         let context = GrapevineTestContext::init().await;
 
-        let mut user_a = GrapevineAccount::new(String::from("user_degree_proof_2_a"));
-        let mut user_b = GrapevineAccount::new(String::from("user_degree_proof_2_b"));
-
-        // Create users
-        let user_a_request = user_a.create_user_request();
-        let user_b_request = user_b.create_user_request();
-        create_user_request(&context, &user_a_request).await;
-        create_user_request(&context, &user_b_request).await;
+        let mut user_a = GrapevineAccount::new(String::from("user_phrase_full_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_phrase_full_b"));
+        let mut user_c = GrapevineAccount::new(String::from("user_phrase_full_c"));
+        let mut user_d = GrapevineAccount::new(String::from("user_phrase_full_d"));
+        for user in [&user_a, &user_b, &user_c, &user_d] {
+            create_user_request(&context, &user.create_user_request()).await;
+        }
 
+        // b and c are connected to a, d is not connected to anyone
         add_relationship_request(&mut user_a, &mut user_b).await;
         add_relationship_request(&mut user_b, &mut user_a).await;
+        add_relationship_request(&mut user_a, &mut user_c).await;
+        add_relationship_request(&mut user_c, &mut user_a).await;
 
-        // Create phrase a phrase as User A
-        let phrase = String::from("The first phrase to end them all");
-        let description = String::from("And on the first day, user_a made a phrase");
-        _ = phrase_request(&phrase, description, &mut user_a).await;
-
-        // prove 2nd degree separation as user b
+        // a knows the phrase, b and c prove 2nd degree through a
+        let phrase = String::from("Everything in one round trip");
+        let (_, msg) = phrase_request(&phrase, String::from("Full"), &mut user_a).await;
+        let index = serde_json::from_str::<PhraseCreationResponse>(&msg)
+            .unwrap()
+            .phrase_index;
         let proofs = get_available_degrees_request(&mut user_b).await.unwrap();
-        let (code, _) = create_degree_proof_request(&proofs[0], &mut user_b).await;
-        assert_eq!(
-            code,
-            Status::Created.code,
-            "Degree proof should have been created"
-        );
+        create_degree_proof_request(&proofs[0], &mut user_b).await;
+        let proofs = get_available_degrees_request(&mut user_c).await.unwrap();
+        create_degree_proof_request(&proofs[0], &mut user_c).await;
+
+        // b and c connect after proving
+        add_relationship_request(&mut user_b, &mut user_c).await;
+        add_relationship_request(&mut user_c, &mut user_b).await;
+
+        for user in [&mut user_a, &mut user_c, &mut user_d] {
+            let full = get_phrase_full_request(user, index).await.unwrap();
+            let (_, phrase) = get_phrase_request(user, index).await;
+            let (total, by_degree) = get_phrase_connection_request(user, index).await.unwrap();
+            assert_eq!(
+                serde_json::to_value(&full.phrase).unwrap(),
+                serde_json::to_value(&phrase.unwrap()).unwrap()
+            );
+            assert_eq!(full.total_connections, total);
+            assert_eq!(full.connections_by_degree, by_degree);
+        }
+
+        // c's relationships are a at degree 1 and b at degree 2
+        let full = get_phrase_full_request(&mut user_c, index).await.unwrap();
+        assert_eq!(full.phrase.degree, Some(2));
+        assert_eq!(full.total_connections, 2);
+        assert_eq!(full.connections_by_degree, vec![1, 1]);
+        // d has no connections but can still see the public phrase
+        let full = get_phrase_full_request(&mut user_d, index).await.unwrap();
+        assert_eq!(full.phrase.description, "Full");
+        assert_eq!(full.phrase.degree, None);
+        assert_eq!(full.total_connections, 0);
+        assert!(full.connections_by_degree.is_empty());
     }
 
     #[rocket::async_test]
-    async fn test_duplicate_degree_proof() {
+    async fn test_read_only_requests_do_not_consume_nonce() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
         let context = GrapevineTestContext::init().await;
 
-        let mut user_a = GrapevineAccount::new(String::from("user_degree_proof_3_a"));
-        let mut user_b = GrapevineAccount::new(String::from("user_degree_proof_3_b"));
+        let mut user = GrapevineAccount::new(String::from("user_read_only_nonce"));
+        create_user_request(&context, &user.create_user_request()).await;
+        let nonce = user.nonce();
+
+        // two sequential reads are both signed with the same nonce
+        let signature = generate_request_signature(&user, "GET", "/user/details");
+        for _ in 0..2 {
+            let res = context
+                .client
+                .get("/user/details")
+                .header(Header::new("X-Authorization", signature.clone()))
+                .header(Header::new("X-Username", user.username().clone()))
+                .dispatch()
+                .await;
+            assert_eq!(res.status(), Status::Ok);
+        }
+        let db = context.client.rocket().state::<GrapevineDB>().unwrap();
+        assert_eq!(db.get_nonce(user.username()).await.unwrap().0, nonce);
 
-        // Create users
-        let user_a_request = user_a.create_user_request();
-        let user_b_request = user_b.create_user_request();
-        create_user_request(&context, &user_a_request).await;
-        create_user_request(&context, &user_b_request).await;
+        // a state-changing request still consumes the nonce
+        let (code, _) = set_delegated_proving_request(&mut user, true).await;
+        assert_eq!(code, Status::Ok.code);
+        assert_eq!(db.get_nonce(user.username()).await.unwrap().0, nonce + 1);
 
-        add_relationship_request(&mut user_a, &mut user_b).await;
-        add_relationship_request(&mut user_b, &mut user_a).await;
+        // so a read signed with the old nonce is no longer accepted
+        let res = context
+            .client
+            .get("/user/details")
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", user.username().clone()))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Unauthorized);
+        assert!(get_account_details_request(&mut user).await.is_some());
+    }
 
-        // Create phrase a phrase as User A
-        let phrase = String::from("The first phrase to end them all");
-        let description = String::from("And on the first day, user_a made a phrase");
-        _ = phrase_request(&phrase, description, &mut user_a).await;
+    #[rocket::async_test]
+    async fn test_read_signature_bound_to_query() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
 
-        // get proofs as user b
-        let proofs = get_available_degrees_request(&mut user_b).await.unwrap();
+        let context = GrapevineTestContext::init().await;
 
-        // prove degree 2 separation of phrase as user b
-        create_degree_proof_request(&proofs[0], &mut user_b).await;
+        let user = GrapevineAccount::new(String::from("user_read_query"));
+        create_user_request(&context, &user.create_user_request()).await;
 
-        // attempt to prove degree 2 separation of phrase as user b again
-        let (_, msg) = create_degree_proof_request(&proofs[0], &mut user_b).await;
-        assert!(
-            msg.unwrap().contains("DegreeProofExists"),
-            "Cannot create a second degree proof between same accounts for same phrase"
+        let signature = generate_request_signature(&user, "GET", "/user/degrees?degree=2");
+        let read = |uri: &'static str| {
+            context
+                .client
+                .get(uri)
+                .header(Header::new("X-Authorization", signature.clone()))
+                .header(Header::new("X-Username", user.username().clone()))
+                .dispatch()
+        };
+
+        // the signed read can be repeated
+        assert_eq!(read("/user/degrees?degree=2").await.status(), Status::Ok);
+        // but not with another query, or with none at all
+        assert_eq!(
+            read("/user/degrees?degree=3").await.status(),
+            Status::Unauthorized
         );
+        assert_eq!(read("/user/degrees").await.status(), Status::Unauthorized);
     }
 
     #[rocket::async_test]
-    async fn test_get_account_details() {
+    async fn test_relationship_activation_rolls_back_on_failure() {
         // Reset db with clean state
         GrapevineDB::drop("grapevine_mocked").await;
 
-        let context: GrapevineTestContext = GrapevineTestContext::init().await;
+        let context = GrapevineTestContext::init().await;
+        let db = context.client.rocket().state::<GrapevineDB>().unwrap();
+        if !db.supports_transactions().await {
+            println!("skipping rollback test: the test database is not a replica set");
+            return;
+        }
 
-        // Create test users
-        let mut users = vec![
-            GrapevineAccount::new(String::from("user_account_details_1")),
-            GrapevineAccount::new(String::from("user_account_details_2")),
-            GrapevineAccount::new(String::from("user_account_details_3")),
-            GrapevineAccount::new(String::from("user_account_details_4")),
-            GrapevineAccount::new(String::from("user_account_details_5")),
-            GrapevineAccount::new(String::from("user_account_details_6")),
-            GrapevineAccount::new(String::from("user_account_details_7")),
-            GrapevineAccount::new(String::from("user_account_details_8")),
-            GrapevineAccount::new(String::from("user_account_details_9")),
-        ];
+        let mut user_a = GrapevineAccount::new(String::from("user_activation_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_activation_b"));
+        let user_c = GrapevineAccount::new(String::from("user_activation_c"));
+        create_user_request(&context, &user_a.create_user_request()).await;
+        create_user_request(&context, &user_b.create_user_request()).await;
+        create_user_request(&context, &user_c.create_user_request()).await;
+        let a = db.get_user(user_a.username()).await.unwrap().id;
+        let b = db.get_user(user_b.username()).await.unwrap().id;
+        let c = db.get_user(user_c.username()).await.unwrap().id;
+        add_relationship_request(&mut user_a, &mut user_b).await;
 
-        for i in 0..users.len() {
-            let request = users[i].create_user_request();
-            create_user_request(&context, &request).await;
+        // occupy an oid so inserting the reverse relationship fails after the earlier writes
+        let taken = ObjectId::new();
+        let decoy = Relationship {
+            id: Some(taken),
+            sender: c,
+            recipient: a,
+            ephemeral_key: Some([0; 32]),
+            ciphertext: Some([0; 48]),
+            active: Some(false),
+        };
+        db.add_pending_relationship(&decoy).await.unwrap();
+        let activation = Relationship {
+            id: Some(taken),
+            sender: b,
+            recipient: a,
+            ephemeral_key: Some([0; 32]),
+            ciphertext: Some([0; 48]),
+            active: Some(true),
+        };
+        assert!(db.activate_relationship(&activation).await.is_err());
+
+        // none of the writes before the failure were kept
+        let pending = get_relationships_request(&context, &mut user_b, false).await;
+        assert_eq!(pending.unwrap(), vec![user_a.username().clone()]);
+        let active = get_relationships_request(&context, &mut user_a, true).await;
+        assert!(active.unwrap().is_empty());
+        for user in [&user_a, &user_b] {
+            let relationships = db.get_user(user.username()).await.unwrap().relationships;
+            assert!(relationships.unwrap_or_default().is_empty());
         }
 
-        let mut user_a = users.remove(0);
-        let mut user_b = users.remove(0);
-        let mut user_c = users.remove(0);
-        let mut user_d = users.remove(0);
-        let mut user_e = users.remove(0);
-        let mut user_f = users.remove(0);
-        let mut user_g = users.remove(0);
-        let mut user_h = users.remove(0);
-        let mut user_i = users.remove(0);
-
-        let details = get_account_details_request(&mut user_a).await.unwrap();
-        assert_eq!(details.0, 0, "Phrase count should be 0");
-        assert_eq!(details.1, 0, "First degree count should be 0");
-        assert_eq!(details.2, 0, "Second degree count should be 0");
+        // the relationship can still be activated normally
+        add_relationship_request(&mut user_b, &mut user_a).await;
+        let active = get_relationships_request(&context, &mut user_a, true).await;
+        assert_eq!(active.unwrap(), vec![user_b.username().clone()]);
+    }
 
-        // Create phrase a phrase as User A
-        let phrase = String::from("The first phrase to end them all");
-        let description = String::from("And on the first day, user_a made a phrase");
-        _ = phrase_request(&phrase, description, &mut user_a).await;
+    #[rocket::async_test]
+    async fn test_prove_path_to_target() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
 
-        let details = get_account_details_request(&mut user_a).await.unwrap();
-        assert_eq!(details.0, 1, "Phrase count should be 1");
-        assert_eq!(details.1, 0, "First degree count should be 0");
-        assert_eq!(details.2, 0, "Second degree count should be 0");
+        let context = GrapevineTestContext::init().await;
 
-        // Add first degree connection and second degree connection
-        add_relationship_request(&mut user_b, &mut user_a).await;
+        let mut user_a = GrapevineAccount::new(String::from("user_path_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_path_b"));
+        let mut user_c = GrapevineAccount::new(String::from("user_path_c"));
+        let mut user_d = GrapevineAccount::new(String::from("user_path_d"));
+        for user in [&user_a, &user_b, &user_c, &user_d] {
+            create_user_request(&context, &user.create_user_request()).await;
+        }
+        // a - b - c, and b - d
         add_relationship_request(&mut user_a, &mut user_b).await;
-        add_relationship_request(&mut user_c, &mut user_b).await;
+        add_relationship_request(&mut user_b, &mut user_a).await;
         add_relationship_request(&mut user_b, &mut user_c).await;
-        let details = get_account_details_request(&mut user_a).await.unwrap();
-        assert_eq!(details.0, 1, "Phrase count should be 1");
-        assert_eq!(details.1, 1, "First degree count should be 1");
-        assert_eq!(details.2, 1, "Second degree count should be 1");
-
-        // Add more second degree connections
+        add_relationship_request(&mut user_c, &mut user_b).await;
         add_relationship_request(&mut user_d, &mut user_b).await;
         add_relationship_request(&mut user_b, &mut user_d).await;
-        add_relationship_request(&mut user_e, &mut user_b).await;
-        add_relationship_request(&mut user_b, &mut user_e).await;
-        let details = get_account_details_request(&mut user_a).await.unwrap();
-        assert_eq!(details.0, 1, "Phrase count should be 1");
-        assert_eq!(details.1, 1, "First degree count should be 1");
-        assert_eq!(details.2, 3, "Second degree count should be 3");
 
-        // Second degree connections become first degree connections
-        add_relationship_request(&mut user_d, &mut user_a).await;
-        add_relationship_request(&mut user_a, &mut user_d).await;
-        add_relationship_request(&mut user_e, &mut user_a).await;
-        add_relationship_request(&mut user_a, &mut user_e).await;
-        let details = get_account_details_request(&mut user_a).await.unwrap();
-        assert_eq!(details.0, 1, "Phrase count should be 1");
-        assert_eq!(details.1, 3, "First degree count should be 3");
-        assert_eq!(details.2, 1, "Second degree count should be 1");
+        // a and d each create a phrase
+        let phrase = String::from("Paths lead to a");
+        _ = phrase_request(&phrase, String::from("From a"), &mut user_a).await;
+        let phrase = String::from("Paths lead to d");
+        _ = phrase_request(&phrase, String::from("From d"), &mut user_d).await;
+
+        // b can build on both phrases, but only one of them leads to a
+        let available = get_available_degrees_request(&mut user_b).await.unwrap();
+        assert_eq!(available.len(), 2);
+        let (code, to_a) = get_available_degrees_from_request(&mut user_b, user_a.username()).await;
+        assert_eq!(code, Status::Ok.code);
+        let to_a = to_a.unwrap();
+        assert_eq!(to_a.len(), 1);
+        let (code, _) = create_degree_proof_request(&to_a[0], &mut user_b).await;
+        assert_eq!(code, Status::Created.code);
+
+        // c proves a 2 relationship path to a through b
+        let (_, to_a) = get_available_degrees_from_request(&mut user_c, user_a.username()).await;
+        let to_a = to_a.unwrap();
+        assert_eq!(to_a.len(), 1);
+        let (code, _) = create_degree_proof_request(&to_a[0], &mut user_c).await;
+        assert_eq!(code, Status::Created.code);
+        let origins = get_connected_origins_request(&mut user_c).await.unwrap();
+        assert_eq!(origins.len(), 1);
+        assert_eq!(&origins[0].origin_username, user_a.username());
+        assert_eq!(origins[0].min_degree, 3);
+
+        // no proof chain leads from c to d since b never proved d's phrase
+        let (code, to_d) = get_available_degrees_from_request(&mut user_c, user_d.username()).await;
+        assert_eq!(code, Status::Ok.code);
+        assert!(to_d.unwrap().is_empty());
+
+        // the target has to exist
+        let missing = String::from("user_path_missing");
+        let (code, _) = get_available_degrees_from_request(&mut user_c, &missing).await;
+        assert_eq!(code, Status::NotFound.code);
+    }
 
-        // Test where 3 new degree 2 connections added at once
-        add_relationship_request(&mut user_f, &mut user_a).await;
-        add_relationship_request(&mut user_a, &mut user_f).await;
-        add_relationship_request(&mut user_f, &mut user_g).await;
-        add_relationship_request(&mut user_g, &mut user_f).await;
-        add_relationship_request(&mut user_f, &mut user_h).await;
-        add_relationship_request(&mut user_h, &mut user_f).await;
-        add_relationship_request(&mut user_f, &mut user_i).await;
-        add_relationship_request(&mut user_i, &mut user_f).await;
-        let details = get_account_details_request(&mut user_a).await.unwrap();
-        assert_eq!(details.0, 1, "Phrase count should be 1");
-        assert_eq!(details.1, 4, "First degree count should be 3");
-        assert_eq!(details.2, 4, "Second degree count should be 1");
+    /** Collects everything a test subscriber writes */
+    #[derive(Clone, Default)]
+    struct LogBuffer(std::sync::Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
     }
 
-    #[rocket::async_test]
-    async fn test_get_phrase_connections() {
-        // Reset db with clean state
-        GrapevineDB::drop("grapevine_mocked").await;
+    #[test]
+    fn test_json_log_format() {
+        assert_eq!(LogFormat::parse("JSON"), Some(LogFormat::Json));
+        assert_eq!(LogFormat::parse(" pretty "), Some(LogFormat::Pretty));
+        assert_eq!(LogFormat::parse("xml"), None);
+
+        // log one event in a request span through a subscriber of each format
+        let log = |format: LogFormat| {
+            let buffer = LogBuffer::default();
+            let writer = buffer.clone();
+            let subscriber =
+                logging::build_subscriber(format, EnvFilter::new("info"), move || writer.clone());
+            tracing::subscriber::with_default(subscriber, || {
+                let span = tracing::info_span!("request", request_id = "req-1");
+                let _entered = span.enter();
+                tracing::info!(phrase_index = 3, "phrase created");
+                tracing::debug!("filtered out");
+            });
+            let bytes = buffer.0.lock().unwrap().clone();
+            String::from_utf8(bytes).unwrap()
+        };
 
+        // json writes one object per event, carrying the request id of its span
+        let output = log(LogFormat::Json);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let event: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(event["level"], "INFO");
+        assert_eq!(event["message"], "phrase created");
+        assert_eq!(event["phrase_index"], 3);
+        assert_eq!(event["span"]["request_id"], "req-1");
+
+        // pretty writes a line that is not json
+        let output = log(LogFormat::Pretty);
+        assert!(output.contains("phrase created"));
+        assert!(output.contains("req-1"));
+        assert!(serde_json::from_str::<serde_json::Value>(output.trim()).is_err());
+    }
+
+    #[rocket::async_test]
+    async fn test_request_id_header() {
         let context = GrapevineTestContext::init().await;
 
-        let mut users: Vec<GrapevineAccount> = vec![];
+        // a client supplied id is kept
+        let res = context
+            .client
+            .get("/health")
+            .header(Header::new("X-Request-Id", "trace-42"))
+            .dispatch()
+            .await;
+        assert_eq!(res.headers().get_one("X-Request-Id"), Some("trace-42"));
 
-        for i in 0..7 {
-            let user =
-                GrapevineAccount::new(String::from(format!("user_account_details_{}", i + 1)));
-            let request = user.create_user_request();
-            create_user_request(&context, &request).await;
-            users.push(user);
+        // otherwise (or if it is not a short token) a new id is generated for each request
+        let res = context
+            .client
+            .get("/health")
+            .header(Header::new("X-Request-Id", "not a token"))
+            .dispatch()
+            .await;
+        let first = res.headers().get_one("X-Request-Id").unwrap().to_string();
+        assert!(uuid::Uuid::parse_str(&first).is_ok());
+        let res = context.client.get("/health").dispatch().await;
+        let second = res.headers().get_one("X-Request-Id").unwrap().to_string();
+        assert!(uuid::Uuid::parse_str(&second).is_ok());
+        assert_ne!(first, second);
+    }
+
+    #[rocket::async_test]
+    async fn test_pending_relationship_quota() {
+        let quotas = UserQuotas {
+            pending_relationships: 2,
+            ..UserQuotas::from_env()
+        };
+        let context = GrapevineTestContext::init_with_quotas(quotas).await;
+
+        let mut user_a = GrapevineAccount::new(String::from("quota_relationship_a"));
+        let mut user_b = GrapevineAccount::new(String::from("quota_relationship_b"));
+        let mut user_c = GrapevineAccount::new(String::from("quota_relationship_c"));
+        let mut user_d = GrapevineAccount::new(String::from("quota_relationship_d"));
+        for user in [&user_a, &user_b, &user_c, &user_d] {
+            create_user_request(&context, &user.create_user_request()).await;
         }
 
-        // create phrase 1
-        let phrase = String::from("Where there's smoke there's fire");
-        let description = String::from("5 alarm");
-        let (_, res) = phrase_request(&phrase, description.clone(), &mut users[0]).await;
-        let data: PhraseCreationResponse = serde_json::from_str(&res).unwrap();
+        // relationships can be sent up to the quota
+        let (code, _) = add_relationship_request_to(&context, &mut user_a, &mut user_b).await;
+        assert_eq!(code, Status::Created.code);
+        let (code, _) = add_relationship_request_to(&context, &mut user_a, &mut user_c).await;
+        assert_eq!(code, Status::Created.code);
+        let (code, msg) = add_relationship_request_to(&context, &mut user_a, &mut user_d).await;
+        assert_eq!(code, Status::Forbidden.code);
+        assert!(msg.unwrap().contains("QuotaExceeded"));
+
+        // accepting a pending relationship frees up room in the sender's quota
+        let (code, _) = add_relationship_request_to(&context, &mut user_b, &mut user_a).await;
+        assert_eq!(code, Status::Created.code);
+        let (code, _) = add_relationship_request_to(&context, &mut user_a, &mut user_d).await;
+        assert_eq!(code, Status::Created.code);
+    }
 
-        let connections = get_phrase_connection_request(&mut users[0], data.phrase_index)
+    #[rocket::async_test]
+    async fn test_phrase_quota() {
+        let quotas = UserQuotas {
+            phrases: 1,
+            ..UserQuotas::from_env()
+        };
+        let context = GrapevineTestContext::init_with_quotas(quotas).await;
+
+        let mut user_a = GrapevineAccount::new(String::from("quota_phrase_a"));
+        let mut user_b = GrapevineAccount::new(String::from("quota_phrase_b"));
+        create_user_request(&context, &user_a.create_user_request()).await;
+        create_user_request(&context, &user_b.create_user_request()).await;
+
+        // a user can originate phrases up to the quota
+        let phrase_1 = String::from("The first phrase under the quota");
+        let body = build_phrase_request(&phrase_1, String::from("First"), vec![], &user_a);
+        let (code, _) = submit_phrase_request_to(&context, &body, &mut user_a).await;
+        assert_eq!(code, Status::Created.code);
+        let phrase_2 = String::from("The second phrase over the quota");
+        let body = build_phrase_request(&phrase_2, String::from("Second"), vec![], &user_a);
+        let (code, msg) = submit_phrase_request_to(&context, &body, &mut user_a).await;
+        assert_eq!(code, Status::Forbidden.code);
+        assert!(msg.contains("QuotaExceeded"));
+
+        // proving knowledge of a phrase originated by someone else does not count against it
+        let body = build_phrase_request(&phrase_2, String::from("Second"), vec![], &user_b);
+        let (code, _) = submit_phrase_request_to(&context, &body, &mut user_b).await;
+        assert_eq!(code, Status::Created.code);
+        let body = build_phrase_request(&phrase_2, String::from("Second"), vec![], &user_a);
+        let (code, _) = submit_phrase_request_to(&context, &body, &mut user_a).await;
+        assert_eq!(code, Status::Created.code);
+    }
+
+    #[rocket::async_test]
+    async fn test_repair_orphaned_relationships() {
+        let context = GrapevineTestContext::init().await;
+        let db = context.client.rocket().state::<GrapevineDB>().unwrap();
+
+        let mut user_a = GrapevineAccount::new(String::from("user_orphaned_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_orphaned_b"));
+        create_user_request(&context, &user_a.create_user_request()).await;
+        create_user_request(&context, &user_b.create_user_request()).await;
+        add_relationship_request_to(&context, &mut user_a, &mut user_b).await;
+        add_relationship_request_to(&context, &mut user_b, &mut user_a).await;
+        // each user references the relationship they received
+        let to_a = db.get_user(user_a.username()).await.unwrap().relationships;
+        let to_a = to_a.unwrap()[0];
+        let to_b = db.get_user(user_b.username()).await.unwrap().relationships;
+        let to_b = to_b.unwrap()[0];
+
+        // delete the relationship to a and drop b's reference to the relationship to b
+        let mongo = mongodb::Client::with_uri_str(&*MONGODB_URI).await.unwrap();
+        let database = mongo.database("grapevine_mocked");
+        database
+            .collection::<Relationship>("relationships")
+            .delete_one(doc! { "_id": to_a }, None)
+            .await
+            .unwrap();
+        database
+            .collection::<User>("users")
+            .update_one(
+                doc! { "username": user_b.username() },
+                doc! { "$pull": { "relationships": to_b } },
+                None,
+            )
             .await
             .unwrap();
-        assert_eq!(connections.0, 0);
-        assert_eq!(connections.1.len(), 0);
-        // Create degree proofs and relationships
-        for i in 0..users.len() - 3 {
-            // Remove users from vector to reference
-            let mut preceding = users.remove(i);
-            // Proceeding is now an index below after removal
-            let mut proceeding = users.remove(i);
 
-            add_relationship_request(&mut preceding, &mut proceeding).await;
-            add_relationship_request(&mut proceeding, &mut preceding).await;
+        // both are detected
+        let orphaned = orphaned_relationships_request(false).await;
+        assert!(orphaned.contains(&to_a.to_hex()));
+        assert!(orphaned.contains(&to_b.to_hex()));
+
+        // repairing removes the dangling reference and restores the missing one
+        let repaired = orphaned_relationships_request(true).await;
+        assert!(repaired.contains(&to_a.to_hex()));
+        assert!(repaired.contains(&to_b.to_hex()));
+        let orphaned = orphaned_relationships_request(false).await;
+        assert!(!orphaned.contains(&to_a.to_hex()));
+        assert!(!orphaned.contains(&to_b.to_hex()));
+        let relationships = db.get_user(user_a.username()).await.unwrap().relationships;
+        assert!(relationships.unwrap_or_default().is_empty());
+        let relationships = db.get_user(user_b.username()).await.unwrap().relationships;
+        assert_eq!(relationships.unwrap(), vec![to_b]);
+    }
 
-            let proofs = get_available_degrees_request(&mut proceeding)
-                .await
-                .unwrap();
-            create_degree_proof_request(&proofs[0], &mut proceeding).await;
+    #[rocket::async_test]
+    async fn test_degree_proof_rejected_after_relationship_deactivated() {
+        let context = GrapevineTestContext::init().await;
+        let db = context.client.rocket().state::<GrapevineDB>().unwrap();
 
-            // Add users back to vector
-            users.insert(i, preceding);
-            users.insert(i + 1, proceeding);
+        let mut user_a = GrapevineAccount::new(String::from("user_stale_relation_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_stale_relation_b"));
+        for user in [&user_a, &user_b] {
+            create_user_request(&context, &user.create_user_request()).await;
         }
-
-        let mut user_a = users.remove(0);
-        let mut user_b = users.remove(0);
-        let mut user_c = users.remove(0);
-        let mut user_d = users.remove(0);
-        let mut user_f = users.remove(1);
-        let mut user_g = users.remove(1);
-
-        // user_b <- user_c -> user_d
-        let connections = get_phrase_connection_request(&mut user_c, data.phrase_index)
+        let phrase = String::from("Proofs only count while the relationship does");
+        _ = phrase_request(&phrase, String::from("Freshness check"), &mut user_a).await;
+        add_relationship_request_to(&context, &mut user_a, &mut user_b).await;
+        add_relationship_request_to(&context, &mut user_b, &mut user_a).await;
+
+        // B builds a proof from A's proof while the relationship is active
+        let proof_a = get_available_degrees_request(&mut user_b).await.unwrap();
+        let request = build_degree_proof_request(&proof_a[0], &mut user_b).await;
+
+        // the relationship from A to B is deactivated before the proof is submitted
+        let a = db.get_user(user_a.username()).await.unwrap().id.unwrap();
+        let b = db.get_user(user_b.username()).await.unwrap().id.unwrap();
+        let mongo = mongodb::Client::with_uri_str(&*MONGODB_URI).await.unwrap();
+        mongo
+            .database("grapevine_mocked")
+            .collection::<Relationship>("relationships")
+            .update_one(
+                doc! { "sender": a, "recipient": b },
+                doc! { "$set": { "active": false } },
+                None,
+            )
             .await
             .unwrap();
-        // has 2 total connections
-        assert_eq!(connections.0, 2);
-        // not connected to user_a at 1st degree spot
-        assert_eq!(*connections.1.get(0).unwrap(), 0);
-        // user_b is is at 2nd degree spot, showing 1 2nd degree connection
-        assert_eq!(*connections.1.get(1).unwrap(), 1);
-        // user_c is not connected to themselves and no other 3rd degree connections
-        assert_eq!(*connections.1.get(2).unwrap(), 0);
-        // user d is at 4th degree spot, showing 1 4th degree connection
-        assert_eq!(*connections.1.get(3).unwrap(), 1);
-        // connection vector is length 4, implying no connections past degree 4
-        assert_eq!(connections.1.len(), 4);
 
-        add_relationship_request(&mut user_a, &mut user_f).await;
-        add_relationship_request(&mut user_f, &mut user_a).await;
+        // the proof is rejected since it no longer builds on an active relationship
+        let serialized = bincode::serialize(&request).unwrap();
+        let signature = generate_request_signature(&user_b, "POST", "/proof/degree");
+        let res = context
+            .client
+            .post("/proof/degree")
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", user_b.username().clone()))
+            .body(serialized)
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Forbidden);
+        let msg = res.into_string().await.unwrap();
+        assert!(msg.contains("PrecedingNotRelationship"));
+        user_b.increment_nonce(None);
+    }
 
-        let proofs = get_available_degrees_request(&mut user_f).await.unwrap();
-        // User F has proof of degree 2
-        create_degree_proof_request(&proofs[0], &mut user_f).await;
-        // User G has degree proof 3
-        add_relationship_request(&mut user_b, &mut user_g).await;
-        add_relationship_request(&mut user_g, &mut user_b).await;
+    #[rocket::async_test]
+    async fn test_get_phrase_proof() {
+        // Reset db with clean state
+        GrapevineDB::drop("grapevine_mocked").await;
 
-        let proofs = get_available_degrees_request(&mut user_g).await.unwrap();
-        create_degree_proof_request(&proofs[0], &mut user_g).await;
+        let context = GrapevineTestContext::init().await;
 
-        add_relationship_request(&mut user_c, &mut user_a).await;
-        add_relationship_request(&mut user_a, &mut user_c).await;
-        add_relationship_request(&mut user_c, &mut user_d).await;
-        add_relationship_request(&mut user_d, &mut user_c).await;
-        add_relationship_request(&mut user_c, &mut user_f).await;
-        add_relationship_request(&mut user_f, &mut user_c).await;
-        add_relationship_request(&mut user_c, &mut user_g).await;
-        add_relationship_request(&mut user_g, &mut user_c).await;
+        let mut user_a = GrapevineAccount::new(String::from("user_phrase_proof_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_phrase_proof_b"));
+        let mut user_c = GrapevineAccount::new(String::from("user_phrase_proof_c"));
+        for user in [&user_a, &user_b, &user_c] {
+            create_user_request(&context, &user.create_user_request()).await;
+        }
+        let phrase = String::from("Proofs can be carried off the server");
+        let (_, msg) = phrase_request(&phrase, String::from("Credential"), &mut user_a).await;
+        let index = serde_json::from_str::<PhraseCreationResponse>(&msg)
+            .unwrap()
+            .phrase_index;
+        add_relationship_request_to(&context, &mut user_a, &mut user_b).await;
+        add_relationship_request_to(&context, &mut user_b, &mut user_a).await;
+        let proof_a = get_available_degrees_request(&mut user_b).await.unwrap();
+        _ = create_degree_proof_request(&proof_a[0], &mut user_b).await;
+
+        // B's proof is returned with its degree and the origin of the phrase
+        let (status, msg) = get_phrase_proof_request(&mut user_b, index).await;
+        assert_eq!(status, Status::Ok);
+        let response = serde_json::from_str::<PhraseProofResponse>(&msg).unwrap();
+        assert_eq!(response.degree, 2);
+        assert_eq!(&response.origin, user_a.username());
+        let (status, _, proof) =
+            download_proof_request(&context, &mut user_b, &response.proof).await;
+        assert_eq!(status, Status::Ok);
+        let params = use_public_params().unwrap();
+        assert!(check_proof(&proof, &params, steps_for_degree(2), 2).is_ok());
+
+        // the origin proves degree 1 of its own phrase
+        let (_, msg) = get_phrase_proof_request(&mut user_a, index).await;
+        let response = serde_json::from_str::<PhraseProofResponse>(&msg).unwrap();
+        assert_eq!(response.degree, 1);
+        assert_eq!(&response.origin, user_a.username());
+
+        // users without a proof of the phrase have nothing to export
+        let (status, msg) = get_phrase_proof_request(&mut user_c, index).await;
+        assert_eq!(status, Status::NotFound);
+        assert!(msg.contains("DegreeProofNotFound"));
+    }
 
-        // User C should have:
-        // * Connection to User A with proof of degree 1
-        // * Connection to User B with proof of degree 2
-        // * Connection to User D with proof of degree 4
-        // * Connection to User F with proof of degree 2
-        // * Connection to User G with proof of degree 3
-        let connections = get_phrase_connection_request(&mut user_c, data.phrase_index)
-            .await
-            .unwrap();
+    #[rocket::async_test]
+    async fn test_auth_headers_from_env() {
+        std::env::set_var("GRAPEVINE_USERNAME_HEADER", "X-Proxy-User");
+        std::env::set_var("GRAPEVINE_AUTHORIZATION_HEADER", "X-Proxy-Signature");
+        let auth_headers = AuthHeaders::from_env();
+        std::env::remove_var("GRAPEVINE_USERNAME_HEADER");
+        std::env::remove_var("GRAPEVINE_AUTHORIZATION_HEADER");
+        assert_eq!(auth_headers.username, "X-Proxy-User");
+        assert_eq!(auth_headers.authorization, "X-Proxy-Signature");
+        // unset names fall back to the defaults
+        assert_eq!(AuthHeaders::from_env(), AuthHeaders::default());
+
+        let context = GrapevineTestContext::init_with(UserQuotas::from_env(), auth_headers).await;
+        let user = GrapevineAccount::new(String::from("user_proxy_headers"));
+        create_user_request(&context, &user.create_user_request()).await;
 
-        assert_eq!(connections.0, 5);
-        assert_eq!(*connections.1.get(0).unwrap(), 1);
-        assert_eq!(*connections.1.get(1).unwrap(), 2);
-        assert_eq!(*connections.1.get(2).unwrap(), 1);
-        assert_eq!(*connections.1.get(3).unwrap(), 1);
-        assert_eq!(connections.1.len(), 4);
+        // requests are authenticated through the configured headers
+        let uri = "/user/relationship/active";
+        let signature = generate_request_signature(&user, "GET", uri);
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Proxy-Signature", signature.clone()))
+            .header(Header::new("X-Proxy-User", user.username().clone()))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::Ok);
 
-        // create phrase 2
-        let phrase = String::from("Raindrops are falling on my head");
-        let description = String::from("Get an umbrella ig");
-        let (_, res) = phrase_request(&phrase, description.clone(), &mut users[0]).await;
-        let data: PhraseCreationResponse = serde_json::from_str(&res).unwrap();
+        // the default names are no longer read
+        let res = context
+            .client
+            .get(uri)
+            .header(Header::new("X-Authorization", signature))
+            .header(Header::new("X-Username", user.username().clone()))
+            .dispatch()
+            .await;
+        assert_eq!(res.status(), Status::BadRequest);
+    }
 
-        let connections = get_phrase_connection_request(&mut user_c, data.phrase_index)
-            .await
-            .unwrap();
+    #[rocket::async_test]
+    async fn test_degree_proof_must_match_claimed_degree() {
+        let context = GrapevineTestContext::init().await;
 
-        assert_eq!(connections.0, 0);
-        assert_eq!(connections.1.len(), 0);
+        let mut user_a = GrapevineAccount::new(String::from("user_claimed_degree_a"));
+        let mut user_b = GrapevineAccount::new(String::from("user_claimed_degree_b"));
+        let mut user_c = GrapevineAccount::new(String::from("user_claimed_degree_c"));
+        for user in [&user_a, &user_b, &user_c] {
+            create_user_request(&context, &user.create_user_request()).await;
+        }
+        let phrase = String::from("Degrees are counted by the proof, not the label");
+        let (_, msg) = phrase_request(&phrase, String::from("Mislabeled"), &mut user_a).await;
+        let proof_a = serde_json::from_str::<PhraseCreationResponse>(&msg)
+            .unwrap()
+            .proof;
+        add_relationship_request_to(&context, &mut user_a, &mut user_b).await;
+        add_relationship_request_to(&context, &mut user_b, &mut user_a).await;
+        _ = create_degree_proof_request(&proof_a, &mut user_b).await;
+        add_relationship_request_to(&context, &mut user_b, &mut user_c).await;
+        add_relationship_request_to(&context, &mut user_c, &mut user_b).await;
+
+        // C builds a degree 3 proof on B's degree 2 proof
+        let proof_b = get_available_degrees_request(&mut user_c).await.unwrap();
+        let request = build_degree_proof_request(&proof_b[0], &mut user_c).await;
+        assert_eq!(request.degree, 3);
+
+        // the degree 3 proof labeled as degree 2 does not verify
+        let mislabeled = DegreeProofRequest {
+            degree: 2,
+            ..request.clone()
+        };
+        let (code, msg) = submit_degree_proof_request(&mislabeled, &mut user_c).await;
+        assert_eq!(code, Status::BadRequest.code);
+        assert!(msg.unwrap().contains("DegreeProofVerificationFailed"));
+
+        // the degree 3 proof cannot be linked to A's degree 1 proof
+        add_relationship_request_to(&context, &mut user_a, &mut user_c).await;
+        add_relationship_request_to(&context, &mut user_c, &mut user_a).await;
+        let relinked = DegreeProofRequest {
+            previous: proof_a,
+            ..request.clone()
+        };
+        let (code, msg) = submit_degree_proof_request(&relinked, &mut user_c).await;
+        assert_eq!(code, Status::BadRequest.code);
+        assert!(msg.unwrap().contains("ProofChainBroken"));
+
+        // the proof is accepted at its real degree on the proof it was built on
+        let (code, _) = submit_degree_proof_request(&request, &mut user_c).await;
+        assert_eq!(code, Status::Created.code);
     }
 }
@@ -1,16 +1,138 @@
+use crate::cache::CachedProof;
+use crate::utils::MERKLE_ZEROS;
 use futures::stream::StreamExt;
+use grapevine_common::crypto::{merkle_hash, merkle_leaf};
 use grapevine_common::errors::GrapevineError;
-use grapevine_common::http::responses::DegreeData;
-use grapevine_common::models::{DegreeProof, Phrase, ProvingData, Relationship, User};
-use mongodb::bson::{self, doc, oid::ObjectId, Binary, Bson};
-use mongodb::options::{ClientOptions, FindOneOptions, FindOptions, ServerApi, ServerApiVersion};
-use mongodb::{Client, Collection};
+use grapevine_common::http::responses::{
+    AccountDetailsResponse, ConnectedOrigin, DegreeData, LeaderboardEntry, MerkleProofResponse,
+    MerkleRootResponse, PhraseFullResponse, PhraseProofResponse, PhraseSearchResult, PhraseSummary,
+    ProofChild,
+};
+use grapevine_common::models::{
+    DegreeProof, MerkleNode, Phrase, PhraseCommitment, PhraseVisibility, Relationship, User,
+    UserStats,
+};
+use grapevine_common::MERKLE_DEPTH;
+use mongodb::bson::{self, doc, oid::ObjectId, Binary, Bson, DateTime, Document};
+use mongodb::options::{
+    ClientOptions, FindOneAndUpdateOptions, FindOneOptions, FindOptions, IndexOptions,
+    ReplaceOptions, ServerApi, ServerApiVersion, UpdateOptions,
+};
+use mongodb::{Client, ClientSession, Collection, Cursor, IndexModel};
+use rocket::tokio::sync::{Mutex as AsyncMutex, OnceCell};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+pub const DEFAULT_MONGO_CONNECT_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_MONGO_SERVER_SELECTION_TIMEOUT_SECS: u64 = 30;
+
+/**
+ * Connection pool and timeout settings applied to the mongodb client
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MongoPoolConfig {
+    pub max_pool_size: Option<u32>, // driver default if unset
+    pub min_pool_size: Option<u32>, // driver default if unset
+    pub connect_timeout: Duration,
+    pub server_selection_timeout: Duration, // how long a request waits for a usable server
+}
+
+impl MongoPoolConfig {
+    /**
+     * Read the pool settings from GRAPEVINE_MONGO_MAX_POOL_SIZE, GRAPEVINE_MONGO_MIN_POOL_SIZE,
+     * GRAPEVINE_MONGO_CONNECT_TIMEOUT_SECS, and GRAPEVINE_MONGO_SERVER_SELECTION_TIMEOUT_SECS,
+     * falling back to the defaults if unset or malformed
+     */
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /**
+     * Read the pool settings named in `from_env` through a lookup, falling back to the defaults if
+     * unset or malformed
+     *
+     * @param var - looks up the value of a setting by its environment variable name
+     */
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let secs = |name: &str, default: u64| {
+            Duration::from_secs(
+                var(name)
+                    .and_then(|secs| secs.parse().ok())
+                    .unwrap_or(default),
+            )
+        };
+        MongoPoolConfig {
+            max_pool_size: var("GRAPEVINE_MONGO_MAX_POOL_SIZE").and_then(|size| size.parse().ok()),
+            min_pool_size: var("GRAPEVINE_MONGO_MIN_POOL_SIZE").and_then(|size| size.parse().ok()),
+            connect_timeout: secs(
+                "GRAPEVINE_MONGO_CONNECT_TIMEOUT_SECS",
+                DEFAULT_MONGO_CONNECT_TIMEOUT_SECS,
+            ),
+            server_selection_timeout: secs(
+                "GRAPEVINE_MONGO_SERVER_SELECTION_TIMEOUT_SECS",
+                DEFAULT_MONGO_SERVER_SELECTION_TIMEOUT_SECS,
+            ),
+        }
+    }
+
+    /**
+     * Apply the settings to client options parsed from the connection string
+     * @notice - overrides any pool or timeout options given in the connection string that are set
+     *
+     * @param options - the client options to configure
+     */
+    pub fn apply(&self, options: &mut ClientOptions) {
+        if self.max_pool_size.is_some() {
+            options.max_pool_size = self.max_pool_size;
+        }
+        if self.min_pool_size.is_some() {
+            options.min_pool_size = self.min_pool_size;
+        }
+        options.connect_timeout = Some(self.connect_timeout);
+        options.server_selection_timeout = Some(self.server_selection_timeout);
+    }
+}
+
+/**
+ * The measures users can be ranked by on the leaderboard
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LeaderboardMetric {
+    Connections, // active relationships to the user
+    Phrases,     // phrases the user has an active proof on
+    Reach,       // other users with an active proof on a phrase the user created
+}
+
+impl LeaderboardMetric {
+    /**
+     * @param metric - the name of the metric (connections, phrases, or reach)
+     * @return - the metric, or None if the name is not recognized
+     */
+    pub fn parse(metric: &str) -> Option<Self> {
+        match metric {
+            "connections" => Some(LeaderboardMetric::Connections),
+            "phrases" => Some(LeaderboardMetric::Phrases),
+            "reach" => Some(LeaderboardMetric::Reach),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct GrapevineDB {
+    client: Client,
+    database_name: String,
+    allowed_tenants: Vec<String>,
+    tenants: Arc<Mutex<HashMap<String, GrapevineDB>>>,
     users: Collection<User>,
     relationships: Collection<Relationship>,
     degree_proofs: Collection<DegreeProof>,
     phrases: Collection<Phrase>,
+    merkle_nodes: Collection<MerkleNode>,
+    commitments: Collection<PhraseCommitment>,
+    merkle_lock: Arc<AsyncMutex<()>>, // serializes merkle tree updates within this process
+    transactions: Arc<OnceCell<bool>>, // whether the deployment supports transactions (once checked)
 }
 
 impl GrapevineDB {
@@ -18,18 +140,122 @@ impl GrapevineDB {
         let mut client_options = ClientOptions::parse(mongodb_uri).await.unwrap();
         let server_api = ServerApi::builder().version(ServerApiVersion::V1).build();
         client_options.server_api = Some(server_api);
+        MongoPoolConfig::from_env().apply(&mut client_options);
+        tracing::info!(
+            max_pool_size = ?client_options.max_pool_size,
+            min_pool_size = ?client_options.min_pool_size,
+            connect_timeout = ?client_options.connect_timeout,
+            server_selection_timeout = ?client_options.server_selection_timeout,
+            "mongodb connection settings"
+        );
         let client = Client::with_options(client_options).unwrap();
-        let db = client.database(database_name);
+        Self::from_client(client, database_name.clone(), vec![])
+    }
+
+    /**
+     * Build collection handles for a database on an existing client
+     *
+     * @param client - the mongodb client to share between databases
+     * @param database_name - the name of the database to use
+     * @param allowed_tenants - the tenant names that may be selected from this database
+     */
+    fn from_client(client: Client, database_name: String, allowed_tenants: Vec<String>) -> Self {
+        let db = client.database(&database_name);
         let users = db.collection("users");
         let relationships = db.collection("relationships");
         let degree_proofs = db.collection("degree_proofs");
         let phrases = db.collection("phrases");
+        let merkle_nodes = db.collection("merkle_nodes");
+        let commitments = db.collection("commitments");
         Self {
+            client,
+            database_name,
+            allowed_tenants,
+            tenants: Arc::new(Mutex::new(HashMap::new())),
             users,
             relationships,
             degree_proofs,
             phrases,
+            merkle_nodes,
+            commitments,
+            merkle_lock: Arc::new(AsyncMutex::new(())),
+            transactions: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /**
+     * @return - the name of the database this instance reads from
+     */
+    pub fn name(&self) -> &String {
+        &self.database_name
+    }
+
+    /**
+     * Set the tenants that may be selected with the X-Tenant header
+     *
+     * @param tenants - the allowlist of tenant names
+     */
+    pub fn with_tenants(mut self, tenants: Vec<String>) -> Self {
+        self.allowed_tenants = tenants;
+        self
+    }
+
+    /**
+     * Get the database for a tenant, creating the collection handles on first use
+     * @notice - tenant databases are named <database_name>_<tenant>
+     *
+     * @param tenant - the name of the tenant
+     * @return - the tenant database, or UnknownTenant if the tenant is not allowlisted
+     */
+    pub fn tenant(&self, tenant: &str) -> Result<GrapevineDB, GrapevineError> {
+        if !self.allowed_tenants.iter().any(|allowed| allowed == tenant) {
+            return Err(GrapevineError::UnknownTenant(String::from(tenant)));
+        }
+        let mut tenants = self.tenants.lock().unwrap();
+        let db = tenants.entry(String::from(tenant)).or_insert_with(|| {
+            let database_name = format!("{}_{}", self.database_name, tenant);
+            Self::from_client(self.client.clone(), database_name, vec![])
+        });
+        Ok(db.clone())
+    }
+
+    /**
+     * Create the degree proof indexes in this database and every allowlisted tenant database:
+     *   - a TTL index that lets mongodb delete proofs once their expires_at time passes
+     *   - a unique index on content_hash so the same proof bytes are never stored twice
+     * @notice - queries also filter on expires_at since the TTL monitor only runs periodically
+     */
+    pub async fn ensure_proof_indexes(&self) -> Result<(), GrapevineError> {
+        let mut databases = vec![self.clone()];
+        for tenant in self.allowed_tenants.iter() {
+            databases.push(self.tenant(tenant)?);
         }
+        for db in databases {
+            let options = IndexOptions::builder()
+                .expire_after(std::time::Duration::from_secs(0))
+                .build();
+            let expiry_index = IndexModel::builder()
+                .keys(doc! { "expires_at": 1 })
+                .options(options)
+                .build();
+            // proofs stored before content hashes were recorded are left out of the index
+            let options = IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "content_hash": { "$exists": true } })
+                .build();
+            let content_index = IndexModel::builder()
+                .keys(doc! { "content_hash": 1 })
+                .options(options)
+                .build();
+            if let Err(e) = db
+                .degree_proofs
+                .create_indexes([expiry_index, content_index], None)
+                .await
+            {
+                return Err(GrapevineError::MongoError(e.to_string()));
+            }
+        }
+        Ok(())
     }
 
     /**
@@ -46,10 +272,151 @@ impl GrapevineDB {
 
     /// USER FUNCTIONS ///
 
-    pub async fn increment_nonce(&self, username: &str) -> Result<(), GrapevineError> {
-        let filter = doc! { "username": username };
+    /**
+     * Consume a user's nonce, only if it has not changed since it was checked
+     *
+     * @param username - the username of the user
+     * @param nonce - the nonce the request was signed with
+     * @return - NonceReplayed if another request consumed the nonce first
+     */
+    pub async fn increment_nonce(&self, username: &str, nonce: u64) -> Result<(), GrapevineError> {
+        let filter = doc! { "username": username, "nonce": nonce as i64 };
         let update = doc! { "$inc": { "nonce": 1 } };
         match self.users.update_one(filter, update, None).await {
+            Ok(res) if res.modified_count == 0 => {
+                Err(GrapevineError::NonceReplayed(nonce, nonce + 1))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Set whether a user allows the server to generate degree proofs on their behalf
+     *
+     * @param username - the username of the user
+     * @param enabled - whether delegated proving is allowed
+     */
+    pub async fn set_delegated_proving(
+        &self,
+        username: &str,
+        enabled: bool,
+    ) -> Result<(), GrapevineError> {
+        let filter = doc! { "username": username };
+        let update = doc! { "$set": { "delegated_proving": enabled } };
+        match self.users.update_one(filter, update, None).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Set the guardians that can approve recovering a user's account
+     * @notice - assumes the guardians were already checked to be active relationships of the user
+     *
+     * @param username - the username of the user
+     * @param guardians - the usernames of the guardians (empty to disable recovery)
+     * @param threshold - the number of guardian approvals needed to recover the account
+     */
+    pub async fn set_guardians(
+        &self,
+        username: &str,
+        guardians: &Vec<String>,
+        threshold: u8,
+    ) -> Result<(), GrapevineError> {
+        // resolve the usernames of the guardians to their ObjectIDs
+        let filter = doc! { "username": { "$in": guardians.clone() } };
+        let projection = doc! { "_id": 1, "pubkey": 1 };
+        let find_options = FindOptions::builder().projection(projection).build();
+        let mut cursor = match self.users.find(filter, Some(find_options)).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        let mut oids: Vec<ObjectId> = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(user) => oids.push(user.id.unwrap()),
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            }
+        }
+        if oids.len() != guardians.len() {
+            return Err(GrapevineError::UserNotFound(String::from("guardian")));
+        }
+
+        let filter = doc! { "username": username };
+        let update = doc! { "$set": { "guardians": oids, "recovery_threshold": threshold as i32 } };
+        match self.users.update_one(filter, update, None).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Get the recovery configuration of a user
+     *
+     * @param username - the username of the user
+     * @returns - None if the user does not exist, or the user's nonce, recovery threshold, and the
+     *            usernames and pubkeys of their guardians (empty if recovery is not set up)
+     */
+    pub async fn get_guardians(
+        &self,
+        username: &String,
+    ) -> Result<Option<(u64, u8, Vec<(String, [u8; 32])>)>, GrapevineError> {
+        let user = match self.get_user(username).await {
+            Some(user) => user,
+            None => return Ok(None),
+        };
+        let oids = user.guardians.unwrap_or_default();
+        let mut guardians: Vec<(String, [u8; 32])> = vec![];
+        if !oids.is_empty() {
+            let filter = doc! { "_id": { "$in": oids } };
+            let projection = doc! { "username": 1, "pubkey": 1 };
+            let find_options = FindOptions::builder().projection(projection).build();
+            let mut cursor = match self.users.find(filter, Some(find_options)).await {
+                Ok(cursor) => cursor,
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            };
+            while let Some(result) = cursor.next().await {
+                match result {
+                    Ok(guardian) => {
+                        guardians.push((guardian.username.unwrap(), guardian.pubkey.unwrap()))
+                    }
+                    Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+                }
+            }
+        }
+        Ok(Some((
+            user.nonce.unwrap(),
+            user.recovery_threshold.unwrap_or(0),
+            guardians,
+        )))
+    }
+
+    /**
+     * Move a user's account to a new pubkey, incrementing the nonce so approvals cannot be replayed
+     * @notice - assumes the rotation was already approved by the user's guardians
+     *
+     * @param username - the username of the user
+     * @param pubkey - the compressed pubkey to move the account to
+     * @param nonce - the nonce the approvals were made for
+     * @return - NonceReplayed if the nonce moved since the approvals were checked
+     */
+    pub async fn rotate_pubkey(
+        &self,
+        username: &String,
+        pubkey: &[u8; 32],
+        nonce: u64,
+    ) -> Result<(), GrapevineError> {
+        let pubkey_binary = Binary {
+            subtype: bson::spec::BinarySubtype::Generic,
+            bytes: pubkey.to_vec(),
+        };
+        let filter = doc! { "username": username, "nonce": nonce as i64 };
+        let update = doc! { "$set": { "pubkey": pubkey_binary }, "$inc": { "nonce": 1 } };
+        match self.users.update_one(filter, update, None).await {
+            Ok(res) if res.matched_count == 0 => {
+                Err(GrapevineError::NonceReplayed(nonce, nonce + 1))
+            }
             Ok(_) => Ok(()),
             Err(e) => Err(GrapevineError::MongoError(e.to_string())),
         }
@@ -144,6 +511,156 @@ impl GrapevineDB {
         }
     }
 
+    /// MERKLE FUNCTIONS ///
+
+    /**
+     * Append a user to the merkle tree of users and update the path from their leaf to the root
+     * @notice - updates are serialized per process, so only one server may write to a database
+     *
+     * @param username - the username of the user to add
+     * @returns - the index of the user's leaf
+     */
+    pub async fn add_merkle_leaf(&self, username: &String) -> Result<u32, GrapevineError> {
+        let _lock = self.merkle_lock.lock().await;
+        let leaf = merkle_leaf(username)?;
+
+        // leaves are appended after the highest leaf index
+        let find_options = FindOneOptions::builder().sort(doc! { "index": -1 }).build();
+        let index = match self
+            .merkle_nodes
+            .find_one(doc! { "level": 0 }, find_options)
+            .await
+        {
+            Ok(Some(node)) => node.index.unwrap() + 1,
+            Ok(None) => 0,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        if index as u64 >= 1 << MERKLE_DEPTH {
+            return Err(GrapevineError::MongoError(String::from(
+                "Merkle tree is full",
+            )));
+        }
+
+        // store the leaf then rehash each node on the path to the root
+        self.set_merkle_node(0, index, &leaf, Some(username))
+            .await?;
+        let mut node = leaf;
+        let mut position = index;
+        for level in 0..MERKLE_DEPTH as u32 {
+            let sibling = self.get_merkle_node(level, position ^ 1).await?;
+            node = match position & 1 {
+                0 => merkle_hash(&node, &sibling),
+                _ => merkle_hash(&sibling, &node),
+            };
+            position >>= 1;
+            self.set_merkle_node(level + 1, position, &node, None)
+                .await?;
+        }
+        Ok(index)
+    }
+
+    /**
+     * Get the root of the merkle tree of users
+     *
+     * @returns - the root and the number of users in the tree
+     */
+    pub async fn get_merkle_root(&self) -> Result<MerkleRootResponse, GrapevineError> {
+        let root = self.get_merkle_node(MERKLE_DEPTH as u32, 0).await?;
+        let size = match self
+            .merkle_nodes
+            .count_documents(doc! { "level": 0 }, None)
+            .await
+        {
+            Ok(size) => size as u32,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        Ok(MerkleRootResponse { root, size })
+    }
+
+    /**
+     * Get the inclusion path of a user in the merkle tree of users
+     *
+     * @param username - the username of the user
+     * @returns - the user's leaf and index with the siblings on the path to the current root, or
+     *            UserNotFound if the user has no leaf
+     */
+    pub async fn get_merkle_proof(
+        &self,
+        username: &String,
+    ) -> Result<MerkleProofResponse, GrapevineError> {
+        // hold the lock so the path and root are read from the same tree
+        let _lock = self.merkle_lock.lock().await;
+        let filter = doc! { "level": 0, "username": username };
+        let leaf = match self.merkle_nodes.find_one(filter, None).await {
+            Ok(Some(node)) => node,
+            Ok(None) => return Err(GrapevineError::UserNotFound(username.clone())),
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        let index = leaf.index.unwrap();
+        let mut siblings = vec![];
+        for level in 0..MERKLE_DEPTH as u32 {
+            siblings.push(self.get_merkle_node(level, (index >> level) ^ 1).await?);
+        }
+        let root = self.get_merkle_node(MERKLE_DEPTH as u32, 0).await?;
+        Ok(MerkleProofResponse {
+            username: username.clone(),
+            index,
+            leaf: leaf.hash.unwrap(),
+            siblings,
+            root,
+        })
+    }
+
+    /**
+     * Get the hash of a node in the merkle tree of users
+     *
+     * @param level - the height of the node above the leaves
+     * @param index - the position of the node within its level
+     * @returns - the hash of the node, or the empty subtree root for the level if it is not stored
+     */
+    async fn get_merkle_node(&self, level: u32, index: u32) -> Result<[u8; 32], GrapevineError> {
+        let filter = doc! { "level": level, "index": index };
+        let projection = doc! { "hash": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        match self.merkle_nodes.find_one(filter, find_options).await {
+            Ok(Some(node)) => Ok(node.hash.unwrap()),
+            Ok(None) => Ok(MERKLE_ZEROS[level as usize]),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Store the hash of a node in the merkle tree of users, replacing any previous hash
+     *
+     * @param level - the height of the node above the leaves
+     * @param index - the position of the node within its level
+     * @param hash - the hash of the node
+     * @param username - the user a leaf belongs to (none for internal nodes)
+     */
+    async fn set_merkle_node(
+        &self,
+        level: u32,
+        index: u32,
+        hash: &[u8; 32],
+        username: Option<&String>,
+    ) -> Result<(), GrapevineError> {
+        let filter = doc! { "level": level, "index": index };
+        let hash_bson: Vec<i32> = hash.iter().map(|x| *x as i32).collect();
+        let mut update = doc! { "hash": hash_bson };
+        if let Some(username) = username {
+            update.insert("username", username);
+        }
+        let options = UpdateOptions::builder().upsert(true).build();
+        match self
+            .merkle_nodes
+            .update_one(filter, doc! { "$set": update }, options)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
     pub async fn get_user(&self, username: &String) -> Option<User> {
         let filter = doc! { "username": username };
         let projection = doc! { "degree_proofs": 0 };
@@ -169,10 +686,20 @@ impl GrapevineDB {
         }
     }
 
+    /**
+     * Adds a pending relationship from one user to another
+     *
+     * @param relationship - the relationship to add
+     * @returns - Ok if added, or RelationshipSenderIsTarget if the sender is the recipient
+     */
     pub async fn add_pending_relationship(
         &self,
         relationship: &Relationship,
     ) -> Result<(), GrapevineError> {
+        // a user cannot have a relationship with themselves
+        if relationship.sender == relationship.recipient {
+            return Err(GrapevineError::RelationshipSenderIsTarget);
+        }
         // create new relationship document
         match self.relationships.insert_one(relationship, None).await {
             Ok(_) => Ok(()),
@@ -180,70 +707,127 @@ impl GrapevineDB {
         }
     }
 
+    /**
+     * Check whether the deployment supports multi-document transactions (a replica set or sharded
+     * cluster), warning the first time it is found not to
+     *
+     * @returns - true if several writes can be committed or rolled back together
+     */
+    pub async fn supports_transactions(&self) -> bool {
+        *self
+            .transactions
+            .get_or_init(|| async {
+                let hello = self
+                    .client
+                    .database("admin")
+                    .run_command(doc! { "hello": 1 }, None)
+                    .await;
+                // replica set members report their set name, mongos reports isdbgrid
+                let supported = match hello {
+                    Ok(reply) => {
+                        reply.contains_key("setName")
+                            || reply.get_str("msg").ok() == Some("isdbgrid")
+                    }
+                    Err(_) => false,
+                };
+                if !supported {
+                    tracing::warn!(
+                        "mongodb is not a replica set, relationship activation is not atomic"
+                    );
+                }
+                supported
+            })
+            .await
+    }
+
     /**
      * Sets pending relationship to be active (to -> from) and creates a new relationship (from -> to)
+     * @notice the writes run in one transaction when the deployment supports it, so a failure
+     *         partway through leaves the relationship pending. On a standalone server they are
+     *         applied one at a time
      *
      * @param relationship - the relationship to activate
-     * @returns - the object id of the activated relationship
+     * @returns - Ok if activated, or MongoError if any write fails
      */
     pub async fn activate_relationship(
         &self,
         relationship: &Relationship,
     ) -> Result<(), GrapevineError> {
-        // set the pending relationship to be active
+        let mongo_error = |e: mongodb::error::Error| GrapevineError::MongoError(e.to_string());
+        let mut session = self.client.start_session(None).await.map_err(mongo_error)?;
+        if !self.supports_transactions().await {
+            return self.write_activation(relationship, &mut session).await;
+        }
+        session.start_transaction(None).await.map_err(mongo_error)?;
+        match self.write_activation(relationship, &mut session).await {
+            Ok(_) => session.commit_transaction().await.map_err(mongo_error),
+            Err(e) => {
+                // discard any writes made before the failure
+                let _ = session.abort_transaction().await;
+                Err(e)
+            }
+        }
+    }
+
+    /**
+     * Apply the writes activating a relationship within a session
+     *
+     * @param relationship - the relationship to activate
+     * @param session - the session (and transaction, if started) to write in
+     * @returns - Ok if every write succeeded, or MongoError at the first that fails
+     */
+    async fn write_activation(
+        &self,
+        relationship: &Relationship,
+        session: &mut ClientSession,
+    ) -> Result<(), GrapevineError> {
+        let mongo_error = |e: mongodb::error::Error| GrapevineError::MongoError(e.to_string());
+        // set the pending relationship to be active, retrieving its oid
         let query = doc! {
             "sender": relationship.recipient.unwrap(),
             "recipient": relationship.sender.unwrap()
         };
         let update = doc! { "$set": { "active": true } };
-        match self
-            .relationships
-            .update_one(query.clone(), update, None)
-            .await
-        {
-            Ok(_) => (),
-            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
-        };
-
-        // retrieve the oid of the activated relationship
-        let find_options = FindOneOptions::builder()
-            .projection(doc! {"_id": 1})
+        let options = FindOneAndUpdateOptions::builder()
+            .projection(doc! { "_id": 1 })
             .build();
-        // probably safe to unwrap here since we just activated the relationship
-        // annoying that API does not return the oid of the updated document
-        let sender_relationship: Bson = self
+        let pending = self
             .relationships
-            .find_one(query, Some(find_options))
+            .find_one_and_update_with_session(query, update, options, session)
             .await
-            .unwrap()
-            .unwrap()
-            .id
-            .unwrap()
-            .into();
+            .map_err(mongo_error)?;
+        let sender_relationship = match pending {
+            Some(pending) => pending.id.unwrap(),
+            None => {
+                return Err(GrapevineError::MongoError(String::from(
+                    "Pending relationship not found",
+                )))
+            }
+        };
 
-        // push the relationship to the 's list of relationships
+        // push the relationship to the sender's list of relationships
         let query = doc! { "_id": relationship.sender.unwrap() };
         let update = doc! { "$push": { "relationships": sender_relationship } };
-        match self.users.update_one(query, update, None).await {
-            Ok(_) => (),
-            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
-        }
+        self.users
+            .update_one_with_session(query, update, None, session)
+            .await
+            .map_err(mongo_error)?;
 
         // create new relationship document
         let recipient_relationship = self
             .relationships
-            .insert_one(relationship, None)
+            .insert_one_with_session(relationship, None, session)
             .await
-            .unwrap()
+            .map_err(mongo_error)?
             .inserted_id;
 
-        // push the relationship to the recipien's list of relationships
+        // push the relationship to the recipient's list of relationships
         let query = doc! { "_id": relationship.recipient.unwrap() };
         let update = doc! { "$push": { "relationships": recipient_relationship } };
-        match self.users.update_one(query, update, None).await {
-            Ok(_) => (),
-            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
-        }
+        self.users
+            .update_one_with_session(query, update, None, session)
+            .await
+            .map_err(mongo_error)?;
         Ok(())
     }
 
@@ -350,7 +934,8 @@ impl GrapevineDB {
      *
      * @param user - the username of the user to find relationships for
      * @param active - whether to find active or pending relationships
-     * @returns - a list of usernames of the users the user has relationships with
+     * @returns - a list of usernames of the users the user has relationships with, sorted by
+     *            username so the order does not depend on when the relationships were added
      */
     pub async fn get_relationships(
         &self,
@@ -391,6 +976,7 @@ impl GrapevineDB {
             doc! { "$unwind": "$relationships" },
             // project only the usernames of the relationships
             doc! { "$project": { "username": "$relationships.username", "_id": 0 } },
+            doc! { "$sort": { "username": 1 } },
         ];
 
         // get the OID's of degree proofs the user can build from
@@ -409,15 +995,143 @@ impl GrapevineDB {
     }
 
     /**
-     * Attempts to find a relationship between to users
+     * Get the usernames of users a user sent relationship requests to that have not been accepted
+     * @notice - the complement of `get_relationships(user, false)`, which finds requests received
      *
-     * @param from - the user enabling relationship
-     * @param to - the user receiving relationship
-     * @returns - the relationship if found, None otherwise
+     * @param user - the username of the sender of the relationship requests
+     * @returns - the usernames of the recipients of the pending requests, sorted by username
      */
-    pub async fn find_pending_relationship(
-        &self,
-        from: &ObjectId,
+    pub async fn get_outbound_pending(&self, user: &String) -> Result<Vec<String>, GrapevineError> {
+        let pipeline = vec![
+            // get the ObjectID of the user doc for the given username
+            doc! { "$match": { "username": user } },
+            doc! { "$project": { "_id": 1 } },
+            // lookup pending relationships sent by the user
+            doc! {
+                "$lookup": {
+                    "from": "relationships",
+                    "localField": "_id",
+                    "foreignField": "sender",
+                    "as": "relationships",
+                    "pipeline": [
+                        doc! { "$match": { "active": false } },
+                        doc! { "$project": { "recipient": 1, "_id": 0 } },
+                    ],
+                }
+            },
+            doc! { "$unwind": "$relationships" },
+            // lookup the usernames of the recipients
+            doc! {
+                "$lookup": {
+                    "from": "users",
+                    "localField": "relationships.recipient",
+                    "foreignField": "_id",
+                    "as": "relationships",
+                    "pipeline": [
+                        doc! { "$project": { "username": 1, "_id": 0 } },
+                    ],
+                }
+            },
+            doc! { "$unwind": "$relationships" },
+            doc! { "$project": { "username": "$relationships.username", "_id": 0 } },
+            doc! { "$sort": { "username": 1 } },
+        ];
+
+        let mut relationships: Vec<String> = vec![];
+        let mut cursor = match self.users.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    let username = document.get_str("username").unwrap();
+                    relationships.push(username.to_string());
+                }
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            }
+        }
+        Ok(relationships)
+    }
+
+    /**
+     * Find the usernames of users who sent a pending relationship request since a given time
+     * @notice - uses the creation time embedded in the relationship ObjectID
+     *
+     * @param user - the username of the recipient of the relationship requests
+     * @param since - unix timestamp (seconds) to find requests sent at or after
+     * @returns - a list of usernames of the users that sent a request since the timestamp
+     */
+    pub async fn get_new_relationship_requests_since(
+        &self,
+        user: &String,
+        since: u32,
+    ) -> Result<Vec<String>, GrapevineError> {
+        // build the smallest ObjectID that could have been created at the cutoff
+        let mut cutoff = [0u8; 12];
+        cutoff[..4].copy_from_slice(&since.to_be_bytes());
+        let cutoff = ObjectId::from_bytes(cutoff);
+        let pipeline = vec![
+            // get the ObjectID of the user doc for the given username
+            doc! { "$match": { "username": user } },
+            doc! { "$project": { "_id": 1 } },
+            // lookup pending relationships for the user created after the cutoff
+            doc! {
+                "$lookup": {
+                    "from": "relationships",
+                    "localField": "_id",
+                    "foreignField": "recipient",
+                    "as": "relationships",
+                    "pipeline": [
+                        doc! { "$match": { "active": false, "_id": { "$gte": cutoff } } },
+                        doc! { "$project": { "sender": 1, "_id": 0 } },
+                    ],
+                }
+            },
+            doc! { "$unwind": "$relationships" },
+            // lookup the usernames of the senders
+            doc! {
+                "$lookup": {
+                    "from": "users",
+                    "localField": "relationships.sender",
+                    "foreignField": "_id",
+                    "as": "relationships",
+                    "pipeline": [
+                        doc! { "$project": { "username": 1, "_id": 0 } },
+                    ],
+                }
+            },
+            doc! { "$unwind": "$relationships" },
+            doc! { "$project": { "username": "$relationships.username", "_id": 0 } },
+        ];
+
+        let mut relationships: Vec<String> = vec![];
+        let mut cursor = match self.users.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    let username = document.get("username").unwrap().as_str().unwrap();
+                    relationships.push(username.to_string());
+                }
+                Err(e) => println!("Error: {}", e),
+            }
+        }
+        Ok(relationships)
+    }
+
+    /**
+     * Attempts to find a relationship between to users
+     *
+     * @param from - the user enabling relationship
+     * @param to - the user receiving relationship
+     * @returns - the relationship if found, None otherwise
+     */
+    pub async fn find_pending_relationship(
+        &self,
+        from: &ObjectId,
         to: &ObjectId,
     ) -> Result<bool, GrapevineError> {
         let filter = doc! { "sender": from, "recipient": to, "active": false };
@@ -433,77 +1147,757 @@ impl GrapevineDB {
     }
 
     /**
-     * Check to see if a relationship already exists between two users
+     * Count the relationships a user has sent that the recipient has not yet accepted
+     *
+     * @param sender - the user whose outbound relationships to count
+     * @returns - the number of pending relationships sent by the user
+     */
+    pub async fn count_pending_relationships_from(
+        &self,
+        sender: &ObjectId,
+    ) -> Result<u64, GrapevineError> {
+        let filter = doc! { "sender": sender, "active": false };
+        match self.relationships.count_documents(filter, None).await {
+            Ok(count) => Ok(count),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Find every user with an active relationship from a given user
+     *
+     * @param sender - the user whose relationships to look up
+     * @returns - the ObjectIDs of the recipients of the sender's active relationships
+     */
+    pub async fn get_active_relationship_recipients(
+        &self,
+        sender: &ObjectId,
+    ) -> Result<Vec<ObjectId>, GrapevineError> {
+        let filter = doc! { "sender": sender, "active": true };
+        let projection = doc! { "recipient": 1 };
+        let find_options = FindOptions::builder().projection(projection).build();
+        let mut cursor = match self.relationships.find(filter, find_options).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        let mut recipients = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(relationship) => recipients.extend(relationship.recipient),
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            }
+        }
+        Ok(recipients)
+    }
+
+    /**
+     * Get the encrypted auth secret of an active relationship between two users
+     *
+     * @param sender - the user that shared their auth secret
+     * @param recipient - the user the auth secret was encrypted for
+     * @returns - the ephemeral key and ciphertext, or None if no active relationship exists
+     */
+    pub async fn get_active_relationship_keys(
+        &self,
+        sender: &ObjectId,
+        recipient: &ObjectId,
+    ) -> Result<Option<([u8; 32], [u8; 48])>, GrapevineError> {
+        let filter = doc! { "sender": sender, "recipient": recipient, "active": true };
+        let projection = doc! { "ephemeral_key": 1, "ciphertext": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        match self.relationships.find_one(filter, find_options).await {
+            Ok(res) => Ok(res.map(|relationship| {
+                (
+                    relationship.ephemeral_key.unwrap(),
+                    relationship.ciphertext.unwrap(),
+                )
+            })),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Check to see if a relationship already exists between two users
+     *
+     * @param sender - the user enabling relationship
+     * @param recipient - the user receiving relationship
+     * @returns
+     *  - 0: true if relationship from sender to user exists
+     *  - 1: true if relationship is active
+     */
+    pub async fn check_relationship_exists(
+        &self,
+        sender: &ObjectId,
+        recipient: &ObjectId,
+    ) -> Result<(bool, bool), GrapevineError> {
+        let query = doc! { "recipient": recipient, "sender": sender };
+        let projection = doc! { "_id": 1, "active": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+
+        match self.relationships.find_one(query, find_options).await {
+            Ok(res) => {
+                let exists = res.is_some();
+                let active = match exists {
+                    true => res.unwrap().active.unwrap(),
+                    false => false,
+                };
+                Ok((exists, active))
+            }
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Find the users both of two users have active relationships with
+     * @notice only relationships received by each user are considered, so a mutual connection
+     *         is never revealed to `a` unless `a` already has an active relationship with them
+     *
+     * @param a - the user asking for mutual connections
+     * @param b - the user to compare connections with
+     * @returns - the usernames of the mutual connections, sorted by username
+     */
+    pub async fn get_mutual_relationships(
+        &self,
+        a: &ObjectId,
+        b: &ObjectId,
+    ) -> Result<Vec<String>, GrapevineError> {
+        let pipeline = vec![
+            // find the active relationships received by either user
+            doc! { "$match": { "recipient": { "$in": [a, b] }, "active": true } },
+            // build the set of senders for each user
+            doc! { "$group": { "_id": "$recipient", "senders": { "$addToSet": "$sender" } } },
+            doc! { "$group": { "_id": null, "sets": { "$push": "$senders" } } },
+            // intersect the sets (null if either user has no relationships)
+            doc! {
+                "$project": {
+                    "mutual": {
+                        "$setIntersection": [
+                            { "$arrayElemAt": ["$sets", 0] },
+                            { "$arrayElemAt": ["$sets", 1] }
+                        ]
+                    },
+                    "_id": 0
+                }
+            },
+            doc! { "$unwind": "$mutual" },
+            // lookup the usernames of the mutual connections
+            doc! {
+                "$lookup": {
+                    "from": "users",
+                    "localField": "mutual",
+                    "foreignField": "_id",
+                    "as": "user",
+                    "pipeline": [
+                        doc! { "$project": { "username": 1, "_id": 0 } },
+                    ],
+                }
+            },
+            doc! { "$unwind": "$user" },
+            doc! { "$project": { "username": "$user.username" } },
+            doc! { "$sort": { "username": 1 } },
+        ];
+
+        let mut mutual: Vec<String> = vec![];
+        let mut cursor = match self.relationships.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    let username = document.get_str("username").unwrap();
+                    mutual.push(username.to_string());
+                }
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            }
+        }
+        Ok(mutual)
+    }
+
+    /**
+     * Creates a new phrase document in the database
+     * @notice assumes that `get_phrase_by_{hash, oid}` has already been called
+     *
+     * @param phrase_hash - the hash of the phrase to create
+     * @param description - the description of the phrase
+     * @param tags - the tags to list the phrase under
+     * @return: (0, 1)
+     *  - 0: the object id of the created phrase document
+     *  - 1: the index of the phrase
+     */
+    pub async fn create_phrase(
+        &self,
+        phrase_hash: [u8; 32],
+        description: String,
+        tags: Vec<String>,
+    ) -> Result<(ObjectId, u32), GrapevineError> {
+        // query for the highest phrase id
+        let find_options = FindOneOptions::builder().sort(doc! {"index": -1}).build();
+
+        // Use find_one with options to get the document with the largest phrase_id
+        let index = match self.phrases.find_one(None, find_options).await {
+            Ok(Some(document)) => {
+                let previous_index = document.index.unwrap();
+                previous_index + 1
+            }
+            Ok(None) => 1,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+
+        // create new phrase document
+        let phrase = Phrase {
+            id: None,
+            index: Some(index),
+            hash: Some(phrase_hash),
+            description: Some(description),
+            tags: Some(tags),
+            visibility: None,
+        };
+        let oid = match self.phrases.insert_one(&phrase, None).await {
+            Ok(res) => res.inserted_id.as_object_id().unwrap(),
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+
+        Ok((oid, index))
+    }
+
+    /**
+     * Count the phrases a user originated (was the first to prove degree 1 for)
+     *
+     * @param user - the ObjectId of the user
+     * @returns - the number of phrases originated by the user
+     */
+    pub async fn count_originated_phrases(&self, user: &ObjectId) -> Result<u64, GrapevineError> {
+        let pipeline = vec![
+            // every phrase the user has proven knowledge of
+            doc! { "$match": { "user": user, "degree": 1 } },
+            // look up the first degree 1 proof made for the phrase
+            doc! {
+                "$lookup": {
+                    "from": "degree_proofs",
+                    "let": { "phrase": "$phrase" },
+                    "as": "origin",
+                    "pipeline": [
+                        doc! {
+                            "$match": {
+                                "$expr": {
+                                    "$and": [
+                                        { "$eq": ["$phrase", "$$phrase"] },
+                                        { "$eq": ["$degree", 1] }
+                                    ]
+                                }
+                            }
+                        },
+                        doc! { "$sort": { "_id": 1 } },
+                        doc! { "$limit": 1 },
+                        doc! { "$project": { "user": 1, "_id": 0 } }
+                    ]
+                }
+            },
+            doc! { "$unwind": "$origin" },
+            // keep the phrases where that proof is the user's
+            doc! { "$match": { "$expr": { "$eq": ["$origin.user", "$user"] } } },
+            doc! { "$count": "count" },
+        ];
+        let mut cursor = match self.degree_proofs.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        match cursor.next().await {
+            Some(Ok(document)) => Ok(document.get_i32("count").unwrap_or(0) as u64),
+            Some(Err(e)) => Err(GrapevineError::MongoError(e.to_string())),
+            None => Ok(0),
+        }
+    }
+
+    /**
+     * Get a phrase that may only be modified by its origin (the first user to prove degree 1)
+     *
+     * @param user - the ObjectId of the user requesting to modify the phrase
+     * @param index - the index of the phrase
+     * @returns - the ObjectId of the phrase, or an error if the phrase is not found or user is not
+     *            the origin
+     */
+    async fn get_phrase_as_origin(
+        &self,
+        user: &ObjectId,
+        index: u32,
+    ) -> Result<ObjectId, GrapevineError> {
+        let phrase = self.get_phrase_by_index(index).await?;
+        if self.get_phrase_origin(&phrase).await? != Some(*user) {
+            return Err(GrapevineError::NotPhraseOrigin);
+        }
+        Ok(phrase)
+    }
+
+    /**
+     * Get the origin of a phrase (the first user to prove degree 1)
+     *
+     * @param phrase - the ObjectId of the phrase
+     * @returns - the ObjectId of the origin, or None if no degree 1 proof of the phrase exists
+     */
+    async fn get_phrase_origin(
+        &self,
+        phrase: &ObjectId,
+    ) -> Result<Option<ObjectId>, GrapevineError> {
+        // find the first degree 1 proof made for the phrase
+        let find_options = FindOneOptions::builder()
+            .projection(doc! { "user": 1 })
+            .sort(doc! { "_id": 1 })
+            .build();
+        let filter = doc! { "phrase": phrase, "degree": 1 };
+        match self.degree_proofs.find_one(filter, find_options).await {
+            Ok(Some(proof)) => Ok(proof.user),
+            Ok(None) => Ok(None),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Get a user's active proof of a phrase along with the origin of the phrase, for packaging the
+     * proof into a credential
+     *
+     * @param user - the ObjectId of the user
+     * @param index - the index of the phrase
+     * @returns - the proof oid, degree, and origin username, or DegreeProofNotFound if the user has
+     *            no active proof of the phrase
+     */
+    pub async fn get_phrase_proof(
+        &self,
+        user: &ObjectId,
+        index: u32,
+    ) -> Result<PhraseProofResponse, GrapevineError> {
+        let phrase = self.get_phrase_by_index(index).await?;
+        let find_options = FindOneOptions::builder()
+            .projection(doc! { "_id": 1, "degree": 1 })
+            .build();
+        let filter = doc! { "user": user, "phrase": phrase, "inactive": false };
+        let proof = match self.degree_proofs.find_one(filter, find_options).await {
+            Ok(Some(proof)) => proof,
+            Ok(None) => return Err(GrapevineError::DegreeProofNotFound),
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        // look up the username of the origin
+        let origin = match self.get_phrase_origin(&phrase).await? {
+            Some(origin) => origin,
+            None => return Err(GrapevineError::DegreeProofNotFound),
+        };
+        let find_options = FindOneOptions::builder()
+            .projection(doc! { "username": 1 })
+            .build();
+        let origin = match self
+            .users
+            .find_one(doc! { "_id": origin }, find_options)
+            .await
+        {
+            Ok(Some(user)) => user.username.unwrap(),
+            Ok(None) => return Err(GrapevineError::UserNotFound(origin.to_hex())),
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        Ok(PhraseProofResponse {
+            proof: proof.id.unwrap().to_hex(),
+            degree: proof.degree.unwrap(),
+            origin,
+        })
+    }
+
+    /**
+     * Update the description of a phrase
+     * @notice only the origin of the phrase (the first user to prove degree 1) may update it
+     *
+     * @param user - the ObjectId of the user requesting the update
+     * @param index - the index of the phrase to update
+     * @param description - the new description of the phrase
+     * @returns - Ok if updated, or an error if the phrase is not found or user is not the origin
+     */
+    pub async fn update_phrase_description(
+        &self,
+        user: &ObjectId,
+        index: u32,
+        description: String,
+    ) -> Result<(), GrapevineError> {
+        let phrase = self.get_phrase_as_origin(user, index).await?;
+
+        // update the description
+        let update = doc! { "$set": { "description": description } };
+        match self
+            .phrases
+            .update_one(doc! { "_id": phrase }, update, None)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Replace the tags of a phrase
+     * @notice only the origin of the phrase (the first user to prove degree 1) may update it
+     *
+     * @param user - the ObjectId of the user requesting the update
+     * @param index - the index of the phrase to update
+     * @param tags - the new tags of the phrase
+     * @returns - Ok if updated, or an error if the phrase is not found or user is not the origin
+     */
+    pub async fn update_phrase_tags(
+        &self,
+        user: &ObjectId,
+        index: u32,
+        tags: Vec<String>,
+    ) -> Result<(), GrapevineError> {
+        let phrase = self.get_phrase_as_origin(user, index).await?;
+
+        // replace the tags
+        let update = doc! { "$set": { "tags": tags } };
+        match self
+            .phrases
+            .update_one(doc! { "_id": phrase }, update, None)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Set who can see a phrase
+     * @notice only the origin of the phrase (the first user to prove degree 1) may update it
+     *
+     * @param user - the ObjectId of the user requesting the update
+     * @param index - the index of the phrase to update
+     * @param visibility - the new visibility of the phrase
+     * @returns - Ok if updated, or an error if the phrase is not found or the user is not the origin
+     */
+    pub async fn update_phrase_visibility(
+        &self,
+        user: &ObjectId,
+        index: u32,
+        visibility: PhraseVisibility,
+    ) -> Result<(), GrapevineError> {
+        let phrase = self.get_phrase_as_origin(user, index).await?;
+
+        let visibility = bson::to_bson(&visibility).unwrap();
+        let update = doc! { "$set": { "visibility": visibility } };
+        match self
+            .phrases
+            .update_one(doc! { "_id": phrase }, update, None)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Find how many degrees of separation a user is from a phrase
+     * @notice - a user is as far from a phrase as their own active proof of it, or one degree
+     *           further than the closest active proof made by one of their active relationships
+     *
+     * @param user - the ObjectId of the user
+     * @param phrase - the ObjectId of the phrase
+     * @returns - the degree of separation, or None if neither the user nor a relationship has an
+     *            active proof of the phrase
+     */
+    pub async fn get_degree_to_phrase(
+        &self,
+        user: &ObjectId,
+        phrase: &ObjectId,
+    ) -> Result<Option<u8>, GrapevineError> {
+        let mut users = self.get_active_relationship_recipients(user).await?;
+        users.push(*user);
+        let filter =
+            doc! { "phrase": phrase, "user": { "$in": users }, "inactive": { "$ne": true } };
+        let projection = doc! { "user": 1, "degree": 1 };
+        let find_options = FindOptions::builder().projection(projection).build();
+        let mut cursor = match self.degree_proofs.find(filter, find_options).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        let mut closest: Option<u8> = None;
+        while let Some(result) = cursor.next().await {
+            let proof = match result {
+                Ok(proof) => proof,
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            };
+            let degree = match proof.user == Some(*user) {
+                true => proof.degree.unwrap(),
+                false => proof.degree.unwrap().saturating_add(1),
+            };
+            closest = Some(closest.map_or(degree, |closest| closest.min(degree)));
+        }
+        Ok(closest)
+    }
+
+    /**
+     * Check whether a user may see a phrase under the phrase's visibility
+     *
+     * @param user - the ObjectId of the user
+     * @param phrase - the ObjectId of the phrase
+     * @returns - true if the phrase is public or the user is within its connections-only degree
+     */
+    pub async fn check_phrase_visible(
+        &self,
+        user: &ObjectId,
+        phrase: &ObjectId,
+    ) -> Result<bool, GrapevineError> {
+        let find_options = FindOneOptions::builder()
+            .projection(doc! { "visibility": 1 })
+            .build();
+        let visibility = match self
+            .phrases
+            .find_one(doc! { "_id": phrase }, find_options)
+            .await
+        {
+            Ok(Some(phrase)) => phrase.visibility.unwrap_or_default(),
+            Ok(None) => return Err(GrapevineError::PhraseNotFound),
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        match visibility {
+            PhraseVisibility::Public => Ok(true),
+            PhraseVisibility::Connections(max) => {
+                let degree = self.get_degree_to_phrase(user, phrase).await?;
+                Ok(degree.map_or(false, |degree| degree <= max))
+            }
+        }
+    }
+
+    /**
+     * Replace the encrypted phrase stored on a user's degree 1 proof of a phrase
+     * @notice used to re-encrypt phrases after the user rotates their keypair
+     *
+     * @param user - the ObjectId of the user that made the proof
+     * @param index - the index of the phrase the proof is for
+     * @param ciphertext - the phrase encrypted under the user's new phrase key
+     * @returns - Ok if updated, or an error if the phrase or the user's degree 1 proof is not found
+     */
+    pub async fn update_phrase_ciphertext(
+        &self,
+        user: &ObjectId,
+        index: u32,
+        ciphertext: &[u8; 192],
+    ) -> Result<(), GrapevineError> {
+        let phrase = self.get_phrase_by_index(index).await?;
+        let ciphertext_binary = Binary {
+            subtype: bson::spec::BinarySubtype::Generic,
+            bytes: ciphertext.to_vec(),
+        };
+        let filter = doc! { "user": user, "phrase": phrase, "degree": 1 };
+        let update = doc! { "$set": { "ciphertext": ciphertext_binary } };
+        match self.degree_proofs.update_one(filter, update, None).await {
+            Ok(res) if res.matched_count == 0 => Err(GrapevineError::DegreeProofNotFound),
+            Ok(_) => Ok(()),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Record a commitment to a phrase made by a user
+     *
+     * @param user - the ObjectId of the user committing to a phrase
+     * @param commitment - the phrase commitment
+     * @returns - Ok if recorded, or CommitmentExists if the commitment was already recorded
+     */
+    pub async fn create_phrase_commitment(
+        &self,
+        user: &ObjectId,
+        commitment: &[u8; 32],
+    ) -> Result<(), GrapevineError> {
+        let commitment_bson: Vec<i32> = commitment.iter().map(|x| *x as i32).collect();
+        match self
+            .commitments
+            .find_one(doc! { "commitment": commitment_bson }, None)
+            .await
+        {
+            Ok(Some(_)) => return Err(GrapevineError::CommitmentExists),
+            Ok(None) => (),
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        }
+        let document = PhraseCommitment {
+            id: None,
+            user: Some(*user),
+            commitment: Some(*commitment),
+            committed_at: Some(DateTime::now()),
+            phrase_hash: None,
+            revealed_at: None,
+        };
+        match self.commitments.insert_one(document, None).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Get a phrase commitment made by a user
+     *
+     * @param user - the ObjectId of the user that made the commitment
+     * @param commitment - the phrase commitment
+     * @returns - the commitment document, or CommitmentNotFound if the user did not make it
+     */
+    pub async fn get_phrase_commitment(
+        &self,
+        user: &ObjectId,
+        commitment: &[u8; 32],
+    ) -> Result<PhraseCommitment, GrapevineError> {
+        let commitment_bson: Vec<i32> = commitment.iter().map(|x| *x as i32).collect();
+        let filter = doc! { "user": user, "commitment": commitment_bson };
+        match self.commitments.find_one(filter, None).await {
+            Ok(Some(commitment)) => Ok(commitment),
+            Ok(None) => Err(GrapevineError::CommitmentNotFound),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Record the phrase hash a commitment opened to
+     * @notice assumes the opening was checked against the commitment
+     *
+     * @param commitment - the ObjectId of the commitment document
+     * @param phrase_hash - the hash of the phrase the commitment opened to
+     * @returns - the time the commitment was revealed
+     */
+    pub async fn reveal_phrase_commitment(
+        &self,
+        commitment: &ObjectId,
+        phrase_hash: &[u8; 32],
+    ) -> Result<DateTime, GrapevineError> {
+        let phrase_hash_bson: Vec<i32> = phrase_hash.iter().map(|x| *x as i32).collect();
+        let revealed_at = DateTime::now();
+        let update = doc! {
+            "$set": { "phrase_hash": phrase_hash_bson, "revealed_at": revealed_at }
+        };
+        match self
+            .commitments
+            .update_one(doc! { "_id": commitment }, update, None)
+            .await
+        {
+            Ok(_) => Ok(revealed_at),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Get a batch of degree proofs in ObjectID order, for iterating over the whole collection
+     *
+     * @param after - only return proofs with an ObjectID greater than this (start if none)
+     * @param limit - the maximum number of proofs to return
+     * @returns - the id, degree, and compressed proof of each proof in the batch
+     */
+    pub async fn get_proof_batch(
+        &self,
+        after: Option<ObjectId>,
+        limit: i64,
+    ) -> Result<Vec<DegreeProof>, GrapevineError> {
+        let filter = match after {
+            Some(oid) => doc! { "_id": { "$gt": oid } },
+            None => doc! {},
+        };
+        let find_options = FindOptions::builder()
+            .projection(doc! { "_id": 1, "degree": 1, "proof": 1 })
+            .sort(doc! { "_id": 1 })
+            .limit(limit)
+            .build();
+        let mut cursor = match self.degree_proofs.find(filter, find_options).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        let mut proofs = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(proof) => proofs.push(proof),
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            }
+        }
+        Ok(proofs)
+    }
+
+    /**
+     * Open a cursor over every degree proof document, as stored, in ObjectID order
+     *
+     * @returns - a cursor yielding the raw bson document of each proof
+     */
+    pub async fn get_proof_documents(&self) -> Result<Cursor<Document>, GrapevineError> {
+        let find_options = FindOptions::builder().sort(doc! { "_id": 1 }).build();
+        match self
+            .degree_proofs
+            .clone_with_type::<Document>()
+            .find(doc! {}, find_options)
+            .await
+        {
+            Ok(cursor) => Ok(cursor),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Write a degree proof document read from a backup, replacing any proof with the same ObjectID
+     *
+     * @param document - the raw bson document of the proof
+     */
+    pub async fn restore_proof_document(&self, document: Document) -> Result<(), GrapevineError> {
+        let oid = match document.get_object_id("_id") {
+            Ok(oid) => oid,
+            Err(_) => {
+                return Err(GrapevineError::InvalidBackup(String::from(
+                    "proof document has no ObjectId",
+                )))
+            }
+        };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        match self
+            .degree_proofs
+            .clone_with_type::<Document>()
+            .replace_one(doc! { "_id": oid }, document, options)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Replace the compressed proof stored in a degree proof document
      *
-     * @param sender - the user enabling relationship
-     * @param recipient - the user receiving relationship
-     * @returns
-     *  - 0: true if relationship from sender to user exists
-     *  - 1: true if relationship is active
+     * @param proof - the ObjectID of the degree proof
+     * @param bytes - the new compressed proof
      */
-    pub async fn check_relationship_exists(
+    pub async fn update_proof_bytes(
         &self,
-        sender: &ObjectId,
-        recipient: &ObjectId,
-    ) -> Result<(bool, bool), GrapevineError> {
-        let query = doc! { "recipient": recipient, "sender": sender };
-        let projection = doc! { "_id": 1, "active": 1 };
-        let find_options = FindOneOptions::builder().projection(projection).build();
-
-        match self.relationships.find_one(query, find_options).await {
-            Ok(res) => {
-                let exists = res.is_some();
-                let active = match exists {
-                    true => res.unwrap().active.unwrap(),
-                    false => false,
-                };
-                Ok((exists, active))
-            }
+        proof: ObjectId,
+        bytes: Vec<u8>,
+    ) -> Result<(), GrapevineError> {
+        let binary = Binary {
+            subtype: bson::spec::BinarySubtype::Generic,
+            bytes,
+        };
+        let update = doc! { "$set": { "proof": binary } };
+        match self
+            .degree_proofs
+            .update_one(doc! { "_id": proof }, update, None)
+            .await
+        {
+            Ok(_) => Ok(()),
             Err(e) => Err(GrapevineError::MongoError(e.to_string())),
         }
     }
 
     /**
-     * Creates a new phrase document in the database
-     * @notice assumes that `get_phrase_by_{hash, oid}` has already been called
+     * Find a degree proof by the hash of its compressed bytes
      *
-     * @param phrase_hash - the hash of the phrase to create
-     * @param description - the description of the phrase
-     * @return: (0, 1)
-     *  - 0: the object id of the created phrase document
-     *  - 1: the index of the phrase
+     * @param content_hash - the sha256 hash of the compressed proof
+     * @return - the id, user, and phrase of the proof if one has the hash
      */
-    pub async fn create_phrase(
+    pub async fn get_proof_by_content_hash(
         &self,
-        phrase_hash: [u8; 32],
-        description: String,
-    ) -> Result<(ObjectId, u32), GrapevineError> {
-        // query for the highest phrase id
-        let find_options = FindOneOptions::builder().sort(doc! {"index": -1}).build();
-
-        // Use find_one with options to get the document with the largest phrase_id
-        let index = match self.phrases.find_one(None, find_options).await {
-            Ok(Some(document)) => {
-                let previous_index = document.index.unwrap();
-                previous_index + 1
-            }
-            Ok(None) => 1,
-            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
-        };
-
-        // create new phrase document
-        let phrase = Phrase {
-            id: None,
-            index: Some(index),
-            hash: Some(phrase_hash),
-            description: Some(description),
-        };
-        let oid = match self.phrases.insert_one(&phrase, None).await {
-            Ok(res) => res.inserted_id.as_object_id().unwrap(),
-            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
-        };
-
-        Ok((oid, index))
+        content_hash: &[u8; 32],
+    ) -> Result<Option<DegreeProof>, GrapevineError> {
+        let content_hash_bson: Vec<i32> = content_hash.iter().map(|x| *x as i32).collect();
+        let filter = doc! { "content_hash": content_hash_bson };
+        let projection = doc! { "_id": 1, "user": 1, "phrase": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        match self.degree_proofs.find_one(filter, find_options).await {
+            Ok(proof) => Ok(proof),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
     }
 
     pub async fn add_proof(
@@ -683,6 +2077,319 @@ impl GrapevineDB {
         Ok(proof_oid)
     }
 
+    /**
+     * Get the user that made a degree proof and the degree it proves
+     *
+     * @param proof - the ObjectId of the proof
+     * @returns - the ObjectId of the proof's owner and the proof's degree, or DegreeProofNotFound
+     *            if it does not exist
+     */
+    pub async fn get_proof_owner(
+        &self,
+        proof: &ObjectId,
+    ) -> Result<(ObjectId, u8), GrapevineError> {
+        let filter = doc! { "_id": proof };
+        let projection = doc! { "user": 1, "degree": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        match self
+            .degree_proofs
+            .find_one(filter, Some(find_options))
+            .await
+        {
+            Ok(Some(document)) => Ok((document.user.unwrap(), document.degree.unwrap())),
+            Ok(None) => Err(GrapevineError::DegreeProofNotFound),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Get the compressed bytes of a degree proof owned by a user
+     *
+     * @param user - the ObjectId of the user requesting the proof
+     * @param proof - the ObjectId of the proof to retrieve
+     * @returns - the gzip-compressed proof, NotProofOwner if another user made it, or
+     *            DegreeProofNotFound if it does not exist or has expired
+     */
+    pub async fn get_owned_proof(
+        &self,
+        user: &ObjectId,
+        proof: &ObjectId,
+    ) -> Result<Vec<u8>, GrapevineError> {
+        let filter = doc! { "_id": proof, "expires_at": { "$not": { "$lte": DateTime::now() } } };
+        let projection = doc! { "user": 1, "proof": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        match self
+            .degree_proofs
+            .find_one(filter, Some(find_options))
+            .await
+        {
+            Ok(Some(document)) => match document.user == Some(*user) {
+                true => Ok(document.proof.unwrap()),
+                false => Err(GrapevineError::NotProofOwner),
+            },
+            Ok(None) => Err(GrapevineError::DegreeProofNotFound),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Rank users by a leaderboard metric
+     *
+     * @param metric - the metric to rank users by
+     * @param limit - the number of top users to return
+     * @returns - the usernames and scores of the top users, highest score first (ties broken by
+     *            who joined first)
+     */
+    pub async fn get_leaderboard(
+        &self,
+        metric: LeaderboardMetric,
+        limit: i64,
+    ) -> Result<Vec<LeaderboardEntry>, GrapevineError> {
+        // reduce the collection to one { _id: user, score } document per user
+        let (collection, mut pipeline) = match metric {
+            LeaderboardMetric::Connections => (
+                "relationships",
+                vec![
+                    doc! { "$match": { "active": true } },
+                    doc! { "$group": { "_id": "$recipient", "score": { "$sum": 1 } } },
+                ],
+            ),
+            LeaderboardMetric::Phrases => (
+                "degree_proofs",
+                vec![
+                    doc! { "$match": { "inactive": { "$ne": true } } },
+                    doc! { "$group": { "_id": { "user": "$user", "phrase": "$phrase" } } },
+                    doc! { "$group": { "_id": "$_id.user", "score": { "$sum": 1 } } },
+                ],
+            ),
+            LeaderboardMetric::Reach => (
+                "degree_proofs",
+                vec![
+                    doc! { "$match": { "inactive": { "$ne": true } } },
+                    // the origin of a phrase is the maker of its degree 1 proof
+                    doc! {
+                        "$group": {
+                            "_id": "$phrase",
+                            "users": { "$addToSet": "$user" },
+                            "origin": {
+                                "$max": { "$cond": [{ "$eq": ["$degree", 1] }, "$user", null] }
+                            },
+                        }
+                    },
+                    doc! { "$match": { "origin": { "$ne": null } } },
+                    doc! {
+                        "$group": {
+                            "_id": "$origin",
+                            "score": { "$sum": { "$subtract": [{ "$size": "$users" }, 1] } },
+                        }
+                    },
+                ],
+            ),
+        };
+        pipeline.extend(vec![
+            doc! { "$sort": { "score": -1, "_id": 1 } },
+            doc! { "$limit": limit },
+            // lookup the username of each ranked user
+            doc! {
+                "$lookup": {
+                    "from": "users",
+                    "localField": "_id",
+                    "foreignField": "_id",
+                    "as": "user",
+                    "pipeline": [
+                        doc! { "$project": { "username": 1, "_id": 0 } },
+                    ],
+                }
+            },
+            doc! { "$unwind": "$user" },
+            doc! { "$project": { "username": "$user.username", "score": 1, "_id": 0 } },
+        ]);
+
+        let mut cursor = match self
+            .client
+            .database(&self.database_name)
+            .collection::<Document>(collection)
+            .aggregate(pipeline, None)
+            .await
+        {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        let mut leaderboard: Vec<LeaderboardEntry> = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    // $sum yields an int32 or int64 depending on the size of the total
+                    let score = match document.get("score") {
+                        Some(Bson::Int32(score)) => *score as u64,
+                        Some(Bson::Int64(score)) => *score as u64,
+                        _ => 0,
+                    };
+                    leaderboard.push(LeaderboardEntry {
+                        username: document.get_str("username").unwrap().to_string(),
+                        score,
+                    });
+                }
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            }
+        }
+        Ok(leaderboard)
+    }
+
+    /**
+     * Find the proofs built directly on top of a proof owned by a user
+     *
+     * @param user - the ObjectId of the user requesting the children
+     * @param proof - the ObjectId of the proof to find the children of
+     * @returns - the oid, maker, degree, and inactive flag of each proof whose preceding proof is
+     *            the given proof, or NotProofOwner/DegreeProofNotFound
+     */
+    pub async fn get_proceeding(
+        &self,
+        user: &ObjectId,
+        proof: &ObjectId,
+    ) -> Result<Vec<ProofChild>, GrapevineError> {
+        // only the maker of a proof can see who built on it
+        let projection = doc! { "user": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        match self
+            .degree_proofs
+            .find_one(doc! { "_id": proof }, Some(find_options))
+            .await
+        {
+            Ok(Some(document)) if document.user != Some(*user) => {
+                return Err(GrapevineError::NotProofOwner)
+            }
+            Ok(Some(_)) => (),
+            Ok(None) => return Err(GrapevineError::DegreeProofNotFound),
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+
+        let pipeline = vec![
+            // find every proof built on the given proof
+            doc! { "$match": { "preceding": proof } },
+            doc! { "$sort": { "_id": 1 } },
+            // lookup the username of the maker of each proof
+            doc! {
+                "$lookup": {
+                    "from": "users",
+                    "localField": "user",
+                    "foreignField": "_id",
+                    "as": "user",
+                    "pipeline": [
+                        doc! { "$project": { "username": 1, "_id": 0 } },
+                    ],
+                }
+            },
+            doc! { "$unwind": "$user" },
+            doc! {
+                "$project": {
+                    "_id": 1,
+                    "degree": 1,
+                    "inactive": 1,
+                    "username": "$user.username",
+                }
+            },
+        ];
+        let mut cursor = match self.degree_proofs.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        let mut children: Vec<ProofChild> = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => children.push(ProofChild {
+                    oid: document.get_object_id("_id").unwrap().to_hex(),
+                    username: document.get_str("username").unwrap().to_string(),
+                    degree: document.get_i32("degree").unwrap() as u8,
+                    inactive: document.get_bool("inactive").unwrap_or(false),
+                }),
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            }
+        }
+        Ok(children)
+    }
+
+    /**
+     * Deactivate a degree proof owned by a user and flag all proofs built on top of it
+     * @notice proofs are not deleted since the chain links are still needed by add_proof
+     *
+     * @param user - the ObjectId of the user requesting the deactivation
+     * @param proof - the ObjectId of the proof to deactivate
+     * @returns - the number of downstream proofs flagged as inactive
+     */
+    pub async fn deactivate_proof(
+        &self,
+        user: &ObjectId,
+        proof: &ObjectId,
+    ) -> Result<u64, GrapevineError> {
+        // check that the proof exists and is owned by the user
+        let projection = doc! { "_id": 1, "user": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        match self
+            .degree_proofs
+            .find_one(doc! { "_id": proof }, Some(find_options))
+            .await
+        {
+            Ok(Some(document)) => match document.user == Some(*user) {
+                true => (),
+                false => return Err(GrapevineError::NotProofOwner),
+            },
+            Ok(None) => return Err(GrapevineError::DegreeProofNotFound),
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+
+        // find all proofs proceeding from this proof
+        let mut cursor = self
+            .degree_proofs
+            .aggregate(
+                vec![
+                    doc! { "$match": { "_id": proof } },
+                    doc! {
+                      "$graphLookup": {
+                        "from": "degree_proofs",
+                        "startWith": "$proceeding",
+                        "connectFromField": "proceeding",
+                        "connectToField": "_id",
+                        "as": "proceeding_chain",
+                      }
+                    },
+                    doc! { "$project": { "_id": 0, "proceeding_chain": "$proceeding_chain._id" } },
+                ],
+                None,
+            )
+            .await
+            .unwrap();
+        let mut dependents: Vec<ObjectId> = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    if let Ok(chain) = document.get_array("proceeding_chain") {
+                        dependents.extend(chain.iter().filter_map(|oid| oid.as_object_id()));
+                    }
+                }
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            }
+        }
+
+        // flag the proof and all dependents as inactive
+        let mut flagged = dependents.clone();
+        flagged.push(*proof);
+        let filter = doc! { "_id": { "$in": flagged.clone() } };
+        let update = doc! { "$set": { "inactive": true } };
+        if let Err(e) = self.degree_proofs.update_many(filter, update, None).await {
+            return Err(GrapevineError::MongoError(e.to_string()));
+        }
+
+        // remove the flagged proofs from their owners' list of degree proofs
+        let filter = doc! { "degree_proofs": { "$in": flagged.clone() } };
+        let update = doc! { "$pull": { "degree_proofs": { "$in": flagged } } };
+        match self.users.update_many(filter, update, None).await {
+            Ok(_) => Ok(dependents.len() as u64),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
     // pub async fn get_proof(&self, proof_oid: &ObjectId) -> Option<DegreeProof> {
     //     self.degree_proofs
     //         .find_one(doc! { "_id": proof_oid }, None)
@@ -701,10 +2408,19 @@ impl GrapevineDB {
      * Given a user, find available degrees of separation proofs they can build from
      *   - find degree chains they are not a part of
      *   - find lower degree proofs they can build from
+     *
+     * @param username - the username of the user to find available proofs for
+     * @param origin - if set, only chains from phrases this user created (made the first degree 1
+     *                 proof of), shortest first
+     * @returns - the stringified OIDs of the proofs the user can build from
      */
-    pub async fn find_available_degrees(&self, username: String) -> Vec<String> {
+    pub async fn find_available_degrees(
+        &self,
+        username: String,
+        origin: Option<ObjectId>,
+    ) -> Vec<String> {
         // find degree chains they are not a part of
-        let pipeline = vec![
+        let mut pipeline = vec![
             // find the user to find available proofs for
             doc! { "$match": { "username": username } },
             doc! { "$project": { "relationships": 1, "degree_proofs": 1, "_id": 0 } },
@@ -737,7 +2453,12 @@ impl GrapevineDB {
                     "foreignField": "user",
                     "as": "relationshipDegreeProofs",
                     "pipeline": [
-                        doc! { "$match": { "inactive": { "$ne": true } } },
+                        doc! {
+                            "$match": {
+                                "inactive": { "$ne": true },
+                                "expires_at": { "$not": { "$lte": DateTime::now() } }
+                            }
+                        },
                         doc! { "$project": { "degree": 1, "phrase": 1 } }
                     ]
                 }
@@ -745,6 +2466,8 @@ impl GrapevineDB {
             // unwind the results
             doc! { "$project": { "userDegreeProofs": 1, "relationshipDegreeProofs": 1 } },
             doc! { "$unwind": "$relationshipDegreeProofs" },
+            // order by degree so the first proof of each chain is the lowest degree one
+            doc! { "$sort": { "relationshipDegreeProofs.degree": 1, "relationshipDegreeProofs._id": 1 } },
             // find the lowest degree proof in each chain from relationship proofs and reference user proofs in this chain if exists
             doc! {
                 "$group": {
@@ -773,11 +2496,41 @@ impl GrapevineDB {
                             { "$eq": ["$userProof", null] }
                         ]
                     }
-                }
-            },
-            // project only the ids of the proofs the user can build from
-            doc! { "$project": { "_id": "$originalId" } },
-        ];
+                }
+            },
+        ];
+        if let Some(origin) = origin {
+            pipeline.extend([
+                // find the first degree 1 proof made for each phrase
+                doc! {
+                    "$lookup": {
+                        "from": "degree_proofs",
+                        "let": { "phrase": "$_id" },
+                        "pipeline": [
+                            {
+                                "$match": {
+                                    "$expr": {
+                                        "$and": [
+                                            { "$eq": ["$phrase", "$$phrase"] },
+                                            { "$eq": ["$degree", 1] }
+                                        ]
+                                    }
+                                }
+                            },
+                            { "$sort": { "_id": 1 } },
+                            { "$limit": 1 },
+                            { "$project": { "_id": 0, "user": 1 } }
+                        ],
+                        "as": "origin"
+                    }
+                },
+                doc! { "$match": { "origin.user": origin } },
+                // shortest chains first
+                doc! { "$sort": { "degree": 1, "_id": 1 } },
+            ]);
+        }
+        // project only the ids of the proofs the user can build from
+        pipeline.push(doc! { "$project": { "_id": "$originalId" } });
         // get the OID's of degree proofs the user can build from
         let mut proofs: Vec<String> = vec![];
         let mut cursor = self.users.aggregate(pipeline, None).await.unwrap();
@@ -886,7 +2639,20 @@ impl GrapevineDB {
     }
 
     // @todo: ask chatgpt for better name
-    pub async fn get_all_degrees(&self, username: String) -> Option<Vec<DegreeData>> {
+    pub async fn get_all_degrees(
+        &self,
+        username: String,
+        degree: Option<u8>,
+        max_degree: Option<u8>,
+    ) -> Option<Vec<DegreeData>> {
+        // only degree proofs (degree > 1), optionally narrowed to an exact or maximum degree
+        let mut conditions = vec![doc! { "$gt": ["$$proof.degree", 1] }];
+        if let Some(degree) = degree {
+            conditions.push(doc! { "$eq": ["$$proof.degree", degree as i32] });
+        }
+        if let Some(max_degree) = max_degree {
+            conditions.push(doc! { "$lte": ["$$proof.degree", max_degree as i32] });
+        }
         let pipeline = vec![
             // get the user to find the proofs of degrees of separation for the user
             doc! { "$match": { "username": username } },
@@ -907,7 +2673,7 @@ impl GrapevineDB {
                         "$filter": {
                           "input": "$proofs",
                           "as": "proof",
-                          "cond": { "$gt": ["$$proof.degree", 1] }
+                          "cond": { "$and": conditions }
                         }
                     },
                 }
@@ -979,91 +2745,510 @@ impl GrapevineDB {
             },
             doc! {
                 "$lookup": {
-                    "from": "users",
-                    "localField": "precedingRelation",
+                    "from": "users",
+                    "localField": "precedingRelation",
+                    "foreignField": "_id",
+                    "as": "precedingRelation",
+                    "pipeline": [doc! { "$project": { "_id": 0, "username": 1 } }]
+                }
+            },
+            doc! {
+                "$project": {
+                    "degree": 1,
+                    "phrase": 1,
+                    "relation": 1,
+                    "precedingRelation": { "$arrayElemAt": ["$precedingRelation.username", 0] },
+                    "_id": 0
+                }
+            },
+            doc! {
+                "$lookup": {
+                    "from": "phrases",
+                    "localField": "phrase",
+                    "foreignField": "_id",
+                    "as": "phrase",
+                    "pipeline": [doc! { "$project": { "index": 1, "hash": 1, "description": 1, "_id": 0 } }]
+                }
+            },
+            doc! {
+                "$unwind": "$phrase"
+            },
+            doc! {
+                "$set": {
+                    "phrase_index": "$phrase.index",
+                    "phrase_hash": "$phrase.hash",
+                    "phrase_description": "$phrase.description"
+                }
+            },
+            doc! {
+                "$project": {
+                    "phrase": 0
+                }
+            },
+            doc! { "$sort": { "degree": 1 }},
+        ];
+        // get the OID's of degree proofs the user can build from
+        let mut degrees: Vec<DegreeData> = vec![];
+        let mut cursor = self.users.aggregate(pipeline, None).await.unwrap();
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    let degree = document.get_i32("degree").unwrap() as u8;
+                    let relation = document
+                        .get("relation")
+                        .unwrap()
+                        .as_str()
+                        .unwrap()
+                        .to_string();
+                    let preceding_relation = match document.get("precedingRelation") {
+                        Some(relation) => Some(relation.as_str().unwrap().to_string()),
+                        None => None,
+                    };
+                    // @todo: can this be retrieved better?
+                    let phrase_hash: [u8; 32] = document
+                        .get("phrase_hash")
+                        .unwrap()
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|x| x.as_i32().unwrap() as u8)
+                        .collect::<Vec<u8>>()
+                        .try_into()
+                        .unwrap();
+                    let phrase_index = document.get_i64("phrase_index").unwrap() as u32;
+                    let phrase_description = document
+                        .get("phrase_description")
+                        .unwrap()
+                        .as_str()
+                        .unwrap()
+                        .to_string();
+                    degrees.push(DegreeData {
+                        description: phrase_description,
+                        degree: Some(degree),
+                        phrase_index,
+                        relation: Some(relation),
+                        preceding_relation,
+                        phrase_hash,
+                        secret_phrase: None,
+                    });
+                }
+                Err(e) => {
+                    println!("Error: {}", e);
+                    return None;
+                }
+            }
+        }
+        Some(degrees)
+    }
+
+    /**
+     * Summarize every phrase a user has an active proof on in a single aggregation
+     *
+     * @param username - the username of the user to summarize phrases for
+     * @return - the index, description, user's degree, and connection count for each phrase,
+     *           sorted by degree ascending
+     */
+    pub async fn get_phrase_summaries(&self, username: String) -> Option<Vec<PhraseSummary>> {
+        let pipeline = vec![
+            // get the user's proofs and relationships
+            doc! { "$match": { "username": username } },
+            doc! { "$project": { "_id": 1, "degree_proofs": 1, "relationships": 1 } },
+            // get the senders of the user's relationships
+            doc! {
+                "$lookup": {
+                    "from": "relationships",
+                    "localField": "relationships",
+                    "foreignField": "_id",
+                    "as": "relationships",
+                    "pipeline": [doc! { "$project": { "_id": 0, "sender": 1 } }]
+                }
+            },
+            // look up the user's active degree proofs
+            doc! {
+                "$lookup": {
+                    "from": "degree_proofs",
+                    "localField": "degree_proofs",
+                    "foreignField": "_id",
+                    "as": "proofs",
+                    "pipeline": [
+                        doc! { "$match": { "inactive": { "$ne": true } } },
+                        doc! { "$project": { "_id": 0, "degree": 1, "phrase": 1 } }
+                    ]
+                }
+            },
+            doc! { "$unwind": "$proofs" },
+            doc! {
+                "$project": {
+                    "_id": 0,
+                    "degree": "$proofs.degree",
+                    "phrase": "$proofs.phrase",
+                    "senders": "$relationships.sender"
+                }
+            },
+            // look up the phrase info
+            doc! {
+                "$lookup": {
+                    "from": "phrases",
+                    "localField": "phrase",
+                    "foreignField": "_id",
+                    "as": "phrase_data",
+                    "pipeline": [doc! { "$project": { "_id": 0, "index": 1, "description": 1 } }]
+                }
+            },
+            doc! { "$unwind": "$phrase_data" },
+            // count the active proofs on the phrase made by relationships
+            doc! {
+                "$lookup": {
+                    "from": "degree_proofs",
+                    "let": { "senders": "$senders", "phrase": "$phrase" },
+                    "pipeline": [
+                        {
+                            "$match": {
+                                "$expr": {
+                                    "$and": [
+                                        { "$in": ["$user", "$$senders"] },
+                                        { "$eq": ["$phrase", "$$phrase"] },
+                                        { "$ne": ["$inactive", true] }
+                                    ]
+                                }
+                            }
+                        },
+                        { "$project": { "_id": 1 } }
+                    ],
+                    "as": "connections"
+                }
+            },
+            doc! {
+                "$project": {
+                    "degree": 1,
+                    "phrase_index": "$phrase_data.index",
+                    "description": "$phrase_data.description",
+                    "connections": { "$size": "$connections" }
+                }
+            },
+            doc! { "$sort": { "degree": 1, "phrase_index": 1 } },
+        ];
+        let mut summaries: Vec<PhraseSummary> = vec![];
+        let mut cursor = match self.users.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => {
+                println!("Error: {}", e);
+                return None;
+            }
+        };
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    let degree = document.get_i32("degree").unwrap() as u8;
+                    let phrase_index = document.get_i64("phrase_index").unwrap() as u32;
+                    let description = document.get_str("description").unwrap().to_string();
+                    let connections = document.get_i32("connections").unwrap() as u64;
+                    summaries.push(PhraseSummary {
+                        phrase_index,
+                        description,
+                        degree,
+                        connections,
+                    });
+                }
+                Err(e) => {
+                    println!("Error: {}", e);
+                    return None;
+                }
+            }
+        }
+        Some(summaries)
+    }
+
+    /**
+     * Find every distinct origin (phrase creator) a user has an active proof back to
+     * @notice - the origin of a phrase is the creator of its first degree 1 proof. The user's own
+     *           phrases are not included
+     *
+     * @param username - the username of the user to find origins for
+     * @return - each origin's username and the lowest degree the user has to any of their phrases,
+     *           sorted by degree ascending
+     */
+    pub async fn get_connected_origins(&self, username: String) -> Option<Vec<ConnectedOrigin>> {
+        let pipeline = vec![
+            // get the user's proofs
+            doc! { "$match": { "username": username } },
+            doc! { "$project": { "_id": 1, "degree_proofs": 1 } },
+            // look up the user's active degree proofs
+            doc! {
+                "$lookup": {
+                    "from": "degree_proofs",
+                    "localField": "degree_proofs",
+                    "foreignField": "_id",
+                    "as": "proofs",
+                    "pipeline": [
+                        doc! { "$match": { "inactive": { "$ne": true } } },
+                        doc! { "$project": { "_id": 0, "degree": 1, "phrase": 1 } }
+                    ]
+                }
+            },
+            doc! { "$unwind": "$proofs" },
+            doc! {
+                "$project": {
+                    "user": "$_id",
+                    "degree": "$proofs.degree",
+                    "phrase": "$proofs.phrase"
+                }
+            },
+            // find the first degree 1 proof made for each phrase
+            doc! {
+                "$lookup": {
+                    "from": "degree_proofs",
+                    "let": { "phrase": "$phrase" },
+                    "pipeline": [
+                        {
+                            "$match": {
+                                "$expr": {
+                                    "$and": [
+                                        { "$eq": ["$phrase", "$$phrase"] },
+                                        { "$eq": ["$degree", 1] }
+                                    ]
+                                }
+                            }
+                        },
+                        { "$sort": { "_id": 1 } },
+                        { "$limit": 1 },
+                        { "$project": { "_id": 0, "user": 1 } }
+                    ],
+                    "as": "origin"
+                }
+            },
+            doc! { "$unwind": "$origin" },
+            // skip the user's own phrases
+            doc! { "$match": { "$expr": { "$ne": ["$origin.user", "$user"] } } },
+            // look up the username of the origin
+            doc! {
+                "$lookup": {
+                    "from": "users",
+                    "localField": "origin.user",
+                    "foreignField": "_id",
+                    "as": "origin",
+                    "pipeline": [doc! { "$project": { "_id": 0, "username": 1 } }]
+                }
+            },
+            doc! { "$unwind": "$origin" },
+            // keep the lowest degree to each origin
+            doc! {
+                "$group": {
+                    "_id": "$origin.username",
+                    "min_degree": { "$min": "$degree" }
+                }
+            },
+            doc! { "$sort": { "min_degree": 1, "_id": 1 } },
+        ];
+        let mut origins: Vec<ConnectedOrigin> = vec![];
+        let mut cursor = match self.users.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => {
+                println!("Error: {}", e);
+                return None;
+            }
+        };
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    let origin_username = document.get_str("_id").unwrap().to_string();
+                    let min_degree = document.get_i32("min_degree").unwrap() as u8;
+                    origins.push(ConnectedOrigin {
+                        origin_username,
+                        min_degree,
+                    });
+                }
+                Err(e) => {
+                    println!("Error: {}", e);
+                    return None;
+                }
+            }
+        }
+        Some(origins)
+    }
+
+    /**
+     * Search the descriptions of phrases visible to a user for a case-insensitive substring
+     * @notice - a phrase is visible if the user or one of their active relationships has an active
+     *           proof on it
+     *
+     * @param username - the username of the user searching
+     * @param query - the text to search phrase descriptions for
+     * @param limit - the maximum number of phrases to return
+     * @return - the index, description, and hash of each matching phrase sorted by index
+     */
+    pub async fn search_phrases(
+        &self,
+        username: String,
+        query: &str,
+        limit: i64,
+    ) -> Option<Vec<PhraseSearchResult>> {
+        let matching = doc! {
+            "$expr": {
+                "$gte": [
+                    { "$indexOfCP": [{ "$toLower": "$description" }, query.to_lowercase()] },
+                    0
+                ]
+            }
+        };
+        self.get_visible_phrases(username, matching, limit).await
+    }
+
+    /**
+     * Get the phrases visible to a user that are listed under a tag
+     * @notice - a phrase is visible if the user or one of their active relationships has an active
+     *           proof on it
+     *
+     * @param username - the username of the user browsing
+     * @param tag - the tag to list phrases for
+     * @param limit - the maximum number of phrases to return
+     * @return - the index, description, hash, and tags of each phrase sorted by index
+     */
+    pub async fn get_phrases_by_tag(
+        &self,
+        username: String,
+        tag: &str,
+        limit: i64,
+    ) -> Option<Vec<PhraseSearchResult>> {
+        self.get_visible_phrases(username, doc! { "tags": tag }, limit)
+            .await
+    }
+
+    /**
+     * Get the phrases visible to a user that match a filter
+     *
+     * @param username - the username of the user the phrases must be visible to
+     * @param matching - the filter phrase documents must match
+     * @param limit - the maximum number of phrases to return
+     * @return - the index, description, hash, and tags of each phrase sorted by index
+     */
+    async fn get_visible_phrases(
+        &self,
+        username: String,
+        matching: Document,
+        limit: i64,
+    ) -> Option<Vec<PhraseSearchResult>> {
+        let pipeline = vec![
+            // get the user's relationships
+            doc! { "$match": { "username": username } },
+            doc! { "$project": { "_id": 1, "relationships": 1 } },
+            doc! {
+                "$lookup": {
+                    "from": "relationships",
+                    "localField": "relationships",
                     "foreignField": "_id",
-                    "as": "precedingRelation",
-                    "pipeline": [doc! { "$project": { "_id": 0, "username": 1 } }]
+                    "as": "relationships",
+                    "pipeline": [doc! { "$project": { "_id": 0, "sender": 1 } }]
                 }
             },
+            // look up the active proofs made by the user or their relationships
             doc! {
                 "$project": {
-                    "degree": 1,
-                    "phrase": 1,
-                    "relation": 1,
-                    "precedingRelation": { "$arrayElemAt": ["$precedingRelation.username", 0] },
-                    "_id": 0
+                    "users": { "$concatArrays": [["$_id"], "$relationships.sender"] }
                 }
             },
             doc! {
                 "$lookup": {
-                    "from": "phrases",
-                    "localField": "phrase",
-                    "foreignField": "_id",
-                    "as": "phrase",
-                    "pipeline": [doc! { "$project": { "index": 1, "hash": 1, "description": 1, "_id": 0 } }]
-                }
-            },
-            doc! {
-                "$unwind": "$phrase"
-            },
-            doc! {
-                "$set": {
-                    "phrase_index": "$phrase.index",
-                    "phrase_hash": "$phrase.hash",
-                    "phrase_description": "$phrase.description"
+                    "from": "degree_proofs",
+                    "let": { "user": "$_id", "users": "$users" },
+                    "pipeline": [
+                        {
+                            "$match": {
+                                "$expr": {
+                                    "$and": [
+                                        { "$in": ["$user", "$$users"] },
+                                        { "$ne": ["$inactive", true] }
+                                    ]
+                                }
+                            }
+                        },
+                        // the user is one degree further than their relationships' proofs
+                        {
+                            "$project": {
+                                "_id": 0,
+                                "phrase": 1,
+                                "degree": {
+                                    "$cond": [
+                                        { "$eq": ["$user", "$$user"] },
+                                        "$degree",
+                                        { "$add": ["$degree", 1] }
+                                    ]
+                                }
+                            }
+                        }
+                    ],
+                    "as": "proofs"
                 }
             },
+            // find the closest the user is to each phrase
+            doc! { "$unwind": "$proofs" },
+            doc! { "$group": { "_id": "$proofs.phrase", "degree": { "$min": "$proofs.degree" } } },
+            // find the visible phrases that match
             doc! {
-                "$project": {
-                    "phrase": 0
+                "$lookup": {
+                    "from": "phrases",
+                    "let": { "phrase": "$_id", "degree": "$degree" },
+                    "as": "phrase",
+                    "pipeline": [
+                        { "$match": { "$expr": { "$eq": ["$_id", "$$phrase"] } } },
+                        { "$match": matching },
+                        // connections-only phrases are hidden from users too far from them
+                        {
+                            "$match": {
+                                "$expr": {
+                                    "$or": [
+                                        { "$eq": [{ "$type": "$visibility.connections" }, "missing"] },
+                                        { "$gte": ["$visibility.connections", "$$degree"] }
+                                    ]
+                                }
+                            }
+                        },
+                        {
+                            "$project": {
+                                "_id": 0, "index": 1, "description": 1, "hash": 1, "tags": 1
+                            }
+                        }
+                    ]
                 }
             },
-            doc! { "$sort": { "degree": 1 }},
+            doc! { "$unwind": "$phrase" },
+            doc! { "$replaceRoot": { "newRoot": "$phrase" } },
+            doc! { "$sort": { "index": 1 } },
+            doc! { "$limit": limit },
         ];
-        // get the OID's of degree proofs the user can build from
-        let mut degrees: Vec<DegreeData> = vec![];
-        let mut cursor = self.users.aggregate(pipeline, None).await.unwrap();
+        let mut phrases: Vec<PhraseSearchResult> = vec![];
+        let mut cursor = match self.users.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => {
+                println!("Error: {}", e);
+                return None;
+            }
+        };
         while let Some(result) = cursor.next().await {
             match result {
                 Ok(document) => {
-                    let degree = document.get_i32("degree").unwrap() as u8;
-                    let relation = document
-                        .get("relation")
-                        .unwrap()
-                        .as_str()
-                        .unwrap()
-                        .to_string();
-                    let preceding_relation = match document.get("precedingRelation") {
-                        Some(relation) => Some(relation.as_str().unwrap().to_string()),
-                        None => None,
-                    };
-                    // @todo: can this be retrieved better?
                     let phrase_hash: [u8; 32] = document
-                        .get("phrase_hash")
-                        .unwrap()
-                        .as_array()
+                        .get_array("hash")
                         .unwrap()
                         .iter()
                         .map(|x| x.as_i32().unwrap() as u8)
                         .collect::<Vec<u8>>()
                         .try_into()
                         .unwrap();
-                    let phrase_index = document.get_i64("phrase_index").unwrap() as u32;
-                    let phrase_description = document
-                        .get("phrase_description")
-                        .unwrap()
-                        .as_str()
-                        .unwrap()
-                        .to_string();
-                    degrees.push(DegreeData {
-                        description: phrase_description,
-                        degree: Some(degree),
+                    let phrase_index = document.get_i64("index").unwrap() as u32;
+                    let description = document.get_str("description").unwrap().to_string();
+                    let tags = match document.get_array("tags") {
+                        Ok(tags) => tags
+                            .iter()
+                            .filter_map(|tag| tag.as_str().map(String::from))
+                            .collect(),
+                        Err(_) => vec![],
+                    };
+                    phrases.push(PhraseSearchResult {
                         phrase_index,
-                        relation: Some(relation),
-                        preceding_relation,
+                        description,
                         phrase_hash,
-                        secret_phrase: None,
+                        tags,
                     });
                 }
                 Err(e) => {
@@ -1072,31 +3257,26 @@ impl GrapevineDB {
                 }
             }
         }
-        Some(degrees)
+        Some(phrases)
     }
 
     /**
-     * Get a proof from the server with all info needed to prove a degree of separation as a given user
+     * Get a proof from the server with the info about its phrase and creator needed to prove a
+     * degree of separation on top of it
      *
-     * @param username - the username of the user proving a degree of separation
-     * @param oid - the id of the proof to get
+     * @param proof - the id of the proof to get
+     * @return - the proof data that is the same for every user requesting it
      */
-    pub async fn get_proof_and_data(
-        &self,
-        username: String,
-        proof: ObjectId,
-    ) -> Option<ProvingData> {
-        // @todo: aggregation pipeline
-        // get the proof
-        let filter = doc! { "_id": proof };
-        let projection = doc! { "user": 1, "degree": 1, "proof": 1, "phrase": 1 };
+    pub async fn get_proof_details(&self, proof: ObjectId) -> Option<CachedProof> {
+        // get the proof if it has not expired
+        let filter = doc! { "_id": proof, "expires_at": { "$not": { "$lte": DateTime::now() } } };
+        let projection = doc! { "user": 1, "degree": 1, "proof": 1, "phrase": 1, "expires_at": 1 };
         let find_options = FindOneOptions::builder().projection(projection).build();
         let proof = self
             .degree_proofs
             .find_one(filter, Some(find_options))
             .await
-            .unwrap()
-            .unwrap();
+            .ok()??;
         // look up the phrase info
         let filter = doc! { "_id": proof.phrase.unwrap() };
         let projection = doc! { "index": 1, "hash": 1, "description": 1 };
@@ -1105,8 +3285,7 @@ impl GrapevineDB {
             .phrases
             .find_one(filter, Some(find_options))
             .await
-            .unwrap()
-            .unwrap();
+            .ok()??;
         // get the username of the user who made the proof
         let proof_creator = proof.user.unwrap();
         let filter = doc! { "_id": proof_creator };
@@ -1116,10 +3295,34 @@ impl GrapevineDB {
             .users
             .find_one(filter, Some(find_options))
             .await
-            .unwrap()
-            .unwrap()
+            .ok()??
             .username
             .unwrap();
+
+        Some(CachedProof {
+            creator: proof_creator,
+            username: proof_creator_username,
+            description: phrase.description.unwrap(),
+            phrase_index: phrase.index.unwrap(),
+            phrase_hash: phrase.hash.unwrap(),
+            degree: proof.degree.unwrap(),
+            proof: proof.proof.unwrap(),
+            expires_at: proof.expires_at,
+        })
+    }
+
+    /**
+     * Get the encrypted auth secret a proof creator shared with a given user
+     *
+     * @param username - the username of the user proving a degree of separation
+     * @param creator - the id of the user that created the proof being built on
+     * @return - the ephemeral key and ciphertext of the auth secret if the relationship exists
+     */
+    pub async fn get_relationship_keys(
+        &self,
+        username: String,
+        creator: ObjectId,
+    ) -> Option<([u8; 32], [u8; 48])> {
         // get the oid of message sender
         let filter = doc! { "username": username };
         let projection = doc! { "_id": 1, "pubkey": 1 };
@@ -1128,32 +3331,70 @@ impl GrapevineDB {
             .users
             .find_one(filter, Some(find_options))
             .await
-            .unwrap()
-            .unwrap()
+            .ok()??
             .id
             .unwrap();
         // look up relationship with sender and recipient
-        let filter = doc! { "sender": proof_creator, "recipient": caller };
+        let filter = doc! { "sender": creator, "recipient": caller };
         let projection = doc! { "ephemeral_key": 1, "ciphertext": 1};
         let find_options = FindOneOptions::builder().projection(projection).build();
         let relationship = self
             .relationships
             .find_one(filter, Some(find_options))
             .await
-            .unwrap()
-            .unwrap();
+            .ok()??;
+        Some((
+            relationship.ephemeral_key.unwrap(),
+            relationship.ciphertext.unwrap(),
+        ))
+    }
 
-        // return the proof data
-        Some(ProvingData {
-            description: phrase.description.unwrap(),
-            phrase_index: phrase.index.unwrap(),
-            phrase_hash: phrase.hash.unwrap(),
-            degree: proof.degree.unwrap(),
-            proof: proof.proof.unwrap(),
-            username: proof_creator_username,
-            ephemeral_key: relationship.ephemeral_key.unwrap(),
-            ciphertext: relationship.ciphertext.unwrap(),
-        })
+    /**
+     * Get every degree proof along with the hash of the phrase it references
+     * @notice - loads every proof into memory, only meant for administrative integrity scans
+     *
+     * @returns - each degree proof document paired with the hash of its phrase
+     */
+    pub async fn get_all_proofs_with_phrase_hash(
+        &self,
+    ) -> Result<Vec<(DegreeProof, [u8; 32])>, GrapevineError> {
+        // map phrase ObjectIDs to their hashes
+        let projection = doc! { "_id": 1, "hash": 1 };
+        let find_options = FindOptions::builder().projection(projection).build();
+        let mut phrase_hashes: HashMap<ObjectId, [u8; 32]> = HashMap::new();
+        let mut cursor = match self.phrases.find(None, Some(find_options)).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(phrase) => {
+                    phrase_hashes.insert(phrase.id.unwrap(), phrase.hash.unwrap());
+                }
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            }
+        }
+
+        // pair each proof with the hash of its phrase
+        let mut proofs = vec![];
+        let mut cursor = match self.degree_proofs.find(None, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(proof) => {
+                    // a proof referencing a missing phrase can never match, so use an empty hash
+                    let hash = proof
+                        .phrase
+                        .and_then(|phrase| phrase_hashes.get(&phrase).copied())
+                        .unwrap_or([0u8; 32]);
+                    proofs.push((proof, hash));
+                }
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            }
+        }
+        Ok(proofs)
     }
 
     /**
@@ -1162,7 +3403,7 @@ impl GrapevineDB {
        - # of second degree connections
        - # of phrases created
     */
-    pub async fn get_account_details(&self, user: &ObjectId) -> Option<(u64, u64, u64)> {
+    pub async fn get_account_details(&self, user: &ObjectId) -> Option<AccountDetailsResponse> {
         let mut cursor = self
             .users
             .aggregate(
@@ -1318,11 +3559,11 @@ impl GrapevineDB {
                 let phrase_count = stats.get_i32("phrase_count").unwrap();
                 let first_degree_connections = stats.get_i32("first_degree_connections").unwrap();
                 let second_degree_connections = stats.get_i32("second_degree_connections").unwrap();
-                return Some((
-                    phrase_count as u64,
-                    first_degree_connections as u64,
-                    second_degree_connections as u64,
-                ));
+                return Some(AccountDetailsResponse {
+                    phrase_count: phrase_count as u64,
+                    first_degree_count: first_degree_connections as u64,
+                    second_degree_count: second_degree_connections as u64,
+                });
             }
             Err(e) => {
                 println!("Error: {:?}", e);
@@ -1331,6 +3572,213 @@ impl GrapevineDB {
         }
     }
 
+    /**
+     * Aggregate a user's account details and cache them on the user document
+     *
+     * @param user - the ObjectID of the user to recompute stats for
+     * @return - the freshly aggregated account details
+     */
+    pub async fn recompute_stats(
+        &self,
+        user: &ObjectId,
+    ) -> Result<AccountDetailsResponse, GrapevineError> {
+        let details = match self.get_account_details(user).await {
+            Some(details) => details,
+            None => {
+                return Err(GrapevineError::MongoError(String::from(
+                    "Error aggregating account details",
+                )))
+            }
+        };
+        let stats = UserStats {
+            phrase_count: details.phrase_count,
+            first_degree_count: details.first_degree_count,
+            second_degree_count: details.second_degree_count,
+            computed_at: DateTime::now(),
+        };
+        let stats = match bson::to_bson(&stats) {
+            Ok(stats) => stats,
+            Err(e) => return Err(GrapevineError::SerdeError(e.to_string())),
+        };
+        let filter = doc! { "_id": user };
+        let update = doc! { "$set": { "stats": stats } };
+        match self.users.update_one(filter, update, None).await {
+            Ok(_) => Ok(details),
+            Err(e) => Err(GrapevineError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Get the ObjectIDs of every user
+     *
+     * @return - the ObjectIDs of all users sorted by creation
+     */
+    pub async fn get_user_ids(&self) -> Result<Vec<ObjectId>, GrapevineError> {
+        let find_options = FindOptions::builder()
+            .projection(doc! { "_id": 1 })
+            .sort(doc! { "_id": 1 })
+            .build();
+        let mut cursor = match self
+            .users
+            .clone_with_type::<Document>()
+            .find(None, Some(find_options))
+            .await
+        {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        let mut oids = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => oids.push(document.get_object_id("_id").unwrap()),
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            }
+        }
+        Ok(oids)
+    }
+
+    /**
+     * Find relationships whose references on users are out of sync with the relationships
+     * collection: OIDs in a user's relationships that have no relationship document, and active
+     * relationships missing from their recipient's relationships
+     * @notice a failed or partial write (before activation was transactional) can leave these
+     *
+     * @return - the OIDs of the orphaned relationships
+     */
+    pub async fn find_orphaned_relationships(&self) -> Result<Vec<ObjectId>, GrapevineError> {
+        let mut orphaned = self.find_dangling_relationship_refs().await?;
+        let unreferenced = self.find_unreferenced_relationships().await?;
+        orphaned.extend(
+            unreferenced
+                .into_iter()
+                .map(|(relationship, _)| relationship),
+        );
+        Ok(orphaned)
+    }
+
+    /**
+     * Repair orphaned relationships by removing references to missing relationship documents and
+     * adding active relationships back to their recipient's relationships
+     *
+     * @return - the OIDs of the relationships that were repaired
+     */
+    pub async fn repair_orphaned_relationships(&self) -> Result<Vec<ObjectId>, GrapevineError> {
+        let mongo_error = |e: mongodb::error::Error| GrapevineError::MongoError(e.to_string());
+        // remove references to relationships that do not exist
+        let mut repaired = self.find_dangling_relationship_refs().await?;
+        if !repaired.is_empty() {
+            let filter = doc! { "relationships": { "$in": repaired.clone() } };
+            let update = doc! { "$pull": { "relationships": { "$in": repaired.clone() } } };
+            self.users
+                .update_many(filter, update, None)
+                .await
+                .map_err(mongo_error)?;
+        }
+        // reference active relationships from their recipient
+        for (relationship, recipient) in self.find_unreferenced_relationships().await? {
+            let update = doc! { "$addToSet": { "relationships": relationship } };
+            self.users
+                .update_one(doc! { "_id": recipient }, update, None)
+                .await
+                .map_err(mongo_error)?;
+            repaired.push(relationship);
+        }
+        Ok(repaired)
+    }
+
+    /**
+     * Find relationship OIDs referenced by users that are missing from the relationships collection
+     *
+     * @return - the OIDs of the missing relationships
+     */
+    async fn find_dangling_relationship_refs(&self) -> Result<Vec<ObjectId>, GrapevineError> {
+        let pipeline = vec![
+            doc! { "$project": { "relationships": 1 } },
+            doc! { "$unwind": "$relationships" },
+            doc! {
+                "$lookup": {
+                    "from": "relationships",
+                    "localField": "relationships",
+                    "foreignField": "_id",
+                    "as": "relationship",
+                    "pipeline": [doc! { "$project": { "_id": 1 } }]
+                }
+            },
+            doc! { "$match": { "relationship": { "$size": 0 } } },
+            doc! { "$group": { "_id": "$relationships" } },
+            doc! { "$sort": { "_id": 1 } },
+        ];
+        let mut cursor = match self.users.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        let mut dangling = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => dangling.push(document.get_object_id("_id").unwrap()),
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            }
+        }
+        Ok(dangling)
+    }
+
+    /**
+     * Find active relationships that are not referenced by their recipient
+     *
+     * @return - (relationship, recipient) for each unreferenced relationship
+     */
+    async fn find_unreferenced_relationships(
+        &self,
+    ) -> Result<Vec<(ObjectId, ObjectId)>, GrapevineError> {
+        let pipeline = vec![
+            doc! { "$match": { "active": true } },
+            // look up the recipient if they reference the relationship
+            doc! {
+                "$lookup": {
+                    "from": "users",
+                    "let": { "relationship": "$_id", "recipient": "$recipient" },
+                    "as": "referenced_by",
+                    "pipeline": [
+                        doc! {
+                            "$match": {
+                                "$expr": {
+                                    "$and": [
+                                        { "$eq": ["$_id", "$$recipient"] },
+                                        {
+                                            "$in": [
+                                                "$$relationship",
+                                                { "$ifNull": ["$relationships", []] }
+                                            ]
+                                        }
+                                    ]
+                                }
+                            }
+                        },
+                        doc! { "$project": { "_id": 1 } }
+                    ]
+                }
+            },
+            doc! { "$match": { "referenced_by": { "$size": 0 } } },
+            doc! { "$project": { "recipient": 1 } },
+            doc! { "$sort": { "_id": 1 } },
+        ];
+        let mut cursor = match self.relationships.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        let mut unreferenced = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => unreferenced.push((
+                    document.get_object_id("_id").unwrap(),
+                    document.get_object_id("recipient").unwrap(),
+                )),
+                Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+            }
+        }
+        Ok(unreferenced)
+    }
+
     /**
      * Get chain of degree proofs linked to a phrase
      *
@@ -1341,83 +3789,12 @@ impl GrapevineDB {
         username: String,
         phrase_index: u32,
     ) -> Option<(u64, Vec<u64>)> {
-        let mut cursor = self
-            .users
-            .aggregate(
-                vec![
-                    // Step 1: get relationships of the user
-                    doc! { "$match": { "username": username } },
-                    doc! { "$unwind": "$relationships" },
-                    doc! {
-                        "$lookup": {
-                            "from": "relationships",
-                            "localField": "relationships",
-                            "foreignField": "_id",
-                            "as": "relationship_details"
-                        }
-                    },
-                    doc! {
-                        "$unwind": "$relationship_details"
-                    },
-                    // step 2: ensure unique senders
-                    doc! {
-                        "$group": {
-                            "_id": null,
-                            "senders": {
-                                "$addToSet": "$relationship_details.sender"
-                            }
-                        }
-                    },
-                    doc! { "$project": { "_id": 0, "senders": 1 } },
-                    // step 3: look up the phrase document by index
-                    doc! {
-                        "$lookup": {
-                            "from": "phrases",
-                            "let": { "index": phrase_index },
-                            "pipeline": [
-                                { "$match": { "$expr": { "$eq": ["$index", "$$index"] } } },
-                                { "$project": { "_id": 1 } }
-                            ],
-                            "as": "phrase_document"
-                        }
-                    },
-                    doc! { "$unwind": "$phrase_document" },
-                    // step 4: find all active degree proofs for the phrase made by relationships
-                    doc! {
-                        "$lookup": {
-                            "from": "degree_proofs",
-                            "let": { "senders": "$senders", "phrase": "$phrase_document._id" },
-                            "pipeline": [
-                                {
-                                    "$match": {
-                                        "$expr": {
-                                            "$and": [
-                                                { "$in": ["$user", "$$senders"] },
-                                                { "$eq": ["$phrase", "$$phrase"] },
-                                                { "$ne": ["$inactive", true] }
-                                            ]
-                                        }
-                                    }
-                                },
-                                { "$project": { "_id": 0, "degree": 1 } }
-                            ],
-                            "as": "degree_proofs"
-                        }
-                    },
-                    doc! { "$unwind": "$degree_proofs" },
-                    doc! {
-                        "$group": {
-                            "_id": null,
-                            "max_degree": { "$max": "$degree_proofs.degree" },
-                            "count": { "$sum": 1 },
-                            "degrees": { "$push": "$degree_proofs.degree" }
-                        }
-                    },
-                ],
-                None,
-            )
-            .await
-            .unwrap();
+        let mut pipeline = vec![
+            // Step 1: get relationships of the user
+            doc! { "$match": { "username": username } },
+        ];
+        pipeline.extend(Self::phrase_connections_stages(phrase_index));
+        let mut cursor = self.users.aggregate(pipeline, None).await.unwrap();
 
         let cursor_res = cursor.next().await;
 
@@ -1426,21 +3803,7 @@ impl GrapevineDB {
         }
 
         match cursor_res.unwrap() {
-            Ok(connection_data) => {
-                let total_count = connection_data.get_i32("count").unwrap();
-                let max_degree = connection_data.get_i32("max_degree").unwrap();
-                let mut degree_counts: Vec<u64> = vec![0; max_degree as usize];
-                let degrees: Vec<i32> = connection_data
-                    .get_array("degrees")
-                    .unwrap()
-                    .iter()
-                    .map(|d| d.as_i32().unwrap())
-                    .collect();
-                for degree in degrees {
-                    degree_counts[(degree - 1) as usize] += 1;
-                }
-                return Some((total_count as u64, degree_counts));
-            }
+            Ok(connection_data) => Some(Self::phrase_connections_from_document(&connection_data)),
             Err(e) => {
                 println!("Error: {:?}", e);
                 return None;
@@ -1448,6 +3811,106 @@ impl GrapevineDB {
         }
     }
 
+    /**
+     * Aggregation stages that count a matched user's relationships with a proof of a phrase
+     *
+     * @param phrase_index - the index of the phrase
+     * @returns - the stages, ending in a single document with the count, highest degree, and
+     *            degrees of the relationships' proofs (no document if there are none)
+     */
+    fn phrase_connections_stages(phrase_index: u32) -> Vec<Document> {
+        vec![
+            doc! { "$unwind": "$relationships" },
+            doc! {
+                "$lookup": {
+                    "from": "relationships",
+                    "localField": "relationships",
+                    "foreignField": "_id",
+                    "as": "relationship_details"
+                }
+            },
+            doc! {
+                "$unwind": "$relationship_details"
+            },
+            // step 2: ensure unique senders
+            doc! {
+                "$group": {
+                    "_id": null,
+                    "senders": {
+                        "$addToSet": "$relationship_details.sender"
+                    }
+                }
+            },
+            doc! { "$project": { "_id": 0, "senders": 1 } },
+            // step 3: look up the phrase document by index
+            doc! {
+                "$lookup": {
+                    "from": "phrases",
+                    "let": { "index": phrase_index },
+                    "pipeline": [
+                        { "$match": { "$expr": { "$eq": ["$index", "$$index"] } } },
+                        { "$project": { "_id": 1 } }
+                    ],
+                    "as": "phrase_document"
+                }
+            },
+            doc! { "$unwind": "$phrase_document" },
+            // step 4: find all active degree proofs for the phrase made by relationships
+            doc! {
+                "$lookup": {
+                    "from": "degree_proofs",
+                    "let": { "senders": "$senders", "phrase": "$phrase_document._id" },
+                    "pipeline": [
+                        {
+                            "$match": {
+                                "$expr": {
+                                    "$and": [
+                                        { "$in": ["$user", "$$senders"] },
+                                        { "$eq": ["$phrase", "$$phrase"] },
+                                        { "$ne": ["$inactive", true] }
+                                    ]
+                                }
+                            }
+                        },
+                        { "$project": { "_id": 0, "degree": 1 } }
+                    ],
+                    "as": "degree_proofs"
+                }
+            },
+            doc! { "$unwind": "$degree_proofs" },
+            doc! {
+                "$group": {
+                    "_id": null,
+                    "max_degree": { "$max": "$degree_proofs.degree" },
+                    "count": { "$sum": 1 },
+                    "degrees": { "$push": "$degree_proofs.degree" }
+                }
+            },
+        ]
+    }
+
+    /**
+     * Parse the connection counts grouped by `phrase_connections_stages`
+     *
+     * @param connection_data - the grouped document
+     * @returns - the total number of connections and the number connected at each degree
+     */
+    fn phrase_connections_from_document(connection_data: &Document) -> (u64, Vec<u64>) {
+        let total_count = connection_data.get_i32("count").unwrap();
+        let max_degree = connection_data.get_i32("max_degree").unwrap();
+        let mut degree_counts: Vec<u64> = vec![0; max_degree as usize];
+        let degrees: Vec<i32> = connection_data
+            .get_array("degrees")
+            .unwrap()
+            .iter()
+            .map(|d| d.as_i32().unwrap())
+            .collect();
+        for degree in degrees {
+            degree_counts[(degree - 1) as usize] += 1;
+        }
+        (total_count as u64, degree_counts)
+    }
+
     /**
      * Check to see if degree already exists between two accounts
      *
@@ -1599,7 +4062,7 @@ impl GrapevineDB {
 
     /**
      * Returns all info about a phrase known to a given user
-     * @notice: connections done separately
+     * @notice: connections done separately (see `get_phrase_full` to get both in one query)
      *
      * @param username - the username of the user
      * @param index - the index of the phrase
@@ -1612,9 +4075,99 @@ impl GrapevineDB {
         index: u32,
     ) -> Result<DegreeData, GrapevineError> {
         // find the degree data for a given proof
+        let mut pipeline = vec![
+            // look up the user by username
+            doc! { "$match": { "username": username } },
+        ];
+        pipeline.extend(Self::phrase_info_stages(index));
+        let mut cursor = self.users.aggregate(pipeline, None).await.unwrap();
+        if let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => {
+                    return Ok(Self::phrase_info_from_document(&document, index));
+                }
+                Err(_) => {
+                    return Err(GrapevineError::MongoError(
+                        "Failed phrase data retrieval".to_string(),
+                    ));
+                }
+            }
+        } else {
+            return Err(GrapevineError::MongoError(
+                "Failed phrase data retrieval".to_string(),
+            ));
+        }
+    }
+
+    /**
+     * Returns all info about a phrase known to a given user along with their connections to it
+     * @notice: runs the `get_phrase_info` and `get_phrase_connections` stages side by side in a
+     *          single aggregation
+     *
+     * @param username - the username of the user
+     * @param index - the index of the phrase
+     * @returns - the degree data of the phrase and the user's connections to it by degree
+     */
+    pub async fn get_phrase_full(
+        &self,
+        username: &String,
+        index: u32,
+    ) -> Result<PhraseFullResponse, GrapevineError> {
         let pipeline = vec![
             // look up the user by username
             doc! { "$match": { "username": username } },
+            doc! {
+                "$facet": {
+                    "phrase": Self::phrase_info_stages(index),
+                    "connections": Self::phrase_connections_stages(index),
+                }
+            },
+        ];
+        let mut cursor = match self.users.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineError::MongoError(e.to_string())),
+        };
+        let document = match cursor.next().await {
+            Some(Ok(document)) => document,
+            Some(Err(e)) => return Err(GrapevineError::MongoError(e.to_string())),
+            None => {
+                return Err(GrapevineError::MongoError(
+                    "Failed phrase data retrieval".to_string(),
+                ))
+            }
+        };
+        let phrase = match document.get_array("phrase").unwrap().first() {
+            Some(Bson::Document(info)) => Self::phrase_info_from_document(info, index),
+            _ => {
+                return Err(GrapevineError::MongoError(
+                    "Failed phrase data retrieval".to_string(),
+                ))
+            }
+        };
+        // no connections are grouped if no relationship has a proof of the phrase
+        let (total_connections, connections_by_degree) =
+            match document.get_array("connections").unwrap().first() {
+                Some(Bson::Document(connections)) => {
+                    Self::phrase_connections_from_document(connections)
+                }
+                _ => (0, vec![]),
+            };
+        Ok(PhraseFullResponse {
+            phrase,
+            total_connections,
+            connections_by_degree,
+        })
+    }
+
+    /**
+     * Aggregation stages that find the degree data of a phrase for a matched user document
+     *
+     * @param index - the index of the phrase
+     * @returns - the stages, ending in a projection of the phrase hash and description along with
+     *            the user's degree, ciphertext, and 1st/ 2nd degree relations
+     */
+    fn phrase_info_stages(index: u32) -> Vec<Document> {
+        vec![
             doc! { "$project": { "_id": 1 } },
             // look up the phrase by index
             doc! {
@@ -1731,75 +4284,69 @@ impl GrapevineDB {
                     "_id": 0
                 }
             },
-        ];
-        let mut cursor = self.users.aggregate(pipeline, None).await.unwrap();
-        if let Some(result) = cursor.next().await {
-            match result {
-                Ok(document) => {
-                    println!("Document: {:#?}", document);
-                    // get the degree of separation found for this user on this phrase
-                    let degree = match document.get_i32("degree") {
-                        Ok(val) => Some(val as u8),
-                        Err(_) => None,
-                    };
-                    println!("Degree: {:?}", degree);
-                    // get any 1st and 2nd degree relations found for this user on this phrase
-                    let relation = match document.get("degree_1") {
-                        Some(degree_1) => Some(degree_1.as_str().unwrap().to_string()),
-                        None => None,
-                    };
-                    println!("Relation: {:?}", relation);
-                    let preceding_relation = match document.get("degree_2") {
-                        Some(degree_2) => Some(degree_2.as_str().unwrap().to_string()),
-                        None => None,
-                    };
-                    println!("Preceding relation: {:?}", preceding_relation);
-                    // get the hash of the phrase
-                    let phrase_hash: [u8; 32] = document
-                        .get("hash")
-                        .unwrap()
-                        .as_array()
-                        .unwrap()
-                        .iter()
-                        .map(|x| x.as_i32().unwrap() as u8)
-                        .collect::<Vec<u8>>()
-                        .try_into()
-                        .unwrap();
-                    println!("Phrase hash: {:?}", phrase_hash);
-                    // get the description of the phrase
-                    let phrase_description = document
-                        .get("description")
-                        .unwrap()
-                        .as_str()
-                        .unwrap()
-                        .to_string();
-                    println!("Phrase description: {:?}", phrase_description);
-                    // get the ciphertext of the proof
-                    let mut secret_phrase: Option<[u8; 192]> = None;
-                    if let Some(Bson::Binary(binary)) = document.get("ciphertext") {
-                        secret_phrase = Some(binary.bytes.clone().try_into().unwrap());
-                    }
-                    println!("Secret phrase: {:?}", secret_phrase);
-                    return Ok(DegreeData {
-                        description: phrase_description,
-                        degree,
-                        phrase_index: index,
-                        relation,
-                        preceding_relation,
-                        phrase_hash,
-                        secret_phrase,
-                    });
-                }
-                Err(_) => {
-                    return Err(GrapevineError::MongoError(
-                        "Failed phrase data retrieval".to_string(),
-                    ));
-                }
-            }
-        } else {
-            return Err(GrapevineError::MongoError(
-                "Failed phrase data retrieval".to_string(),
-            ));
+        ]
+    }
+
+    /**
+     * Parse the degree data projected by `phrase_info_stages`
+     *
+     * @param document - the projected document
+     * @param index - the index of the phrase
+     * @returns - the degree data of the phrase for the user
+     */
+    fn phrase_info_from_document(document: &Document, index: u32) -> DegreeData {
+        println!("Document: {:#?}", document);
+        // get the degree of separation found for this user on this phrase
+        let degree = match document.get_i32("degree") {
+            Ok(val) => Some(val as u8),
+            Err(_) => None,
+        };
+        println!("Degree: {:?}", degree);
+        // get any 1st and 2nd degree relations found for this user on this phrase
+        let relation = match document.get("degree_1") {
+            Some(degree_1) => Some(degree_1.as_str().unwrap().to_string()),
+            None => None,
+        };
+        println!("Relation: {:?}", relation);
+        let preceding_relation = match document.get("degree_2") {
+            Some(degree_2) => Some(degree_2.as_str().unwrap().to_string()),
+            None => None,
+        };
+        println!("Preceding relation: {:?}", preceding_relation);
+        // get the hash of the phrase
+        let phrase_hash: [u8; 32] = document
+            .get("hash")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x.as_i32().unwrap() as u8)
+            .collect::<Vec<u8>>()
+            .try_into()
+            .unwrap();
+        println!("Phrase hash: {:?}", phrase_hash);
+        // get the description of the phrase
+        let phrase_description = document
+            .get("description")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string();
+        println!("Phrase description: {:?}", phrase_description);
+        // get the ciphertext of the proof
+        let mut secret_phrase: Option<[u8; 192]> = None;
+        if let Some(Bson::Binary(binary)) = document.get("ciphertext") {
+            secret_phrase = Some(binary.bytes.clone().try_into().unwrap());
+        }
+        println!("Secret phrase: {:?}", secret_phrase);
+        DegreeData {
+            description: phrase_description,
+            degree,
+            phrase_index: index,
+            relation,
+            preceding_relation,
+            phrase_hash,
+            secret_phrase,
         }
     }
 }
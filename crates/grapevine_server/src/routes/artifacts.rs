@@ -0,0 +1,69 @@
+use crate::catchers::StaticArtifact;
+use crate::guards::ConditionalRequest;
+use rocket::fs::{relative, NamedFile};
+use rocket::http::{ContentType, Status};
+use rocket::tokio::io::{AsyncReadExt, AsyncSeekExt};
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// GET REQUESTS ///
+
+/**
+ * Serve a proving artifact (r1cs, wasm, public params) from the static directory. Responses carry
+ * an ETag and Last-Modified so clients can revalidate a cached copy instead of downloading the
+ * large files again, and a single byte range can be requested to resume an interrupted download
+ *
+ * @param file - the path of the artifact within the static directory
+ * @return - the artifact or the requested range of it, or an empty response if the client's copy
+ *           is current
+ * @return status:
+ *         - 200 if the artifact is returned
+ *         - 206 if the requested byte range of the artifact is returned
+ *         - 304 if If-None-Match or If-Modified-Since show the client's copy is current
+ *         - 404 if the artifact does not exist
+ *         - 416 if the requested range starts past the end of the artifact
+ */
+#[get("/<file..>")]
+pub async fn get_artifact(
+    file: PathBuf,
+    conditional: ConditionalRequest,
+) -> Result<StaticArtifact, Status> {
+    let path = Path::new(relative!("static")).join(file);
+    let artifact = NamedFile::open(&path).await.map_err(|_| Status::NotFound)?;
+    let metadata = match artifact.file().metadata().await {
+        Ok(metadata) if metadata.is_file() => metadata,
+        _ => return Err(Status::NotFound),
+    };
+    // the size and modification time change whenever an artifact is redeployed
+    let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+    let nanos = modified
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_nanos())
+        .unwrap_or(0);
+    let len = metadata.len();
+    let etag = format!("\"{:x}-{:x}\"", len, nanos);
+    if conditional.is_current(&etag, modified) {
+        return Ok(StaticArtifact::NotModified(etag));
+    }
+    let (first, last) = match conditional.byte_range(&etag, modified, len) {
+        Ok(Some(range)) => range,
+        Ok(None) => return Ok(StaticArtifact::File(artifact, etag, modified)),
+        Err(_) => return Ok(StaticArtifact::Unsatisfiable(len)),
+    };
+    let content_type = path
+        .extension()
+        .and_then(|extension| ContentType::from_extension(&extension.to_string_lossy()));
+    let mut body = artifact.take_file();
+    if body.seek(SeekFrom::Start(first)).await.is_err() {
+        return Err(Status::InternalServerError);
+    }
+    Ok(StaticArtifact::Partial {
+        body: body.take(last - first + 1),
+        content_type,
+        etag,
+        modified,
+        range: (first, last),
+        len,
+    })
+}
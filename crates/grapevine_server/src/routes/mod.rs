@@ -1,6 +1,8 @@
 use lazy_static::lazy_static;
 use rocket::route::Route;
+mod artifacts;
 mod proof;
+mod stats;
 mod user;
 
 lazy_static! {
@@ -9,20 +11,64 @@ lazy_static! {
         user::add_relationship,
         user::reject_pending_relationship,
         user::get_pending_relationships,
+        user::get_new_relationships,
+        user::get_outbound_relationships,
+        user::set_delegated_proving,
+        user::set_guardians,
+        user::recover_account,
+        user::recompute_user_stats,
+        user::recompute_all_stats,
+        user::get_orphaned_relationships,
+        user::repair_orphaned_relationships,
         user::get_active_relationships,
+        user::get_mutual_relationships,
+        user::get_relationship_auth_secret,
         user::get_account_details,
         user::get_user,
         user::get_nonce,
         user::get_pubkey,
-        user::get_all_degrees
-    ];
-    pub(crate) static ref PROOF_ROUTES: Vec<Route> = routes![
-        proof::prove_phrase,
-        proof::degree_proof,
-        proof::get_available_proofs,
-        proof::get_phrase_connections,
-        proof::get_proof_with_params,
-        proof::get_known_phrases,
-        proof::get_phrase
+        user::get_all_degrees,
+        user::get_merkle_root,
+        user::get_merkle_proof
     ];
+    pub(crate) static ref PROOF_ROUTES: Vec<Route> = {
+        #[allow(unused_mut)]
+        let mut proof_routes = routes![
+            proof::prove_phrase,
+            proof::degree_proof,
+            proof::degree_proof_batch,
+            proof::deactivate_proof,
+            proof::update_phrase_description,
+            proof::update_phrase_tags,
+            proof::update_phrase_visibility,
+            proof::update_phrase_ciphertext,
+            proof::commit_phrase,
+            proof::reveal_phrase,
+            proof::verify_proof_in_set,
+            proof::scan_proof_integrity,
+            proof::migrate_proofs,
+            proof::export_proofs,
+            proof::import_proofs,
+            proof::get_available_proofs,
+            proof::get_phrase_connections,
+            proof::get_proof_with_params,
+            proof::get_known_phrases,
+            proof::get_phrase_list,
+            proof::get_connected_origins,
+            proof::search_phrases,
+            proof::get_phrases_by_tag,
+            proof::get_phrase,
+            proof::get_phrase_full,
+            proof::get_phrase_proof,
+            proof::get_circuit_info,
+            proof::download_proof,
+            proof::get_proof_children
+        ];
+        #[cfg(feature = "delegated-proving")]
+        proof_routes.extend(routes![proof::delegated_degree_proof]);
+        proof_routes
+    };
+    pub(crate) static ref WS_ROUTES: Vec<Route> = routes![proof::proof_events];
+    pub(crate) static ref STATS_ROUTES: Vec<Route> = routes![stats::get_leaderboard];
+    pub(crate) static ref ARTIFACT_ROUTES: Vec<Route> = routes![artifacts::get_artifact];
 }
@@ -1,20 +1,63 @@
+use crate::cache::ProofCache;
 use crate::catchers::ErrorMessage;
+use crate::events::ProofEvents;
+use crate::limits::{read_body, ProofLimits, UserQuotas};
 use crate::mongo::GrapevineDB;
-use crate::utils::PUBLIC_PARAMS;
-use crate::{catchers::GrapevineResponse, guards::AuthenticatedUser};
-use grapevine_circuits::{nova::verify_nova_proof, utils::decompress_proof};
+use crate::shutdown::ProofDrain;
+use crate::utils::{
+    check_proof, check_request_version, log_proof_rejection, proof_expiry, ProofRejectionReason,
+    CIRCUIT_INFO, PUBLIC_PARAMS,
+};
+#[cfg(feature = "delegated-proving")]
+use crate::utils::{R1CS_FILE, WASM_PATH};
+use crate::verifier::verify_in_pool;
+use crate::{
+    catchers::{EncodedProof, GrapevineResponse},
+    guards::{AcceptProofEncoding, Admin, AuthenticatedUser, TenantDB},
+};
+use futures::{SinkExt, StreamExt};
+#[cfg(feature = "delegated-proving")]
+use grapevine_circuits::nova::continue_nova_proof;
+use grapevine_circuits::{
+    nova::{verify_degree_proof_metadata, verify_nova_proof, verify_nova_proof_in_set},
+    steps_for_degree,
+    utils::{compress_proof, reencode_proof, try_decompress_proof, ProofEncoding},
+};
+use grapevine_common::backup::{BackupDigest, BACKUP_MAGIC, MAX_BACKUP_RECORD};
+use grapevine_common::crypto::{commit_phrase_hash, proof_content_hash};
 use grapevine_common::errors::GrapevineError;
+#[cfg(feature = "delegated-proving")]
+use grapevine_common::{http::requests::DelegatedDegreeProofRequest, Fr};
 use grapevine_common::{
     http::{
-        requests::{DegreeProofRequest, PhraseRequest},
-        responses::{DegreeData, PhraseCreationResponse},
+        requests::{
+            decode_request, DegreeProofBatchRequest, DegreeProofRequest, PhraseCiphertextRequest,
+            PhraseCommitRequest, PhraseDescriptionRequest, PhraseRequest, PhraseRequestV1,
+            PhraseRevealRequest, PhraseTagsRequest, PhraseVisibilityRequest, VerifyInSetRequest,
+        },
+        responses::{
+            AvailableProofEvent, CircuitInfoResponse, ConnectedOrigin, DegreeData,
+            DegreeProofBatchResult, PhraseCreationResponse, PhraseFullResponse,
+            PhraseProofResponse, PhraseRevealResponse, PhraseSearchResult, PhraseSummary,
+            ProofChild, ProofMigrationResponse, VerifyInSetResponse,
+        },
     },
-    models::{DegreeProof, ProvingData},
+    models::{DegreeProof, PhraseVisibility, ProvingData, User},
+    utils::validate_tags,
+    MAX_DEGREE_PROOF_BATCH,
 };
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, Document};
+use rocket::tokio::io::{AsyncRead, AsyncReadExt};
+use rocket::tokio::sync::broadcast::error::RecvError;
 use rocket::{
-    data::ToByteUnit, http::Status, serde::json::Json, tokio::io::AsyncReadExt, Data, State,
+    data::ToByteUnit,
+    http::{ContentType, Status},
+    response::stream::ByteStream,
+    serde::json::Json,
+    Data, State,
 };
+use rocket_ws::{Channel, Message, WebSocket};
+use std::net::IpAddr;
 use std::str::FromStr;
 
 // /// POST REQUESTS ///
@@ -26,53 +69,75 @@ use std::str::FromStr;
  *             * hash: the hash of the phrase
  *             * ciphertext: the encrypted phrase
  *             * description: the description of the phrase
+ *             * tags: the tags to list the phrase under (absent from version 1 requests)
  *        
  * @return status:
+ *             * 200 if the user already submitted this proof (returns the existing proof)
  *             * 201 if success
- *             * 400 if deserialization fails
+ *             * 400 if deserialization fails or the tags are invalid
  *             * 401 if signature mismatch or nonce mismatch
+ *             * 403 if the phrase is new and the user has reached their phrase quota
  *             * 404 if user not found
- *             * 409 if phrase already exists
+ *             * 409 if phrase already exists or another user submitted this proof
+ *             * 413 if the request body exceeds the identity proof limit
  *             * 500 if db fails or other unknown issue
+ *             * 503 if the server is shutting down
  */
 #[post("/phrase", data = "<data>")]
 pub async fn prove_phrase(
     user: AuthenticatedUser,
+    address: Option<IpAddr>,
     data: Data<'_>,
-    db: &State<GrapevineDB>,
+    db: TenantDB,
+    limits: &State<ProofLimits>,
+    quotas: &State<UserQuotas>,
+    events: &State<ProofEvents>,
+    drain: &State<ProofDrain>,
 ) -> Result<GrapevineResponse, GrapevineResponse> {
+    // hold the submission in flight until it is stored so shutdown waits for it
+    let _in_flight = drain.begin()?;
     // stream in data
-    let mut buffer = Vec::new();
-    let mut stream = data.open(2.mebibytes()); // Adjust size limit as needed
-    if let Err(e) = stream.read_to_end(&mut buffer).await {
-        println!("Error reading request body: {:?}", e);
-        return Err(GrapevineResponse::TooLarge(
-            "Request body execeeds 2 MiB".to_string(),
-        ));
-    }
-    let request = match bincode::deserialize::<PhraseRequest>(&buffer) {
+    let buffer = read_body(data, limits.identity).await?;
+    check_request_version(&buffer)?;
+    // version 1 requests predate phrase tags
+    let request = match buffer[0] {
+        1 => decode_request::<PhraseRequestV1>(&buffer).map(PhraseRequest::from),
+        _ => decode_request::<PhraseRequest>(&buffer),
+    };
+    let request = match request {
         Ok(req) => req,
-        Err(e) => {
-            println!(
-                "Error deserializing body from binary to PhraseRequest: {:?}",
-                e
-            );
+        Err(_) => {
+            log_proof_rejection(ProofRejectionReason::Parse, &user.0, address, None);
             return Err(GrapevineResponse::BadRequest(ErrorMessage(
-                Some(GrapevineError::SerdeError(String::from(
-                    "NewPhraseRequest",
-                ))),
+                Some(GrapevineError::SerdeError(String::from("NewPhraseRequest"))),
                 None,
             )));
         }
     };
+    validate_tags(&request.tags)?;
+
+    // return the existing proof if this exact proof was already submitted
+    if let Some(existing) = find_resubmitted_proof(&db, &user.0, &request.proof).await? {
+        let phrase_index = db.get_phrase_index(&existing.phrase.unwrap()).await?;
+        let response_data = PhraseCreationResponse {
+            phrase_index,
+            new_phrase: false,
+            proof: existing.id.unwrap().to_hex(),
+        };
+        return Ok(GrapevineResponse::Success(
+            serde_json::to_string(&response_data).unwrap(),
+        ));
+    }
 
-    // verify the proof
-    let decompressed_proof = decompress_proof(&request.proof);
-    let verify_res = verify_nova_proof(&decompressed_proof, &*PUBLIC_PARAMS, 2);
+    // verify the proof off of the async runtime
+    let proof = request.proof.clone();
+    let verify_res =
+        verify_in_pool(move || check_proof(&proof, &*PUBLIC_PARAMS, steps_for_degree(1), 1))
+            .await?;
     let (phrase_hash, auth_hash) = match verify_res {
-        Ok(res) => (res.0[1].to_bytes(), res.0[2].to_bytes()),
-        Err(e) => {
-            println!("Proof verification failed: {:?}", e);
+        Ok(outputs) => (outputs[1].to_bytes(), outputs[2].to_bytes()),
+        Err(reason) => {
+            log_proof_rejection(reason, &user.0, address, Some(1));
             return Err(GrapevineResponse::BadRequest(ErrorMessage(
                 Some(GrapevineError::DegreeProofVerificationFailed),
                 None,
@@ -131,8 +196,17 @@ pub async fn prove_phrase(
             index
         }
         false => {
+            // enforce the cap on how many phrases a user may originate
+            let origin = db.get_user(&user.0).await.unwrap();
+            if db.count_originated_phrases(&origin.id.unwrap()).await? >= quotas.phrases {
+                let resource = String::from("originated phrases");
+                return Err(GrapevineError::QuotaExceeded(resource, quotas.phrases).into());
+            }
             // if phrase does not exist, create it
-            let (oid, index) = match db.create_phrase(phrase_hash, request.description).await {
+            let (oid, index) = match db
+                .create_phrase(phrase_hash, request.description, request.tags)
+                .await
+            {
                 Ok(res) => res,
                 Err(e) => {
                     println!("Error adding proof: {:?}", e);
@@ -161,14 +235,18 @@ pub async fn prove_phrase(
         proof: Some(request.proof.clone()),
         preceding: None,
         proceeding: Some(vec![]),
+        expires_at: proof_expiry(),
+        content_hash: Some(proof_content_hash(&request.proof)),
     };
 
     // Add the proof to the db
     match db.add_proof(&user.id.unwrap(), &proof_doc).await {
-        Ok(_) => {
+        Ok(oid) => {
+            notify_relationships(&db, events, &user, oid, 1).await;
             let response_data = PhraseCreationResponse {
                 phrase_index,
                 new_phrase: !exists,
+                proof: oid.to_hex(),
             };
             Ok(GrapevineResponse::Created(
                 serde_json::to_string(&response_data).unwrap(),
@@ -195,51 +273,319 @@ pub async fn prove_phrase(
  *             * previous: the stringified OID of the previous proof to continue IVC from
  *             * degree: the separation degree of the given proof
  * @return status:
+ *             * 200 if the user already submitted this proof
  *             * 201 if successful proof update
  *             * 400 if proof verification failed, deserialization fails, or proof decompression
  *               fails
  *             * 401 if signature mismatch or nonce mismatch
+ *             * 403 if the previous proof was not made by an active relationship of the user
  *             * 404 if user or previous proof not found not found
+ *             * 409 if another user already submitted this proof
+ *             * 413 if the request body exceeds the degree proof limit
  *             * 500 if db fails or other unknown issue
+ *             * 503 if the server is shutting down
  */
 #[post("/degree", data = "<data>")]
 pub async fn degree_proof(
     user: AuthenticatedUser,
+    address: Option<IpAddr>,
     data: Data<'_>,
-    db: &State<GrapevineDB>,
+    db: TenantDB,
+    limits: &State<ProofLimits>,
+    events: &State<ProofEvents>,
+    drain: &State<ProofDrain>,
 ) -> Result<Status, GrapevineResponse> {
+    // hold the submission in flight until it is stored so shutdown waits for it
+    let _in_flight = drain.begin()?;
     // stream in data
     // todo: implement FromData trait on DegreeProofRequest
-    let mut buffer = Vec::new();
-    let mut stream = data.open(2.mebibytes()); // Adjust size limit as needed
-    if let Err(_) = stream.read_to_end(&mut buffer).await {
-        return Err(GrapevineResponse::TooLarge(
-            "Request body execeeds 2 MiB".to_string(),
-        ));
-    }
-    let request = match bincode::deserialize::<DegreeProofRequest>(&buffer) {
+    let buffer = read_body(data, limits.degree).await?;
+    check_request_version(&buffer)?;
+    let request = match decode_request::<DegreeProofRequest>(&buffer) {
         Ok(req) => req,
         Err(_) => {
+            log_proof_rejection(ProofRejectionReason::Parse, &user.0, address, None);
             return Err(GrapevineResponse::BadRequest(ErrorMessage(
                 Some(GrapevineError::SerdeError(String::from(
                     "DegreeProofRequest",
                 ))),
                 None,
+            )));
+        }
+    };
+
+    verify_and_store_degree_proof(&db, events, &user.0, address, request).await
+}
+
+/**
+ * Build from several previous degree of connection proofs in one request. Each proof is verified
+ * and stored independently, so one bad proof does not fail the rest of the batch
+ *
+ * @param data - binary serialized DegreeProofBatchRequest containing:
+ *             * proofs: up to MAX_DEGREE_PROOF_BATCH DegreeProofRequests
+ * @return - a DegreeProofBatchResult for each proof in the order submitted, containing:
+ *             * previous: the stringified OID of the proof the submitted proof was built from
+ *             * status: the status the proof would have received from POST /proof/degree
+ * @return status:
+ *             * 200 if the batch was processed
+ *             * 400 if deserialization fails
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 413 if the request body or number of proofs exceeds the batch limit
+ *             * 503 if the server is shutting down
+ */
+#[post("/degree/batch", data = "<data>")]
+pub async fn degree_proof_batch(
+    user: AuthenticatedUser,
+    address: Option<IpAddr>,
+    data: Data<'_>,
+    db: TenantDB,
+    limits: &State<ProofLimits>,
+    events: &State<ProofEvents>,
+    drain: &State<ProofDrain>,
+) -> Result<Json<Vec<DegreeProofBatchResult>>, GrapevineResponse> {
+    // hold the submission in flight until it is stored so shutdown waits for it
+    let _in_flight = drain.begin()?;
+    // stream in data
+    let limit = limits.degree.saturating_mul(MAX_DEGREE_PROOF_BATCH as u64);
+    let buffer = read_body(data, limit).await?;
+    check_request_version(&buffer)?;
+    let request = match decode_request::<DegreeProofBatchRequest>(&buffer) {
+        Ok(req) => req,
+        Err(_) => {
+            log_proof_rejection(ProofRejectionReason::Parse, &user.0, address, None);
+            return Err(GrapevineResponse::BadRequest(ErrorMessage(
+                Some(GrapevineError::SerdeError(String::from(
+                    "DegreeProofBatchRequest",
+                ))),
+                None,
+            )));
+        }
+    };
+    if request.proofs.len() > MAX_DEGREE_PROOF_BATCH {
+        return Err(GrapevineResponse::TooLarge(format!(
+            "Batch exceeds {} proofs",
+            MAX_DEGREE_PROOF_BATCH
+        )));
+    }
+
+    // verify and store each proof, recording the outcome instead of failing the batch
+    let mut results = vec![];
+    for proof in request.proofs {
+        let previous = proof.previous.clone();
+        let status = match check_request_version(&[proof.version]) {
+            Ok(_) => {
+                match verify_and_store_degree_proof(&db, events, &user.0, address, proof).await {
+                    Ok(status) => status,
+                    Err(res) => res.status(),
+                }
+            }
+            Err(_) => Status::BadRequest,
+        };
+        results.push(DegreeProofBatchResult {
+            previous,
+            status: status.code,
+        });
+    }
+    Ok(Json(results))
+}
+
+/**
+ * Build a degree proof on the user's behalf from a previous proof and the decrypted auth secrets
+ * @notice: the server learns the auth secrets, so this is only for users that opt in
+ *
+ * @param data - binary serialized DelegatedDegreeProofRequest containing:
+ *             * previous: the stringified OID of the proof to build from
+ *             * auth_secrets: the previous prover's auth secret and the requester's auth secret
+ * @return status:
+ *             * 201 if success
+ *             * 400 if deserialization fails, auth secrets are malformed, or proving fails
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 403 if the user has not opted in to delegated proving
+ *             * 404 if the previous proof or relationship is not found
+ *             * 409 if the degree proof already exists
+ *             * 500 if db fails or other unknown issue
+ *             * 503 if the server is shutting down
+ */
+#[cfg(feature = "delegated-proving")]
+#[post("/degree/delegated", data = "<data>")]
+pub async fn delegated_degree_proof(
+    user: AuthenticatedUser,
+    address: Option<IpAddr>,
+    data: Data<'_>,
+    db: TenantDB,
+    limits: &State<ProofLimits>,
+    events: &State<ProofEvents>,
+    drain: &State<ProofDrain>,
+) -> Result<Status, GrapevineResponse> {
+    // hold the submission in flight until it is stored so shutdown waits for it
+    let _in_flight = drain.begin()?;
+    // stream in data
+    let buffer = read_body(data, limits.degree).await?;
+    check_request_version(&buffer)?;
+    let request = match decode_request::<DelegatedDegreeProofRequest>(&buffer) {
+        Ok(req) => req,
+        Err(_) => {
+            log_proof_rejection(ProofRejectionReason::Parse, &user.0, address, None);
+            return Err(GrapevineResponse::BadRequest(ErrorMessage(
+                Some(GrapevineError::SerdeError(String::from(
+                    "DelegatedDegreeProofRequest",
+                ))),
+                None,
+            )));
+        }
+    };
+
+    // check that the user has opted in to delegated proving
+    let opted_in = match db.get_user(&user.0).await {
+        Some(user) => user.delegated_proving.unwrap_or(false),
+        None => false,
+    };
+    if !opted_in {
+        return Err(GrapevineResponse::Forbidden(ErrorMessage(
+            Some(GrapevineError::DelegatedProvingDisabled),
+            None,
+        )));
+    }
+
+    // get the proof being built on
+    let previous_oid = match ObjectId::from_str(&request.previous) {
+        Ok(oid) => oid,
+        Err(_) => {
+            return Err(GrapevineResponse::BadRequest(ErrorMessage(
+                Some(GrapevineError::SerdeError(String::from("ObjectId"))),
+                None,
             )))
         }
     };
+    let previous = match db.get_proof_details(previous_oid).await {
+        Some(proof) => proof,
+        None => {
+            return Err(GrapevineResponse::NotFound(format!(
+                "No proof found with oid {}",
+                previous_oid
+            )))
+        }
+    };
+    if db
+        .get_relationship_keys(user.0.clone(), previous.creator)
+        .await
+        .is_none()
+    {
+        return Err(GrapevineResponse::NotFound(format!(
+            "No relationship found with creator of proof {}",
+            previous_oid
+        )));
+    }
 
-    // verify the proof
-    let decompressed_proof = decompress_proof(&request.proof);
-    let verify_res = verify_nova_proof(
-        &decompressed_proof,
-        &*PUBLIC_PARAMS,
-        (request.degree * 2) as usize,
-    );
+    // parse the auth secrets into field elements
+    let mut auth_secrets = vec![];
+    for secret in request.auth_secrets.iter() {
+        match Option::<Fr>::from(Fr::from_bytes(secret)) {
+            Some(secret) => auth_secrets.push(secret),
+            None => {
+                return Err(GrapevineResponse::BadRequest(ErrorMessage(
+                    Some(GrapevineError::SerdeError(String::from("auth secret"))),
+                    None,
+                )))
+            }
+        }
+    }
+    let usernames = vec![previous.username.clone(), user.0.clone()];
+
+    // verify the previous proof and fold in the new degree on the bounded verification pool
+    let degree = previous.degree + 1;
+    let proving_res = verify_in_pool(move || {
+        let mut proof = try_decompress_proof(&previous.proof).ok()?;
+        let iterations = steps_for_degree(previous.degree as usize);
+        let previous_output = verify_nova_proof(&proof, &*PUBLIC_PARAMS, iterations)
+            .ok()?
+            .0;
+        continue_nova_proof(
+            &usernames,
+            &auth_secrets,
+            &mut proof,
+            previous_output,
+            WASM_PATH.clone(),
+            &*R1CS_FILE,
+            &*PUBLIC_PARAMS,
+        )
+        .ok()?;
+        let outputs = verify_nova_proof(&proof, &*PUBLIC_PARAMS, steps_for_degree(degree as usize))
+            .ok()?
+            .0;
+        Some((compress_proof(&proof), outputs))
+    })
+    .await;
+    let (proof, outputs) = match proving_res {
+        Ok(Some(res)) => res,
+        _ => {
+            log_proof_rejection(ProofRejectionReason::Verify, &user.0, address, Some(degree));
+            return Err(GrapevineResponse::BadRequest(ErrorMessage(
+                Some(GrapevineError::DegreeProofVerificationFailed),
+                None,
+            )));
+        }
+    };
+
+    store_degree_proof(
+        &db,
+        events,
+        &user.0,
+        proof,
+        &request.previous,
+        degree,
+        outputs[1].to_bytes(),
+        outputs[2].to_bytes(),
+    )
+    .await
+}
+
+/**
+ * Verify a degree proof against its claimed degree, then store it
+ *
+ * @param db - the database to store the proof in
+ * @param events - the channels to notify relationships of the new proof on
+ * @param username - the username of the user that made the proof
+ * @param address - the remote address of the requester if known (for logging rejections)
+ * @param request - the degree proof request
+ * @return status:
+ *             * 200 if the user already submitted this proof
+ *             * 201 if success
+ *             * 400 if proof verification failed or proof decompression fails
+ *             * 404 if the phrase is not found
+ *             * 409 if the degree proof already exists or another user submitted this proof
+ *             * 500 if db fails or other unknown issue
+ */
+async fn verify_and_store_degree_proof(
+    db: &GrapevineDB,
+    events: &ProofEvents,
+    username: &String,
+    address: Option<IpAddr>,
+    request: DegreeProofRequest,
+) -> Result<Status, GrapevineResponse> {
+    // skip verification if this exact proof was already submitted
+    if find_resubmitted_proof(db, username, &request.proof)
+        .await?
+        .is_some()
+    {
+        return Ok(Status::Ok);
+    }
+
+    // verify the proof off of the async runtime
+    let (proof, degree) = (request.proof.clone(), request.degree);
+    let verify_res = verify_in_pool(move || {
+        check_proof(
+            &proof,
+            &*PUBLIC_PARAMS,
+            steps_for_degree(degree as usize),
+            degree,
+        )
+    })
+    .await?;
     let (phrase_hash, auth_hash) = match verify_res {
-        Ok(res) => (res.0[1].to_bytes(), res.0[2].to_bytes()),
-        Err(e) => {
-            println!("Proof verification failed: {:?}", e);
+        Ok(outputs) => (outputs[1].to_bytes(), outputs[2].to_bytes()),
+        Err(reason) => {
+            log_proof_rejection(reason, username, address, Some(request.degree));
             return Err(GrapevineResponse::BadRequest(ErrorMessage(
                 Some(GrapevineError::DegreeProofVerificationFailed),
                 None,
@@ -247,6 +593,48 @@ pub async fn degree_proof(
         }
     };
 
+    store_degree_proof(
+        db,
+        events,
+        username,
+        request.proof,
+        &request.previous,
+        request.degree,
+        phrase_hash,
+        auth_hash,
+    )
+    .await
+}
+
+/**
+ * Store a verified degree proof and link it into the proof chain it extends
+ *
+ * @param db - the database to store the proof in
+ * @param events - the channels to notify relationships of the new proof on
+ * @param username - the username of the user the proof was made for
+ * @param proof - the gzip-compressed proof
+ * @param previous - the stringified ObjectID of the proof being built on
+ * @param degree - the degree of separation proven
+ * @param phrase_hash - the phrase hash output by the proof
+ * @param auth_hash - the auth hash output by the proof
+ * @return status:
+ *             * 201 if success
+ *             * 400 if the previous OID is malformed or the previous proof is not one degree lower
+ *             * 403 if the previous proof was not made by an active relationship of the user
+ *             * 404 if the phrase or previous proof is not found
+ *             * 409 if the degree proof already exists
+ *             * 500 if db fails or other unknown issue
+ */
+async fn store_degree_proof(
+    db: &GrapevineDB,
+    events: &ProofEvents,
+    username: &String,
+    proof: Vec<u8>,
+    previous: &String,
+    degree: u8,
+    phrase_hash: [u8; 32],
+    auth_hash: [u8; 32],
+) -> Result<Status, GrapevineResponse> {
     // get the phrase oid from the hash
     let phrase_oid = match db.get_phrase_by_hash(&phrase_hash).await {
         Ok(phrase) => phrase,
@@ -259,8 +647,29 @@ pub async fn degree_proof(
     };
 
     // get user doc
-    let user = db.get_user(&user.0).await.unwrap();
+    let user = db.get_user(username).await.unwrap();
+    // the preceding proof must be made by a user that shared their auth secret with this user
+    let preceding = match ObjectId::from_str(previous) {
+        Ok(preceding) => preceding,
+        Err(_) => return Err(GrapevineError::SerdeError(String::from("ObjectId")).into()),
+    };
+    let (owner, preceding_degree) = db.get_proof_owner(&preceding).await?;
+    let (_, active) = db
+        .check_relationship_exists(&owner, &user.id.unwrap())
+        .await?;
+    if !active {
+        return Err(GrapevineError::PrecedingNotRelationship(previous.clone()).into());
+    }
+    // the proof must extend the preceding proof by exactly one degree
+    if preceding_degree.checked_add(1) != Some(degree) {
+        return Err(GrapevineError::ProofChainBroken(
+            degree,
+            format!("preceding proof is degree {}", preceding_degree),
+        )
+        .into());
+    }
     // @TODO: needs to delete a previous proof by same user on same phrase hash if exists, including removing from last proof's previous field
+    let content_hash = proof_content_hash(&proof);
     // build DegreeProof struct
     let proof_doc = DegreeProof {
         id: None,
@@ -268,35 +677,26 @@ pub async fn degree_proof(
         phrase: Some(phrase_oid),
         auth_hash: Some(auth_hash),
         user: Some(user.id.unwrap()),
-        degree: Some(request.degree),
+        degree: Some(degree),
         ciphertext: None,
-        proof: Some(request.proof.clone()),
-        preceding: Some(ObjectId::from_str(&request.previous).unwrap()),
+        proof: Some(proof),
+        preceding: Some(preceding),
         proceeding: Some(vec![]),
+        expires_at: proof_expiry(),
+        content_hash: Some(content_hash),
     };
 
     // check to see that degree proof doesn't already exist between two accounts
-    match db.check_degree_exists(&proof_doc).await {
-        Ok(exists) => match exists {
-            true => {
-                return Err(GrapevineResponse::Conflict(ErrorMessage(
-                    Some(GrapevineError::DegreeProofExists),
-                    None,
-                )))
-            }
-            false => (),
-        },
-        Err(e) => {
-            return Err(GrapevineResponse::InternalError(ErrorMessage(
-                Some(e),
-                None,
-            )));
-        }
+    if db.check_degree_exists(&proof_doc).await? {
+        return Err(GrapevineError::DegreeProofExists.into());
     }
 
     // add proof to db and update references
     match db.add_proof(&user.id.unwrap(), &proof_doc).await {
-        Ok(_) => Ok(Status::Created),
+        Ok(oid) => {
+            notify_relationships(db, events, &user, oid, degree).await;
+            Ok(Status::Created)
+        }
         Err(e) => {
             println!("Error adding proof: {:?}", e);
             Err(GrapevineResponse::InternalError(ErrorMessage(
@@ -309,134 +709,1337 @@ pub async fn degree_proof(
     }
 }
 
-/// GET REQUESTS ///
+/**
+ * Look up a proof previously stored with the same compressed bytes as a submission
+ *
+ * @param db - the database to search
+ * @param username - the username of the user submitting the proof
+ * @param proof - the gzip-compressed proof being submitted
+ * @return - the id, user, and phrase of the existing proof if the user already submitted it, or
+ *           DegreeProofExists if a different user did
+ */
+async fn find_resubmitted_proof(
+    db: &GrapevineDB,
+    username: &String,
+    proof: &Vec<u8>,
+) -> Result<Option<DegreeProof>, GrapevineError> {
+    let content_hash = proof_content_hash(proof);
+    let existing = match db.get_proof_by_content_hash(&content_hash).await? {
+        Some(existing) => existing,
+        None => return Ok(None),
+    };
+    let user = match db.get_user(username).await {
+        Some(user) => user,
+        None => return Err(GrapevineError::UserNotFound(username.clone())),
+    };
+    match existing.user == user.id {
+        true => Ok(Some(existing)),
+        false => Err(GrapevineError::DegreeProofExists),
+    }
+}
 
 /**
- * Return a list of all available (new) degree proofs from existing connections that a user can
- * build from
+ * Notify every user with an active relationship from the prover that a new proof is available
+ * @notice: delivery is best effort, so failing to look up relationships does not fail the request
  *
- * @param username - the username to look up the available proofs for
- * @return - a vector of stringified OIDs of available proofs to use with get_proof_with_params
- *           route (empty if none)
- * @return status:
- *         - 200 if successful retrieval
- *         - 401 if signature mismatch or nonce mismatch
- *         - 404 if user not found
- *         - 500 if db fails or other unknown issue
+ * @param db - the database the proof was stored in
+ * @param events - the channels to publish the event on
+ * @param user - the user that made the proof
+ * @param proof - the ObjectID of the new proof
+ * @param degree - the degree of separation of the new proof
  */
-#[get("/available")]
-pub async fn get_available_proofs(
-    user: AuthenticatedUser,
-    db: &State<GrapevineDB>,
-) -> Result<Json<Vec<String>>, Status> {
-    Ok(Json(db.find_available_degrees(user.0).await))
+async fn notify_relationships(
+    db: &GrapevineDB,
+    events: &ProofEvents,
+    user: &User,
+    proof: ObjectId,
+    degree: u8,
+) {
+    let recipients = match db
+        .get_active_relationship_recipients(&user.id.unwrap())
+        .await
+    {
+        Ok(recipients) => recipients,
+        Err(e) => {
+            println!("Error finding relationships to notify: {:?}", e);
+            return;
+        }
+    };
+    let event = AvailableProofEvent {
+        proof: proof.to_hex(),
+        username: user.username.clone().unwrap(),
+        degree,
+    };
+    events.publish(&recipients, &event);
 }
 
 /**
- * Returns all the information needed to construct a proof of degree of separation from a given user
+ * Update the description of a phrase created by the user
  *
- * @param oid - the ObjectID of the proof to retrieve
- * @param username - the username to retrieve encrypted auth secret for when proving relationship
- * @return - a ProvingData struct containing:
- *         * degree: the separation degree of the returned proof
- *         * proof: the gzip-compressed fold proof
- *         * username: the username of the proof creator
- *         * ephemeral_key: the ephemeral pubkey that can be combined with the requesting user's
- *           private key to derive returned proof creator's auth secret decryption key
- *         * ciphertext: the encrypted auth secret
+ * @param phrase_index - the index of the phrase to update
+ * @param request - the PhraseDescriptionRequest containing:
+ *             * description: the new description of the phrase
  * @return status:
- *         - 200 if successful retrieval
- *         - 401 if signature mismatch or nonce mismatch
- *         - 404 if username or proof not found
- *         - 500 if db fails or other unknown issue
+ *             * 200 if successful update
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 403 if the user is not the origin of the phrase
+ *             * 404 if user or phrase not found
+ *             * 500 if db fails or other unknown issue
  */
-#[get("/params/<oid>")]
-pub async fn get_proof_with_params(
+#[post(
+    "/phrase/<phrase_index>/description",
+    format = "json",
+    data = "<request>"
+)]
+pub async fn update_phrase_description(
     user: AuthenticatedUser,
-    oid: String,
-    db: &State<GrapevineDB>,
-) -> Result<Json<ProvingData>, GrapevineResponse> {
-    let oid = ObjectId::from_str(&oid).unwrap();
-    match db.get_proof_and_data(user.0, oid).await {
-        Some(data) => Ok(Json(data)),
-        None => Err(GrapevineResponse::NotFound(format!(
-            "No proof found with oid {}",
-            oid
-        ))),
+    phrase_index: u32,
+    request: Json<PhraseDescriptionRequest>,
+    db: TenantDB,
+    cache: &State<ProofCache>,
+) -> Result<Status, GrapevineResponse> {
+    // get user doc
+    let user = db.get_user(&user.0).await.unwrap();
+    // update the description if the user is the origin of the phrase
+    match db
+        .update_phrase_description(&user.id.unwrap(), phrase_index, request.description.clone())
+        .await
+    {
+        Ok(_) => {
+            // cached proofs hold the old description
+            cache.invalidate();
+            Ok(Status::Ok)
+        }
+        Err(e) => match e {
+            GrapevineError::PhraseNotFound => Err(GrapevineResponse::NotFound(format!(
+                "No phrase found with id {}",
+                phrase_index
+            ))),
+            GrapevineError::NotPhraseOrigin => {
+                Err(GrapevineResponse::Forbidden(ErrorMessage(Some(e), None)))
+            }
+            _ => Err(GrapevineResponse::InternalError(ErrorMessage(
+                Some(e),
+                None,
+            ))),
+        },
     }
 }
 
 /**
- * Get all created phrases
+ * Replace the tags of a phrase created by the user
+ *
+ * @param phrase_index - the index of the phrase to update
+ * @param request - the PhraseTagsRequest containing:
+ *             * tags: the new tags of the phrase
+ * @return status:
+ *             * 200 if successful update
+ *             * 400 if the tags are invalid
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 403 if the user is not the origin of the phrase
+ *             * 404 if user or phrase not found
+ *             * 500 if db fails or other unknown issue
  */
-#[get("/known")]
-pub async fn get_known_phrases(
+#[post("/phrase/<phrase_index>/tags", format = "json", data = "<request>")]
+pub async fn update_phrase_tags(
     user: AuthenticatedUser,
-    db: &State<GrapevineDB>,
-) -> Result<Json<Vec<DegreeData>>, GrapevineResponse> {
-    match db.get_known(user.0).await {
-        Some(proofs) => Ok(Json(proofs)),
-        None => Err(GrapevineResponse::InternalError(ErrorMessage(
-            Some(GrapevineError::MongoError(String::from(
-                "Error retrieving degrees in db",
-            ))),
-            None,
+    phrase_index: u32,
+    request: Json<PhraseTagsRequest>,
+    db: TenantDB,
+) -> Result<Status, GrapevineResponse> {
+    validate_tags(&request.tags)?;
+    // get user doc
+    let user = db.get_user(&user.0).await.unwrap();
+    // update the tags if the user is the origin of the phrase
+    match db
+        .update_phrase_tags(&user.id.unwrap(), phrase_index, request.tags.clone())
+        .await
+    {
+        Ok(_) => Ok(Status::Ok),
+        Err(GrapevineError::PhraseNotFound) => Err(GrapevineResponse::NotFound(format!(
+            "No phrase found with id {}",
+            phrase_index
         ))),
+        Err(e) => Err(e.into()),
     }
 }
 
 /**
- * Get total number of connections and
+ * Set who can see a phrase created by the user
+ *
+ * @param phrase_index - the index of the phrase to update
+ * @param request - the PhraseVisibilityRequest containing:
+ *             * visibility: public, or connections-only within a number of degrees of separation
+ * @return status:
+ *             * 200 if successful update
+ *             * 400 if the visibility is connections-only within 0 degrees
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 403 if the user is not the origin of the phrase
+ *             * 404 if user or phrase not found
+ *             * 500 if db fails or other unknown issue
  */
-#[get("/connections/<phrase_index>")]
-pub async fn get_phrase_connections(
+#[post(
+    "/phrase/<phrase_index>/visibility",
+    format = "json",
+    data = "<request>"
+)]
+pub async fn update_phrase_visibility(
     user: AuthenticatedUser,
     phrase_index: u32,
-    db: &State<GrapevineDB>,
-) -> Result<Json<(u64, Vec<u64>)>, GrapevineResponse> {
-    // check if phrase exists in db
-    match db.get_phrase_by_index(phrase_index).await {
-        Ok(_) => (),
-        Err(e) => match e {
-            GrapevineError::PhraseNotFound => {
-                return Err(GrapevineResponse::NotFound(format!(
-                    "No phrase found with id {}",
-                    phrase_index
-                )));
-            }
-            _ => {
-                return Err(GrapevineResponse::InternalError(ErrorMessage(
-                    Some(e),
-                    None,
-                )))
-            }
-        },
+    request: Json<PhraseVisibilityRequest>,
+    db: TenantDB,
+) -> Result<Status, GrapevineResponse> {
+    // the origin is always 1 degree from the phrase
+    if request.visibility == PhraseVisibility::Connections(0) {
+        return Err(GrapevineError::InvalidVisibility(String::from(
+            "connections-only phrases must be visible to at least 1 degree",
+        ))
+        .into());
     }
-
-    // retrieve all connections for the given phrase
-    match db.get_phrase_connections(user.0, phrase_index).await {
-        Some(connection_data) => Ok(Json(connection_data)),
-        None => Err(GrapevineResponse::InternalError(ErrorMessage(
-            Some(GrapevineError::MongoError(String::from(
-                "Error retrieving degrees in db",
-            ))),
-            None,
+    // get user doc
+    let user = db.get_user(&user.0).await.unwrap();
+    // update the visibility if the user is the origin of the phrase
+    match db
+        .update_phrase_visibility(&user.id.unwrap(), phrase_index, request.visibility)
+        .await
+    {
+        Ok(_) => Ok(Status::Ok),
+        Err(GrapevineError::PhraseNotFound) => Err(GrapevineResponse::NotFound(format!(
+            "No phrase found with id {}",
+            phrase_index
         ))),
+        Err(e) => Err(e.into()),
     }
 }
 
 /**
- * Get all info about a phrase
+ * Replace the encrypted phrase stored with the user's degree 1 proof of a phrase, so phrases stay
+ * readable after the user rotates their keypair
+ *
+ * @param phrase_index - the index of the phrase to update
+ * @param request - the PhraseCiphertextRequest containing:
+ *             * ciphertext: the phrase encrypted under the user's new phrase key
+ * @return status:
+ *             * 200 if successful update
+ *             * 400 if deserialization fails
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 404 if the phrase is not found or the user has no degree 1 proof of it
+ *             * 500 if db fails or other unknown issue
  */
-#[get("/phrase/<phrase_index>")]
-pub async fn get_phrase(
+#[post(
+    "/phrase/<phrase_index>/ciphertext",
+    format = "json",
+    data = "<request>"
+)]
+pub async fn update_phrase_ciphertext(
     user: AuthenticatedUser,
     phrase_index: u32,
-    db: &State<GrapevineDB>,
+    request: Json<PhraseCiphertextRequest>,
+    db: TenantDB,
+) -> Result<Status, GrapevineResponse> {
+    // get user doc
+    let user = db.get_user(&user.0).await.unwrap();
+    db.update_phrase_ciphertext(&user.id.unwrap(), phrase_index, &request.ciphertext)
+        .await?;
+    Ok(Status::Ok)
+}
+
+/**
+ * Record a commitment to a phrase without revealing the phrase, so the user can later show they
+ * knew it at the time of the commitment
+ *
+ * @param request - the PhraseCommitRequest containing:
+ *             * commitment: the poseidon hash of the phrase hash and a blinding factor
+ * @return status:
+ *             * 201 if the commitment was recorded
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 409 if the commitment was already recorded
+ *             * 500 if db fails or other unknown issue
+ */
+#[post("/phrase/commit", format = "json", data = "<request>")]
+pub async fn commit_phrase(
+    user: AuthenticatedUser,
+    request: Json<PhraseCommitRequest>,
+    db: TenantDB,
+) -> Result<GrapevineResponse, GrapevineResponse> {
+    let user = db.get_user(&user.0).await.unwrap();
+    db.create_phrase_commitment(&user.id.unwrap(), &request.commitment)
+        .await?;
+    Ok(GrapevineResponse::Created(String::from(
+        "Phrase commitment recorded",
+    )))
+}
+
+/**
+ * Reveal the phrase hash behind a commitment the user made. The phrase itself is never sent
+ *
+ * @param request - the PhraseRevealRequest containing:
+ *             * commitment: the commitment being opened
+ *             * phrase_hash: the hash of the committed phrase
+ *             * blinding: the blinding factor the commitment was made with
+ * @return - the phrase hash and when the commitment was made and first revealed
+ * @return status:
+ *             * 200 if the opening matches the commitment
+ *             * 400 if the opening does not match the commitment
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 404 if the user has not made the commitment
+ *             * 500 if db fails or other unknown issue
+ */
+#[post("/phrase/reveal", format = "json", data = "<request>")]
+pub async fn reveal_phrase(
+    user: AuthenticatedUser,
+    request: Json<PhraseRevealRequest>,
+    db: TenantDB,
+) -> Result<Json<PhraseRevealResponse>, GrapevineResponse> {
+    let user = db.get_user(&user.0).await.unwrap();
+    let commitment = db
+        .get_phrase_commitment(&user.id.unwrap(), &request.commitment)
+        .await?;
+    if commit_phrase_hash(&request.phrase_hash, &request.blinding)? != request.commitment {
+        return Err(GrapevineError::InvalidCommitment(String::from(
+            "opening does not match the commitment",
+        ))
+        .into());
+    }
+    // keep the time of the first reveal if the commitment is opened again
+    let revealed_at = match commitment.revealed_at {
+        Some(revealed_at) => revealed_at,
+        None => {
+            db.reveal_phrase_commitment(&commitment.id.unwrap(), &request.phrase_hash)
+                .await?
+        }
+    };
+    Ok(Json(PhraseRevealResponse {
+        phrase_hash: request.phrase_hash,
+        committed_at: commitment.committed_at.unwrap().timestamp_millis(),
+        revealed_at: revealed_at.timestamp_millis(),
+    }))
+}
+
+/**
+ * Deactivate a degree proof created by the user, flagging all proofs built on top of it
+ *
+ * @param oid - the stringified ObjectID of the proof to deactivate
+ * @return - the number of downstream proofs that were flagged as inactive
+ * @return status:
+ *             * 200 if successful deactivation
+ *             * 400 if oid cannot be parsed
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 403 if proof not owned by user
+ *             * 404 if user or proof not found
+ *             * 500 if db fails or other unknown issue
+ */
+#[post("/deactivate/<oid>")]
+pub async fn deactivate_proof(
+    user: AuthenticatedUser,
+    oid: String,
+    db: TenantDB,
+    cache: &State<ProofCache>,
+) -> Result<Json<u64>, GrapevineResponse> {
+    let proof_oid = match ObjectId::from_str(&oid) {
+        Ok(oid) => oid,
+        Err(_) => {
+            return Err(GrapevineResponse::BadRequest(ErrorMessage(
+                Some(GrapevineError::SerdeError(String::from("ObjectId"))),
+                None,
+            )))
+        }
+    };
+    // get user doc
+    let user = db.get_user(&user.0).await.unwrap();
+    // flag the proof and its dependents as inactive
+    match db.deactivate_proof(&user.id.unwrap(), &proof_oid).await {
+        Ok(flagged) => {
+            // cached proofs may now be inactive
+            cache.invalidate();
+            Ok(Json(flagged))
+        }
+        Err(e) => match e {
+            GrapevineError::DegreeProofNotFound => Err(GrapevineResponse::NotFound(format!(
+                "No proof found with oid {}",
+                oid
+            ))),
+            GrapevineError::NotProofOwner => {
+                Err(GrapevineResponse::Forbidden(ErrorMessage(Some(e), None)))
+            }
+            _ => Err(GrapevineResponse::InternalError(ErrorMessage(
+                Some(e),
+                None,
+            ))),
+        },
+    }
+}
+
+/**
+ * Re-serialize a batch of stored degree proofs in the current proof format. Each proof is
+ * decompressed and verified, then rewritten only if its compressed bytes changed, so running the
+ * migration again is a no-op.
+ *
+ * @param after - the stringified OID to resume after (start of the collection if none)
+ * @param limit - the maximum number of proofs to process in this batch (default 100)
+ * @return - a ProofMigrationResponse containing:
+ *         * processed: the number of proofs read
+ *         * migrated: the number of proofs rewritten
+ *         * failed: the stringified OIDs of proofs that could not be read or verified
+ *         * last: the stringified OID to pass as `after` for the next batch (none when done)
+ * @return status:
+ *         - 200 if successful migration of the batch
+ *         - 400 if the after OID is malformed
+ *         - 401 if the admin token is wrong
+ *         - 403 if admin routes are disabled
+ *         - 500 if db fails or other unknown issue
+ */
+#[post("/admin/migrate?<after>&<limit>")]
+pub async fn migrate_proofs(
+    _admin: Admin,
+    after: Option<String>,
+    limit: Option<i64>,
+    db: TenantDB,
+    cache: &State<ProofCache>,
+) -> Result<Json<ProofMigrationResponse>, GrapevineResponse> {
+    let after = match after.map(|oid| ObjectId::from_str(&oid)).transpose() {
+        Ok(after) => after,
+        Err(_) => {
+            return Err(GrapevineResponse::BadRequest(ErrorMessage(
+                Some(GrapevineError::SerdeError(String::from("ObjectId"))),
+                None,
+            )))
+        }
+    };
+    let proofs = match db.get_proof_batch(after, limit.unwrap_or(100)).await {
+        Ok(proofs) => proofs,
+        Err(e) => {
+            return Err(GrapevineResponse::InternalError(ErrorMessage(
+                Some(e),
+                None,
+            )))
+        }
+    };
+    let last = proofs.last().map(|proof| proof.id.unwrap());
+
+    // verify and re-serialize off of the async runtime
+    let reserialized = rocket::tokio::task::spawn_blocking(move || {
+        proofs
+            .into_iter()
+            .map(|proof| {
+                let oid = proof.id.unwrap();
+                let (stored, degree) = match (proof.proof, proof.degree) {
+                    (Some(stored), Some(degree)) => (stored, degree),
+                    _ => return (oid, Err(())),
+                };
+                let decompressed = match try_decompress_proof(&stored) {
+                    Ok(decompressed) => decompressed,
+                    Err(_) => return (oid, Err(())),
+                };
+                let iterations = steps_for_degree(degree as usize);
+                if verify_nova_proof(&decompressed, &*PUBLIC_PARAMS, iterations).is_err() {
+                    return (oid, Err(()));
+                }
+                let recompressed = compress_proof(&decompressed);
+                match recompressed == stored {
+                    true => (oid, Ok(None)),
+                    false => (oid, Ok(Some(recompressed))),
+                }
+            })
+            .collect::<Vec<(ObjectId, Result<Option<Vec<u8>>, ()>)>>()
+    })
+    .await;
+    let reserialized = match reserialized {
+        Ok(reserialized) => reserialized,
+        Err(_) => {
+            return Err(GrapevineResponse::InternalError(ErrorMessage(
+                Some(GrapevineError::InternalError),
+                None,
+            )))
+        }
+    };
+
+    // write back the proofs whose serialization changed
+    let mut response = ProofMigrationResponse {
+        processed: reserialized.len() as u32,
+        migrated: 0,
+        failed: vec![],
+        last: last.map(|oid| oid.to_string()),
+    };
+    for (oid, result) in reserialized {
+        match result {
+            Ok(Some(bytes)) => match db.update_proof_bytes(oid, bytes).await {
+                Ok(_) => response.migrated += 1,
+                Err(e) => {
+                    tracing::warn!(proof = %oid, error = %e, "failed to rewrite migrated proof");
+                    response.failed.push(oid.to_string());
+                }
+            },
+            Ok(None) => (),
+            Err(_) => {
+                tracing::warn!(proof = %oid, "failed to verify proof for migration");
+                response.failed.push(oid.to_string());
+            }
+        }
+    }
+    if response.migrated > 0 {
+        // cached proofs may hold the old serialization
+        cache.invalidate();
+    }
+    tracing::info!(
+        processed = response.processed,
+        migrated = response.migrated,
+        failed = response.failed.len(),
+        last = ?response.last,
+        "migrated proof batch"
+    );
+    Ok(Json(response))
+}
+
+/**
+ * Restore degree proofs from a backup written by export_proofs, replacing any stored proof with
+ * the same ObjectID
+ * @notice - the backup is spooled to a temporary file and its footer checked before any proof is
+ *           written, so a malformed backup leaves the stored proofs untouched
+ *
+ * @param data - the backup: magic bytes, length prefixed bson proof documents, then a footer with
+ *               the record count and checksum
+ * @return - the number of proofs restored
+ * @return status:
+ *         - 200 if every proof was restored and the footer matches
+ *         - 400 if the backup is malformed, truncated, or fails the count or checksum
+ *         - 401 if the admin token is wrong
+ *         - 403 if admin routes are disabled
+ *         - 413 if the backup exceeds the configured limit
+ *         - 500 if db fails or other unknown issue
+ */
+#[post("/admin/import", data = "<data>")]
+pub async fn import_proofs(
+    _admin: Admin,
+    data: Data<'_>,
+    db: TenantDB,
+    cache: &State<ProofCache>,
+    limits: &State<ProofLimits>,
+) -> Result<Json<u64>, GrapevineResponse> {
+    let path = std::env::temp_dir().join(format!("grapevine-import-{}", uuid::Uuid::new_v4()));
+    let result = match data.open(limits.backup.bytes()).into_file(&path).await {
+        Ok(file) if file.is_complete() => restore_spooled_backup(&path, &db, cache).await,
+        Ok(_) => Err(GrapevineResponse::TooLarge(format!(
+            "Backup exceeds {} bytes",
+            limits.backup
+        ))),
+        Err(e) => {
+            tracing::error!(error = %e, "failed to spool backup");
+            Err(GrapevineResponse::InternalError(ErrorMessage(
+                Some(GrapevineError::InternalError),
+                None,
+            )))
+        }
+    };
+    _ = rocket::tokio::fs::remove_file(&path).await;
+    result
+}
+
+/**
+ * Check a spooled backup end to end, then restore its proofs
+ *
+ * @param path - the temporary file the backup was written to
+ * @param db - the database to restore the proofs to
+ * @param cache - the proof cache to invalidate once proofs are written
+ * @return - the number of proofs restored
+ */
+async fn restore_spooled_backup(
+    path: &std::path::Path,
+    db: &GrapevineDB,
+    cache: &ProofCache,
+) -> Result<Json<u64>, GrapevineResponse> {
+    let open = || async {
+        rocket::tokio::fs::File::open(path)
+            .await
+            .map(rocket::tokio::io::BufReader::new)
+            .map_err(|e| {
+                tracing::error!(error = %e, "failed to read spooled backup");
+                GrapevineError::InternalError
+            })
+    };
+    // nothing is written until the whole backup passes its footer check
+    let checked = match open().await {
+        Ok(mut file) => restore_backup(&mut file, None).await,
+        Err(e) => Err(e),
+    };
+    let result = match checked {
+        Ok(_) => match open().await {
+            Ok(mut file) => {
+                let result = restore_backup(&mut file, Some(db)).await;
+                // a db failure part way through can still leave some proofs restored
+                cache.invalidate();
+                result
+            }
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(count) => {
+            tracing::info!(count, "restored proofs from backup");
+            Ok(Json(count))
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to restore proofs from backup");
+            Err(GrapevineResponse::from(e))
+        }
+    }
+}
+
+/**
+ * Read proof documents out of a backup, optionally writing them to the database
+ *
+ * @param stream - the backup being read
+ * @param db - the database to restore the proofs to, or None to only check the backup
+ * @return - the number of proofs in the backup, or InvalidBackup if the backup is malformed
+ */
+async fn restore_backup<R: AsyncRead + Unpin>(
+    stream: &mut R,
+    db: Option<&GrapevineDB>,
+) -> Result<u64, GrapevineError> {
+    let truncated = |_| GrapevineError::InvalidBackup(String::from("backup is truncated"));
+    let mut magic = [0u8; 8];
+    stream.read_exact(&mut magic).await.map_err(truncated)?;
+    if magic != BACKUP_MAGIC {
+        return Err(GrapevineError::InvalidBackup(String::from(
+            "not a proof backup",
+        )));
+    }
+    let mut digest = BackupDigest::new();
+    loop {
+        let length = stream.read_u32_le().await.map_err(truncated)?;
+        if length == 0 {
+            let count = stream.read_u64_le().await.map_err(truncated)?;
+            let mut checksum = [0u8; 32];
+            stream.read_exact(&mut checksum).await.map_err(truncated)?;
+            return digest.verify(count, &checksum);
+        }
+        if length > MAX_BACKUP_RECORD {
+            return Err(GrapevineError::InvalidBackup(format!(
+                "record of {} bytes is too large",
+                length
+            )));
+        }
+        let mut bytes = vec![0u8; length as usize];
+        stream.read_exact(&mut bytes).await.map_err(truncated)?;
+        digest.update(&bytes);
+        let document = match Document::from_reader(&bytes[..]) {
+            Ok(document) => document,
+            Err(e) => return Err(GrapevineError::InvalidBackup(e.to_string())),
+        };
+        if document.get_object_id("_id").is_err() {
+            return Err(GrapevineError::InvalidBackup(String::from(
+                "proof document has no ObjectId",
+            )));
+        }
+        if let Some(db) = db {
+            db.restore_proof_document(document).await?;
+        }
+    }
+}
+
+/**
+ * Verify a degree proof against a set of phrase hashes without revealing which phrase the proof
+ * was made for
+ *
+ * @param data - binary serialized VerifyInSetRequest containing:
+ *             * proof: the compressed degree proof
+ *             * degree: the degree of separation claimed by the prover
+ *             * allowed: the set of phrase hashes the proof may have been made for
+ * @return - whether the proof is valid for one of the phrase hashes and the claimed degree
+ * @return status:
+ *             * 200 if verification completed
+ *             * 400 if deserialization fails
+ *             * 413 if the request body is too large
+ *             * 500 if verification panics
+ */
+#[post("/verify-in-set", data = "<data>")]
+pub async fn verify_proof_in_set(
+    data: Data<'_>,
+    limits: &State<ProofLimits>,
+) -> Result<Json<VerifyInSetResponse>, GrapevineResponse> {
+    // stream in data
+    let buffer = read_body(data, limits.degree).await?;
+    let request = match decode_request::<VerifyInSetRequest>(&buffer) {
+        Ok(req) => req,
+        Err(_) => {
+            return Err(GrapevineResponse::BadRequest(ErrorMessage(
+                Some(GrapevineError::SerdeError(String::from(
+                    "VerifyInSetRequest",
+                ))),
+                None,
+            )))
+        }
+    };
+    let proof = match try_decompress_proof(&request.proof) {
+        Ok(proof) => proof,
+        Err(_) => {
+            return Err(GrapevineResponse::BadRequest(ErrorMessage(
+                Some(GrapevineError::SerdeError(String::from("NovaProof"))),
+                None,
+            )))
+        }
+    };
+    // an invalid proof is reported the same as a proof outside of the set
+    let (degree, allowed) = (request.degree, request.allowed);
+    let verified = verify_in_pool(move || {
+        verify_nova_proof_in_set(&proof, &*PUBLIC_PARAMS, degree, &allowed).unwrap_or(false)
+    })
+    .await?;
+    Ok(Json(VerifyInSetResponse {
+        verified,
+        degree: request.degree,
+    }))
+}
+
+/// GET REQUESTS ///
+
+/**
+ * Scan every stored degree proof for metadata that does not match the proof outputs
+ * @notice - verifies every proof in the database, so this can take a long time
+ *
+ * @return - the stringified OIDs of proofs whose stored degree, phrase, or auth hash do not
+ *           match the proof (or whose proof fails to verify)
+ * @return status:
+ *         - 200 if successful scan
+ *         - 401 if the admin token is wrong
+ *         - 403 if admin routes are disabled
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/admin/integrity")]
+pub async fn scan_proof_integrity(
+    _admin: Admin,
+    db: TenantDB,
+) -> Result<Json<Vec<String>>, GrapevineResponse> {
+    let proofs = match db.get_all_proofs_with_phrase_hash().await {
+        Ok(proofs) => proofs,
+        Err(e) => {
+            return Err(GrapevineResponse::InternalError(ErrorMessage(
+                Some(e),
+                None,
+            )))
+        }
+    };
+    // verify off of the async runtime
+    let scan = rocket::tokio::task::spawn_blocking(move || {
+        proofs
+            .iter()
+            .filter_map(|(proof, phrase_hash)| {
+                match verify_degree_proof_metadata(proof, phrase_hash, &*PUBLIC_PARAMS) {
+                    Ok(_) => None,
+                    Err(e) => {
+                        tracing::warn!(proof = ?proof.id, error = %e, "proof integrity mismatch");
+                        Some(proof.id.unwrap().to_string())
+                    }
+                }
+            })
+            .collect::<Vec<String>>()
+    })
+    .await;
+    match scan {
+        Ok(mismatches) => Ok(Json(mismatches)),
+        Err(_) => Err(GrapevineResponse::InternalError(ErrorMessage(
+            Some(GrapevineError::InternalError),
+            None,
+        ))),
+    }
+}
+
+/**
+ * Stream every stored degree proof out as a backup that can be restored with import_proofs
+ * @notice - if the database fails partway through, the stream ends without a footer so the
+ *           backup is rejected as truncated
+ *
+ * @return - the backup: magic bytes, then each proof document as a u32 little endian length and
+ *           bson bytes, then a footer of a zero length, the u64 record count, and the sha3-256
+ *           checksum of the records
+ * @return status:
+ *         - 200 if the export started
+ *         - 401 if the admin token is wrong
+ *         - 403 if admin routes are disabled
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/admin/export")]
+pub async fn export_proofs(
+    _admin: Admin,
+    db: TenantDB,
+) -> Result<ByteStream![Vec<u8>], GrapevineResponse> {
+    let mut cursor = db.get_proof_documents().await?;
+    Ok(ByteStream! {
+        yield BACKUP_MAGIC.to_vec();
+        let mut digest = BackupDigest::new();
+        while let Some(result) = cursor.next().await {
+            let mut bytes = vec![];
+            match result.map(|document| document.to_writer(&mut bytes)) {
+                Ok(Ok(_)) => yield digest.record(&bytes),
+                Ok(Err(e)) => {
+                    tracing::error!(error = %e, "failed to serialize proof for export");
+                    return;
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "failed to read proof for export");
+                    return;
+                }
+            }
+        }
+        tracing::info!(count = digest.count(), "exported proofs");
+        yield digest.footer();
+    })
+}
+
+/**
+ * Return a list of all available (new) degree proofs from existing connections that a user can
+ * build from
+ *
+ * @param origin - if set, only proofs on phrases created by this user, lowest degree first, so the
+ *                 first proof is the shortest path to the origin
+ * @return - a vector of stringified OIDs of available proofs to use with get_proof_with_params
+ *           route (empty if none)
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 401 if signature mismatch or nonce mismatch
+ *         - 404 if user or origin not found
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/available?<origin>")]
+pub async fn get_available_proofs(
+    user: AuthenticatedUser,
+    origin: Option<String>,
+    db: TenantDB,
+) -> Result<Json<Vec<String>>, GrapevineResponse> {
+    let origin = match origin {
+        Some(origin) => match db.get_user(&origin).await {
+            Some(origin) => Some(origin.id.unwrap()),
+            None => return Err(GrapevineError::UserNotFound(origin).into()),
+        },
+        None => None,
+    };
+    Ok(Json(db.find_available_degrees(user.0, origin).await))
+}
+
+/**
+ * Open a websocket that pushes an event whenever a relationship creates a proof the user can
+ * build from, as an alternative to polling GET /proof/available
+ * @notice: mounted at /ws/proofs
+ *
+ * @return - a websocket sending a JSON AvailableProofEvent for each new proof, containing:
+ *         * proof: the stringified OID of the new proof
+ *         * username: the username of the relationship that made the proof
+ *         * degree: the separation degree of the new proof
+ * @return status:
+ *         - 101 if the websocket is opened
+ *         - 401 if signature mismatch or nonce mismatch
+ *         - 404 if user not found
+ */
+#[get("/proofs")]
+pub async fn proof_events(
+    user: AuthenticatedUser,
+    ws: WebSocket,
+    db: TenantDB,
+    events: &State<ProofEvents>,
+) -> Result<Channel<'static>, GrapevineResponse> {
+    let oid = match db.get_user(&user.0).await {
+        Some(user) => user.id.unwrap(),
+        None => {
+            return Err(GrapevineResponse::NotFound(format!(
+                "User {} not found",
+                user.0
+            )))
+        }
+    };
+    let mut receiver = events.subscribe(&oid);
+    Ok(ws.channel(move |mut stream| {
+        Box::pin(async move {
+            loop {
+                rocket::tokio::select! {
+                    event = receiver.recv() => match event {
+                        Ok(event) => {
+                            let message = serde_json::to_string(&event).unwrap();
+                            stream.send(Message::Text(message)).await?;
+                        }
+                        // skip events dropped while the client was behind
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    },
+                    // ignore anything the client sends until it closes the socket
+                    message = stream.next() => match message {
+                        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                        Some(Ok(_)) => continue,
+                    },
+                }
+            }
+            Ok(())
+        })
+    }))
+}
+
+/**
+ * Download the compressed bytes of a proof made by the user
+ *
+ * @param oid - the stringified ObjectID of the proof to download
+ * @param X-Accept-Proof-Encoding - optional header listing the proof formats the client prefers
+ *        (gzip, zstd, or raw)
+ * @return - the fold proof as an octet stream, in the format named by the X-Proof-Encoding header
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 400 if oid cannot be parsed
+ *         - 401 if signature mismatch or nonce mismatch
+ *         - 403 if the proof was made by another user
+ *         - 404 if the proof is not found
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/<oid>")]
+pub async fn download_proof(
+    user: AuthenticatedUser,
+    oid: String,
+    db: TenantDB,
+    accept: AcceptProofEncoding,
+) -> Result<EncodedProof<(ContentType, Vec<u8>)>, GrapevineResponse> {
+    let proof_oid = match ObjectId::from_str(&oid) {
+        Ok(oid) => oid,
+        Err(_) => {
+            return Err(GrapevineResponse::BadRequest(ErrorMessage(
+                Some(GrapevineError::SerdeError(String::from("ObjectId"))),
+                None,
+            )))
+        }
+    };
+    // get user doc
+    let user = db.get_user(&user.0).await.unwrap();
+    match db.get_owned_proof(&user.id.unwrap(), &proof_oid).await {
+        Ok(proof) => {
+            let proof = encode_for_download(proof, accept.0).await?;
+            Ok(EncodedProof((ContentType::Binary, proof), accept.0))
+        }
+        Err(e) => match e {
+            GrapevineError::DegreeProofNotFound => Err(GrapevineResponse::NotFound(format!(
+                "No proof found with oid {}",
+                oid
+            ))),
+            GrapevineError::NotProofOwner => {
+                Err(GrapevineResponse::Forbidden(ErrorMessage(Some(e), None)))
+            }
+            _ => Err(GrapevineResponse::InternalError(ErrorMessage(
+                Some(e),
+                None,
+            ))),
+        },
+    }
+}
+
+/**
+ * Convert a stored proof to the format negotiated for a download
+ *
+ * @param proof - the gzip compressed proof as stored
+ * @param encoding - the format to send the proof in
+ * @return - the proof in the requested format
+ */
+async fn encode_for_download(
+    proof: Vec<u8>,
+    encoding: ProofEncoding,
+) -> Result<Vec<u8>, GrapevineResponse> {
+    if encoding == ProofEncoding::Gzip {
+        return Ok(proof);
+    }
+    // decompressing and re-encoding is cpu bound, so keep it off of the async runtime
+    match rocket::tokio::task::spawn_blocking(move || reencode_proof(&proof, encoding)).await {
+        Ok(Ok(encoded)) => Ok(encoded),
+        _ => Err(GrapevineResponse::InternalError(ErrorMessage(
+            Some(GrapevineError::SerdeError(String::from("NovaProof"))),
+            None,
+        ))),
+    }
+}
+
+/**
+ * List the proofs built directly on top of a proof made by the user
+ * @notice - ranked after the static routes that share its shape (e.g. /params/<oid>)
+ *
+ * @param oid - the stringified ObjectID of the proof to list the children of
+ * @return - a vector of ProofChild structs containing:
+ *             * oid: the stringified ObjectID of the proof built on top of the given proof
+ *             * username: the user that made the proof
+ *             * degree: the degree of the proof
+ *             * inactive: whether the proof was flagged inactive
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 400 if oid cannot be parsed
+ *         - 401 if signature mismatch or nonce mismatch
+ *         - 403 if the proof was made by another user
+ *         - 404 if the proof is not found
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/<oid>/children", rank = 1)]
+pub async fn get_proof_children(
+    user: AuthenticatedUser,
+    oid: String,
+    db: TenantDB,
+) -> Result<Json<Vec<ProofChild>>, GrapevineResponse> {
+    let proof_oid = match ObjectId::from_str(&oid) {
+        Ok(oid) => oid,
+        Err(_) => {
+            return Err(GrapevineResponse::BadRequest(ErrorMessage(
+                Some(GrapevineError::SerdeError(String::from("ObjectId"))),
+                None,
+            )))
+        }
+    };
+    // get user doc
+    let user = db.get_user(&user.0).await.unwrap();
+    match db.get_proceeding(&user.id.unwrap(), &proof_oid).await {
+        Ok(children) => Ok(Json(children)),
+        Err(GrapevineError::DegreeProofNotFound) => Err(GrapevineResponse::NotFound(format!(
+            "No proof found with oid {}",
+            oid
+        ))),
+        Err(GrapevineError::NotProofOwner) => Err(GrapevineResponse::Forbidden(ErrorMessage(
+            Some(GrapevineError::NotProofOwner),
+            None,
+        ))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Returns all the information needed to construct a proof of degree of separation from a given user
+ *
+ * @param oid - the ObjectID of the proof to retrieve
+ * @param username - the username to retrieve encrypted auth secret for when proving relationship
+ * @param X-Accept-Proof-Encoding - optional header listing the proof formats the client prefers
+ *        (gzip, zstd, or raw)
+ * @return - a ProvingData struct containing:
+ *         * degree: the separation degree of the returned proof
+ *         * proof: the fold proof, in the format named by the X-Proof-Encoding header
+ *         * username: the username of the proof creator
+ *         * ephemeral_key: the ephemeral pubkey that can be combined with the requesting user's
+ *           private key to derive returned proof creator's auth secret decryption key
+ *         * ciphertext: the encrypted auth secret
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 401 if signature mismatch or nonce mismatch
+ *         - 404 if username or proof not found
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/params/<oid>")]
+pub async fn get_proof_with_params(
+    user: AuthenticatedUser,
+    oid: String,
+    db: TenantDB,
+    cache: &State<ProofCache>,
+    accept: AcceptProofEncoding,
+) -> Result<EncodedProof<Json<ProvingData>>, GrapevineResponse> {
+    let oid = ObjectId::from_str(&oid).unwrap();
+    // get the proof from the cache or fall back to the db
+    let key = (db.name().clone(), oid);
+    let proof = match cache.get(&key) {
+        // expired proofs are filtered out of the db but may still be cached
+        Some(proof) if proof.is_expired() => {
+            return Err(GrapevineResponse::NotFound(format!(
+                "No proof found with oid {}",
+                oid
+            )))
+        }
+        Some(proof) => proof,
+        None => match db.get_proof_details(oid).await {
+            Some(proof) => {
+                cache.insert(key, proof.clone());
+                proof
+            }
+            None => {
+                return Err(GrapevineResponse::NotFound(format!(
+                    "No proof found with oid {}",
+                    oid
+                )))
+            }
+        },
+    };
+    // get the auth secret shared with the requesting user by the proof creator
+    let (ephemeral_key, ciphertext) = match db.get_relationship_keys(user.0, proof.creator).await {
+        Some(keys) => keys,
+        None => {
+            return Err(GrapevineResponse::NotFound(format!(
+                "No relationship found with creator of proof {}",
+                oid
+            )))
+        }
+    };
+    let encoded = encode_for_download(proof.proof, accept.0).await?;
+    let proving_data = ProvingData {
+        description: proof.description,
+        phrase_index: proof.phrase_index,
+        phrase_hash: proof.phrase_hash,
+        degree: proof.degree,
+        proof: encoded,
+        username: proof.username,
+        ephemeral_key,
+        ciphertext,
+    };
+    Ok(EncodedProof(Json(proving_data), accept.0))
+}
+
+/**
+ * Return a summary of every phrase the user has an active proof on
+ *
+ * @return - a vector of phrase summaries sorted by degree ascending, each containing:
+ *         * phrase_index: the index of the phrase
+ *         * description: the description of the phrase
+ *         * degree: the user's degree of separation from the phrase
+ *         * connections: the number of relationships with active proofs on the phrase
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 401 if signature mismatch or nonce mismatch
+ *         - 404 if user not found
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/list")]
+pub async fn get_phrase_list(
+    user: AuthenticatedUser,
+    db: TenantDB,
+) -> Result<Json<Vec<PhraseSummary>>, GrapevineResponse> {
+    match db.get_phrase_summaries(user.0).await {
+        Some(summaries) => Ok(Json(summaries)),
+        None => Err(GrapevineResponse::InternalError(ErrorMessage(
+            Some(GrapevineError::MongoError(String::from(
+                "Error retrieving phrase summaries",
+            ))),
+            None,
+        ))),
+    }
+}
+
+/**
+ * Return every distinct origin (phrase creator) the user has an active proof back to
+ *
+ * @return - a vector of origins sorted by degree ascending, each containing:
+ *         * origin_username: the username of the phrase creator
+ *         * min_degree: the lowest degree of separation the user has from the creator
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 401 if signature mismatch or nonce mismatch
+ *         - 404 if user not found
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/origins")]
+pub async fn get_connected_origins(
+    user: AuthenticatedUser,
+    db: TenantDB,
+) -> Result<Json<Vec<ConnectedOrigin>>, GrapevineResponse> {
+    match db.get_connected_origins(user.0).await {
+        Some(origins) => Ok(Json(origins)),
+        None => Err(GrapevineResponse::InternalError(ErrorMessage(
+            Some(GrapevineError::MongoError(String::from(
+                "Error retrieving connected origins",
+            ))),
+            None,
+        ))),
+    }
+}
+
+/**
+ * Search the descriptions of phrases the user can see (the user or one of their relationships has
+ * a proof on the phrase)
+ *
+ * @param q - the case-insensitive text to search phrase descriptions for
+ * @param limit - the maximum number of phrases to return (default 25)
+ * @return - a vector of matching phrases sorted by index, each containing:
+ *         * phrase_index: the index of the phrase
+ *         * description: the description of the phrase
+ *         * phrase_hash: the hash of the phrase
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 401 if signature mismatch or nonce mismatch
+ *         - 404 if user not found
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/search?<q>&<limit>")]
+pub async fn search_phrases(
+    user: AuthenticatedUser,
+    q: &str,
+    limit: Option<i64>,
+    db: TenantDB,
+) -> Result<Json<Vec<PhraseSearchResult>>, GrapevineResponse> {
+    match db.search_phrases(user.0, q, limit.unwrap_or(25)).await {
+        Some(phrases) => Ok(Json(phrases)),
+        None => Err(GrapevineResponse::InternalError(ErrorMessage(
+            Some(GrapevineError::MongoError(String::from(
+                "Error searching phrases",
+            ))),
+            None,
+        ))),
+    }
+}
+
+/**
+ * List the phrases the user can see (the user or one of their relationships has a proof on the
+ * phrase) that are tagged with a given tag
+ *
+ * @param tag - the tag to list phrases for
+ * @param limit - the maximum number of phrases to return (default 25)
+ * @return - a vector of tagged phrases sorted by index, each containing:
+ *         * phrase_index: the index of the phrase
+ *         * description: the description of the phrase
+ *         * phrase_hash: the hash of the phrase
+ *         * tags: the tags of the phrase
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 401 if signature mismatch or nonce mismatch
+ *         - 404 if user not found
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/phrases/by-tag/<tag>?<limit>")]
+pub async fn get_phrases_by_tag(
+    user: AuthenticatedUser,
+    tag: &str,
+    limit: Option<i64>,
+    db: TenantDB,
+) -> Result<Json<Vec<PhraseSearchResult>>, GrapevineResponse> {
+    match db
+        .get_phrases_by_tag(user.0, tag, limit.unwrap_or(25))
+        .await
+    {
+        Some(phrases) => Ok(Json(phrases)),
+        None => Err(GrapevineResponse::InternalError(ErrorMessage(
+            Some(GrapevineError::MongoError(String::from(
+                "Error listing phrases by tag",
+            ))),
+            None,
+        ))),
+    }
+}
+
+/**
+ * Get all created phrases
+ */
+#[get("/known")]
+pub async fn get_known_phrases(
+    user: AuthenticatedUser,
+    db: TenantDB,
+) -> Result<Json<Vec<DegreeData>>, GrapevineResponse> {
+    match db.get_known(user.0).await {
+        Some(proofs) => Ok(Json(proofs)),
+        None => Err(GrapevineResponse::InternalError(ErrorMessage(
+            Some(GrapevineError::MongoError(String::from(
+                "Error retrieving degrees in db",
+            ))),
+            None,
+        ))),
+    }
+}
+
+/**
+ * Get total number of connections and
+ * @notice connections-only phrases are reported as not found to users too far from them
+ */
+#[get("/connections/<phrase_index>")]
+pub async fn get_phrase_connections(
+    user: AuthenticatedUser,
+    phrase_index: u32,
+    db: TenantDB,
+) -> Result<Json<(u64, Vec<u64>)>, GrapevineResponse> {
+    check_phrase_access(&db, &user.0, phrase_index).await?;
+
+    // retrieve all connections for the given phrase
+    match db.get_phrase_connections(user.0, phrase_index).await {
+        Some(connection_data) => Ok(Json(connection_data)),
+        None => Err(GrapevineResponse::InternalError(ErrorMessage(
+            Some(GrapevineError::MongoError(String::from(
+                "Error retrieving degrees in db",
+            ))),
+            None,
+        ))),
+    }
+}
+
+/**
+ * Get all info about a phrase
+ * @notice connections-only phrases are reported as not found to users too far from them
+ */
+#[get("/phrase/<phrase_index>")]
+pub async fn get_phrase(
+    user: AuthenticatedUser,
+    phrase_index: u32,
+    db: TenantDB,
 ) -> Result<Json<DegreeData>, GrapevineResponse> {
+    check_phrase_access(&db, &user.0, phrase_index).await?;
+    println!("2");
+    // get degree data for this phrase
+    match db.get_phrase_info(&user.0, phrase_index).await {
+        Ok(phrase_data) => Ok(Json(phrase_data)),
+        Err(e) => Err(GrapevineResponse::InternalError(ErrorMessage(
+            Some(e),
+            None,
+        ))),
+    }
+}
+
+/**
+ * Get all info about a phrase along with the user's connections to it in one request
+ * @notice connections-only phrases are reported as not found to users too far from them
+ *
+ * @param phrase_index - the index of the phrase
+ * @return - the phrase info and connections, containing:
+ *         * phrase: the same degree data returned by `/phrase/<phrase_index>`
+ *         * total_connections: the number of relationships with a proof of the phrase
+ *         * connections_by_degree: the number of those relationships at each degree
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 401 if signature mismatch or nonce mismatch
+ *         - 404 if the phrase is not found
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/phrase/<phrase_index>/full")]
+pub async fn get_phrase_full(
+    user: AuthenticatedUser,
+    phrase_index: u32,
+    db: TenantDB,
+) -> Result<Json<PhraseFullResponse>, GrapevineResponse> {
+    check_phrase_access(&db, &user.0, phrase_index).await?;
+    let phrase = db.get_phrase_full(&user.0, phrase_index).await?;
+    Ok(Json(phrase))
+}
+
+/**
+ * Get the user's active proof of a phrase along with the origin of the phrase, so the proof can be
+ * packaged into a credential
+ *
+ * @param phrase_index - the index of the phrase
+ * @return - the proof reference, containing:
+ *         * proof: the stringified oid of the proof (downloadable from `/<oid>`)
+ *         * degree: the degree of separation proven
+ *         * origin: the username of the first user to prove knowledge of the phrase
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 401 if signature mismatch or nonce mismatch
+ *         - 404 if the phrase is not found or the user has no active proof of it
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/phrase/<phrase_index>/proof")]
+pub async fn get_phrase_proof(
+    user: AuthenticatedUser,
+    phrase_index: u32,
+    db: TenantDB,
+) -> Result<Json<PhraseProofResponse>, GrapevineResponse> {
+    let user = db.get_user(&user.0).await.unwrap();
+    let proof = db.get_phrase_proof(&user.id.unwrap(), phrase_index).await?;
+    Ok(Json(proof))
+}
+
+/**
+ * Check that a phrase exists and the user may see it
+ *
+ * @param db - the database to look the phrase up in
+ * @param username - the username of the user requesting the phrase
+ * @param phrase_index - the index of the phrase
+ * @return - NotFound if the phrase does not exist or is hidden from the user
+ */
+async fn check_phrase_access(
+    db: &GrapevineDB,
+    username: &String,
+    phrase_index: u32,
+) -> Result<(), GrapevineResponse> {
     // check if phrase exists in db
-    match db.get_phrase_by_index(phrase_index).await {
-        Ok(_) => (),
+    let phrase = match db.get_phrase_by_index(phrase_index).await {
+        Ok(phrase) => phrase,
         Err(e) => match e {
             GrapevineError::PhraseNotFound => {
                 return Err(GrapevineResponse::NotFound(format!(
@@ -451,14 +2054,31 @@ pub async fn get_phrase(
                 )))
             }
         },
+    };
+    // check that the user may see the phrase
+    let caller = db.get_user(username).await.unwrap().id.unwrap();
+    let visible = db.check_phrase_visible(&caller, &phrase).await?;
+    if !visible {
+        return Err(GrapevineResponse::NotFound(format!(
+            "No phrase found with id {}",
+            phrase_index
+        )));
     }
-    println!("2");
-    // get degree data for this phrase
-    match db.get_phrase_info(&user.0, phrase_index).await {
-        Ok(phrase_data) => Ok(Json(phrase_data)),
-        Err(e) => Err(GrapevineResponse::InternalError(ErrorMessage(
-            Some(e),
-            None,
-        ))),
-    }
+    Ok(())
+}
+
+/**
+ * Return the version and fingerprints of the circuit artifacts the server verifies proofs with
+ *
+ * @return - the circuit info, containing:
+ *         * circuit_version: the version of the circuit
+ *         * r1cs_hash: sha256 hash of the served r1cs
+ *         * params_hash: sha256 hash of the served public params
+ *         * max_degree: the highest degree of separation accepted
+ * @return status:
+ *         - 200 always
+ */
+#[get("/circuit-info")]
+pub async fn get_circuit_info() -> Json<CircuitInfoResponse> {
+    Json(CIRCUIT_INFO.clone())
 }
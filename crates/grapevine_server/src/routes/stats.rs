@@ -0,0 +1,63 @@
+use crate::cache::LeaderboardCache;
+use crate::catchers::{ErrorMessage, GrapevineResponse};
+use crate::guards::TenantDB;
+use crate::mongo::LeaderboardMetric;
+use grapevine_common::errors::GrapevineError;
+use grapevine_common::http::responses::LeaderboardEntry;
+use rocket::serde::json::Json;
+use rocket::State;
+
+/// Leaderboards are computed and cached at this size, then truncated to the requested limit
+pub const MAX_LEADERBOARD_SIZE: i64 = 100;
+
+/// GET REQUESTS ///
+
+/**
+ * Rank the users with the highest score for a metric
+ * @notice - leaderboards are cached briefly (GRAPEVINE_LEADERBOARD_TTL seconds) since computing
+ *           one aggregates over a whole collection
+ *
+ * @param metric - what to rank users by (default connections):
+ *             * connections: active relationships to the user
+ *             * phrases: phrases the user has an active proof on
+ *             * reach: other users with an active proof on a phrase the user created
+ * @param limit - the number of users to return (default 10, at most 100)
+ * @return - a vector of LeaderboardEntry structs containing:
+ *             * username: the username of the ranked user
+ *             * score: the user's value for the metric
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 400 if the metric is not recognized
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/leaderboard?<metric>&<limit>")]
+pub async fn get_leaderboard(
+    metric: Option<&str>,
+    limit: Option<i64>,
+    cache: &State<LeaderboardCache>,
+    db: TenantDB,
+) -> Result<Json<Vec<LeaderboardEntry>>, GrapevineResponse> {
+    let metric = match LeaderboardMetric::parse(metric.unwrap_or("connections")) {
+        Some(metric) => metric,
+        None => {
+            return Err(GrapevineResponse::BadRequest(ErrorMessage(
+                Some(GrapevineError::SerdeError(String::from(
+                    "leaderboard metric (expected connections, phrases, or reach)",
+                ))),
+                None,
+            )))
+        }
+    };
+    let limit = limit.unwrap_or(10).clamp(1, MAX_LEADERBOARD_SIZE) as usize;
+    let key = (db.name().clone(), metric);
+    let mut leaderboard = match cache.get(&key) {
+        Some(leaderboard) => leaderboard,
+        None => {
+            let leaderboard = db.get_leaderboard(metric, MAX_LEADERBOARD_SIZE).await?;
+            cache.insert(key, leaderboard.clone());
+            leaderboard
+        }
+    };
+    leaderboard.truncate(limit);
+    Ok(Json(leaderboard))
+}
@@ -1,21 +1,27 @@
 use crate::catchers::{ErrorMessage, GrapevineResponse};
-use crate::guards::AuthenticatedUser;
-use crate::mongo::GrapevineDB;
+use crate::guards::{Admin, AuthenticatedUser, TenantDB};
+use crate::limits::UserQuotas;
+use crate::utils::{stats_are_fresh, UNICODE_USERNAMES};
 use babyjubjub_rs::{decompress_point, decompress_signature, verify};
+use grapevine_common::auth_secret::AuthSecretEncrypted;
+use grapevine_common::crypto::{decompress_pubkey, recovery_hash};
 use grapevine_common::errors::GrapevineError;
-use grapevine_common::http::requests::GetNonceRequest;
-use grapevine_common::http::{requests::CreateUserRequest, responses::DegreeData};
-use grapevine_common::utils::convert_username_to_fr;
-use grapevine_common::MAX_USERNAME_CHARS;
+use grapevine_common::http::requests::{GetNonceRequest, GuardiansRequest, RecoveryRequest};
+use grapevine_common::http::{
+    requests::CreateUserRequest,
+    responses::{AccountDetailsResponse, DegreeData, MerkleProofResponse, MerkleRootResponse},
+};
+use grapevine_common::utils::{convert_username_to_fr, normalize_username, validate_username};
+use grapevine_common::MAX_GUARDIANS;
 use grapevine_common::{
     http::requests::NewRelationshipRequest,
     models::{Relationship, User},
 };
-use rocket::State;
 
 use num_bigint::{BigInt, Sign};
 use rocket::http::Status;
 use rocket::serde::json::Json;
+use rocket::State;
 
 /// POST REQUESTS ///
 
@@ -27,8 +33,9 @@ use rocket::serde::json::Json;
  *             * pubkey: the public key used to authZ/authN and deriving AES encryption keys
  *             * signature: the signature over the username by pubkey
  * @return status:
- *             * 201 if success
- *             * 400 if username length exceeds 30 characters, username is not valid ASCII,
+ *             * 201 if success (the user is also appended to the merkle tree of users)
+ *             * 200 if the user already exists with the same username and pubkey (a retry)
+ *             * 400 if username is empty, length exceeds 30 characters, is not valid ASCII,
  *               invalid signature over username by pubkey, or issues deserializing request
  *             * 409 if username || pubkey are already in use by another user
  *             * 500 if db fails or other unknown issue
@@ -36,28 +43,17 @@ use rocket::serde::json::Json;
 #[post("/create", format = "json", data = "<request>")]
 pub async fn create_user(
     request: Json<CreateUserRequest>,
-    db: &State<GrapevineDB>,
+    db: TenantDB,
 ) -> Result<GrapevineResponse, GrapevineResponse> {
-    // check username length is valid
-    if request.username.len() > MAX_USERNAME_CHARS {
-        return Err(GrapevineResponse::BadRequest(ErrorMessage(
-            Some(GrapevineError::UsernameTooLong(request.username.clone())),
-            None,
-        )));
-    };
-    // check request is ascii
-    if !request.username.is_ascii() {
-        return Err(GrapevineResponse::BadRequest(ErrorMessage(
-            Some(GrapevineError::UsernameNotAscii(request.username.clone())),
-            None,
-        )));
-    };
+    // store the NFC form of the username, which is also what the client signs and proves with
+    let mut request = request.into_inner();
+    request.username = normalize_username(&request.username);
+    // check username is non-empty, within the length limit, and ascii (unless unicode is enabled)
+    validate_username(&request.username, *UNICODE_USERNAMES)?;
     // check the validity of the signature over the username
-    let message = BigInt::from_bytes_le(
-        Sign::Plus,
-        &convert_username_to_fr(&request.username).unwrap()[..],
-    );
-    let pubkey_decompressed = decompress_point(request.pubkey).unwrap();
+    let username_bytes = convert_username_to_fr(&request.username)?;
+    let message = BigInt::from_bytes_le(Sign::Plus, &username_bytes[..]);
+    let pubkey_decompressed = decompress_pubkey(&request.pubkey)?;
     let signature_decompressed = decompress_signature(&request.signature).unwrap();
     match verify(pubkey_decompressed, signature_decompressed, message) {
         true => (),
@@ -71,40 +67,27 @@ pub async fn create_user(
         }
     };
     // check that the username or pubkey are not already used
-    match db
+    let found = db
         .check_creation_params(&request.username, &request.pubkey)
-        .await
-    {
-        Ok(found) => match found {
-            [true, true] => {
-                return Err(GrapevineResponse::Conflict(ErrorMessage(
-                    Some(GrapevineError::UserExists(request.username.clone())),
-                    None,
-                )));
-            }
-            [true, false] => {
-                return Err(GrapevineResponse::Conflict(ErrorMessage(
-                    Some(GrapevineError::UsernameExists(request.username.clone())),
-                    None,
+        .await?;
+    match found {
+        [true, true] => {
+            // a retry of a creation that already succeeded is not a conflict
+            if db.get_pubkey(request.username.clone()).await == Some(request.pubkey) {
+                return Ok(GrapevineResponse::Success(String::from(
+                    "User already created",
                 )));
             }
-            [false, true] => {
-                return Err(GrapevineResponse::Conflict(ErrorMessage(
-                    Some(GrapevineError::PubkeyExists(format!(
-                        "0x{}",
-                        hex::encode(request.pubkey.clone())
-                    ))),
-                    None,
-                )));
-            }
-            _ => (),
-        },
-        Err(e) => {
-            return Err(GrapevineResponse::InternalError(ErrorMessage(
-                Some(e),
-                None,
-            )))
+            return Err(GrapevineError::UserExists(request.username.clone()).into());
+        }
+        [true, false] => {
+            return Err(GrapevineError::UsernameExists(request.username.clone()).into());
+        }
+        [false, true] => {
+            let pubkey = format!("0x{}", hex::encode(request.pubkey.clone()));
+            return Err(GrapevineError::PubkeyExists(pubkey).into());
         }
+        _ => (),
     };
     // create the new user in the database
     let user = User {
@@ -114,16 +97,22 @@ pub async fn create_user(
         pubkey: Some(request.pubkey.clone()),
         relationships: Some(vec![]),
         degree_proofs: Some(vec![]),
+        delegated_proving: Some(false),
+        guardians: None,
+        recovery_threshold: None,
+        stats: None,
     };
-    match db.create_user(user).await {
-        Ok(_) => Ok(GrapevineResponse::Created(
-            "User succefully created".to_string(),
-        )),
-        Err(e) => Err(GrapevineResponse::InternalError(ErrorMessage(
+    if let Err(e) = db.create_user(user).await {
+        return Err(GrapevineResponse::InternalError(ErrorMessage(
             Some(e),
             None,
-        ))),
+        )));
     }
+    // add the user to the merkle tree of users
+    db.add_merkle_leaf(&request.username).await?;
+    Ok(GrapevineResponse::Created(
+        "User succefully created".to_string(),
+    ))
 }
 
 /**
@@ -140,6 +129,7 @@ pub async fn create_user(
  *            * 201 if success
  *            * 400 if from == to or issues deserializing request
  *            * 401 if signanture or nonce mismatch for sender
+ *            * 403 if the sender has reached their pending relationship quota
  *            * 404 if from or to user does not exist
  *            * 409 if relationship already exists
  */
@@ -147,7 +137,8 @@ pub async fn create_user(
 pub async fn add_relationship(
     user: AuthenticatedUser,
     request: Json<NewRelationshipRequest>,
-    db: &State<GrapevineDB>,
+    db: TenantDB,
+    quotas: &State<UserQuotas>,
 ) -> Result<GrapevineResponse, GrapevineResponse> {
     // ensure from != to
     if &user.0 == &request.to {
@@ -167,6 +158,13 @@ pub async fn add_relationship(
             )));
         }
     };
+    // guard against distinct usernames resolving to the same account
+    if sender.id == recipient.id {
+        return Err(GrapevineResponse::BadRequest(ErrorMessage(
+            Some(GrapevineError::RelationshipSenderIsTarget),
+            None,
+        )));
+    }
 
     // ensure relationship does not alreaday exist between two users
     match db
@@ -205,6 +203,18 @@ pub async fn add_relationship(
         }
     };
 
+    // enforce the cap on outbound relationships awaiting acceptance
+    if !activate {
+        let pending = db
+            .count_pending_relationships_from(&sender.id.unwrap())
+            .await?;
+        if pending >= quotas.pending_relationships {
+            let resource = String::from("pending relationships");
+            let limit = quotas.pending_relationships;
+            return Err(GrapevineError::QuotaExceeded(resource, limit).into());
+        }
+    }
+
     // add relationship doc and push to recipient array
     let relationship_doc = Relationship {
         id: None,
@@ -231,6 +241,9 @@ pub async fn add_relationship(
                 user.0, request.to, msg
             )))
         }
+        Err(GrapevineError::RelationshipSenderIsTarget) => Err(GrapevineResponse::BadRequest(
+            ErrorMessage(Some(GrapevineError::RelationshipSenderIsTarget), None),
+        )),
         Err(_) => Err(GrapevineResponse::InternalError(ErrorMessage(
             Some(GrapevineError::MongoError(String::from(
                 "Failed to add relationship to db",
@@ -244,7 +257,7 @@ pub async fn add_relationship(
 pub async fn reject_pending_relationship(
     user: AuthenticatedUser,
     username: String,
-    db: &State<GrapevineDB>,
+    db: TenantDB,
 ) -> Result<Status, GrapevineResponse> {
     // attempt to delete the pending relationship
     println!("Rejecting relationship from {} to {}", username, user.0);
@@ -262,10 +275,248 @@ pub async fn reject_pending_relationship(
     }
 }
 
+/**
+ * Opt in or out of letting the server generate degree proofs on the user's behalf
+ *
+ * @param enabled - whether delegated proving should be allowed for the user
+ * @return status:
+ *             * 200 if success
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 500 if db fails or other unknown issue
+ */
+#[post("/delegated-proving/<enabled>")]
+pub async fn set_delegated_proving(
+    user: AuthenticatedUser,
+    enabled: bool,
+    db: TenantDB,
+) -> Result<Status, GrapevineResponse> {
+    match db.set_delegated_proving(&user.0, enabled).await {
+        Ok(_) => Ok(Status::Ok),
+        Err(e) => Err(GrapevineResponse::InternalError(ErrorMessage(
+            Some(e),
+            None,
+        ))),
+    }
+}
+
+/**
+ * Designate the active relationships that can approve recovering the user's account
+ *
+ * @param data - the GuardiansRequest containing:
+ *             * guardians: usernames of users with an active relationship to the user
+ *             * threshold: the number of guardian approvals needed to recover the account
+ *             (an empty list of guardians with a threshold of 0 disables recovery)
+ * @return status:
+ *             * 200 if success
+ *             * 400 if a guardian is repeated or not an active relationship of the user, more than
+ *               MAX_GUARDIANS are given, or the threshold is 0 or exceeds the number of guardians
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 500 if db fails or other unknown issue
+ */
+#[post("/guardians", format = "json", data = "<request>")]
+pub async fn set_guardians(
+    user: AuthenticatedUser,
+    request: Json<GuardiansRequest>,
+    db: TenantDB,
+) -> Result<Status, GrapevineResponse> {
+    let guardians = &request.guardians;
+    let threshold = request.threshold as usize;
+    if guardians.len() > MAX_GUARDIANS {
+        let msg = format!("at most {} guardians can be set", MAX_GUARDIANS);
+        return Err(GrapevineError::InvalidGuardians(msg).into());
+    }
+    if !guardians.is_empty() && (threshold == 0 || threshold > guardians.len()) {
+        let msg = format!("threshold must be between 1 and {}", guardians.len());
+        return Err(GrapevineError::InvalidGuardians(msg).into());
+    }
+    if guardians.is_empty() && threshold != 0 {
+        let msg = String::from("threshold must be 0 when no guardians are set");
+        return Err(GrapevineError::InvalidGuardians(msg).into());
+    }
+    // guardians must be distinct users with an active relationship to the user
+    let relationships = db.get_relationships(&user.0, true).await?;
+    for (i, guardian) in guardians.iter().enumerate() {
+        if guardians[..i].contains(guardian) {
+            let msg = format!("{} is listed more than once", guardian);
+            return Err(GrapevineError::InvalidGuardians(msg).into());
+        }
+        if !relationships.contains(guardian) {
+            let msg = format!("{} does not have an active relationship with you", guardian);
+            return Err(GrapevineError::InvalidGuardians(msg).into());
+        }
+    }
+    db.set_guardians(&user.0, guardians, request.threshold)
+        .await?;
+    Ok(Status::Ok)
+}
+
+/**
+ * Move an account to a new pubkey with the approval of its guardians
+ * @notice - auth secrets sent to the account were encrypted to the old pubkey, so relationships
+ *           must be re-added before the account can build new degree proofs from them
+ *
+ * @param data - the RecoveryRequest containing:
+ *             * username: the username of the account being recovered
+ *             * pubkey: the compressed pubkey to move the account to
+ *             * approvals: guardian signatures over recovery_hash(username, pubkey, nonce)
+ * @return status:
+ *             * 200 if success (the nonce of the account is also incremented)
+ *             * 400 if the pubkey is malformed or issues deserializing request
+ *             * 401 if fewer than the threshold of distinct guardians gave a valid approval, or
+ *                   the nonce changed while the approvals were being checked
+ *             * 403 if the account has not set up guardians
+ *             * 404 if the user does not exist
+ *             * 409 if the pubkey is already used by another user
+ *             * 500 if db fails or other unknown issue
+ */
+#[post("/recover", format = "json", data = "<request>")]
+pub async fn recover_account(
+    request: Json<RecoveryRequest>,
+    db: TenantDB,
+) -> Result<Status, GrapevineResponse> {
+    let (nonce, threshold, guardians) = match db.get_guardians(&request.username).await? {
+        Some(config) => config,
+        None => return Err(GrapevineError::UserNotFound(request.username.clone()).into()),
+    };
+    if guardians.is_empty() || threshold == 0 {
+        return Err(GrapevineError::RecoveryNotEnabled(request.username.clone()).into());
+    }
+    decompress_pubkey(&request.pubkey)?;
+    if db
+        .check_creation_params(&request.username, &request.pubkey)
+        .await?[1]
+    {
+        let pubkey = format!("0x{}", hex::encode(request.pubkey));
+        return Err(GrapevineError::PubkeyExists(pubkey).into());
+    }
+    // count the distinct guardians that approved moving the account to the new pubkey
+    let hash = recovery_hash(&request.username, &request.pubkey, nonce);
+    let mut approved: Vec<&String> = vec![];
+    for approval in request.approvals.iter() {
+        let guardian = match guardians
+            .iter()
+            .find(|(name, _)| name == &approval.guardian)
+        {
+            Some(guardian) => guardian,
+            None => continue,
+        };
+        if approved.contains(&&guardian.0) {
+            continue;
+        }
+        let pubkey = decompress_point(guardian.1).unwrap();
+        let signature = match decompress_signature(&approval.signature) {
+            Ok(signature) => signature,
+            Err(_) => continue,
+        };
+        let message = BigInt::from_bytes_le(Sign::Plus, &hash[..]);
+        if verify(pubkey, signature, message) {
+            approved.push(&guardian.0);
+        }
+    }
+    if approved.len() < threshold as usize {
+        return Err(
+            GrapevineError::RecoveryThresholdNotMet(approved.len() as u8, threshold).into(),
+        );
+    }
+    // only rotate if no other request moved the nonce since the approvals were checked
+    db.rotate_pubkey(&request.username, &request.pubkey, nonce)
+        .await?;
+    Ok(Status::Ok)
+}
+
+/**
+ * Aggregate a user's account details and cache them on the user, so the details route can serve
+ * them without running the aggregation
+ *
+ * @param username - the username of the user to recompute stats for
+ * @return - the recomputed count of first degree connections, second degree connections, and
+ *           phrases created
+ * @return status:
+ *             * 200 if success
+ *             * 401 if the admin token is wrong
+ *             * 403 if admin routes are disabled
+ *             * 404 if the user does not exist
+ *             * 500 if db fails or other unknown issue
+ */
+#[post("/admin/recompute-stats/<username>")]
+pub async fn recompute_user_stats(
+    _admin: Admin,
+    username: String,
+    db: TenantDB,
+) -> Result<Json<AccountDetailsResponse>, GrapevineResponse> {
+    let user = match db.get_user(&username).await {
+        Some(user) => user,
+        None => return Err(GrapevineError::UserNotFound(username).into()),
+    };
+    Ok(Json(db.recompute_stats(&user.id.unwrap()).await?))
+}
+
+/**
+ * Aggregate and cache the account details of every user
+ *
+ * @return - the number of users whose stats were recomputed
+ * @return status:
+ *             * 200 if success
+ *             * 401 if the admin token is wrong
+ *             * 403 if admin routes are disabled
+ *             * 500 if db fails or other unknown issue
+ */
+#[post("/admin/recompute-stats")]
+pub async fn recompute_all_stats(
+    _admin: Admin,
+    db: TenantDB,
+) -> Result<Json<u64>, GrapevineResponse> {
+    let users = db.get_user_ids().await?;
+    for user in users.iter() {
+        db.recompute_stats(user).await?;
+    }
+    Ok(Json(users.len() as u64))
+}
+
+/**
+ * Find relationships whose references on users are out of sync with the relationships
+ * collection, left behind by failed or partial writes
+ *
+ * @return - the stringified OIDs of the orphaned relationships
+ * @return status:
+ *             * 200 if success
+ *             * 401 if the admin token is wrong
+ *             * 403 if admin routes are disabled
+ *             * 500 if db fails or other unknown issue
+ */
+#[get("/admin/orphaned-relationships")]
+pub async fn get_orphaned_relationships(
+    _admin: Admin,
+    db: TenantDB,
+) -> Result<Json<Vec<String>>, GrapevineResponse> {
+    let orphaned = db.find_orphaned_relationships().await?;
+    Ok(Json(orphaned.iter().map(|oid| oid.to_hex()).collect()))
+}
+
+/**
+ * Remove references to relationships that no longer exist and reference active relationships
+ * from their recipient again
+ *
+ * @return - the stringified OIDs of the relationships that were repaired
+ * @return status:
+ *             * 200 if success
+ *             * 401 if the admin token is wrong
+ *             * 403 if admin routes are disabled
+ *             * 500 if db fails or other unknown issue
+ */
+#[post("/admin/orphaned-relationships/repair")]
+pub async fn repair_orphaned_relationships(
+    _admin: Admin,
+    db: TenantDB,
+) -> Result<Json<Vec<String>>, GrapevineResponse> {
+    let repaired = db.repair_orphaned_relationships().await?;
+    Ok(Json(repaired.iter().map(|oid| oid.to_hex()).collect()))
+}
+
 #[get("/relationship/pending")]
 pub async fn get_pending_relationships(
     user: AuthenticatedUser,
-    db: &State<GrapevineDB>,
+    db: TenantDB,
 ) -> Result<Json<Vec<String>>, GrapevineResponse> {
     match db.get_relationships(&user.0, false).await {
         Ok(relationships) => Ok(Json(relationships)),
@@ -276,10 +527,53 @@ pub async fn get_pending_relationships(
     }
 }
 
+/**
+ * Return the usernames of users who sent a pending relationship request since a given time
+ *
+ * @param since - unix timestamp (seconds) to return requests sent at or after
+ * @return - a vector of usernames that sent a pending relationship request since the timestamp
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/relationship/new?<since>")]
+pub async fn get_new_relationships(
+    user: AuthenticatedUser,
+    since: u32,
+    db: TenantDB,
+) -> Result<Json<Vec<String>>, GrapevineResponse> {
+    match db.get_new_relationship_requests_since(&user.0, since).await {
+        Ok(relationships) => Ok(Json(relationships)),
+        Err(e) => Err(GrapevineResponse::InternalError(ErrorMessage(
+            Some(e),
+            None,
+        ))),
+    }
+}
+
+/**
+ * Return the usernames of users the caller sent relationship requests to that are still pending
+ *
+ * @return - a vector of usernames that have not yet accepted or rejected the caller's request
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/relationship/outbound")]
+pub async fn get_outbound_relationships(
+    user: AuthenticatedUser,
+    db: TenantDB,
+) -> Result<Json<Vec<String>>, GrapevineResponse> {
+    let relationships = db.get_outbound_pending(&user.0).await?;
+    Ok(Json(relationships))
+}
+
 #[get("/relationship/active")]
 pub async fn get_active_relationships(
     user: AuthenticatedUser,
-    db: &State<GrapevineDB>,
+    db: TenantDB,
 ) -> Result<Json<Vec<String>>, GrapevineResponse> {
     match db.get_relationships(&user.0, true).await {
         Ok(relationships) => Ok(Json(relationships)),
@@ -290,16 +584,92 @@ pub async fn get_active_relationships(
     }
 }
 
+/**
+ * Return the connections the caller shares with another user
+ * @notice only the caller's view of their relationships is used, so the response never lists
+ *         a connection the caller does not already have
+ *
+ * @param username - the username of the user to compare connections with
+ * @return - the usernames of users both the caller and the other user have active relationships with
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 404 if the other user does not exist
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/mutual/<username>")]
+pub async fn get_mutual_relationships(
+    user: AuthenticatedUser,
+    username: String,
+    db: TenantDB,
+) -> Result<Json<Vec<String>>, GrapevineResponse> {
+    let caller = db.get_user(&user.0).await.unwrap();
+    let other = match db.get_user(&username).await {
+        Some(other) => other,
+        None => {
+            return Err(GrapevineResponse::NotFound(format!(
+                "User {} does not exist.",
+                username
+            )))
+        }
+    };
+    let mutual = db
+        .get_mutual_relationships(&caller.id.unwrap(), &other.id.unwrap())
+        .await?;
+    Ok(Json(mutual))
+}
+
 /// GET REQUESTS ///
 
+/**
+ * Get the auth secret a connection encrypted for the caller, needed to prove a degree through them
+ *
+ * @param username - the username of the user that sent the relationship to the caller
+ * @return - the encrypted auth secret (sender username, caller pubkey, ephemeral key, ciphertext)
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 404 if the user does not exist or has no active relationship to the caller
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/relationship/auth/<username>")]
+pub async fn get_relationship_auth_secret(
+    user: AuthenticatedUser,
+    username: String,
+    db: TenantDB,
+) -> Result<Json<AuthSecretEncrypted>, GrapevineResponse> {
+    let caller = db.get_user(&user.0).await.unwrap();
+    let sender = match db.get_user(&username).await {
+        Some(sender) => sender,
+        None => {
+            return Err(GrapevineResponse::NotFound(format!(
+                "User {} does not exist.",
+                username
+            )))
+        }
+    };
+    match db
+        .get_active_relationship_keys(&sender.id.unwrap(), &caller.id.unwrap())
+        .await?
+    {
+        Some((ephemeral_key, ciphertext)) => Ok(Json(AuthSecretEncrypted {
+            username,
+            recipient: caller.pubkey.unwrap(),
+            ephemeral_key,
+            ciphertext,
+        })),
+        None => Err(GrapevineResponse::NotFound(format!(
+            "No active relationship from {} to {}",
+            username, user.0
+        ))),
+    }
+}
+
 /**
  * @todo: remove / replace with get nonce
  */
 #[get("/<username>")]
-pub async fn get_user(
-    username: String,
-    db: &State<GrapevineDB>,
-) -> Result<Json<User>, GrapevineResponse> {
+pub async fn get_user(username: String, db: TenantDB) -> Result<Json<User>, GrapevineResponse> {
     match db.get_user(&username).await {
         Some(user) => Ok(Json(user)),
         None => Err(GrapevineResponse::NotFound(format!(
@@ -312,7 +682,7 @@ pub async fn get_user(
 #[post("/nonce", format = "json", data = "<request>")]
 pub async fn get_nonce(
     request: Json<GetNonceRequest>,
-    db: &State<GrapevineDB>,
+    db: TenantDB,
 ) -> Result<String, GrapevineResponse> {
     // get pubkey & nonce for user
     let (nonce, pubkey) = match db.get_nonce(&request.username).await {
@@ -324,10 +694,13 @@ pub async fn get_nonce(
         }
     };
     // check the validity of the signature over the username
-    let message = BigInt::from_bytes_le(
-        Sign::Plus,
-        &convert_username_to_fr(&request.username).unwrap()[..],
-    );
+    let username_bytes = match convert_username_to_fr(&request.username) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return Err(GrapevineResponse::BadRequest(ErrorMessage(Some(e), None)));
+        }
+    };
+    let message = BigInt::from_bytes_le(Sign::Plus, &username_bytes[..]);
     let pubkey_decompressed = decompress_point(pubkey).unwrap();
     let signature_decompressed = decompress_signature(&request.signature).unwrap();
     match verify(pubkey_decompressed, signature_decompressed, message) {
@@ -356,10 +729,7 @@ pub async fn get_nonce(
  *            * 500 if db fails or other unknown issue
  */
 #[get("/<username>/pubkey")]
-pub async fn get_pubkey(
-    username: String,
-    db: &State<GrapevineDB>,
-) -> Result<String, GrapevineResponse> {
+pub async fn get_pubkey(username: String, db: TenantDB) -> Result<String, GrapevineResponse> {
     match db.get_pubkey(username).await {
         Some(pubkey) => Ok(hex::encode(pubkey)),
         None => Err(GrapevineResponse::NotFound(String::from(
@@ -368,11 +738,52 @@ pub async fn get_pubkey(
     }
 }
 
+/**
+ * Return the root of the merkle tree of users
+ *
+ * @return - a MerkleRootResponse containing:
+ *         * root: the merkle root
+ *         * size: the number of users in the tree
+ * @return status:
+ *            * 200 if success
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/merkle-root")]
+pub async fn get_merkle_root(db: TenantDB) -> Result<Json<MerkleRootResponse>, GrapevineResponse> {
+    Ok(Json(db.get_merkle_root().await?))
+}
+
+/**
+ * Return the inclusion path of a user in the merkle tree of users
+ * @notice - ranked after /<username>/pubkey, which also matches /merkle-proof/pubkey
+ *
+ * @param username - the username to get the inclusion path for
+ * @return - a MerkleProofResponse containing:
+ *         * username: the username of the user
+ *         * index: the position of the user's leaf
+ *         * leaf: the poseidon hash of the username
+ *         * siblings: the sibling of each node on the path from the leaf to the root
+ *         * root: the merkle root the path hashes to
+ * @return status:
+ *            * 200 if success
+ *            * 404 if user not found in the tree
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/merkle-proof/<username>", rank = 1)]
+pub async fn get_merkle_proof(
+    username: String,
+    db: TenantDB,
+) -> Result<Json<MerkleProofResponse>, GrapevineResponse> {
+    Ok(Json(db.get_merkle_proof(&username).await?))
+}
+
 /**
  * Return a list of all available (new) degree proofs from existing connections that a user can
  * build from (empty if none)
  *
  * @param username - the username to look up the available proofs for
+ * @param degree - if provided, only return proofs of exactly this degree
+ * @param max_degree - if provided, only return proofs of at most this degree
  * @return - a vector of DegreeData structs containing:
  *             * oid: the ObjectID of the proof to build from
  *             * relation: the separation degree of the proof
@@ -383,12 +794,14 @@ pub async fn get_pubkey(
  *            * 404 if user not found
  *            * 500 if db fails or other unknown issue
  */
-#[get("/degrees")]
+#[get("/degrees?<degree>&<max_degree>")]
 pub async fn get_all_degrees(
     user: AuthenticatedUser,
-    db: &State<GrapevineDB>,
+    degree: Option<u8>,
+    max_degree: Option<u8>,
+    db: TenantDB,
 ) -> Result<Json<Vec<DegreeData>>, GrapevineResponse> {
-    match db.get_all_degrees(user.0).await {
+    match db.get_all_degrees(user.0, degree, max_degree).await {
         Some(proofs) => Ok(Json(proofs)),
         None => Err(GrapevineResponse::InternalError(ErrorMessage(
             Some(GrapevineError::MongoError(String::from(
@@ -401,6 +814,8 @@ pub async fn get_all_degrees(
 
 /**
  * Returns account details related to degree proofs
+ * @notice - stats cached by an admin recompute are served until they are older than
+ *           GRAPEVINE_STATS_TTL seconds, after which the details are aggregated live
  *
  * @param username - the username to look up details for
  * @return - count of first degree connections, second degree connections, and phrases created
@@ -412,8 +827,8 @@ pub async fn get_all_degrees(
 #[get("/details")]
 pub async fn get_account_details(
     user: AuthenticatedUser,
-    db: &State<GrapevineDB>,
-) -> Result<Json<(u64, u64, u64)>, GrapevineResponse> {
+    db: TenantDB,
+) -> Result<Json<AccountDetailsResponse>, GrapevineResponse> {
     let recipient = match db.get_user(&user.0).await {
         Some(user) => user,
         None => {
@@ -422,6 +837,13 @@ pub async fn get_account_details(
             )));
         }
     };
+    if let Some(stats) = recipient.stats.filter(stats_are_fresh) {
+        return Ok(Json(AccountDetailsResponse {
+            phrase_count: stats.phrase_count,
+            first_degree_count: stats.first_degree_count,
+            second_degree_count: stats.second_degree_count,
+        }));
+    }
     match db.get_account_details(&recipient.id.unwrap()).await {
         Some(details) => Ok(Json(details)),
         None => Err(GrapevineResponse::InternalError(ErrorMessage(
@@ -446,7 +868,7 @@ pub async fn get_account_details(
 //  */
 // pub async fn get_relationships(
 //     username: String,
-//     db: &State<GrapevineDB>,
+//     db: TenantDB,
 // ) -> Result<Json<Vec<String>>, Status> {
 //     todo!("implement get_relationships")
 // }
@@ -0,0 +1,137 @@
+use grapevine_common::errors::GrapevineError;
+use rocket::fairing::AdHoc;
+use rocket::tokio::sync::Notify;
+use rocket::tokio::time::{timeout, Duration};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+pub const DEFAULT_DRAIN_TIMEOUT_SECS: u64 = 30;
+
+#[derive(Default)]
+struct DrainState {
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/**
+ * Tracks proof submissions being verified so shutdown can wait for them to finish
+ */
+#[derive(Clone, Default)]
+pub struct ProofDrain {
+    state: Arc<DrainState>,
+    timeout: Duration, // longest shutdown waits for in-flight proofs
+}
+
+/**
+ * Marks a proof submission as in flight until dropped
+ */
+pub struct InFlight {
+    state: Arc<DrainState>,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if self.state.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.state.idle.notify_waiters();
+        }
+    }
+}
+
+impl ProofDrain {
+    /**
+     * Read the drain timeout in seconds from GRAPEVINE_DRAIN_TIMEOUT_SECS, falling back to the
+     * default if unset or malformed
+     */
+    pub fn from_env() -> Self {
+        let secs = match std::env::var("GRAPEVINE_DRAIN_TIMEOUT_SECS") {
+            Ok(secs) => secs.parse::<u64>().unwrap_or(DEFAULT_DRAIN_TIMEOUT_SECS),
+            Err(_) => DEFAULT_DRAIN_TIMEOUT_SECS,
+        };
+        ProofDrain {
+            state: Arc::new(DrainState::default()),
+            timeout: Duration::from_secs(secs),
+        }
+    }
+
+    /**
+     * Start tracking a proof submission
+     *
+     * @return - a guard holding the submission in flight, or ShuttingDown if draining
+     */
+    pub fn begin(&self) -> Result<InFlight, GrapevineError> {
+        self.state.in_flight.fetch_add(1, Ordering::SeqCst);
+        let in_flight = InFlight {
+            state: self.state.clone(),
+        };
+        // checked after counting so a drain never misses a submission that was let through
+        match self.state.draining.load(Ordering::SeqCst) {
+            true => Err(GrapevineError::ShuttingDown),
+            false => Ok(in_flight),
+        }
+    }
+
+    /**
+     * @return - the number of proof submissions currently in flight
+     */
+    pub fn in_flight(&self) -> usize {
+        self.state.in_flight.load(Ordering::SeqCst)
+    }
+
+    /**
+     * Stop accepting new proof submissions
+     */
+    pub fn stop(&self) {
+        self.state.draining.store(true, Ordering::SeqCst);
+    }
+
+    /**
+     * Wait for in-flight proof submissions to finish
+     *
+     * @param limit - the longest to wait
+     * @return - the number of submissions still in flight when the wait ended
+     */
+    pub async fn wait(&self, limit: Duration) -> usize {
+        let idle = async {
+            loop {
+                // register for the notification before checking to avoid missing it
+                let notified = self.state.idle.notified();
+                if self.in_flight() == 0 {
+                    return;
+                }
+                notified.await;
+            }
+        };
+        let _ = timeout(limit, idle).await;
+        self.in_flight()
+    }
+
+    /**
+     * Fairing that drains proof submissions when the server is asked to shut down (ctrl-c or
+     * SIGTERM). Rocket keeps serving requests until shutdown fairings complete, so in-flight
+     * verifications finish while new submissions are turned away
+     */
+    pub fn fairing() -> AdHoc {
+        AdHoc::on_shutdown("Drain proof submissions", |rocket| {
+            Box::pin(async move {
+                let drain = match rocket.state::<ProofDrain>() {
+                    Some(drain) => drain,
+                    None => return,
+                };
+                drain.stop();
+                tracing::info!(
+                    in_flight = drain.in_flight(),
+                    "shutting down, draining proof submissions"
+                );
+                let remaining = drain.wait(drain.timeout).await;
+                match remaining {
+                    0 => tracing::info!("proof submissions drained"),
+                    _ => tracing::warn!(
+                        in_flight = remaining,
+                        "timed out draining proof submissions"
+                    ),
+                }
+            })
+        })
+    }
+}
@@ -1,13 +1,67 @@
-use grapevine_common::{Fr, Params, G1, G2};
+use grapevine_circuits::{
+    nova::verify_nova_proof, utils::try_decompress_proof, CIRCUIT_VERSION, MAX_DEGREE,
+};
+use grapevine_common::crypto::{artifact_hash, merkle_zero_hashes};
+use grapevine_common::errors::GrapevineError;
+use grapevine_common::http::responses::CircuitInfoResponse;
+use grapevine_common::models::UserStats;
+use grapevine_common::{Fr, Params, G1, G2, MIN_REQUEST_VERSION, REQUEST_VERSION};
 use lazy_static::lazy_static;
+use mongodb::bson::DateTime;
 use nova_scotia::circom::circuit::R1CS;
 use nova_scotia::circom::reader::load_r1cs;
 use nova_scotia::FileLocation;
 use std::env::current_dir;
+use std::net::IpAddr;
 use std::path::PathBuf;
 
 lazy_static! {
     pub static ref PUBLIC_PARAMS: Params = use_public_params().unwrap();
+    pub static ref CIRCUIT_INFO: CircuitInfoResponse = use_circuit_info().unwrap();
+    pub static ref MERKLE_ZEROS: Vec<[u8; 32]> = merkle_zero_hashes();
+    pub static ref PROOF_TTL_DAYS: Option<u64> = match std::env::var("GRAPEVINE_PROOF_TTL_DAYS") {
+        Ok(days) => days.parse::<u64>().ok(),
+        Err(_) => None,
+    };
+    pub static ref UNICODE_USERNAMES: bool = match std::env::var("GRAPEVINE_UNICODE_USERNAMES") {
+        Ok(enabled) => enabled == "true" || enabled == "1",
+        Err(_) => false,
+    };
+    pub static ref STATS_TTL_SECS: u64 = match std::env::var("GRAPEVINE_STATS_TTL") {
+        Ok(seconds) => seconds.parse::<u64>().unwrap_or(DEFAULT_STATS_TTL),
+        Err(_) => DEFAULT_STATS_TTL,
+    };
+}
+
+pub const DEFAULT_STATS_TTL: u64 = 60 * 60; // serve cached account stats for up to an hour
+
+#[cfg(feature = "delegated-proving")]
+lazy_static! {
+    pub static ref R1CS_FILE: R1CS<Fr> = use_r1cs().unwrap();
+    pub static ref WASM_PATH: PathBuf = use_wasm().unwrap();
+}
+
+/**
+ * Get the time a proof stored now should expire at, if proofs are configured to expire
+ *
+ * @return - the current time plus GRAPEVINE_PROOF_TTL_DAYS, or None if proofs never expire
+ */
+pub fn proof_expiry() -> Option<DateTime> {
+    PROOF_TTL_DAYS.map(|days| {
+        let ttl = std::time::Duration::from_secs(days * 24 * 60 * 60);
+        DateTime::from_system_time(std::time::SystemTime::now() + ttl)
+    })
+}
+
+/**
+ * Check whether a user's cached stats were recomputed recently enough to serve
+ *
+ * @param stats - the cached stats of the user
+ * @return - true if the stats were computed within GRAPEVINE_STATS_TTL seconds
+ */
+pub fn stats_are_fresh(stats: &UserStats) -> bool {
+    let age = DateTime::now().timestamp_millis() - stats.computed_at.timestamp_millis();
+    age < (*STATS_TTL_SECS * 1000) as i64
 }
 
 // @TODO: lazy static implementation for public params and r1cs
@@ -41,3 +95,103 @@ pub fn use_wasm() -> Result<PathBuf, Box<dyn std::error::Error>> {
     let filepath = current_dir().unwrap().join("static/grapevine.wasm");
     Ok(filepath)
 }
+
+/**
+ * Fingerprint the artifacts served from the static directory so clients can detect stale downloads
+ *
+ * @return - the circuit version, hashes of the r1cs and public params, and the max accepted degree
+ */
+pub fn use_circuit_info() -> Result<CircuitInfoResponse, Box<dyn std::error::Error>> {
+    let static_dir = current_dir()?.join("static");
+    let r1cs = std::fs::read(static_dir.join("grapevine.r1cs"))?;
+    let public_params = std::fs::read(static_dir.join("public_params.json"))?;
+    Ok(CircuitInfoResponse {
+        circuit_version: String::from(CIRCUIT_VERSION),
+        r1cs_hash: artifact_hash(&r1cs),
+        params_hash: artifact_hash(&public_params),
+        max_degree: MAX_DEGREE,
+    })
+}
+
+/**
+ * Classifies why a submitted proof was rejected by the server
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofRejectionReason {
+    // the request body or compressed proof could not be deserialized
+    Parse,
+    // the proof failed nova verification
+    Verify,
+    // the proof verified but the outputs do not match what was claimed by the requester
+    OutputMismatch,
+}
+
+/**
+ * Check the version byte leading a bincode-serialized proof request before deserializing it
+ *
+ * @param buffer - the raw request body
+ * @return - UnsupportedVersion error if the version is outside of the accepted range
+ */
+pub fn check_request_version(buffer: &[u8]) -> Result<(), GrapevineError> {
+    let version = buffer.first().copied().unwrap_or(0);
+    match (MIN_REQUEST_VERSION..=REQUEST_VERSION).contains(&version) {
+        true => Ok(()),
+        false => Err(GrapevineError::UnsupportedVersion(
+            version,
+            MIN_REQUEST_VERSION,
+            REQUEST_VERSION,
+        )),
+    }
+}
+
+/**
+ * Decompress and verify a proof, checking that the degree output by the proof matches the claim
+ *
+ * @param proof - the gzip-compressed proof
+ * @param public_params - the public params to verify the proof with
+ * @param iterations - the number of folds to verify the proof with
+ * @param degree - the degree of separation claimed by the requester
+ * @return - the outputs of the proof if valid, or the reason the proof was rejected
+ */
+pub fn check_proof(
+    proof: &[u8],
+    public_params: &Params,
+    iterations: usize,
+    degree: u8,
+) -> Result<Vec<Fr>, ProofRejectionReason> {
+    let decompressed = match try_decompress_proof(proof) {
+        Ok(proof) => proof,
+        Err(_) => return Err(ProofRejectionReason::Parse),
+    };
+    let outputs = match verify_nova_proof(&decompressed, public_params, iterations) {
+        Ok(res) => res.0,
+        Err(_) => return Err(ProofRejectionReason::Verify),
+    };
+    match outputs[0] == Fr::from(degree as u64) {
+        true => Ok(outputs),
+        false => Err(ProofRejectionReason::OutputMismatch),
+    }
+}
+
+/**
+ * Emit a structured warning for a rejected proof (never logs the proof itself)
+ *
+ * @param reason - the category of failure
+ * @param username - the authenticated username that submitted the proof
+ * @param address - the remote address of the requester if known
+ * @param degree - the degree of separation claimed by the requester if known
+ */
+pub fn log_proof_rejection(
+    reason: ProofRejectionReason,
+    username: &String,
+    address: Option<IpAddr>,
+    degree: Option<u8>,
+) {
+    tracing::warn!(
+        reason = ?reason,
+        username = %username,
+        address = ?address,
+        claimed_degree = ?degree,
+        "rejected proof"
+    );
+}
@@ -0,0 +1,56 @@
+use grapevine_common::errors::GrapevineError;
+use lazy_static::lazy_static;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use rocket::tokio::sync::oneshot;
+
+lazy_static! {
+    pub static ref VERIFY_POOL: ThreadPool = build_verify_pool(verify_threads_from_env());
+}
+
+/**
+ * Read the number of proof verification threads from GRAPEVINE_VERIFY_THREADS
+ *
+ * @return - the configured thread count, or 0 (one thread per core) if unset or malformed
+ */
+pub fn verify_threads_from_env() -> usize {
+    match std::env::var("GRAPEVINE_VERIFY_THREADS") {
+        Ok(threads) => threads.parse::<usize>().unwrap_or(0),
+        Err(_) => 0,
+    }
+}
+
+/**
+ * Build the thread pool proofs are verified on
+ *
+ * @param threads - the number of threads in the pool (0 for one per core)
+ * @return - the verification thread pool
+ */
+pub fn build_verify_pool(threads: usize) -> ThreadPool {
+    ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|index| format!("grapevine-verify-{}", index))
+        // rayon aborts the process on a panic unless it is handled
+        .panic_handler(|_| tracing::error!("proof verification panicked"))
+        .build()
+        .unwrap()
+}
+
+/**
+ * Run CPU-bound proof verification on the verification pool so it does not block the async
+ * workers serving other requests
+ *
+ * @param verify - the verification to run
+ * @return - the result of the verification, or InternalError if it panicked
+ */
+pub async fn verify_in_pool<T, F>(verify: F) -> Result<T, GrapevineError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    VERIFY_POOL.spawn(move || {
+        // the request may have been dropped while waiting, so the result can be discarded
+        let _ = sender.send(verify());
+    });
+    receiver.await.map_err(|_| GrapevineError::InternalError)
+}